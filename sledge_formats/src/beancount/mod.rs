@@ -0,0 +1,168 @@
+/*!
+Import and export Beancount ledgers.
+
+Each `open` directive creates an account, dated by the directive, in the commodity of its first
+currency constraint; any account only named by postings, and the parents of every account, are
+created as well. A `close` directive deactivates the account. An account's kind comes from its
+root, one of `Assets`, `Liabilities`, `Equity`, `Income`, or `Expenses`.
+
+Transactions become a [`Transaction`](sledge_model::journal::Transaction) with a split per
+posting. Cost annotations, `{}` per unit and `{{}}` in total, and prices, `@` and `@@`, become a
+[`RatedQuantity`](sledge_model::commodity::RatedQuantity) holding the posting's weight; an
+empty cost, as used to reduce a lot, takes the amount that balances the transaction. The
+payee, tags, links, a flag other than `*`, and any transaction metadata are kept as
+transaction metadata, and `price` directives become price records.
+
+Balance assertions are checked against the balance engine once all transactions are read, and
+each failure is listed in the [`ImportReport`](crate::ImportReport). A `pad` directive inserts
+a transaction, with `pad` metadata, that brings the account to the balance of its next
+assertion.
+
+Commodity symbols are mapped to currencies for ISO 4217 codes, to securities for valid ISINs or
+when the `commodity` directive has `isin` metadata, and to anything else with
+[`BeancountOptions::with_commodity`].
+
+*/
+
+use codes_iso_4217::CurrencyCode;
+use sledge_model::commodity::{CommodityId, InternationalSecuritiesId};
+use sledge_model::ledger::{AccountKind, LedgerKind};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Clone, Debug)]
+pub struct BeancountOptions {
+    ledger_kind: LedgerKind,
+    journal_name: String,
+    currency: CurrencyCode,
+    commodities: HashMap<String, CommodityId>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl Default for BeancountOptions {
+    fn default() -> Self {
+        Self {
+            ledger_kind: LedgerKind::General,
+            journal_name: "general".to_string(),
+            currency: CurrencyCode::USD,
+            commodities: Default::default(),
+        }
+    }
+}
+
+impl BeancountOptions {
+    pub fn with_ledger_kind(self, ledger_kind: LedgerKind) -> Self {
+        Self {
+            ledger_kind,
+            ..self
+        }
+    }
+
+    pub fn with_journal_name<S: Into<String>>(self, journal_name: S) -> Self {
+        Self {
+            journal_name: journal_name.into(),
+            ..self
+        }
+    }
+
+    ///
+    /// The currency of the imported ledger and journal, and of accounts that neither
+    /// constrain their currencies nor have postings.
+    ///
+    pub fn with_currency(self, currency: CurrencyCode) -> Self {
+        Self { currency, ..self }
+    }
+
+    ///
+    /// Import amounts in the commodity `symbol`, such as `"AAPL"`, as `commodity`.
+    ///
+    pub fn with_commodity<S: Into<String>>(mut self, symbol: S, commodity: CommodityId) -> Self {
+        let _ = self.commodities.insert(symbol.into(), commodity);
+        self
+    }
+
+    pub fn ledger_kind(&self) -> &LedgerKind {
+        &self.ledger_kind
+    }
+
+    pub fn journal_name(&self) -> &String {
+        &self.journal_name
+    }
+
+    pub fn currency(&self) -> CurrencyCode {
+        self.currency
+    }
+
+    ///
+    /// The commodity for `symbol`, from those added to these options, then currency codes,
+    /// and finally valid ISINs.
+    ///
+    pub fn commodity(&self, symbol: &str) -> Option<CommodityId> {
+        self.commodities.get(symbol).cloned().or_else(|| {
+            CurrencyCode::from_str(symbol)
+                .ok()
+                .map(CommodityId::Currency)
+                .or_else(|| {
+                    let isin = InternationalSecuritiesId::from(symbol);
+                    isin.is_valid().then_some(CommodityId::Security(isin))
+                })
+        })
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+pub(crate) fn root_name(kind: AccountKind) -> &'static str {
+    match kind {
+        AccountKind::Asset => "Assets",
+        AccountKind::Liability => "Liabilities",
+        AccountKind::Equity => "Equity",
+        AccountKind::Income => "Income",
+        AccountKind::Expense => "Expenses",
+    }
+}
+
+pub(crate) fn root_kind(name: &str) -> Option<AccountKind> {
+    [
+        AccountKind::Asset,
+        AccountKind::Liability,
+        AccountKind::Equity,
+        AccountKind::Income,
+        AccountKind::Expense,
+    ]
+    .into_iter()
+    .find(|kind| name.split(':').next() == Some(root_name(*kind)))
+}
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
+
+#[doc(hidden)]
+mod read;
+pub use read::{read_beancount, read_beancount_file};
+
+#[doc(hidden)]
+mod write;
+pub use write::{write_beancount, write_beancount_file};
@@ -0,0 +1,1123 @@
+/*!
+Read Beancount ledger files.
+
+*/
+
+use super::{root_kind, BeancountOptions};
+use crate::error::{syntax_error, Result};
+use crate::{ImportReport, Imported};
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use sledge_model::commodity::{
+    CommodityId, InternationalSecuritiesId, Quantity, RateRecord, RatedQuantity,
+};
+use sledge_model::journal::{Journal, Split, Transaction, TransactionId};
+use sledge_model::ledger::{Account, AccountId, Balances, Ledger};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::str::FromStr;
+use tracing::info;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+pub fn read_beancount_file<P: AsRef<Path>>(
+    path: P,
+    options: &BeancountOptions,
+) -> Result<Imported> {
+    info!("Reading Beancount file {:?}", path.as_ref());
+    read_beancount(BufReader::new(File::open(path)?), options)
+}
+
+pub fn read_beancount<R: BufRead>(reader: R, options: &BeancountOptions) -> Result<Imported> {
+    let mut parser = Parser::new(options);
+    let mut block = Block::None;
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let number = index + 1;
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with(';') {
+            continue;
+        }
+        if line.starts_with(char::is_whitespace) {
+            parser.continuation(&mut block, number, trimmed)?;
+        } else {
+            parser.finish(std::mem::replace(&mut block, Block::None));
+            block = parser.directive(number, &line)?;
+        }
+    }
+    parser.finish(block);
+    Ok(parser.into_imported())
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Text(String),
+    Tag(String),
+    Link(String),
+    Punct(&'static str),
+}
+
+#[derive(Debug)]
+struct Parser<'a> {
+    options: &'a BeancountOptions,
+    report: ImportReport,
+    pushed_tags: Vec<String>,
+    opened: BTreeMap<String, Opened>,
+    closed: Vec<String>,
+    securities: HashMap<String, InternationalSecuritiesId>,
+    transactions: Vec<ParsedTransaction>,
+    prices: Vec<ParsedPrice>,
+    assertions: Vec<Assertion>,
+    pads: Vec<Pad>,
+}
+
+#[derive(Debug)]
+struct Opened {
+    date: NaiveDate,
+    currencies: Vec<String>,
+    description: Option<String>,
+}
+
+#[derive(Debug)]
+enum Block {
+    None,
+    Open(String),
+    Commodity(String),
+    Transaction(ParsedTransaction),
+    Skip,
+}
+
+#[derive(Clone, Debug)]
+struct Amount {
+    number: Decimal,
+    symbol: String,
+}
+
+#[derive(Debug)]
+enum Cost {
+    Empty,
+    PerUnit(Amount),
+    Total(Amount),
+}
+
+#[derive(Debug)]
+struct ParsedTransaction {
+    line: usize,
+    date: NaiveDate,
+    flag: String,
+    payee: Option<String>,
+    narration: Option<String>,
+    tags: Vec<String>,
+    links: Vec<String>,
+    metadata: Vec<(String, String)>,
+    postings: Vec<ParsedPosting>,
+}
+
+#[derive(Debug)]
+struct ParsedPosting {
+    account: String,
+    units: Option<Amount>,
+    cost: Option<Cost>,
+    price: Option<Cost>,
+    description: Option<String>,
+}
+
+#[derive(Debug)]
+struct ParsedPrice {
+    line: usize,
+    date: NaiveDate,
+    symbol: String,
+    amount: Amount,
+}
+
+#[derive(Debug)]
+struct Assertion {
+    line: usize,
+    date: NaiveDate,
+    account: String,
+    amount: Amount,
+    tolerance: Option<Decimal>,
+}
+
+#[derive(Debug)]
+struct Pad {
+    line: usize,
+    date: NaiveDate,
+    account: String,
+    source: String,
+}
+
+///
+/// A posting with its commodities mapped; `weight` is the amount the posting contributes to
+/// balancing the transaction when that differs from its units.
+///
+#[derive(Debug)]
+struct Posting {
+    account: String,
+    units: Option<(CommodityId, Decimal)>,
+    weight: Option<(CommodityId, Decimal)>,
+    unknown_cost: bool,
+    description: Option<String>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl<'a> Parser<'a> {
+    fn new(options: &'a BeancountOptions) -> Self {
+        Self {
+            options,
+            report: Default::default(),
+            pushed_tags: Default::default(),
+            opened: Default::default(),
+            closed: Default::default(),
+            securities: Default::default(),
+            transactions: Default::default(),
+            prices: Default::default(),
+            assertions: Default::default(),
+            pads: Default::default(),
+        }
+    }
+
+    fn directive(&mut self, number: usize, line: &str) -> Result<Block> {
+        // Org-mode headings, and other punctuation, start lines Beancount ignores.
+        if !line.starts_with(|c: char| c.is_ascii_alphanumeric()) {
+            return Ok(Block::None);
+        }
+        let tokens = tokenize(line).ok_or_else(|| syntax_error(number, line))?;
+        let invalid = || syntax_error(number, line);
+        let mut tokens = tokens.into_iter();
+        let first = word(tokens.next()).ok_or_else(invalid)?;
+        let date = match NaiveDate::parse_from_str(&first.replace('/', "-"), "%Y-%m-%d") {
+            Ok(date) => date,
+            Err(_) => return self.undated(number, &first, tokens.collect()),
+        };
+        let keyword = match tokens.next() {
+            Some(Token::Word(keyword)) => keyword,
+            Some(Token::Text(text)) => {
+                // A transaction without a flag, which Beancount accepts as "txn".
+                let rest = std::iter::once(Token::Text(text)).chain(tokens).collect();
+                return self.transaction(number, line, date, "*".to_string(), rest);
+            }
+            None => "txn".to_string(),
+            _ => return Err(invalid()),
+        };
+        match keyword.as_str() {
+            "open" => {
+                let account = word(tokens.next()).ok_or_else(invalid)?;
+                let currencies = tokens
+                    .filter_map(|token| match token {
+                        Token::Word(symbol) => Some(symbol),
+                        _ => None,
+                    })
+                    .collect();
+                let _ = self.opened.insert(
+                    account.clone(),
+                    Opened {
+                        date,
+                        currencies,
+                        description: None,
+                    },
+                );
+                Ok(Block::Open(account))
+            }
+            "close" => {
+                self.closed.push(word(tokens.next()).ok_or_else(invalid)?);
+                Ok(Block::Skip)
+            }
+            "commodity" => Ok(Block::Commodity(word(tokens.next()).ok_or_else(invalid)?)),
+            "price" => {
+                let symbol = word(tokens.next()).ok_or_else(invalid)?;
+                let rest: Vec<Token> = tokens.collect();
+                let amount = match rest.as_slice() {
+                    [number, symbol] => amount(number, symbol),
+                    _ => None,
+                }
+                .ok_or_else(invalid)?;
+                self.prices.push(ParsedPrice {
+                    line: number,
+                    date,
+                    symbol,
+                    amount,
+                });
+                Ok(Block::Skip)
+            }
+            "balance" => {
+                let account = word(tokens.next()).ok_or_else(invalid)?;
+                let rest: Vec<Token> = tokens.collect();
+                let (amount, tolerance) = match rest.as_slice() {
+                    [number, symbol] => (amount(number, symbol), None),
+                    [number, Token::Word(tilde), tolerance, symbol] if tilde == "~" => (
+                        amount(number, symbol),
+                        Some(decimal(tolerance).ok_or_else(invalid)?),
+                    ),
+                    _ => (None, None),
+                };
+                self.assertions.push(Assertion {
+                    line: number,
+                    date,
+                    account,
+                    amount: amount.ok_or_else(invalid)?,
+                    tolerance,
+                });
+                Ok(Block::Skip)
+            }
+            "pad" => {
+                let account = word(tokens.next()).ok_or_else(invalid)?;
+                let source = word(tokens.next()).ok_or_else(invalid)?;
+                self.pads.push(Pad {
+                    line: number,
+                    date,
+                    account,
+                    source,
+                });
+                Ok(Block::Skip)
+            }
+            "txn" => self.transaction(number, line, date, "*".to_string(), tokens.collect()),
+            flag if flag.chars().count() == 1 => {
+                self.transaction(number, line, date, flag.to_string(), tokens.collect())
+            }
+            _ => {
+                self.report.add(
+                    "directive",
+                    format!("line {}", number),
+                    format!("the {:?} directive is not supported", keyword),
+                );
+                Ok(Block::Skip)
+            }
+        }
+    }
+
+    fn undated(&mut self, number: usize, keyword: &str, tokens: Vec<Token>) -> Result<Block> {
+        match (keyword, tokens.as_slice()) {
+            ("pushtag", [Token::Tag(tag)]) => self.pushed_tags.push(tag.clone()),
+            ("poptag", [Token::Tag(tag)]) => {
+                if let Some(index) = self.pushed_tags.iter().rposition(|t| t == tag) {
+                    let _ = self.pushed_tags.remove(index);
+                }
+            }
+            _ => self.report.add(
+                "directive",
+                format!("line {}", number),
+                format!("the {:?} directive is not supported", keyword),
+            ),
+        }
+        Ok(Block::Skip)
+    }
+
+    fn transaction(
+        &mut self,
+        number: usize,
+        line: &str,
+        date: NaiveDate,
+        flag: String,
+        tokens: Vec<Token>,
+    ) -> Result<Block> {
+        let mut strings: Vec<String> = Default::default();
+        let mut tags = self.pushed_tags.clone();
+        let mut links: Vec<String> = Default::default();
+        for token in tokens {
+            match token {
+                Token::Text(text) if tags.len() == self.pushed_tags.len() && links.is_empty() => {
+                    strings.push(text)
+                }
+                Token::Tag(tag) => {
+                    if !tags.contains(&tag) {
+                        tags.push(tag)
+                    }
+                }
+                Token::Link(link) => links.push(link),
+                _ => return Err(syntax_error(number, line)),
+            }
+        }
+        let (payee, narration) = match strings.len() {
+            0 => (None, None),
+            1 => (None, strings.pop()),
+            2 => {
+                let narration = strings.pop();
+                (strings.pop(), narration)
+            }
+            _ => return Err(syntax_error(number, line)),
+        };
+        Ok(Block::Transaction(ParsedTransaction {
+            line: number,
+            date,
+            flag,
+            payee: payee.filter(|p| !p.is_empty()),
+            narration: narration.filter(|n| !n.is_empty()),
+            tags,
+            links,
+            metadata: Default::default(),
+            postings: Default::default(),
+        }))
+    }
+
+    fn continuation(&mut self, block: &mut Block, number: usize, line: &str) -> Result<()> {
+        let tokens = tokenize(line).ok_or_else(|| syntax_error(number, line))?;
+        let metadata = metadata(&tokens);
+        match block {
+            Block::Transaction(transaction) => match metadata {
+                Some((key, value)) => match transaction.postings.last_mut() {
+                    Some(posting) if key == "description" => posting.description = Some(value),
+                    Some(_) => self.report.add(
+                        "posting",
+                        format!("line {}", number),
+                        format!("the posting metadata {:?} is not imported", key),
+                    ),
+                    None => transaction.metadata.push((key, value)),
+                },
+                None => {
+                    let posting = posting(tokens).ok_or_else(|| syntax_error(number, line))?;
+                    transaction.postings.push(posting);
+                }
+            },
+            Block::Open(account) => {
+                if let Some((key, value)) = metadata {
+                    if key == "description" {
+                        if let Some(opened) = self.opened.get_mut(account) {
+                            opened.description = Some(value);
+                        }
+                    }
+                }
+            }
+            Block::Commodity(symbol) => {
+                if let Some((key, value)) = metadata {
+                    if key == "isin" {
+                        let isin = InternationalSecuritiesId::from(value.as_str());
+                        if isin.is_valid() {
+                            let _ = self.securities.insert(symbol.clone(), isin);
+                        } else {
+                            return Err(syntax_error(number, line));
+                        }
+                    }
+                }
+            }
+            Block::Skip => {}
+            Block::None => return Err(syntax_error(number, line)),
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, block: Block) {
+        if let Block::Transaction(transaction) = block {
+            self.transactions.push(transaction);
+        }
+    }
+
+    ///
+    /// The commodity for `symbol`, from the options, then the `isin` metadata of `commodity`
+    /// directives, and then currency codes and ISINs.
+    ///
+    fn commodity(&mut self, symbol: &str) -> Option<CommodityId> {
+        let commodity = match self.securities.get(symbol) {
+            Some(isin) if self.options.commodity(symbol).is_none() => {
+                Some(CommodityId::Security(isin.clone()))
+            }
+            _ => self.options.commodity(symbol),
+        };
+        if commodity.is_none()
+            && self
+                .report
+                .unmapped_of_kind("commodity")
+                .all(|item| item.id() != symbol)
+        {
+            self.report.add(
+                "commodity",
+                symbol,
+                "not a currency or ISIN, and not mapped in the options or by isin metadata",
+            );
+        }
+        commodity
+    }
+
+    fn into_imported(mut self) -> Imported {
+        // Sorting is stable, keeping file order within a day, which export preserves.
+        self.transactions.sort_by_key(|t| t.date);
+        self.assertions.sort_by_key(|a| a.date);
+        self.pads.sort_by_key(|p| p.date);
+
+        let currency = self.options.currency();
+        let mut ledger = Ledger::new(
+            self.options.ledger_kind().clone(),
+            "Imported Beancount ledger",
+            currency,
+        );
+        for account in self.accounts() {
+            ledger.add_account(account);
+        }
+
+        let mut journal = Journal::new(self.options.journal_name().clone(), currency);
+        let mut sequence: HashMap<NaiveDate, usize> = Default::default();
+        for transaction in std::mem::take(&mut self.transactions) {
+            let index = sequence.entry(transaction.date).or_default();
+            *index += 1;
+            let id = format!("{}-{:03}", transaction.date.format("%Y%m%d"), index);
+            if let Some(mapped) = self.map_transaction(transaction, id, &ledger) {
+                if let Err(e) = journal.add_transaction(mapped) {
+                    self.report.add("transaction", "", e.to_string());
+                }
+            }
+        }
+        self.check_assertions(&ledger, &mut journal);
+
+        let mut imported = Imported::default();
+        for price in std::mem::take(&mut self.prices) {
+            match (
+                self.commodity(&price.symbol),
+                self.commodity(&price.amount.symbol),
+            ) {
+                (Some(from), Some(into)) => imported.contents.prices.push(RateRecord::new(
+                    from,
+                    into,
+                    price.amount.number,
+                    midnight(price.date),
+                    "beancount",
+                )),
+                _ => self.report.add(
+                    "price",
+                    format!("line {}", price.line),
+                    format!(
+                        "the price of {:?} in {:?} uses an unmapped commodity",
+                        price.symbol, price.amount.symbol
+                    ),
+                ),
+            }
+        }
+        imported.contents.ledgers.push(ledger);
+        imported.contents.journals.push(journal);
+        imported.report = self.report;
+        imported
+    }
+
+    ///
+    /// Every opened, posted, asserted, or padded account, and each of their parents, in name
+    /// order. Accounts are dated by their `open` directive or, if they have none, by the first
+    /// date they, or any descendant, appear.
+    ///
+    fn accounts(&mut self) -> Vec<Account> {
+        let mut first_seen: BTreeMap<String, NaiveDate> = Default::default();
+        let mut seen = |name: &str, date: NaiveDate| {
+            let mut current = Some(name);
+            while let Some(name) = current {
+                let first = first_seen.entry(name.to_string()).or_insert(date);
+                *first = (*first).min(date);
+                current = name.rsplit_once(':').map(|(parent, _)| parent);
+            }
+        };
+        for (name, opened) in &self.opened {
+            seen(name, opened.date);
+        }
+        let mut posted: HashMap<String, String> = Default::default();
+        for transaction in &self.transactions {
+            for posting in &transaction.postings {
+                seen(&posting.account, transaction.date);
+                if let Some(units) = &posting.units {
+                    let _ = posted
+                        .entry(posting.account.clone())
+                        .or_insert_with(|| units.symbol.clone());
+                }
+            }
+        }
+        for assertion in &self.assertions {
+            seen(&assertion.account, assertion.date);
+        }
+        for pad in &self.pads {
+            seen(&pad.account, pad.date);
+            seen(&pad.source, pad.date);
+        }
+
+        let mut accounts: Vec<Account> = Default::default();
+        for (name, first) in first_seen {
+            let Some(kind) = root_kind(&name) else {
+                self.report.add(
+                    "account",
+                    &name,
+                    "the account is not under Assets, Liabilities, Equity, Income, or Expenses",
+                );
+                continue;
+            };
+            let (symbol, description, created) = match self.opened.get(&name) {
+                Some(opened) => (
+                    opened.currencies.first().cloned(),
+                    opened.description.clone(),
+                    opened.date,
+                ),
+                None => (None, None, first),
+            };
+            let commodity = symbol
+                .or_else(|| posted.get(&name).cloned())
+                .and_then(|symbol| self.commodity(&symbol))
+                .unwrap_or(CommodityId::Currency(self.options.currency()));
+            let description = description
+                .unwrap_or_else(|| name.rsplit(':').next().unwrap_or_default().to_string());
+            let mut account =
+                Account::new(AccountId::new(name.clone()), kind, description, commodity)
+                    .with_created(midnight(created));
+            if let Some((parent, _)) = name.rsplit_once(':') {
+                account = account.with_parent(AccountId::new(parent));
+            }
+            if self.closed.contains(&name) {
+                account.set_active(false);
+            }
+            accounts.push(account);
+        }
+        accounts
+    }
+
+    fn map_transaction(
+        &mut self,
+        transaction: ParsedTransaction,
+        id: String,
+        ledger: &Ledger,
+    ) -> Option<Transaction> {
+        let line = format!("line {}", transaction.line);
+        if let Some(posting) = transaction.postings.iter().find(|p| {
+            ledger
+                .account(&AccountId::from(p.account.as_str()))
+                .is_none()
+        }) {
+            self.report.add(
+                "transaction",
+                line,
+                format!("the account {:?} was not imported", posting.account),
+            );
+            return None;
+        }
+        let mut postings: Vec<Posting> = Default::default();
+        let mut scales: Vec<(CommodityId, u32)> = Default::default();
+        for posting in transaction.postings {
+            match self.map_posting(posting, &mut scales) {
+                Ok(posting) => postings.push(posting),
+                Err(symbol) => {
+                    self.report.add(
+                        "transaction",
+                        line,
+                        format!("the commodity {:?} is unmapped", symbol),
+                    );
+                    return None;
+                }
+            }
+        }
+        if let Err(reason) = balance(&mut postings, &scales, self.options) {
+            self.report.add("transaction", line, reason);
+            return None;
+        }
+
+        let mut metadata: BTreeMap<String, String> = transaction.metadata.into_iter().collect();
+        let id = TransactionId::new(metadata.remove("id").unwrap_or(id));
+        let posted = midnight(transaction.date);
+        // Beancount has no entry date, and the current time would make imports differ.
+        let mut mapped = Transaction::new(id.clone(), posted).with_entered(posted);
+        if let Some(reference) = metadata.remove("reference") {
+            mapped = mapped.with_reference(reference);
+        }
+        if let Some(narration) = transaction.narration {
+            mapped = mapped.with_description(narration);
+        }
+        if let Some(payee) = transaction.payee {
+            mapped = mapped.with_metadata("payee", payee);
+        }
+        if !transaction.tags.is_empty() {
+            mapped = mapped.with_metadata("tags", transaction.tags.join(" "));
+        }
+        if !transaction.links.is_empty() {
+            mapped = mapped.with_metadata("links", transaction.links.join(" "));
+        }
+        if transaction.flag != "*" {
+            mapped = mapped.with_metadata("flag", transaction.flag);
+        }
+        for (key, value) in metadata {
+            mapped = mapped.with_metadata(key, value);
+        }
+        for posting in postings {
+            // Balancing has filled in any missing amount.
+            let (commodity, units) = posting.units.unwrap();
+            let mut split = Split::new(
+                id.clone(),
+                AccountId::new(posting.account),
+                Quantity::new(commodity, units),
+            );
+            if let Some((weight_commodity, weight)) = posting.weight {
+                split = split.with_exchanged_from(RatedQuantity::new(
+                    Quantity::new(weight_commodity, weight),
+                    units.checked_div(weight).unwrap_or_default(),
+                ));
+            }
+            if let Some(description) = posting.description {
+                split = split.with_description(description);
+            }
+            mapped.add_split(split);
+        }
+        Some(mapped)
+    }
+
+    ///
+    /// Map the commodities of a posting and compute its weight from any cost, or else any
+    /// price; returns the first unmapped symbol on failure.
+    ///
+    fn map_posting(
+        &mut self,
+        posting: ParsedPosting,
+        scales: &mut Vec<(CommodityId, u32)>,
+    ) -> std::result::Result<Posting, String> {
+        let mut mapped = |parser: &mut Self, amount: &Amount| {
+            let commodity = parser
+                .commodity(&amount.symbol)
+                .ok_or_else(|| amount.symbol.clone())?;
+            match scales.iter_mut().find(|(c, _)| c == &commodity) {
+                Some((_, scale)) => *scale = (*scale).max(amount.number.scale()),
+                None => scales.push((commodity.clone(), amount.number.scale())),
+            }
+            Ok::<_, String>(commodity)
+        };
+        let units = match &posting.units {
+            Some(amount) => Some((mapped(self, amount)?, amount.number)),
+            None => None,
+        };
+        let mut unknown_cost = false;
+        let mut weight = None;
+        // A cost gives the posting's weight, and any price is then only informational.
+        for rate in [&posting.cost, &posting.price].into_iter().flatten() {
+            let (total, amount) = match rate {
+                Cost::Empty => {
+                    unknown_cost = weight.is_none();
+                    continue;
+                }
+                Cost::PerUnit(amount) => (false, amount),
+                Cost::Total(amount) => (true, amount),
+            };
+            let commodity = mapped(self, amount)?;
+            if weight.is_some() || unknown_cost {
+                continue;
+            }
+            if let Some((_, units)) = &units {
+                let value = if total {
+                    amount.number.abs()
+                } else {
+                    (amount.number * *units).abs()
+                };
+                weight = Some((
+                    commodity,
+                    if units.is_sign_negative() {
+                        -value
+                    } else {
+                        value
+                    },
+                ));
+            }
+        }
+        Ok(Posting {
+            account: posting.account,
+            units,
+            weight,
+            unknown_cost,
+            description: posting.description,
+        })
+    }
+
+    ///
+    /// Check each balance assertion, in date order, against the balance engine. A preceding
+    /// `pad` for the account first adds a transaction making up any difference.
+    ///
+    fn check_assertions(&mut self, ledger: &Ledger, journal: &mut Journal) {
+        let mut used = vec![false; self.pads.len()];
+        let mut by_symbol: HashMap<String, Journal> = Default::default();
+        let mut pad_sequence = 0;
+        for assertion in std::mem::take(&mut self.assertions) {
+            let line = format!("line {}", assertion.line);
+            let Some(commodity) = self.commodity(&assertion.amount.symbol) else {
+                self.report
+                    .add("balance", line, "the commodity is unmapped");
+                continue;
+            };
+            let account = AccountId::new(assertion.account.clone());
+            if ledger.account(&account).is_none() {
+                self.report
+                    .add("balance", line, "the account was not imported");
+                continue;
+            }
+            let symbol = assertion.amount.symbol.clone();
+            let filtered = by_symbol
+                .entry(symbol.clone())
+                .or_insert_with(|| in_commodity(journal, &commodity));
+            let until = midnight(assertion.date);
+            let actual = Balances::as_at(std::slice::from_ref(filtered), until)
+                .rolled_up(ledger, &account)
+                .net();
+            let expected = assertion.amount.number;
+
+            let pad = self
+                .pads
+                .iter()
+                .enumerate()
+                .rev()
+                .find(|(index, pad)| {
+                    !used[*index] && pad.account == assertion.account && pad.date < assertion.date
+                })
+                .map(|(index, _)| index);
+            if let Some(index) = pad {
+                used[index] = true;
+                let difference = expected - actual;
+                if difference.is_zero() {
+                    continue;
+                }
+                let pad = &self.pads[index];
+                pad_sequence += 1;
+                let id = TransactionId::new(format!(
+                    "{}-pad-{:03}",
+                    pad.date.format("%Y%m%d"),
+                    pad_sequence
+                ));
+                let posted = midnight(pad.date);
+                let make = || {
+                    Transaction::new(id.clone(), posted)
+                        .with_entered(posted)
+                        .with_description(format!(
+                            "Padding inserted for balance of {} {}",
+                            expected, symbol
+                        ))
+                        .with_metadata("flag", "P")
+                        .with_metadata("pad", pad.source.clone())
+                        .with_split(Split::new(
+                            id.clone(),
+                            account.clone(),
+                            Quantity::new(commodity.clone(), difference),
+                        ))
+                        .with_split(Split::new(
+                            id.clone(),
+                            AccountId::new(pad.source.clone()),
+                            Quantity::new(commodity.clone(), -difference),
+                        ))
+                };
+                if ledger
+                    .account(&AccountId::from(pad.source.as_str()))
+                    .is_none()
+                {
+                    self.report.add(
+                        "pad",
+                        format!("line {}", pad.line),
+                        "the source account was not imported",
+                    );
+                    continue;
+                }
+                if let Err(e) = journal.add_transaction(make()) {
+                    self.report
+                        .add("pad", format!("line {}", pad.line), e.to_string());
+                    continue;
+                }
+                let _ = filtered.add_transaction(make());
+                continue;
+            }
+
+            let tolerance = assertion
+                .tolerance
+                .unwrap_or_else(|| half_unit(expected.scale()));
+            if (actual - expected).abs() > tolerance {
+                self.report.add(
+                    "balance",
+                    line,
+                    format!(
+                        "{} has a balance of {} {}, not {} {}",
+                        assertion.account, actual, symbol, expected, symbol
+                    ),
+                );
+            }
+        }
+        for (pad, used) in self.pads.iter().zip(used) {
+            if !used {
+                self.report.add(
+                    "pad",
+                    format!("line {}", pad.line),
+                    "no balance assertion follows the pad",
+                );
+            }
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+///
+/// Split a line into tokens, stopping at any comment; `None` if a string is not closed.
+///
+fn tokenize(line: &str) -> Option<Vec<Token>> {
+    let mut tokens: Vec<Token> = Default::default();
+    let mut chars = line.chars().peekable();
+    let is_separator = |c: char| c.is_whitespace() || "{}@,;\"".contains(c);
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            ';' => break,
+            '"' => {
+                let mut text = String::new();
+                loop {
+                    match chars.next()? {
+                        '"' => break,
+                        '\\' => text.push(chars.next()?),
+                        c => text.push(c),
+                    }
+                }
+                tokens.push(Token::Text(text));
+            }
+            '{' | '}' | '@' => {
+                let double = chars.peek() == Some(&c);
+                if double {
+                    let _ = chars.next();
+                }
+                tokens.push(Token::Punct(match (c, double) {
+                    ('{', false) => "{",
+                    ('{', true) => "{{",
+                    ('}', false) => "}",
+                    ('}', true) => "}}",
+                    ('@', false) => "@",
+                    _ => "@@",
+                }));
+            }
+            ',' => tokens.push(Token::Punct(",")),
+            _ => {
+                let numeric = c.is_ascii_digit() || matches!(c, '-' | '+' | '.');
+                let mut value = String::from(c);
+                while let Some(&next) = chars.peek() {
+                    // Numbers may group their digits with commas.
+                    if is_separator(next) && !(numeric && next == ',') {
+                        break;
+                    }
+                    value.push(next);
+                    let _ = chars.next();
+                }
+                tokens.push(match c {
+                    '#' if value.len() > 1 => Token::Tag(value[1..].to_string()),
+                    '^' if value.len() > 1 => Token::Link(value[1..].to_string()),
+                    _ => Token::Word(value.trim_end_matches(',').to_string()),
+                });
+                if value.ends_with(',') {
+                    tokens.push(Token::Punct(","));
+                }
+            }
+        }
+    }
+    Some(tokens)
+}
+
+fn word(token: Option<Token>) -> Option<String> {
+    match token {
+        Some(Token::Word(word)) => Some(word),
+        _ => None,
+    }
+}
+
+fn decimal(token: &Token) -> Option<Decimal> {
+    match token {
+        Token::Word(number) => Decimal::from_str(&number.replace(',', "")).ok(),
+        _ => None,
+    }
+}
+
+fn amount(number: &Token, symbol: &Token) -> Option<Amount> {
+    match symbol {
+        Token::Word(symbol) if symbol.starts_with(|c: char| c.is_ascii_uppercase()) => {
+            Some(Amount {
+                number: decimal(number)?,
+                symbol: symbol.clone(),
+            })
+        }
+        _ => None,
+    }
+}
+
+///
+/// The key and value of a metadata line, such as `isin: "US0378331005"`.
+///
+fn metadata(tokens: &[Token]) -> Option<(String, String)> {
+    let key = match tokens.first() {
+        Some(Token::Word(key)) if key.starts_with(|c: char| c.is_ascii_lowercase()) => {
+            key.strip_suffix(':')?
+        }
+        _ => return None,
+    };
+    let value = match tokens.get(1) {
+        Some(Token::Word(value) | Token::Text(value)) => value.clone(),
+        Some(Token::Tag(tag)) => format!("#{}", tag),
+        Some(Token::Link(link)) => format!("^{}", link),
+        _ => String::new(),
+    };
+    Some((key.to_string(), value))
+}
+
+///
+/// Parse a posting, such as `Assets:Brokerage  10 AAPL {145.00 USD, 2022-10-10} @ 146 USD`.
+///
+fn posting(tokens: Vec<Token>) -> Option<ParsedPosting> {
+    let mut tokens = tokens.into_iter().peekable();
+    let mut account = word(tokens.next())?;
+    if account == "*" || account == "!" {
+        account = word(tokens.next())?;
+    }
+    if !account.starts_with(|c: char| c.is_ascii_uppercase()) {
+        return None;
+    }
+    let mut units = None;
+    if let Some(Token::Word(_)) = tokens.peek() {
+        let number = tokens.next()?;
+        units = Some(amount(&number, &tokens.next()?)?);
+    }
+    let mut cost = None;
+    if let Some(Token::Punct(open @ ("{" | "{{"))) = tokens.peek().cloned() {
+        let _ = tokens.next();
+        let close = if open == "{" { "}" } else { "}}" };
+        let mut inside: Vec<Token> = Default::default();
+        loop {
+            match tokens.next()? {
+                Token::Punct(p) if p == close => break,
+                token => inside.push(token),
+            }
+        }
+        // Components are separated by commas; only the amount matters, not a date or label.
+        let mut amounts = inside
+            .split(|token| token == &Token::Punct(","))
+            .filter_map(|component| match component {
+                [number, symbol] => amount(number, symbol),
+                _ => None,
+            });
+        cost = Some(match (amounts.next(), open) {
+            (None, _) => Cost::Empty,
+            (Some(amount), "{") => Cost::PerUnit(amount),
+            (Some(amount), _) => Cost::Total(amount),
+        });
+    }
+    let mut price = None;
+    if let Some(Token::Punct(at)) = tokens.next() {
+        let amount = amount(&tokens.next()?, &tokens.next()?)?;
+        price = Some(match at {
+            "@" => Cost::PerUnit(amount),
+            "@@" => Cost::Total(amount),
+            _ => return None,
+        });
+    }
+    if tokens.next().is_some() || (units.is_none() && (cost.is_some() || price.is_some())) {
+        return None;
+    }
+    Some(ParsedPosting {
+        account,
+        units,
+        cost,
+        price,
+        description: None,
+    })
+}
+
+///
+/// Fill in the one posting without an amount, or the one reducing a lot at an unknown cost,
+/// and check the postings balance in each commodity within the tolerance implied by the
+/// precision of the amounts written.
+///
+fn balance(
+    postings: &mut [Posting],
+    scales: &[(CommodityId, u32)],
+    options: &BeancountOptions,
+) -> std::result::Result<(), String> {
+    let mut residuals: Vec<(CommodityId, Decimal)> = Default::default();
+    for posting in postings.iter().filter(|p| !p.unknown_cost) {
+        if let Some((commodity, amount)) = posting.weight.as_ref().or(posting.units.as_ref()) {
+            match residuals.iter_mut().find(|(c, _)| c == commodity) {
+                Some((_, total)) => *total += *amount,
+                None => residuals.push((commodity.clone(), *amount)),
+            }
+        }
+    }
+    residuals.retain(|(commodity, total)| {
+        let scale = scales
+            .iter()
+            .find(|(c, _)| c == commodity)
+            .map(|(_, scale)| *scale)
+            .unwrap_or_default();
+        total.abs() > half_unit(scale)
+    });
+
+    let mut unknown = postings
+        .iter_mut()
+        .filter(|p| p.units.is_none() || p.unknown_cost);
+    match (unknown.next(), unknown.next()) {
+        (Some(_), Some(_)) => Err("more than one posting has no amount or cost".to_string()),
+        (Some(posting), None) => match (residuals.len(), posting.unknown_cost) {
+            (0, false) => {
+                posting.units = Some((CommodityId::Currency(options.currency()), Decimal::ZERO));
+                Ok(())
+            }
+            (1, false) => {
+                let (commodity, total) = residuals.remove(0);
+                posting.units = Some((commodity, -total));
+                Ok(())
+            }
+            (1, true) => {
+                let (commodity, total) = residuals.remove(0);
+                posting.weight = Some((commodity, -total));
+                posting.unknown_cost = false;
+                Ok(())
+            }
+            (0, true) => Err("the cost of the reduced lot cannot be inferred".to_string()),
+            _ => Err("the missing amount would be in more than one commodity".to_string()),
+        },
+        (None, _) if residuals.is_empty() => Ok(()),
+        (None, _) => Err("the postings do not balance".to_string()),
+    }
+}
+
+///
+/// A copy of `journal` with only the splits in `commodity`, as the balance engine totals
+/// quantities without regard to their commodity.
+///
+fn in_commodity(journal: &Journal, commodity: &CommodityId) -> Journal {
+    let mut filtered = Journal::new(journal.name().clone(), journal.currency());
+    for transaction in journal.transactions() {
+        let splits: Vec<Split> = transaction
+            .splits()
+            .filter(|split| split.quantity().commodity() == commodity)
+            .map(|split| {
+                Split::new(
+                    transaction.id().clone(),
+                    split.account().clone(),
+                    split.quantity().clone(),
+                )
+            })
+            .collect();
+        if !splits.is_empty() {
+            let _ = filtered.add_transaction(
+                Transaction::new(transaction.id().clone(), transaction.posted())
+                    .with_splits(splits),
+            );
+        }
+    }
+    filtered
+}
+
+///
+/// Half of the smallest unit at `scale` decimal places, Beancount's default tolerance.
+///
+fn half_unit(scale: u32) -> Decimal {
+    Decimal::new(5, scale + 1)
+}
+
+fn midnight(date: NaiveDate) -> DateTime<Utc> {
+    date.and_hms_opt(0, 0, 0).unwrap().and_utc()
+}
@@ -0,0 +1,291 @@
+/*!
+Write Beancount ledger files.
+
+*/
+
+use super::root_name;
+use crate::error::Result;
+use chrono::{DateTime, Days, Utc};
+use rust_decimal::Decimal;
+use sledge_model::commodity::{CommodityId, RateRecord};
+use sledge_model::journal::Transaction;
+use sledge_model::ledger::{Account, AccountId};
+use sledge_store::CreateDatastoreContents;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use tracing::info;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+pub fn write_beancount_file<P: AsRef<Path>>(
+    path: P,
+    contents: &CreateDatastoreContents,
+) -> Result<()> {
+    info!("Writing Beancount file {:?}", path.as_ref());
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_beancount(&mut writer, contents)?;
+    writer.flush()?;
+    Ok(())
+}
+
+///
+/// Write an `open` directive for the accounts of every ledger, the prices, the transactions of
+/// every journal in posted order, and then a `close` directive for each inactive account.
+///
+/// Account names are made valid for Beancount, under the root for their kind. Postings in a
+/// security are written with their total cost, and accounts holding a security use the `NONE`
+/// booking method so that reductions need not match a lot; other exchanges are written as `@@`
+/// totals.
+///
+pub fn write_beancount<W: Write>(writer: &mut W, contents: &CreateDatastoreContents) -> Result<()> {
+    let mut transactions: Vec<&Transaction> = contents
+        .journals
+        .iter()
+        .flat_map(|journal| journal.transactions())
+        .collect();
+    transactions.sort_by_key(|transaction| transaction.posted());
+    let mut first_posted: HashMap<&AccountId, DateTime<Utc>> = Default::default();
+    let mut last_posted: HashMap<&AccountId, DateTime<Utc>> = Default::default();
+    for transaction in &transactions {
+        for split in transaction.splits() {
+            let _ = first_posted
+                .entry(split.account())
+                .or_insert(transaction.posted());
+            let _ = last_posted.insert(split.account(), transaction.posted());
+        }
+    }
+
+    let mut names: HashMap<&AccountId, String> = Default::default();
+    let mut accounts: Vec<&Account> = Default::default();
+    for account in contents.ledgers.iter().flat_map(|ledger| ledger.book()) {
+        if !names.contains_key(account.id()) {
+            let _ = names.insert(account.id(), account_name(account));
+            accounts.push(account);
+        }
+    }
+    for account in &accounts {
+        let opened = first_posted
+            .get(account.id())
+            .map(|posted| (*posted).min(account.created()))
+            .unwrap_or(account.created());
+        write!(
+            writer,
+            "{} open {} {}",
+            format_date(opened),
+            names[account.id()],
+            format_symbol(account.commodity())
+        )?;
+        if let CommodityId::Security(_) = account.commodity() {
+            write!(writer, " \"NONE\"")?;
+        }
+        writeln!(writer)?;
+        if Some(account.description().as_str()) != account.id().as_str().rsplit(':').next() {
+            writeln!(writer, "  description: {}", quoted(account.description()))?;
+        }
+    }
+    if !accounts.is_empty() {
+        writeln!(writer)?;
+    }
+
+    let mut prices: Vec<_> = contents.prices.iter().collect();
+    prices.sort_by_key(|price| price.date());
+    for price in &prices {
+        writeln!(
+            writer,
+            "{} price {} {} {}",
+            format_date(price.date()),
+            format_symbol(price.from()),
+            price.rate(),
+            format_symbol(RateRecord::into(price))
+        )?;
+    }
+    if !prices.is_empty() {
+        writeln!(writer)?;
+    }
+
+    for transaction in transactions {
+        write_transaction(writer, transaction, &names)?;
+        writeln!(writer)?;
+    }
+
+    let mut closed = false;
+    for account in accounts.iter().filter(|account| !account.is_active()) {
+        let date = match last_posted.get(account.id()) {
+            // Postings are not allowed on or after the date an account is closed.
+            Some(posted) => posted.date_naive() + Days::new(1),
+            None => account.created().date_naive(),
+        };
+        writeln!(
+            writer,
+            "{} close {}",
+            date.format("%Y-%m-%d"),
+            names[account.id()]
+        )?;
+        closed = true;
+    }
+    if closed {
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+const HEADER_METADATA: [&str; 4] = ["payee", "tags", "links", "flag"];
+
+fn write_transaction<W: Write>(
+    writer: &mut W,
+    transaction: &Transaction,
+    names: &HashMap<&AccountId, String>,
+) -> Result<()> {
+    write!(
+        writer,
+        "{} {}",
+        format_date(transaction.posted()),
+        transaction
+            .metadata_value("flag")
+            .map(String::as_str)
+            .unwrap_or("*")
+    )?;
+    let narration = transaction.description().map(String::as_str).unwrap_or("");
+    if let Some(payee) = transaction.metadata_value("payee") {
+        write!(writer, " {} {}", quoted(payee), quoted(narration))?;
+    } else if transaction.description().is_some() {
+        write!(writer, " {}", quoted(narration))?;
+    }
+    for (key, prefix) in [("tags", '#'), ("links", '^')] {
+        for value in transaction
+            .metadata_value(key)
+            .into_iter()
+            .flat_map(|values| values.split_whitespace())
+        {
+            write!(writer, " {}{}", prefix, value)?;
+        }
+    }
+    writeln!(writer)?;
+
+    writeln!(writer, "  id: {}", quoted(transaction.id().as_str()))?;
+    if let Some(reference) = transaction.reference() {
+        writeln!(writer, "  reference: {}", quoted(reference))?;
+    }
+    for (key, value) in transaction.metadata() {
+        if !HEADER_METADATA.contains(&key.as_str()) {
+            writeln!(writer, "  {}: {}", key, quoted(value))?;
+        }
+    }
+
+    for split in transaction.splits() {
+        let name = names
+            .get(split.account())
+            .cloned()
+            .unwrap_or_else(|| sanitize(split.account().as_str()));
+        let quantity = split.quantity();
+        write!(
+            writer,
+            "  {}  {}",
+            name,
+            format_amount(quantity.commodity(), quantity.quantity())
+        )?;
+        if let Some(exchanged) = split.exchanged_from() {
+            let total = format_amount(
+                exchanged.quantity().commodity(),
+                exchanged.quantity().quantity().abs(),
+            );
+            match quantity.commodity() {
+                CommodityId::Security(_) => write!(writer, " {{{{{}}}}}", total)?,
+                CommodityId::Currency(_) => write!(writer, " @@ {}", total)?,
+            }
+        }
+        writeln!(writer)?;
+        if let Some(description) = split.description() {
+            writeln!(writer, "    description: {}", quoted(description))?;
+        }
+    }
+    Ok(())
+}
+
+///
+/// The Beancount name for an account, its identifier with each component made valid and,
+/// unless it already has one, the root for its kind prefixed.
+///
+fn account_name(account: &Account) -> String {
+    let name = sanitize(account.id().as_str());
+    let root = root_name(account.kind());
+    if name.split(':').next() == Some(root) {
+        name
+    } else {
+        format!("{}:{}", root, name)
+    }
+}
+
+///
+/// Beancount account name components start with a capital letter or digit, and contain only
+/// letters, digits, and dashes.
+///
+fn sanitize(name: &str) -> String {
+    name.split(':')
+        .map(|component| {
+            let mut component: String = component
+                .trim()
+                .chars()
+                .map(|c| {
+                    if c.is_alphanumeric() || c == '-' {
+                        c
+                    } else {
+                        '-'
+                    }
+                })
+                .collect();
+            match component.chars().next() {
+                Some(c) if c.is_ascii_digit() || c.is_uppercase() => {}
+                Some(c) if c.is_lowercase() => {
+                    component = c.to_uppercase().chain(component.chars().skip(1)).collect()
+                }
+                _ => component.insert(0, 'X'),
+            }
+            component
+        })
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+fn format_symbol(commodity: &CommodityId) -> String {
+    match commodity {
+        CommodityId::Currency(currency) => currency.alpha_code().to_string(),
+        CommodityId::Security(isin) => isin.to_string(),
+    }
+}
+
+fn format_amount(commodity: &CommodityId, amount: Decimal) -> String {
+    format!("{} {}", amount, format_symbol(commodity))
+}
+
+fn format_date(date: DateTime<Utc>) -> String {
+    date.date_naive().format("%Y-%m-%d").to_string()
+}
+
+///
+/// A string literal, on one line so that the file is read back as it was written.
+///
+fn quoted(text: &str) -> String {
+    format!(
+        "\"{}\"",
+        text.replace(['\n', '\r'], " ")
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+    )
+}
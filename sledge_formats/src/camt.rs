@@ -0,0 +1,344 @@
+/*!
+Import ISO 20022 bank-to-customer statements; camt.053 end-of-day statements, camt.052
+account reports, and camt.054 debit and credit notifications.
+
+Each statement, report, or notification becomes a [`Statement`] for the IBAN, or other
+identifier, of its account. The booked opening balance (`OPBD`, or the previous closing balance
+`PRCD`) and closing balance (`CLBD`) become the statement's balances, so that the
+reconciliation drafted from it checks the account against the bank's balance. Entries that are
+not booked, such as pending entries, are listed in the report.
+
+A booked entry with one set of transaction details becomes one statement entry. A batched
+entry, such as a collection of direct debits booked as a single amount, becomes an entry for
+each of its transaction details, provided their amounts add up to the entry's; otherwise it is
+imported as one entry, and listed in the report. The structured remittance information, the
+creditor reference (`CdtrRefInf`) such as an RF or national payment reference, becomes the
+entry's reference, or failing that the end-to-end identifier. The unstructured remittance
+information becomes the description, and the debtor of a credit or the creditor of a debit the
+counterparty.
+
+Entries are identified by the bank's reference (`AcctSvcrRef`), or the entry reference, and
+the transactions of a batched entry by their position in it; see
+[`draft_transactions`](crate::statement::draft_transactions).
+
+Both the 2009 versions of the messages, such as `camt.053.001.02`, and later ones are read;
+elements are matched by name, whatever their namespace.
+
+*/
+
+use crate::error::{invalid_value, missing_element, Result};
+use crate::statement::{Statement, StatementBalance, StatementEntry, Statements};
+use chrono::{DateTime, Days, NaiveDate, NaiveDateTime, Utc};
+use codes_iso_4217::CurrencyCode;
+use roxmltree::{Document, Node};
+use rust_decimal::Decimal;
+use sledge_model::ledger::AccountRepresentsKind;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::str::FromStr;
+use tracing::info;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+pub fn read_camt_file<P: AsRef<Path>>(path: P) -> Result<Statements> {
+    info!("Reading ISO 20022 statement file {:?}", path.as_ref());
+    read_camt(File::open(path)?)
+}
+
+pub fn read_camt<R: Read>(mut reader: R) -> Result<Statements> {
+    let mut bytes: Vec<u8> = Default::default();
+    let _ = reader.read_to_end(&mut bytes)?;
+    let text = String::from_utf8(bytes).map_err(|e| invalid_value("encoding", e.to_string()))?;
+    let document = Document::parse(text.trim_start_matches('\u{feff}'))?;
+    let root = document.root_element();
+    if root.tag_name().name() != "Document" {
+        return Err(invalid_value("root element", root.tag_name().name()));
+    }
+    let (message, statement) = MESSAGES
+        .iter()
+        .find_map(|(message, statement)| child(root, message).map(|node| (node, *statement)))
+        .ok_or_else(|| missing_element("Document", "BkToCstmrStmt"))?;
+
+    let mut statements = Statements::default();
+    for node in children(message, statement) {
+        read_statement(node, &mut statements)?;
+    }
+    Ok(statements)
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// The message element, and the statement elements within it, of camt.053, camt.052, and
+/// camt.054.
+///
+const MESSAGES: [(&str, &str); 3] = [
+    ("BkToCstmrStmt", "Stmt"),
+    ("BkToCstmrAcctRpt", "Rpt"),
+    ("BkToCstmrDbtCdtNtfctn", "Ntfctn"),
+];
+
+const NOT_PROVIDED: &str = "NOTPROVIDED";
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+fn read_statement(node: Node<'_, '_>, statements: &mut Statements) -> Result<()> {
+    let statement_id = text(node, &["Id"]).unwrap_or_default();
+    let account = text(node, &["Acct", "Id", "IBAN"])
+        .or_else(|| text(node, &["Acct", "Id", "Othr", "Id"]))
+        .ok_or_else(|| missing_element(node.tag_name().name(), "Acct/Id"))?;
+    let currency = text(node, &["Acct", "Ccy"])
+        .or_else(|| {
+            children(node, "Bal")
+                .chain(children(node, "Ntry"))
+                .find_map(|node| find(node, &["Amt"]).and_then(|amount| amount.attribute("Ccy")))
+        })
+        .ok_or_else(|| missing_element("Acct", "Ccy"))?;
+    let currency = CurrencyCode::from_str(currency).map_err(|_| invalid_value("Ccy", currency))?;
+
+    let mut statement = Statement::new(AccountRepresentsKind::BankAccount, account, currency);
+    if let (Some(from), Some(to)) = (
+        text(node, &["FrToDt", "FrDtTm"]),
+        text(node, &["FrToDt", "ToDtTm"]),
+    ) {
+        statement = statement.with_period(parse_datetime(from)?, parse_datetime(to)?);
+    }
+    for balance in children(node, "Bal") {
+        let kind = text(balance, &["Tp", "CdOrPrtry", "Cd"]).unwrap_or_default();
+        let amount = signed_amount(balance)?;
+        let date = date(balance, "Dt")?;
+        match kind {
+            "OPBD" | "PRCD" if statement.opening_balance().is_none() => {
+                statement = statement.with_opening_balance(StatementBalance::new(amount, date));
+            }
+            // A closing balance on a date is the balance at the end of that day.
+            "CLBD" => {
+                let as_at = if text(balance, &["Dt", "Dt"]).is_some() {
+                    date + Days::new(1)
+                } else {
+                    date
+                };
+                statement = statement.with_closing_balance(StatementBalance::new(amount, as_at));
+            }
+            _ => {}
+        }
+    }
+
+    for (n, entry) in children(node, "Ntry").enumerate() {
+        let id = text(entry, &["AcctSvcrRef"])
+            .or_else(|| text(entry, &["NtryRef"]))
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("{}/{}", statement_id, n + 1));
+        let status = text(entry, &["Sts", "Cd"])
+            .or_else(|| text(entry, &["Sts"]))
+            .unwrap_or_default();
+        if status != "BOOK" {
+            statements.report.add(
+                "entry",
+                id,
+                format!("the entry's status is {:?}, not booked", status),
+            );
+            continue;
+        }
+        for entry in entries(entry, id, statements)? {
+            statement.add_entry(entry);
+        }
+    }
+    statements.statements.push(statement);
+    Ok(())
+}
+
+///
+/// The statement entries for a booked entry; one for each of its transaction details if it is
+/// a batch whose details add up to its amount, and otherwise one.
+///
+fn entries(
+    entry: Node<'_, '_>,
+    id: String,
+    statements: &mut Statements,
+) -> Result<Vec<StatementEntry>> {
+    let posted = date(entry, "BookgDt")?;
+    let amount = signed_amount(entry)?;
+    let details: Vec<Node<'_, '_>> = children(entry, "NtryDtls")
+        .flat_map(|details| children(details, "TxDtls"))
+        .collect();
+
+    if details.len() > 1 {
+        let amounts: Option<Vec<Decimal>> = details
+            .iter()
+            .map(|details| transaction_amount(*details, amount))
+            .collect();
+        match amounts {
+            Some(amounts) if amounts.iter().sum::<Decimal>() == amount => {
+                return Ok(details
+                    .iter()
+                    .zip(amounts)
+                    .enumerate()
+                    .map(|(n, (details, amount))| {
+                        describe(
+                            StatementEntry::new(format!("{}/{}", id, n + 1), posted, amount),
+                            entry,
+                            Some(*details),
+                        )
+                    })
+                    .collect());
+            }
+            _ => statements.report.add(
+                "entry",
+                &id,
+                "the transaction details of the batched entry do not add up to its amount, it is \
+                 imported as one entry",
+            ),
+        }
+    }
+    Ok(vec![describe(
+        StatementEntry::new(id, posted, amount),
+        entry,
+        details.first().copied().filter(|_| details.len() == 1),
+    )])
+}
+
+fn describe(
+    mut statement_entry: StatementEntry,
+    entry: Node<'_, '_>,
+    details: Option<Node<'_, '_>>,
+) -> StatementEntry {
+    let entry_information = text(entry, &["AddtlNtryInf"]);
+    let Some(details) = details else {
+        if let Some(information) = entry_information {
+            statement_entry = statement_entry.with_description(information);
+        }
+        return statement_entry;
+    };
+
+    if let Some(reference) = text(details, &["RmtInf", "Strd", "CdtrRefInf", "Ref"])
+        .or_else(|| text(details, &["Refs", "EndToEndId"]).filter(|id| *id != NOT_PROVIDED))
+    {
+        statement_entry = statement_entry.with_reference(reference);
+    }
+    let unstructured: Vec<&str> = find(details, &["RmtInf"])
+        .into_iter()
+        .flat_map(|information| children(information, "Ustrd"))
+        .filter_map(|node| node.text())
+        .map(str::trim)
+        .collect();
+    if !unstructured.is_empty() {
+        statement_entry = statement_entry.with_description(unstructured.join(" "));
+        if let Some(information) = entry_information {
+            statement_entry = statement_entry.with_memo(information);
+        }
+    } else if let Some(information) = text(details, &["AddtlTxInf"]).or(entry_information) {
+        statement_entry = statement_entry.with_description(information);
+    }
+    // The counterparty of a credit is the debtor, and of a debit the creditor.
+    let party = if statement_entry.amount().is_sign_negative() {
+        "Cdtr"
+    } else {
+        "Dbtr"
+    };
+    if let Some(name) = text(details, &["RltdPties", party, "Nm"])
+        .or_else(|| text(details, &["RltdPties", party, "Pty", "Nm"]))
+    {
+        statement_entry = statement_entry.with_counterparty(name);
+    }
+    statement_entry
+}
+
+///
+/// The amount of a transaction within a batch, in the account's currency, signed as the
+/// entry's if the transaction has no credit or debit indicator of its own.
+///
+fn transaction_amount(details: Node<'_, '_>, entry_amount: Decimal) -> Option<Decimal> {
+    let amount = find(details, &["Amt"])
+        .or_else(|| find(details, &["AmtDtls", "TxAmt", "Amt"]))
+        .and_then(|node| node.text())
+        .and_then(|value| Decimal::from_str(value.trim()).ok())?;
+    let negative = match text(details, &["CdtDbtInd"]) {
+        Some(indicator) => indicator == "DBIT",
+        None => entry_amount.is_sign_negative(),
+    };
+    Some(if negative { -amount } else { amount })
+}
+
+///
+/// The amount of a balance or entry, negative for debits; a reversal reverses the sign.
+///
+fn signed_amount(node: Node<'_, '_>) -> Result<Decimal> {
+    let value =
+        text(node, &["Amt"]).ok_or_else(|| missing_element(node.tag_name().name(), "Amt"))?;
+    let amount = Decimal::from_str(value).map_err(|_| invalid_value("Amt", value))?;
+    let debit = text(node, &["CdtDbtInd"]) == Some("DBIT");
+    let reversal = text(node, &["RvslInd"]) == Some("true");
+    Ok(if debit != reversal { -amount } else { amount })
+}
+
+fn date(node: Node<'_, '_>, name: &str) -> Result<DateTime<Utc>> {
+    let value = text(node, &[name, "Dt"])
+        .or_else(|| text(node, &[name, "DtTm"]))
+        .ok_or_else(|| missing_element(node.tag_name().name(), name))?;
+    parse_datetime(value)
+}
+
+///
+/// Parse an ISO 8601 date, or date and time; a time without an offset is taken to be UTC.
+///
+fn parse_datetime(value: &str) -> Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|datetime| datetime.with_timezone(&Utc))
+        .or_else(|_| {
+            NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f")
+                .map(|local| local.and_utc())
+        })
+        .or_else(|_| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .map(|date| date.and_hms_opt(0, 0, 0).unwrap().and_utc())
+        })
+        .map_err(|_| invalid_value("date", value))
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children()
+        .find(|child| child.is_element() && child.tag_name().name() == name)
+}
+
+fn children<'a, 'input: 'a>(
+    node: Node<'a, 'input>,
+    name: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> + 'a {
+    node.children()
+        .filter(move |child| child.is_element() && child.tag_name().name() == name)
+}
+
+fn find<'a, 'input>(node: Node<'a, 'input>, path: &[&str]) -> Option<Node<'a, 'input>> {
+    path.iter().try_fold(node, |node, name| child(node, name))
+}
+
+fn text<'a>(node: Node<'a, '_>, path: &[&str]) -> Option<&'a str> {
+    find(node, path)
+        .and_then(|node| node.text())
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+}
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
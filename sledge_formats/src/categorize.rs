@@ -0,0 +1,446 @@
+/*!
+Categorize imported transactions, moving the amounts drafted to the uncategorized account to
+the accounts they belong in.
+
+A [`Rule`] has conditions, all of which must hold, on a transaction's description, amount,
+counterparty, or reference, and actions taken when they do: post the uncategorized amount to
+another account, divide it between accounts by percentage, set the transaction's action, or add
+tags. A [`Categorizer`] tries its rules in order of their priority, highest first and, among
+equal priorities, in the order they were added; the first rule that matches is applied.
+
+Transactions that no rule matches may instead be given a [`Suggestion`], learned from the
+transactions already categorized in a set of journals: the account most often used with the
+same account, for transactions with the same counterparty or words in their description.
+Suggestions are only applied when accepted with [`Categorizer::accept`].
+
+The amount matched by a rule is that of the statement entry; positive for money paid in to the
+account, and so the negation of the uncategorized split. Tags are kept, as by the importers,
+as the space-separated `tags` metadata value.
+
+*/
+
+use regex::Regex;
+use rust_decimal::Decimal;
+use sledge_model::journal::{Action, Journal, Reconciliation, Split, Transaction};
+use sledge_model::ledger::AccountId;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Clone, Debug)]
+pub enum RuleCondition {
+    /// The description matches the regular expression.
+    Description(Regex),
+    /// The amount is at least `min`, and at most `max`, where given.
+    Amount {
+        min: Option<Decimal>,
+        max: Option<Decimal>,
+    },
+    /// The counterparty matches the regular expression.
+    Counterparty(Regex),
+    /// The reference matches the regular expression.
+    Reference(Regex),
+}
+
+#[derive(Clone, Debug)]
+pub enum RuleAction {
+    /// Post the uncategorized amount to the account; only the first `Account` or `Split`
+    /// action of a rule is taken.
+    Account(AccountId),
+    /// Divide the uncategorized amount between the accounts by the percentages given, which
+    /// should add to 100; any amount left after rounding goes to the last account.
+    Split(Vec<(AccountId, Decimal)>),
+    /// Set the transaction's action.
+    Action(Action),
+    /// Add the tag.
+    Tag(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct Rule {
+    name: String,
+    priority: i32,
+    conditions: Vec<RuleCondition>,
+    actions: Vec<RuleAction>,
+}
+
+///
+/// An account suggested for an uncategorized amount, with the share, from 0 to 1, of the
+/// matching past transactions that used it.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct Suggestion {
+    pub account: AccountId,
+    pub confidence: f64,
+}
+
+///
+/// The accounts used with each account in past transactions, by the counterparty and the words
+/// of their descriptions.
+///
+#[derive(Clone, Debug, Default)]
+pub struct Suggestions {
+    by_key: HashMap<(AccountId, String), HashMap<AccountId, usize>>,
+}
+
+#[derive(Clone, Debug)]
+pub enum Categorization {
+    /// The named rule matched, and was applied.
+    Rule(String),
+    /// No rule matched; the account is suggested.
+    Suggested(Suggestion),
+    /// No rule matched and there is no suggestion, or the transaction had no uncategorized
+    /// amount.
+    Unmatched,
+}
+
+#[derive(Clone, Debug)]
+pub struct Categorizer {
+    uncategorized: AccountId,
+    rules: Vec<Rule>,
+    suggestions: Option<Suggestions>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+const TAGS: &str = "tags";
+
+const COUNTERPARTY: &str = "counterparty";
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl Rule {
+    pub fn new<S: Into<String>>(name: S, priority: i32) -> Self {
+        Self {
+            name: name.into(),
+            priority,
+            conditions: Default::default(),
+            actions: Default::default(),
+        }
+    }
+
+    pub fn with_condition(mut self, condition: RuleCondition) -> Self {
+        self.conditions.push(condition);
+        self
+    }
+
+    pub fn with_action(mut self, action: RuleAction) -> Self {
+        self.actions.push(action);
+        self
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    pub fn conditions(&self) -> impl Iterator<Item = &RuleCondition> {
+        self.conditions.iter()
+    }
+
+    pub fn actions(&self) -> impl Iterator<Item = &RuleAction> {
+        self.actions.iter()
+    }
+
+    ///
+    /// Returns `true` if every condition holds for `transaction`, whose uncategorized amount,
+    /// as the statement has it, is `amount`.
+    ///
+    pub fn matches(&self, transaction: &Transaction, amount: Decimal) -> bool {
+        self.conditions.iter().all(|condition| match condition {
+            RuleCondition::Description(regex) => transaction
+                .description()
+                .map(|description| regex.is_match(description))
+                .unwrap_or(false),
+            RuleCondition::Amount { min, max } => {
+                min.map(|min| amount >= min).unwrap_or(true)
+                    && max.map(|max| amount <= max).unwrap_or(true)
+            }
+            RuleCondition::Counterparty(regex) => transaction
+                .metadata_value(COUNTERPARTY)
+                .map(|counterparty| regex.is_match(counterparty))
+                .unwrap_or(false),
+            RuleCondition::Reference(regex) => transaction
+                .reference()
+                .map(|reference| regex.is_match(reference))
+                .unwrap_or(false),
+        })
+    }
+
+    fn apply(&self, transaction: &mut Transaction, uncategorized: Vec<Split>) {
+        let mut posted = false;
+        for action in &self.actions {
+            match action {
+                RuleAction::Account(account) if !posted => {
+                    post_to(
+                        transaction,
+                        &uncategorized,
+                        &[(account.clone(), Decimal::ONE_HUNDRED)],
+                    );
+                    posted = true;
+                }
+                RuleAction::Split(shares) if !posted => {
+                    post_to(transaction, &uncategorized, shares);
+                    posted = true;
+                }
+                RuleAction::Account(_) | RuleAction::Split(_) => {}
+                RuleAction::Action(action) => transaction.set_action(action.clone()),
+                RuleAction::Tag(tag) => add_tag(transaction, tag),
+            }
+        }
+        // Without an action to post it elsewhere the amount stays uncategorized.
+        if !posted {
+            for split in uncategorized {
+                transaction.add_split(split);
+            }
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl Suggestions {
+    ///
+    /// Learn from the transactions in `journals` that have no amount in the `uncategorized`
+    /// account.
+    ///
+    pub fn learn(journals: &[Journal], uncategorized: &AccountId) -> Self {
+        let mut suggestions = Self::default();
+        for transaction in journals.iter().flat_map(|journal| journal.transactions()) {
+            if transaction
+                .splits()
+                .any(|split| split.account() == uncategorized)
+            {
+                continue;
+            }
+            let mut accounts: Vec<&AccountId> = transaction.splits().map(Split::account).collect();
+            accounts.sort();
+            accounts.dedup();
+            let keys = keys(transaction);
+            for anchor in &accounts {
+                for account in accounts.iter().filter(|account| *account != anchor) {
+                    for key in &keys {
+                        *suggestions
+                            .by_key
+                            .entry(((*anchor).clone(), key.clone()))
+                            .or_default()
+                            .entry((*account).clone())
+                            .or_default() += 1;
+                    }
+                }
+            }
+        }
+        suggestions
+    }
+
+    ///
+    /// The account most often used with `account` in past transactions like `transaction`.
+    ///
+    pub fn suggest(&self, account: &AccountId, transaction: &Transaction) -> Option<Suggestion> {
+        let mut votes: HashMap<&AccountId, usize> = Default::default();
+        for key in keys(transaction) {
+            if let Some(counts) = self.by_key.get(&(account.clone(), key)) {
+                for (account, count) in counts {
+                    *votes.entry(account).or_default() += count;
+                }
+            }
+        }
+        let total: usize = votes.values().sum();
+        votes
+            .into_iter()
+            .max_by(|lhs, rhs| lhs.1.cmp(&rhs.1).then_with(|| rhs.0.cmp(lhs.0)))
+            .map(|(account, count)| Suggestion {
+                account: account.clone(),
+                confidence: count as f64 / total as f64,
+            })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_key.is_empty()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl Categorizer {
+    pub fn new(uncategorized: AccountId) -> Self {
+        Self {
+            uncategorized,
+            rules: Default::default(),
+            suggestions: None,
+        }
+    }
+
+    pub fn with_rule(mut self, rule: Rule) -> Self {
+        self.add_rule(rule);
+        self
+    }
+
+    ///
+    /// Suggest accounts for the transactions no rule matches.
+    ///
+    pub fn with_suggestions(self, suggestions: Suggestions) -> Self {
+        Self {
+            suggestions: Some(suggestions),
+            ..self
+        }
+    }
+
+    pub fn add_rule(&mut self, rule: Rule) {
+        self.rules.push(rule);
+        // A stable sort keeps rules of equal priority in the order they were added.
+        self.rules.sort_by_key(|rule| Reverse(rule.priority));
+    }
+
+    pub fn uncategorized(&self) -> &AccountId {
+        &self.uncategorized
+    }
+
+    pub fn rules(&self) -> impl Iterator<Item = &Rule> {
+        self.rules.iter()
+    }
+
+    pub fn categorize(&self, transaction: &mut Transaction) -> Categorization {
+        let uncategorized = transaction.remove_splits_for(&self.uncategorized);
+        if uncategorized.is_empty() {
+            return Categorization::Unmatched;
+        }
+        let amount: Decimal = -uncategorized
+            .iter()
+            .map(|split| split.quantity().quantity())
+            .sum::<Decimal>();
+        match self
+            .rules
+            .iter()
+            .find(|rule| rule.matches(transaction, amount))
+        {
+            Some(rule) => {
+                rule.apply(transaction, uncategorized);
+                Categorization::Rule(rule.name.clone())
+            }
+            None => {
+                let suggestion = self.suggestions.as_ref().and_then(|suggestions| {
+                    transaction
+                        .splits()
+                        .find_map(|split| suggestions.suggest(split.account(), transaction))
+                });
+                for split in uncategorized {
+                    transaction.add_split(split);
+                }
+                match suggestion {
+                    Some(suggestion) => Categorization::Suggested(suggestion),
+                    None => Categorization::Unmatched,
+                }
+            }
+        }
+    }
+
+    ///
+    /// Categorize each of the reconciliation's drafts, in order.
+    ///
+    pub fn categorize_drafts(&self, reconciliation: &mut Reconciliation) -> Vec<Categorization> {
+        reconciliation
+            .drafts_mut()
+            .map(|draft| self.categorize(draft))
+            .collect()
+    }
+
+    ///
+    /// Post the uncategorized amount of `transaction` to the suggested account.
+    ///
+    pub fn accept(&self, transaction: &mut Transaction, suggestion: &Suggestion) {
+        let uncategorized = transaction.remove_splits_for(&self.uncategorized);
+        post_to(
+            transaction,
+            &uncategorized,
+            &[(suggestion.account.clone(), Decimal::ONE_HUNDRED)],
+        );
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+///
+/// Post each uncategorized split to the accounts in `shares`, dividing it by their percentages
+/// at the split's own scale.
+///
+fn post_to(
+    transaction: &mut Transaction,
+    uncategorized: &[Split],
+    shares: &[(AccountId, Decimal)],
+) {
+    for split in uncategorized {
+        let quantity = split.quantity();
+        let scale = quantity.quantity().scale();
+        let mut remaining = quantity.quantity();
+        for (i, (account, percentage)) in shares.iter().enumerate() {
+            let amount = if i + 1 == shares.len() {
+                remaining
+            } else {
+                (quantity.quantity() * percentage / Decimal::ONE_HUNDRED).round_dp(scale)
+            };
+            remaining -= amount;
+            transaction.add_split(Split::new(
+                split.id().clone(),
+                account.clone(),
+                quantity.with_quantity(amount),
+            ));
+        }
+    }
+}
+
+fn add_tag(transaction: &mut Transaction, tag: &str) {
+    let tags = match transaction.metadata_value(TAGS) {
+        Some(tags) if tags.split(' ').any(|existing| existing == tag) => return,
+        Some(tags) => format!("{} {}", tags, tag),
+        None => tag.to_string(),
+    };
+    transaction.add_metadata(TAGS, tags);
+}
+
+///
+/// The keys a transaction is learned, and suggested, by; its counterparty, and the words of its
+/// description, ignoring case and any word with a digit, such as a date or reference number.
+///
+fn keys(transaction: &Transaction) -> Vec<String> {
+    let mut keys: Vec<String> = transaction
+        .metadata_value(COUNTERPARTY)
+        .map(|counterparty| format!("@{}", counterparty.to_lowercase()))
+        .into_iter()
+        .collect();
+    if let Some(description) = transaction.description() {
+        keys.extend(
+            description
+                .split(|c: char| !c.is_alphanumeric())
+                .filter(|word| word.len() > 2 && !word.chars().any(|c| c.is_ascii_digit()))
+                .map(str::to_lowercase),
+        );
+    }
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
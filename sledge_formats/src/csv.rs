@@ -0,0 +1,316 @@
+/*!
+Read the CSV statement exports of banks, laid out as described by a stored
+[`CsvProfile`](sledge_model::import::CsvProfile).
+
+Every bank's export differs, in its delimiter and encoding, in the rows before the entries, in
+how it writes dates and amounts, and in which columns hold what, so a profile is created once
+for each and kept in the data store (see `DataStore::import_profiles`). Fields may be quoted,
+as in RFC 4180, and a quoted field may hold the delimiter, a line break, or a doubled quote.
+
+Amounts may use either decimal separator, with the other separating thousands; currency
+symbols and spaces are ignored, and an amount in parentheses is negative. With separate debit
+and credit columns the amount is the credit less the debit.
+
+A CSV file names no account, so the statement read is given the profile's name as its account
+number, and the account to draft transactions for is chosen with
+[`StatementOptions::with_account`](crate::statement::StatementOptions::with_account). Each
+entry's identifier, used to skip entries already imported, is the value of the profile's entry
+identifier column or, if it has none, is made from the entry's date, amount, and description,
+and how many entries before it in the file share them; so re-importing an export that overlaps
+an earlier one drafts only the entries that are new. Rows that cannot be read are listed in the
+report, with the kind `"row"` and their line number.
+
+*/
+
+use crate::error::{invalid_value, missing_element, Result};
+use crate::statement::{Statement, StatementEntry, Statements};
+use crate::text;
+use chrono::{DateTime, Days, NaiveDate, NaiveDateTime, Utc};
+use rust_decimal::Decimal;
+use sledge_model::import::{CsvAmount, CsvColumn, CsvProfile, TextEncoding};
+use sledge_model::ledger::AccountRepresentsKind;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::str::FromStr;
+use tracing::info;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+pub fn read_csv_file<P: AsRef<Path>>(path: P, profile: &CsvProfile) -> Result<Statements> {
+    info!("Reading CSV file {:?}", path.as_ref());
+    read_csv(File::open(path)?, profile)
+}
+
+pub fn read_csv<R: Read>(mut reader: R, profile: &CsvProfile) -> Result<Statements> {
+    let mut bytes: Vec<u8> = Default::default();
+    let _ = reader.read_to_end(&mut bytes)?;
+    let text = decode(bytes, profile.encoding())?;
+
+    let mut records = records(text.trim_start_matches('\u{feff}'), profile.delimiter())
+        .into_iter()
+        .skip(profile.skip_rows());
+    let header = if profile.has_header() {
+        match records.next() {
+            Some((_, names)) => Some(names),
+            None => return Err(missing_element("CSV", "header row")),
+        }
+    } else {
+        None
+    };
+    let columns = Columns::resolve(profile, header.as_deref())?;
+
+    let mut statements = Statements::default();
+    let mut entries: Vec<StatementEntry> = Default::default();
+    let mut occurrences: HashMap<String, usize> = Default::default();
+    for (line, fields) in records {
+        if fields.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+        match columns.entry(profile, &fields, &mut occurrences) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => statements
+                .report
+                .add("row", line.to_string(), e.to_string()),
+        }
+    }
+
+    let mut statement = Statement::new(
+        AccountRepresentsKind::BankAccount,
+        profile.name().clone(),
+        profile.currency(),
+    );
+    let first = entries.iter().map(StatementEntry::posted).min();
+    let last = entries.iter().map(StatementEntry::posted).max();
+    if let (Some(first), Some(last)) = (first, last) {
+        // The statement covers the whole of the last day.
+        let end = (last.date_naive() + Days::new(1))
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc();
+        statement = statement.with_period(first, end);
+    }
+    for entry in entries {
+        statement.add_entry(entry);
+    }
+    statements.statements.push(statement);
+    Ok(statements)
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// The profile's columns, as positions in each row.
+///
+#[derive(Debug)]
+struct Columns {
+    date: usize,
+    amount: Amount,
+    description: Option<usize>,
+    reference: Option<usize>,
+    counterparty: Option<usize>,
+    id: Option<usize>,
+}
+
+#[derive(Debug)]
+enum Amount {
+    Signed(usize),
+    DebitCredit { debit: usize, credit: usize },
+}
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl Columns {
+    fn resolve(profile: &CsvProfile, header: Option<&[String]>) -> Result<Self> {
+        let column = |column: &CsvColumn| -> Result<usize> {
+            match column {
+                CsvColumn::Index(index) => Ok(*index),
+                CsvColumn::Header(name) => header
+                    .and_then(|header| {
+                        header
+                            .iter()
+                            .position(|field| field.trim().eq_ignore_ascii_case(name.trim()))
+                    })
+                    .ok_or_else(|| missing_element("header row", name)),
+            }
+        };
+        let optional = |value: Option<&CsvColumn>| value.map(column).transpose();
+        Ok(Self {
+            date: column(profile.date())?,
+            amount: match profile.amount() {
+                CsvAmount::Signed(amount) => Amount::Signed(column(amount)?),
+                CsvAmount::DebitCredit { debit, credit } => Amount::DebitCredit {
+                    debit: column(debit)?,
+                    credit: column(credit)?,
+                },
+            },
+            description: optional(profile.entry_description())?,
+            reference: optional(profile.reference())?,
+            counterparty: optional(profile.counterparty())?,
+            id: optional(profile.entry_id())?,
+        })
+    }
+
+    fn entry(
+        &self,
+        profile: &CsvProfile,
+        fields: &[String],
+        occurrences: &mut HashMap<String, usize>,
+    ) -> Result<StatementEntry> {
+        let field = |index: usize| fields.get(index).map(|field| field.trim()).unwrap_or("");
+        let optional = |index: Option<usize>| index.map(field).filter(|value| !value.is_empty());
+
+        let posted = parse_date(field(self.date), profile.date_format())?;
+        let separator = profile.decimal_separator();
+        let amount = match self.amount {
+            Amount::Signed(amount) => parse_amount(field(amount), separator)?
+                .ok_or_else(|| invalid_value("amount", ""))?,
+            Amount::DebitCredit { debit, credit } => {
+                let debit = parse_amount(field(debit), separator)?;
+                let credit = parse_amount(field(credit), separator)?;
+                if debit.is_none() && credit.is_none() {
+                    return Err(invalid_value("amount", ""));
+                }
+                credit.unwrap_or_default().abs() - debit.unwrap_or_default().abs()
+            }
+        };
+        let description = optional(self.description);
+
+        let id = match optional(self.id) {
+            Some(id) => id.to_string(),
+            None => {
+                let key = format!(
+                    "{}/{}/{}",
+                    posted.date_naive(),
+                    amount.normalize(),
+                    description.unwrap_or_default()
+                );
+                let count = occurrences.entry(key.clone()).or_default();
+                *count += 1;
+                format!("{}/{}", key, count)
+            }
+        };
+
+        let mut entry = StatementEntry::new(id, posted, amount);
+        if let Some(description) = description {
+            entry = entry.with_description(description);
+        }
+        if let Some(reference) = optional(self.reference) {
+            entry = entry.with_reference(reference);
+        }
+        if let Some(counterparty) = optional(self.counterparty) {
+            entry = entry.with_counterparty(counterparty);
+        }
+        Ok(entry)
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+fn decode(bytes: Vec<u8>, encoding: TextEncoding) -> Result<String> {
+    match encoding {
+        TextEncoding::Utf8 => text::utf_8(bytes),
+        TextEncoding::Latin1 => Ok(text::latin_1(bytes)),
+        TextEncoding::Windows1252 => Ok(text::windows_1252(bytes)),
+    }
+}
+
+///
+/// Split `text` into records of fields, each with the line it starts on.
+///
+fn records(text: &str, delimiter: char) -> Vec<(usize, Vec<String>)> {
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut line = 1;
+    let mut start = 1;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    let _ = chars.next();
+                }
+                '"' => quoted = false,
+                _ => {
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    field.push(c);
+                }
+            }
+        } else if c == '"' && field.trim().is_empty() {
+            field.clear();
+            quoted = true;
+        } else if c == delimiter {
+            fields.push(std::mem::take(&mut field));
+        } else if c == '\n' {
+            fields.push(std::mem::take(&mut field));
+            records.push((start, std::mem::take(&mut fields)));
+            line += 1;
+            start = line;
+        } else if c != '\r' {
+            field.push(c);
+        }
+    }
+    if !field.is_empty() || !fields.is_empty() {
+        fields.push(field);
+        records.push((start, fields));
+    }
+    records
+}
+
+fn parse_date(value: &str, format: &str) -> Result<DateTime<Utc>> {
+    NaiveDate::parse_from_str(value, format)
+        .map(|date| date.and_hms_opt(0, 0, 0).unwrap())
+        .or_else(|_| NaiveDateTime::parse_from_str(value, format))
+        .map(|posted| posted.and_utc())
+        .map_err(|_| invalid_value("date", value))
+}
+
+///
+/// Parse an amount written with `separator` as its decimal separator; an empty value is
+/// `None`.
+///
+fn parse_amount(value: &str, separator: char) -> Result<Option<Decimal>> {
+    if value.is_empty() {
+        return Ok(None);
+    }
+    let negative = value.contains('-') || (value.starts_with('(') && value.ends_with(')'));
+    let mut digits = String::with_capacity(value.len());
+    for c in value.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+        } else if c == separator {
+            digits.push('.');
+        }
+    }
+    if !digits.chars().any(|c| c.is_ascii_digit()) {
+        return Err(invalid_value("amount", value));
+    }
+    let amount = Decimal::from_str(&digits).map_err(|_| invalid_value("amount", value))?;
+    Ok(Some(if negative { -amount } else { amount }))
+}
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
@@ -0,0 +1,167 @@
+/*!
+Provides the crate's Error and Result types as well as helper
+functions.
+
+ */
+
+use std::fmt::{Debug, Display};
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// The Error type for this crate.
+///
+#[derive(Debug)]
+pub enum Error {
+    /// An error was signaled by the standard library I/O functions.
+    IoError { source: std::io::Error },
+    /// The source is not well-formed XML.
+    XmlError { source: roxmltree::Error },
+    /// An error was signaled by the SQLite database.
+    SqlError { source: rusqlite::Error },
+    /// The model rejected the data being imported or exported.
+    ModelError { source: sledge_model::error::Error },
+    /// The source is missing an element the format requires.
+    MissingElement { parent: String, name: String },
+    /// An element or field has a value that could not be parsed.
+    InvalidValue { name: String, value: String },
+    /// A line of a text format could not be parsed.
+    SyntaxError { line: usize, text: String },
+}
+
+///
+/// A Result type that specifically uses this crate's Error.
+///
+pub type Result<T> = std::result::Result<T, Error>;
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+/// Construct an Error from the provided source.
+#[inline]
+pub fn io_error(source: std::io::Error) -> Error {
+    Error::IoError { source }
+}
+
+/// Construct an Error from the provided source.
+#[inline]
+pub fn xml_error(source: roxmltree::Error) -> Error {
+    Error::XmlError { source }
+}
+
+/// Construct an Error from the provided source.
+#[inline]
+pub fn sql_error(source: rusqlite::Error) -> Error {
+    Error::SqlError { source }
+}
+
+/// Construct an Error from the provided source.
+#[inline]
+pub fn model_error(source: sledge_model::error::Error) -> Error {
+    Error::ModelError { source }
+}
+
+/// Construct an Error from the provided parent and element names.
+#[inline]
+pub fn missing_element<S1, S2>(parent: S1, name: S2) -> Error
+where
+    S1: Into<String>,
+    S2: Into<String>,
+{
+    Error::MissingElement {
+        parent: parent.into(),
+        name: name.into(),
+    }
+}
+
+/// Construct an Error from the provided name and value.
+#[inline]
+pub fn invalid_value<S1, S2>(name: S1, value: S2) -> Error
+where
+    S1: Into<String>,
+    S2: Into<String>,
+{
+    Error::InvalidValue {
+        name: name.into(),
+        value: value.into(),
+    }
+}
+
+/// Construct an Error from the provided line number and text.
+#[inline]
+pub fn syntax_error<S>(line: usize, text: S) -> Error
+where
+    S: Into<String>,
+{
+    Error::SyntaxError {
+        line,
+        text: text.into(),
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Error::IoError { source } => format!("An I/O error occurred; source: {}", source),
+                Error::XmlError { source } =>
+                    format!("The XML source could not be parsed; source: {}", source),
+                Error::SqlError { source } =>
+                    format!("The SQLite database could not be read; source: {}", source),
+                Error::ModelError { source } =>
+                    format!("The data is not valid for the model; source: {}", source),
+                Error::MissingElement { parent, name } =>
+                    format!("The element {:?} is missing a required {:?}", parent, name),
+                Error::InvalidValue { name, value } =>
+                    format!("The value {:?} is not valid for {:?}", value, name),
+                Error::SyntaxError { line, text } =>
+                    format!("The line {} could not be parsed; text: {:?}", line, text),
+            }
+        )
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::IoError { source } => Some(source),
+            Error::XmlError { source } => Some(source),
+            Error::SqlError { source } => Some(source),
+            Error::ModelError { source } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(source: std::io::Error) -> Self {
+        io_error(source)
+    }
+}
+
+impl From<roxmltree::Error> for Error {
+    fn from(source: roxmltree::Error) -> Self {
+        xml_error(source)
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(source: rusqlite::Error) -> Self {
+        sql_error(source)
+    }
+}
+
+impl From<sledge_model::error::Error> for Error {
+    fn from(source: sledge_model::error::Error) -> Self {
+        model_error(source)
+    }
+}
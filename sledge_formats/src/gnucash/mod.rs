@@ -0,0 +1,708 @@
+/*!
+Import GnuCash books.
+
+Both GnuCash storage formats are first read into the same intermediate form, mirroring the
+GnuCash object model, and then converted into a single [`Ledger`] and [`Journal`] with the
+book's prices and reconciliation state. Accounts are identified by their full GnuCash name,
+such as `"Expenses:Insurance:Home Insurance"`, and the book's root account is dropped so its
+children become top-level accounts. Placeholder accounts become non-recording accounts, and
+hidden accounts become inactive.
+
+GnuCash commodities that are not currencies can only be imported as securities when their
+ISIN is known, either from the commodity's `xcode` or from the [`GnuCashOptions`]. Accounts in
+unmapped commodities are skipped, and so is any transaction with a split posted to a skipped
+account, as the transaction would not otherwise balance. Everything skipped, along with the
+GnuCash features that have no equivalent here, such as scheduled transactions and budgets, is
+listed in the [`ImportReport`](crate::ImportReport).
+
+*/
+
+use crate::error::{invalid_value, missing_element, Result};
+use crate::Imported;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use codes_iso_4217::CurrencyCode;
+use rust_decimal::Decimal;
+use sledge_model::commodity::{
+    CommodityId, InternationalSecuritiesId, Quantity, RateRecord, RatedQuantity,
+};
+use sledge_model::journal::{Journal, Reconciled, Split, Transaction, TransactionId};
+use sledge_model::ledger::{Account, AccountId, AccountKind, Ledger, LedgerKind};
+use std::collections::HashMap;
+use std::str::FromStr;
+use tracing::{trace, warn};
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Clone, Debug)]
+pub struct GnuCashOptions {
+    ledger_kind: LedgerKind,
+    journal_name: String,
+    securities: HashMap<String, InternationalSecuritiesId>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct CommodityRef {
+    pub(crate) space: String,
+    pub(crate) id: String,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct GncCommodity {
+    pub(crate) commodity: CommodityRef,
+    pub(crate) xcode: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct GncAccount {
+    pub(crate) guid: String,
+    pub(crate) name: String,
+    pub(crate) kind: String,
+    pub(crate) commodity: Option<CommodityRef>,
+    pub(crate) description: Option<String>,
+    pub(crate) parent: Option<String>,
+    pub(crate) placeholder: bool,
+    pub(crate) hidden: bool,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct GncSplit {
+    pub(crate) guid: String,
+    pub(crate) memo: Option<String>,
+    pub(crate) action: Option<String>,
+    pub(crate) reconciled_state: char,
+    pub(crate) reconcile_date: Option<DateTime<Utc>>,
+    pub(crate) value: Decimal,
+    pub(crate) quantity: Decimal,
+    pub(crate) account: String,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct GncTransaction {
+    pub(crate) guid: String,
+    pub(crate) currency: CommodityRef,
+    pub(crate) num: Option<String>,
+    pub(crate) posted: DateTime<Utc>,
+    pub(crate) entered: DateTime<Utc>,
+    pub(crate) description: Option<String>,
+    pub(crate) splits: Vec<GncSplit>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct GncPrice {
+    pub(crate) guid: String,
+    pub(crate) commodity: CommodityRef,
+    pub(crate) currency: CommodityRef,
+    pub(crate) time: DateTime<Utc>,
+    pub(crate) source: Option<String>,
+    pub(crate) value: Decimal,
+    /// Set when the value, as GnuCash kept it, has no exact decimal and was rounded.
+    pub(crate) rounded: bool,
+}
+
+///
+/// Anything in the book with no equivalent in the model, such as budgets, scheduled
+/// transactions, and business objects.
+///
+#[derive(Clone, Debug)]
+pub(crate) struct GncOther {
+    pub(crate) kind: String,
+    pub(crate) id: String,
+    pub(crate) reason: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct GncBook {
+    pub(crate) id: String,
+    pub(crate) commodities: Vec<GncCommodity>,
+    pub(crate) accounts: Vec<GncAccount>,
+    pub(crate) transactions: Vec<GncTransaction>,
+    pub(crate) prices: Vec<GncPrice>,
+    pub(crate) other: Vec<GncOther>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl Default for GnuCashOptions {
+    fn default() -> Self {
+        Self {
+            ledger_kind: LedgerKind::General,
+            journal_name: "general".to_string(),
+            securities: Default::default(),
+        }
+    }
+}
+
+impl GnuCashOptions {
+    pub fn with_ledger_kind(self, ledger_kind: LedgerKind) -> Self {
+        Self {
+            ledger_kind,
+            ..self
+        }
+    }
+
+    pub fn with_journal_name<S: Into<String>>(self, journal_name: S) -> Self {
+        Self {
+            journal_name: journal_name.into(),
+            ..self
+        }
+    }
+
+    ///
+    /// Import the GnuCash commodity with the mnemonic `symbol`, such as `"AMZN"`, as the
+    /// security `isin`.
+    ///
+    pub fn with_security<S: Into<String>>(
+        mut self,
+        symbol: S,
+        isin: InternationalSecuritiesId,
+    ) -> Self {
+        let _ = self.securities.insert(symbol.into(), isin);
+        self
+    }
+
+    pub fn ledger_kind(&self) -> &LedgerKind {
+        &self.ledger_kind
+    }
+
+    pub fn journal_name(&self) -> &String {
+        &self.journal_name
+    }
+
+    pub fn security(&self, symbol: &str) -> Option<&InternationalSecuritiesId> {
+        self.securities.get(symbol)
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl CommodityRef {
+    pub(crate) fn new<S1: Into<String>, S2: Into<String>>(space: S1, id: S2) -> Self {
+        Self {
+            space: space.into(),
+            id: id.into(),
+        }
+    }
+
+    fn is_currency(&self) -> bool {
+        self.space == "CURRENCY" || self.space == "ISO4217"
+    }
+
+    fn is_template(&self) -> bool {
+        self.space == "template"
+    }
+}
+
+impl std::fmt::Display for CommodityRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.space, self.id)
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl GncOther {
+    pub(crate) fn new<S1, S2>(kind: S1, id: S2, name: Option<String>) -> Self
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+    {
+        Self {
+            kind: kind.into(),
+            id: id.into(),
+            reason: match name.filter(|name| !name.is_empty()) {
+                Some(name) => format!("{:?} has no equivalent and is not imported", name),
+                None => "has no equivalent and is not imported".to_string(),
+            },
+        }
+    }
+
+    pub(crate) fn template<S: Into<String>>(id: S) -> Self {
+        Self {
+            kind: "transaction".to_string(),
+            id: id.into(),
+            reason: "scheduled transaction templates are not imported".to_string(),
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl GncBook {
+    ///
+    /// Convert the book into the content for a new data store.
+    ///
+    pub(crate) fn convert(self, options: &GnuCashOptions) -> Result<Imported> {
+        let mut imported = Imported::default();
+        let report = &mut imported.report;
+
+        let commodities = map_commodities(&self, options, report);
+        let (accounts, currency) = map_accounts(&self, &commodities, report)?;
+
+        let mut ledger = Ledger::new(
+            options.ledger_kind.clone(),
+            format!("GnuCash book {}", self.id),
+            currency,
+        );
+        // Storage order differs between formats, sorting by name also puts parents first.
+        let mut book: Vec<Account> = accounts.values().flatten().cloned().collect();
+        book.sort_by(|lhs, rhs| lhs.id().cmp(rhs.id()));
+        for account in book {
+            ledger.add_account(account);
+        }
+
+        let mut journal = Journal::new(options.journal_name.clone(), currency);
+        let mut transactions = self.transactions;
+        transactions.sort_by(|lhs, rhs| (lhs.posted, &lhs.guid).cmp(&(rhs.posted, &rhs.guid)));
+        for transaction in transactions {
+            match map_transaction(&transaction, &commodities, &accounts, report) {
+                Some((mapped, reconciled)) => {
+                    if let Err(e) = journal.add_transaction(mapped) {
+                        warn!(
+                            "Could not add transaction {}; error: {}",
+                            transaction.guid, e
+                        );
+                        report.add("transaction", &transaction.guid, e.to_string());
+                    } else {
+                        imported.contents.reconciliations.extend(reconciled);
+                    }
+                }
+                None => trace!("Skipped transaction {}", transaction.guid),
+            }
+        }
+
+        let mut prices = self.prices;
+        prices.sort_by(|lhs, rhs| (lhs.time, &lhs.guid).cmp(&(rhs.time, &rhs.guid)));
+        for price in prices {
+            match (
+                commodities.get(&price.commodity).cloned().flatten(),
+                commodities.get(&price.currency).cloned().flatten(),
+            ) {
+                (Some(from), Some(into)) => {
+                    if price.rounded {
+                        report.add(
+                            "price",
+                            &price.guid,
+                            format!("the value has no exact decimal, rounded to {}", price.value),
+                        );
+                    }
+                    imported.contents.prices.push(RateRecord::new(
+                        from,
+                        into,
+                        price.value,
+                        price.time,
+                        price.source.unwrap_or_else(|| "gnucash".to_string()),
+                    ))
+                }
+                _ => report.add(
+                    "price",
+                    &price.guid,
+                    format!(
+                        "the price of {} in {} uses an unmapped commodity",
+                        price.commodity, price.currency
+                    ),
+                ),
+            }
+        }
+
+        for other in self.other {
+            report.add(other.kind, other.id, other.reason);
+        }
+
+        imported.contents.ledgers.push(ledger);
+        imported.contents.journals.push(journal);
+        Ok(imported)
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+///
+/// Convert a GnuCash rational number, `num/denom`, into an exact decimal; this is always
+/// possible when the denominator is a power of ten, as it is for all amounts GnuCash writes,
+/// and `None` is returned for any number, such as `1/3`, that has no exact decimal.
+///
+pub(crate) fn rational(num: i64, denom: i64) -> Option<Decimal> {
+    if denom <= 0 {
+        return None;
+    }
+    let mut scale = 0;
+    let mut remaining = denom;
+    while remaining % 10 == 0 {
+        remaining /= 10;
+        scale += 1;
+    }
+    if remaining == 1 {
+        Decimal::try_from_i128_with_scale(i128::from(num), scale).ok()
+    } else {
+        let quotient = Decimal::from(num).checked_div(Decimal::from(denom))?;
+        (quotient.checked_mul(Decimal::from(denom)) == Some(Decimal::from(num))).then_some(quotient)
+    }
+}
+
+///
+/// Convert a price, which GnuCash keeps as any fraction its quote or calculation produced;
+/// a value with no exact decimal is rounded, and returned with `true` so it can be reported.
+///
+pub(crate) fn price_rational(num: i64, denom: i64) -> Option<(Decimal, bool)> {
+    match rational(num, denom) {
+        Some(value) => Some((value, false)),
+        None if denom > 0 => Decimal::from(num)
+            .checked_div(Decimal::from(denom))
+            .map(|value| (value, true)),
+        None => None,
+    }
+}
+
+pub(crate) fn parse_rational(value: &str) -> Result<Decimal> {
+    let (num, denom) = split_rational(value)?;
+    rational(num, denom).ok_or_else(|| invalid_value("numeric", value))
+}
+
+pub(crate) fn split_rational(value: &str) -> Result<(i64, i64)> {
+    let (num, denom) = value
+        .trim()
+        .split_once('/')
+        .ok_or_else(|| invalid_value("numeric", value))?;
+    match (num.parse::<i64>(), denom.parse::<i64>()) {
+        (Ok(num), Ok(denom)) => Ok((num, denom)),
+        _ => Err(invalid_value("numeric", value)),
+    }
+}
+
+///
+/// Parse a GnuCash timestamp; the XML format includes a zone offset, the SQL format is UTC
+/// without one, and dates alone are taken as midnight UTC.
+///
+pub(crate) fn parse_timestamp(value: &str) -> Result<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(date_time) = DateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S %z") {
+        Ok(date_time.with_timezone(&Utc))
+    } else if let Ok(date_time) = NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S") {
+        Ok(date_time.and_utc())
+    } else if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        Ok(date.and_hms_opt(0, 0, 0).unwrap().and_utc())
+    } else {
+        Err(invalid_value("timestamp", value))
+    }
+}
+
+fn map_commodities(
+    book: &GncBook,
+    options: &GnuCashOptions,
+    report: &mut crate::ImportReport,
+) -> HashMap<CommodityRef, Option<CommodityId>> {
+    let mut commodities: HashMap<CommodityRef, Option<CommodityId>> = Default::default();
+    let referenced = book
+        .commodities
+        .iter()
+        .map(|c| (c.commodity.clone(), c.xcode.clone()))
+        .chain(
+            book.accounts
+                .iter()
+                .filter_map(|a| a.commodity.clone())
+                .chain(book.transactions.iter().map(|t| t.currency.clone()))
+                .chain(
+                    book.prices
+                        .iter()
+                        .flat_map(|p| [p.commodity.clone(), p.currency.clone()]),
+                )
+                .map(|c| (c, None)),
+        );
+    for (commodity, xcode) in referenced {
+        if commodity.is_template() || commodities.contains_key(&commodity) {
+            continue;
+        }
+        let mapped = if commodity.is_currency() {
+            CurrencyCode::from_str(&commodity.id)
+                .ok()
+                .map(CommodityId::Currency)
+        } else {
+            options
+                .security(&commodity.id)
+                .cloned()
+                .or_else(|| {
+                    [xcode.as_deref(), Some(commodity.id.as_str())]
+                        .into_iter()
+                        .flatten()
+                        .map(InternationalSecuritiesId::from)
+                        .find(InternationalSecuritiesId::is_valid)
+                })
+                .map(CommodityId::Security)
+        };
+        if mapped.is_none() {
+            report.add(
+                "commodity",
+                commodity.to_string(),
+                if commodity.is_currency() {
+                    "not an ISO 4217 currency code"
+                } else {
+                    "the security has no known ISIN"
+                },
+            );
+        }
+        let _ = commodities.insert(commodity, mapped);
+    }
+    commodities
+}
+
+fn map_accounts(
+    book: &GncBook,
+    commodities: &HashMap<CommodityRef, Option<CommodityId>>,
+    report: &mut crate::ImportReport,
+) -> Result<(HashMap<String, Option<Account>>, CurrencyCode)> {
+    let by_guid: HashMap<&String, &GncAccount> =
+        book.accounts.iter().map(|a| (&a.guid, a)).collect();
+    let is_root = |guid: &String| {
+        by_guid
+            .get(guid)
+            .map(|a| a.kind == "ROOT")
+            .unwrap_or_default()
+    };
+    // Names are trimmed, GnuCash keeps any stray whitespace entered with them.
+    let full_name = |account: &GncAccount| {
+        let mut names = vec![account.name.trim()];
+        let mut current = account.parent.as_ref();
+        while let Some(parent) = current.and_then(|guid| by_guid.get(guid)) {
+            if parent.kind == "ROOT" || names.len() > by_guid.len() {
+                break;
+            }
+            names.push(parent.name.trim());
+            current = parent.parent.as_ref();
+        }
+        names.reverse();
+        names.join(":")
+    };
+
+    let mut currency = None;
+    let mut accounts: HashMap<String, Option<Account>> = Default::default();
+    for account in &book.accounts {
+        if account.kind == "ROOT" {
+            if let Some(Some(CommodityId::Currency(root_currency))) =
+                account.commodity.as_ref().and_then(|c| commodities.get(c))
+            {
+                currency = Some(*root_currency);
+            }
+            continue;
+        }
+        let name = full_name(account);
+        let kind = account_kind(&account.kind);
+        let commodity = account
+            .commodity
+            .as_ref()
+            .and_then(|c| commodities.get(c))
+            .cloned()
+            .flatten();
+        let mapped = match (kind, commodity) {
+            (Some(kind), Some(commodity)) => {
+                let mut mapped = Account::new(
+                    AccountId::new(name),
+                    kind,
+                    account
+                        .description
+                        .as_deref()
+                        .map(str::trim)
+                        .filter(|d| !d.is_empty())
+                        .unwrap_or_else(|| account.name.trim()),
+                    commodity,
+                );
+                if account.placeholder {
+                    mapped = mapped.non_recording();
+                }
+                if account.hidden {
+                    mapped.set_active(false);
+                }
+                Some(mapped)
+            }
+            (None, _) => {
+                report.add(
+                    "account",
+                    &account.guid,
+                    format!("{:?} has the unknown type {:?}", name, account.kind),
+                );
+                None
+            }
+            (_, None) => {
+                report.add(
+                    "account",
+                    &account.guid,
+                    format!("{:?} is in an unmapped commodity", name),
+                );
+                None
+            }
+        };
+        let _ = accounts.insert(account.guid.clone(), mapped);
+    }
+
+    // Parent each account on its nearest mapped ancestor.
+    let parents: Vec<(String, AccountId)> = book
+        .accounts
+        .iter()
+        .filter_map(|account| {
+            let mut current = account.parent.as_ref();
+            while let Some(guid) = current {
+                if is_root(guid) {
+                    break;
+                }
+                if let Some(Some(parent)) = accounts.get(guid) {
+                    return Some((account.guid.clone(), parent.id().clone()));
+                }
+                current = by_guid.get(guid).and_then(|a| a.parent.as_ref());
+            }
+            None
+        })
+        .collect();
+    for (guid, parent) in parents {
+        if let Some(Some(account)) = accounts.remove(&guid) {
+            let _ = accounts.insert(guid, Some(account.with_parent(parent)));
+        }
+    }
+
+    let currency = currency
+        .or_else(|| {
+            book.transactions
+                .iter()
+                .filter_map(|t| match commodities.get(&t.currency) {
+                    Some(Some(CommodityId::Currency(currency))) => Some((t.posted, *currency)),
+                    _ => None,
+                })
+                .min_by_key(|(posted, _)| *posted)
+                .map(|(_, currency)| currency)
+        })
+        .ok_or_else(|| missing_element("book", "currency"))?;
+
+    Ok((accounts, currency))
+}
+
+fn account_kind(kind: &str) -> Option<AccountKind> {
+    match kind {
+        "ASSET" | "BANK" | "CASH" | "STOCK" | "MUTUAL" | "RECEIVABLE" | "CURRENCY" => {
+            Some(AccountKind::Asset)
+        }
+        "LIABILITY" | "CREDIT" | "PAYABLE" | "CREDITLINE" => Some(AccountKind::Liability),
+        "EQUITY" | "TRADING" => Some(AccountKind::Equity),
+        "INCOME" => Some(AccountKind::Income),
+        "EXPENSE" => Some(AccountKind::Expense),
+        _ => None,
+    }
+}
+
+fn map_transaction(
+    transaction: &GncTransaction,
+    commodities: &HashMap<CommodityRef, Option<CommodityId>>,
+    accounts: &HashMap<String, Option<Account>>,
+    report: &mut crate::ImportReport,
+) -> Option<(Transaction, Vec<Reconciled>)> {
+    let currency = match commodities.get(&transaction.currency).cloned().flatten() {
+        Some(currency) => currency,
+        None => {
+            report.add(
+                "transaction",
+                &transaction.guid,
+                format!("the currency {} is unmapped", transaction.currency),
+            );
+            return None;
+        }
+    };
+    if let Some(split) = transaction
+        .splits
+        .iter()
+        .find(|split| !matches!(accounts.get(&split.account), Some(Some(_))))
+    {
+        report.add(
+            "transaction",
+            &transaction.guid,
+            format!(
+                "the split {} is posted to an account that was not imported",
+                split.guid
+            ),
+        );
+        return None;
+    }
+
+    let id = TransactionId::new(transaction.guid.clone());
+    let mut mapped =
+        Transaction::new(id.clone(), transaction.posted).with_entered(transaction.entered);
+    if let Some(description) = transaction.description.as_ref().filter(|s| !s.is_empty()) {
+        mapped = mapped.with_description(description.clone());
+    }
+    if let Some(num) = transaction.num.as_ref().filter(|s| !s.is_empty()) {
+        mapped = mapped.with_reference(num.clone());
+    }
+
+    let mut reconciled: Vec<Reconciled> = Default::default();
+    for split in &transaction.splits {
+        // Checked above, every split's account was imported.
+        let account = accounts.get(&split.account).unwrap().as_ref().unwrap();
+        let mut mapped_split = Split::new(
+            id.clone(),
+            account.id().clone(),
+            Quantity::new(account.commodity().clone(), split.quantity),
+        );
+        if account.commodity() != &currency {
+            let rate = split.quantity.checked_div(split.value).unwrap_or_default();
+            mapped_split = mapped_split.with_exchanged_from(RatedQuantity::new(
+                Quantity::new(currency.clone(), split.value),
+                rate,
+            ));
+        }
+        if let Some(memo) = split.memo.as_ref().filter(|s| !s.is_empty()) {
+            mapped_split = mapped_split.with_description(memo.clone());
+        }
+        if let Some(action) = split.action.as_ref().filter(|s| !s.is_empty()) {
+            report.add(
+                "split",
+                &split.guid,
+                format!("the action {:?} has no equivalent", action),
+            );
+        }
+        match split.reconciled_state {
+            'y' => reconciled.push(Reconciled::new(
+                id.clone(),
+                account.id().clone(),
+                split.guid.clone(),
+                split.reconcile_date.unwrap_or(transaction.posted),
+            )),
+            'n' => {}
+            'c' => report.add("split", &split.guid, "cleared, but not reconciled"),
+            'f' => report.add("split", &split.guid, "frozen"),
+            'v' => report.add("split", &split.guid, "voided"),
+            state => report.add(
+                "split",
+                &split.guid,
+                format!("unknown reconciled state {:?}", state),
+            ),
+        }
+        mapped.add_split(mapped_split);
+    }
+    Some((mapped, reconciled))
+}
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
+
+#[doc(hidden)]
+mod sqlite;
+pub use sqlite::{read_sqlite, read_sqlite_file};
+
+#[doc(hidden)]
+mod xml;
+pub use xml::{read_xml, read_xml_file};
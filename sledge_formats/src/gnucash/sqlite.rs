@@ -0,0 +1,306 @@
+/*!
+Read the GnuCash SQLite file format.
+
+The database is opened read-only, and amounts stored as `value_num/value_denom` pairs are
+converted into exact decimals. Template accounts, those under the book's template root, and the
+transactions posted to them belong to scheduled transactions and are not imported.
+
+*/
+
+use super::{
+    parse_timestamp, price_rational, rational, CommodityRef, GncAccount, GncBook, GncCommodity,
+    GncOther, GncPrice, GncSplit, GncTransaction, GnuCashOptions,
+};
+use crate::error::{invalid_value, missing_element, Result};
+use crate::Imported;
+use chrono::{DateTime, Utc};
+use rusqlite::{Connection, OpenFlags, OptionalExtension, Row};
+use rust_decimal::Decimal;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use tracing::info;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+pub fn read_sqlite_file<P: AsRef<Path>>(path: P, options: &GnuCashOptions) -> Result<Imported> {
+    info!("Reading GnuCash SQLite file {:?}", path.as_ref());
+    let connection = Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )?;
+    read_sqlite(&connection, options)
+}
+
+pub fn read_sqlite(connection: &Connection, options: &GnuCashOptions) -> Result<Imported> {
+    read_book(connection)?.convert(options)
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// Tables of GnuCash business objects, and the kind reported for their rows.
+///
+const BUSINESS_TABLES: &[(&str, &str)] = &[
+    ("billterms", "billterm"),
+    ("customers", "customer"),
+    ("employees", "employee"),
+    ("entries", "entry"),
+    ("invoices", "invoice"),
+    ("jobs", "job"),
+    ("orders", "order"),
+    ("taxtables", "taxtable"),
+    ("vendors", "vendor"),
+];
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+fn read_book(connection: &Connection) -> Result<GncBook> {
+    let (id, template_root) = connection
+        .query_row("SELECT guid, root_template_guid FROM books", [], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .optional()?
+        .ok_or_else(|| missing_element("books", "book"))?;
+
+    let commodities = read_commodities(connection)?;
+    let commodity = |guid: &str| {
+        commodities
+            .get(guid)
+            .map(|c| c.commodity.clone())
+            .ok_or_else(|| invalid_value("commodity_guid", guid))
+    };
+
+    let mut book = GncBook {
+        id,
+        ..Default::default()
+    };
+    let flags = read_flags(connection)?;
+    let mut templates: HashSet<String> = Default::default();
+    let mut parents: HashMap<String, Option<String>> = Default::default();
+    let mut statement = connection.prepare(
+        "SELECT guid, name, account_type, commodity_guid, parent_guid, description, hidden, placeholder
+         FROM accounts ORDER BY rowid",
+    )?;
+    let mut rows = statement.query([])?;
+    while let Some(row) = rows.next()? {
+        let guid: String = row.get(0)?;
+        let commodity_guid: Option<String> = row.get(3)?;
+        let parent: Option<String> = row.get(4)?;
+        let _ = parents.insert(guid.clone(), parent.clone());
+        let flag = |column: usize, name: &str| -> Result<bool> {
+            Ok(row.get::<_, Option<i64>>(column)?.unwrap_or_default() != 0
+                || flags.contains(&(guid.clone(), name.to_string())))
+        };
+        book.accounts.push(GncAccount {
+            guid: guid.clone(),
+            name: row.get(1)?,
+            kind: row.get(2)?,
+            commodity: commodity_guid
+                .filter(|c| !c.is_empty())
+                .map(|c| commodity(&c))
+                .transpose()?,
+            description: row.get(5)?,
+            parent,
+            hidden: flag(6, "hidden")?,
+            placeholder: flag(7, "placeholder")?,
+        });
+    }
+    for account in &book.accounts {
+        let mut current = Some(&account.guid);
+        while let Some(guid) = current {
+            if guid == &template_root {
+                let _ = templates.insert(account.guid.clone());
+                break;
+            }
+            current = parents.get(guid).and_then(Option::as_ref);
+        }
+    }
+    book.accounts
+        .retain(|account| !templates.contains(&account.guid));
+
+    let mut splits = read_splits(connection)?;
+    let mut statement = connection.prepare(
+        "SELECT guid, currency_guid, num, post_date, enter_date, description
+         FROM transactions ORDER BY rowid",
+    )?;
+    let mut rows = statement.query([])?;
+    while let Some(row) = rows.next()? {
+        let guid: String = row.get(0)?;
+        let splits = splits.remove(&guid).unwrap_or_default();
+        if splits
+            .iter()
+            .any(|split| templates.contains(&split.account))
+        {
+            book.other.push(GncOther::template(guid));
+            continue;
+        }
+        let posted = required_timestamp(row, 3, "post_date")?;
+        book.transactions.push(GncTransaction {
+            guid,
+            currency: commodity(&row.get::<_, String>(1)?)?,
+            num: row.get(2)?,
+            posted,
+            entered: timestamp(row, 4, "enter_date")?.unwrap_or(posted),
+            description: row.get(5)?,
+            splits,
+        });
+    }
+
+    let mut statement = connection.prepare(
+        "SELECT guid, commodity_guid, currency_guid, date, source, value_num, value_denom
+         FROM prices ORDER BY rowid",
+    )?;
+    let mut rows = statement.query([])?;
+    while let Some(row) = rows.next()? {
+        let (num, denom): (i64, i64) = (row.get(5)?, row.get(6)?);
+        let (value, rounded) = price_rational(num, denom)
+            .ok_or_else(|| invalid_value("value", format!("{}/{}", num, denom)))?;
+        book.prices.push(GncPrice {
+            guid: row.get(0)?,
+            commodity: commodity(&row.get::<_, String>(1)?)?,
+            currency: commodity(&row.get::<_, String>(2)?)?,
+            time: required_timestamp(row, 3, "date")?,
+            source: row.get(4)?,
+            value,
+            rounded,
+        });
+    }
+
+    book.commodities = commodities.into_values().collect();
+    book.other.extend(read_other(
+        connection,
+        "schedxactions",
+        "scheduled transaction",
+        true,
+    )?);
+    book.other
+        .extend(read_other(connection, "budgets", "budget", true)?);
+    for (table, kind) in BUSINESS_TABLES {
+        book.other
+            .extend(read_other(connection, table, kind, false)?);
+    }
+    Ok(book)
+}
+
+fn read_commodities(connection: &Connection) -> Result<HashMap<String, GncCommodity>> {
+    let mut statement =
+        connection.prepare("SELECT guid, namespace, mnemonic, cusip FROM commodities")?;
+    let mut rows = statement.query([])?;
+    let mut commodities: HashMap<String, GncCommodity> = Default::default();
+    while let Some(row) = rows.next()? {
+        let _ = commodities.insert(
+            row.get(0)?,
+            GncCommodity {
+                commodity: CommodityRef::new(row.get::<_, String>(1)?, row.get::<_, String>(2)?),
+                xcode: row
+                    .get::<_, Option<String>>(3)?
+                    .filter(|cusip| !cusip.is_empty()),
+            },
+        );
+    }
+    Ok(commodities)
+}
+
+///
+/// Boolean flags set as slots, as older versions of GnuCash did for accounts.
+///
+fn read_flags(connection: &Connection) -> Result<HashSet<(String, String)>> {
+    let mut statement = connection.prepare(
+        "SELECT obj_guid, name FROM slots
+         WHERE name IN ('hidden', 'placeholder') AND string_val = 'true'",
+    )?;
+    let flags = statement
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<std::result::Result<_, _>>()?;
+    Ok(flags)
+}
+
+fn read_splits(connection: &Connection) -> Result<HashMap<String, Vec<GncSplit>>> {
+    let mut statement = connection.prepare(
+        "SELECT guid, tx_guid, account_guid, memo, action, reconcile_state, reconcile_date,
+                value_num, value_denom, quantity_num, quantity_denom
+         FROM splits ORDER BY rowid",
+    )?;
+    let mut rows = statement.query([])?;
+    let mut splits: HashMap<String, Vec<GncSplit>> = Default::default();
+    while let Some(row) = rows.next()? {
+        let split = GncSplit {
+            guid: row.get(0)?,
+            account: row.get(2)?,
+            memo: row.get(3)?,
+            action: row.get(4)?,
+            reconciled_state: row.get::<_, String>(5)?.chars().next().unwrap_or('n'),
+            reconcile_date: timestamp(row, 6, "reconcile_date")?,
+            value: numeric(row, 7, "value")?,
+            quantity: numeric(row, 9, "quantity")?,
+        };
+        splits.entry(row.get(1)?).or_default().push(split);
+    }
+    Ok(splits)
+}
+
+fn read_other(
+    connection: &Connection,
+    table: &str,
+    kind: &str,
+    has_name: bool,
+) -> Result<Vec<GncOther>> {
+    // Older books do not have all the business tables.
+    let exists: bool = connection.query_row(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = ?1",
+        [table],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        return Ok(Default::default());
+    }
+    let query = if has_name {
+        format!("SELECT guid, name FROM {} ORDER BY rowid", table)
+    } else {
+        format!("SELECT guid, NULL FROM {} ORDER BY rowid", table)
+    };
+    let mut statement = connection.prepare(&query)?;
+    let other = statement
+        .query_map([], |row| {
+            Ok(GncOther::new(
+                kind,
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<String>>(1)?,
+            ))
+        })?
+        .collect::<std::result::Result<_, _>>()?;
+    Ok(other)
+}
+
+fn numeric(row: &Row<'_>, column: usize, name: &str) -> Result<Decimal> {
+    let num: i64 = row.get(column)?;
+    let denom: i64 = row.get(column + 1)?;
+    rational(num, denom).ok_or_else(|| invalid_value(name, format!("{}/{}", num, denom)))
+}
+
+fn timestamp(row: &Row<'_>, column: usize, name: &str) -> Result<Option<DateTime<Utc>>> {
+    match row.get::<_, Option<String>>(column)? {
+        Some(value) if !value.is_empty() => parse_timestamp(&value)
+            .map(Some)
+            .map_err(|_| invalid_value(name, value)),
+        _ => Ok(None),
+    }
+}
+
+fn required_timestamp(row: &Row<'_>, column: usize, name: &str) -> Result<DateTime<Utc>> {
+    timestamp(row, column, name)?.ok_or_else(|| missing_element("row", name))
+}
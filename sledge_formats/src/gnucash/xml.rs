@@ -0,0 +1,246 @@
+/*!
+Read the GnuCash XML file format, compressed or not.
+
+*/
+
+use super::{
+    parse_rational, parse_timestamp, price_rational, split_rational, CommodityRef, GncAccount,
+    GncBook, GncCommodity, GncOther, GncPrice, GncSplit, GncTransaction, GnuCashOptions,
+};
+use crate::error::{invalid_value, missing_element, Result};
+use crate::Imported;
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use roxmltree::{Document, Node};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use tracing::info;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+pub fn read_xml_file<P: AsRef<Path>>(path: P, options: &GnuCashOptions) -> Result<Imported> {
+    info!("Reading GnuCash XML file {:?}", path.as_ref());
+    read_xml(File::open(path)?, options)
+}
+
+///
+/// Read a GnuCash XML book; GnuCash compresses these with gzip by default, so compressed
+/// content is detected and decompressed.
+///
+pub fn read_xml<R: Read>(mut reader: R, options: &GnuCashOptions) -> Result<Imported> {
+    let mut bytes: Vec<u8> = Default::default();
+    let _ = reader.read_to_end(&mut bytes)?;
+    if bytes.starts_with(&GZIP_MAGIC) {
+        let mut decompressed: Vec<u8> = Default::default();
+        let _ = GzDecoder::new(bytes.as_slice()).read_to_end(&mut decompressed)?;
+        bytes = decompressed;
+    }
+    let text = String::from_utf8(bytes).map_err(|e| invalid_value("encoding", e.to_string()))?;
+    let document = Document::parse(&text)?;
+    parse_book(document.root_element())?.convert(options)
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+fn parse_book(root: Node<'_, '_>) -> Result<GncBook> {
+    if root.tag_name().name() != "gnc-v2" {
+        return Err(invalid_value("root element", root.tag_name().name()));
+    }
+    // Older files, and account hierarchy templates, hold the book content directly.
+    let container = child(root, "book").unwrap_or(root);
+
+    let mut book = GncBook::default();
+    for node in container.children().filter(Node::is_element) {
+        match node.tag_name().name() {
+            "id" => book.id = node_text(node),
+            "commodity" => book.commodities.push(parse_commodity(node)?),
+            "pricedb" => {
+                for price in node.children().filter(|n| n.has_tag_name("price")) {
+                    book.prices.push(parse_price(price)?);
+                }
+            }
+            "account" => book.accounts.push(parse_account(node)?),
+            "transaction" => book.transactions.push(parse_transaction(node)?),
+            "template-transactions" => {
+                for transaction in node.children().filter(|n| n.has_tag_name("transaction")) {
+                    book.other.push(GncOther::template(required_text(
+                        transaction,
+                        "transaction",
+                        "id",
+                    )?));
+                }
+            }
+            "schedxaction" => book.other.push(other(node, "scheduled transaction")?),
+            "budget" => book.other.push(other(node, "budget")?),
+            "count-data" | "slots" => {}
+            // Business objects, such as "GncCustomer" and "GncInvoice".
+            name => book
+                .other
+                .push(other(node, &name.trim_start_matches("Gnc").to_lowercase())?),
+        }
+    }
+    Ok(book)
+}
+
+fn parse_commodity(node: Node<'_, '_>) -> Result<GncCommodity> {
+    Ok(GncCommodity {
+        commodity: commodity_ref(node, "commodity")?,
+        xcode: text(node, "xcode"),
+    })
+}
+
+fn parse_price(node: Node<'_, '_>) -> Result<GncPrice> {
+    let value = required_text(node, "price", "value")?;
+    let (num, denom) = split_rational(&value)?;
+    let (value, rounded) =
+        price_rational(num, denom).ok_or_else(|| invalid_value("numeric", value))?;
+    Ok(GncPrice {
+        guid: required_text(node, "price", "id")?,
+        commodity: commodity_ref(required(node, "price", "commodity")?, "commodity")?,
+        currency: commodity_ref(required(node, "price", "currency")?, "currency")?,
+        time: required_timestamp(node, "price", "time")?,
+        source: text(node, "source"),
+        value,
+        rounded,
+    })
+}
+
+fn parse_account(node: Node<'_, '_>) -> Result<GncAccount> {
+    let slots = child(node, "slots");
+    let flag = |key: &str| {
+        slots
+            .and_then(|slots| slot_value(slots, key))
+            .map(|value| value == "true")
+            .unwrap_or_default()
+    };
+    Ok(GncAccount {
+        guid: required_text(node, "account", "id")?,
+        name: required_text(node, "account", "name")?,
+        kind: required_text(node, "account", "type")?,
+        commodity: child(node, "commodity")
+            .map(|c| commodity_ref(c, "commodity"))
+            .transpose()?,
+        description: text(node, "description"),
+        parent: text(node, "parent"),
+        placeholder: flag("placeholder"),
+        hidden: flag("hidden"),
+    })
+}
+
+fn parse_transaction(node: Node<'_, '_>) -> Result<GncTransaction> {
+    let mut splits: Vec<GncSplit> = Default::default();
+    if let Some(container) = child(node, "splits") {
+        for split in container.children().filter(|n| n.has_tag_name("split")) {
+            splits.push(parse_split(split)?);
+        }
+    }
+    let posted = required_timestamp(node, "transaction", "date-posted")?;
+    Ok(GncTransaction {
+        guid: required_text(node, "transaction", "id")?,
+        currency: commodity_ref(required(node, "transaction", "currency")?, "currency")?,
+        num: text(node, "num"),
+        posted,
+        entered: timestamp(node, "date-entered")?.unwrap_or(posted),
+        description: text(node, "description"),
+        splits,
+    })
+}
+
+fn parse_split(node: Node<'_, '_>) -> Result<GncSplit> {
+    let value = parse_rational(&required_text(node, "split", "value")?)?;
+    Ok(GncSplit {
+        guid: required_text(node, "split", "id")?,
+        memo: text(node, "memo"),
+        action: text(node, "action"),
+        reconciled_state: text(node, "reconciled-state")
+            .and_then(|state| state.chars().next())
+            .unwrap_or('n'),
+        reconcile_date: timestamp(node, "reconcile-date")?,
+        value,
+        quantity: text(node, "quantity")
+            .map(|quantity| parse_rational(&quantity))
+            .transpose()?
+            .unwrap_or(value),
+        account: required_text(node, "split", "account")?,
+    })
+}
+
+fn other(node: Node<'_, '_>, kind: &str) -> Result<GncOther> {
+    // The identifier of every GnuCash object is its first guid-typed child.
+    let id = node
+        .children()
+        .find(|n| n.attribute("type") == Some("guid"))
+        .map(node_text)
+        .ok_or_else(|| missing_element(node.tag_name().name(), "id"))?;
+    Ok(GncOther::new(kind, id, text(node, "name")))
+}
+
+fn commodity_ref(node: Node<'_, '_>, parent: &str) -> Result<CommodityRef> {
+    Ok(CommodityRef::new(
+        required_text(node, parent, "space")?,
+        required_text(node, parent, "id")?,
+    ))
+}
+
+fn slot_value(slots: Node<'_, '_>, key: &str) -> Option<String> {
+    slots
+        .children()
+        .filter(|n| n.has_tag_name("slot"))
+        .find(|slot| text(*slot, "key").as_deref() == Some(key))
+        .and_then(|slot| text(slot, "value"))
+}
+
+fn timestamp(node: Node<'_, '_>, name: &str) -> Result<Option<DateTime<Utc>>> {
+    child(node, name)
+        .and_then(|container| child(container, "date").or_else(|| child(container, "gdate")))
+        .map(|date| parse_timestamp(&node_text(date)))
+        .transpose()
+}
+
+fn required_timestamp(node: Node<'_, '_>, parent: &str, name: &str) -> Result<DateTime<Utc>> {
+    timestamp(node, name)?.ok_or_else(|| missing_element(parent, name))
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children()
+        .find(|n| n.is_element() && n.tag_name().name() == name)
+}
+
+fn required<'a, 'input>(
+    node: Node<'a, 'input>,
+    parent: &str,
+    name: &str,
+) -> Result<Node<'a, 'input>> {
+    child(node, name).ok_or_else(|| missing_element(parent, name))
+}
+
+fn node_text(node: Node<'_, '_>) -> String {
+    node.text().map(str::trim).unwrap_or_default().to_string()
+}
+
+fn text(node: Node<'_, '_>, name: &str) -> Option<String> {
+    child(node, name).map(node_text)
+}
+
+fn required_text(node: Node<'_, '_>, parent: &str, name: &str) -> Result<String> {
+    text(node, name).ok_or_else(|| missing_element(parent, name))
+}
@@ -0,0 +1,212 @@
+/*!
+Import and export the plain-text journal format of ledger-cli and hledger.
+
+Accounts are identified by their full name, such as `"Expenses:Food:Groceries"`, and are
+created for every `account` directive, every account a posting names, and every parent of
+those. An account's kind comes from an hledger `type:` tag on its directive, or that of an
+ancestor, and otherwise from its top-level name, so that `Assets`, `Liabilities`, `Equity`,
+`Income` or `Revenue`, and `Expenses` need no declaration.
+
+Transactions become a [`Transaction`](sledge_model::journal::Transaction) with a split per
+posting; a posting's comment becomes the split's description, and the transaction code becomes
+its reference. Costs given with `@` or `@@` become a
+[`RatedQuantity`](sledge_model::commodity::RatedQuantity), `P` directives become price records,
+and cleared postings, marked `*`, are recorded as reconciled. Commodity symbols are mapped to
+currencies for ISO 4217 codes and the common currency signs, to securities for valid ISINs,
+and to anything else with [`LedgerCliOptions::with_commodity`].
+
+Transactions are exported with their cost as `@@` totals, so that hledger and ledger-cli
+balance them exactly as they were imported, and with an `id:` tag to keep their identifier.
+
+*/
+
+use crate::error::{invalid_value, Result};
+use codes_iso_4217::CurrencyCode;
+use rust_decimal::Decimal;
+use sledge_model::commodity::{CommodityId, InternationalSecuritiesId};
+use sledge_model::ledger::LedgerKind;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Clone, Debug)]
+pub struct LedgerCliOptions {
+    ledger_kind: LedgerKind,
+    journal_name: String,
+    currency: CurrencyCode,
+    commodities: HashMap<String, CommodityId>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl Default for LedgerCliOptions {
+    fn default() -> Self {
+        Self {
+            ledger_kind: LedgerKind::General,
+            journal_name: "general".to_string(),
+            currency: CurrencyCode::USD,
+            commodities: Default::default(),
+        }
+    }
+}
+
+impl LedgerCliOptions {
+    pub fn with_ledger_kind(self, ledger_kind: LedgerKind) -> Self {
+        Self {
+            ledger_kind,
+            ..self
+        }
+    }
+
+    pub fn with_journal_name<S: Into<String>>(self, journal_name: S) -> Self {
+        Self {
+            journal_name: journal_name.into(),
+            ..self
+        }
+    }
+
+    ///
+    /// The currency of the imported ledger and journal, also used for amounts written
+    /// without a commodity symbol.
+    ///
+    pub fn with_currency(self, currency: CurrencyCode) -> Self {
+        Self { currency, ..self }
+    }
+
+    ///
+    /// Import amounts in the commodity `symbol`, such as `"AAPL"`, as `commodity`.
+    ///
+    pub fn with_commodity<S: Into<String>>(mut self, symbol: S, commodity: CommodityId) -> Self {
+        let _ = self.commodities.insert(symbol.into(), commodity);
+        self
+    }
+
+    pub fn ledger_kind(&self) -> &LedgerKind {
+        &self.ledger_kind
+    }
+
+    pub fn journal_name(&self) -> &String {
+        &self.journal_name
+    }
+
+    pub fn currency(&self) -> CurrencyCode {
+        self.currency
+    }
+
+    ///
+    /// The commodity for `symbol`, from those added to these options, then the currency
+    /// codes and signs, and finally valid ISINs.
+    ///
+    pub fn commodity(&self, symbol: &str) -> Option<CommodityId> {
+        if symbol.is_empty() {
+            return Some(CommodityId::Currency(self.currency));
+        }
+        self.commodities.get(symbol).cloned().or_else(|| {
+            match symbol {
+                "$" => Some(CurrencyCode::USD),
+                "€" => Some(CurrencyCode::EUR),
+                "£" => Some(CurrencyCode::GBP),
+                "¥" => Some(CurrencyCode::JPY),
+                _ => CurrencyCode::from_str(symbol).ok(),
+            }
+            .map(CommodityId::Currency)
+            .or_else(|| {
+                let isin = InternationalSecuritiesId::from(symbol);
+                isin.is_valid().then_some(CommodityId::Security(isin))
+            })
+        })
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+///
+/// Parse an amount such as `$-1,000.50`, `-10 AAPL`, or `3 "ABC 1"`, returning the commodity
+/// symbol, which is empty if there is none, and the number.
+///
+pub(crate) fn parse_amount(value: &str) -> Result<(String, Decimal)> {
+    let invalid = || invalid_value("amount", value);
+    let mut rest = value.trim();
+    let mut negative = false;
+    if let Some(stripped) = rest.strip_prefix('-') {
+        negative = true;
+        rest = stripped.trim_start();
+    }
+    let is_numeric = |c: char| c.is_ascii_digit() || matches!(c, '.' | ',' | '-' | '+');
+    let (symbol, number) = if rest.starts_with(|c: char| is_numeric(c)) {
+        let end = rest.find(|c: char| !is_numeric(c)).unwrap_or(rest.len());
+        (
+            parse_symbol(rest[end..].trim()).ok_or_else(invalid)?.0,
+            &rest[..end],
+        )
+    } else {
+        let (symbol, remainder) = parse_symbol(rest).ok_or_else(invalid)?;
+        (symbol, remainder.trim())
+    };
+    let mut number = Decimal::from_str(&number.replace(',', "")).map_err(|_| invalid())?;
+    if negative {
+        number = -number;
+    }
+    Ok((symbol, number))
+}
+
+///
+/// Split a leading commodity symbol, quoted or not, from `value`.
+///
+pub(crate) fn parse_symbol(value: &str) -> Option<(String, &str)> {
+    if let Some(quoted) = value.strip_prefix('"') {
+        let end = quoted.find('"')?;
+        Some((quoted[..end].to_string(), &quoted[end + 1..]))
+    } else {
+        let end = value
+            .find(|c: char| c.is_ascii_digit() || c.is_whitespace() || matches!(c, '.' | '-' | '+'))
+            .unwrap_or(value.len());
+        Some((value[..end].to_string(), &value[end..]))
+    }
+}
+
+///
+/// The symbol written for a commodity; symbols containing anything other than letters are
+/// quoted.
+///
+pub(crate) fn format_symbol(commodity: &CommodityId) -> String {
+    match commodity {
+        CommodityId::Currency(currency) => currency.alpha_code().to_string(),
+        CommodityId::Security(isin) => format!("\"{}\"", isin),
+    }
+}
+
+pub(crate) fn format_amount(commodity: &CommodityId, amount: Decimal) -> String {
+    format!("{} {}", amount, format_symbol(commodity))
+}
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
+
+#[doc(hidden)]
+mod read;
+pub use read::{read_journal, read_journal_file};
+
+#[doc(hidden)]
+mod write;
+pub use write::{write_journal, write_journal_file};
@@ -0,0 +1,680 @@
+/*!
+Read ledger-cli and hledger journal files.
+
+*/
+
+use super::{parse_amount, parse_symbol, LedgerCliOptions};
+use crate::error::{syntax_error, Result};
+use crate::{ImportReport, Imported};
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use sledge_model::commodity::{CommodityId, Quantity, RateRecord, RatedQuantity};
+use sledge_model::journal::{Journal, Reconciled, Split, Transaction, TransactionId};
+use sledge_model::ledger::{Account, AccountId, AccountKind, Ledger};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use tracing::info;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+pub fn read_journal_file<P: AsRef<Path>>(path: P, options: &LedgerCliOptions) -> Result<Imported> {
+    info!("Reading ledger journal file {:?}", path.as_ref());
+    read_journal(BufReader::new(File::open(path)?), options)
+}
+
+pub fn read_journal<R: BufRead>(reader: R, options: &LedgerCliOptions) -> Result<Imported> {
+    let mut parser = Parser::new(options);
+    let mut block = Block::None;
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let number = index + 1;
+        if let Block::Comment = block {
+            if line.trim() == "end comment" {
+                block = Block::None;
+            }
+            continue;
+        }
+        if line.trim().is_empty() {
+            parser.finish(std::mem::replace(&mut block, Block::None));
+        } else if line.starts_with(char::is_whitespace) {
+            parser.continuation(&mut block, number, line.trim())?;
+        } else {
+            parser.finish(std::mem::replace(&mut block, Block::None));
+            block = parser.directive(number, &line)?;
+        }
+    }
+    parser.finish(block);
+    Ok(parser.into_imported())
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Debug)]
+struct Parser<'a> {
+    options: &'a LedgerCliOptions,
+    report: ImportReport,
+    declared: HashMap<String, DeclaredAccount>,
+    transactions: Vec<ParsedTransaction>,
+    prices: Vec<RateRecord>,
+}
+
+#[derive(Debug, Default)]
+struct DeclaredAccount {
+    kind: Option<AccountKind>,
+    description: Option<String>,
+}
+
+#[derive(Debug)]
+enum Block {
+    None,
+    Account(String),
+    Transaction(ParsedTransaction),
+    Comment,
+    Skip,
+}
+
+#[derive(Debug)]
+struct ParsedTransaction {
+    line: usize,
+    date: NaiveDate,
+    cleared: bool,
+    code: Option<String>,
+    description: String,
+    id: Option<String>,
+    postings: Vec<ParsedPosting>,
+    skip: Option<String>,
+}
+
+#[derive(Debug)]
+struct ParsedPosting {
+    account: String,
+    cleared: bool,
+    amount: Option<(CommodityId, Decimal)>,
+    cost: Option<(CommodityId, Decimal)>,
+    comment: Option<String>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl<'a> Parser<'a> {
+    fn new(options: &'a LedgerCliOptions) -> Self {
+        Self {
+            options,
+            report: Default::default(),
+            declared: Default::default(),
+            transactions: Default::default(),
+            prices: Default::default(),
+        }
+    }
+
+    fn directive(&mut self, number: usize, line: &str) -> Result<Block> {
+        if line.starts_with([';', '#', '*', '%', '|']) {
+            return Ok(Block::None);
+        }
+        if line.starts_with(|c: char| c.is_ascii_digit()) {
+            return self.transaction(number, line).map(Block::Transaction);
+        }
+        let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        match keyword {
+            "account" => {
+                let (name, comment) = split_comment(rest);
+                let name = name.trim().to_string();
+                let declared = self.declared.entry(name.clone()).or_default();
+                if let Some(kind) = comment.and_then(|c| tag(c, "type")) {
+                    declared.kind =
+                        Some(account_type(&kind).ok_or_else(|| syntax_error(number, line))?);
+                }
+                Ok(Block::Account(name))
+            }
+            "P" => {
+                self.price(number, rest)?;
+                Ok(Block::None)
+            }
+            "comment" => Ok(Block::Comment),
+            // Commodity directives only declare display formats.
+            "commodity" => Ok(Block::Skip),
+            _ => {
+                self.report.add(
+                    "directive",
+                    format!("line {}", number),
+                    format!("the {:?} directive is not supported", keyword),
+                );
+                Ok(Block::Skip)
+            }
+        }
+    }
+
+    fn continuation(&mut self, block: &mut Block, number: usize, line: &str) -> Result<()> {
+        match block {
+            Block::Transaction(transaction) => {
+                if let Some(comment) = line.strip_prefix(';') {
+                    if let Some(id) = tag(comment, "id") {
+                        transaction.id = Some(id);
+                    }
+                } else if let Some(posting) = self.posting(transaction, number, line)? {
+                    transaction.postings.push(posting);
+                }
+            }
+            Block::Account(name) => {
+                if let Some(comment) = line.strip_prefix(';') {
+                    let comment = comment.trim();
+                    let declared = self.declared.entry(name.clone()).or_default();
+                    match tag(comment, "type") {
+                        Some(kind) => {
+                            declared.kind = Some(
+                                account_type(&kind).ok_or_else(|| syntax_error(number, line))?,
+                            )
+                        }
+                        None => declared.description = Some(comment.to_string()),
+                    }
+                }
+            }
+            Block::Skip | Block::Comment => {}
+            Block::None => {
+                if !line.starts_with([';', '#']) {
+                    return Err(syntax_error(number, line));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn transaction(&mut self, number: usize, line: &str) -> Result<ParsedTransaction> {
+        let (head, comment) = split_comment(line);
+        let (date, rest) = head.split_once(char::is_whitespace).unwrap_or((head, ""));
+        // Any secondary date, after '=', is ignored.
+        let date = parse_date(date.split('=').next().unwrap_or_default())
+            .ok_or_else(|| syntax_error(number, line))?;
+        let mut rest = rest.trim();
+        let mut cleared = false;
+        if let Some(stripped) = rest.strip_prefix('*') {
+            cleared = true;
+            rest = stripped.trim_start();
+        } else if let Some(stripped) = rest.strip_prefix('!') {
+            self.report.add(
+                "transaction",
+                format!("line {}", number),
+                "pending, but not cleared",
+            );
+            rest = stripped.trim_start();
+        }
+        let mut code = None;
+        if let Some(stripped) = rest.strip_prefix('(') {
+            let (inner, remainder) = stripped
+                .split_once(')')
+                .ok_or_else(|| syntax_error(number, line))?;
+            code = Some(inner.trim().to_string()).filter(|c| !c.is_empty());
+            rest = remainder.trim_start();
+        }
+        Ok(ParsedTransaction {
+            line: number,
+            date,
+            cleared,
+            code,
+            description: rest.to_string(),
+            id: comment.and_then(|c| tag(c, "id")),
+            postings: Default::default(),
+            skip: None,
+        })
+    }
+
+    fn posting(
+        &mut self,
+        transaction: &mut ParsedTransaction,
+        number: usize,
+        line: &str,
+    ) -> Result<Option<ParsedPosting>> {
+        let (body, comment) = split_comment(line);
+        let mut body = body.trim();
+        let mut cleared = false;
+        if let Some(stripped) = body.strip_prefix('*') {
+            cleared = true;
+            body = stripped.trim_start();
+        } else if let Some(stripped) = body.strip_prefix('!') {
+            self.report.add(
+                "posting",
+                format!("line {}", number),
+                "pending, but not cleared",
+            );
+            body = stripped.trim_start();
+        }
+        let end = [body.find("  "), body.find('\t')]
+            .into_iter()
+            .flatten()
+            .min()
+            .unwrap_or(body.len());
+        let (account, amount) = (body[..end].trim(), body[end..].trim());
+
+        if account.starts_with(['(', '[']) {
+            self.report.add(
+                "posting",
+                format!("line {}", number),
+                "virtual postings are not imported",
+            );
+            return Ok(None);
+        }
+        let (amount, assertion) = match amount.split_once('=') {
+            Some((amount, assertion)) => (amount.trim(), Some(assertion.trim())),
+            None => (amount, None),
+        };
+        if assertion.is_some() {
+            if amount.is_empty() {
+                transaction.skip = Some("balance assignments are not supported".to_string());
+            } else {
+                self.report.add(
+                    "posting",
+                    format!("line {}", number),
+                    "the balance assertion was not checked",
+                );
+            }
+        }
+        if amount.contains('{') {
+            transaction.skip = Some("lot prices are not supported".to_string());
+        }
+
+        let has_amount = !amount.is_empty() && !amount.starts_with('@');
+        let (amount, cost) = match amount.split_once('@') {
+            Some((amount, cost)) => (amount.trim(), Some(cost)),
+            None => (amount, None),
+        };
+        let amount = if amount.is_empty() {
+            None
+        } else {
+            self.quantity(transaction, number, line, amount)?
+        };
+        let cost = match (cost, &amount) {
+            (Some(cost), Some((_, quantity))) => {
+                let (total, cost) = match cost.strip_prefix('@') {
+                    Some(cost) => (true, cost),
+                    None => (false, cost),
+                };
+                self.quantity(transaction, number, line, cost)?
+                    .map(|(commodity, price)| {
+                        let cost = if total {
+                            price.abs()
+                        } else {
+                            (price * *quantity).abs()
+                        };
+                        (
+                            commodity,
+                            if quantity.is_sign_negative() {
+                                -cost
+                            } else {
+                                cost
+                            },
+                        )
+                    })
+            }
+            (Some(_), None) if !has_amount => return Err(syntax_error(number, line)),
+            _ => None,
+        };
+        Ok(Some(ParsedPosting {
+            account: account.to_string(),
+            cleared,
+            amount,
+            cost,
+            comment: comment
+                .map(|c| c.trim().to_string())
+                .filter(|c| !c.is_empty()),
+        }))
+    }
+
+    fn quantity(
+        &mut self,
+        transaction: &mut ParsedTransaction,
+        number: usize,
+        line: &str,
+        amount: &str,
+    ) -> Result<Option<(CommodityId, Decimal)>> {
+        let (symbol, amount) = parse_amount(amount).map_err(|_| syntax_error(number, line))?;
+        match self.options.commodity(&symbol) {
+            Some(commodity) => Ok(Some((commodity, amount))),
+            None => {
+                self.unmapped_symbol(&symbol);
+                transaction.skip = Some(format!("the commodity {:?} is unmapped", symbol));
+                Ok(None)
+            }
+        }
+    }
+
+    fn price(&mut self, number: usize, rest: &str) -> Result<()> {
+        let rest = rest.trim();
+        let (date, rest) = rest
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| syntax_error(number, rest))?;
+        let date = parse_date(date).ok_or_else(|| syntax_error(number, rest))?;
+        let mut rest = rest.trim_start();
+        // Skip any time of day.
+        if rest.starts_with(|c: char| c.is_ascii_digit()) {
+            rest = rest
+                .split_once(char::is_whitespace)
+                .map(|(_, rest)| rest.trim_start())
+                .unwrap_or_default();
+        }
+        let (symbol, amount) = parse_symbol(rest).ok_or_else(|| syntax_error(number, rest))?;
+        let (into_symbol, rate) = parse_amount(amount).map_err(|_| syntax_error(number, amount))?;
+        match (
+            self.options.commodity(&symbol),
+            self.options.commodity(&into_symbol),
+        ) {
+            (Some(from), Some(into)) => {
+                self.prices
+                    .push(RateRecord::new(from, into, rate, midnight(date), "journal"))
+            }
+            _ => self.report.add(
+                "price",
+                format!("line {}", number),
+                format!(
+                    "the price of {:?} in {:?} uses an unmapped commodity",
+                    symbol, into_symbol
+                ),
+            ),
+        }
+        Ok(())
+    }
+
+    fn unmapped_symbol(&mut self, symbol: &str) {
+        if self
+            .report
+            .unmapped_of_kind("commodity")
+            .all(|item| item.id() != symbol)
+        {
+            self.report.add(
+                "commodity",
+                symbol,
+                "not a currency or ISIN, and not mapped in the options",
+            );
+        }
+    }
+
+    fn finish(&mut self, block: Block) {
+        if let Block::Transaction(transaction) = block {
+            self.transactions.push(transaction);
+        }
+    }
+
+    fn into_imported(mut self) -> Imported {
+        // Sorting is stable, keeping file order within a day, which export preserves.
+        self.transactions.sort_by_key(|t| t.date);
+        let accounts = self.accounts();
+
+        let currency = self.options.currency();
+        let mut ledger = Ledger::new(
+            self.options.ledger_kind().clone(),
+            "Imported ledger journal",
+            currency,
+        );
+        for account in accounts.values().flatten() {
+            ledger.add_account(account.clone());
+        }
+
+        let mut imported = Imported::default();
+        let mut journal = Journal::new(self.options.journal_name().clone(), currency);
+        let mut sequence: HashMap<NaiveDate, usize> = Default::default();
+        for transaction in std::mem::take(&mut self.transactions) {
+            let index = sequence.entry(transaction.date).or_default();
+            *index += 1;
+            let id = transaction
+                .id
+                .clone()
+                .unwrap_or_else(|| format!("{}-{:03}", transaction.date.format("%Y%m%d"), index));
+            if let Some((mapped, reconciled)) = self.map_transaction(transaction, id, &accounts) {
+                if let Err(e) = journal.add_transaction(mapped) {
+                    self.report.add("transaction", "", e.to_string());
+                } else {
+                    imported.contents.reconciliations.extend(reconciled);
+                }
+            }
+        }
+
+        imported.contents.ledgers.push(ledger);
+        imported.contents.journals.push(journal);
+        imported.contents.prices = self.prices;
+        imported.report = self.report;
+        imported
+    }
+
+    ///
+    /// Every declared or posted account, and each of their parents, in name order.
+    ///
+    fn accounts(&mut self) -> BTreeMap<String, Option<Account>> {
+        let mut names: BTreeSet<String> = self.declared.keys().cloned().collect();
+        let mut commodities: HashMap<&String, &CommodityId> = Default::default();
+        for posting in self.transactions.iter().flat_map(|t| t.postings.iter()) {
+            let _ = names.insert(posting.account.clone());
+            if let Some((commodity, _)) = &posting.amount {
+                let _ = commodities.entry(&posting.account).or_insert(commodity);
+            }
+        }
+        for name in names.clone() {
+            let mut parent = name.as_str();
+            while let Some((prefix, _)) = parent.rsplit_once(':') {
+                let _ = names.insert(prefix.to_string());
+                parent = prefix;
+            }
+        }
+
+        let mut accounts: BTreeMap<String, Option<Account>> = Default::default();
+        for name in names {
+            let kind = self.kind_of(&name);
+            let parent = name.rsplit_once(':').map(|(parent, _)| parent);
+            let mapped = kind.map(|kind| {
+                let account = Account::new(
+                    AccountId::new(name.clone()),
+                    kind,
+                    self.declared
+                        .get(&name)
+                        .and_then(|d| d.description.clone())
+                        .unwrap_or_else(|| name.rsplit(':').next().unwrap_or_default().to_string()),
+                    commodities
+                        .get(&name)
+                        .map(|c| (*c).clone())
+                        .unwrap_or(CommodityId::Currency(self.options.currency())),
+                );
+                match parent {
+                    Some(parent) => account.with_parent(AccountId::new(parent)),
+                    None => account,
+                }
+            });
+            if mapped.is_none() {
+                self.report.add(
+                    "account",
+                    &name,
+                    "the account type is not declared and not implied by its name",
+                );
+            }
+            let _ = accounts.insert(name, mapped);
+        }
+        accounts
+    }
+
+    fn kind_of(&self, name: &str) -> Option<AccountKind> {
+        let mut current = Some(name);
+        while let Some(name) = current {
+            if let Some(kind) = self.declared.get(name).and_then(|d| d.kind) {
+                return Some(kind);
+            }
+            current = name.rsplit_once(':').map(|(parent, _)| parent);
+        }
+        let top = name.split(':').next().unwrap_or_default().to_lowercase();
+        match top.as_str() {
+            "asset" | "assets" => Some(AccountKind::Asset),
+            "liability" | "liabilities" | "debts" => Some(AccountKind::Liability),
+            "equity" => Some(AccountKind::Equity),
+            "income" | "revenue" | "revenues" => Some(AccountKind::Income),
+            "expense" | "expenses" => Some(AccountKind::Expense),
+            _ => None,
+        }
+    }
+
+    fn map_transaction(
+        &mut self,
+        mut transaction: ParsedTransaction,
+        id: String,
+        accounts: &BTreeMap<String, Option<Account>>,
+    ) -> Option<(Transaction, Vec<Reconciled>)> {
+        let line = format!("line {}", transaction.line);
+        if let Some(reason) = transaction.skip.take() {
+            self.report.add("transaction", line, reason);
+            return None;
+        }
+        if let Some(posting) = transaction
+            .postings
+            .iter()
+            .find(|p| !matches!(accounts.get(&p.account), Some(Some(_))))
+        {
+            self.report.add(
+                "transaction",
+                line,
+                format!("the account {:?} was not imported", posting.account),
+            );
+            return None;
+        }
+        if let Err(reason) = balance(&mut transaction.postings, self.options) {
+            self.report.add("transaction", line, reason);
+            return None;
+        }
+
+        let id = TransactionId::new(id);
+        let posted = midnight(transaction.date);
+        // Journals have no entry date, and the current time would make imports differ.
+        let mut mapped = Transaction::new(id.clone(), posted).with_entered(posted);
+        if !transaction.description.is_empty() {
+            mapped = mapped.with_description(transaction.description);
+        }
+        if let Some(code) = &transaction.code {
+            mapped = mapped.with_reference(code.clone());
+        }
+        let mut reconciled: Vec<Reconciled> = Default::default();
+        for posting in transaction.postings {
+            // Balancing has filled in any missing amount.
+            let (commodity, amount) = posting.amount.unwrap();
+            let account = AccountId::new(posting.account);
+            let mut split = Split::new(
+                id.clone(),
+                account.clone(),
+                Quantity::new(commodity, amount),
+            );
+            if let Some((cost_commodity, cost)) = posting.cost {
+                split = split.with_exchanged_from(RatedQuantity::new(
+                    Quantity::new(cost_commodity, cost),
+                    amount.checked_div(cost).unwrap_or_default(),
+                ));
+            }
+            if let Some(comment) = posting.comment {
+                split = split.with_description(comment);
+            }
+            if transaction.cleared || posting.cleared {
+                reconciled.push(Reconciled::new(
+                    id.clone(),
+                    account,
+                    transaction.code.clone().unwrap_or_else(|| id.to_string()),
+                    posted,
+                ));
+            }
+            mapped.add_split(split);
+        }
+        Some((mapped, reconciled))
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+///
+/// Fill in a posting without an amount, if there is one, and check the postings balance in
+/// each commodity, using the cost of postings that have one.
+///
+fn balance(
+    postings: &mut [ParsedPosting],
+    options: &LedgerCliOptions,
+) -> std::result::Result<(), String> {
+    let mut residuals: Vec<(CommodityId, Decimal)> = Default::default();
+    for (commodity, amount) in postings
+        .iter()
+        .filter_map(|p| p.cost.as_ref().or(p.amount.as_ref()))
+    {
+        match residuals.iter_mut().find(|(c, _)| c == commodity) {
+            Some((_, total)) => *total += *amount,
+            None => residuals.push((commodity.clone(), *amount)),
+        }
+    }
+    residuals.retain(|(_, total)| !total.is_zero());
+
+    let mut missing = postings.iter_mut().filter(|p| p.amount.is_none());
+    match (missing.next(), missing.next()) {
+        (Some(_), Some(_)) => Err("more than one posting has no amount".to_string()),
+        (Some(posting), None) => match residuals.len() {
+            0 => {
+                posting.amount = Some((CommodityId::Currency(options.currency()), Decimal::ZERO));
+                Ok(())
+            }
+            1 => {
+                let (commodity, total) = residuals.remove(0);
+                posting.amount = Some((commodity, -total));
+                Ok(())
+            }
+            _ => Err("the missing amount would be in more than one commodity".to_string()),
+        },
+        (None, _) if residuals.is_empty() => Ok(()),
+        (None, _) => Err("the postings do not balance".to_string()),
+    }
+}
+
+///
+/// Split a line at the start of its comment, if it has one.
+///
+fn split_comment(line: &str) -> (&str, Option<&str>) {
+    match line.split_once(';') {
+        Some((body, comment)) => (body, Some(comment)),
+        None => (line, None),
+    }
+}
+
+///
+/// The value of the hledger tag `name` in `comment`; tags are written `name: value` and
+/// separated by commas.
+///
+fn tag(comment: &str, name: &str) -> Option<String> {
+    comment.split(',').find_map(|part| {
+        let (key, value) = part.split_once(':')?;
+        let key = key.split_whitespace().last()?;
+        (key == name).then(|| value.trim().to_string())
+    })
+}
+
+fn account_type(value: &str) -> Option<AccountKind> {
+    match value.to_lowercase().as_str() {
+        "a" | "asset" | "c" | "cash" => Some(AccountKind::Asset),
+        "l" | "liability" => Some(AccountKind::Liability),
+        "e" | "equity" | "v" | "conversion" => Some(AccountKind::Equity),
+        "r" | "revenue" => Some(AccountKind::Income),
+        "x" | "expense" => Some(AccountKind::Expense),
+        _ => None,
+    }
+}
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(&value.replace(['/', '.'], "-"), "%Y-%m-%d").ok()
+}
+
+fn midnight(date: NaiveDate) -> DateTime<Utc> {
+    date.and_hms_opt(0, 0, 0).unwrap().and_utc()
+}
@@ -0,0 +1,169 @@
+/*!
+Write ledger-cli and hledger journal files.
+
+*/
+
+use super::{format_amount, format_symbol};
+use crate::error::Result;
+use sledge_model::commodity::RateRecord;
+use sledge_model::journal::{Split, Transaction};
+use sledge_model::ledger::{Account, AccountKind};
+use sledge_store::CreateDatastoreContents;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use tracing::info;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+pub fn write_journal_file<P: AsRef<Path>>(
+    path: P,
+    contents: &CreateDatastoreContents,
+) -> Result<()> {
+    info!("Writing ledger journal file {:?}", path.as_ref());
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_journal(&mut writer, contents)?;
+    writer.flush()?;
+    Ok(())
+}
+
+///
+/// Write the accounts of every ledger, the prices, and the transactions of every journal in
+/// posted order.
+///
+pub fn write_journal<W: Write>(writer: &mut W, contents: &CreateDatastoreContents) -> Result<()> {
+    let mut written: HashSet<&str> = Default::default();
+    for account in contents.ledgers.iter().flat_map(|ledger| ledger.book()) {
+        if written.insert(account.id().as_str()) {
+            write_account(writer, account)?;
+        }
+    }
+    if !written.is_empty() {
+        writeln!(writer)?;
+    }
+
+    let mut prices: Vec<_> = contents.prices.iter().collect();
+    prices.sort_by_key(|price| price.date());
+    for price in &prices {
+        writeln!(
+            writer,
+            "P {} {} {}",
+            price.date().format("%Y-%m-%d"),
+            format_symbol(price.from()),
+            format_amount(RateRecord::into(price), price.rate())
+        )?;
+    }
+    if !prices.is_empty() {
+        writeln!(writer)?;
+    }
+
+    let reconciled: HashSet<(&str, &str)> = contents
+        .reconciliations
+        .iter()
+        .map(|r| (r.split_id().as_str(), r.account().as_str()))
+        .collect();
+    let mut transactions: Vec<&Transaction> = contents
+        .journals
+        .iter()
+        .flat_map(|journal| journal.transactions())
+        .collect();
+    transactions.sort_by_key(|transaction| transaction.posted());
+    for transaction in transactions {
+        write_transaction(writer, transaction, &reconciled)?;
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+fn write_account<W: Write>(writer: &mut W, account: &Account) -> Result<()> {
+    let name = account.id().as_str();
+    writeln!(
+        writer,
+        "account {}  ; type: {}",
+        name,
+        match account.kind() {
+            AccountKind::Asset => "A",
+            AccountKind::Liability => "L",
+            AccountKind::Equity => "E",
+            AccountKind::Income => "R",
+            AccountKind::Expense => "X",
+        }
+    )?;
+    if Some(account.description().as_str()) != name.rsplit(':').next() {
+        writeln!(writer, "    ; {}", single_line(account.description()))?;
+    }
+    Ok(())
+}
+
+fn write_transaction<W: Write>(
+    writer: &mut W,
+    transaction: &Transaction,
+    reconciled: &HashSet<(&str, &str)>,
+) -> Result<()> {
+    let id = transaction.id().as_str();
+    let is_reconciled = |split: &Split| reconciled.contains(&(id, split.account().as_str()));
+    let all_reconciled = transaction.splits().all(is_reconciled);
+
+    write!(writer, "{}", transaction.posted().format("%Y-%m-%d"))?;
+    if all_reconciled {
+        write!(writer, " *")?;
+    }
+    if let Some(reference) = transaction.reference() {
+        write!(writer, " ({})", single_line(reference).replace(')', ""))?;
+    }
+    if let Some(description) = transaction.description() {
+        write!(writer, " {}", single_line(description))?;
+    }
+    writeln!(writer, "  ; id: {}", id)?;
+
+    for split in transaction.splits() {
+        write!(
+            writer,
+            "    {}{}  {}",
+            if !all_reconciled && is_reconciled(split) {
+                "* "
+            } else {
+                ""
+            },
+            split.account(),
+            format_amount(split.quantity().commodity(), split.quantity().quantity())
+        )?;
+        if let Some(exchanged) = split.exchanged_from() {
+            write!(
+                writer,
+                " @@ {}",
+                format_amount(
+                    exchanged.quantity().commodity(),
+                    exchanged.quantity().quantity().abs()
+                )
+            )?;
+        }
+        if let Some(description) = split.description() {
+            write!(writer, "  ; {}", single_line(description))?;
+        }
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+///
+/// Text written on one line, without anything that would start a comment.
+///
+fn single_line(text: &str) -> String {
+    text.replace(['\n', '\r'], " ").replace(';', ",")
+}
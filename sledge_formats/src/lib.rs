@@ -0,0 +1,209 @@
+/*!
+Importers and exporters for the file formats of other accounting tools, and of the banks and
+authorities that exchange financial data.
+
+Importers produce the [`CreateDatastoreContents`] used to populate a new data store, together
+with an [`ImportReport`] listing anything in the source that could not be mapped onto the
+model, so that nothing is dropped silently.
+
+# Features
+
+* [`beancount`]; Beancount ledgers, for import and export, with balance assertions checked.
+* [`camt`]; ISO 20022 camt.053 statements, camt.052 reports, and camt.054 notifications.
+* [`csv`]; bank statement CSV exports, read as described by a stored mapping profile.
+* [`gnucash`]; GnuCash books in the compressed or plain XML format, or the SQLite format.
+* [`ledger_cli`]; ledger-cli and hledger plain-text journals, for import and export.
+* [`mt940`]; SWIFT MT940 statements and MT942 interim reports, with the common `:86:` layouts.
+* [`ofx`]; OFX and QFX bank, credit card, and investment statements.
+* [`pain`]; ISO 20022 pain.001 credit transfer initiations, for payment runs sent to the bank.
+* [`qif`]; Quicken QIF files, with their account and category lists and investment registers.
+* [`saft`]; the OECD Standard Audit File for Tax, for the books of a company over a period.
+* [`ubl`]; UBL 2.1 invoices under Peppol BIS Billing 3.0, written for sales and read as bills.
+* [`xbrl`]; XBRL instance and inline XBRL documents of the balance sheet and income statement.
+
+Statements are turned into draft transactions, ready for reconciliation, by [`statement`], and
+the drafts are categorized, by rules or by suggestions learned from past transactions, by
+[`categorize`].
+
+*/
+
+#![warn(
+    unknown_lints,
+    // ---------- Stylistic
+    absolute_paths_not_starting_with_crate,
+    elided_lifetimes_in_paths,
+    explicit_outlives_requirements,
+    macro_use_extern_crate,
+    nonstandard_style, /* group */
+    noop_method_call,
+    rust_2018_idioms,
+    single_use_lifetimes,
+    trivial_casts,
+    trivial_numeric_casts,
+    // ---------- Future
+    future_incompatible, /* group */
+    rust_2021_compatibility, /* group */
+    // ---------- Public
+    missing_debug_implementations,
+    // missing_docs,
+    unreachable_pub,
+    // ---------- Unsafe
+    unsafe_code,
+    unsafe_op_in_unsafe_fn,
+    // ---------- Unused
+    unused, /* group */
+)]
+#![deny(
+    // ---------- Public
+    exported_private_dependencies,
+    private_in_public,
+    // ---------- Deprecated
+    anonymous_parameters,
+    bare_trait_objects,
+    ellipsis_inclusive_range_patterns,
+    // ---------- Unsafe
+    deref_nullptr,
+    drop_bounds,
+    dyn_drop,
+)]
+
+use sledge_store::CreateDatastoreContents;
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// Something in the source that the importer could not represent in the model.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnmappedItem {
+    kind: String,
+    id: String,
+    reason: String,
+}
+
+///
+/// The items an importer skipped, or imported only in part.
+///
+#[derive(Clone, Debug, Default)]
+pub struct ImportReport {
+    unmapped: Vec<UnmappedItem>,
+}
+
+///
+/// The result of an import; the content for a new data store and the report of what could
+/// not be mapped.
+///
+#[derive(Debug, Default)]
+pub struct Imported {
+    pub contents: CreateDatastoreContents,
+    pub report: ImportReport,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl UnmappedItem {
+    pub fn new<S1, S2, S3>(kind: S1, id: S2, reason: S3) -> Self
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+        S3: Into<String>,
+    {
+        Self {
+            kind: kind.into(),
+            id: id.into(),
+            reason: reason.into(),
+        }
+    }
+
+    ///
+    /// The kind of item in the source format, such as `"account"` or `"split"`.
+    ///
+    pub fn kind(&self) -> &String {
+        &self.kind
+    }
+
+    ///
+    /// The item's identifier in the source format.
+    ///
+    pub fn id(&self) -> &String {
+        &self.id
+    }
+
+    pub fn reason(&self) -> &String {
+        &self.reason
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl ImportReport {
+    pub fn add<S1, S2, S3>(&mut self, kind: S1, id: S2, reason: S3)
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+        S3: Into<String>,
+    {
+        self.unmapped.push(UnmappedItem::new(kind, id, reason));
+    }
+
+    pub fn unmapped(&self) -> impl Iterator<Item = &UnmappedItem> {
+        self.unmapped.iter()
+    }
+
+    pub fn unmapped_of_kind<'a>(&'a self, kind: &'a str) -> impl Iterator<Item = &'a UnmappedItem> {
+        self.unmapped.iter().filter(move |item| item.kind == kind)
+    }
+
+    ///
+    /// Returns `true` if everything in the source was mapped.
+    ///
+    pub fn is_complete(&self) -> bool {
+        self.unmapped.is_empty()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
+
+pub mod beancount;
+
+pub mod camt;
+
+pub mod categorize;
+
+pub mod csv;
+
+pub mod error;
+
+pub mod gnucash;
+
+pub mod ledger_cli;
+
+pub mod mt940;
+
+pub mod ofx;
+
+pub mod pain;
+
+pub mod qif;
+
+pub mod saft;
+
+pub mod statement;
+
+mod text;
+
+pub mod ubl;
+
+pub mod xbrl;
+
+mod xml;
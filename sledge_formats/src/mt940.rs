@@ -0,0 +1,565 @@
+/*!
+Import SWIFT MT940 customer statements and MT942 interim transaction reports.
+
+A file may hold several messages, with or without their SWIFT header blocks; each
+transaction reference, field `:20:`, starts a [`Statement`] for the account in field `:25:`.
+The opening balance, `:60F:` or `:60M:`, and the closing balance, `:62F:` or `:62M:`, become
+the statement's balances. An MT942 report has no balances; its period ends at the time in field
+`:13D:`.
+
+Each statement line, field `:61:`, becomes an entry, posted on its booking date or, if it has
+none, its value date. Reversals, `RC` and `RD`, take the sign of the opposite of the entry they
+reverse. The customer reference becomes the entry's reference, unless it is `NONREF`, and the
+bank's reference its identifier; lines without a bank reference are identified by their value
+date, amount, and customer reference, and how many lines before them in the statement share
+them, so that the same line in an MT942 report and a later MT940 statement is imported once.
+
+The information to account owner, field `:86:`, is written differently by different banks;
+three layouts are read.
+
+* The structured layout of German banks, such as `166?00SEPA-GUTSCHRIFT?20EREF+...?32NAME`;
+  the booking text (`?00`) becomes the memo, the purpose lines (`?20` to `?29` and `?60` to
+  `?63`) the description, or just the purpose of a SEPA transfer after `SVWZ+`, and the name
+  lines (`?32` and `?33`) the counterparty. A SEPA end-to-end reference, `EREF+`, becomes the
+  reference.
+* The keyword layout of Dutch and other banks, such as
+  `/TRTP/SEPA OVERBOEKING/IBAN/.../NAME/.../REMI/.../EREF/...`; the transaction type becomes
+  the memo, the remittance information the description, the name, or that of the counterparty
+  (`CNTP`), the counterparty, and the end-to-end reference the reference.
+* Any other text becomes the description.
+
+*/
+
+use crate::error::{invalid_value, missing_element, syntax_error, Result};
+use crate::statement::{Statement, StatementBalance, StatementEntry, Statements};
+use chrono::{DateTime, Datelike, Days, NaiveDate, NaiveDateTime, Utc};
+use codes_iso_4217::CurrencyCode;
+use rust_decimal::Decimal;
+use sledge_model::ledger::AccountRepresentsKind;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::str::FromStr;
+use tracing::info;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+pub fn read_mt940_file<P: AsRef<Path>>(path: P) -> Result<Statements> {
+    info!("Reading MT940 file {:?}", path.as_ref());
+    read_mt940(File::open(path)?)
+}
+
+///
+/// Read MT940 statements, MT942 reports, or both.
+///
+pub fn read_mt940<R: Read>(mut reader: R) -> Result<Statements> {
+    let mut bytes: Vec<u8> = Default::default();
+    let _ = reader.read_to_end(&mut bytes)?;
+    // Banks in Germany often write Latin-1, rather than the SWIFT character set.
+    let text = match String::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(e) => e.into_bytes().into_iter().map(char::from).collect(),
+    };
+
+    let mut reader = Reader::default();
+    for (line, tag, value) in fields(&text)? {
+        reader.field(line, &tag, &value)?;
+    }
+    reader.finish()?;
+    Ok(reader.statements)
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Debug, Default)]
+struct Reader {
+    statements: Statements,
+    current: Option<Current>,
+}
+
+///
+/// The statement being read, and what is known of it before its account and currency.
+///
+#[derive(Debug, Default)]
+struct Current {
+    line: usize,
+    reference: String,
+    account: Option<String>,
+    currency: Option<CurrencyCode>,
+    opening_balance: Option<StatementBalance>,
+    closing_balance: Option<StatementBalance>,
+    reported_at: Option<DateTime<Utc>>,
+    entries: Vec<StatementEntry>,
+    occurrences: HashMap<String, usize>,
+}
+
+#[derive(Debug, Default)]
+struct Information {
+    description: Option<String>,
+    memo: Option<String>,
+    reference: Option<String>,
+    counterparty: Option<String>,
+}
+
+const NONREF: &str = "NONREF";
+
+const NOT_PROVIDED: &str = "NOTPROVIDED";
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl Reader {
+    fn field(&mut self, line: usize, tag: &str, value: &str) -> Result<()> {
+        if tag == "20" {
+            self.finish()?;
+            self.current = Some(Current {
+                line,
+                reference: value.trim().to_string(),
+                ..Default::default()
+            });
+            return Ok(());
+        }
+        let Some(current) = self.current.as_mut() else {
+            return Err(syntax_error(line, format!(":{}: before :20:", tag)));
+        };
+        match tag {
+            "25" => current.account = Some(value.trim().to_string()),
+            "60F" | "60M" => {
+                let (currency, balance) = parse_balance(line, value)?;
+                current.currency = Some(currency);
+                current.opening_balance = Some(balance);
+            }
+            "62F" | "62M" => {
+                let (currency, balance) = parse_balance(line, value)?;
+                current.currency = Some(currency);
+                current.closing_balance = Some(balance);
+            }
+            // The floor limit of an MT942 report, which gives its currency.
+            "34F" => {
+                let currency = value.get(..3).unwrap_or_default();
+                current.currency = Some(
+                    CurrencyCode::from_str(currency)
+                        .map_err(|_| invalid_value("currency", currency))?,
+                );
+            }
+            "13D" | "13" => current.reported_at = Some(parse_reported_at(line, value)?),
+            "61" => {
+                let entry = current.statement_line(line, value)?;
+                current.entries.push(entry);
+            }
+            // Information for the statement as a whole, before any line, is not kept.
+            "86" => {
+                if let Some(entry) = current.entries.pop() {
+                    current.entries.push(information(value).describe(entry));
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        let Some(current) = self.current.take() else {
+            return Ok(());
+        };
+        if current.entries.is_empty() && current.closing_balance.is_none() {
+            self.statements.report.add(
+                "statement",
+                current.reference,
+                format!("the statement at line {} is empty", current.line),
+            );
+            return Ok(());
+        }
+        let account = current
+            .account
+            .ok_or_else(|| missing_element(&current.reference, ":25:"))?;
+        let currency = current
+            .currency
+            .ok_or_else(|| missing_element(&current.reference, ":60F:"))?;
+        let mut statement = Statement::new(AccountRepresentsKind::BankAccount, account, currency);
+        if let Some(balance) = current.opening_balance {
+            statement = statement.with_opening_balance(balance);
+        }
+        if let Some(balance) = current.closing_balance {
+            statement = statement.with_closing_balance(balance);
+        }
+        if let Some(reported_at) = current.reported_at {
+            let start = current
+                .entries
+                .iter()
+                .map(StatementEntry::posted)
+                .min()
+                .unwrap_or(reported_at);
+            statement = statement.with_period(start, reported_at);
+        }
+        for entry in current.entries {
+            statement.add_entry(entry);
+        }
+        self.statements.statements.push(statement);
+        Ok(())
+    }
+}
+
+impl Current {
+    ///
+    /// Parse a statement line, `YYMMDD[MMDD](C|D|RC|RD)[funds code]amount type reference
+    /// [//bank reference][\ndetails]`.
+    ///
+    fn statement_line(&mut self, line: usize, value: &str) -> Result<StatementEntry> {
+        let invalid = || syntax_error(line, format!(":61:{}", value));
+        let (first, details) = match value.split_once('\n') {
+            Some((first, details)) => (first, Some(details.trim())),
+            None => (value, None),
+        };
+        let value_date = parse_date(first.get(..6).ok_or_else(invalid)?).ok_or_else(invalid)?;
+        let mut rest = &first[6..];
+
+        let mut posted = value_date;
+        // Text decoded as Latin-1 may hold characters of more than one byte.
+        let booking_date = rest.get(..4).ok_or_else(invalid)?;
+        if booking_date.chars().all(|c| c.is_ascii_digit()) {
+            let month: u32 = booking_date[..2].parse().map_err(|_| invalid())?;
+            let day: u32 = booking_date[2..].parse().map_err(|_| invalid())?;
+            // The booking date may fall in the year before or after the value date.
+            let year = match (value_date.month(), month) {
+                (12, 1) => value_date.year() + 1,
+                (1, 12) => value_date.year() - 1,
+                _ => value_date.year(),
+            };
+            posted = NaiveDate::from_ymd_opt(year, month, day).ok_or_else(invalid)?;
+            rest = &rest[4..];
+        }
+
+        let (negative, length) = if rest.starts_with("RC") {
+            (true, 2)
+        } else if rest.starts_with("RD") {
+            (false, 2)
+        } else if rest.starts_with('C') {
+            (false, 1)
+        } else if rest.starts_with('D') {
+            (true, 1)
+        } else {
+            return Err(invalid());
+        };
+        rest = &rest[length..];
+        // The funds code, the third letter of the currency code, is optional.
+        if rest.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            rest = &rest[1..];
+        }
+        let amount_length = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == ','))
+            .ok_or_else(invalid)?;
+        let amount = parse_amount(&rest[..amount_length]).ok_or_else(invalid)?;
+        let amount = if negative { -amount } else { amount };
+        // The transaction type, such as NTRF, is followed by the customer's reference.
+        rest = rest.get(amount_length + 4..).ok_or_else(invalid)?;
+        let (customer_reference, bank_reference) = match rest.split_once("//") {
+            Some((customer, bank)) => (customer.trim(), Some(bank.trim())),
+            None => (rest.trim(), None),
+        };
+        let customer_reference = Some(customer_reference)
+            .filter(|reference| !reference.is_empty() && *reference != NONREF);
+
+        let id = match bank_reference.filter(|reference| !reference.is_empty()) {
+            Some(reference) => reference.to_string(),
+            None => {
+                let key = format!(
+                    "{}/{}/{}",
+                    value_date,
+                    amount,
+                    customer_reference.unwrap_or_default()
+                );
+                let count = self.occurrences.entry(key.clone()).or_default();
+                *count += 1;
+                format!("{}/{}", key, count)
+            }
+        };
+        let mut entry =
+            StatementEntry::new(id, posted.and_hms_opt(0, 0, 0).unwrap().and_utc(), amount);
+        if let Some(reference) = customer_reference {
+            entry = entry.with_reference(reference);
+        }
+        if let Some(details) = details.filter(|details| !details.is_empty()) {
+            entry = entry.with_memo(details);
+        }
+        Ok(entry)
+    }
+}
+
+impl Information {
+    fn describe(self, mut entry: StatementEntry) -> StatementEntry {
+        if let Some(description) = self.description {
+            entry = entry.with_description(description);
+        }
+        if let Some(memo) = self.memo {
+            entry = entry.with_memo(memo);
+        }
+        // The customer's reference on the statement line comes first.
+        if let Some(reference) = self.reference.filter(|_| entry.reference().is_none()) {
+            entry = entry.with_reference(reference);
+        }
+        if let Some(counterparty) = self.counterparty {
+            entry = entry.with_counterparty(counterparty);
+        }
+        entry
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+///
+/// The fields of every message in `text`, as their line, tag, and value; a value continued
+/// over several lines keeps its line breaks.
+///
+fn fields(text: &str) -> Result<Vec<(usize, String, String)>> {
+    let mut fields: Vec<(usize, String, String)> = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        let mut line = line.trim_end();
+        // The header blocks end where the text block, {4:, starts.
+        if line.starts_with('{') {
+            match line.find("{4:") {
+                Some(index) => line = &line[index + 3..],
+                None => continue,
+            }
+        }
+        if line.is_empty() || line == "-" || line == "-}" {
+            continue;
+        }
+        match line
+            .strip_prefix(':')
+            .and_then(|rest| rest.split_once(':'))
+            .filter(|(tag, _)| is_tag(tag))
+        {
+            Some((tag, value)) => fields.push((n + 1, tag.to_string(), value.to_string())),
+            None => match fields.last_mut() {
+                Some((_, _, value)) => {
+                    value.push('\n');
+                    value.push_str(line);
+                }
+                None => return Err(syntax_error(n + 1, line)),
+            },
+        }
+    }
+    Ok(fields)
+}
+
+///
+/// A tag is two digits, and an optional letter.
+///
+fn is_tag(tag: &str) -> bool {
+    let bytes = tag.as_bytes();
+    (bytes.len() == 2 || bytes.len() == 3)
+        && bytes[..2].iter().all(u8::is_ascii_digit)
+        && bytes[2..].iter().all(u8::is_ascii_uppercase)
+}
+
+///
+/// Parse a balance, `(C|D)YYMMDDCCCamount`, as at the end of its date.
+///
+fn parse_balance(line: usize, value: &str) -> Result<(CurrencyCode, StatementBalance)> {
+    let invalid = || syntax_error(line, value);
+    let value = value.trim();
+    let negative = match value.get(..1) {
+        Some("C") => false,
+        Some("D") => true,
+        _ => return Err(invalid()),
+    };
+    let date = parse_date(value.get(1..7).ok_or_else(invalid)?).ok_or_else(invalid)?;
+    let currency = value.get(7..10).ok_or_else(invalid)?;
+    let currency =
+        CurrencyCode::from_str(currency).map_err(|_| invalid_value("currency", currency))?;
+    let amount = parse_amount(value.get(10..).ok_or_else(invalid)?).ok_or_else(invalid)?;
+    let as_at = (date + Days::new(1))
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_utc();
+    Ok((
+        currency,
+        StatementBalance::new(if negative { -amount } else { amount }, as_at),
+    ))
+}
+
+///
+/// Parse the time of an MT942 report, `YYMMDDHHMM` and an offset, `+HHMM` or `-HHMM`, from
+/// UTC.
+///
+fn parse_reported_at(line: usize, value: &str) -> Result<DateTime<Utc>> {
+    let value = value.trim();
+    let invalid = || syntax_error(line, value);
+    let local = NaiveDateTime::parse_from_str(value.get(..10).ok_or_else(invalid)?, "%y%m%d%H%M")
+        .map_err(|_| invalid())?;
+    match value.get(10..).filter(|offset| !offset.is_empty()) {
+        Some(offset) => DateTime::parse_from_str(
+            &format!("{}{}", local.format("%Y%m%d%H%M"), offset),
+            "%Y%m%d%H%M%z",
+        )
+        .map(|datetime| datetime.with_timezone(&Utc))
+        .map_err(|_| invalid()),
+        None => Ok(local.and_utc()),
+    }
+}
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%y%m%d").ok()
+}
+
+///
+/// Parse an amount, written with a decimal comma.
+///
+fn parse_amount(value: &str) -> Option<Decimal> {
+    Decimal::from_str(&value.replace(',', ".")).ok()
+}
+
+fn information(value: &str) -> Information {
+    let joined: String = value.lines().map(str::trim_end).collect();
+    let bytes = joined.as_bytes();
+    if bytes.len() > 5
+        && bytes[..3].iter().all(u8::is_ascii_digit)
+        && joined.get(4..6) == Some("00")
+    {
+        let separator = joined[3..].chars().next().unwrap_or('?');
+        if !separator.is_ascii_alphanumeric() {
+            return structured_information(&joined[3..], separator);
+        }
+    }
+    if joined.starts_with('/') && joined[1..].contains('/') {
+        return keyword_information(&joined);
+    }
+    Information {
+        description: Some(value.lines().map(str::trim).collect::<Vec<_>>().join(" "))
+            .filter(|text| !text.is_empty()),
+        ..Default::default()
+    }
+}
+
+///
+/// The German structured layout; sub-fields each start with the separator and a two-digit
+/// number.
+///
+fn structured_information(value: &str, separator: char) -> Information {
+    let mut purpose: Vec<&str> = Vec::new();
+    let mut names: Vec<&str> = Vec::new();
+    let mut information = Information::default();
+    for sub_field in value.split(separator).filter(|s| s.len() >= 2) {
+        let (number, text) = sub_field.split_at(2);
+        match number {
+            "00" => information.memo = Some(text.trim().to_string()).filter(|t| !t.is_empty()),
+            "20" | "21" | "22" | "23" | "24" | "25" | "26" | "27" | "28" | "29" | "60" | "61"
+            | "62" | "63" => purpose.push(text),
+            "32" | "33" => names.push(text),
+            _ => {}
+        }
+    }
+    let purpose: String = purpose.concat();
+    let sepa = sepa_fields(&purpose);
+    information.reference = sepa
+        .iter()
+        .find(|(key, _)| *key == "EREF")
+        .map(|(_, value)| value.to_string())
+        .filter(|reference| reference != NOT_PROVIDED);
+    information.description = sepa
+        .iter()
+        .find(|(key, _)| *key == "SVWZ")
+        .map(|(_, value)| value.to_string())
+        .or_else(|| Some(purpose.trim().to_string()))
+        .filter(|description| !description.is_empty());
+    information.counterparty =
+        Some(names.concat().trim().to_string()).filter(|name| !name.is_empty());
+    information
+}
+
+///
+/// The SEPA fields, such as `EREF+` and `SVWZ+`, in a German purpose, or none if it has none.
+///
+fn sepa_fields(purpose: &str) -> Vec<(&str, &str)> {
+    const KEYS: [&str; 8] = [
+        "EREF", "KREF", "MREF", "CRED", "DEBT", "SVWZ", "ABWA", "ABWE",
+    ];
+    let mut starts: Vec<(usize, &str)> = KEYS
+        .iter()
+        .filter_map(|key| purpose.find(&format!("{}+", key)).map(|at| (at, *key)))
+        .collect();
+    starts.sort();
+    starts
+        .iter()
+        .enumerate()
+        .map(|(i, (at, key))| {
+            let end = starts
+                .get(i + 1)
+                .map(|(at, _)| *at)
+                .unwrap_or(purpose.len());
+            (*key, purpose[at + key.len() + 1..end].trim())
+        })
+        .collect()
+}
+
+///
+/// The keyword layout; keywords and values alternate, each between slashes. A value may itself
+/// hold slashes, as in `/CNTP/iban/bic/name/place/`, so only known keywords start a new pair.
+///
+fn keyword_information(value: &str) -> Information {
+    const KEYWORDS: [&str; 13] = [
+        "TRTP", "IBAN", "BIC", "NAME", "REMI", "EREF", "MARF", "CSID", "ORDP", "BENM", "CNTP",
+        "PREF", "ID",
+    ];
+    let parts: Vec<&str> = value.trim_matches('/').split('/').collect();
+    let mut pairs: Vec<(&str, Vec<&str>)> = Vec::new();
+    for part in parts {
+        match pairs.last_mut() {
+            Some((_, values)) if !KEYWORDS.contains(&part) || values.is_empty() => {
+                values.push(part)
+            }
+            _ => pairs.push((part, Vec::new())),
+        }
+    }
+    let get = |keyword: &str| -> Option<&Vec<&str>> {
+        pairs
+            .iter()
+            .find(|(key, _)| *key == keyword)
+            .map(|(_, values)| values)
+    };
+    let text = |values: &Vec<&str>| -> Option<String> {
+        // Structured remittance is written /REMI/USTD//text/ or /REMI/STRD/CUR/reference/.
+        let values: Vec<&str> = values
+            .iter()
+            .copied()
+            .filter(|value| !value.is_empty() && !matches!(*value, "USTD" | "STRD" | "CUR"))
+            .collect();
+        Some(values.join("/").trim().to_string()).filter(|text| !text.is_empty())
+    };
+    Information {
+        description: get("REMI").and_then(text),
+        memo: get("TRTP").and_then(text),
+        reference: get("EREF")
+            .and_then(text)
+            .filter(|reference| reference != NOT_PROVIDED),
+        counterparty: get("NAME")
+            .and_then(text)
+            .or_else(|| {
+                get("CNTP")
+                    .and_then(|values| values.get(2))
+                    .map(|name| name.to_string())
+            })
+            .filter(|name| !name.is_empty()),
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
@@ -0,0 +1,96 @@
+/*!
+Import OFX and QFX statements, as downloaded from banks, card issuers, and brokers.
+
+Both OFX 1.x, which is SGML, and OFX 2.x, which is XML, are read. Each bank, credit card, and
+investment statement response becomes a [`Statement`](crate::statement::Statement) for the
+account number in its `BANKACCTFROM`, `CCACCTFROM`, or `INVACCTFROM`, with the ledger balance,
+or for investment accounts the available cash, as its closing balance. Each transaction's
+`FITID` becomes the entry's identifier, which
+[`draft_transactions`](crate::statement::draft_transactions) uses to skip transactions already
+imported.
+
+For investment statements, buys and sells become entries holding the units of the security
+traded and the cash total, income and interest become cash entries, and reinvested income
+becomes an income entry and a buy. Securities are identified by their `SECID`; ISINs are used
+as they are, CUSIPs are turned into US ISINs, and others can be mapped with
+[`OfxOptions::with_security`]. Other investment transactions, such as transfers and splits, are
+listed in the report.
+
+*/
+
+use sledge_model::commodity::{CommodityId, InternationalSecuritiesId};
+use std::collections::HashMap;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Clone, Debug, Default)]
+pub struct OfxOptions {
+    securities: HashMap<String, CommodityId>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl OfxOptions {
+    ///
+    /// Import the security with the `SECID` unique identifier, or ticker, `id` as `commodity`.
+    ///
+    pub fn with_security<S: Into<String>>(mut self, id: S, commodity: CommodityId) -> Self {
+        let _ = self.securities.insert(id.into(), commodity);
+        self
+    }
+
+    ///
+    /// The commodity for a security, from those added to these options by unique identifier
+    /// and then by ticker, and otherwise from an ISIN or a CUSIP.
+    ///
+    pub fn security(
+        &self,
+        unique_id: &str,
+        unique_id_type: &str,
+        ticker: Option<&str>,
+    ) -> Option<CommodityId> {
+        self.securities
+            .get(unique_id)
+            .or_else(|| ticker.and_then(|ticker| self.securities.get(ticker)))
+            .cloned()
+            .or_else(|| match unique_id_type {
+                "ISIN" => Some(InternationalSecuritiesId::from(unique_id))
+                    .filter(InternationalSecuritiesId::is_valid)
+                    .map(CommodityId::Security),
+                "CUSIP" => InternationalSecuritiesId::from_national("US", unique_id)
+                    .map(CommodityId::Security),
+                _ => None,
+            })
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
+
+#[doc(hidden)]
+mod tree;
+
+#[doc(hidden)]
+mod read;
+pub use read::{read_ofx, read_ofx_file};
@@ -0,0 +1,415 @@
+/*!
+Read OFX and QFX statement files.
+
+*/
+
+use super::tree::{parse, Element};
+use super::OfxOptions;
+use crate::error::{invalid_value, missing_element, Result};
+use crate::statement::{Statement, StatementBalance, StatementEntry, Statements};
+use crate::text;
+use chrono::{DateTime, Days, Duration, NaiveDate, NaiveDateTime, Utc};
+use codes_iso_4217::CurrencyCode;
+use rust_decimal::Decimal;
+use sledge_model::commodity::{CommodityId, Quantity};
+use sledge_model::ledger::AccountRepresentsKind;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::str::FromStr;
+use tracing::info;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+pub fn read_ofx_file<P: AsRef<Path>>(path: P, options: &OfxOptions) -> Result<Statements> {
+    info!("Reading OFX file {:?}", path.as_ref());
+    read_ofx(File::open(path)?, options)
+}
+
+pub fn read_ofx<R: Read>(mut reader: R, options: &OfxOptions) -> Result<Statements> {
+    let mut bytes: Vec<u8> = Default::default();
+    let _ = reader.read_to_end(&mut bytes)?;
+    let document = decode(bytes)?;
+    let ofx = parse(&document)?;
+
+    let mut reader = Reader {
+        options,
+        securities: securities(&ofx),
+        statements: Default::default(),
+    };
+    for (messages, response, statement, from, kind) in [
+        (
+            "BANKMSGSRSV1",
+            "STMTTRNRS",
+            "STMTRS",
+            "BANKACCTFROM",
+            AccountRepresentsKind::BankAccount,
+        ),
+        (
+            "CREDITCARDMSGSRSV1",
+            "CCSTMTTRNRS",
+            "CCSTMTRS",
+            "CCACCTFROM",
+            AccountRepresentsKind::CreditCard,
+        ),
+        (
+            "INVSTMTMSGSRSV1",
+            "INVSTMTTRNRS",
+            "INVSTMTRS",
+            "INVACCTFROM",
+            AccountRepresentsKind::BrokerageAccount,
+        ),
+    ] {
+        for transaction_response in ofx
+            .child(messages)
+            .into_iter()
+            .flat_map(|messages| messages.children())
+            .filter(|element| element.name() == response)
+        {
+            match transaction_response.child(statement) {
+                Some(statement) => reader.statement(statement, from, kind)?,
+                None => reader.statements.report.add(
+                    "statement",
+                    transaction_response.text(&["TRNUID"]).unwrap_or_default(),
+                    format!(
+                        "the response has no statement: {}",
+                        transaction_response
+                            .text(&["STATUS", "MESSAGE"])
+                            .or_else(|| transaction_response.text(&["STATUS", "CODE"]))
+                            .unwrap_or("no status")
+                    ),
+                ),
+            }
+        }
+    }
+    Ok(reader.statements)
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Debug)]
+struct Reader<'a> {
+    options: &'a OfxOptions,
+    securities: HashMap<String, SecurityInfo>,
+    statements: Statements,
+}
+
+#[derive(Debug)]
+struct SecurityInfo {
+    ticker: Option<String>,
+    name: Option<String>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl Reader<'_> {
+    fn statement(
+        &mut self,
+        response: &Element,
+        from: &str,
+        kind: AccountRepresentsKind,
+    ) -> Result<()> {
+        let currency = required(response, &["CURDEF"])?;
+        let currency =
+            CurrencyCode::from_str(currency).map_err(|_| invalid_value("CURDEF", currency))?;
+        let mut statement = Statement::new(kind, required(response, &[from, "ACCTID"])?, currency);
+
+        let list = if kind == AccountRepresentsKind::BrokerageAccount {
+            "INVTRANLIST"
+        } else {
+            "BANKTRANLIST"
+        };
+        if let (Some(start), Some(end)) = (
+            response.text(&[list, "DTSTART"]),
+            response.text(&[list, "DTEND"]),
+        ) {
+            statement = statement.with_period(parse_datetime(start)?, parse_datetime(end)?);
+        }
+        let balance = if kind == AccountRepresentsKind::BrokerageAccount {
+            response
+                .text(&["INVBAL", "AVAILCASH"])
+                .zip(response.text(&["DTASOF"]))
+        } else {
+            response
+                .text(&["LEDGERBAL", "BALAMT"])
+                .zip(response.text(&["LEDGERBAL", "DTASOF"]))
+        };
+        if let Some((amount, as_at)) = balance {
+            statement = statement.with_closing_balance(StatementBalance::new(
+                parse_decimal("BALAMT", amount)?,
+                parse_as_at(as_at)?,
+            ));
+        }
+
+        for transaction in response
+            .child(list)
+            .into_iter()
+            .flat_map(|list| list.children())
+        {
+            match transaction.name() {
+                "DTSTART" | "DTEND" => {}
+                "STMTTRN" => statement.add_entry(bank_entry(transaction)?),
+                "INVBANKTRAN" => statement.add_entry(bank_entry(
+                    transaction
+                        .child("STMTTRN")
+                        .ok_or_else(|| missing_element("INVBANKTRAN", "STMTTRN"))?,
+                )?),
+                name => {
+                    for entry in self.investment_entries(transaction)? {
+                        statement.add_entry(entry);
+                    }
+                    if !is_investment_entry(name) {
+                        self.statements.report.add(
+                            "entry",
+                            transaction.text(&["INVTRAN", "FITID"]).unwrap_or_default(),
+                            format!("the OFX {} transaction is not imported", name),
+                        );
+                    }
+                }
+            }
+        }
+        self.statements.statements.push(statement);
+        Ok(())
+    }
+
+    ///
+    /// The entries for an investment transaction; none if it is not supported, or its
+    /// security is not mapped.
+    ///
+    fn investment_entries(&mut self, transaction: &Element) -> Result<Vec<StatementEntry>> {
+        let name = transaction.name();
+        if !is_investment_entry(name) {
+            return Ok(Vec::new());
+        }
+        // Trades hold their details in an INVBUY or INVSELL aggregate.
+        let details = transaction
+            .child("INVBUY")
+            .or_else(|| transaction.child("INVSELL"))
+            .unwrap_or(transaction);
+        let id = required(details, &["INVTRAN", "FITID"])?;
+        let posted = parse_datetime(required(details, &["INVTRAN", "DTTRADE"])?)?;
+        let total = parse_decimal("TOTAL", required(details, &["TOTAL"])?)?;
+        let memo = details.text(&["INVTRAN", "MEMO"]);
+        let unique_id = details.text(&["SECID", "UNIQUEID"]);
+        let info = unique_id.and_then(|id| self.securities.get(id));
+        let label = info
+            .and_then(|info| info.ticker.as_deref().or(info.name.as_deref()))
+            .or(unique_id)
+            .unwrap_or_default()
+            .to_string();
+
+        let cash = |id: String, amount: Decimal, description: String| {
+            let entry = StatementEntry::new(id, posted, amount).with_description(description);
+            match memo {
+                Some(memo) => entry.with_memo(memo),
+                None => entry,
+            }
+        };
+        if matches!(name, "INCOME" | "MARGININTEREST" | "RETOFCAP") {
+            let kind = transaction.text(&["INCOMETYPE"]).unwrap_or(name);
+            return Ok(vec![cash(
+                id.to_string(),
+                total,
+                format!("{} {}", kind, label).trim().to_string(),
+            )]);
+        }
+
+        let Some(security) = self.security(details) else {
+            self.statements.report.add(
+                "entry",
+                id,
+                format!("the security {:?} is not mapped", label),
+            );
+            return Ok(Vec::new());
+        };
+        let units = parse_decimal("UNITS", required(details, &["UNITS"])?)?;
+        let verb = if units.is_sign_negative() {
+            "Sell"
+        } else {
+            "Buy"
+        };
+        let trade = |id: String| {
+            cash(id, total, format!("{} {} {}", verb, units.abs(), label))
+                .with_security(Quantity::new(security.clone(), units))
+        };
+        if name == "REINVEST" {
+            // The income is paid in cash, and the same amount then buys the security.
+            let kind = transaction.text(&["INCOMETYPE"]).unwrap_or("INCOME");
+            return Ok(vec![
+                cash(
+                    format!("{}-income", id),
+                    -total,
+                    format!("{} {}", kind, label),
+                ),
+                trade(id.to_string()),
+            ]);
+        }
+        Ok(vec![trade(id.to_string())])
+    }
+
+    fn security(&self, details: &Element) -> Option<CommodityId> {
+        let unique_id = details.text(&["SECID", "UNIQUEID"])?;
+        self.options.security(
+            unique_id,
+            details.text(&["SECID", "UNIQUEIDTYPE"]).unwrap_or_default(),
+            self.securities
+                .get(unique_id)
+                .and_then(|info| info.ticker.as_deref()),
+        )
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+///
+/// Decode a file in the encoding its OFX 1.x SGML header declares, either `ENCODING:UTF-8` or
+/// one of the `CHARSET` code pages; files that do not say are most often Windows-1252 when they
+/// are not UTF-8.
+///
+fn decode(bytes: Vec<u8>) -> Result<String> {
+    let end = bytes
+        .windows(5)
+        .position(|window| window == b"<OFX>")
+        .unwrap_or(bytes.len());
+    let header = String::from_utf8_lossy(&bytes[..end]).into_owned();
+    let value = |name: &str| {
+        header.lines().find_map(|line| {
+            line.trim()
+                .strip_prefix(name)
+                .and_then(|rest| rest.strip_prefix(':'))
+                .map(str::trim)
+        })
+    };
+    if value("ENCODING").is_some_and(|encoding| encoding.eq_ignore_ascii_case("UTF-8")) {
+        return text::utf_8(bytes);
+    }
+    match value("CHARSET") {
+        Some("1252") => Ok(text::windows_1252(bytes)),
+        Some(charset) if charset.eq_ignore_ascii_case("ISO-8859-1") => Ok(text::latin_1(bytes)),
+        _ => match String::from_utf8(bytes) {
+            Ok(document) => Ok(document),
+            Err(e) => Ok(text::windows_1252(e.into_bytes())),
+        },
+    }
+}
+
+fn is_investment_entry(name: &str) -> bool {
+    name.starts_with("BUY")
+        || name.starts_with("SELL")
+        || matches!(name, "INCOME" | "MARGININTEREST" | "RETOFCAP" | "REINVEST")
+}
+
+///
+/// The ticker and name of each security in the security list, by unique identifier.
+///
+fn securities(ofx: &Element) -> HashMap<String, SecurityInfo> {
+    ofx.find(&["SECLISTMSGSRSV1", "SECLIST"])
+        .into_iter()
+        .flat_map(|list| list.children())
+        .filter_map(|info| {
+            let secinfo = info.child("SECINFO")?;
+            Some((
+                secinfo.text(&["SECID", "UNIQUEID"])?.to_string(),
+                SecurityInfo {
+                    ticker: secinfo.text(&["TICKER"]).map(str::to_string),
+                    name: secinfo.text(&["SECNAME"]).map(str::to_string),
+                },
+            ))
+        })
+        .collect()
+}
+
+fn bank_entry(transaction: &Element) -> Result<StatementEntry> {
+    let mut entry = StatementEntry::new(
+        required(transaction, &["FITID"])?,
+        parse_datetime(required(transaction, &["DTPOSTED"])?)?,
+        parse_decimal("TRNAMT", required(transaction, &["TRNAMT"])?)?,
+    );
+    if let Some(name) = transaction
+        .text(&["NAME"])
+        .or_else(|| transaction.text(&["PAYEE", "NAME"]))
+    {
+        entry = entry.with_description(name).with_counterparty(name);
+    }
+    if let Some(memo) = transaction.text(&["MEMO"]) {
+        entry = entry.with_memo(memo);
+    }
+    if let Some(reference) = transaction
+        .text(&["CHECKNUM"])
+        .or_else(|| transaction.text(&["REFNUM"]))
+    {
+        entry = entry.with_reference(reference);
+    }
+    Ok(entry)
+}
+
+fn required<'a>(element: &'a Element, path: &[&str]) -> Result<&'a str> {
+    element
+        .text(path)
+        .ok_or_else(|| missing_element(element.name(), path.join("/")))
+}
+
+fn parse_decimal(name: &str, value: &str) -> Result<Decimal> {
+    // Some institutions write a decimal comma.
+    Decimal::from_str(&value.replace(',', ".")).map_err(|_| invalid_value(name, value))
+}
+
+///
+/// Parse an OFX date and time, `YYYYMMDD[HHMMSS[.XXX]][[offset[:zone]]]`, where the offset is
+/// in hours from UTC, which is assumed if there is none.
+///
+fn parse_datetime(value: &str) -> Result<DateTime<Utc>> {
+    let invalid = || invalid_value("date", value);
+    let (local, zone) = match value.split_once('[') {
+        Some((local, zone)) => (local, Some(zone.trim_end_matches(']'))),
+        None => (value, None),
+    };
+    let digits = local.split('.').next().unwrap_or_default();
+    let local = match digits.len() {
+        8 => NaiveDate::parse_from_str(digits, "%Y%m%d")
+            .map_err(|_| invalid())?
+            .and_hms_opt(0, 0, 0)
+            .unwrap(),
+        12 => NaiveDateTime::parse_from_str(digits, "%Y%m%d%H%M").map_err(|_| invalid())?,
+        14 => NaiveDateTime::parse_from_str(digits, "%Y%m%d%H%M%S").map_err(|_| invalid())?,
+        _ => return Err(invalid()),
+    };
+    let offset = match zone.and_then(|zone| zone.split(':').next()) {
+        Some(hours) if !hours.is_empty() => {
+            let hours = Decimal::from_str(hours).map_err(|_| invalid())?;
+            let seconds = (hours * Decimal::from(3600)).round();
+            Duration::seconds(i64::try_from(seconds).map_err(|_| invalid())?)
+        }
+        _ => Duration::zero(),
+    };
+    Ok((local - offset).and_utc())
+}
+
+///
+/// The time a balance is as at; a date alone means the end of that day.
+///
+fn parse_as_at(value: &str) -> Result<DateTime<Utc>> {
+    let as_at = parse_datetime(value)?;
+    if value.split(['.', '[']).next().map(str::len) == Some(8) {
+        Ok(as_at + Days::new(1))
+    } else {
+        Ok(as_at)
+    }
+}
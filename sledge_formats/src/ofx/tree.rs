@@ -0,0 +1,177 @@
+/*!
+A tolerant element tree for OFX documents, which are SGML in version 1 and XML in version 2.
+
+SGML OFX leaves the elements holding a value unclosed, as in `<TRNAMT>-42.50`, so an element
+that has text is closed by the next tag; aggregates are always closed. XML OFX closes every
+element, which reads the same way.
+
+*/
+
+use crate::error::{invalid_value, Result};
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Element {
+    name: String,
+    text: String,
+    children: Vec<Element>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+///
+/// Parse the `OFX` element of a document, ignoring the SGML header or XML declaration and
+/// processing instructions before it.
+///
+pub(crate) fn parse(document: &str) -> Result<Element> {
+    let start = document
+        .find("<OFX>")
+        .ok_or_else(|| invalid_value("OFX", "the document has no OFX element"))?;
+    let mut rest = &document[start..];
+    let mut stack: Vec<Element> = vec![Element::default()];
+    while !rest.is_empty() {
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment
+                .split_once("-->")
+                .map(|(_, r)| r)
+                .unwrap_or_default();
+        } else if let Some(tag) = rest.strip_prefix('<') {
+            let (tag, remainder) = tag
+                .split_once('>')
+                .ok_or_else(|| invalid_value("OFX", "a tag is not closed"))?;
+            rest = remainder;
+            let tag = tag.trim();
+            if tag.starts_with(['?', '!']) {
+                continue;
+            }
+            if let Some(name) = tag.strip_prefix('/') {
+                close(&mut stack, name.trim())?;
+            } else {
+                close_value(&mut stack);
+                let (name, empty) = match tag.strip_suffix('/') {
+                    Some(name) => (name.trim(), true),
+                    None => (tag, false),
+                };
+                let element = Element {
+                    name: name.to_string(),
+                    ..Default::default()
+                };
+                if empty {
+                    stack.last_mut().unwrap().children.push(element);
+                } else {
+                    stack.push(element);
+                }
+            }
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            let text = rest[..end].trim();
+            if !text.is_empty() {
+                if stack.len() < 2 {
+                    return Err(invalid_value("OFX", text));
+                }
+                stack.last_mut().unwrap().text = unescape(text);
+            }
+            rest = &rest[end..];
+        }
+    }
+    close_value(&mut stack);
+    while stack.len() > 1 {
+        let element = stack.pop().unwrap();
+        stack.last_mut().unwrap().children.push(element);
+    }
+    stack
+        .pop()
+        .and_then(|root| root.children.into_iter().next())
+        .ok_or_else(|| invalid_value("OFX", "the document has no OFX element"))
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl Element {
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn children(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter()
+    }
+
+    pub(crate) fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    ///
+    /// The descendant at `path`, a list of element names, such as `["STMTRS", "LEDGERBAL"]`.
+    ///
+    pub(crate) fn find(&self, path: &[&str]) -> Option<&Element> {
+        path.iter()
+            .try_fold(self, |element, name| element.child(name))
+    }
+
+    ///
+    /// The text of the descendant at `path`, if it has any.
+    ///
+    pub(crate) fn text(&self, path: &[&str]) -> Option<&str> {
+        self.find(path)
+            .map(|element| element.text.as_str())
+            .filter(|text| !text.is_empty())
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+///
+/// Close the innermost open element if it holds a value, as SGML leaves those unclosed.
+///
+fn close_value(stack: &mut Vec<Element>) {
+    if stack.len() > 1 && !stack.last().unwrap().text.is_empty() {
+        let element = stack.pop().unwrap();
+        stack.last_mut().unwrap().children.push(element);
+    }
+}
+
+///
+/// Close the element `name`, and any unclosed elements inside it.
+///
+fn close(stack: &mut Vec<Element>, name: &str) -> Result<()> {
+    if !stack.iter().skip(1).any(|element| element.name == name) {
+        return Err(invalid_value(
+            "OFX",
+            format!("</{}> closes no element", name),
+        ));
+    }
+    loop {
+        let element = stack.pop().unwrap();
+        let done = element.name == name;
+        stack.last_mut().unwrap().children.push(element);
+        if done {
+            return Ok(());
+        }
+    }
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
@@ -0,0 +1,604 @@
+/*!
+Write ISO 20022 pain.001 customer credit transfer initiations, the payment files a bank accepts
+for a run of supplier payments.
+
+A [`PaymentRun`] is the set of credit transfers made from one debtor account in one currency.
+Each [`CreditTransfer`] pays a creditor's IBAN, and is identified end-to-end by the id of the
+[`Transaction`] that records the payment, so that the bank's statement entry for it (see
+[`camt`](crate::camt)) can be matched back to the transaction. The transfers of a run are
+grouped into a payment information block for each requested execution date.
+
+The file is written as `pain.001.001.03`, the version accepted for SEPA credit transfers; a run
+in euro is marked with the `SEPA` service level, with charges following it (`SLEV`). Before
+anything is written the run is checked against the constraints of the schema, bundled as
+[`PAIN_001_SCHEMA`]: identifiers and names within their maximum lengths, IBANs and BICs well
+formed, IBAN check digits correct, and amounts positive and within the currency's minor units.
+Remittance information longer than the schema allows is truncated.
+
+# Example
+
+```rust,no_run
+use chrono::NaiveDate;
+use codes_iso_4217::CurrencyCode;
+use rust_decimal::Decimal;
+use sledge_formats::pain::{write_pain001_file, CreditTransfer, PaymentParty, PaymentRun};
+use sledge_model::journal::TransactionId;
+
+let debtor = PaymentParty::new("Example GmbH", "DE89370400440532013000")
+    .with_bic("COBADEFFXXX");
+let run = PaymentRun::new("RUN-2022-12-01", debtor, CurrencyCode::EUR).with_transfer(
+    CreditTransfer::new(
+        TransactionId::from("pay-118"),
+        PaymentParty::new("Paper Supplies BV", "NL91ABNA0417164300"),
+        Decimal::new(124950, 2),
+        NaiveDate::from_ymd_opt(2022, 12, 2).unwrap(),
+    )
+    .with_remittance("Invoice 2022-118"),
+);
+write_pain001_file("payments.xml", &run).unwrap();
+```
+
+*/
+
+use crate::error::{invalid_value, Result};
+use crate::xml::escape;
+use chrono::{DateTime, NaiveDate, Utc};
+use codes_iso_4217::CurrencyCode;
+use rust_decimal::Decimal;
+use sledge_model::commodity::CommodityId;
+use sledge_model::journal::{Transaction, TransactionId};
+use sledge_model::ledger::AccountId;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use tracing::info;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// The published XML schema of the `pain.001.001.03` message, for validating files before they
+/// are uploaded.
+///
+pub const PAIN_001_SCHEMA: &str = include_str!("../schemas/pain.001.001.03.xsd");
+
+///
+/// The XML namespace of the `pain.001.001.03` message.
+///
+pub const PAIN_001_NAMESPACE: &str = "urn:iso:std:iso:20022:tech:xsd:pain.001.001.03";
+
+///
+/// The holder of an account, and the account, that a payment is made from or to.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PaymentParty {
+    name: String,
+    iban: String,
+    bic: Option<String>,
+}
+
+///
+/// A single payment to a creditor, identified end-to-end by the transaction that records it.
+///
+#[derive(Clone, Debug)]
+pub struct CreditTransfer {
+    transaction: TransactionId,
+    creditor: PaymentParty,
+    amount: Decimal,
+    execution_date: NaiveDate,
+    remittance: Option<String>,
+}
+
+///
+/// The credit transfers made from one debtor account, in one currency, and sent to the bank as
+/// a single message.
+///
+#[derive(Clone, Debug)]
+pub struct PaymentRun {
+    message_id: String,
+    created: DateTime<Utc>,
+    debtor: PaymentParty,
+    currency: CurrencyCode,
+    batch_booking: Option<bool>,
+    transfers: Vec<CreditTransfer>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+pub fn write_pain001_file<P: AsRef<Path>>(path: P, run: &PaymentRun) -> Result<()> {
+    info!("Writing ISO 20022 credit transfer file {:?}", path.as_ref());
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_pain001(&mut writer, run)?;
+    writer.flush()?;
+    Ok(())
+}
+
+///
+/// Write the run as a `pain.001.001.03` message, after checking it against the schema; nothing
+/// is written if the check fails.
+///
+pub fn write_pain001<W: Write>(writer: &mut W, run: &PaymentRun) -> Result<()> {
+    run.validate()?;
+    let mut dates: BTreeMap<NaiveDate, Vec<&CreditTransfer>> = Default::default();
+    for transfer in &run.transfers {
+        dates
+            .entry(transfer.execution_date)
+            .or_default()
+            .push(transfer);
+    }
+    let currency = run.currency.alpha_code();
+    let sepa = run.currency == CurrencyCode::EUR;
+
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(writer, r#"<Document xmlns="{}">"#, PAIN_001_NAMESPACE)?;
+    writeln!(writer, "  <CstmrCdtTrfInitn>")?;
+    writeln!(writer, "    <GrpHdr>")?;
+    writeln!(writer, "      <MsgId>{}</MsgId>", escape(&run.message_id))?;
+    writeln!(
+        writer,
+        "      <CreDtTm>{}</CreDtTm>",
+        run.created.format("%Y-%m-%dT%H:%M:%S")
+    )?;
+    writeln!(writer, "      <NbOfTxs>{}</NbOfTxs>", run.transfers.len())?;
+    writeln!(writer, "      <CtrlSum>{}</CtrlSum>", run.control_sum())?;
+    writeln!(
+        writer,
+        "      <InitgPty><Nm>{}</Nm></InitgPty>",
+        escape(&run.debtor.name)
+    )?;
+    writeln!(writer, "    </GrpHdr>")?;
+
+    for (n, (date, transfers)) in dates.iter().enumerate() {
+        let control_sum: Decimal = transfers.iter().map(|transfer| transfer.amount).sum();
+        writeln!(writer, "    <PmtInf>")?;
+        writeln!(
+            writer,
+            "      <PmtInfId>{}</PmtInfId>",
+            escape(&run.payment_information_id(n))
+        )?;
+        writeln!(writer, "      <PmtMtd>TRF</PmtMtd>")?;
+        if let Some(batch_booking) = run.batch_booking {
+            writeln!(writer, "      <BtchBookg>{}</BtchBookg>", batch_booking)?;
+        }
+        writeln!(writer, "      <NbOfTxs>{}</NbOfTxs>", transfers.len())?;
+        writeln!(writer, "      <CtrlSum>{}</CtrlSum>", control_sum)?;
+        if sepa {
+            writeln!(
+                writer,
+                "      <PmtTpInf><SvcLvl><Cd>SEPA</Cd></SvcLvl></PmtTpInf>"
+            )?;
+        }
+        writeln!(
+            writer,
+            "      <ReqdExctnDt>{}</ReqdExctnDt>",
+            date.format("%Y-%m-%d")
+        )?;
+        writeln!(
+            writer,
+            "      <Dbtr><Nm>{}</Nm></Dbtr>",
+            escape(&run.debtor.name)
+        )?;
+        writeln!(
+            writer,
+            "      <DbtrAcct><Id><IBAN>{}</IBAN></Id><Ccy>{}</Ccy></DbtrAcct>",
+            run.debtor.iban, currency
+        )?;
+        match &run.debtor.bic {
+            Some(bic) => writeln!(
+                writer,
+                "      <DbtrAgt><FinInstnId><BIC>{}</BIC></FinInstnId></DbtrAgt>",
+                bic
+            )?,
+            None => writeln!(
+                writer,
+                "      <DbtrAgt><FinInstnId><Othr><Id>{}</Id></Othr></FinInstnId></DbtrAgt>",
+                NOT_PROVIDED
+            )?,
+        }
+        if sepa {
+            writeln!(writer, "      <ChrgBr>SLEV</ChrgBr>")?;
+        }
+        for transfer in transfers {
+            write_transfer(writer, transfer, currency)?;
+        }
+        writeln!(writer, "    </PmtInf>")?;
+    }
+
+    writeln!(writer, "  </CstmrCdtTrfInitn>")?;
+    writeln!(writer, "</Document>")?;
+    Ok(())
+}
+
+///
+/// Returns `true` if `iban` is formed as the schema requires and its check digits are
+/// correct.
+///
+pub fn is_valid_iban(iban: &str) -> bool {
+    // [A-Z]{2}[0-9]{2}[a-zA-Z0-9]{1,30}
+    let bytes = iban.as_bytes();
+    if !(5..=34).contains(&bytes.len())
+        || !bytes[..2].iter().all(u8::is_ascii_uppercase)
+        || !bytes[2..4].iter().all(u8::is_ascii_digit)
+        || !bytes[4..].iter().all(u8::is_ascii_alphanumeric)
+    {
+        return false;
+    }
+    let (country, rest) = iban.split_at(4);
+    let remainder = rest
+        .chars()
+        .chain(country.chars())
+        .map(|c| c.to_digit(36).unwrap())
+        .fold(0, |remainder, digit| {
+            if digit < 10 {
+                (remainder * 10 + digit) % 97
+            } else {
+                (remainder * 100 + digit) % 97
+            }
+        });
+    remainder == 1
+}
+
+///
+/// Returns `true` if `bic` is formed as the schema requires.
+///
+pub fn is_valid_bic(bic: &str) -> bool {
+    // [A-Z]{6}[A-Z2-9][A-NP-Z0-9]([A-Z0-9]{3})?
+    let bytes = bic.as_bytes();
+    (bytes.len() == 8 || bytes.len() == 11)
+        && bytes[..6].iter().all(u8::is_ascii_uppercase)
+        && matches!(bytes[6], b'A'..=b'Z' | b'2'..=b'9')
+        && matches!(bytes[7], b'A'..=b'N' | b'P'..=b'Z' | b'0'..=b'9')
+        && bytes[8..]
+            .iter()
+            .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit())
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+const NOT_PROVIDED: &str = "NOTPROVIDED";
+
+const MAX_TEXT: usize = 35;
+
+const MAX_NAME: usize = 140;
+
+const MAX_REMITTANCE: usize = 140;
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl PaymentParty {
+    ///
+    /// The IBAN may be given in its printed form, in groups of four separated by spaces.
+    ///
+    pub fn new<S1, S2>(name: S1, iban: S2) -> Self
+    where
+        S1: Into<String>,
+        S2: AsRef<str>,
+    {
+        Self {
+            name: name.into(),
+            iban: compact(iban.as_ref()),
+            bic: None,
+        }
+    }
+
+    pub fn with_bic<S: AsRef<str>>(self, bic: S) -> Self {
+        Self {
+            bic: Some(compact(bic.as_ref())),
+            ..self
+        }
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn iban(&self) -> &String {
+        &self.iban
+    }
+
+    pub fn bic(&self) -> Option<&String> {
+        self.bic.as_ref()
+    }
+
+    fn validate(&self, role: &str) -> Result<()> {
+        if self.name.is_empty() || self.name.chars().count() > MAX_NAME {
+            return Err(invalid_value(format!("{} name", role), &self.name));
+        }
+        if !is_valid_iban(&self.iban) {
+            return Err(invalid_value(format!("{} IBAN", role), &self.iban));
+        }
+        match &self.bic {
+            Some(bic) if !is_valid_bic(bic) => Err(invalid_value(format!("{} BIC", role), bic)),
+            _ => Ok(()),
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl CreditTransfer {
+    pub fn new(
+        transaction: TransactionId,
+        creditor: PaymentParty,
+        amount: Decimal,
+        execution_date: NaiveDate,
+    ) -> Self {
+        Self {
+            transaction,
+            creditor,
+            amount,
+            execution_date,
+            remittance: None,
+        }
+    }
+
+    ///
+    /// The unstructured remittance information, such as the supplier's invoice number, shown to
+    /// the creditor.
+    ///
+    pub fn with_remittance<S: Into<String>>(self, remittance: S) -> Self {
+        Self {
+            remittance: Some(remittance.into()),
+            ..self
+        }
+    }
+
+    ///
+    /// The transaction recording the payment, whose id is the end-to-end identifier.
+    ///
+    pub fn transaction(&self) -> &TransactionId {
+        &self.transaction
+    }
+
+    pub fn creditor(&self) -> &PaymentParty {
+        &self.creditor
+    }
+
+    pub fn amount(&self) -> Decimal {
+        self.amount
+    }
+
+    pub fn execution_date(&self) -> NaiveDate {
+        self.execution_date
+    }
+
+    pub fn remittance(&self) -> Option<&String> {
+        self.remittance.as_ref()
+    }
+
+    fn validate(&self, currency: CurrencyCode) -> Result<()> {
+        let id = self.transaction.as_str();
+        if id.is_empty() || id.chars().count() > MAX_TEXT {
+            return Err(invalid_value("EndToEndId", id));
+        }
+        let minor_units = currency.monetary_units().unwrap_or(2) as u32;
+        if self.amount <= Decimal::ZERO || self.amount.normalize().scale() > minor_units {
+            return Err(invalid_value(
+                format!("amount of {}", id),
+                self.amount.to_string(),
+            ));
+        }
+        self.creditor.validate("creditor")
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl PaymentRun {
+    ///
+    /// The message id must be unique for the debtor's bank, which rejects a file it has seen
+    /// before.
+    ///
+    pub fn new<S: Into<String>>(
+        message_id: S,
+        debtor: PaymentParty,
+        currency: CurrencyCode,
+    ) -> Self {
+        Self {
+            message_id: message_id.into(),
+            created: Utc::now(),
+            debtor,
+            currency,
+            batch_booking: None,
+            transfers: Default::default(),
+        }
+    }
+
+    pub fn with_created(self, created: DateTime<Utc>) -> Self {
+        Self { created, ..self }
+    }
+
+    ///
+    /// Whether the bank should book the transfers of each execution date as a single debit,
+    /// rather than one for each transfer; if not set the bank's default applies.
+    ///
+    pub fn with_batch_booking(self, batch_booking: bool) -> Self {
+        Self {
+            batch_booking: Some(batch_booking),
+            ..self
+        }
+    }
+
+    pub fn with_transfer(mut self, transfer: CreditTransfer) -> Self {
+        self.add_transfer(transfer);
+        self
+    }
+
+    pub fn message_id(&self) -> &String {
+        &self.message_id
+    }
+
+    pub fn created(&self) -> DateTime<Utc> {
+        self.created
+    }
+
+    pub fn debtor(&self) -> &PaymentParty {
+        &self.debtor
+    }
+
+    pub fn currency(&self) -> CurrencyCode {
+        self.currency
+    }
+
+    pub fn batch_booking(&self) -> Option<bool> {
+        self.batch_booking
+    }
+
+    pub fn transfers(&self) -> impl Iterator<Item = &CreditTransfer> {
+        self.transfers.iter()
+    }
+
+    ///
+    /// The sum of the amounts of all transfers.
+    ///
+    pub fn control_sum(&self) -> Decimal {
+        self.transfers.iter().map(|transfer| transfer.amount).sum()
+    }
+
+    pub fn add_transfer(&mut self, transfer: CreditTransfer) {
+        self.transfers.push(transfer);
+    }
+
+    ///
+    /// Add the payment recorded by `transaction` to the run; the amount is the run's currency
+    /// credited to the `from` account, the paying bank account, and the execution date the date
+    /// the transaction is posted. The transaction's reference and description become the
+    /// remittance information.
+    ///
+    pub fn add_transaction(
+        &mut self,
+        transaction: &Transaction,
+        from: &AccountId,
+        creditor: PaymentParty,
+    ) -> Result<()> {
+        let commodity = CommodityId::Currency(self.currency);
+        let amount: Decimal = -transaction
+            .splits()
+            .filter(|split| split.account() == from && split.quantity().commodity() == &commodity)
+            .map(|split| split.quantity().quantity())
+            .sum::<Decimal>();
+        if amount <= Decimal::ZERO {
+            return Err(invalid_value(
+                format!("payment from {}", from),
+                transaction.id().to_string(),
+            ));
+        }
+        let remittance: Vec<&str> = transaction
+            .reference()
+            .into_iter()
+            .chain(transaction.description())
+            .map(String::as_str)
+            .collect();
+        let mut transfer = CreditTransfer::new(
+            transaction.id().clone(),
+            creditor,
+            amount,
+            transaction.posted().date_naive(),
+        );
+        if !remittance.is_empty() {
+            transfer = transfer.with_remittance(remittance.join(" "));
+        }
+        self.add_transfer(transfer);
+        Ok(())
+    }
+
+    ///
+    /// Check the run against the constraints of the schema, returning the first value that
+    /// does not meet them.
+    ///
+    pub fn validate(&self) -> Result<()> {
+        if self.message_id.is_empty() || self.message_id.chars().count() > MAX_TEXT {
+            return Err(invalid_value("MsgId", &self.message_id));
+        }
+        if self.transfers.is_empty() {
+            return Err(invalid_value("NbOfTxs", "0"));
+        }
+        let id = self.payment_information_id(self.transfers.len());
+        if id.chars().count() > MAX_TEXT {
+            return Err(invalid_value("PmtInfId", id));
+        }
+        self.debtor.validate("debtor")?;
+        for transfer in &self.transfers {
+            transfer.validate(self.currency)?;
+        }
+        Ok(())
+    }
+
+    fn payment_information_id(&self, index: usize) -> String {
+        format!("{}-{}", self.message_id, index + 1)
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+fn write_transfer<W: Write>(
+    writer: &mut W,
+    transfer: &CreditTransfer,
+    currency: &str,
+) -> Result<()> {
+    writeln!(writer, "      <CdtTrfTxInf>")?;
+    writeln!(
+        writer,
+        "        <PmtId><EndToEndId>{}</EndToEndId></PmtId>",
+        escape(transfer.transaction.as_str())
+    )?;
+    writeln!(
+        writer,
+        r#"        <Amt><InstdAmt Ccy="{}">{}</InstdAmt></Amt>"#,
+        currency, transfer.amount
+    )?;
+    if let Some(bic) = &transfer.creditor.bic {
+        writeln!(
+            writer,
+            "        <CdtrAgt><FinInstnId><BIC>{}</BIC></FinInstnId></CdtrAgt>",
+            bic
+        )?;
+    }
+    writeln!(
+        writer,
+        "        <Cdtr><Nm>{}</Nm></Cdtr>",
+        escape(&transfer.creditor.name)
+    )?;
+    writeln!(
+        writer,
+        "        <CdtrAcct><Id><IBAN>{}</IBAN></Id></CdtrAcct>",
+        transfer.creditor.iban
+    )?;
+    if let Some(remittance) = transfer
+        .remittance
+        .as_deref()
+        .map(str::trim)
+        .filter(|remittance| !remittance.is_empty())
+    {
+        let remittance: String = remittance.chars().take(MAX_REMITTANCE).collect();
+        writeln!(
+            writer,
+            "        <RmtInf><Ustrd>{}</Ustrd></RmtInf>",
+            escape(&remittance)
+        )?;
+    }
+    writeln!(writer, "      </CdtTrfTxInf>")?;
+    Ok(())
+}
+
+fn compact(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_uppercase()
+}
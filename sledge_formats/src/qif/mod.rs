@@ -0,0 +1,174 @@
+/*!
+Import the Quicken Interchange Format, QIF, still exported by many older tools and banks.
+
+A QIF file is a sequence of sections, each starting with a header such as `!Type:Bank`, holding
+records of one field per line that end with `^`. The bank, cash, credit card, other asset and
+other liability, and investment registers are read, as are the account list (`!Account`), the
+category list (`!Type:Cat`), the security list (`!Type:Security`), and security prices
+(`!Type:Prices`). Other sections, such as memorized transactions and classes, are listed in the
+report.
+
+Each QIF account becomes an account named for it under `Assets` or, for credit cards and other
+liabilities, `Liabilities`; registers read without an account list go to an account named for
+their type, such as `Assets:Bank`. Categories become accounts under `Income` or `Expenses`,
+as the category list has them or, for those not in it, as their first amount is income or an
+expense; any category can be mapped to another account with [`QifOptions::with_category`].
+Investment income without a category uses one named for its kind, such as `Dividends` or
+`Interest`. Transactions with no category are posted to `Expenses:Uncategorized`, and the
+opening balance, a transfer to the account itself, to `Equity:Opening Balances`.
+
+Split lines, the `S`, `E`, and `$` fields, become a split each, and a transaction's class,
+written after a `/` in its category, is kept as the `class` metadata value. Transfers between
+accounts appear in the registers of both; the second is recognized as the counterpart of the
+first and not imported again. Cleared and reconciled transactions are recorded as reconciled.
+
+QIF dates are written in the order of the exporting tool's locale, so the order is set with
+[`QifOptions::with_date_format`]. Two-digit years after an apostrophe, as in `12/31'22`, are in
+this century; other two-digit years are in this century if below 70, and the last otherwise.
+
+*/
+
+use codes_iso_4217::CurrencyCode;
+use sledge_model::commodity::CommodityId;
+use sledge_model::ledger::{AccountId, LedgerKind};
+use std::collections::HashMap;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// The order of the day, month, and year in QIF dates.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum QifDateFormat {
+    /// As in `12/31/2022`, written by US versions of Quicken.
+    #[default]
+    MonthDayYear,
+    /// As in `31/12/2022` or `31.12.2022`.
+    DayMonthYear,
+    /// As in `2022-12-31`.
+    YearMonthDay,
+}
+
+#[derive(Clone, Debug)]
+pub struct QifOptions {
+    ledger_kind: LedgerKind,
+    journal_name: String,
+    currency: CurrencyCode,
+    date_format: QifDateFormat,
+    categories: HashMap<String, AccountId>,
+    securities: HashMap<String, CommodityId>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl Default for QifOptions {
+    fn default() -> Self {
+        Self {
+            ledger_kind: LedgerKind::General,
+            journal_name: "general".to_string(),
+            currency: CurrencyCode::USD,
+            date_format: Default::default(),
+            categories: Default::default(),
+            securities: Default::default(),
+        }
+    }
+}
+
+impl QifOptions {
+    pub fn with_ledger_kind(self, ledger_kind: LedgerKind) -> Self {
+        Self {
+            ledger_kind,
+            ..self
+        }
+    }
+
+    pub fn with_journal_name<S: Into<String>>(self, journal_name: S) -> Self {
+        Self {
+            journal_name: journal_name.into(),
+            ..self
+        }
+    }
+
+    ///
+    /// The currency of the imported ledger, journal, and every amount in the file, as QIF
+    /// does not record currencies.
+    ///
+    pub fn with_currency(self, currency: CurrencyCode) -> Self {
+        Self { currency, ..self }
+    }
+
+    pub fn with_date_format(self, date_format: QifDateFormat) -> Self {
+        Self {
+            date_format,
+            ..self
+        }
+    }
+
+    ///
+    /// Post amounts in the QIF category `category`, such as `"Auto:Fuel"`, to `account`.
+    ///
+    pub fn with_category<S: Into<String>>(mut self, category: S, account: AccountId) -> Self {
+        let _ = self.categories.insert(category.into(), account);
+        self
+    }
+
+    ///
+    /// Import the security with the name, or symbol, `name` as `commodity`.
+    ///
+    pub fn with_security<S: Into<String>>(mut self, name: S, commodity: CommodityId) -> Self {
+        let _ = self.securities.insert(name.into(), commodity);
+        self
+    }
+
+    pub fn ledger_kind(&self) -> &LedgerKind {
+        &self.ledger_kind
+    }
+
+    pub fn journal_name(&self) -> &String {
+        &self.journal_name
+    }
+
+    pub fn currency(&self) -> CurrencyCode {
+        self.currency
+    }
+
+    pub fn date_format(&self) -> QifDateFormat {
+        self.date_format
+    }
+
+    pub fn category(&self, category: &str) -> Option<&AccountId> {
+        self.categories.get(category)
+    }
+
+    pub fn security(&self, name: &str) -> Option<&CommodityId> {
+        self.securities.get(name)
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
+
+#[doc(hidden)]
+mod read;
+pub use read::{read_qif, read_qif_file};
@@ -0,0 +1,946 @@
+/*!
+Read QIF files.
+
+*/
+
+use super::{QifDateFormat, QifOptions};
+use crate::error::{syntax_error, Result};
+use crate::{ImportReport, Imported};
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use sledge_model::commodity::{
+    CommodityId, InternationalSecuritiesId, Quantity, RateRecord, RatedQuantity,
+};
+use sledge_model::journal::{Journal, Reconciled, Split, Transaction, TransactionId};
+use sledge_model::ledger::{Account, AccountId, AccountKind, Ledger};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::str::FromStr;
+use tracing::info;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+pub fn read_qif_file<P: AsRef<Path>>(path: P, options: &QifOptions) -> Result<Imported> {
+    info!("Reading QIF file {:?}", path.as_ref());
+    read_qif(BufReader::new(File::open(path)?), options)
+}
+
+pub fn read_qif<R: BufRead>(reader: R, options: &QifOptions) -> Result<Imported> {
+    let mut parser = Parser::new(options);
+    for (index, line) in reader.split(b'\n').enumerate() {
+        // Files from older tools are often Windows-1252, which is close enough to Latin-1 for
+        // the names and memos kept here.
+        let line = match String::from_utf8(line?) {
+            Ok(line) => line,
+            Err(e) => e.into_bytes().into_iter().map(char::from).collect(),
+        };
+        parser.line(index + 1, line.trim())?;
+    }
+    parser.end_record()?;
+    parser.into_imported()
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Debug)]
+struct Parser<'a> {
+    options: &'a QifOptions,
+    report: ImportReport,
+    section: Section,
+    current: Option<String>,
+    fields: Vec<(char, String)>,
+    first_line: usize,
+    accounts: BTreeMap<String, AccountKind>,
+    categories: HashMap<String, AccountKind>,
+    securities: HashMap<String, String>,
+    records: Vec<Record>,
+    prices: Vec<RateRecord>,
+    ledger_accounts: BTreeMap<String, (AccountKind, String)>,
+    transfers: Vec<Transfer>,
+    transactions: Vec<Transaction>,
+    reconciled: Vec<Reconciled>,
+    sequence: HashMap<NaiveDate, usize>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Section {
+    None,
+    Accounts,
+    Categories,
+    Securities,
+    Prices,
+    Register,
+    Investments,
+    Skip,
+}
+
+#[derive(Debug)]
+struct Record {
+    line: usize,
+    account: String,
+    investment: bool,
+    fields: Vec<(char, String)>,
+}
+
+///
+/// Where an amount in a register is posted; a QIF account, for transfers, or a ledger account.
+///
+#[derive(Debug)]
+enum Target {
+    Transfer(String),
+    Account(AccountId),
+}
+
+#[derive(Debug)]
+struct DraftSplit {
+    account: AccountId,
+    transfer: Option<String>,
+    quantity: Quantity,
+    exchanged_from: Option<RatedQuantity>,
+    description: Option<String>,
+}
+
+///
+/// A transfer imported from the register of `from`, whose counterpart in the register of `to`
+/// is not imported again.
+///
+#[derive(Debug)]
+struct Transfer {
+    date: NaiveDate,
+    from: String,
+    to: String,
+    amount: Decimal,
+    id: TransactionId,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl<'a> Parser<'a> {
+    fn new(options: &'a QifOptions) -> Self {
+        Self {
+            options,
+            report: Default::default(),
+            section: Section::None,
+            current: None,
+            fields: Default::default(),
+            first_line: 0,
+            accounts: Default::default(),
+            categories: Default::default(),
+            securities: Default::default(),
+            records: Default::default(),
+            prices: Default::default(),
+            ledger_accounts: Default::default(),
+            transfers: Default::default(),
+            transactions: Default::default(),
+            reconciled: Default::default(),
+            sequence: Default::default(),
+        }
+    }
+
+    fn line(&mut self, number: usize, line: &str) -> Result<()> {
+        if line.is_empty() {
+            return Ok(());
+        }
+        if let Some(header) = line.strip_prefix('!') {
+            self.end_record()?;
+            self.header(number, header.trim());
+        } else if line.starts_with('^') {
+            self.end_record()?;
+        } else {
+            if self.fields.is_empty() {
+                self.first_line = number;
+            }
+            if self.section == Section::Prices {
+                self.fields.push((' ', line.to_string()));
+            } else {
+                let mut chars = line.chars();
+                // Lines are never empty here.
+                let code = chars.next().unwrap();
+                self.fields.push((code, chars.as_str().trim().to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    fn header(&mut self, number: usize, header: &str) {
+        let (name, kind, section) = match header.to_lowercase().as_str() {
+            "account" => {
+                self.section = Section::Accounts;
+                return;
+            }
+            "option:autoswitch" => {
+                self.section = Section::None;
+                return;
+            }
+            "clear:autoswitch" => {
+                // Registers after the account list name their own account.
+                self.section = Section::None;
+                self.current = None;
+                return;
+            }
+            "type:cat" => {
+                self.section = Section::Categories;
+                return;
+            }
+            "type:security" => {
+                self.section = Section::Securities;
+                return;
+            }
+            "type:prices" => {
+                self.section = Section::Prices;
+                return;
+            }
+            "type:bank" => ("Bank", AccountKind::Asset, Section::Register),
+            "type:cash" => ("Cash", AccountKind::Asset, Section::Register),
+            "type:ccard" => ("Credit Card", AccountKind::Liability, Section::Register),
+            "type:oth a" => ("Other Asset", AccountKind::Asset, Section::Register),
+            "type:oth l" => ("Other Liability", AccountKind::Liability, Section::Register),
+            "type:invst" | "type:port" => ("Investments", AccountKind::Asset, Section::Investments),
+            _ => {
+                self.report.add(
+                    "section",
+                    format!("line {}", number),
+                    format!("the {:?} section is not imported", header),
+                );
+                self.section = Section::Skip;
+                return;
+            }
+        };
+        self.section = section;
+        // A register read without an account list goes to an account named for its type.
+        if self.current.is_none() {
+            let _ = self.accounts.entry(name.to_string()).or_insert(kind);
+            self.current = Some(name.to_string());
+        }
+    }
+
+    fn end_record(&mut self) -> Result<()> {
+        if self.fields.is_empty() {
+            return Ok(());
+        }
+        let fields = std::mem::take(&mut self.fields);
+        let line = self.first_line;
+        match self.section {
+            Section::Accounts => {
+                let Some(name) = field(&fields, 'N') else {
+                    self.report.add(
+                        "account",
+                        format!("line {}", line),
+                        "the account has no name",
+                    );
+                    return Ok(());
+                };
+                let kind = match field(&fields, 'T').map(str::to_lowercase).as_deref() {
+                    Some("bank" | "cash" | "oth a" | "invst" | "port" | "mutual")
+                    | Some("401(k)/403(b)") => AccountKind::Asset,
+                    Some("ccard" | "oth l") => AccountKind::Liability,
+                    kind => {
+                        self.report.add(
+                            "account",
+                            name,
+                            format!(
+                                "the account type {:?} is not known, imported as an asset",
+                                kind
+                            ),
+                        );
+                        AccountKind::Asset
+                    }
+                };
+                let _ = self.accounts.insert(name.to_string(), kind);
+                self.current = Some(name.to_string());
+            }
+            Section::Categories => {
+                if let Some(name) = field(&fields, 'N') {
+                    let kind = if fields.iter().any(|(code, _)| *code == 'I') {
+                        AccountKind::Income
+                    } else {
+                        AccountKind::Expense
+                    };
+                    let _ = self.categories.insert(name.to_string(), kind);
+                }
+            }
+            Section::Securities => {
+                if let (Some(name), Some(symbol)) = (field(&fields, 'N'), field(&fields, 'S')) {
+                    let _ = self.securities.insert(name.to_string(), symbol.to_string());
+                }
+            }
+            Section::Prices => {
+                for (_, price) in &fields {
+                    self.price(line, price)?;
+                }
+            }
+            Section::Register | Section::Investments => self.records.push(Record {
+                line,
+                // Register sections always have an account.
+                account: self.current.clone().unwrap_or_default(),
+                investment: self.section == Section::Investments,
+                fields,
+            }),
+            Section::None => self.report.add(
+                "record",
+                format!("line {}", line),
+                "the record is not in a section",
+            ),
+            Section::Skip => {}
+        }
+        Ok(())
+    }
+
+    ///
+    /// A price line, such as `"AAPL",145.50,"10/31'22"`.
+    ///
+    fn price(&mut self, number: usize, line: &str) -> Result<()> {
+        let parts: Vec<&str> = line
+            .split(',')
+            .map(|p| p.trim().trim_matches('"'))
+            .collect();
+        let [symbol, price, date] = parts[..] else {
+            return Err(syntax_error(number, line));
+        };
+        let date = parse_date(date, self.options.date_format())
+            .ok_or_else(|| syntax_error(number, line))?;
+        let Some(price) = parse_amount(price) else {
+            self.report.add(
+                "price",
+                format!("line {}", number),
+                format!("the price {:?} is not a decimal number", price),
+            );
+            return Ok(());
+        };
+        match self.security(symbol) {
+            Some(security) => self.prices.push(RateRecord::new(
+                security,
+                CommodityId::Currency(self.options.currency()),
+                price,
+                midnight(date),
+                "qif",
+            )),
+            None => self.unmapped_security(symbol),
+        }
+        Ok(())
+    }
+
+    fn into_imported(mut self) -> Result<Imported> {
+        for (name, kind) in self.accounts.clone() {
+            self.use_account(&account_name(&name, kind), kind, Some(&name));
+        }
+        for record in std::mem::take(&mut self.records) {
+            self.record(record)?;
+        }
+
+        let currency = self.options.currency();
+        let mut ledger = Ledger::new(
+            self.options.ledger_kind().clone(),
+            "Imported QIF file",
+            currency,
+        );
+        for (name, (kind, description)) in &self.ledger_accounts {
+            let account = Account::new(
+                AccountId::new(name.clone()),
+                *kind,
+                description.clone(),
+                CommodityId::Currency(currency),
+            );
+            ledger.add_account(match name.rsplit_once(':') {
+                Some((parent, _)) => account.with_parent(AccountId::new(parent)),
+                None => account,
+            });
+        }
+
+        let mut imported = Imported::default();
+        let mut journal = Journal::new(self.options.journal_name().clone(), currency);
+        // Sorting is stable, keeping file order within a day.
+        self.transactions.sort_by_key(|t| t.posted());
+        for transaction in std::mem::take(&mut self.transactions) {
+            if let Err(e) = journal.add_transaction(transaction) {
+                self.report.add("transaction", "", e.to_string());
+            }
+        }
+
+        imported.contents.ledgers.push(ledger);
+        imported.contents.journals.push(journal);
+        imported.contents.prices = self.prices;
+        imported.contents.reconciliations = self.reconciled;
+        imported.report = self.report;
+        Ok(imported)
+    }
+
+    fn record(&mut self, record: Record) -> Result<()> {
+        let fields = &record.fields;
+        let line = format!("line {}", record.line);
+        let Some(date) = field(fields, 'D') else {
+            self.report
+                .add("transaction", line, "the transaction has no date");
+            return Ok(());
+        };
+        let date = parse_date(date, self.options.date_format())
+            .ok_or_else(|| syntax_error(record.line, date))?;
+        let amount = match field(fields, 'T').or_else(|| field(fields, 'U')) {
+            Some(amount) => {
+                parse_amount(amount).ok_or_else(|| syntax_error(record.line, amount))?
+            }
+            None => Decimal::ZERO,
+        };
+
+        let own = account_name(&record.account, self.accounts[&record.account]);
+        let mut classes: Vec<String> = Default::default();
+        let splits = if record.investment {
+            match self.investment_splits(&record, &own, amount, &mut classes)? {
+                Some(splits) => splits,
+                None => return Ok(()),
+            }
+        } else {
+            self.register_splits(&record, &own, amount, &mut classes)?
+        };
+        self.add_transaction(&record, date, own, splits, classes)
+    }
+
+    ///
+    /// The splits of a bank, cash, credit card, or other asset or liability transaction; the
+    /// amount in the account, and the opposite amount in the category or each split line.
+    ///
+    fn register_splits(
+        &mut self,
+        record: &Record,
+        own: &str,
+        amount: Decimal,
+        classes: &mut Vec<String>,
+    ) -> Result<Vec<DraftSplit>> {
+        let fields = &record.fields;
+        let mut splits = vec![self.split(
+            Target::Account(AccountId::new(own)),
+            amount,
+            field(fields, 'M'),
+        )];
+
+        let mut lines: Vec<(&str, Option<&str>, Option<&str>)> = Default::default();
+        for (code, value) in fields {
+            match code {
+                'S' => lines.push((value, None, None)),
+                'E' | '$' if lines.is_empty() => lines.push(("", None, None)),
+                _ => {}
+            }
+            if let Some(last) = lines.last_mut() {
+                match code {
+                    'E' => last.1 = Some(value),
+                    '$' => last.2 = Some(value),
+                    _ => {}
+                }
+            }
+        }
+
+        if lines.is_empty() {
+            let target = self.target(
+                &record.account,
+                field(fields, 'L').unwrap_or_default(),
+                amount.is_sign_positive(),
+                classes,
+            );
+            splits.push(self.split(target, -amount, None));
+        } else {
+            let mut rest = amount;
+            for (category, memo, value) in lines {
+                let value = match value {
+                    Some(value) => {
+                        parse_amount(value).ok_or_else(|| syntax_error(record.line, value))?
+                    }
+                    None => Decimal::ZERO,
+                };
+                rest -= value;
+                let target =
+                    self.target(&record.account, category, value.is_sign_positive(), classes);
+                splits.push(self.split(target, -value, memo));
+            }
+            // Quicken leaves any amount not in a split line uncategorized.
+            if !rest.is_zero() {
+                let target = self.target(&record.account, "", rest.is_sign_positive(), classes);
+                splits.push(self.split(target, -rest, None));
+            }
+        }
+        Ok(splits)
+    }
+
+    ///
+    /// The splits of an investment transaction, or none if its action or security is not
+    /// supported.
+    ///
+    fn investment_splits(
+        &mut self,
+        record: &Record,
+        own: &str,
+        amount: Decimal,
+        classes: &mut Vec<String>,
+    ) -> Result<Option<Vec<DraftSplit>>> {
+        let fields = &record.fields;
+        let line = format!("line {}", record.line);
+        let action = field(fields, 'N').unwrap_or("cash").to_lowercase();
+        let total = amount.abs();
+        let transferred = match field(fields, '$') {
+            Some(value) => parse_amount(value)
+                .ok_or_else(|| syntax_error(record.line, value))?
+                .abs(),
+            None => total,
+        };
+        let memo = field(fields, 'M');
+        let other = field(fields, 'L').unwrap_or_default();
+        let own_account = || Target::Account(AccountId::new(own));
+
+        let (trade, income) = match action.as_str() {
+            "buy" | "buyx" => (Some(Decimal::ONE), None),
+            "sell" | "sellx" => (Some(Decimal::NEGATIVE_ONE), None),
+            "reinvdiv" => (Some(Decimal::ONE), Some("Dividends")),
+            "reinvint" => (Some(Decimal::ONE), Some("Interest")),
+            "reinvlg" => (Some(Decimal::ONE), Some("Capital Gains:Long Term")),
+            "reinvmd" => (Some(Decimal::ONE), Some("Capital Gains:Mid Term")),
+            "reinvsh" => (Some(Decimal::ONE), Some("Capital Gains:Short Term")),
+            "div" | "divx" => (None, Some("Dividends")),
+            "intinc" | "intincx" => (None, Some("Interest")),
+            "cglong" | "cglongx" => (None, Some("Capital Gains:Long Term")),
+            "cgmid" | "cgmidx" => (None, Some("Capital Gains:Mid Term")),
+            "cgshort" | "cgshortx" => (None, Some("Capital Gains:Short Term")),
+            "miscinc" | "miscincx" => (None, Some("Investment Income")),
+            _ => (None, None),
+        };
+        let transfer_action = action.ends_with('x');
+
+        if let Some(sign) = trade {
+            let Some(name) = field(fields, 'Y') else {
+                self.report
+                    .add("transaction", line, "the trade has no security");
+                return Ok(None);
+            };
+            let Some(security) = self.security(name) else {
+                self.unmapped_security(name);
+                self.report.add(
+                    "transaction",
+                    line,
+                    format!("the security {:?} is unmapped", name),
+                );
+                return Ok(None);
+            };
+            let units = match field(fields, 'Q') {
+                Some(units) => parse_amount(units)
+                    .ok_or_else(|| syntax_error(record.line, units))?
+                    .abs(),
+                None => Decimal::ZERO,
+            };
+            if units.is_zero() {
+                self.report
+                    .add("transaction", line, "the trade has no quantity");
+                return Ok(None);
+            }
+            let units = units * sign;
+            let cost = total * sign;
+            let currency = CommodityId::Currency(self.options.currency());
+            let mut splits = vec![DraftSplit {
+                account: AccountId::new(own),
+                transfer: None,
+                quantity: Quantity::new(security, units),
+                exchanged_from: Some(RatedQuantity::new(
+                    Quantity::new(currency, cost),
+                    units.checked_div(cost).unwrap_or_default(),
+                )),
+                description: memo.map(str::to_string),
+            }];
+            let target = if let Some(category) = income {
+                let category = if other.is_empty() { category } else { other };
+                self.target(&record.account, category, true, classes)
+            } else if transfer_action {
+                self.target(&record.account, other, false, classes)
+            } else {
+                own_account()
+            };
+            let cost = if transfer_action {
+                transferred * sign
+            } else {
+                cost
+            };
+            splits.push(self.split(target, -cost, None));
+            return Ok(Some(splits));
+        }
+
+        let (cash, counter) = if let Some(category) = income {
+            let category = if transfer_action || other.is_empty() {
+                category
+            } else {
+                other
+            };
+            let counter = self.target(&record.account, category, true, classes);
+            if transfer_action {
+                let target = self.target(&record.account, other, true, classes);
+                return Ok(Some(vec![
+                    self.split(target, transferred, memo),
+                    self.split(counter, -transferred, None),
+                ]));
+            }
+            (total, counter)
+        } else {
+            match action.as_str() {
+                "miscexp" | "margint" => {
+                    let category = match (other.is_empty(), action.as_str()) {
+                        (false, _) => other,
+                        (true, "margint") => "Margin Interest",
+                        (true, _) => "Investment Expenses",
+                    };
+                    (
+                        -total,
+                        self.target(&record.account, category, false, classes),
+                    )
+                }
+                "xin" | "contribx" => (
+                    if action == "xin" { total } else { transferred },
+                    self.target(&record.account, other, true, classes),
+                ),
+                "xout" | "withdrwx" => (
+                    if action == "xout" {
+                        -total
+                    } else {
+                        -transferred
+                    },
+                    self.target(&record.account, other, false, classes),
+                ),
+                "cash" => (
+                    amount,
+                    self.target(&record.account, other, amount.is_sign_positive(), classes),
+                ),
+                _ => {
+                    self.report.add(
+                        "transaction",
+                        line,
+                        format!("the {:?} action is not imported", action),
+                    );
+                    return Ok(None);
+                }
+            }
+        };
+        Ok(Some(vec![
+            self.split(own_account(), cash, memo),
+            self.split(counter, -cash, None),
+        ]))
+    }
+
+    fn add_transaction(
+        &mut self,
+        record: &Record,
+        date: NaiveDate,
+        own: String,
+        mut splits: Vec<DraftSplit>,
+        classes: Vec<String>,
+    ) -> Result<()> {
+        let fields = &record.fields;
+        let posted = midnight(date);
+        let cleared = matches!(field(fields, 'C'), Some("*" | "c" | "X" | "R"));
+        let reference = if record.investment {
+            None
+        } else {
+            field(fields, 'N')
+        };
+
+        // A transfer that is the counterpart of one already imported is posted in this account
+        // instead, as the other account's side was imported with it.
+        let mut transfers: Vec<(String, Decimal)> = Default::default();
+        for split in splits.iter_mut() {
+            let Some(other) = split.transfer.take() else {
+                continue;
+            };
+            let amount = split.quantity.quantity();
+            match self.transfers.iter().position(|t| {
+                t.date == date && t.from == other && t.to == record.account && t.amount == -amount
+            }) {
+                Some(index) => {
+                    let transfer = self.transfers.remove(index);
+                    split.account = AccountId::new(own.clone());
+                    if cleared {
+                        self.reconciled.push(Reconciled::new(
+                            transfer.id.clone(),
+                            AccountId::new(own.clone()),
+                            reference.map_or_else(|| transfer.id.to_string(), str::to_string),
+                            posted,
+                        ));
+                    }
+                }
+                None => transfers.push((other, amount)),
+            }
+        }
+        let own_id = AccountId::new(own);
+        if splits.iter().all(|split| {
+            split.account == own_id
+                && split.exchanged_from.is_none()
+                && split.quantity.commodity() == &CommodityId::Currency(self.options.currency())
+        }) && splits
+            .iter()
+            .map(|split| split.quantity.quantity())
+            .sum::<Decimal>()
+            .is_zero()
+        {
+            return Ok(());
+        }
+
+        let index = self.sequence.entry(date).or_default();
+        *index += 1;
+        let id = TransactionId::new(format!("{}-{:03}", date.format("%Y%m%d"), index));
+        for (other, amount) in transfers {
+            self.transfers.push(Transfer {
+                date,
+                from: record.account.clone(),
+                to: other,
+                amount,
+                id: id.clone(),
+            });
+        }
+
+        // Imports use the posting date as the entry date, so that they do not differ.
+        let mut transaction = Transaction::new(id.clone(), posted).with_entered(posted);
+        let description = field(fields, 'P').map(str::to_string).or_else(|| {
+            record.investment.then(|| {
+                [field(fields, 'N'), field(fields, 'Y')]
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+        });
+        if let Some(description) = description.filter(|d| !d.is_empty()) {
+            transaction = transaction.with_description(description);
+        }
+        if let Some(reference) = reference {
+            transaction = transaction.with_reference(reference);
+        }
+        if let Some(class) = classes.into_iter().next() {
+            transaction = transaction.with_metadata("class", class);
+        }
+        for draft in splits {
+            let mut split = Split::new(id.clone(), draft.account, draft.quantity);
+            if let Some(exchanged_from) = draft.exchanged_from {
+                split = split.with_exchanged_from(exchanged_from);
+            }
+            if let Some(description) = draft.description {
+                split = split.with_description(description);
+            }
+            transaction.add_split(split);
+        }
+        if cleared {
+            self.reconciled.push(Reconciled::new(
+                id.clone(),
+                own_id,
+                reference.map_or_else(|| id.to_string(), str::to_string),
+                posted,
+            ));
+        }
+        self.transactions.push(transaction);
+        Ok(())
+    }
+
+    fn split(&self, target: Target, amount: Decimal, description: Option<&str>) -> DraftSplit {
+        let (account, transfer) = match target {
+            Target::Transfer(name) => (
+                AccountId::new(account_name(&name, self.accounts[&name])),
+                Some(name),
+            ),
+            Target::Account(account) => (account, None),
+        };
+        DraftSplit {
+            account,
+            transfer,
+            quantity: Quantity::new(CommodityId::Currency(self.options.currency()), amount),
+            exchanged_from: None,
+            description: description.filter(|d| !d.is_empty()).map(str::to_string),
+        }
+    }
+
+    ///
+    /// The target of a category field, such as `Auto:Fuel/Business` or `[Savings]`; `income`
+    /// decides whether a category not in the category list is income or an expense.
+    ///
+    fn target(
+        &mut self,
+        own: &str,
+        value: &str,
+        income: bool,
+        classes: &mut Vec<String>,
+    ) -> Target {
+        let (category, class) = match value.strip_prefix('[') {
+            Some(transfer) => match transfer.split_once(']') {
+                Some((name, rest)) => (
+                    value[..name.len() + 2].trim(),
+                    rest.trim().strip_prefix('/'),
+                ),
+                None => (value, None),
+            },
+            None => match value.split_once('/') {
+                Some((category, class)) => (category.trim(), Some(class)),
+                None => (value.trim(), None),
+            },
+        };
+        if let Some(class) = class.map(str::trim).filter(|c| !c.is_empty()) {
+            classes.push(class.to_string());
+        }
+
+        if let Some(name) = category.strip_prefix('[').and_then(|c| c.strip_suffix(']')) {
+            if name == own {
+                let id = "Equity:Opening Balances";
+                self.use_account(id, AccountKind::Equity, None);
+                return Target::Account(AccountId::new(id));
+            }
+            let kind = *self
+                .accounts
+                .entry(name.to_string())
+                .or_insert(AccountKind::Asset);
+            self.use_account(&account_name(name, kind), kind, Some(name));
+            return Target::Transfer(name.to_string());
+        }
+
+        let category = if category.is_empty() {
+            "Uncategorized"
+        } else {
+            category
+        };
+        let kind = *self
+            .categories
+            .entry(category.to_string())
+            .or_insert(if income {
+                AccountKind::Income
+            } else {
+                AccountKind::Expense
+            });
+        let id = match self.options.category(category) {
+            Some(id) => id.to_string(),
+            None => account_name(category, kind),
+        };
+        self.use_account(&id, kind, None);
+        Target::Account(AccountId::new(id))
+    }
+
+    ///
+    /// Add the account `id`, and each of its parents, to the ledger.
+    ///
+    fn use_account(&mut self, id: &str, kind: AccountKind, description: Option<&str>) {
+        let leaf = |name: &str| name.rsplit(':').next().unwrap_or_default().to_string();
+        let _ = self
+            .ledger_accounts
+            .entry(id.to_string())
+            .or_insert_with(|| (kind, description.map_or_else(|| leaf(id), str::to_string)));
+        let mut name = id;
+        while let Some((parent, _)) = name.rsplit_once(':') {
+            let _ = self
+                .ledger_accounts
+                .entry(parent.to_string())
+                .or_insert_with(|| (kind, leaf(parent)));
+            name = parent;
+        }
+    }
+
+    ///
+    /// The commodity for a security, by its name or symbol in the options, and then by either
+    /// if that is an ISIN.
+    ///
+    fn security(&self, name: &str) -> Option<CommodityId> {
+        let related: Vec<&str> = std::iter::once(name)
+            .chain(self.securities.iter().filter_map(|(n, symbol)| {
+                if n == name {
+                    Some(symbol.as_str())
+                } else if symbol == name {
+                    Some(n.as_str())
+                } else {
+                    None
+                }
+            }))
+            .collect();
+        related
+            .iter()
+            .find_map(|id| self.options.security(id).cloned())
+            .or_else(|| {
+                related.iter().find_map(|id| {
+                    let isin = InternationalSecuritiesId::from(*id);
+                    isin.is_valid().then_some(CommodityId::Security(isin))
+                })
+            })
+    }
+
+    fn unmapped_security(&mut self, name: &str) {
+        if self
+            .report
+            .unmapped_of_kind("security")
+            .all(|item| item.id() != name)
+        {
+            self.report.add(
+                "security",
+                name,
+                "not an ISIN, and not mapped in the options",
+            );
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+///
+/// The first non-empty value of the field `code` in a record.
+///
+fn field(fields: &[(char, String)], code: char) -> Option<&str> {
+    fields
+        .iter()
+        .find(|(c, value)| *c == code && !value.is_empty())
+        .map(|(_, value)| value.as_str())
+}
+
+///
+/// The ledger account for a QIF account, or category, of `kind`.
+///
+fn account_name(name: &str, kind: AccountKind) -> String {
+    let root = match kind {
+        AccountKind::Asset => "Assets",
+        AccountKind::Liability => "Liabilities",
+        AccountKind::Equity => "Equity",
+        AccountKind::Income => "Income",
+        AccountKind::Expense => "Expenses",
+    };
+    format!("{}:{}", root, name)
+}
+
+fn parse_amount(value: &str) -> Option<Decimal> {
+    Decimal::from_str(&value.replace([',', ' ', '$'], "")).ok()
+}
+
+fn parse_date(value: &str, format: QifDateFormat) -> Option<NaiveDate> {
+    let parts: Vec<&str> = value.split(['/', '-', '.', '\'']).map(str::trim).collect();
+    let [first, second, third] = parts[..] else {
+        return None;
+    };
+    let (year, month, day) = match format {
+        QifDateFormat::MonthDayYear => (third, first, second),
+        QifDateFormat::DayMonthYear => (third, second, first),
+        QifDateFormat::YearMonthDay => (first, second, third),
+    };
+    let mut year: i32 = year.parse().ok()?;
+    if year < 100 {
+        year += if value.contains('\'') || year < 70 {
+            2000
+        } else {
+            1900
+        };
+    }
+    NaiveDate::from_ymd_opt(year, month.parse().ok()?, day.parse().ok()?)
+}
+
+fn midnight(date: NaiveDate) -> DateTime<Utc> {
+    date.and_hms_opt(0, 0, 0).unwrap().and_utc()
+}
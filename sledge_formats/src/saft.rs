@@ -0,0 +1,939 @@
+/*!
+Export the OECD Standard Audit File for Tax (SAF-T), version 2.00, for a company's ledger and
+journals over a date range.
+
+The file has the four parts of the standard:
+
+* the header, identifying the company, from its [`Party`], and the selection period;
+* the master files; the chart of accounts, every account in [`Ledger::book`] with its opening
+  and closing balances for the period, the customers and suppliers, from the parties that the
+  ledger's [`Customer`](AccountRepresents::Customer) and
+  [`Supplier`](AccountRepresents::Supplier) accounts represent, and the tax table;
+* the general ledger entries, every transaction posted in the period, by journal;
+* the source documents; each transaction that increases a customer's balance is a sales
+  invoice, and each that increases the balance owed to a supplier a purchase invoice, while
+  those that reduce either are payments.
+
+The sections of entries, and of each kind of source document, start with their control
+totals; the number of entries and the total debit and credit amounts of their lines. The
+same totals are returned by [`SaftExport::control_totals`] so that they can be checked, or
+reported, before the file is submitted.
+
+Amounts are in the ledger's currency. A split in the ledger's currency that was exchanged from
+another currency is written with the original currency amount and the rate; a split in any
+other commodity can not be represented and the export fails. Lines of taxable supplies, those
+with a tax code that are not posted to the code's tax accounts, carry the code, the rate in
+effect on the posting date, and the tax on the line's amount; the invoice totals carry the tax
+actually posted for each code.
+
+National variants of SAF-T add elements, and constraints, of their own; the file written here
+contains only those of the OECD standard.
+
+*/
+
+use crate::error::{invalid_value, Result};
+use crate::xml::XmlWriter;
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use codes_iso_4217::CurrencyCode;
+use rust_decimal::Decimal;
+use sledge_model::commodity::{CommodityId, Rate};
+use sledge_model::journal::{Journal, Split, Transaction};
+use sledge_model::ledger::{Account, AccountId, AccountKind, AccountRepresents, Balances, Ledger};
+use sledge_model::party::{Address, Party, PartyId};
+use sledge_model::tax::{TaxCodeId, TaxTable};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use tracing::info;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// The XML namespace of the OECD SAF-T 2.00 schema.
+///
+pub const SAFT_NAMESPACE: &str = "urn:OECD:StandardAuditFile-Taxation/2.00";
+
+///
+/// What to export; the company, its books, and the period.
+///
+#[derive(Clone, Debug)]
+pub struct SaftExport<'a> {
+    company: &'a Party,
+    ledger: &'a Ledger,
+    journals: &'a [Journal],
+    start: NaiveDate,
+    end: NaiveDate,
+    parties: Vec<&'a Party>,
+    tax_table: Option<&'a TaxTable>,
+    tax_accounting_basis: String,
+    created: DateTime<Utc>,
+}
+
+///
+/// The number of entries in a section, and the total of the debit and credit amounts of their
+/// lines.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ControlTotals {
+    entries: usize,
+    debit: Decimal,
+    credit: Decimal,
+}
+
+///
+/// The control totals of each section of the file.
+///
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SaftControlTotals {
+    pub general_ledger: ControlTotals,
+    pub sales_invoices: ControlTotals,
+    pub purchase_invoices: ControlTotals,
+    pub payments: ControlTotals,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+pub fn write_saft_file<P: AsRef<Path>>(path: P, export: &SaftExport<'_>) -> Result<()> {
+    info!("Writing SAF-T file {:?}", path.as_ref());
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_saft(&mut writer, export)?;
+    writer.flush()?;
+    Ok(())
+}
+
+///
+/// Write the audit file; everything is checked before anything is written, so nothing is
+/// written if the export fails.
+///
+pub fn write_saft<W: Write>(writer: &mut W, export: &SaftExport<'_>) -> Result<()> {
+    let contents = Contents::new(export)?;
+    let mut xml = XmlWriter::new(writer)?;
+    xml.start_with("AuditFile", &[("xmlns", SAFT_NAMESPACE)])?;
+    write_header(&mut xml, export)?;
+    write_master_files(&mut xml, export, &contents)?;
+    write_entries(&mut xml, &contents)?;
+    write_source_documents(&mut xml, &contents)?;
+    xml.finish()
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+const SAFT_VERSION: &str = "2.00";
+
+const TAX_TYPE: &str = "VAT";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DocumentKind {
+    SalesInvoice,
+    PurchaseInvoice,
+    Payment,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Role {
+    Customer,
+    Supplier,
+}
+
+#[derive(Debug)]
+struct PartyAccounts<'a> {
+    party: &'a Party,
+    accounts: Vec<&'a AccountId>,
+}
+
+#[derive(Debug)]
+struct ForeignAmount {
+    currency: CurrencyCode,
+    amount: Decimal,
+    rate: Rate,
+}
+
+#[derive(Debug)]
+struct LineTax<'a> {
+    code: &'a TaxCodeId,
+    percentage: Decimal,
+    base: Decimal,
+    amount: Decimal,
+}
+
+#[derive(Debug)]
+struct Line<'a> {
+    split: &'a Split,
+    party: Option<(Role, &'a PartyId)>,
+    foreign: Option<ForeignAmount>,
+    tax: Option<LineTax<'a>>,
+    is_tax: bool,
+}
+
+#[derive(Debug)]
+struct Entry<'a> {
+    transaction: &'a Transaction,
+    lines: Vec<Line<'a>>,
+}
+
+#[derive(Debug)]
+struct Document {
+    kind: DocumentKind,
+    journal: usize,
+    entry: usize,
+    party_line: usize,
+}
+
+#[derive(Debug)]
+struct Contents<'a> {
+    customers: Vec<PartyAccounts<'a>>,
+    suppliers: Vec<PartyAccounts<'a>>,
+    opening: Balances,
+    closing: Balances,
+    journals: Vec<(&'a Journal, Vec<Entry<'a>>)>,
+    documents: Vec<Document>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl<'a> SaftExport<'a> {
+    ///
+    /// Export the transactions of `journals` posted from `start` to `end`, both inclusive.
+    ///
+    pub fn new(
+        company: &'a Party,
+        ledger: &'a Ledger,
+        journals: &'a [Journal],
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Self {
+        Self {
+            company,
+            ledger,
+            journals,
+            start,
+            end,
+            parties: Default::default(),
+            tax_table: None,
+            tax_accounting_basis: "A".to_string(),
+            created: Utc::now(),
+        }
+    }
+
+    ///
+    /// The parties that the ledger's customer and supplier accounts represent.
+    ///
+    pub fn with_parties<I>(self, parties: I) -> Self
+    where
+        I: IntoIterator<Item = &'a Party>,
+    {
+        Self {
+            parties: parties.into_iter().collect(),
+            ..self
+        }
+    }
+
+    pub fn with_tax_table(self, tax_table: &'a TaxTable) -> Self {
+        Self {
+            tax_table: Some(tax_table),
+            ..self
+        }
+    }
+
+    ///
+    /// The basis on which the books are kept, as defined by the tax authority; the default is
+    /// `"A"`, accounting.
+    ///
+    pub fn with_tax_accounting_basis<S: Into<String>>(self, tax_accounting_basis: S) -> Self {
+        Self {
+            tax_accounting_basis: tax_accounting_basis.into(),
+            ..self
+        }
+    }
+
+    pub fn with_created(self, created: DateTime<Utc>) -> Self {
+        Self { created, ..self }
+    }
+
+    pub fn start(&self) -> NaiveDate {
+        self.start
+    }
+
+    pub fn end(&self) -> NaiveDate {
+        self.end
+    }
+
+    ///
+    /// The control totals of each section, as they will be written.
+    ///
+    pub fn control_totals(&self) -> Result<SaftControlTotals> {
+        Ok(Contents::new(self)?.control_totals())
+    }
+
+    fn from(&self) -> DateTime<Utc> {
+        self.start.and_hms_opt(0, 0, 0).unwrap().and_utc()
+    }
+
+    fn until(&self) -> DateTime<Utc> {
+        self.end
+            .succ_opt()
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc()
+    }
+
+    fn party(&self, id: &PartyId) -> Result<&'a Party> {
+        self.parties
+            .iter()
+            .find(|party| party.id() == id)
+            .copied()
+            .ok_or_else(|| invalid_value("party", id.to_string()))
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl ControlTotals {
+    pub fn number_of_entries(&self) -> usize {
+        self.entries
+    }
+
+    pub fn total_debit(&self) -> Decimal {
+        self.debit
+    }
+
+    pub fn total_credit(&self) -> Decimal {
+        self.credit
+    }
+
+    fn add_entry(&mut self, amounts: impl Iterator<Item = Decimal>) {
+        self.entries += 1;
+        for amount in amounts {
+            if amount < Decimal::ZERO {
+                self.credit -= amount;
+            } else {
+                self.debit += amount;
+            }
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl<'a> Contents<'a> {
+    fn new(export: &SaftExport<'a>) -> Result<Self> {
+        if export.end < export.start {
+            return Err(invalid_value("SelectionEndDate", export.end.to_string()));
+        }
+        let mut customers: Vec<PartyAccounts<'a>> = Default::default();
+        let mut suppliers: Vec<PartyAccounts<'a>> = Default::default();
+        let mut roles: HashMap<&AccountId, (Role, &PartyId)> = Default::default();
+        for account in export.ledger.book() {
+            let (role, party, list) = match account.represents() {
+                Some(AccountRepresents::Customer(customer)) => {
+                    (Role::Customer, customer.party(), &mut customers)
+                }
+                Some(AccountRepresents::Supplier(supplier)) => {
+                    (Role::Supplier, supplier.party(), &mut suppliers)
+                }
+                _ => continue,
+            };
+            roles.insert(account.id(), (role, party));
+            match list.iter_mut().find(|entry| entry.party.id() == party) {
+                Some(entry) => entry.accounts.push(account.id()),
+                None => list.push(PartyAccounts {
+                    party: export.party(party)?,
+                    accounts: vec![account.id()],
+                }),
+            }
+        }
+
+        let tax_accounts: HashSet<&AccountId> = export
+            .tax_table
+            .iter()
+            .flat_map(|table| table.codes())
+            .flat_map(|code| {
+                code.output_account()
+                    .into_iter()
+                    .chain(code.input_account())
+            })
+            .collect();
+        let currency = export.ledger.currency();
+        let minor_units = currency.monetary_units().unwrap_or(2) as u32;
+
+        let mut journals: Vec<(&'a Journal, Vec<Entry<'a>>)> = Default::default();
+        let mut documents: Vec<Document> = Default::default();
+        for journal in export.journals {
+            let mut transactions: Vec<_> = journal
+                .transactions_between(export.from(), export.until())
+                .collect();
+            transactions.sort_by_key(|transaction| transaction.posted());
+            let mut entries: Vec<Entry<'a>> = Default::default();
+            for transaction in transactions {
+                let mut lines: Vec<Line<'a>> = Default::default();
+                for split in transaction.splits() {
+                    let is_tax = tax_accounts.contains(split.account());
+                    let tax = match split.tax_code() {
+                        Some(code) if !is_tax => {
+                            Some(line_tax(export, transaction, split, code, minor_units)?)
+                        }
+                        _ => None,
+                    };
+                    lines.push(Line {
+                        split,
+                        party: roles.get(split.account()).copied(),
+                        foreign: foreign_amount(transaction, split, currency)?,
+                        tax,
+                        is_tax,
+                    });
+                }
+                if let Some(document) = classify(&lines).map(|(kind, party_line)| Document {
+                    kind,
+                    journal: journals.len(),
+                    entry: entries.len(),
+                    party_line,
+                }) {
+                    documents.push(document);
+                }
+                entries.push(Entry { transaction, lines });
+            }
+            journals.push((journal, entries));
+        }
+
+        Ok(Self {
+            customers,
+            suppliers,
+            opening: Balances::as_at(export.journals, export.from()),
+            closing: Balances::as_at(export.journals, export.until()),
+            journals,
+            documents,
+        })
+    }
+
+    fn entry(&self, document: &Document) -> &Entry<'a> {
+        &self.journals[document.journal].1[document.entry]
+    }
+
+    fn documents(&self, kind: DocumentKind) -> impl Iterator<Item = &Document> {
+        self.documents
+            .iter()
+            .filter(move |document| document.kind == kind)
+    }
+
+    ///
+    /// The lines of a document; an invoice's lines are those of the goods or services, not
+    /// those of the tax or of the amount due.
+    ///
+    fn document_lines<'b>(&'b self, document: &'b Document) -> impl Iterator<Item = &'b Line<'a>> {
+        self.entry(document)
+            .lines
+            .iter()
+            .enumerate()
+            .filter(move |(n, line)| {
+                document.kind == DocumentKind::Payment
+                    || (*n != document.party_line && !line.is_tax)
+            })
+            .map(|(_, line)| line)
+    }
+
+    fn control_totals(&self) -> SaftControlTotals {
+        let mut totals = SaftControlTotals::default();
+        for (_, entries) in &self.journals {
+            for entry in entries {
+                totals
+                    .general_ledger
+                    .add_entry(entry.lines.iter().map(|line| line.amount()));
+            }
+        }
+        for document in &self.documents {
+            let section = match document.kind {
+                DocumentKind::SalesInvoice => &mut totals.sales_invoices,
+                DocumentKind::PurchaseInvoice => &mut totals.purchase_invoices,
+                DocumentKind::Payment => &mut totals.payments,
+            };
+            section.add_entry(self.document_lines(document).map(|line| line.amount()));
+        }
+        totals
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl Line<'_> {
+    fn amount(&self) -> Decimal {
+        self.split.quantity().quantity()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+fn classify(lines: &[Line<'_>]) -> Option<(DocumentKind, usize)> {
+    let (n, line) = lines
+        .iter()
+        .enumerate()
+        .find(|(_, line)| line.party.is_some())?;
+    let increases = line.amount() > Decimal::ZERO;
+    let kind = match (line.party.unwrap().0, increases) {
+        (Role::Customer, true) => DocumentKind::SalesInvoice,
+        (Role::Supplier, false) => DocumentKind::PurchaseInvoice,
+        _ => DocumentKind::Payment,
+    };
+    Some((kind, n))
+}
+
+fn foreign_amount(
+    transaction: &Transaction,
+    split: &Split,
+    currency: CurrencyCode,
+) -> Result<Option<ForeignAmount>> {
+    if split.quantity().commodity() != &CommodityId::Currency(currency) {
+        return Err(invalid_value(
+            format!("currency of {} in {}", split.account(), transaction.id()),
+            format!("{:?}", split.quantity().commodity()),
+        ));
+    }
+    Ok(match split.exchanged_from() {
+        Some(from) => match from.quantity().commodity() {
+            CommodityId::Currency(original) if *original != currency => Some(ForeignAmount {
+                currency: *original,
+                amount: from.quantity().quantity().abs(),
+                rate: from.rate(),
+            }),
+            _ => None,
+        },
+        None => None,
+    })
+}
+
+fn line_tax<'a>(
+    export: &SaftExport<'_>,
+    transaction: &Transaction,
+    split: &Split,
+    code: &'a TaxCodeId,
+    minor_units: u32,
+) -> Result<LineTax<'a>> {
+    let rate = export
+        .tax_table
+        .and_then(|table| table.get(code))
+        .ok_or_else(|| invalid_value("tax code", code.to_string()))?
+        .rate_on(transaction.posted())
+        .map_err(|_| invalid_value("tax rate", code.to_string()))?;
+    let base = split.quantity().quantity().abs();
+    Ok(LineTax {
+        code,
+        percentage: (rate * Decimal::ONE_HUNDRED).normalize(),
+        base,
+        amount: (base * rate).round_dp(minor_units),
+    })
+}
+
+fn write_header<W: Write>(xml: &mut XmlWriter<'_, W>, export: &SaftExport<'_>) -> Result<()> {
+    let company = export.company;
+    xml.start("Header")?;
+    xml.element("AuditFileVersion", SAFT_VERSION)?;
+    xml.element(
+        "AuditFileCountry",
+        company.mailing_address().country().as_str(),
+    )?;
+    xml.element("AuditFileDateCreated", export.created.format("%Y-%m-%d"))?;
+    xml.element("SoftwareCompanyName", "sledge")?;
+    xml.element("SoftwareID", env!("CARGO_PKG_NAME"))?;
+    xml.element("SoftwareVersion", env!("CARGO_PKG_VERSION"))?;
+    xml.start("Company")?;
+    write_company(xml, company)?;
+    xml.end()?;
+    xml.element("DefaultCurrencyCode", export.ledger.currency().alpha_code())?;
+    xml.start("SelectionCriteria")?;
+    xml.element("SelectionStartDate", export.start)?;
+    xml.element("SelectionEndDate", export.end)?;
+    xml.end()?;
+    xml.element("TaxAccountingBasis", &export.tax_accounting_basis)?;
+    xml.end()
+}
+
+fn write_company<W: Write>(xml: &mut XmlWriter<'_, W>, party: &Party) -> Result<()> {
+    xml.element("RegistrationNumber", party.id().as_str())?;
+    xml.element("Name", party.name())?;
+    write_address(xml, "Address", party.mailing_address())
+}
+
+fn write_address<W: Write>(
+    xml: &mut XmlWriter<'_, W>,
+    name: &str,
+    address: &Address,
+) -> Result<()> {
+    xml.start(name)?;
+    xml.element("StreetName", address.street_name())?;
+    xml.element("Number", address.street_number())?;
+    xml.optional("AdditionalAddressDetail", address.unit_number())?;
+    xml.element("City", address.city_or_town())?;
+    xml.element("PostalCode", address.postal_code())?;
+    if !address.province_or_state().is_empty() {
+        xml.element("Region", address.province_or_state())?;
+    }
+    xml.element("Country", address.country().as_str())?;
+    xml.end()
+}
+
+fn write_master_files<W: Write>(
+    xml: &mut XmlWriter<'_, W>,
+    export: &SaftExport<'_>,
+    contents: &Contents<'_>,
+) -> Result<()> {
+    xml.start("MasterFiles")?;
+    xml.start("GeneralLedgerAccounts")?;
+    for account in export.ledger.book() {
+        write_account(xml, account, contents)?;
+    }
+    xml.end()?;
+
+    for (section, element, id, parties) in [
+        ("Customers", "Customer", "CustomerID", &contents.customers),
+        ("Suppliers", "Supplier", "SupplierID", &contents.suppliers),
+    ] {
+        if parties.is_empty() {
+            continue;
+        }
+        xml.start(section)?;
+        for party in parties.iter() {
+            let balance = |balances: &Balances| -> Decimal {
+                party
+                    .accounts
+                    .iter()
+                    .map(|account| balances.balance(account))
+                    .sum()
+            };
+            xml.start(element)?;
+            write_company(xml, party.party)?;
+            xml.element(id, party.party.id().as_str())?;
+            xml.element("AccountID", party.accounts[0])?;
+            write_balance(xml, "Opening", balance(&contents.opening))?;
+            write_balance(xml, "Closing", balance(&contents.closing))?;
+            xml.end()?;
+        }
+        xml.end()?;
+    }
+
+    if let Some(table) = export.tax_table {
+        let country = export.company.mailing_address().country().as_str();
+        xml.start("TaxTable")?;
+        xml.start("TaxTableEntry")?;
+        xml.element("TaxType", TAX_TYPE)?;
+        xml.element("Description", "Value added tax")?;
+        for code in table.codes() {
+            let rates: Vec<(Option<NaiveDate>, Decimal)> = match code.rates().next() {
+                Some(_) if code.treatment().is_taxable() => code
+                    .rates()
+                    .map(|rate| (Some(rate.effective_from().date_naive()), rate.rate()))
+                    .collect(),
+                _ => vec![(None, Decimal::ZERO)],
+            };
+            for (effective, rate) in rates {
+                xml.start("TaxCodeDetails")?;
+                xml.element("TaxCode", code.id())?;
+                xml.optional("EffectiveDate", effective)?;
+                xml.element("Description", code.name())?;
+                xml.element("TaxPercentage", (rate * Decimal::ONE_HUNDRED).normalize())?;
+                xml.element("Country", country)?;
+                xml.end()?;
+            }
+        }
+        xml.end()?;
+        xml.end()?;
+    }
+    xml.end()
+}
+
+fn write_account<W: Write>(
+    xml: &mut XmlWriter<'_, W>,
+    account: &Account,
+    contents: &Contents<'_>,
+) -> Result<()> {
+    xml.start("Account")?;
+    xml.element("AccountID", account.id())?;
+    xml.element("AccountDescription", account.description())?;
+    xml.element(
+        "AccountType",
+        match account.kind() {
+            AccountKind::Asset => "Asset",
+            AccountKind::Liability => "Liability",
+            AccountKind::Equity => "Equity",
+            AccountKind::Income => "Income",
+            AccountKind::Expense => "Expense",
+        },
+    )?;
+    write_balance(xml, "Opening", contents.opening.balance(account.id()))?;
+    write_balance(xml, "Closing", contents.closing.balance(account.id()))?;
+    xml.end()
+}
+
+fn write_balance<W: Write>(
+    xml: &mut XmlWriter<'_, W>,
+    prefix: &str,
+    balance: Decimal,
+) -> Result<()> {
+    if balance < Decimal::ZERO {
+        xml.element(&format!("{}CreditBalance", prefix), -balance)
+    } else {
+        xml.element(&format!("{}DebitBalance", prefix), balance)
+    }
+}
+
+fn write_totals<W: Write>(xml: &mut XmlWriter<'_, W>, totals: &ControlTotals) -> Result<()> {
+    xml.element("NumberOfEntries", totals.entries)?;
+    xml.element("TotalDebit", totals.debit)?;
+    xml.element("TotalCredit", totals.credit)
+}
+
+fn write_period<W: Write>(xml: &mut XmlWriter<'_, W>, transaction: &Transaction) -> Result<()> {
+    let posted = transaction.posted();
+    xml.element("Period", posted.month())?;
+    xml.element("PeriodYear", posted.year())
+}
+
+fn write_entries<W: Write>(xml: &mut XmlWriter<'_, W>, contents: &Contents<'_>) -> Result<()> {
+    xml.start("GeneralLedgerEntries")?;
+    write_totals(xml, &contents.control_totals().general_ledger)?;
+    for (journal, entries) in &contents.journals {
+        xml.start("Journal")?;
+        xml.element("JournalID", journal.name())?;
+        xml.element("Description", journal.name())?;
+        xml.element("Type", "GL")?;
+        for entry in entries {
+            let transaction = entry.transaction;
+            xml.start("Transaction")?;
+            xml.element("TransactionID", transaction.id())?;
+            write_period(xml, transaction)?;
+            xml.element("TransactionDate", transaction.posted().date_naive())?;
+            xml.element("Description", describe(transaction))?;
+            xml.element("SystemEntryDate", transaction.entered().date_naive())?;
+            xml.element("GLPostingDate", transaction.posted().date_naive())?;
+            for (n, line) in entry.lines.iter().enumerate() {
+                xml.start("Line")?;
+                xml.element("RecordID", n + 1)?;
+                xml.element("AccountID", line.split.account())?;
+                write_party(xml, line)?;
+                xml.element(
+                    "Description",
+                    line.split
+                        .description()
+                        .map(String::as_str)
+                        .unwrap_or_else(|| describe(transaction)),
+                )?;
+                let amount = line.amount();
+                xml.start(if amount < Decimal::ZERO {
+                    "CreditAmount"
+                } else {
+                    "DebitAmount"
+                })?;
+                write_amount(xml, line)?;
+                xml.end()?;
+                write_line_tax(xml, line)?;
+                xml.end()?;
+            }
+            xml.end()?;
+        }
+        xml.end()?;
+    }
+    xml.end()
+}
+
+fn write_source_documents<W: Write>(
+    xml: &mut XmlWriter<'_, W>,
+    contents: &Contents<'_>,
+) -> Result<()> {
+    let totals = contents.control_totals();
+    xml.start("SourceDocuments")?;
+    for (kind, section, party_info, id, totals) in [
+        (
+            DocumentKind::SalesInvoice,
+            "SalesInvoices",
+            "CustomerInfo",
+            "CustomerID",
+            &totals.sales_invoices,
+        ),
+        (
+            DocumentKind::PurchaseInvoice,
+            "PurchaseInvoices",
+            "SupplierInfo",
+            "SupplierID",
+            &totals.purchase_invoices,
+        ),
+    ] {
+        if totals.entries == 0 {
+            continue;
+        }
+        xml.start(section)?;
+        write_totals(xml, totals)?;
+        for document in contents.documents(kind) {
+            let entry = contents.entry(document);
+            let transaction = entry.transaction;
+            let party_line = &entry.lines[document.party_line];
+            xml.start("Invoice")?;
+            xml.element(
+                "InvoiceNo",
+                transaction
+                    .reference()
+                    .map(String::as_str)
+                    .unwrap_or(transaction.id().as_str()),
+            )?;
+            xml.start(party_info)?;
+            xml.element(id, party_line.party.unwrap().1.as_str())?;
+            xml.end()?;
+            xml.element("AccountID", party_line.split.account())?;
+            write_period(xml, transaction)?;
+            xml.element("InvoiceDate", transaction.posted().date_naive())?;
+            xml.element("GLPostingDate", transaction.posted().date_naive())?;
+            xml.element("TransactionID", transaction.id())?;
+            let mut net = Decimal::ZERO;
+            let mut taxes: BTreeMap<&TaxCodeId, (Decimal, Decimal, Decimal)> = Default::default();
+            for (n, line) in contents.document_lines(document).enumerate() {
+                let amount = line.amount();
+                net += amount.abs();
+                xml.start("Line")?;
+                xml.element("LineNumber", n + 1)?;
+                xml.element("AccountID", line.split.account())?;
+                xml.element(
+                    "Description",
+                    line.split
+                        .description()
+                        .map(String::as_str)
+                        .unwrap_or_else(|| describe(transaction)),
+                )?;
+                xml.start("InvoiceLineAmount")?;
+                write_amount(xml, line)?;
+                xml.end()?;
+                xml.element("DebitCreditIndicator", indicator(amount))?;
+                write_line_tax(xml, line)?;
+                xml.end()?;
+                if let Some(tax) = &line.tax {
+                    let total = taxes.entry(tax.code).or_default();
+                    total.0 = tax.percentage;
+                    total.1 += tax.base;
+                }
+            }
+            for line in entry.lines.iter().filter(|line| line.is_tax) {
+                if let Some(code) = line.split.tax_code() {
+                    taxes.entry(code).or_default().2 += line.amount().abs();
+                }
+            }
+            xml.start("DocumentTotals")?;
+            for (code, (percentage, base, amount)) in taxes {
+                xml.start("TaxInformationTotals")?;
+                xml.element("TaxType", TAX_TYPE)?;
+                xml.element("TaxCode", code)?;
+                xml.element("TaxPercentage", percentage)?;
+                xml.element("TaxBase", base)?;
+                xml.start("TaxAmount")?;
+                xml.element("Amount", amount)?;
+                xml.end()?;
+                xml.end()?;
+            }
+            xml.element("NetTotal", net)?;
+            xml.element("GrossTotal", party_line.amount().abs())?;
+            xml.end()?;
+            xml.end()?;
+        }
+        xml.end()?;
+    }
+
+    if totals.payments.entries > 0 {
+        xml.start("Payments")?;
+        write_totals(xml, &totals.payments)?;
+        for document in contents.documents(DocumentKind::Payment) {
+            let entry = contents.entry(document);
+            let transaction = entry.transaction;
+            xml.start("Payment")?;
+            xml.element(
+                "PaymentRefNo",
+                transaction
+                    .reference()
+                    .map(String::as_str)
+                    .unwrap_or(transaction.id().as_str()),
+            )?;
+            write_period(xml, transaction)?;
+            xml.element("TransactionID", transaction.id())?;
+            xml.element("TransactionDate", transaction.posted().date_naive())?;
+            xml.element("Description", describe(transaction))?;
+            for (n, line) in contents.document_lines(document).enumerate() {
+                xml.start("Line")?;
+                xml.element("LineNumber", n + 1)?;
+                xml.element("AccountID", line.split.account())?;
+                write_party(xml, line)?;
+                xml.element("DebitCreditIndicator", indicator(line.amount()))?;
+                xml.start("PaymentLineAmount")?;
+                write_amount(xml, line)?;
+                xml.end()?;
+                xml.end()?;
+            }
+            let gross = entry.lines[document.party_line].amount().abs();
+            xml.start("DocumentTotals")?;
+            xml.element("NetTotal", gross)?;
+            xml.element("GrossTotal", gross)?;
+            xml.end()?;
+            xml.end()?;
+        }
+        xml.end()?;
+    }
+    xml.end()
+}
+
+fn write_party<W: Write>(xml: &mut XmlWriter<'_, W>, line: &Line<'_>) -> Result<()> {
+    match line.party {
+        Some((Role::Customer, party)) => xml.element("CustomerID", party.as_str()),
+        Some((Role::Supplier, party)) => xml.element("SupplierID", party.as_str()),
+        None => Ok(()),
+    }
+}
+
+fn write_amount<W: Write>(xml: &mut XmlWriter<'_, W>, line: &Line<'_>) -> Result<()> {
+    xml.element("Amount", line.amount().abs())?;
+    if let Some(foreign) = &line.foreign {
+        xml.element("CurrencyCode", foreign.currency.alpha_code())?;
+        xml.element("CurrencyAmount", foreign.amount)?;
+        xml.element("ExchangeRate", foreign.rate)?;
+    }
+    Ok(())
+}
+
+fn write_line_tax<W: Write>(xml: &mut XmlWriter<'_, W>, line: &Line<'_>) -> Result<()> {
+    if let Some(tax) = &line.tax {
+        xml.start("TaxInformation")?;
+        xml.element("TaxType", TAX_TYPE)?;
+        xml.element("TaxCode", tax.code)?;
+        xml.element("TaxPercentage", tax.percentage)?;
+        xml.element("TaxBase", tax.base)?;
+        xml.start("TaxAmount")?;
+        xml.element("Amount", tax.amount)?;
+        xml.end()?;
+        xml.end()?;
+    }
+    Ok(())
+}
+
+fn describe(transaction: &Transaction) -> &str {
+    transaction
+        .description()
+        .or(transaction.reference())
+        .map(String::as_str)
+        .unwrap_or(transaction.id().as_str())
+}
+
+fn indicator(amount: Decimal) -> &'static str {
+    if amount < Decimal::ZERO {
+        "C"
+    } else {
+        "D"
+    }
+}
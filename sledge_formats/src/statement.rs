@@ -0,0 +1,490 @@
+/*!
+Statements downloaded from banks, card issuers, and brokers, and the draft transactions made
+from them.
+
+Each statement importer produces [`Statement`]s, which [`draft_transactions`] matches to the
+account in a ledger that represents the statement's bank account, credit card, or brokerage
+account, by account number. Every statement entry becomes a draft
+[`Transaction`](sledge_model::journal::Transaction) between that account and an
+uncategorized account, and the drafts are collected in a
+[`Reconciliation`](sledge_model::journal::Reconciliation) seeded with the statement's balances,
+to be reviewed and posted.
+
+Drafts keep the identifier the institution gave the entry, such as an OFX `FITID`, as the
+[`IMPORT_ID`] metadata value, and entries whose identifier is already in a journal, or
+earlier in the same import, are skipped so that repeated or overlapping downloads can be
+imported again safely.
+
+*/
+
+use crate::ImportReport;
+use chrono::{DateTime, Utc};
+use codes_iso_4217::CurrencyCode;
+use rust_decimal::Decimal;
+use sledge_model::commodity::{CommodityId, Quantity, RatedQuantity};
+use sledge_model::journal::{Journal, Reconciliation, Split, Transaction, TransactionId};
+use sledge_model::ledger::{Account, AccountId, AccountRepresentsKind, Ledger};
+use std::collections::HashSet;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// The transaction metadata key holding the identifier an institution gave a statement entry.
+///
+pub const IMPORT_ID: &str = "import_id";
+
+///
+/// A statement for one account, as downloaded from the institution.
+///
+#[derive(Clone, Debug)]
+pub struct Statement {
+    account_kind: AccountRepresentsKind,
+    account_number: String,
+    currency: CurrencyCode,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+    opening_balance: Option<StatementBalance>,
+    closing_balance: Option<StatementBalance>,
+    entries: Vec<StatementEntry>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StatementBalance {
+    amount: Decimal,
+    as_at: DateTime<Utc>,
+}
+
+///
+/// A single entry on a statement. The amount is the change to the account's cash balance; an
+/// entry that buys or sells a security also has the quantity of the security.
+///
+#[derive(Clone, Debug)]
+pub struct StatementEntry {
+    id: String,
+    posted: DateTime<Utc>,
+    amount: Decimal,
+    description: Option<String>,
+    memo: Option<String>,
+    reference: Option<String>,
+    counterparty: Option<String>,
+    security: Option<Quantity>,
+}
+
+///
+/// The result of reading a statement file; the statements it holds, and the report of any
+/// entries that could not be read.
+///
+#[derive(Clone, Debug, Default)]
+pub struct Statements {
+    pub statements: Vec<Statement>,
+    pub report: ImportReport,
+}
+
+#[derive(Clone, Debug)]
+pub struct StatementOptions {
+    uncategorized: AccountId,
+    account: Option<AccountId>,
+}
+
+///
+/// The result of drafting transactions from statements: a reconciliation for each statement
+/// matched to an account, the identifiers of entries skipped as already imported, and the
+/// report of the statements read, adding those that could not be matched.
+///
+#[derive(Debug, Default)]
+pub struct Drafts {
+    pub reconciliations: Vec<Reconciliation>,
+    pub duplicates: Vec<String>,
+    pub report: ImportReport,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+///
+/// Create draft transactions for the entries of each statement, skipping any already imported
+/// into `journals`.
+///
+pub fn draft_transactions(
+    statements: Statements,
+    ledger: &Ledger,
+    journals: &[Journal],
+    options: &StatementOptions,
+) -> Drafts {
+    let mut seen: HashSet<(AccountId, String)> = journals
+        .iter()
+        .flat_map(|journal| journal.transactions())
+        .filter_map(|transaction| {
+            transaction
+                .metadata_value(IMPORT_ID)
+                .map(|id| (transaction, id))
+        })
+        .flat_map(|(transaction, id)| {
+            transaction
+                .splits()
+                .map(move |split| (split.account().clone(), id.clone()))
+        })
+        .collect();
+
+    let mut drafts = Drafts {
+        report: statements.report,
+        ..Default::default()
+    };
+    for statement in statements.statements {
+        let account = match &options.account {
+            Some(id) => ledger.account(id),
+            None => find_account(ledger, &statement),
+        };
+        let Some(account) = account else {
+            drafts.report.add(
+                "statement",
+                &statement.account_number,
+                format!(
+                    "no {:?} account in the ledger has this account number",
+                    statement.account_kind
+                ),
+            );
+            continue;
+        };
+        let currency = CommodityId::Currency(statement.currency);
+        let until = statement
+            .closing_balance
+            .map(|balance| balance.as_at)
+            .or(statement.end)
+            .or_else(|| statement.entries.iter().map(|e| e.posted).max())
+            .unwrap_or_else(Utc::now);
+        let mut reconciliation = Reconciliation::new(account.id().clone(), currency.clone(), until);
+        if let Some(balance) = statement.opening_balance {
+            reconciliation = reconciliation.with_opening_balance(balance.amount);
+        }
+        if let Some(balance) = statement.closing_balance {
+            reconciliation = reconciliation.with_statement_balance(balance.amount);
+        }
+        for entry in statement.entries {
+            if !seen.insert((account.id().clone(), entry.id.clone())) {
+                drafts.duplicates.push(entry.id);
+                continue;
+            }
+            reconciliation.add_draft(draft(entry, account, &currency, &options.uncategorized));
+        }
+        drafts.reconciliations.push(reconciliation);
+    }
+    drafts
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl Statement {
+    pub fn new<S: Into<String>>(
+        account_kind: AccountRepresentsKind,
+        account_number: S,
+        currency: CurrencyCode,
+    ) -> Self {
+        Self {
+            account_kind,
+            account_number: account_number.into(),
+            currency,
+            start: None,
+            end: None,
+            opening_balance: None,
+            closing_balance: None,
+            entries: Default::default(),
+        }
+    }
+
+    pub fn with_period(self, start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
+        Self {
+            start: Some(start),
+            end: Some(end),
+            ..self
+        }
+    }
+
+    pub fn with_opening_balance(self, opening_balance: StatementBalance) -> Self {
+        Self {
+            opening_balance: Some(opening_balance),
+            ..self
+        }
+    }
+
+    pub fn with_closing_balance(self, closing_balance: StatementBalance) -> Self {
+        Self {
+            closing_balance: Some(closing_balance),
+            ..self
+        }
+    }
+
+    pub fn add_entry(&mut self, entry: StatementEntry) {
+        self.entries.push(entry);
+    }
+
+    pub fn account_kind(&self) -> AccountRepresentsKind {
+        self.account_kind
+    }
+
+    pub fn account_number(&self) -> &String {
+        &self.account_number
+    }
+
+    pub fn currency(&self) -> CurrencyCode {
+        self.currency
+    }
+
+    pub fn start(&self) -> Option<DateTime<Utc>> {
+        self.start
+    }
+
+    pub fn end(&self) -> Option<DateTime<Utc>> {
+        self.end
+    }
+
+    pub fn opening_balance(&self) -> Option<StatementBalance> {
+        self.opening_balance
+    }
+
+    pub fn closing_balance(&self) -> Option<StatementBalance> {
+        self.closing_balance
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &StatementEntry> {
+        self.entries.iter()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl StatementBalance {
+    ///
+    /// The balance `amount` of the account, including every entry before `as_at`.
+    ///
+    pub fn new(amount: Decimal, as_at: DateTime<Utc>) -> Self {
+        Self { amount, as_at }
+    }
+
+    pub fn amount(&self) -> Decimal {
+        self.amount
+    }
+
+    pub fn as_at(&self) -> DateTime<Utc> {
+        self.as_at
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl StatementEntry {
+    pub fn new<S: Into<String>>(id: S, posted: DateTime<Utc>, amount: Decimal) -> Self {
+        Self {
+            id: id.into(),
+            posted,
+            amount,
+            description: None,
+            memo: None,
+            reference: None,
+            counterparty: None,
+            security: None,
+        }
+    }
+
+    pub fn with_description<S: Into<String>>(self, description: S) -> Self {
+        Self {
+            description: Some(description.into()),
+            ..self
+        }
+    }
+
+    pub fn with_memo<S: Into<String>>(self, memo: S) -> Self {
+        Self {
+            memo: Some(memo.into()),
+            ..self
+        }
+    }
+
+    pub fn with_reference<S: Into<String>>(self, reference: S) -> Self {
+        Self {
+            reference: Some(reference.into()),
+            ..self
+        }
+    }
+
+    pub fn with_counterparty<S: Into<String>>(self, counterparty: S) -> Self {
+        Self {
+            counterparty: Some(counterparty.into()),
+            ..self
+        }
+    }
+
+    ///
+    /// The quantity of a security bought, if positive, or sold, for the entry's amount.
+    ///
+    pub fn with_security(self, security: Quantity) -> Self {
+        Self {
+            security: Some(security),
+            ..self
+        }
+    }
+
+    pub fn id(&self) -> &String {
+        &self.id
+    }
+
+    pub fn posted(&self) -> DateTime<Utc> {
+        self.posted
+    }
+
+    pub fn amount(&self) -> Decimal {
+        self.amount
+    }
+
+    pub fn description(&self) -> Option<&String> {
+        self.description.as_ref()
+    }
+
+    pub fn memo(&self) -> Option<&String> {
+        self.memo.as_ref()
+    }
+
+    pub fn reference(&self) -> Option<&String> {
+        self.reference.as_ref()
+    }
+
+    pub fn counterparty(&self) -> Option<&String> {
+        self.counterparty.as_ref()
+    }
+
+    pub fn security(&self) -> Option<&Quantity> {
+        self.security.as_ref()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl StatementOptions {
+    ///
+    /// Draft transactions against `uncategorized`, where they wait to be categorized.
+    ///
+    pub fn new(uncategorized: AccountId) -> Self {
+        Self {
+            uncategorized,
+            account: None,
+        }
+    }
+
+    ///
+    /// Draft every statement's transactions for `account`, rather than the account matching
+    /// the statement's account number.
+    ///
+    pub fn with_account(self, account: AccountId) -> Self {
+        Self {
+            account: Some(account),
+            ..self
+        }
+    }
+
+    pub fn uncategorized(&self) -> &AccountId {
+        &self.uncategorized
+    }
+
+    pub fn account(&self) -> Option<&AccountId> {
+        self.account.as_ref()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+fn find_account<'a>(ledger: &'a Ledger, statement: &Statement) -> Option<&'a Account> {
+    ledger.book().find(|account| {
+        account
+            .represents()
+            .filter(|represents| represents.kind() == statement.account_kind)
+            .and_then(|represents| represents.account_number())
+            .map(|number| same_account_number(number, &statement.account_number))
+            .unwrap_or_default()
+    })
+}
+
+///
+/// Compare account numbers ignoring spaces and dashes. Institutions often mask all but the
+/// last digits of a number, as in `XXXXXXXX1234`, in which case the unmasked digits must end
+/// the number.
+///
+fn same_account_number(number: &str, statement_number: &str) -> bool {
+    let normalize = |number: &str| -> String {
+        number
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '-')
+            .flat_map(char::to_uppercase)
+            .collect()
+    };
+    let (number, statement_number) = (normalize(number), normalize(statement_number));
+    if number == statement_number {
+        return true;
+    }
+    match statement_number.rfind(['X', '*']) {
+        Some(index) if statement_number.len() - index > 4 => {
+            number.ends_with(&statement_number[index + 1..])
+        }
+        _ => false,
+    }
+}
+
+fn draft(
+    entry: StatementEntry,
+    account: &Account,
+    currency: &CommodityId,
+    uncategorized: &AccountId,
+) -> Transaction {
+    let id = TransactionId::new(format!("{}/{}", account.id(), entry.id));
+    let mut draft = Transaction::new(id.clone(), entry.posted).with_metadata(IMPORT_ID, entry.id);
+    if let Some(description) = entry.description.or_else(|| entry.memo.clone()) {
+        draft = draft.with_description(description);
+    }
+    if let Some(memo) = entry.memo {
+        draft = draft.with_metadata("memo", memo);
+    }
+    if let Some(reference) = entry.reference {
+        draft = draft.with_reference(reference);
+    }
+    if let Some(counterparty) = entry.counterparty {
+        draft = draft.with_metadata("counterparty", counterparty);
+    }
+    let cash = Quantity::new(currency.clone(), entry.amount);
+    match entry.security {
+        // A trade exchanges cash for the security within the brokerage account.
+        Some(security) => {
+            let rate = security
+                .quantity()
+                .checked_div(-entry.amount)
+                .unwrap_or_default();
+            draft
+                .with_split(
+                    Split::new(id.clone(), account.id().clone(), security).with_exchanged_from(
+                        RatedQuantity::new(cash.with_quantity(-entry.amount), rate),
+                    ),
+                )
+                .with_split(Split::new(id, account.id().clone(), cash))
+        }
+        None => draft
+            .with_split(Split::new(id.clone(), account.id().clone(), cash.clone()))
+            .with_split(Split::new(
+                id,
+                uncategorized.clone(),
+                cash.with_quantity(-entry.amount),
+            )),
+    }
+}
@@ -0,0 +1,47 @@
+/*!
+Decoding of the character sets statement files are written in, shared by the readers of the
+formats that declare their encoding.
+
+*/
+
+use crate::error::{invalid_value, Result};
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+pub(crate) fn utf_8(bytes: Vec<u8>) -> Result<String> {
+    String::from_utf8(bytes).map_err(|e| invalid_value("encoding", e.to_string()))
+}
+
+pub(crate) fn latin_1(bytes: Vec<u8>) -> String {
+    bytes.into_iter().map(char::from).collect()
+}
+
+pub(crate) fn windows_1252(bytes: Vec<u8>) -> String {
+    bytes.into_iter().map(windows_1252_char).collect()
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+///
+/// Windows-1252 differs from Latin-1 only in the bytes `0x80` to `0x9F`; the five it leaves
+/// undefined are kept as the Latin-1 control characters.
+///
+fn windows_1252_char(byte: u8) -> char {
+    const HIGH: [char; 32] = [
+        '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8d}', 'Ž',
+        '\u{8f}', '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9d}',
+        'ž', 'Ÿ',
+    ];
+    match byte {
+        0x80..=0x9F => HIGH[usize::from(byte - 0x80)],
+        _ => char::from(byte),
+    }
+}
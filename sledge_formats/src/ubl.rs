@@ -0,0 +1,1236 @@
+/*!
+Write sales invoices as OASIS UBL 2.1 invoices conforming to Peppol BIS Billing 3.0, and read
+supplier invoices in the same format as draft purchase bills.
+
+Parties are addressed by a [`PartyIdentifier`], an identifier qualified by its
+[`InternationalCodeDesignator`](sledge_model::party::InternationalCodeDesignator) scheme; a
+[`LegalEntityId`](sledge_model::party::LegalEntityId),
+[`GlobalLocationNumber`](sledge_model::party::GlobalLocationNumber), or [`VatNumber`] can each
+be turned into one. The scheme is written as the `schemeID` of the party's electronic address,
+its `EndpointID`, and of any further identification.
+
+Each invoice line has a tax code from the [`TaxTable`], and the code's treatment decides the
+VAT category of the line; standard rated codes are category `S` with the rate in effect on
+the issue date, zero rated `Z`, exempt `E`, and reverse charge `AE`. VAT is totalled per
+category and rate, as the standard requires, rather than per line. An invoice is validated
+against the Peppol rules that can be checked from its own contents before it is written.
+
+Incoming invoices are read into a [`SupplierInvoice`], and [`draft_bill`] turns one into a
+draft transaction; the supplier account is credited with the invoice total, each line is
+debited to an expense account, and the VAT to the input tax account of the tax code matching
+each category and rate. Reverse charge VAT is self-assessed as the tax engine would. Only
+invoices are read, credit notes are not.
+
+*/
+
+use crate::error::{invalid_value, missing_element, Result};
+use crate::statement::IMPORT_ID;
+use crate::xml::XmlWriter;
+use chrono::{DateTime, NaiveDate, Utc};
+use codes_iso_4217::CurrencyCode;
+use roxmltree::{Document, Node};
+use rust_decimal::Decimal;
+use sledge_model::commodity::{CommodityId, Quantity};
+use sledge_model::error::missing_tax_account;
+use sledge_model::journal::{Split, Transaction, TransactionId};
+use sledge_model::ledger::{AccountId, AccountRepresents, Ledger};
+use sledge_model::party::{Party, PartyId, PartyIdentifier, VatNumber};
+use sledge_model::tax::{TaxCode, TaxCodeId, TaxTable, TaxTreatment};
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+use tracing::info;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// The XML namespace of UBL 2.1 invoices.
+///
+pub const UBL_INVOICE_NAMESPACE: &str = "urn:oasis:names:specification:ubl:schema:xsd:Invoice-2";
+
+///
+/// The specification identifier of Peppol BIS Billing 3.0.
+///
+pub const PEPPOL_CUSTOMIZATION_ID: &str =
+    "urn:cen.eu:en16931:2017#compliant#urn:fdc:peppol.eu:2017:poacc:billing:3.0";
+
+///
+/// The business process identifier of Peppol BIS Billing 3.0.
+///
+pub const PEPPOL_PROFILE_ID: &str = "urn:fdc:peppol.eu:2017:poacc:billing:01:1.0";
+
+///
+/// The seller or buyer of a sales invoice; the party, its electronic address, and the
+/// identifiers it is registered under.
+///
+#[derive(Clone, Debug)]
+pub struct InvoiceParty<'a> {
+    party: &'a Party,
+    endpoint: PartyIdentifier,
+    identification: Option<PartyIdentifier>,
+    vat_number: Option<VatNumber>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SalesInvoiceLine {
+    id: String,
+    name: String,
+    description: Option<String>,
+    quantity: Decimal,
+    unit_code: String,
+    price: Decimal,
+    tax_code: TaxCodeId,
+}
+
+#[derive(Clone, Debug)]
+pub struct SalesInvoice<'a> {
+    id: String,
+    issue_date: NaiveDate,
+    due_date: Option<NaiveDate>,
+    currency: CurrencyCode,
+    seller: InvoiceParty<'a>,
+    buyer: InvoiceParty<'a>,
+    buyer_reference: Option<String>,
+    order_reference: Option<String>,
+    note: Option<String>,
+    payee_account: Option<String>,
+    payment_id: Option<String>,
+    lines: Vec<SalesInvoiceLine>,
+}
+
+///
+/// The supplier of an incoming invoice, as it identified itself.
+///
+#[derive(Clone, Debug, Default)]
+pub struct SupplierParty {
+    name: String,
+    endpoint: Option<PartyIdentifier>,
+    identifiers: Vec<PartyIdentifier>,
+    vat_number: Option<VatNumber>,
+}
+
+///
+/// A line of an incoming invoice. Document level allowances and charges are read as lines of
+/// their own, with a negative amount for an allowance.
+///
+#[derive(Clone, Debug)]
+pub struct SupplierInvoiceLine {
+    id: String,
+    name: String,
+    quantity: Decimal,
+    amount: Decimal,
+    category: String,
+    percent: Decimal,
+}
+
+///
+/// The VAT of one category and rate, as totalled on an invoice.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvoiceTaxSubtotal {
+    category: String,
+    percent: Decimal,
+    taxable: Decimal,
+    tax: Decimal,
+}
+
+#[derive(Clone, Debug)]
+pub struct SupplierInvoice {
+    id: String,
+    issue_date: NaiveDate,
+    due_date: Option<NaiveDate>,
+    currency: CurrencyCode,
+    supplier: SupplierParty,
+    buyer_reference: Option<String>,
+    lines: Vec<SupplierInvoiceLine>,
+    tax_subtotals: Vec<InvoiceTaxSubtotal>,
+    tax_exclusive: Decimal,
+    tax_inclusive: Decimal,
+    payable: Decimal,
+}
+
+///
+/// Where a draft bill is posted; lines go to the expense account, and the total to the
+/// supplier account, if given, or else the ledger's account representing the supplier.
+///
+#[derive(Clone, Debug)]
+pub struct BillOptions {
+    expense: AccountId,
+    supplier: Option<AccountId>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+pub fn write_ubl_invoice_file<P: AsRef<Path>>(
+    path: P,
+    invoice: &SalesInvoice<'_>,
+    tax_table: &TaxTable,
+) -> Result<()> {
+    info!("Writing UBL invoice file {:?}", path.as_ref());
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_ubl_invoice(&mut writer, invoice, tax_table)?;
+    writer.flush()?;
+    Ok(())
+}
+
+///
+/// Write the invoice; it is validated, and its VAT calculated, before anything is written.
+///
+pub fn write_ubl_invoice<W: Write>(
+    writer: &mut W,
+    invoice: &SalesInvoice<'_>,
+    tax_table: &TaxTable,
+) -> Result<()> {
+    let totals = invoice.validate(tax_table)?;
+    let currency = invoice.currency.alpha_code();
+    let amount = |value: Decimal| format_amount(value, totals.minor_units);
+    let mut xml = XmlWriter::new(writer)?;
+    xml.start_with(
+        "Invoice",
+        &[
+            ("xmlns", UBL_INVOICE_NAMESPACE),
+            ("xmlns:cac", CAC_NAMESPACE),
+            ("xmlns:cbc", CBC_NAMESPACE),
+        ],
+    )?;
+    xml.element("cbc:CustomizationID", PEPPOL_CUSTOMIZATION_ID)?;
+    xml.element("cbc:ProfileID", PEPPOL_PROFILE_ID)?;
+    xml.element("cbc:ID", &invoice.id)?;
+    xml.element("cbc:IssueDate", invoice.issue_date)?;
+    xml.optional("cbc:DueDate", invoice.due_date)?;
+    xml.element("cbc:InvoiceTypeCode", COMMERCIAL_INVOICE)?;
+    xml.optional("cbc:Note", invoice.note.as_ref())?;
+    xml.element("cbc:DocumentCurrencyCode", currency)?;
+    xml.optional("cbc:BuyerReference", invoice.buyer_reference.as_ref())?;
+    if let Some(order_reference) = &invoice.order_reference {
+        xml.start("cac:OrderReference")?;
+        xml.element("cbc:ID", order_reference)?;
+        xml.end()?;
+    }
+    xml.start("cac:AccountingSupplierParty")?;
+    write_party(&mut xml, &invoice.seller)?;
+    xml.end()?;
+    xml.start("cac:AccountingCustomerParty")?;
+    write_party(&mut xml, &invoice.buyer)?;
+    xml.end()?;
+
+    if let Some(account) = &invoice.payee_account {
+        xml.start("cac:PaymentMeans")?;
+        xml.element("cbc:PaymentMeansCode", SEPA_CREDIT_TRANSFER)?;
+        xml.optional("cbc:PaymentID", invoice.payment_id.as_ref())?;
+        xml.start("cac:PayeeFinancialAccount")?;
+        xml.element("cbc:ID", account)?;
+        xml.end()?;
+        xml.end()?;
+    }
+
+    xml.start("cac:TaxTotal")?;
+    xml.element_with(
+        "cbc:TaxAmount",
+        &[("currencyID", currency)],
+        amount(totals.tax),
+    )?;
+    for (subtotal, reason) in &totals.subtotals {
+        xml.start("cac:TaxSubtotal")?;
+        xml.element_with(
+            "cbc:TaxableAmount",
+            &[("currencyID", currency)],
+            amount(subtotal.taxable),
+        )?;
+        xml.element_with(
+            "cbc:TaxAmount",
+            &[("currencyID", currency)],
+            amount(subtotal.tax),
+        )?;
+        xml.start("cac:TaxCategory")?;
+        xml.element("cbc:ID", &subtotal.category)?;
+        xml.element("cbc:Percent", subtotal.percent)?;
+        if let Some((code, text)) = reason {
+            xml.optional("cbc:TaxExemptionReasonCode", *code)?;
+            xml.element("cbc:TaxExemptionReason", text)?;
+        }
+        write_tax_scheme(&mut xml)?;
+        xml.end()?;
+        xml.end()?;
+    }
+    xml.end()?;
+
+    xml.start("cac:LegalMonetaryTotal")?;
+    for (name, value) in [
+        ("cbc:LineExtensionAmount", totals.net),
+        ("cbc:TaxExclusiveAmount", totals.net),
+        ("cbc:TaxInclusiveAmount", totals.net + totals.tax),
+        ("cbc:PayableAmount", totals.net + totals.tax),
+    ] {
+        xml.element_with(name, &[("currencyID", currency)], amount(value))?;
+    }
+    xml.end()?;
+
+    for (line, (category, percent)) in invoice.lines.iter().zip(&totals.categories) {
+        xml.start("cac:InvoiceLine")?;
+        xml.element("cbc:ID", &line.id)?;
+        xml.element_with(
+            "cbc:InvoicedQuantity",
+            &[("unitCode", line.unit_code.as_str())],
+            line.quantity.normalize(),
+        )?;
+        xml.element_with(
+            "cbc:LineExtensionAmount",
+            &[("currencyID", currency)],
+            amount(line.net(totals.minor_units)),
+        )?;
+        xml.start("cac:Item")?;
+        xml.optional("cbc:Description", line.description.as_ref())?;
+        xml.element("cbc:Name", &line.name)?;
+        xml.start("cac:ClassifiedTaxCategory")?;
+        xml.element("cbc:ID", category)?;
+        xml.element("cbc:Percent", percent)?;
+        write_tax_scheme(&mut xml)?;
+        xml.end()?;
+        xml.end()?;
+        xml.start("cac:Price")?;
+        xml.element_with(
+            "cbc:PriceAmount",
+            &[("currencyID", currency)],
+            line.price.normalize(),
+        )?;
+        xml.end()?;
+        xml.end()?;
+    }
+    xml.finish()
+}
+
+pub fn read_ubl_invoice_file<P: AsRef<Path>>(path: P) -> Result<SupplierInvoice> {
+    info!("Reading UBL invoice file {:?}", path.as_ref());
+    read_ubl_invoice(File::open(path)?)
+}
+
+pub fn read_ubl_invoice<R: Read>(mut reader: R) -> Result<SupplierInvoice> {
+    let mut bytes: Vec<u8> = Default::default();
+    let _ = reader.read_to_end(&mut bytes)?;
+    let source = String::from_utf8(bytes).map_err(|e| invalid_value("encoding", e.to_string()))?;
+    let document = Document::parse(source.trim_start_matches('\u{feff}'))?;
+    let root = document.root_element();
+    if root.tag_name().name() != "Invoice" {
+        return Err(invalid_value("root element", root.tag_name().name()));
+    }
+
+    let currency = required(root, &["DocumentCurrencyCode"])?;
+    let currency = CurrencyCode::from_str(currency)
+        .map_err(|_| invalid_value("DocumentCurrencyCode", currency))?;
+    let supplier = find(root, &["AccountingSupplierParty", "Party"])
+        .ok_or_else(|| missing_element("Invoice", "AccountingSupplierParty"))?;
+
+    let mut lines: Vec<SupplierInvoiceLine> = Default::default();
+    for line in children(root, "InvoiceLine") {
+        let (category, percent) = tax_category(line, &["Item", "ClassifiedTaxCategory"])?;
+        lines.push(SupplierInvoiceLine {
+            id: required(line, &["ID"])?.to_string(),
+            name: text(line, &["Item", "Name"])
+                .unwrap_or_default()
+                .to_string(),
+            quantity: text(line, &["InvoicedQuantity"])
+                .map(|value| parse_decimal("InvoicedQuantity", value))
+                .transpose()?
+                .unwrap_or(Decimal::ONE),
+            amount: amount(line, "LineExtensionAmount")?,
+            category,
+            percent,
+        });
+    }
+    if lines.is_empty() {
+        return Err(missing_element("Invoice", "InvoiceLine"));
+    }
+    for allowance_charge in children(root, "AllowanceCharge") {
+        let is_charge = required(allowance_charge, &["ChargeIndicator"])? == "true";
+        let value = amount(allowance_charge, "Amount")?;
+        let (category, percent) = tax_category(allowance_charge, &["TaxCategory"])?;
+        lines.push(SupplierInvoiceLine {
+            id: if is_charge { "charge" } else { "allowance" }.to_string(),
+            name: text(allowance_charge, &["AllowanceChargeReason"])
+                .unwrap_or_default()
+                .to_string(),
+            quantity: Decimal::ONE,
+            amount: if is_charge { value } else { -value },
+            category,
+            percent,
+        });
+    }
+
+    let mut tax_subtotals: Vec<InvoiceTaxSubtotal> = Default::default();
+    for subtotal in children(root, "TaxTotal").flat_map(|total| children(total, "TaxSubtotal")) {
+        let (category, percent) = tax_category(subtotal, &["TaxCategory"])?;
+        tax_subtotals.push(InvoiceTaxSubtotal {
+            category,
+            percent,
+            taxable: amount(subtotal, "TaxableAmount")?,
+            tax: amount(subtotal, "TaxAmount")?,
+        });
+    }
+
+    let totals = child(root, "LegalMonetaryTotal")
+        .ok_or_else(|| missing_element("Invoice", "LegalMonetaryTotal"))?;
+    Ok(SupplierInvoice {
+        id: required(root, &["ID"])?.to_string(),
+        issue_date: parse_date(required(root, &["IssueDate"])?)?,
+        due_date: text(root, &["DueDate"]).map(parse_date).transpose()?,
+        currency,
+        supplier: read_party(supplier),
+        buyer_reference: text(root, &["BuyerReference"]).map(str::to_string),
+        lines,
+        tax_subtotals,
+        tax_exclusive: amount(totals, "TaxExclusiveAmount")?,
+        tax_inclusive: amount(totals, "TaxInclusiveAmount")?,
+        payable: amount(totals, "PayableAmount")?,
+    })
+}
+
+///
+/// Create a draft purchase bill from an incoming invoice. Each VAT category and rate must
+/// match a tax code in `tax_table`; standard and zero rated categories by their rate on the
+/// issue date, exempt and reverse charge categories by treatment alone.
+///
+pub fn draft_bill(
+    invoice: &SupplierInvoice,
+    ledger: &Ledger,
+    tax_table: &TaxTable,
+    options: &BillOptions,
+) -> Result<Transaction> {
+    let supplier = match &options.supplier {
+        Some(account) => account.clone(),
+        None => find_supplier_account(ledger, &invoice.supplier)
+            .ok_or_else(|| invalid_value("AccountingSupplierParty", &invoice.supplier.name))?,
+    };
+    let posted = invoice.issue_date.and_hms_opt(0, 0, 0).unwrap().and_utc();
+    let currency = CommodityId::Currency(invoice.currency);
+    let quantity = |value: Decimal| Quantity::new(currency.clone(), value);
+    let id = TransactionId::new(format!("{}/{}", supplier, invoice.id));
+
+    let mut draft = Transaction::new(id.clone(), posted)
+        .with_reference(invoice.id.clone())
+        .with_description(invoice.supplier.name.clone())
+        .with_metadata(IMPORT_ID, invoice.id.clone());
+    if let Some(due_date) = invoice.due_date {
+        draft = draft.with_metadata("due_date", due_date.to_string());
+    }
+
+    for line in &invoice.lines {
+        let code = matching_tax_code(tax_table, &line.category, line.percent, posted)?;
+        let mut split = Split::new(id.clone(), options.expense.clone(), quantity(line.amount));
+        if !line.name.is_empty() {
+            split = split.with_description(line.name.clone());
+        }
+        if let Some(code) = code {
+            split = split.with_tax_code(code.id().clone());
+        }
+        draft.add_split(split);
+    }
+    for subtotal in &invoice.tax_subtotals {
+        let Some(code) =
+            matching_tax_code(tax_table, &subtotal.category, subtotal.percent, posted)?
+        else {
+            continue;
+        };
+        let input = code.input_account().unwrap_or(&options.expense);
+        if code.treatment() == TaxTreatment::ReverseCharge {
+            // Self-assessed: account for the output tax and reclaim it as input tax.
+            let output = code
+                .output_account()
+                .ok_or_else(|| missing_tax_account(code.id().clone()))?;
+            let tax =
+                (subtotal.taxable * code.rate_on(posted)?).round_dp(minor_units(invoice.currency));
+            if !tax.is_zero() {
+                draft.add_split(
+                    Split::new(id.clone(), input.clone(), quantity(tax))
+                        .with_tax_code(code.id().clone()),
+                );
+                draft.add_split(
+                    Split::new(id.clone(), output.clone(), quantity(-tax))
+                        .with_tax_code(code.id().clone()),
+                );
+            }
+        } else if !subtotal.tax.is_zero() {
+            draft.add_split(
+                Split::new(id.clone(), input.clone(), quantity(subtotal.tax))
+                    .with_tax_code(code.id().clone()),
+            );
+        }
+    }
+    draft.add_split(Split::new(id, supplier, quantity(-invoice.tax_inclusive)));
+
+    if !draft.is_balanced() {
+        return Err(invalid_value(
+            "TaxInclusiveAmount",
+            invoice.tax_inclusive.to_string(),
+        ));
+    }
+    Ok(draft)
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+const CAC_NAMESPACE: &str =
+    "urn:oasis:names:specification:ubl:schema:xsd:CommonAggregateComponents-2";
+
+const CBC_NAMESPACE: &str = "urn:oasis:names:specification:ubl:schema:xsd:CommonBasicComponents-2";
+
+/// UNCL 1001 commercial invoice.
+const COMMERCIAL_INVOICE: &str = "380";
+
+/// UNCL 4461 SEPA credit transfer.
+const SEPA_CREDIT_TRANSFER: &str = "58";
+
+/// UNCL 5153 value added tax.
+const VAT_SCHEME: &str = "VAT";
+
+const DEFAULT_UNIT_CODE: &str = "C62";
+
+const REVERSE_CHARGE_REASON_CODE: &str = "VATEX-EU-AE";
+
+const REVERSE_CHARGE_REASON: &str = "Reverse charge";
+
+///
+/// An exemption reason code, if any, and text.
+///
+type ExemptionReason = Option<(Option<&'static str>, String)>;
+
+///
+/// The amounts of a sales invoice, calculated while it is validated; the category and
+/// percent of each line, in order, and the VAT subtotals with any exemption reason.
+///
+#[derive(Debug)]
+struct InvoiceTotals {
+    minor_units: u32,
+    categories: Vec<(&'static str, Decimal)>,
+    subtotals: Vec<(InvoiceTaxSubtotal, ExemptionReason)>,
+    net: Decimal,
+    tax: Decimal,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl<'a> InvoiceParty<'a> {
+    pub fn new(party: &'a Party, endpoint: PartyIdentifier) -> Self {
+        Self {
+            party,
+            endpoint,
+            identification: None,
+            vat_number: None,
+        }
+    }
+
+    ///
+    /// An identifier, such as a GLN, the party is known by other than its endpoint.
+    ///
+    pub fn with_identification(self, identification: PartyIdentifier) -> Self {
+        Self {
+            identification: Some(identification),
+            ..self
+        }
+    }
+
+    pub fn with_vat_number(self, vat_number: VatNumber) -> Self {
+        Self {
+            vat_number: Some(vat_number),
+            ..self
+        }
+    }
+
+    pub fn party(&self) -> &Party {
+        self.party
+    }
+
+    pub fn endpoint(&self) -> &PartyIdentifier {
+        &self.endpoint
+    }
+
+    pub fn identification(&self) -> Option<&PartyIdentifier> {
+        self.identification.as_ref()
+    }
+
+    pub fn vat_number(&self) -> Option<&VatNumber> {
+        self.vat_number.as_ref()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl SalesInvoiceLine {
+    pub fn new<S1, S2>(
+        id: S1,
+        name: S2,
+        quantity: Decimal,
+        price: Decimal,
+        tax_code: TaxCodeId,
+    ) -> Self
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+    {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            description: None,
+            quantity,
+            unit_code: DEFAULT_UNIT_CODE.to_string(),
+            price,
+            tax_code,
+        }
+    }
+
+    pub fn with_description<S: Into<String>>(self, description: S) -> Self {
+        Self {
+            description: Some(description.into()),
+            ..self
+        }
+    }
+
+    ///
+    /// The UN/ECE recommendation 20 code of the quantity's unit, by default `C62`, one.
+    ///
+    pub fn with_unit_code<S: Into<String>>(self, unit_code: S) -> Self {
+        Self {
+            unit_code: unit_code.into(),
+            ..self
+        }
+    }
+
+    pub fn id(&self) -> &String {
+        &self.id
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn description(&self) -> Option<&String> {
+        self.description.as_ref()
+    }
+
+    pub fn quantity(&self) -> Decimal {
+        self.quantity
+    }
+
+    pub fn unit_code(&self) -> &String {
+        &self.unit_code
+    }
+
+    pub fn price(&self) -> Decimal {
+        self.price
+    }
+
+    pub fn tax_code(&self) -> &TaxCodeId {
+        &self.tax_code
+    }
+
+    fn net(&self, minor_units: u32) -> Decimal {
+        (self.quantity * self.price).round_dp(minor_units)
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl<'a> SalesInvoice<'a> {
+    pub fn new<S: Into<String>>(
+        id: S,
+        issue_date: NaiveDate,
+        currency: CurrencyCode,
+        seller: InvoiceParty<'a>,
+        buyer: InvoiceParty<'a>,
+    ) -> Self {
+        Self {
+            id: id.into(),
+            issue_date,
+            due_date: None,
+            currency,
+            seller,
+            buyer,
+            buyer_reference: None,
+            order_reference: None,
+            note: None,
+            payee_account: None,
+            payment_id: None,
+            lines: Default::default(),
+        }
+    }
+
+    pub fn with_due_date(self, due_date: NaiveDate) -> Self {
+        Self {
+            due_date: Some(due_date),
+            ..self
+        }
+    }
+
+    pub fn with_buyer_reference<S: Into<String>>(self, buyer_reference: S) -> Self {
+        Self {
+            buyer_reference: Some(buyer_reference.into()),
+            ..self
+        }
+    }
+
+    pub fn with_order_reference<S: Into<String>>(self, order_reference: S) -> Self {
+        Self {
+            order_reference: Some(order_reference.into()),
+            ..self
+        }
+    }
+
+    pub fn with_note<S: Into<String>>(self, note: S) -> Self {
+        Self {
+            note: Some(note.into()),
+            ..self
+        }
+    }
+
+    ///
+    /// Request payment by credit transfer to the seller's account, quoting `payment_id` if
+    /// given.
+    ///
+    pub fn with_payment<S: Into<String>>(self, iban: S, payment_id: Option<String>) -> Self {
+        Self {
+            payee_account: Some(iban.into()),
+            payment_id,
+            ..self
+        }
+    }
+
+    pub fn with_line(mut self, line: SalesInvoiceLine) -> Self {
+        self.add_line(line);
+        self
+    }
+
+    pub fn id(&self) -> &String {
+        &self.id
+    }
+
+    pub fn issue_date(&self) -> NaiveDate {
+        self.issue_date
+    }
+
+    pub fn due_date(&self) -> Option<NaiveDate> {
+        self.due_date
+    }
+
+    pub fn currency(&self) -> CurrencyCode {
+        self.currency
+    }
+
+    pub fn seller(&self) -> &InvoiceParty<'a> {
+        &self.seller
+    }
+
+    pub fn buyer(&self) -> &InvoiceParty<'a> {
+        &self.buyer
+    }
+
+    pub fn buyer_reference(&self) -> Option<&String> {
+        self.buyer_reference.as_ref()
+    }
+
+    pub fn order_reference(&self) -> Option<&String> {
+        self.order_reference.as_ref()
+    }
+
+    pub fn note(&self) -> Option<&String> {
+        self.note.as_ref()
+    }
+
+    pub fn payee_account(&self) -> Option<&String> {
+        self.payee_account.as_ref()
+    }
+
+    pub fn payment_id(&self) -> Option<&String> {
+        self.payment_id.as_ref()
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = &SalesInvoiceLine> {
+        self.lines.iter()
+    }
+
+    pub fn add_line(&mut self, line: SalesInvoiceLine) {
+        self.lines.push(line);
+    }
+
+    ///
+    /// The VAT of each category and rate, in the order first used by a line.
+    ///
+    pub fn tax_subtotals(&self, tax_table: &TaxTable) -> Result<Vec<InvoiceTaxSubtotal>> {
+        Ok(self
+            .totals(tax_table)?
+            .subtotals
+            .into_iter()
+            .map(|(subtotal, _)| subtotal)
+            .collect())
+    }
+
+    fn validate(&self, tax_table: &TaxTable) -> Result<InvoiceTotals> {
+        if self.lines.is_empty() {
+            return Err(missing_element("Invoice", "InvoiceLine"));
+        }
+        if self.buyer_reference.is_none() && self.order_reference.is_none() {
+            return Err(missing_element("Invoice", "BuyerReference"));
+        }
+        for party in [&self.seller, &self.buyer] {
+            if !party.endpoint.scheme().is_valid() {
+                return Err(invalid_value(
+                    "EndpointID/@schemeID",
+                    party.endpoint.scheme().as_str(),
+                ));
+            }
+        }
+        let totals = self.totals(tax_table)?;
+        let has_category = |categories: &[&str]| {
+            totals
+                .categories
+                .iter()
+                .any(|(category, _)| categories.contains(category))
+        };
+        if has_category(&["S", "Z", "E", "AE"]) && self.seller.vat_number.is_none() {
+            return Err(missing_element("AccountingSupplierParty", "PartyTaxScheme"));
+        }
+        if has_category(&["AE"]) && self.buyer.vat_number.is_none() {
+            return Err(missing_element("AccountingCustomerParty", "PartyTaxScheme"));
+        }
+        Ok(totals)
+    }
+
+    fn totals(&self, tax_table: &TaxTable) -> Result<InvoiceTotals> {
+        let minor_units = minor_units(self.currency);
+        let on = self.issue_date.and_hms_opt(0, 0, 0).unwrap().and_utc();
+        let mut totals = InvoiceTotals {
+            minor_units,
+            categories: Default::default(),
+            subtotals: Default::default(),
+            net: Decimal::ZERO,
+            tax: Decimal::ZERO,
+        };
+        for line in &self.lines {
+            let code = tax_table
+                .get(&line.tax_code)
+                .ok_or_else(|| invalid_value("tax code", line.tax_code.to_string()))?;
+            let (category, percent, reason): (_, _, ExemptionReason) = match code.treatment() {
+                TaxTreatment::Standard => (
+                    "S",
+                    (code
+                        .rate_on(on)
+                        .map_err(|_| invalid_value("tax rate", code.id().to_string()))?
+                        * Decimal::ONE_HUNDRED)
+                        .normalize(),
+                    None,
+                ),
+                TaxTreatment::ZeroRated => ("Z", Decimal::ZERO, None),
+                TaxTreatment::Exempt => ("E", Decimal::ZERO, Some((None, code.name().clone()))),
+                TaxTreatment::ReverseCharge => (
+                    "AE",
+                    Decimal::ZERO,
+                    Some((
+                        Some(REVERSE_CHARGE_REASON_CODE),
+                        REVERSE_CHARGE_REASON.to_string(),
+                    )),
+                ),
+            };
+            let net = line.net(minor_units);
+            totals.net += net;
+            totals.categories.push((category, percent));
+            match totals
+                .subtotals
+                .iter_mut()
+                .find(|(s, _)| s.category == category && s.percent == percent)
+            {
+                Some((subtotal, _)) => subtotal.taxable += net,
+                None => totals.subtotals.push((
+                    InvoiceTaxSubtotal {
+                        category: category.to_string(),
+                        percent,
+                        taxable: net,
+                        tax: Decimal::ZERO,
+                    },
+                    reason,
+                )),
+            }
+        }
+        for (subtotal, _) in &mut totals.subtotals {
+            subtotal.tax =
+                (subtotal.taxable * subtotal.percent / Decimal::ONE_HUNDRED).round_dp(minor_units);
+            totals.tax += subtotal.tax;
+        }
+        Ok(totals)
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl SupplierParty {
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn endpoint(&self) -> Option<&PartyIdentifier> {
+        self.endpoint.as_ref()
+    }
+
+    ///
+    /// The party's identifications and legal registration, other than its endpoint.
+    ///
+    pub fn identifiers(&self) -> impl Iterator<Item = &PartyIdentifier> {
+        self.identifiers.iter()
+    }
+
+    pub fn vat_number(&self) -> Option<&VatNumber> {
+        self.vat_number.as_ref()
+    }
+
+    ///
+    /// The party identifier of the supplier, from the first of its identifiers with a scheme
+    /// that party identifiers use.
+    ///
+    pub fn party_id(&self) -> Option<PartyId> {
+        self.endpoint
+            .iter()
+            .chain(self.identifiers.iter())
+            .find_map(PartyIdentifier::to_party_id)
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl SupplierInvoiceLine {
+    pub fn id(&self) -> &String {
+        &self.id
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn quantity(&self) -> Decimal {
+        self.quantity
+    }
+
+    ///
+    /// The net amount of the line, excluding VAT.
+    ///
+    pub fn amount(&self) -> Decimal {
+        self.amount
+    }
+
+    pub fn category(&self) -> &String {
+        &self.category
+    }
+
+    pub fn percent(&self) -> Decimal {
+        self.percent
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl InvoiceTaxSubtotal {
+    ///
+    /// The UNCL 5305 VAT category code.
+    ///
+    pub fn category(&self) -> &String {
+        &self.category
+    }
+
+    pub fn percent(&self) -> Decimal {
+        self.percent
+    }
+
+    pub fn taxable(&self) -> Decimal {
+        self.taxable
+    }
+
+    pub fn tax(&self) -> Decimal {
+        self.tax
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl SupplierInvoice {
+    pub fn id(&self) -> &String {
+        &self.id
+    }
+
+    pub fn issue_date(&self) -> NaiveDate {
+        self.issue_date
+    }
+
+    pub fn due_date(&self) -> Option<NaiveDate> {
+        self.due_date
+    }
+
+    pub fn currency(&self) -> CurrencyCode {
+        self.currency
+    }
+
+    pub fn supplier(&self) -> &SupplierParty {
+        &self.supplier
+    }
+
+    pub fn buyer_reference(&self) -> Option<&String> {
+        self.buyer_reference.as_ref()
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = &SupplierInvoiceLine> {
+        self.lines.iter()
+    }
+
+    pub fn tax_subtotals(&self) -> impl Iterator<Item = &InvoiceTaxSubtotal> {
+        self.tax_subtotals.iter()
+    }
+
+    pub fn tax_exclusive(&self) -> Decimal {
+        self.tax_exclusive
+    }
+
+    pub fn tax_inclusive(&self) -> Decimal {
+        self.tax_inclusive
+    }
+
+    ///
+    /// The amount due, after any prepayment and rounding.
+    ///
+    pub fn payable(&self) -> Decimal {
+        self.payable
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl BillOptions {
+    pub fn new(expense: AccountId) -> Self {
+        Self {
+            expense,
+            supplier: None,
+        }
+    }
+
+    pub fn with_supplier(self, supplier: AccountId) -> Self {
+        Self {
+            supplier: Some(supplier),
+            ..self
+        }
+    }
+
+    pub fn expense(&self) -> &AccountId {
+        &self.expense
+    }
+
+    pub fn supplier(&self) -> Option<&AccountId> {
+        self.supplier.as_ref()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+fn write_party<W: Write>(
+    xml: &mut XmlWriter<'_, W>,
+    invoice_party: &InvoiceParty<'_>,
+) -> Result<()> {
+    let party = invoice_party.party;
+    let address = party.mailing_address();
+    xml.start("cac:Party")?;
+    xml.element_with(
+        "cbc:EndpointID",
+        &[("schemeID", invoice_party.endpoint.scheme().as_str())],
+        invoice_party.endpoint.id(),
+    )?;
+    if let Some(identification) = &invoice_party.identification {
+        xml.start("cac:PartyIdentification")?;
+        xml.element_with(
+            "cbc:ID",
+            &[("schemeID", identification.scheme().as_str())],
+            identification.id(),
+        )?;
+        xml.end()?;
+    }
+    xml.start("cac:PartyName")?;
+    xml.element("cbc:Name", party.name())?;
+    xml.end()?;
+
+    xml.start("cac:PostalAddress")?;
+    let street = format!("{} {}", address.street_name(), address.street_number());
+    if !street.trim().is_empty() {
+        xml.element("cbc:StreetName", street.trim())?;
+    }
+    xml.optional("cbc:AdditionalStreetName", address.po_box())?;
+    xml.element("cbc:CityName", address.city_or_town())?;
+    xml.element("cbc:PostalZone", address.postal_code())?;
+    if !address.province_or_state().is_empty() {
+        xml.element("cbc:CountrySubentity", address.province_or_state())?;
+    }
+    xml.start("cac:Country")?;
+    xml.element("cbc:IdentificationCode", address.country().as_str())?;
+    xml.end()?;
+    xml.end()?;
+
+    if let Some(vat_number) = &invoice_party.vat_number {
+        xml.start("cac:PartyTaxScheme")?;
+        xml.element("cbc:CompanyID", vat_number.as_str())?;
+        write_tax_scheme(xml)?;
+        xml.end()?;
+    }
+    xml.start("cac:PartyLegalEntity")?;
+    xml.element("cbc:RegistrationName", party.name())?;
+    if let Some(legal) = PartyIdentifier::from_party_id(party.id()) {
+        xml.element_with(
+            "cbc:CompanyID",
+            &[("schemeID", legal.scheme().as_str())],
+            legal.id(),
+        )?;
+    }
+    xml.end()?;
+    xml.end()
+}
+
+fn write_tax_scheme<W: Write>(xml: &mut XmlWriter<'_, W>) -> Result<()> {
+    xml.start("cac:TaxScheme")?;
+    xml.element("cbc:ID", VAT_SCHEME)?;
+    xml.end()
+}
+
+fn read_party(node: Node<'_, '_>) -> SupplierParty {
+    let identifier = |node: Node<'_, '_>| {
+        let id = node.text().map(str::trim).filter(|id| !id.is_empty())?;
+        node.attribute("schemeID")
+            .map(|scheme| PartyIdentifier::new(scheme.into(), id))
+    };
+    SupplierParty {
+        name: text(node, &["PartyLegalEntity", "RegistrationName"])
+            .or_else(|| text(node, &["PartyName", "Name"]))
+            .unwrap_or_default()
+            .to_string(),
+        endpoint: child(node, "EndpointID").and_then(identifier),
+        identifiers: children(node, "PartyIdentification")
+            .filter_map(|identification| child(identification, "ID"))
+            .chain(find(node, &["PartyLegalEntity", "CompanyID"]))
+            .filter_map(identifier)
+            .collect(),
+        vat_number: children(node, "PartyTaxScheme")
+            .find(|scheme| text(*scheme, &["TaxScheme", "ID"]) == Some(VAT_SCHEME))
+            .and_then(|scheme| text(scheme, &["CompanyID"]))
+            .map(VatNumber::from),
+    }
+}
+
+fn find_supplier_account(ledger: &Ledger, supplier: &SupplierParty) -> Option<AccountId> {
+    let party = supplier.party_id()?;
+    ledger
+        .book()
+        .find(|account| match account.represents() {
+            Some(AccountRepresents::Supplier(represents)) => represents.party() == &party,
+            _ => false,
+        })
+        .map(|account| account.id().clone())
+}
+
+///
+/// The tax code for a VAT category and rate; `None` for category `O`, services outside the
+/// scope of tax.
+///
+fn matching_tax_code<'a>(
+    tax_table: &'a TaxTable,
+    category: &str,
+    percent: Decimal,
+    on: DateTime<Utc>,
+) -> Result<Option<&'a TaxCode>> {
+    let rate = percent / Decimal::ONE_HUNDRED;
+    let matches = |code: &&TaxCode| match (category, code.treatment()) {
+        ("S", TaxTreatment::Standard) => code.rate_on(on).map(|r| r == rate).unwrap_or_default(),
+        ("Z", TaxTreatment::ZeroRated) => true,
+        ("E", TaxTreatment::Exempt) => true,
+        ("AE" | "K", TaxTreatment::ReverseCharge) => true,
+        _ => false,
+    };
+    match category {
+        "O" => Ok(None),
+        _ => tax_table
+            .codes()
+            .find(matches)
+            .map(Some)
+            .ok_or_else(|| invalid_value("TaxCategory", format!("{} {}", category, percent))),
+    }
+}
+
+fn tax_category(node: Node<'_, '_>, path: &[&str]) -> Result<(String, Decimal)> {
+    let category =
+        find(node, path).ok_or_else(|| missing_element(node.tag_name().name(), path.join("/")))?;
+    Ok((
+        required(category, &["ID"])?.to_string(),
+        text(category, &["Percent"])
+            .map(|value| parse_decimal("Percent", value))
+            .transpose()?
+            .unwrap_or_default(),
+    ))
+}
+
+fn amount(node: Node<'_, '_>, name: &str) -> Result<Decimal> {
+    parse_decimal(name, required(node, &[name])?)
+}
+
+fn minor_units(currency: CurrencyCode) -> u32 {
+    currency.monetary_units().unwrap_or(2) as u32
+}
+
+fn format_amount(amount: Decimal, minor_units: u32) -> String {
+    let mut amount = amount.round_dp(minor_units);
+    amount.rescale(minor_units);
+    amount.to_string()
+}
+
+fn parse_decimal(name: &str, value: &str) -> Result<Decimal> {
+    Decimal::from_str(value).map_err(|_| invalid_value(name, value))
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| invalid_value("date", value))
+}
+
+fn required<'a>(node: Node<'a, '_>, path: &[&str]) -> Result<&'a str> {
+    text(node, path).ok_or_else(|| missing_element(node.tag_name().name(), path.join("/")))
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children()
+        .find(|child| child.is_element() && child.tag_name().name() == name)
+}
+
+fn children<'a, 'input: 'a>(
+    node: Node<'a, 'input>,
+    name: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> + 'a {
+    node.children()
+        .filter(move |child| child.is_element() && child.tag_name().name() == name)
+}
+
+fn find<'a, 'input>(node: Node<'a, 'input>, path: &[&str]) -> Option<Node<'a, 'input>> {
+    path.iter().try_fold(node, |node, name| child(node, name))
+}
+
+fn text<'a>(node: Node<'a, '_>, path: &[&str]) -> Option<&'a str> {
+    find(node, path)
+        .and_then(|node| node.text())
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+}
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
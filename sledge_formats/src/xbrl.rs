@@ -0,0 +1,577 @@
+/*!
+Export a ledger's balance sheet and income statement as an XBRL 2.1 instance document, or as
+an Inline XBRL (iXBRL) 1.1 XHTML document for filings that must be human readable.
+
+Each line of the statements is reported against the taxonomy concept that the ledger's
+[`ConceptMapping`] maps its account to, and lines that map to the same concept are reported
+as a single fact with their total. Balance sheet facts, and current earnings, are reported
+for the instant of the balance sheet date and income statement facts for the duration of the
+statement's period. Amounts are in the ledger's currency, in the account kind's normal sign,
+to the currency's minor units.
+
+The mapping is validated before anything is written; every account with a balance on either
+statement must map to exactly one concept, and any current earnings must have a concept of
+their own. The taxonomy itself is not read, so the concepts are not checked against it.
+
+The reporting entity is identified by its [`PartyId`]; a legal entity by its LEI, and a
+business by its D-U-N-S number.
+
+*/
+
+use crate::error::{invalid_value, missing_element, Result};
+use crate::xml::XmlWriter;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use sledge_model::ledger::{AccountId, Ledger};
+use sledge_model::party::{Party, PartyId};
+use sledge_model::statement::{BalanceSheet, ConceptMapping, IncomeStatement, StatementLine};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use tracing::info;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// The XML namespace of XBRL 2.1 instance documents.
+///
+pub const XBRLI_NAMESPACE: &str = "http://www.xbrl.org/2003/instance";
+
+///
+/// The XML namespace of Inline XBRL 1.1.
+///
+pub const IX_NAMESPACE: &str = "http://www.xbrl.org/2013/inlineXBRL";
+
+///
+/// What to export; the reporting entity, its ledger, and the statements prepared from it.
+///
+#[derive(Clone, Debug)]
+pub struct XbrlReport<'a> {
+    entity: &'a Party,
+    ledger: &'a Ledger,
+    balance_sheet: &'a BalanceSheet,
+    income_statement: &'a IncomeStatement,
+    title: Option<String>,
+}
+
+///
+/// The section of the statements that a fact is reported in.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum XbrlSection {
+    Assets,
+    Liabilities,
+    Equity,
+    Income,
+    Expenses,
+}
+
+///
+/// The value of one concept; the total of the statement lines mapped to it.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct XbrlFact {
+    concept: String,
+    section: XbrlSection,
+    label: String,
+    accounts: Vec<AccountId>,
+    amount: Decimal,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+pub fn write_xbrl_file<P: AsRef<Path>>(path: P, report: &XbrlReport<'_>) -> Result<()> {
+    info!("Writing XBRL instance file {:?}", path.as_ref());
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_xbrl(&mut writer, report)?;
+    writer.flush()?;
+    Ok(())
+}
+
+///
+/// Write the instance document; the mapping is validated before anything is written.
+///
+pub fn write_xbrl<W: Write>(writer: &mut W, report: &XbrlReport<'_>) -> Result<()> {
+    let contents = Contents::new(report)?;
+    let mut xml = XmlWriter::new(writer)?;
+    let prefix = format!("xmlns:{}", contents.mapping.prefix());
+    xml.start_with(
+        "xbrli:xbrl",
+        &[
+            ("xmlns:xbrli", XBRLI_NAMESPACE),
+            ("xmlns:link", LINK_NAMESPACE),
+            ("xmlns:xlink", XLINK_NAMESPACE),
+            ("xmlns:iso4217", ISO4217_NAMESPACE),
+            (prefix.as_str(), contents.mapping.namespace().as_str()),
+        ],
+    )?;
+    write_schema_ref(&mut xml, contents.mapping)?;
+    write_contexts(&mut xml, &contents)?;
+    write_unit(&mut xml, &contents)?;
+    let decimals = contents.decimals.to_string();
+    for fact in &contents.facts {
+        xml.element_with(
+            &contents.concept_name(fact),
+            &[
+                ("contextRef", contents.context_for(fact)),
+                ("unitRef", contents.unit.as_str()),
+                ("decimals", decimals.as_str()),
+            ],
+            contents.format(fact.amount),
+        )?;
+    }
+    xml.finish()
+}
+
+pub fn write_ixbrl_file<P: AsRef<Path>>(path: P, report: &XbrlReport<'_>) -> Result<()> {
+    info!("Writing inline XBRL file {:?}", path.as_ref());
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_ixbrl(&mut writer, report)?;
+    writer.flush()?;
+    Ok(())
+}
+
+///
+/// Write the statements as an XHTML document with each fact tagged inline; the contexts and
+/// unit are in a hidden header. The mapping is validated before anything is written.
+///
+pub fn write_ixbrl<W: Write>(writer: &mut W, report: &XbrlReport<'_>) -> Result<()> {
+    let contents = Contents::new(report)?;
+    let mut xml = XmlWriter::new(writer)?;
+    let prefix = format!("xmlns:{}", contents.mapping.prefix());
+    xml.start_with(
+        "html",
+        &[
+            ("xmlns", XHTML_NAMESPACE),
+            ("xmlns:ix", IX_NAMESPACE),
+            ("xmlns:ixt", IXT_NAMESPACE),
+            ("xmlns:xbrli", XBRLI_NAMESPACE),
+            ("xmlns:link", LINK_NAMESPACE),
+            ("xmlns:xlink", XLINK_NAMESPACE),
+            ("xmlns:iso4217", ISO4217_NAMESPACE),
+            (prefix.as_str(), contents.mapping.namespace().as_str()),
+        ],
+    )?;
+    let title = report
+        .title
+        .clone()
+        .unwrap_or_else(|| report.entity.name().clone());
+    xml.start("head")?;
+    xml.element("title", &title)?;
+    xml.end()?;
+
+    xml.start("body")?;
+    xml.start_with("div", &[("style", "display:none")])?;
+    xml.start("ix:header")?;
+    xml.start("ix:references")?;
+    write_schema_ref(&mut xml, contents.mapping)?;
+    xml.end()?;
+    xml.start("ix:resources")?;
+    write_contexts(&mut xml, &contents)?;
+    write_unit(&mut xml, &contents)?;
+    xml.end()?;
+    xml.end()?;
+    xml.end()?;
+
+    xml.element("h1", &title)?;
+    xml.element("h2", "Balance sheet")?;
+    xml.element("p", format!("As at {}", contents.instant))?;
+    xml.start("table")?;
+    write_ixbrl_section(&mut xml, &contents, "Assets", XbrlSection::Assets)?;
+    write_total(
+        &mut xml,
+        &contents,
+        "Total assets",
+        report.balance_sheet.total_assets(),
+    )?;
+    write_ixbrl_section(&mut xml, &contents, "Liabilities", XbrlSection::Liabilities)?;
+    write_ixbrl_section(&mut xml, &contents, "Equity", XbrlSection::Equity)?;
+    write_total(
+        &mut xml,
+        &contents,
+        "Total liabilities and equity",
+        report.balance_sheet.total_liabilities() + report.balance_sheet.total_equity(),
+    )?;
+    xml.end()?;
+
+    xml.element("h2", "Income statement")?;
+    xml.element(
+        "p",
+        format!("For the period {} to {}", contents.start, contents.end),
+    )?;
+    xml.start("table")?;
+    for (heading, section) in [
+        ("Income", XbrlSection::Income),
+        ("Expenses", XbrlSection::Expenses),
+    ] {
+        write_ixbrl_section(&mut xml, &contents, heading, section)?;
+    }
+    write_total(
+        &mut xml,
+        &contents,
+        "Net income",
+        report.income_statement.net_income(),
+    )?;
+    xml.finish()
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+const LINK_NAMESPACE: &str = "http://www.xbrl.org/2003/linkbase";
+
+const XLINK_NAMESPACE: &str = "http://www.w3.org/1999/xlink";
+
+const ISO4217_NAMESPACE: &str = "http://www.xbrl.org/2003/iso4217";
+
+const XHTML_NAMESPACE: &str = "http://www.w3.org/1999/xhtml";
+
+const IXT_NAMESPACE: &str = "http://www.xbrl.org/inlineXBRL/transformation/2020-02-12";
+
+const LEI_SCHEME: &str = "http://standards.iso.org/iso/17442";
+
+const DUNS_SCHEME: &str = "http://www.dnb.com/duns";
+
+const CURRENT_EARNINGS_LABEL: &str = "Current earnings";
+
+///
+/// Everything needed to write either document, checked and calculated up front.
+///
+#[derive(Debug)]
+struct Contents<'a> {
+    mapping: &'a ConceptMapping,
+    scheme: &'static str,
+    identifier: String,
+    instant: NaiveDate,
+    start: NaiveDate,
+    end: NaiveDate,
+    instant_context: String,
+    duration_context: String,
+    unit: String,
+    decimals: u32,
+    facts: Vec<XbrlFact>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl<'a> XbrlReport<'a> {
+    pub fn new(
+        entity: &'a Party,
+        ledger: &'a Ledger,
+        balance_sheet: &'a BalanceSheet,
+        income_statement: &'a IncomeStatement,
+    ) -> Self {
+        Self {
+            entity,
+            ledger,
+            balance_sheet,
+            income_statement,
+            title: None,
+        }
+    }
+
+    ///
+    /// The title of the inline document, by default the entity's name.
+    ///
+    pub fn with_title<S: Into<String>>(self, title: S) -> Self {
+        Self {
+            title: Some(title.into()),
+            ..self
+        }
+    }
+
+    ///
+    /// The facts that will be reported, balance sheet facts first; this fails if the
+    /// ledger has no mapping or the mapping is not valid for the statements.
+    ///
+    pub fn facts(&self) -> Result<Vec<XbrlFact>> {
+        let mapping = self.mapping()?;
+        mapping.validate(self.ledger, self.balance_sheet, self.income_statement)?;
+
+        let mut facts: Vec<XbrlFact> = Default::default();
+        let balance_sheet = self.balance_sheet;
+        let income_statement = self.income_statement;
+        for (section, lines) in [
+            (
+                XbrlSection::Assets,
+                balance_sheet.assets().collect::<Vec<_>>(),
+            ),
+            (
+                XbrlSection::Liabilities,
+                balance_sheet.liabilities().collect(),
+            ),
+            (XbrlSection::Equity, balance_sheet.equity().collect()),
+            (XbrlSection::Income, income_statement.income().collect()),
+            (XbrlSection::Expenses, income_statement.expenses().collect()),
+        ] {
+            for line in lines {
+                let concept = mapping.concept_for(self.ledger, line.account())?;
+                add_fact(&mut facts, concept, section, Some(line), line.amount());
+            }
+            if section == XbrlSection::Equity {
+                if let Some(concept) = mapping.current_earnings() {
+                    add_fact(
+                        &mut facts,
+                        concept,
+                        section,
+                        None,
+                        balance_sheet.current_earnings(),
+                    );
+                }
+            }
+        }
+        Ok(facts)
+    }
+
+    fn mapping(&self) -> Result<&'a ConceptMapping> {
+        self.ledger
+            .concept_mapping()
+            .ok_or_else(|| missing_element(self.ledger.description().as_str(), "concept mapping"))
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl XbrlFact {
+    ///
+    /// The concept's local name, within the mapping's taxonomy namespace.
+    ///
+    pub fn concept(&self) -> &String {
+        &self.concept
+    }
+
+    pub fn section(&self) -> XbrlSection {
+        self.section
+    }
+
+    ///
+    /// The descriptions of the accounts reported, for the inline document.
+    ///
+    pub fn label(&self) -> &String {
+        &self.label
+    }
+
+    pub fn accounts(&self) -> impl Iterator<Item = &AccountId> {
+        self.accounts.iter()
+    }
+
+    pub fn amount(&self) -> Decimal {
+        self.amount
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl XbrlSection {
+    ///
+    /// Returns `true` for the balance sheet sections, whose facts are reported for an
+    /// instant rather than a duration.
+    ///
+    pub fn is_instant(&self) -> bool {
+        matches!(self, Self::Assets | Self::Liabilities | Self::Equity)
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl<'a> Contents<'a> {
+    fn new(report: &XbrlReport<'a>) -> Result<Self> {
+        let mapping = report.mapping()?;
+        let facts = report.facts()?;
+        let (scheme, identifier) = match report.entity.id() {
+            PartyId::LegalEntity(_) => (LEI_SCHEME, report.entity.id().as_str()),
+            PartyId::Business(_) => (DUNS_SCHEME, report.entity.id().as_str()),
+            PartyId::Person(_) => {
+                return Err(invalid_value("entity", report.entity.id().to_string()))
+            }
+        };
+        let currency = report.ledger.currency();
+        let instant = last_day_before(report.balance_sheet.as_at());
+        let start = report.income_statement.from().date_naive();
+        let end = last_day_before(report.income_statement.until());
+        Ok(Self {
+            mapping,
+            scheme,
+            identifier: identifier.to_string(),
+            instant,
+            start,
+            end,
+            instant_context: format!("I{}", instant),
+            duration_context: format!("D{}_{}", start, end),
+            unit: currency.alpha_code().to_string(),
+            decimals: currency.monetary_units().unwrap_or(2) as u32,
+            facts,
+        })
+    }
+
+    fn concept_name(&self, fact: &XbrlFact) -> String {
+        format!("{}:{}", self.mapping.prefix(), fact.concept)
+    }
+
+    fn context_for(&self, fact: &XbrlFact) -> &str {
+        if fact.section.is_instant() {
+            &self.instant_context
+        } else {
+            &self.duration_context
+        }
+    }
+
+    fn format(&self, amount: Decimal) -> String {
+        let mut amount = amount.round_dp(self.decimals);
+        amount.rescale(self.decimals);
+        amount.to_string()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+fn add_fact(
+    facts: &mut Vec<XbrlFact>,
+    concept: &str,
+    section: XbrlSection,
+    line: Option<&StatementLine>,
+    amount: Decimal,
+) {
+    let label = line
+        .map(|line| line.description().as_str())
+        .unwrap_or(CURRENT_EARNINGS_LABEL);
+    match facts
+        .iter_mut()
+        .find(|fact| fact.concept == concept && fact.section.is_instant() == section.is_instant())
+    {
+        Some(fact) => {
+            fact.label = format!("{}; {}", fact.label, label);
+            fact.amount += amount;
+            if let Some(line) = line {
+                fact.accounts.push(line.account().clone());
+            }
+        }
+        None => facts.push(XbrlFact {
+            concept: concept.to_string(),
+            section,
+            label: label.to_string(),
+            accounts: line
+                .map(|line| line.account().clone())
+                .into_iter()
+                .collect(),
+            amount,
+        }),
+    }
+}
+
+///
+/// Statements are prepared up to, but not including, a point in time, while XBRL periods
+/// end at the end of a day.
+///
+fn last_day_before(until: DateTime<Utc>) -> NaiveDate {
+    (until - Duration::nanoseconds(1)).date_naive()
+}
+
+fn write_schema_ref<W: Write>(xml: &mut XmlWriter<'_, W>, mapping: &ConceptMapping) -> Result<()> {
+    xml.element_with(
+        "link:schemaRef",
+        &[
+            ("xlink:type", "simple"),
+            ("xlink:href", mapping.schema_ref()),
+        ],
+        "",
+    )
+}
+
+fn write_contexts<W: Write>(xml: &mut XmlWriter<'_, W>, contents: &Contents<'_>) -> Result<()> {
+    for (id, instant) in [
+        (&contents.instant_context, true),
+        (&contents.duration_context, false),
+    ] {
+        xml.start_with("xbrli:context", &[("id", id.as_str())])?;
+        xml.start("xbrli:entity")?;
+        xml.element_with(
+            "xbrli:identifier",
+            &[("scheme", contents.scheme)],
+            &contents.identifier,
+        )?;
+        xml.end()?;
+        xml.start("xbrli:period")?;
+        if instant {
+            xml.element("xbrli:instant", contents.instant)?;
+        } else {
+            xml.element("xbrli:startDate", contents.start)?;
+            xml.element("xbrli:endDate", contents.end)?;
+        }
+        xml.end()?;
+        xml.end()?;
+    }
+    Ok(())
+}
+
+fn write_unit<W: Write>(xml: &mut XmlWriter<'_, W>, contents: &Contents<'_>) -> Result<()> {
+    xml.start_with("xbrli:unit", &[("id", contents.unit.as_str())])?;
+    xml.element("xbrli:measure", format!("iso4217:{}", contents.unit))?;
+    xml.end()
+}
+
+fn write_ixbrl_section<W: Write>(
+    xml: &mut XmlWriter<'_, W>,
+    contents: &Contents<'_>,
+    heading: &str,
+    section: XbrlSection,
+) -> Result<()> {
+    xml.start("tr")?;
+    xml.element_with("th", &[("colspan", "2")], heading)?;
+    xml.end()?;
+    for fact in contents.facts.iter().filter(|fact| fact.section == section) {
+        let decimals = contents.decimals.to_string();
+        let name = contents.concept_name(fact);
+        let mut attributes = vec![
+            ("name", name.as_str()),
+            ("contextRef", contents.context_for(fact)),
+            ("unitRef", contents.unit.as_str()),
+            ("decimals", decimals.as_str()),
+            ("format", "ixt:num-dot-decimal"),
+        ];
+        // inline values are written without a sign, which is given by an attribute instead.
+        if fact.amount < Decimal::ZERO {
+            attributes.push(("sign", "-"));
+        }
+        xml.start("tr")?;
+        xml.element("td", &fact.label)?;
+        xml.start("td")?;
+        xml.element_with(
+            "ix:nonFraction",
+            &attributes,
+            contents.format(fact.amount.abs()),
+        )?;
+        xml.end()?;
+        xml.end()?;
+    }
+    Ok(())
+}
+
+fn write_total<W: Write>(
+    xml: &mut XmlWriter<'_, W>,
+    contents: &Contents<'_>,
+    label: &str,
+    amount: Decimal,
+) -> Result<()> {
+    xml.start("tr")?;
+    xml.element("th", label)?;
+    xml.element("th", contents.format(amount))?;
+    xml.end()
+}
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
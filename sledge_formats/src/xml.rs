@@ -0,0 +1,143 @@
+/*!
+A minimal, indenting, XML writer shared by the exporters of XML formats.
+
+*/
+
+use crate::error::Result;
+use std::fmt::Display;
+use std::io::Write;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// Writes elements, one per line, indented by their depth; text and attribute values are
+/// escaped, element and attribute names are written as given.
+///
+#[derive(Debug)]
+pub(crate) struct XmlWriter<'a, W: Write> {
+    writer: &'a mut W,
+    open: Vec<String>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+///
+/// Escape the characters that may not appear literally in text or attribute values.
+///
+pub(crate) fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl<'a, W: Write> XmlWriter<'a, W> {
+    ///
+    /// Start a document, writing the XML declaration.
+    ///
+    pub(crate) fn new(writer: &'a mut W) -> Result<Self> {
+        writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        Ok(Self {
+            writer,
+            open: Default::default(),
+        })
+    }
+
+    pub(crate) fn start(&mut self, name: &str) -> Result<()> {
+        self.start_with(name, &[])
+    }
+
+    pub(crate) fn start_with(&mut self, name: &str, attributes: &[(&str, &str)]) -> Result<()> {
+        self.indent()?;
+        writeln!(self.writer, "<{}{}>", name, format_attributes(attributes))?;
+        self.open.push(name.to_string());
+        Ok(())
+    }
+
+    ///
+    /// Close the most recently started element.
+    ///
+    pub(crate) fn end(&mut self) -> Result<()> {
+        let name = self.open.pop().expect("no element to end");
+        self.indent()?;
+        writeln!(self.writer, "</{}>", name)?;
+        Ok(())
+    }
+
+    pub(crate) fn element<V: Display>(&mut self, name: &str, value: V) -> Result<()> {
+        self.element_with(name, &[], value)
+    }
+
+    pub(crate) fn element_with<V: Display>(
+        &mut self,
+        name: &str,
+        attributes: &[(&str, &str)],
+        value: V,
+    ) -> Result<()> {
+        self.indent()?;
+        writeln!(
+            self.writer,
+            "<{0}{1}>{2}</{0}>",
+            name,
+            format_attributes(attributes),
+            escape(&value.to_string())
+        )?;
+        Ok(())
+    }
+
+    ///
+    /// Write the element only if there is a value for it.
+    ///
+    pub(crate) fn optional<V: Display>(&mut self, name: &str, value: Option<V>) -> Result<()> {
+        match value {
+            Some(value) => self.element(name, value),
+            None => Ok(()),
+        }
+    }
+
+    ///
+    /// End any elements still open.
+    ///
+    pub(crate) fn finish(mut self) -> Result<()> {
+        while !self.open.is_empty() {
+            self.end()?;
+        }
+        Ok(())
+    }
+
+    fn indent(&mut self) -> Result<()> {
+        write!(self.writer, "{:1$}", "", self.open.len() * 2)?;
+        Ok(())
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+fn format_attributes(attributes: &[(&str, &str)]) -> String {
+    attributes
+        .iter()
+        .map(|(name, value)| format!(r#" {}="{}""#, name, escape(value)))
+        .collect()
+}
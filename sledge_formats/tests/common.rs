@@ -0,0 +1,20 @@
+use chrono::{DateTime, TimeZone, Utc};
+use codes_iso_4217::CurrencyCode;
+use rust_decimal::Decimal;
+use sledge_model::commodity::Quantity;
+
+pub fn date(y: i32, m: u32, d: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(y, m, d, 0, 0, 0).unwrap()
+}
+
+pub fn eur(cents: i64) -> Quantity {
+    Quantity::new(CurrencyCode::EUR.into(), Decimal::new(cents, 2))
+}
+
+pub fn nok(cents: i64) -> Quantity {
+    Quantity::new(CurrencyCode::NOK.into(), Decimal::new(cents, 2))
+}
+
+pub fn usd(cents: i64) -> Quantity {
+    Quantity::new(CurrencyCode::USD.into(), Decimal::new(cents, 2))
+}
@@ -0,0 +1,193 @@
+use rust_decimal::Decimal;
+use sledge_formats::beancount::{read_beancount, write_beancount, BeancountOptions};
+use sledge_formats::Imported;
+use sledge_model::commodity::{CommodityId, InternationalSecuritiesId};
+use sledge_model::ledger::{AccountId, AccountKind};
+
+const LEDGER: &str = r#"; A small household ledger
+option "title" "Household"
+option "operating_currency" "USD"
+
+2022-01-01 open Assets:Checking USD
+  description: "First Tech Checking"
+2022-01-01 open Assets:Brokerage AAPL
+2022-01-01 open Assets:Old USD
+2022-01-01 open Liabilities:Card USD
+2022-01-01 open Equity:Opening
+2022-01-01 open Expenses:Food
+2022-06-30 close Assets:Old
+
+2022-01-01 commodity AAPL
+  isin: "US0378331005"
+
+2022-10-21 price AAPL 147.27 USD
+
+pushtag #household
+
+2022-10-01 pad Assets:Checking Equity:Opening
+2022-10-02 balance Assets:Checking 1,000.00 USD
+
+2022-10-05 * "Grocer" "Weekly shop" #food ^receipt-1042
+  reference: "1042"
+  Expenses:Food  42.50 USD
+    description: "groceries"
+  Liabilities:Card
+
+2022-10-10 * "Buy shares"
+  Assets:Brokerage  10 AAPL {145.00 USD, 2022-10-10}
+  Assets:Checking  -1450.00 USD
+
+2022-10-15 ! "Sell shares"
+  Assets:Brokerage  -4 AAPL {} @ 150.00 USD
+  Assets:Checking  600.00 USD
+
+poptag #household
+
+2022-10-20 balance Assets:Checking 150.00 USD
+2022-10-20 balance Liabilities:Card -40.00 USD
+
+2022-10-21 * "Unbalanced"
+  Expenses:Food  10 USD
+  Assets:Checking  -9 USD
+
+2022-10-22 note Assets:Checking "Called the bank"
+"#;
+
+fn summary(imported: &Imported) -> Vec<String> {
+    let contents = &imported.contents;
+    contents.ledgers[0]
+        .book()
+        .map(|account| {
+            format!(
+                "{:?}",
+                (
+                    account.id(),
+                    account.parent_id(),
+                    account.kind(),
+                    account.description(),
+                    account.commodity(),
+                    account.is_active(),
+                )
+            )
+        })
+        .chain(
+            contents.journals[0]
+                .transactions()
+                .map(|transaction| format!("{:?}", transaction)),
+        )
+        .chain(contents.prices.iter().map(|price| format!("{:?}", price)))
+        .collect()
+}
+
+#[test]
+fn test_import_beancount() {
+    let imported = read_beancount(LEDGER.as_bytes(), &BeancountOptions::default()).unwrap();
+    let ledger = &imported.contents.ledgers[0];
+
+    let checking = ledger.account(&AccountId::from("Assets:Checking")).unwrap();
+    assert_eq!(checking.description(), "First Tech Checking");
+    assert_eq!(checking.parent_id(), Some(&AccountId::from("Assets")));
+    assert!(checking.is_active());
+    assert!(!ledger
+        .account(&AccountId::from("Assets:Old"))
+        .unwrap()
+        .is_active());
+    assert_eq!(
+        ledger
+            .account(&AccountId::from("Assets:Brokerage"))
+            .unwrap()
+            .commodity(),
+        &CommodityId::Security(InternationalSecuritiesId::from("US0378331005"))
+    );
+    assert_eq!(
+        ledger.account(&AccountId::from("Equity")).unwrap().kind(),
+        AccountKind::Equity
+    );
+    assert_eq!(ledger.book().count(), 10);
+
+    let transactions: Vec<_> = imported.contents.journals[0].transactions().collect();
+    assert_eq!(transactions.len(), 4);
+
+    let pad = transactions[0];
+    assert_eq!(
+        pad.metadata_value("pad").map(String::as_str),
+        Some("Equity:Opening")
+    );
+    assert_eq!(
+        pad.splits().next().unwrap().quantity().quantity(),
+        Decimal::new(100000, 2)
+    );
+
+    let grocery = transactions[1];
+    assert_eq!(grocery.reference().map(String::as_str), Some("1042"));
+    assert_eq!(
+        grocery.description().map(String::as_str),
+        Some("Weekly shop")
+    );
+    assert_eq!(
+        grocery.metadata_value("payee").map(String::as_str),
+        Some("Grocer")
+    );
+    assert_eq!(
+        grocery.metadata_value("tags").map(String::as_str),
+        Some("household food")
+    );
+    assert_eq!(
+        grocery.metadata_value("links").map(String::as_str),
+        Some("receipt-1042")
+    );
+    assert_eq!(
+        grocery.splits().nth(1).unwrap().quantity().quantity(),
+        Decimal::new(-4250, 2)
+    );
+
+    let buy = transactions[2].splits().next().unwrap();
+    assert_eq!(
+        buy.exchanged_from().unwrap().quantity().quantity(),
+        Decimal::new(145000, 2)
+    );
+
+    // The cost of the reduced lot is inferred from the rest of the transaction.
+    let sell = transactions[3];
+    assert_eq!(sell.metadata_value("flag").map(String::as_str), Some("!"));
+    assert_eq!(
+        sell.splits()
+            .next()
+            .unwrap()
+            .exchanged_from()
+            .unwrap()
+            .quantity()
+            .quantity(),
+        Decimal::new(-60000, 2)
+    );
+
+    assert_eq!(imported.contents.prices.len(), 1);
+
+    let report = &imported.report;
+    assert_eq!(report.unmapped_of_kind("balance").count(), 1);
+    assert_eq!(report.unmapped_of_kind("transaction").count(), 1);
+    assert_eq!(report.unmapped_of_kind("directive").count(), 3);
+    assert_eq!(report.unmapped().count(), 5);
+}
+
+#[test]
+fn test_export_round_trip() {
+    let imported = read_beancount(LEDGER.as_bytes(), &BeancountOptions::default()).unwrap();
+    let mut exported: Vec<u8> = Default::default();
+    write_beancount(&mut exported, &imported.contents).unwrap();
+    let text = String::from_utf8(exported).unwrap();
+
+    assert!(text
+        .contains("2022-01-01 open Assets:Checking USD\n  description: \"First Tech Checking\"\n"));
+    assert!(text.contains("2022-01-01 open Assets:Brokerage US0378331005 \"NONE\"\n"));
+    assert!(text.contains("2022-10-21 price US0378331005 147.27 USD\n"));
+    assert!(text.contains(
+        "2022-10-05 * \"Grocer\" \"Weekly shop\" #household #food ^receipt-1042\n  id: \"20221005-001\"\n  reference: \"1042\"\n"
+    ));
+    assert!(text.contains("  Assets:Brokerage  -4 US0378331005 {{600.00 USD}}\n"));
+    assert!(text.contains("2022-01-01 close Assets:Old\n"));
+
+    let reimported = read_beancount(text.as_bytes(), &BeancountOptions::default()).unwrap();
+    assert!(reimported.report.is_complete());
+    assert_eq!(summary(&reimported), summary(&imported));
+}
@@ -0,0 +1,258 @@
+use chrono::{TimeZone, Utc};
+use codes_iso_4217::CurrencyCode;
+use rust_decimal::Decimal;
+use sledge_formats::camt::read_camt;
+use sledge_formats::statement::{draft_transactions, StatementOptions};
+use sledge_model::commodity::CommodityId;
+use sledge_model::journal::{Journal, Split, Transaction, TransactionId};
+use sledge_model::ledger::{
+    Account, AccountId, AccountKind, AccountRepresents, BankAccount, Ledger, LedgerKind,
+};
+use sledge_model::party::{LegalEntityId, PartyId};
+
+pub mod common;
+
+use common::{date, eur};
+
+const CAMT_053: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.02">
+  <BkToCstmrStmt>
+    <GrpHdr><MsgId>MSG-2022-11-30</MsgId><CreDtTm>2022-11-30T20:00:00</CreDtTm></GrpHdr>
+    <Stmt>
+      <Id>STMT-2022-11-30</Id>
+      <FrToDt><FrDtTm>2022-11-30T00:00:00</FrDtTm><ToDtTm>2022-11-30T23:59:59</ToDtTm></FrToDt>
+      <Acct><Id><IBAN>DE89370400440532013000</IBAN></Id><Ccy>EUR</Ccy></Acct>
+      <Bal>
+        <Tp><CdOrPrtry><Cd>OPBD</Cd></CdOrPrtry></Tp>
+        <Amt Ccy="EUR">1000.00</Amt><CdtDbtInd>CRDT</CdtDbtInd>
+        <Dt><Dt>2022-11-30</Dt></Dt>
+      </Bal>
+      <Bal>
+        <Tp><CdOrPrtry><Cd>CLBD</Cd></CdOrPrtry></Tp>
+        <Amt Ccy="EUR">2385.50</Amt><CdtDbtInd>CRDT</CdtDbtInd>
+        <Dt><Dt>2022-11-30</Dt></Dt>
+      </Bal>
+      <Ntry>
+        <Amt Ccy="EUR">1500.00</Amt><CdtDbtInd>CRDT</CdtDbtInd>
+        <Sts>BOOK</Sts>
+        <BookgDt><Dt>2022-11-30</Dt></BookgDt>
+        <AcctSvcrRef>BANK-0001</AcctSvcrRef>
+        <NtryDtls><TxDtls>
+          <Refs><EndToEndId>INV-2022-118</EndToEndId></Refs>
+          <RltdPties><Dbtr><Nm>Example Customer GmbH</Nm></Dbtr></RltdPties>
+          <RmtInf>
+            <Ustrd>Invoice 2022-118</Ustrd>
+            <Strd><CdtrRefInf><Ref>RF18539007547034</Ref></CdtrRefInf></Strd>
+          </RmtInf>
+        </TxDtls></NtryDtls>
+      </Ntry>
+      <Ntry>
+        <Amt Ccy="EUR">124.50</Amt><CdtDbtInd>DBIT</CdtDbtInd>
+        <Sts>BOOK</Sts>
+        <BookgDt><Dt>2022-11-30</Dt></BookgDt>
+        <AcctSvcrRef>BANK-0002</AcctSvcrRef>
+        <AddtlNtryInf>SEPA direct debits</AddtlNtryInf>
+        <NtryDtls>
+          <Btch><NbOfTxs>2</NbOfTxs></Btch>
+          <TxDtls>
+            <Refs><EndToEndId>NOTPROVIDED</EndToEndId></Refs>
+            <AmtDtls><TxAmt><Amt Ccy="EUR">99.50</Amt></TxAmt></AmtDtls>
+            <RltdPties><Cdtr><Nm>City Power</Nm></Cdtr></RltdPties>
+            <RmtInf><Ustrd>Electricity November</Ustrd></RmtInf>
+          </TxDtls>
+          <TxDtls>
+            <Refs><EndToEndId>TEL-11-22</EndToEndId></Refs>
+            <AmtDtls><TxAmt><Amt Ccy="EUR">25.00</Amt></TxAmt></AmtDtls>
+            <RltdPties><Cdtr><Nm>Telco AG</Nm></Cdtr></RltdPties>
+          </TxDtls>
+        </NtryDtls>
+      </Ntry>
+      <Ntry>
+        <Amt Ccy="EUR">10.00</Amt><CdtDbtInd>DBIT</CdtDbtInd>
+        <RvslInd>true</RvslInd>
+        <Sts>BOOK</Sts>
+        <BookgDt><Dt>2022-11-30</Dt></BookgDt>
+        <NtryRef>REV-1</NtryRef>
+        <AddtlNtryInf>Returned card fee</AddtlNtryInf>
+      </Ntry>
+      <Ntry>
+        <Amt Ccy="EUR">42.00</Amt><CdtDbtInd>DBIT</CdtDbtInd>
+        <Sts>PDNG</Sts>
+        <BookgDt><Dt>2022-11-30</Dt></BookgDt>
+        <AcctSvcrRef>BANK-0003</AcctSvcrRef>
+      </Ntry>
+    </Stmt>
+  </BkToCstmrStmt>
+</Document>
+"#;
+
+const CAMT_054: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.054.001.08">
+  <BkToCstmrDbtCdtNtfctn>
+    <GrpHdr><MsgId>NTF-1</MsgId><CreDtTm>2022-12-01T09:30:00+01:00</CreDtTm></GrpHdr>
+    <Ntfctn>
+      <Id>NTF-1</Id>
+      <Acct><Id><IBAN>DE89 3704 0044 0532 0130 00</IBAN></Id></Acct>
+      <Ntry>
+        <Amt Ccy="EUR">300.00</Amt><CdtDbtInd>CRDT</CdtDbtInd>
+        <Sts><Cd>BOOK</Cd></Sts>
+        <BookgDt><DtTm>2022-12-01T09:15:00+01:00</DtTm></BookgDt>
+        <AcctSvcrRef>BANK-0004</AcctSvcrRef>
+        <NtryDtls>
+          <TxDtls>
+            <Amt Ccy="EUR">200.00</Amt><CdtDbtInd>CRDT</CdtDbtInd>
+            <RltdPties><Dbtr><Pty><Nm>First Payer</Nm></Pty></Dbtr></RltdPties>
+          </TxDtls>
+          <TxDtls>
+            <Amt Ccy="EUR">50.00</Amt><CdtDbtInd>CRDT</CdtDbtInd>
+          </TxDtls>
+        </NtryDtls>
+      </Ntry>
+    </Ntfctn>
+  </BkToCstmrDbtCdtNtfctn>
+</Document>
+"#;
+
+fn ledger() -> Ledger {
+    let bank = PartyId::LegalEntity(LegalEntityId::from("5493001KJTIIGC8Y1R12"));
+    let account = |id: &str, kind: AccountKind| {
+        Account::new(
+            AccountId::from(id),
+            kind,
+            id,
+            CommodityId::Currency(CurrencyCode::EUR),
+        )
+    };
+    Ledger::new(LedgerKind::General, "General", CurrencyCode::EUR)
+        .with_account(
+            account("Assets:Current", AccountKind::Asset).with_represents(
+                AccountRepresents::BankAccount(BankAccount::new(
+                    bank,
+                    "DE89 3704 0044 0532 0130 00",
+                )),
+            ),
+        )
+        .with_account(account("Expenses:Uncategorized", AccountKind::Expense))
+        .with_account(account("Equity:Opening Balances", AccountKind::Equity))
+}
+
+#[test]
+fn test_read_statement() {
+    let read = read_camt(CAMT_053.as_bytes()).unwrap();
+    assert_eq!(read.report.unmapped_of_kind("entry").count(), 1);
+
+    let statement = &read.statements[0];
+    assert_eq!(statement.account_number(), "DE89370400440532013000");
+    assert_eq!(statement.currency(), CurrencyCode::EUR);
+    assert_eq!(
+        statement.opening_balance().unwrap().amount(),
+        Decimal::new(100000, 2)
+    );
+    let closing = statement.closing_balance().unwrap();
+    assert_eq!(closing.amount(), Decimal::new(238550, 2));
+    assert_eq!(closing.as_at(), date(2022, 12, 1));
+
+    let entries: Vec<_> = statement.entries().collect();
+    assert_eq!(entries.len(), 4);
+    assert_eq!(entries[0].id(), "BANK-0001");
+    assert_eq!(
+        entries[0].reference().map(String::as_str),
+        Some("RF18539007547034")
+    );
+    assert_eq!(
+        entries[0].counterparty().map(String::as_str),
+        Some("Example Customer GmbH")
+    );
+    assert_eq!(
+        entries[0].description().map(String::as_str),
+        Some("Invoice 2022-118")
+    );
+
+    // The batch is imported as its two direct debits.
+    assert_eq!(entries[1].id(), "BANK-0002/1");
+    assert_eq!(entries[1].amount(), Decimal::new(-9950, 2));
+    assert_eq!(entries[1].reference(), None);
+    assert_eq!(
+        entries[1].counterparty().map(String::as_str),
+        Some("City Power")
+    );
+    assert_eq!(
+        entries[1].memo().map(String::as_str),
+        Some("SEPA direct debits")
+    );
+    assert_eq!(entries[2].amount(), Decimal::new(-2500, 2));
+    assert_eq!(
+        entries[2].reference().map(String::as_str),
+        Some("TEL-11-22")
+    );
+    assert_eq!(
+        entries[2].description().map(String::as_str),
+        Some("SEPA direct debits")
+    );
+
+    // The reversal of a debit is a credit.
+    assert_eq!(entries[3].id(), "REV-1");
+    assert_eq!(entries[3].amount(), Decimal::new(1000, 2));
+}
+
+#[test]
+fn test_notification_batch_mismatch() {
+    let read = read_camt(CAMT_054.as_bytes()).unwrap();
+    assert_eq!(read.report.unmapped().count(), 1);
+    let statement = &read.statements[0];
+    assert_eq!(statement.currency(), CurrencyCode::EUR);
+    let entries: Vec<_> = statement.entries().collect();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].amount(), Decimal::new(30000, 2));
+    assert_eq!(
+        entries[0].posted(),
+        Utc.with_ymd_and_hms(2022, 12, 1, 8, 15, 0).unwrap()
+    );
+}
+
+#[test]
+fn test_reconcile_with_statement_balance() {
+    let ledger = ledger();
+    let account = AccountId::from("Assets:Current");
+    let mut journal = Journal::new("general", CurrencyCode::EUR);
+    let id = TransactionId::from("opening");
+    let posted = date(2022, 11, 1);
+    journal
+        .add_transaction(
+            Transaction::new(id.clone(), posted)
+                .with_entered(posted)
+                .with_split(Split::new(id.clone(), account.clone(), eur(100000)))
+                .with_split(Split::new(
+                    id,
+                    AccountId::from("Equity:Opening Balances"),
+                    eur(-100000),
+                )),
+        )
+        .unwrap();
+
+    let options = StatementOptions::new(AccountId::from("Expenses:Uncategorized"));
+    let read = read_camt(CAMT_053.as_bytes()).unwrap();
+    let mut drafts = draft_transactions(read, &ledger, &[], &options);
+    let reconciliation = &mut drafts.reconciliations[0];
+    assert_eq!(reconciliation.account(), &account);
+    assert_eq!(
+        reconciliation.opening_balance(),
+        Some(Decimal::new(100000, 2))
+    );
+    assert_eq!(
+        reconciliation.statement_balance(),
+        Some(Decimal::new(238550, 2))
+    );
+    assert_eq!(reconciliation.drafts().count(), 4);
+
+    reconciliation.post_drafts(&mut journal).unwrap();
+    let journals = [journal];
+    assert_eq!(reconciliation.difference(&journals), Some(Decimal::ZERO));
+    assert_eq!(
+        reconciliation
+            .reconcile(&journals, "STMT-2022-11-30", &[])
+            .unwrap()
+            .len(),
+        5
+    );
+}
@@ -0,0 +1,240 @@
+use chrono::{TimeZone, Utc};
+use codes_iso_4217::CurrencyCode;
+use regex::Regex;
+use rust_decimal::Decimal;
+use sledge_formats::categorize::{
+    Categorization, Categorizer, Rule, RuleAction, RuleCondition, Suggestions,
+};
+use sledge_formats::csv::read_csv;
+use sledge_formats::statement::{draft_transactions, StatementOptions};
+use sledge_model::commodity::{CommodityId, Quantity};
+use sledge_model::import::{CsvAmount, CsvColumn, CsvProfile};
+use sledge_model::journal::{
+    Action, ActionId, Journal, Reconciliation, Split, Transaction, TransactionId,
+};
+use sledge_model::ledger::{Account, AccountId, AccountKind, Ledger, LedgerKind};
+
+const CSV: &str = "Date,Description,Amount,Counterparty,Reference
+2022-11-01,Rent November,-1200.00,Landlord & Co,R-11
+2022-11-02,SUPERMARKET 0423,-80.00,,
+2022-11-03,Supermarket large shop,-250.00,,
+2022-11-04,Phone and internet,-60.00,,
+2022-11-05,Corner Bakery,-4.50,,
+2022-11-06,Unknown payee,-10.00,,
+";
+
+fn uncategorized() -> AccountId {
+    AccountId::from("Expenses:Uncategorized")
+}
+
+fn ledger() -> Ledger {
+    let account = |id: &str, kind: AccountKind| {
+        Account::new(
+            AccountId::from(id),
+            kind,
+            id,
+            CommodityId::Currency(CurrencyCode::USD),
+        )
+    };
+    Ledger::new(LedgerKind::General, "General", CurrencyCode::USD)
+        .with_account(account("Assets:Checking", AccountKind::Asset))
+        .with_account(account("Expenses:Uncategorized", AccountKind::Expense))
+}
+
+fn drafts() -> Reconciliation {
+    let profile = CsvProfile::new(
+        "Checking",
+        CurrencyCode::USD,
+        CsvColumn::from("Date"),
+        CsvAmount::Signed(CsvColumn::from("Amount")),
+    )
+    .with_entry_description(CsvColumn::from("Description"))
+    .with_counterparty(CsvColumn::from("Counterparty"))
+    .with_reference(CsvColumn::from("Reference"));
+    let options =
+        StatementOptions::new(uncategorized()).with_account(AccountId::from("Assets:Checking"));
+    let statements = read_csv(CSV.as_bytes(), &profile).unwrap();
+    draft_transactions(statements, &ledger(), &[], &options)
+        .reconciliations
+        .remove(0)
+}
+
+fn amounts(transaction: &Transaction) -> Vec<(String, Decimal)> {
+    transaction
+        .splits()
+        .map(|split| (split.account().to_string(), split.quantity().quantity()))
+        .collect()
+}
+
+fn history() -> Journal {
+    let usd = |amount: i64| Quantity::new(CommodityId::Currency(CurrencyCode::USD), amount.into());
+    let mut journal = Journal::new("general", CurrencyCode::USD);
+    for (n, (description, account)) in [
+        ("Corner Bakery", "Expenses:Food:Dining"),
+        ("Corner Bakery", "Expenses:Food:Dining"),
+        ("Bakery supplies", "Expenses:Food:Groceries"),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let id = TransactionId::new(format!("history-{}", n));
+        let posted = Utc
+            .with_ymd_and_hms(2022, 10, 1 + n as u32, 0, 0, 0)
+            .unwrap();
+        journal
+            .add_transaction(
+                Transaction::new(id.clone(), posted)
+                    .with_entered(posted)
+                    .with_description(description)
+                    .with_split(Split::new(
+                        id.clone(),
+                        AccountId::from("Assets:Checking"),
+                        usd(-5),
+                    ))
+                    .with_split(Split::new(id, AccountId::from(account), usd(5))),
+            )
+            .unwrap();
+    }
+    journal
+}
+
+fn categorizer() -> Categorizer {
+    Categorizer::new(uncategorized())
+        .with_rule(
+            Rule::new("groceries", 0)
+                .with_condition(RuleCondition::Description(
+                    Regex::new("(?i)supermarket").unwrap(),
+                ))
+                .with_action(RuleAction::Account(AccountId::from(
+                    "Expenses:Food:Groceries",
+                ))),
+        )
+        .with_rule(
+            Rule::new("big shop", 10)
+                .with_condition(RuleCondition::Description(
+                    Regex::new("(?i)supermarket").unwrap(),
+                ))
+                .with_condition(RuleCondition::Amount {
+                    min: None,
+                    max: Some(Decimal::new(-200, 0)),
+                })
+                .with_action(RuleAction::Split(vec![
+                    (
+                        AccountId::from("Expenses:Food:Groceries"),
+                        Decimal::new(70, 0),
+                    ),
+                    (AccountId::from("Expenses:Household"), Decimal::new(30, 0)),
+                ]))
+                .with_action(RuleAction::Tag("bulk".to_string())),
+        )
+        .with_rule(
+            Rule::new("rent", 0)
+                .with_condition(RuleCondition::Counterparty(
+                    Regex::new("^Landlord").unwrap(),
+                ))
+                .with_condition(RuleCondition::Reference(Regex::new("^R-").unwrap()))
+                .with_action(RuleAction::Account(AccountId::from("Expenses:Rent")))
+                .with_action(RuleAction::Action(Action::new(
+                    ActionId::from("standing-order"),
+                    "Standing order",
+                    true,
+                )))
+                .with_action(RuleAction::Tag("home".to_string())),
+        )
+        .with_rule(
+            Rule::new("phone", 0)
+                .with_condition(RuleCondition::Description(Regex::new("(?i)phone").unwrap()))
+                .with_action(RuleAction::Split(vec![
+                    (AccountId::from("Expenses:Phone"), Decimal::new(3333, 2)),
+                    (AccountId::from("Expenses:Internet"), Decimal::new(6667, 2)),
+                ])),
+        )
+}
+
+#[test]
+fn test_rules_by_priority() {
+    let categorizer = categorizer();
+    assert_eq!(categorizer.rules().next().unwrap().name(), "big shop");
+
+    let mut reconciliation = drafts();
+    let results = categorizer.categorize_drafts(&mut reconciliation);
+    let drafts: Vec<_> = reconciliation.drafts().collect();
+    let names: Vec<_> = results
+        .iter()
+        .map(|result| match result {
+            Categorization::Rule(name) => name.as_str(),
+            _ => "",
+        })
+        .collect();
+    assert_eq!(
+        names,
+        vec!["rent", "groceries", "big shop", "phone", "", ""]
+    );
+
+    let rent = drafts[0];
+    assert_eq!(
+        rent.action().map(|action| action.id().as_str()),
+        Some("standing-order")
+    );
+    assert_eq!(
+        rent.metadata_value("tags").map(String::as_str),
+        Some("home")
+    );
+    assert_eq!(
+        amounts(rent)[1],
+        ("Expenses:Rent".to_string(), Decimal::new(120000, 2))
+    );
+
+    assert_eq!(
+        amounts(drafts[2])[1..],
+        [
+            (
+                "Expenses:Food:Groceries".to_string(),
+                Decimal::new(17500, 2)
+            ),
+            ("Expenses:Household".to_string(), Decimal::new(7500, 2)),
+        ]
+    );
+    assert_eq!(
+        drafts[2].metadata_value("tags").map(String::as_str),
+        Some("bulk")
+    );
+
+    // The rounding difference goes to the last account.
+    assert_eq!(
+        amounts(drafts[3])[1..],
+        [
+            ("Expenses:Phone".to_string(), Decimal::new(2000, 2)),
+            ("Expenses:Internet".to_string(), Decimal::new(4000, 2)),
+        ]
+    );
+    assert!(drafts.iter().all(|draft| draft.is_balanced()));
+    assert_eq!(amounts(drafts[5])[1].0, "Expenses:Uncategorized");
+}
+
+#[test]
+fn test_suggestions_from_history() {
+    let suggestions = Suggestions::learn(&[history()], &uncategorized());
+    let categorizer = categorizer().with_suggestions(suggestions);
+
+    let mut reconciliation = drafts();
+    let mut drafts: Vec<_> = reconciliation.drafts_mut().collect();
+    let bakery = &mut *drafts[4];
+    let Categorization::Suggested(suggestion) = categorizer.categorize(bakery) else {
+        panic!("expected a suggestion");
+    };
+    assert_eq!(suggestion.account, AccountId::from("Expenses:Food:Dining"));
+    assert!(suggestion.confidence > 0.5 && suggestion.confidence < 1.0);
+    assert_eq!(amounts(bakery)[1].0, "Expenses:Uncategorized");
+
+    categorizer.accept(bakery, &suggestion);
+    assert_eq!(
+        amounts(bakery)[1],
+        ("Expenses:Food:Dining".to_string(), Decimal::new(450, 2))
+    );
+
+    assert!(matches!(
+        categorizer.categorize(drafts[5]),
+        Categorization::Unmatched
+    ));
+}
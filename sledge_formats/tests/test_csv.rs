@@ -0,0 +1,138 @@
+use chrono::{TimeZone, Utc};
+use codes_iso_4217::CurrencyCode;
+use rust_decimal::Decimal;
+use sledge_formats::csv::read_csv;
+use sledge_formats::statement::{draft_transactions, StatementOptions};
+use sledge_model::commodity::CommodityId;
+use sledge_model::import::{CsvAmount, CsvColumn, CsvProfile, TextEncoding};
+use sledge_model::journal::Journal;
+use sledge_model::ledger::{Account, AccountId, AccountKind, Ledger, LedgerKind};
+
+const SIGNED: &str = "\u{feff}Date,Description,Amount,Reference
+2022-10-03,\"ACME Payroll, Inc.\",\"2,500.00\",PAY-10
+2022-10-05,Grocer,-42.50,
+2022-10-05,Grocer,-42.50,
+2022-10-07,\"Cafe \"\"Bean\"\"\",(3.80),
+
+2022-10-09,Refund,twelve,
+";
+
+const OVERLAPPING: &str = "Date,Description,Amount,Reference
+2022-10-05,Grocer,-42.50,
+2022-10-05,Grocer,-42.50,
+2022-10-07,\"Cafe \"\"Bean\"\"\",(3.80),
+2022-10-10,Bookshop,-19.99,
+";
+
+fn signed_profile() -> CsvProfile {
+    CsvProfile::new(
+        "First Tech Checking",
+        CurrencyCode::USD,
+        CsvColumn::from("date"),
+        CsvAmount::Signed(CsvColumn::from("Amount")),
+    )
+    .with_entry_description(CsvColumn::from("Description"))
+    .with_reference(CsvColumn::from("Reference"))
+}
+
+fn ledger() -> Ledger {
+    let account = |id: &str, kind: AccountKind| {
+        Account::new(
+            AccountId::from(id),
+            kind,
+            id,
+            CommodityId::Currency(CurrencyCode::USD),
+        )
+    };
+    Ledger::new(LedgerKind::General, "General", CurrencyCode::USD)
+        .with_account(account("Assets:Checking", AccountKind::Asset))
+        .with_account(account("Expenses:Uncategorized", AccountKind::Expense))
+}
+
+#[test]
+fn test_read_signed_amounts() {
+    let read = read_csv(SIGNED.as_bytes(), &signed_profile()).unwrap();
+    assert_eq!(read.report.unmapped_of_kind("row").count(), 1);
+    assert_eq!(read.report.unmapped().next().unwrap().id(), "7");
+
+    let statement = &read.statements[0];
+    assert_eq!(statement.account_number(), "First Tech Checking");
+    assert_eq!(
+        statement.end(),
+        Some(Utc.with_ymd_and_hms(2022, 10, 8, 0, 0, 0).unwrap())
+    );
+    let entries: Vec<_> = statement.entries().collect();
+    assert_eq!(entries.len(), 4);
+    assert_eq!(
+        entries[0].description().map(String::as_str),
+        Some("ACME Payroll, Inc.")
+    );
+    assert_eq!(entries[0].amount(), Decimal::new(250000, 2));
+    assert_eq!(entries[0].reference().map(String::as_str), Some("PAY-10"));
+    assert_eq!(entries[1].reference(), None);
+    assert_ne!(entries[1].id(), entries[2].id());
+    assert_eq!(
+        entries[3].description().map(String::as_str),
+        Some("Cafe \"Bean\"")
+    );
+    assert_eq!(entries[3].amount(), Decimal::new(-380, 2));
+}
+
+#[test]
+fn test_read_debit_credit_latin1() {
+    let csv = b"Konto;DE89370400440532013000\r\n\r\nBuchungstag;Empf\xe4nger;Soll;Haben\r\n\
+        31.10.2022;B\xe4ckerei M\xfcller;1.234,56;\r\n\
+        01.11.2022;Gehalt;;3.000,00\r\n";
+    let profile = CsvProfile::new(
+        "Girokonto",
+        CurrencyCode::EUR,
+        CsvColumn::Index(0),
+        CsvAmount::DebitCredit {
+            debit: CsvColumn::from("Soll"),
+            credit: CsvColumn::from("Haben"),
+        },
+    )
+    .with_delimiter(';')
+    .with_encoding(TextEncoding::Latin1)
+    .with_skip_rows(2)
+    .with_date_format("%d.%m.%Y")
+    .with_decimal_separator(',')
+    .with_counterparty(CsvColumn::Index(1));
+
+    let read = read_csv(&csv[..], &profile).unwrap();
+    assert!(read.report.is_complete());
+    let entries: Vec<_> = read.statements[0].entries().collect();
+    assert_eq!(
+        entries[0].counterparty().map(String::as_str),
+        Some("Bäckerei Müller")
+    );
+    assert_eq!(entries[0].amount(), Decimal::new(-123456, 2));
+    assert_eq!(
+        entries[1].posted(),
+        Utc.with_ymd_and_hms(2022, 11, 1, 0, 0, 0).unwrap()
+    );
+    assert_eq!(entries[1].amount(), Decimal::new(300000, 2));
+
+    let missing = profile.with_header(false);
+    assert!(read_csv(&csv[..], &missing).is_err());
+}
+
+#[test]
+fn test_reimport_skips_duplicates() {
+    let ledger = ledger();
+    let options = StatementOptions::new(AccountId::from("Expenses:Uncategorized"))
+        .with_account(AccountId::from("Assets:Checking"));
+    let mut journal = Journal::new("general", CurrencyCode::USD);
+
+    let read = read_csv(SIGNED.as_bytes(), &signed_profile()).unwrap();
+    let mut drafts = draft_transactions(read, &ledger, &[], &options);
+    assert_eq!(drafts.reconciliations[0].drafts().count(), 4);
+    drafts.reconciliations[0].post_drafts(&mut journal).unwrap();
+
+    let read = read_csv(OVERLAPPING.as_bytes(), &signed_profile()).unwrap();
+    let drafts = draft_transactions(read, &ledger, &[journal], &options);
+    assert_eq!(drafts.duplicates.len(), 3);
+    let new: Vec<_> = drafts.reconciliations[0].drafts().collect();
+    assert_eq!(new.len(), 1);
+    assert_eq!(new[0].description().map(String::as_str), Some("Bookshop"));
+}
@@ -0,0 +1,70 @@
+use sledge_formats::gnucash::{read_sqlite_file, read_xml_file, GnuCashOptions};
+use sledge_formats::Imported;
+use sledge_model::commodity::InternationalSecuritiesId;
+
+fn options() -> GnuCashOptions {
+    GnuCashOptions::default().with_security("AMZN", InternationalSecuritiesId::from("US0231351067"))
+}
+
+fn summary(imported: &Imported) -> Vec<String> {
+    let contents = &imported.contents;
+    contents.ledgers[0]
+        .book()
+        .map(|account| {
+            format!(
+                "{:?}",
+                (
+                    account.id(),
+                    account.parent_id(),
+                    account.kind(),
+                    account.description(),
+                    account.commodity(),
+                    account.is_recording(),
+                    account.is_active(),
+                )
+            )
+        })
+        .chain(
+            contents.journals[0]
+                .transactions()
+                .map(|transaction| format!("{:?}", transaction)),
+        )
+        .chain(contents.prices.iter().map(|price| format!("{:?}", price)))
+        .chain(
+            contents
+                .reconciliations
+                .iter()
+                .map(|reconciled| format!("{:?}", reconciled)),
+        )
+        .collect()
+}
+
+#[test]
+fn test_sqlite_matches_xml() {
+    let from_sqlite = read_sqlite_file("../doc/gnucash/gnucash.sqlite3", &options()).unwrap();
+    let from_xml = read_xml_file("../doc/gnucash/gnucash-copy.gnucash", &options()).unwrap();
+
+    assert_eq!(from_sqlite.contents.ledgers[0].book().count(), 81);
+    assert_eq!(from_sqlite.contents.journals[0].transactions().count(), 42);
+    assert_eq!(from_sqlite.contents.prices.len(), 4);
+    assert_eq!(from_sqlite.contents.reconciliations.len(), 19);
+    assert_eq!(
+        from_sqlite.contents.ledgers[0].description(),
+        from_xml.contents.ledgers[0].description()
+    );
+    assert_eq!(summary(&from_sqlite), summary(&from_xml));
+
+    let mut sqlite_report: Vec<String> = from_sqlite
+        .report
+        .unmapped()
+        .map(|item| format!("{:?}", item))
+        .collect();
+    let mut xml_report: Vec<String> = from_xml
+        .report
+        .unmapped()
+        .map(|item| format!("{:?}", item))
+        .collect();
+    sqlite_report.sort();
+    xml_report.sort();
+    assert_eq!(sqlite_report, xml_report);
+}
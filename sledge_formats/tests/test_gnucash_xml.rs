@@ -0,0 +1,131 @@
+use codes_iso_4217::CurrencyCode;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use rust_decimal::Decimal;
+use sledge_formats::gnucash::{read_xml, read_xml_file, GnuCashOptions};
+use sledge_formats::Imported;
+use sledge_model::commodity::{CommodityId, InternationalSecuritiesId};
+use sledge_model::ledger::{AccountId, AccountKind};
+use std::io::Write;
+
+const BOOK: &str = "../doc/gnucash/gnucash-copy.gnucash";
+
+fn amazon() -> InternationalSecuritiesId {
+    InternationalSecuritiesId::from("US0231351067")
+}
+
+#[test]
+fn test_import_book_with_securities() {
+    let options = GnuCashOptions::default().with_security("AMZN", amazon());
+    let imported = read_xml_file(BOOK, &options).unwrap();
+    let contents = &imported.contents;
+
+    assert_eq!(contents.ledgers.len(), 1);
+    let ledger = &contents.ledgers[0];
+    assert_eq!(ledger.currency(), CurrencyCode::USD);
+    assert_eq!(ledger.book().count(), 81);
+
+    let insurance = ledger
+        .account(&AccountId::from("Expenses:Insurance"))
+        .unwrap();
+    assert_eq!(insurance.kind(), AccountKind::Expense);
+    assert_eq!(insurance.parent_id(), Some(&AccountId::from("Expenses")));
+    assert!(ledger.account(&AccountId::from("Root Account")).is_none());
+
+    let stock = ledger
+        .account(&AccountId::from("Assets:Amazon Stock"))
+        .unwrap();
+    assert_eq!(stock.commodity(), &CommodityId::Security(amazon()));
+
+    assert_eq!(contents.journals.len(), 1);
+    let journal = &contents.journals[0];
+    assert_eq!(journal.transactions().count(), 42);
+    let buy = journal
+        .transactions()
+        .flat_map(|t| t.splits())
+        .find(|s| s.account() == stock.id() && s.quantity().quantity() > Decimal::ZERO)
+        .unwrap();
+    assert_eq!(buy.quantity().quantity(), Decimal::new(588, 0));
+    let exchanged = buy.exchanged_from().unwrap();
+    assert_eq!(exchanged.quantity().quantity(), Decimal::new(7865511, 2));
+
+    assert_eq!(contents.prices.len(), 4);
+    assert_eq!(contents.reconciliations.len(), 19);
+
+    let report = &imported.report;
+    assert_eq!(report.unmapped_of_kind("budget").count(), 1);
+    assert_eq!(report.unmapped_of_kind("split").count(), 4);
+    assert_eq!(report.unmapped_of_kind("transaction").count(), 0);
+}
+
+#[test]
+fn test_import_book_without_securities() {
+    let imported = read_xml_file(BOOK, &GnuCashOptions::default()).unwrap();
+    let contents = &imported.contents;
+
+    assert_eq!(contents.ledgers[0].book().count(), 80);
+    assert_eq!(contents.journals[0].transactions().count(), 40);
+    assert_eq!(contents.prices.len(), 1);
+
+    let report = &imported.report;
+    assert!(!report.is_complete());
+    assert_eq!(report.unmapped_of_kind("commodity").count(), 1);
+    assert_eq!(report.unmapped_of_kind("account").count(), 1);
+    assert_eq!(report.unmapped_of_kind("transaction").count(), 2);
+    assert_eq!(report.unmapped_of_kind("price").count(), 3);
+}
+
+#[test]
+fn test_import_compressed_book() {
+    let options = GnuCashOptions::default().with_security("AMZN", amazon());
+    let plain = std::fs::read(BOOK).unwrap();
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&plain).unwrap();
+    let compressed = encoder.finish().unwrap();
+
+    let from_plain = read_xml(plain.as_slice(), &options).unwrap();
+    let from_compressed = read_xml(compressed.as_slice(), &options).unwrap();
+    assert_eq!(summary(&from_plain), summary(&from_compressed));
+}
+
+#[test]
+fn test_import_inexact_price() {
+    let options = GnuCashOptions::default().with_security("AMZN", amazon());
+    let book = std::fs::read_to_string(BOOK)
+        .unwrap()
+        .replace("<price:value>4549/50<", "<price:value>1/3<");
+    let imported = read_xml(book.as_bytes(), &options).unwrap();
+
+    assert_eq!(imported.contents.prices.len(), 4);
+    assert!(imported
+        .contents
+        .prices
+        .iter()
+        .any(|price| price.rate() == Decimal::ONE / Decimal::from(3)));
+    let rounded: Vec<_> = imported.report.unmapped_of_kind("price").collect();
+    assert_eq!(rounded.len(), 1);
+    assert_eq!(rounded[0].id(), "b7ea254b7f5d4e2fa1f3b2a3ec2c5694");
+
+    let book = book.replace("<split:value>2246/100<", "<split:value>1/3<");
+    assert!(read_xml(book.as_bytes(), &options).is_err());
+}
+
+fn summary(imported: &Imported) -> Vec<String> {
+    let contents = &imported.contents;
+    contents.ledgers[0]
+        .book()
+        .map(|account| format!("{:?}", (account.id(), account.parent_id(), account.kind())))
+        .chain(
+            contents.journals[0]
+                .transactions()
+                .map(|transaction| format!("{:?}", transaction)),
+        )
+        .chain(contents.prices.iter().map(|price| format!("{:?}", price)))
+        .chain(
+            contents
+                .reconciliations
+                .iter()
+                .map(|reconciled| format!("{:?}", reconciled)),
+        )
+        .collect()
+}
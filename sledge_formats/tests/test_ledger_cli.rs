@@ -0,0 +1,167 @@
+use rust_decimal::Decimal;
+use sledge_formats::ledger_cli::{read_journal, write_journal, LedgerCliOptions};
+use sledge_formats::Imported;
+use sledge_model::commodity::{CommodityId, InternationalSecuritiesId};
+use sledge_model::ledger::{AccountId, AccountKind};
+
+const JOURNAL: &str = r#"; A small household journal
+account Assets:Checking  ; type: A
+    ; First Tech Checking
+account Savings  ; type: Asset
+account Liabilities:Card
+
+commodity $1,000.00
+
+P 2022-10-21 AAPL $147.27
+P 2022/10/22 GBP 1.13 USD
+
+2022-10-01 * Opening balance
+    Assets:Checking       $1,000.00
+    Equity:Opening
+
+2022-10-05 (1042) Grocery store
+    Expenses:Food         $42.50  ; weekly shop
+    * Liabilities:Card   -$42.50
+
+2022/10/10 Buy shares
+    Assets:Brokerage      10 AAPL @ $145.00
+    Assets:Checking
+
+2022-10-12 ! Transfer
+    Savings               200 USD
+    Assets:Checking      -200 USD
+
+2022-10-15 Sell shares
+    Assets:Brokerage      -4 AAPL @@ $600
+    Assets:Checking       $600
+
+2022-10-20 Bad transaction
+    Expenses:Food         $10
+    Assets:Checking       $-9
+
+2022-10-21 Unknown commodity
+    Expenses:Food         5 ZZZ
+    Assets:Checking
+
+~ monthly
+    Expenses:Rent         $1,200
+    Assets:Checking
+"#;
+
+fn options() -> LedgerCliOptions {
+    LedgerCliOptions::default().with_commodity(
+        "AAPL",
+        CommodityId::Security(InternationalSecuritiesId::from("US0378331005")),
+    )
+}
+
+fn summary(imported: &Imported) -> Vec<String> {
+    let contents = &imported.contents;
+    contents.ledgers[0]
+        .book()
+        .map(|account| {
+            format!(
+                "{:?}",
+                (
+                    account.id(),
+                    account.parent_id(),
+                    account.kind(),
+                    account.description(),
+                    account.commodity(),
+                )
+            )
+        })
+        .chain(
+            contents.journals[0]
+                .transactions()
+                .map(|transaction| format!("{:?}", transaction)),
+        )
+        .chain(contents.prices.iter().map(|price| format!("{:?}", price)))
+        .chain(
+            contents
+                .reconciliations
+                .iter()
+                .map(|reconciled| format!("{:?}", reconciled)),
+        )
+        .collect()
+}
+
+#[test]
+fn test_import_journal() {
+    let imported = read_journal(JOURNAL.as_bytes(), &options()).unwrap();
+    let ledger = &imported.contents.ledgers[0];
+
+    let checking = ledger.account(&AccountId::from("Assets:Checking")).unwrap();
+    assert_eq!(checking.description(), "First Tech Checking");
+    assert_eq!(checking.parent_id(), Some(&AccountId::from("Assets")));
+    assert_eq!(
+        ledger.account(&AccountId::from("Savings")).unwrap().kind(),
+        AccountKind::Asset
+    );
+    assert_eq!(
+        ledger.account(&AccountId::from("Equity")).unwrap().kind(),
+        AccountKind::Equity
+    );
+    assert_eq!(ledger.book().count(), 10);
+
+    let journal = &imported.contents.journals[0];
+    let transactions: Vec<_> = journal.transactions().collect();
+    assert_eq!(transactions.len(), 5);
+
+    let opening = transactions[0].splits().nth(1).unwrap();
+    assert_eq!(opening.quantity().quantity(), Decimal::new(-100000, 2));
+
+    let grocery = transactions[1];
+    assert_eq!(grocery.reference().map(String::as_str), Some("1042"));
+    assert_eq!(
+        grocery
+            .splits()
+            .next()
+            .unwrap()
+            .description()
+            .map(String::as_str),
+        Some("weekly shop")
+    );
+
+    let buy = transactions[2].splits().next().unwrap();
+    assert_eq!(buy.quantity().quantity(), Decimal::new(10, 0));
+    let cost = buy.exchanged_from().unwrap();
+    assert_eq!(cost.quantity().quantity(), Decimal::new(145000, 2));
+    let paid = transactions[2].splits().nth(1).unwrap();
+    assert_eq!(paid.quantity().quantity(), Decimal::new(-145000, 2));
+
+    let sell = transactions[4].splits().next().unwrap();
+    assert_eq!(
+        sell.exchanged_from().unwrap().quantity().quantity(),
+        Decimal::new(-600, 0)
+    );
+
+    assert_eq!(imported.contents.prices.len(), 2);
+    // Both opening postings, and the one cleared posting of the grocery transaction.
+    assert_eq!(imported.contents.reconciliations.len(), 3);
+
+    let report = &imported.report;
+    assert_eq!(report.unmapped_of_kind("transaction").count(), 3);
+    assert_eq!(report.unmapped_of_kind("commodity").count(), 1);
+    assert_eq!(report.unmapped_of_kind("directive").count(), 1);
+}
+
+#[test]
+fn test_export_round_trip() {
+    let imported = read_journal(JOURNAL.as_bytes(), &options()).unwrap();
+    let mut exported: Vec<u8> = Default::default();
+    write_journal(&mut exported, &imported.contents).unwrap();
+    let text = String::from_utf8(exported).unwrap();
+
+    assert!(text.contains("account Assets:Checking  ; type: A\n    ; First Tech Checking\n"));
+    assert!(text.contains("P 2022-10-21 \"US0378331005\" 147.27 USD\n"));
+    assert!(text.contains(
+        "2022-10-05 (1042) Grocery store  ; id: 20221005-001\n    Expenses:Food  42.50 USD  ; weekly shop\n    * Liabilities:Card  -42.50 USD\n"
+    ));
+    assert!(text.contains("    Assets:Brokerage  10 \"US0378331005\" @@ 1450.00 USD\n"));
+    assert!(text.contains("    Assets:Brokerage  -4 \"US0378331005\" @@ 600 USD\n"));
+
+    let reimported = read_journal(text.as_bytes(), &options()).unwrap();
+    assert!(reimported.report.is_complete());
+    assert_eq!(summary(&reimported), summary(&imported));
+}
@@ -0,0 +1,235 @@
+use chrono::{TimeZone, Utc};
+use codes_iso_4217::CurrencyCode;
+use rust_decimal::Decimal;
+use sledge_formats::mt940::read_mt940;
+use sledge_formats::statement::{draft_transactions, StatementOptions};
+use sledge_model::commodity::CommodityId;
+use sledge_model::journal::Journal;
+use sledge_model::ledger::{
+    Account, AccountId, AccountKind, AccountRepresents, BankAccount, Ledger, LedgerKind,
+};
+use sledge_model::party::{LegalEntityId, PartyId};
+
+const MT940: &str = "{1:F01DEUTDEFFAXXX0000000000}{2:O9401200221130DEUTDEFFAXXX00000000002211301200N}{4:
+:20:STMT221130
+:25:DE89370400440532013000
+:28C:00112/001
+:60F:C221129EUR1000,00
+:61:2211301130CR1500,00NTRFNONREF//BANK-0001
+:86:166?00SEPA-GUTSCHRIFT?109310?20EREF+INV-2022-118?21SVWZ+Rechnung 2022-118 vom?22 15.11.2022?30COBADEFFXXX?31DE02100
+400000123456789?32Example Customer?33 GmbH
+:61:221130D124,50NDDTMANDATE-77
+:86:105?00SEPA-LASTSCHRIFT?20Strom November?32City Power
+:61:221130RD10,00NCHG
+:86:Returned card fee
+:62F:C221130EUR2385,50
+-}
+:20:940S221130
+:25:NL91ABNA0417164300
+:28C:118/1
+:60F:D221129EUR250,00
+:61:221130C1250,NTRFNONREF//AB-77
+:86:/TRTP/SEPA OVERBOEKING/IBAN/NL20INGB0001234567/BIC/INGBNL2A/NAME/J. Jansen
+/REMI/USTD//Huur december/EREF/NOTPROVIDED
+:61:221130D45,99NTRFREF-991//AB-78
+:86:/TRTP/SEPA INCASSO/CNTP/NL13RABO0123456789/RABONL2U/Energie BV/Utrecht/REMI/
+Klantnummer 42/EREF/INC-42
+:62F:C221130EUR954,01
+-
+";
+
+const MT942: &str = ":20:INTRADAY1
+:25:DE89370400440532013000
+:28C:00113/001
+:34F:EURD0,
+:13D:2212011015+0100
+:61:221201D12,00NMSCNONREF
+:86:Cafe
+:61:221201C300,00NTRFINV-119
+:90D:1EUR12,00
+:90C:1EUR300,00
+-
+";
+
+const MT940_NEXT_DAY: &str = ":20:STMT221201
+:25:DE89370400440532013000
+:28C:00113/001
+:60F:C221130EUR2385,50
+:61:221201D12,00NMSCNONREF
+:86:Cafe
+:61:221201C300,00NTRFINV-119
+:61:221201D80,00NDDTNONREF
+:62F:C221201EUR2593,50
+-
+";
+
+fn ledger() -> Ledger {
+    let bank = PartyId::LegalEntity(LegalEntityId::from("5493001KJTIIGC8Y1R12"));
+    let account = |id: &str, kind: AccountKind| {
+        Account::new(
+            AccountId::from(id),
+            kind,
+            id,
+            CommodityId::Currency(CurrencyCode::EUR),
+        )
+    };
+    Ledger::new(LedgerKind::General, "General", CurrencyCode::EUR)
+        .with_account(
+            account("Assets:Current", AccountKind::Asset).with_represents(
+                AccountRepresents::BankAccount(BankAccount::new(bank, "DE89370400440532013000")),
+            ),
+        )
+        .with_account(account("Expenses:Uncategorized", AccountKind::Expense))
+}
+
+#[test]
+fn test_read_statements() {
+    let read = read_mt940(MT940.as_bytes()).unwrap();
+    assert!(read.report.is_complete());
+    assert_eq!(read.statements.len(), 2);
+
+    let german = &read.statements[0];
+    assert_eq!(german.account_number(), "DE89370400440532013000");
+    assert_eq!(german.currency(), CurrencyCode::EUR);
+    assert_eq!(
+        german.opening_balance().unwrap().amount(),
+        Decimal::new(100000, 2)
+    );
+    let closing = german.closing_balance().unwrap();
+    assert_eq!(closing.amount(), Decimal::new(238550, 2));
+    assert_eq!(
+        closing.as_at(),
+        Utc.with_ymd_and_hms(2022, 12, 1, 0, 0, 0).unwrap()
+    );
+
+    let entries: Vec<_> = german.entries().collect();
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[0].id(), "BANK-0001");
+    assert_eq!(entries[0].amount(), Decimal::new(150000, 2));
+    assert_eq!(
+        entries[0].reference().map(String::as_str),
+        Some("INV-2022-118")
+    );
+    assert_eq!(
+        entries[0].description().map(String::as_str),
+        Some("Rechnung 2022-118 vom 15.11.2022")
+    );
+    assert_eq!(
+        entries[0].memo().map(String::as_str),
+        Some("SEPA-GUTSCHRIFT")
+    );
+    assert_eq!(
+        entries[0].counterparty().map(String::as_str),
+        Some("Example Customer GmbH")
+    );
+
+    assert_eq!(entries[1].amount(), Decimal::new(-12450, 2));
+    assert_eq!(
+        entries[1].reference().map(String::as_str),
+        Some("MANDATE-77")
+    );
+    assert_eq!(
+        entries[1].description().map(String::as_str),
+        Some("Strom November")
+    );
+    assert_eq!(
+        entries[1].counterparty().map(String::as_str),
+        Some("City Power")
+    );
+    // The reversal of a debit is a credit.
+    assert_eq!(entries[2].amount(), Decimal::new(1000, 2));
+    assert_eq!(
+        entries[2].description().map(String::as_str),
+        Some("Returned card fee")
+    );
+
+    let dutch = &read.statements[1];
+    assert_eq!(
+        dutch.opening_balance().unwrap().amount(),
+        Decimal::new(-25000, 2)
+    );
+    let entries: Vec<_> = dutch.entries().collect();
+    assert_eq!(
+        entries[0].counterparty().map(String::as_str),
+        Some("J. Jansen")
+    );
+    assert_eq!(
+        entries[0].description().map(String::as_str),
+        Some("Huur december")
+    );
+    assert_eq!(entries[0].reference(), None);
+    assert_eq!(
+        entries[0].memo().map(String::as_str),
+        Some("SEPA OVERBOEKING")
+    );
+    assert_eq!(
+        entries[1].counterparty().map(String::as_str),
+        Some("Energie BV")
+    );
+    assert_eq!(
+        entries[1].description().map(String::as_str),
+        Some("Klantnummer 42")
+    );
+    assert_eq!(entries[1].reference().map(String::as_str), Some("REF-991"));
+}
+
+#[test]
+fn test_interim_report_then_statement() {
+    let ledger = ledger();
+    let options = StatementOptions::new(AccountId::from("Expenses:Uncategorized"));
+
+    let interim = read_mt940(MT942.as_bytes()).unwrap();
+    let statement = &interim.statements[0];
+    assert_eq!(statement.closing_balance(), None);
+    assert_eq!(
+        statement.end(),
+        Some(Utc.with_ymd_and_hms(2022, 12, 1, 9, 15, 0).unwrap())
+    );
+    let mut drafts = draft_transactions(interim, &ledger, &[], &options);
+    assert_eq!(drafts.reconciliations[0].drafts().count(), 2);
+    let mut journal = Journal::new("general", CurrencyCode::EUR);
+    drafts.reconciliations[0].post_drafts(&mut journal).unwrap();
+
+    // The end of day statement repeats the two lines already reported.
+    let statement = read_mt940(MT940_NEXT_DAY.as_bytes()).unwrap();
+    let drafts = draft_transactions(statement, &ledger, &[journal], &options);
+    assert_eq!(drafts.duplicates.len(), 2);
+    let new: Vec<_> = drafts.reconciliations[0].drafts().collect();
+    assert_eq!(new.len(), 1);
+    assert_eq!(
+        drafts.reconciliations[0].statement_balance(),
+        Some(Decimal::new(259350, 2))
+    );
+}
+
+#[test]
+fn test_invalid_statement_line() {
+    let text = ":20:X\n:25:DE89370400440532013000\n:60F:C221129EUR0,\n:61:22113X\n-\n";
+    assert!(read_mt940(text.as_bytes()).is_err());
+}
+
+#[test]
+fn test_latin_1_information() {
+    let statement = |line: &[u8], information: &[u8]| {
+        [
+            b":20:X\n:25:DE89370400440532013000\n:60F:C221129EUR0,\n:61:".as_slice(),
+            line,
+            b"\n:86:",
+            information,
+            b"\n:62F:C221130EUR12,00\n-\n",
+        ]
+        .concat()
+    };
+
+    let read =
+        read_mt940(statement(b"221130C12,00NTRFNONREF", b"\xDCberweisung M\xFCller").as_slice())
+            .unwrap();
+    let entry = read.statements[0].entries().next().unwrap();
+    assert_eq!(
+        entry.description().map(String::as_str),
+        Some("Überweisung Müller")
+    );
+
+    let text = statement(b"221130\xC41\xC4C12,00NTRFNONREF", b"\xDCberweisung");
+    assert!(read_mt940(text.as_slice()).is_err());
+}
@@ -0,0 +1,380 @@
+use chrono::{TimeZone, Utc};
+use codes_iso_4217::CurrencyCode;
+use rust_decimal::Decimal;
+use sledge_formats::ofx::{read_ofx, OfxOptions};
+use sledge_formats::statement::{draft_transactions, StatementOptions, Statements, IMPORT_ID};
+use sledge_model::commodity::{CommodityId, InternationalSecuritiesId, Quantity};
+use sledge_model::journal::Journal;
+use sledge_model::ledger::{
+    Account, AccountId, AccountKind, AccountRepresents, BankAccount, BrokerageAccount, CreditCard,
+    Ledger, LedgerKind,
+};
+use sledge_model::party::{LegalEntityId, PartyId};
+
+pub mod common;
+
+use common::{date, usd};
+
+const BANK: &str = "OFXHEADER:100
+DATA:OFXSGML
+VERSION:102
+SECURITY:NONE
+ENCODING:USASCII
+CHARSET:1252
+COMPRESSION:NONE
+OLDFILEUID:NONE
+NEWFILEUID:NONE
+
+<OFX>
+<SIGNONMSGSRSV1>
+<SONRS>
+<STATUS>
+<CODE>0
+<SEVERITY>INFO
+</STATUS>
+<DTSERVER>20221101120000[-5:EST]
+<LANGUAGE>ENG
+</SONRS>
+</SIGNONMSGSRSV1>
+<BANKMSGSRSV1>
+<STMTTRNRS>
+<TRNUID>1001
+<STATUS>
+<CODE>0
+<SEVERITY>INFO
+</STATUS>
+<STMTRS>
+<CURDEF>USD
+<BANKACCTFROM>
+<BANKID>121000248
+<ACCTID>123-456-789
+<ACCTTYPE>CHECKING
+</BANKACCTFROM>
+<BANKTRANLIST>
+<DTSTART>20221001
+<DTEND>20221031
+<STMTTRN>
+<TRNTYPE>CREDIT
+<DTPOSTED>20221003120000.000[-5:EST]
+<TRNAMT>2500.00
+<FITID>202210030001
+<NAME>ACME PAYROLL
+<MEMO>Salary October
+</STMTTRN>
+<STMTTRN>
+<TRNTYPE>CHECK
+<DTPOSTED>20221010
+<TRNAMT>-1200.00
+<FITID>202210100002
+<CHECKNUM>1042
+<NAME>Landlord &amp; Co
+</STMTTRN>
+<STMTTRN>
+<TRNTYPE>DEBIT
+<DTPOSTED>20221020
+<TRNAMT>-42.50
+<FITID>202210200003
+<NAME>GROCER
+</STMTTRN>
+</BANKTRANLIST>
+<LEDGERBAL>
+<BALAMT>1257.50
+<DTASOF>20221031
+</LEDGERBAL>
+</STMTRS>
+</STMTTRNRS>
+</BANKMSGSRSV1>
+</OFX>
+";
+
+const CARD: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>
+<?OFX OFXHEADER="200" VERSION="220" SECURITY="NONE" OLDFILEUID="NONE" NEWFILEUID="NONE"?>
+<OFX>
+  <CREDITCARDMSGSRSV1>
+    <CCSTMTTRNRS>
+      <TRNUID>2001</TRNUID>
+      <STATUS><CODE>0</CODE><SEVERITY>INFO</SEVERITY></STATUS>
+      <CCSTMTRS>
+        <CURDEF>USD</CURDEF>
+        <CCACCTFROM><ACCTID>XXXXXXXXXXXX4321</ACCTID></CCACCTFROM>
+        <BANKTRANLIST>
+          <DTSTART>20221001</DTSTART>
+          <DTEND>20221031</DTEND>
+          <STMTTRN>
+            <TRNTYPE>DEBIT</TRNTYPE>
+            <DTPOSTED>20221012</DTPOSTED>
+            <TRNAMT>-89.99</TRNAMT>
+            <FITID>CC-1</FITID>
+            <NAME>BOOKSHOP</NAME>
+          </STMTTRN>
+          <STMTTRN>
+            <TRNTYPE>CREDIT</TRNTYPE>
+            <DTPOSTED>20221025</DTPOSTED>
+            <TRNAMT>50.00</TRNAMT>
+            <FITID>CC-2</FITID>
+            <NAME>PAYMENT THANK YOU</NAME>
+          </STMTTRN>
+        </BANKTRANLIST>
+        <LEDGERBAL><BALAMT>-39.99</BALAMT><DTASOF>20221031</DTASOF></LEDGERBAL>
+      </CCSTMTRS>
+    </CCSTMTTRNRS>
+  </CREDITCARDMSGSRSV1>
+</OFX>
+"#;
+
+const BROKERAGE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<?OFX OFXHEADER="200" VERSION="220"?>
+<OFX>
+  <INVSTMTMSGSRSV1>
+    <INVSTMTTRNRS>
+      <TRNUID>3001</TRNUID>
+      <STATUS><CODE>0</CODE><SEVERITY>INFO</SEVERITY></STATUS>
+      <INVSTMTRS>
+        <DTASOF>20221031</DTASOF>
+        <CURDEF>USD</CURDEF>
+        <INVACCTFROM><BROKERID>broker.example.com</BROKERID><ACCTID>BRK-1001</ACCTID></INVACCTFROM>
+        <INVTRANLIST>
+          <DTSTART>20221001</DTSTART>
+          <DTEND>20221031</DTEND>
+          <INVBANKTRAN>
+            <STMTTRN>
+              <TRNTYPE>CREDIT</TRNTYPE>
+              <DTPOSTED>20221003</DTPOSTED>
+              <TRNAMT>2000.00</TRNAMT>
+              <FITID>INV-1</FITID>
+              <NAME>DEPOSIT</NAME>
+            </STMTTRN>
+            <SUBACCTFUND>CASH</SUBACCTFUND>
+          </INVBANKTRAN>
+          <BUYSTOCK>
+            <INVBUY>
+              <INVTRAN><FITID>INV-2</FITID><DTTRADE>20221010</DTTRADE></INVTRAN>
+              <SECID><UNIQUEID>037833100</UNIQUEID><UNIQUEIDTYPE>CUSIP</UNIQUEIDTYPE></SECID>
+              <UNITS>10</UNITS>
+              <UNITPRICE>145.00</UNITPRICE>
+              <COMMISSION>5.00</COMMISSION>
+              <TOTAL>-1455.00</TOTAL>
+              <SUBACCTSEC>CASH</SUBACCTSEC>
+              <SUBACCTFUND>CASH</SUBACCTFUND>
+            </INVBUY>
+            <BUYTYPE>BUY</BUYTYPE>
+          </BUYSTOCK>
+          <SELLSTOCK>
+            <INVSELL>
+              <INVTRAN><FITID>INV-3</FITID><DTTRADE>20221015</DTTRADE></INVTRAN>
+              <SECID><UNIQUEID>037833100</UNIQUEID><UNIQUEIDTYPE>CUSIP</UNIQUEIDTYPE></SECID>
+              <UNITS>-4</UNITS>
+              <UNITPRICE>150.00</UNITPRICE>
+              <TOTAL>600.00</TOTAL>
+              <SUBACCTSEC>CASH</SUBACCTSEC>
+              <SUBACCTFUND>CASH</SUBACCTFUND>
+            </INVSELL>
+            <SELLTYPE>SELL</SELLTYPE>
+          </SELLSTOCK>
+          <INCOME>
+            <INVTRAN>
+              <FITID>INV-4</FITID>
+              <DTTRADE>20221020</DTTRADE>
+              <MEMO>Quarterly dividend</MEMO>
+            </INVTRAN>
+            <SECID><UNIQUEID>037833100</UNIQUEID><UNIQUEIDTYPE>CUSIP</UNIQUEIDTYPE></SECID>
+            <INCOMETYPE>DIV</INCOMETYPE>
+            <TOTAL>1.38</TOTAL>
+            <SUBACCTSEC>CASH</SUBACCTSEC>
+            <SUBACCTFUND>CASH</SUBACCTFUND>
+          </INCOME>
+          <TRANSFER>
+            <INVTRAN><FITID>INV-5</FITID><DTTRADE>20221025</DTTRADE></INVTRAN>
+            <SECID><UNIQUEID>037833100</UNIQUEID><UNIQUEIDTYPE>CUSIP</UNIQUEIDTYPE></SECID>
+            <SUBACCTSEC>CASH</SUBACCTSEC>
+            <UNITS>5</UNITS>
+            <TFERACTION>IN</TFERACTION>
+            <POSTYPE>LONG</POSTYPE>
+          </TRANSFER>
+        </INVTRANLIST>
+        <INVBAL>
+          <AVAILCASH>1146.38</AVAILCASH>
+          <MARGINBALANCE>0</MARGINBALANCE>
+          <SHORTBALANCE>0</SHORTBALANCE>
+        </INVBAL>
+      </INVSTMTRS>
+    </INVSTMTTRNRS>
+  </INVSTMTMSGSRSV1>
+  <SECLISTMSGSRSV1>
+    <SECLIST>
+      <STOCKINFO>
+        <SECINFO>
+          <SECID><UNIQUEID>037833100</UNIQUEID><UNIQUEIDTYPE>CUSIP</UNIQUEIDTYPE></SECID>
+          <SECNAME>Apple Inc.</SECNAME>
+          <TICKER>AAPL</TICKER>
+        </SECINFO>
+      </STOCKINFO>
+    </SECLIST>
+  </SECLISTMSGSRSV1>
+</OFX>
+"#;
+
+fn ledger() -> Ledger {
+    let bank = PartyId::LegalEntity(LegalEntityId::from("5493001KJTIIGC8Y1R12"));
+    let account = |id: &str, kind: AccountKind| {
+        Account::new(
+            AccountId::from(id),
+            kind,
+            id,
+            CommodityId::Currency(CurrencyCode::USD),
+        )
+    };
+    Ledger::new(LedgerKind::General, "General", CurrencyCode::USD)
+        .with_account(
+            account("Assets:Checking", AccountKind::Asset).with_represents(
+                AccountRepresents::BankAccount(BankAccount::new(bank.clone(), "123456789")),
+            ),
+        )
+        .with_account(
+            account("Assets:Brokerage", AccountKind::Asset).with_represents(
+                AccountRepresents::BrokerageAccount(BrokerageAccount::new(
+                    bank.clone(),
+                    "BRK-1001",
+                )),
+            ),
+        )
+        .with_account(
+            account("Liabilities:Visa", AccountKind::Liability).with_represents(
+                AccountRepresents::CreditCard(CreditCard::new(
+                    bank,
+                    "4111 1111 1111 4321",
+                    1,
+                    25,
+                    Decimal::new(2499, 2),
+                    usd(0),
+                )),
+            ),
+        )
+        .with_account(account("Expenses:Uncategorized", AccountKind::Expense))
+}
+
+fn statements() -> Statements {
+    let mut all = Statements::default();
+    for document in [BANK, CARD, BROKERAGE] {
+        let read = read_ofx(document.as_bytes(), &OfxOptions::default()).unwrap();
+        all.statements.extend(read.statements);
+        for item in read.report.unmapped() {
+            all.report.add(item.kind(), item.id(), item.reason());
+        }
+    }
+    all
+}
+
+#[test]
+fn test_read_sgml_statement() {
+    let read = read_ofx(BANK.as_bytes(), &OfxOptions::default()).unwrap();
+    assert!(read.report.is_complete());
+    assert_eq!(read.statements.len(), 1);
+
+    let statement = &read.statements[0];
+    assert_eq!(statement.account_number(), "123-456-789");
+    assert_eq!(statement.currency(), CurrencyCode::USD);
+    let balance = statement.closing_balance().unwrap();
+    assert_eq!(balance.amount(), Decimal::new(125750, 2));
+    assert_eq!(balance.as_at(), date(2022, 11, 1));
+
+    let entries: Vec<_> = statement.entries().collect();
+    assert_eq!(entries.len(), 3);
+    assert_eq!(
+        entries[0].posted(),
+        Utc.with_ymd_and_hms(2022, 10, 3, 17, 0, 0).unwrap()
+    );
+    assert_eq!(
+        entries[0].memo().map(String::as_str),
+        Some("Salary October")
+    );
+    assert_eq!(
+        entries[1].description().map(String::as_str),
+        Some("Landlord & Co")
+    );
+    assert_eq!(entries[1].reference().map(String::as_str), Some("1042"));
+    assert_eq!(entries[2].amount(), Decimal::new(-4250, 2));
+}
+
+#[test]
+fn test_read_windows_1252_statement() {
+    let (before, after) = BANK.split_once("GROCER").unwrap();
+    let document = [before.as_bytes(), b"CAF\xC9 \x805", after.as_bytes()].concat();
+
+    let read = read_ofx(document.as_slice(), &OfxOptions::default()).unwrap();
+    let entries: Vec<_> = read.statements[0].entries().collect();
+    assert_eq!(
+        entries[2].description().map(String::as_str),
+        Some("CAFÉ €5")
+    );
+}
+
+#[test]
+fn test_draft_and_reconcile() {
+    let ledger = ledger();
+    let options = StatementOptions::new(AccountId::from("Expenses:Uncategorized"));
+    let mut drafts = draft_transactions(statements(), &ledger, &[], &options);
+
+    // The transfer of shares in is the only entry not imported.
+    assert_eq!(drafts.report.unmapped().count(), 1);
+    assert_eq!(drafts.report.unmapped_of_kind("entry").count(), 1);
+    assert!(drafts.duplicates.is_empty());
+    assert_eq!(drafts.reconciliations.len(), 3);
+    let accounts: Vec<_> = drafts
+        .reconciliations
+        .iter()
+        .map(|r| (r.account().as_str(), r.drafts().count()))
+        .collect();
+    assert_eq!(
+        accounts,
+        vec![
+            ("Assets:Checking", 3),
+            ("Liabilities:Visa", 2),
+            ("Assets:Brokerage", 4)
+        ]
+    );
+
+    let buy = drafts.reconciliations[2].drafts().nth(1).unwrap();
+    assert_eq!(
+        buy.metadata_value(IMPORT_ID).map(String::as_str),
+        Some("INV-2")
+    );
+    assert_eq!(buy.description().map(String::as_str), Some("Buy 10 AAPL"));
+    let shares = buy.splits().next().unwrap();
+    assert_eq!(
+        shares.quantity(),
+        &Quantity::new(
+            CommodityId::Security(InternationalSecuritiesId::from("US0378331005")),
+            Decimal::new(10, 0)
+        )
+    );
+    assert_eq!(shares.exchanged_from().unwrap().quantity(), &usd(145500));
+    assert_eq!(buy.splits().nth(1).unwrap().quantity(), &usd(-145500));
+
+    let mut journal = Journal::new("general", CurrencyCode::USD);
+    for reconciliation in drafts.reconciliations.iter_mut() {
+        reconciliation.post_drafts(&mut journal).unwrap();
+        assert_eq!(reconciliation.drafts().count(), 0);
+    }
+    let journals = [journal];
+    let reconciled: Vec<usize> = drafts
+        .reconciliations
+        .iter()
+        .map(|reconciliation| {
+            assert_eq!(reconciliation.difference(&journals), Some(Decimal::ZERO));
+            reconciliation
+                .reconcile(&journals, "October", &[])
+                .unwrap()
+                .len()
+        })
+        .collect();
+    assert_eq!(reconciled, vec![3, 2, 4]);
+
+    // Importing the same files again finds only duplicates.
+    let again = draft_transactions(statements(), &ledger, &journals, &options);
+    assert_eq!(again.duplicates.len(), 9);
+    assert!(again
+        .reconciliations
+        .iter()
+        .all(|reconciliation| reconciliation.drafts().count() == 0));
+}
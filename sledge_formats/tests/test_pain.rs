@@ -0,0 +1,215 @@
+use chrono::{NaiveDate, TimeZone, Utc};
+use codes_iso_4217::CurrencyCode;
+use roxmltree::{Document, Node};
+use rust_decimal::Decimal;
+use sledge_formats::pain::{
+    is_valid_iban, write_pain001, CreditTransfer, PaymentParty, PaymentRun, PAIN_001_SCHEMA,
+};
+use sledge_model::journal::{Split, Transaction, TransactionId};
+use sledge_model::ledger::AccountId;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+pub mod common;
+
+use common::{date, eur};
+
+fn bank() -> AccountId {
+    AccountId::from("Assets:Current")
+}
+
+fn payment(id: &str, day: u32, units: i64, reference: Option<&str>) -> Transaction {
+    let id = TransactionId::from(id);
+    let posted = date(2022, 12, day);
+    let transaction = Transaction::new(id.clone(), posted)
+        .with_entered(posted)
+        .with_description("Supplier payment")
+        .with_split(Split::new(id.clone(), bank(), eur(-units)))
+        .with_split(Split::new(
+            id,
+            AccountId::from("Liabilities:Payable"),
+            eur(units),
+        ));
+    match reference {
+        Some(reference) => transaction.with_reference(reference),
+        None => transaction,
+    }
+}
+
+fn run() -> PaymentRun {
+    let debtor =
+        PaymentParty::new("Example GmbH", "DE89 3704 0044 0532 0130 00").with_bic("COBADEFFXXX");
+    let mut run = PaymentRun::new("RUN-2022-12-01", debtor, CurrencyCode::EUR)
+        .with_created(Utc.with_ymd_and_hms(2022, 12, 1, 9, 0, 0).unwrap());
+    run.add_transaction(
+        &payment("pay-118", 2, 124950, Some("INV-2022-118")),
+        &bank(),
+        PaymentParty::new("Paper & Ink BV", "NL91ABNA0417164300").with_bic("ABNANL2A"),
+    )
+    .unwrap();
+    run.add_transaction(
+        &payment("pay-119", 5, 8000, None),
+        &bank(),
+        PaymentParty::new("City Power", "DE90100400000123456789"),
+    )
+    .unwrap();
+    run.add_transfer(CreditTransfer::new(
+        TransactionId::from("pay-120"),
+        PaymentParty::new("Telco AG", "DE75512108001245126199"),
+        Decimal::new(2500, 2),
+        NaiveDate::from_ymd_opt(2022, 12, 2).unwrap(),
+    ));
+    run
+}
+
+fn write(run: &PaymentRun) -> String {
+    let mut bytes: Vec<u8> = Default::default();
+    write_pain001(&mut bytes, run).unwrap();
+    String::from_utf8(bytes).unwrap()
+}
+
+///
+/// Validate a document against the bundled schema with `xmllint`.
+///
+fn validate(xml: &str) -> Result<(), String> {
+    let schema =
+        std::env::temp_dir().join(format!("sledge-pain.001.001.03-{}.xsd", std::process::id()));
+    std::fs::write(&schema, PAIN_001_SCHEMA).unwrap();
+    let mut xmllint = Command::new("xmllint")
+        .args(["--noout", "--nonet", "--schema"])
+        .arg(&schema)
+        .arg("-")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("xmllint must be installed to validate against the schema");
+    xmllint
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(xml.as_bytes())
+        .unwrap();
+    let output = xmllint.wait_with_output().unwrap();
+    let _ = std::fs::remove_file(&schema);
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).into_owned())
+    }
+}
+
+fn text<'a>(node: Node<'a, 'a>, path: &[&str]) -> Vec<&'a str> {
+    let mut nodes = vec![node];
+    for name in path {
+        nodes = nodes
+            .into_iter()
+            .flat_map(|node| node.children().filter(|child| child.has_tag_name(*name)))
+            .collect();
+    }
+    nodes.into_iter().filter_map(|node| node.text()).collect()
+}
+
+#[test]
+fn test_write_payment_run() {
+    let run = run();
+    assert_eq!(run.control_sum(), Decimal::new(135450, 2));
+    let xml = write(&run);
+
+    let document = Document::parse(&xml).unwrap();
+    let message = document.root_element().first_element_child().unwrap();
+    assert_eq!(text(message, &["GrpHdr", "NbOfTxs"]), vec!["3"]);
+    assert_eq!(text(message, &["GrpHdr", "CtrlSum"]), vec!["1354.50"]);
+
+    // One payment information block for each execution date.
+    assert_eq!(
+        text(message, &["PmtInf", "ReqdExctnDt"]),
+        vec!["2022-12-02", "2022-12-05"]
+    );
+    assert_eq!(
+        text(message, &["PmtInf", "CtrlSum"]),
+        vec!["1274.50", "80.00"]
+    );
+    assert_eq!(
+        text(message, &["PmtInf", "DbtrAcct", "Id", "IBAN"]),
+        vec!["DE89370400440532013000", "DE89370400440532013000"]
+    );
+    assert_eq!(
+        text(message, &["PmtInf", "CdtTrfTxInf", "PmtId", "EndToEndId"]),
+        vec!["pay-118", "pay-120", "pay-119"]
+    );
+    assert_eq!(
+        text(message, &["PmtInf", "CdtTrfTxInf", "Cdtr", "Nm"]),
+        vec!["Paper & Ink BV", "Telco AG", "City Power"]
+    );
+    assert_eq!(
+        text(
+            message,
+            &["PmtInf", "CdtTrfTxInf", "CdtrAgt", "FinInstnId", "BIC"]
+        ),
+        vec!["ABNANL2A"]
+    );
+    assert_eq!(
+        text(message, &["PmtInf", "CdtTrfTxInf", "RmtInf", "Ustrd"]),
+        vec!["INV-2022-118 Supplier payment", "Supplier payment"]
+    );
+}
+
+#[test]
+fn test_invalid_run_rejected() {
+    assert!(is_valid_iban("GB82WEST12345698765432"));
+    assert!(!is_valid_iban("GB82WEST12345698765433"));
+
+    let date = NaiveDate::from_ymd_opt(2022, 12, 2).unwrap();
+    let creditor = PaymentParty::new("Telco AG", "DE75512108001245126199");
+    for transfer in [
+        CreditTransfer::new(
+            TransactionId::from("pay-1"),
+            PaymentParty::new("Telco AG", "DE75512108001245126198"),
+            Decimal::ONE,
+            date,
+        ),
+        CreditTransfer::new(
+            TransactionId::from("a transaction id longer than 35 chars"),
+            creditor.clone(),
+            Decimal::ONE,
+            date,
+        ),
+        CreditTransfer::new(
+            TransactionId::from("pay-2"),
+            creditor.clone(),
+            Decimal::new(1005, 3),
+            date,
+        ),
+        CreditTransfer::new(
+            TransactionId::from("pay-3"),
+            creditor.clone().with_bic("DEUTDEF"),
+            Decimal::ONE,
+            date,
+        ),
+    ] {
+        let run = run().with_transfer(transfer);
+        let mut bytes: Vec<u8> = Default::default();
+        assert!(write_pain001(&mut bytes, &run).is_err());
+        assert!(bytes.is_empty());
+    }
+
+    // A refund, received rather than paid, is not a credit transfer.
+    let mut run = run();
+    assert!(run
+        .add_transaction(&payment("refund", 3, -500, None), &bank(), creditor)
+        .is_err());
+}
+
+#[test]
+#[ignore = "needs xmllint, from libxml2"]
+fn test_payment_run_matches_schema() {
+    let xml = write(&run());
+    validate(&xml).unwrap();
+
+    // And the schema check itself rejects a document that breaks it.
+    let xml = write(&run()).replace("<NbOfTxs>3</NbOfTxs>", "<NbOfTxs>three</NbOfTxs>");
+    assert!(validate(&xml).is_err());
+    let xml = write(&run()).replacen("<PmtMtd>TRF</PmtMtd>", "", 1);
+    assert!(validate(&xml).is_err());
+}
@@ -0,0 +1,275 @@
+use rust_decimal::Decimal;
+use sledge_formats::qif::{read_qif, QifDateFormat, QifOptions};
+use sledge_model::commodity::{CommodityId, InternationalSecuritiesId};
+use sledge_model::journal::Transaction;
+use sledge_model::ledger::{AccountId, AccountKind};
+
+const QIF: &str = r#"!Option:AutoSwitch
+!Account
+NChecking
+TBank
+DFirst Tech Checking
+^
+NVisa
+TCCard
+^
+NBrokerage
+TInvst
+^
+!Clear:AutoSwitch
+!Type:Cat
+NSalary
+DPay
+I
+^
+NGroceries
+DFood
+E
+^
+!Type:Security
+NApple Inc.
+SAAPL
+TStock
+^
+!Type:Class
+NHome
+^
+!Account
+NChecking
+TBank
+^
+!Type:Bank
+D10/ 1'22
+T1,000.00
+CX
+POpening Balance
+L[Checking]
+^
+D10/3'22
+T2,500.00
+C*
+PACME Payroll
+LSalary
+^
+D10/5'22
+T-142.50
+N1042
+PGrocer
+MWeekly shop
+LGroceries
+SGroceries/Home
+EFood
+$-100.00
+SHousehold
+$-42.50
+^
+D10/10'22
+T-1,455.00
+PTransfer to brokerage
+L[Brokerage]
+^
+D10/12'22
+T-50.00
+PCard payment
+L[Visa]
+^
+!Account
+NVisa
+TCCard
+^
+!Type:CCard
+D10/8'22
+T-89.99
+PBookshop
+LBooks
+^
+D10/12'22
+T50.00
+PCard payment
+L[Checking]
+^
+!Account
+NBrokerage
+TInvst
+^
+!Type:Invst
+D10/10'22
+NBuyX
+YApple Inc.
+I145.00
+Q10
+T1,455.00
+O5.00
+L[Checking]
+$1,455.00
+^
+D10/15'22
+NSell
+YApple Inc.
+I150
+Q4
+T600.00
+^
+D10/20'22
+NDiv
+YApple Inc.
+T1.38
+^
+D10/25'22
+NShrsIn
+YApple Inc.
+Q5
+^
+!Type:Prices
+"AAPL",147.27,"10/21'22"
+^
+"#;
+
+fn options() -> QifOptions {
+    QifOptions::default().with_security(
+        "AAPL",
+        CommodityId::Security(InternationalSecuritiesId::from("US0378331005")),
+    )
+}
+
+fn amounts(transaction: &Transaction) -> Vec<(String, Decimal)> {
+    transaction
+        .splits()
+        .map(|split| (split.account().to_string(), split.quantity().quantity()))
+        .collect()
+}
+
+#[test]
+fn test_import_qif() {
+    let imported = read_qif(QIF.as_bytes(), &options()).unwrap();
+    let ledger = &imported.contents.ledgers[0];
+    assert_eq!(ledger.book().count(), 14);
+    let visa = ledger
+        .account(&AccountId::from("Liabilities:Visa"))
+        .unwrap();
+    assert_eq!(visa.kind(), AccountKind::Liability);
+    assert_eq!(visa.parent_id(), Some(&AccountId::from("Liabilities")));
+    assert_eq!(
+        ledger
+            .account(&AccountId::from("Income:Salary"))
+            .unwrap()
+            .kind(),
+        AccountKind::Income
+    );
+    assert_eq!(
+        ledger
+            .account(&AccountId::from("Expenses:Household"))
+            .unwrap()
+            .kind(),
+        AccountKind::Expense
+    );
+
+    let journal = &imported.contents.journals[0];
+    let transactions: Vec<_> = journal.transactions().collect();
+    // The card payment appears in both registers, but is imported once.
+    assert_eq!(transactions.len(), 9);
+
+    assert_eq!(
+        amounts(transactions[0]),
+        vec![
+            ("Assets:Checking".to_string(), Decimal::new(100000, 2)),
+            (
+                "Equity:Opening Balances".to_string(),
+                Decimal::new(-100000, 2)
+            ),
+        ]
+    );
+
+    let grocer = transactions[2];
+    assert_eq!(grocer.description().map(String::as_str), Some("Grocer"));
+    assert_eq!(grocer.reference().map(String::as_str), Some("1042"));
+    assert_eq!(
+        grocer.metadata_value("class").map(String::as_str),
+        Some("Home")
+    );
+    assert_eq!(
+        amounts(grocer),
+        vec![
+            ("Assets:Checking".to_string(), Decimal::new(-14250, 2)),
+            ("Expenses:Groceries".to_string(), Decimal::new(10000, 2)),
+            ("Expenses:Household".to_string(), Decimal::new(4250, 2)),
+        ]
+    );
+    assert_eq!(
+        grocer
+            .splits()
+            .nth(1)
+            .unwrap()
+            .description()
+            .map(String::as_str),
+        Some("Food")
+    );
+
+    // The purchase was paid by the transfer from checking, so it uses the cash it moved.
+    let buy = transactions[5];
+    assert_eq!(buy.posted(), transactions[4].posted());
+    let shares = buy.splits().next().unwrap();
+    assert_eq!(shares.account(), &AccountId::from("Assets:Brokerage"));
+    assert_eq!(
+        shares.quantity().commodity(),
+        &CommodityId::Security(InternationalSecuritiesId::from("US0378331005"))
+    );
+    assert_eq!(shares.quantity().quantity(), Decimal::new(10, 0));
+    assert_eq!(
+        shares.exchanged_from().unwrap().quantity().quantity(),
+        Decimal::new(145500, 2)
+    );
+    assert_eq!(
+        amounts(buy)[1],
+        ("Assets:Brokerage".to_string(), Decimal::new(-145500, 2))
+    );
+
+    assert_eq!(
+        amounts(transactions[8]),
+        vec![
+            ("Assets:Brokerage".to_string(), Decimal::new(138, 2)),
+            ("Income:Dividends".to_string(), Decimal::new(-138, 2)),
+        ]
+    );
+
+    let checking: Decimal = transactions
+        .iter()
+        .flat_map(|transaction| transaction.splits())
+        .filter(|split| split.account() == &AccountId::from("Assets:Checking"))
+        .map(|split| split.quantity().quantity())
+        .sum();
+    assert_eq!(checking, Decimal::new(185250, 2));
+
+    assert_eq!(imported.contents.prices.len(), 1);
+    // The opening balance, reconciled, and the pay cheque, cleared.
+    assert_eq!(imported.contents.reconciliations.len(), 2);
+
+    let report = &imported.report;
+    assert_eq!(report.unmapped_of_kind("section").count(), 1);
+    assert_eq!(report.unmapped_of_kind("transaction").count(), 1);
+    assert_eq!(report.unmapped().count(), 2);
+}
+
+#[test]
+fn test_date_formats_and_categories() {
+    let qif = "!Type:Bank\nD31.12.2022\nT-12.50\nPCafe\nLDining:Coffee\n^\n";
+    assert!(read_qif(qif.as_bytes(), &QifOptions::default()).is_err());
+
+    let options = QifOptions::default()
+        .with_date_format(QifDateFormat::DayMonthYear)
+        .with_category("Dining:Coffee", AccountId::from("Expenses:Food:Coffee"));
+    let imported = read_qif(qif.as_bytes(), &options).unwrap();
+    assert!(imported.report.is_complete());
+    let transaction = imported.contents.journals[0].transactions().next().unwrap();
+    assert_eq!(
+        transaction.posted().date_naive().to_string(),
+        "2022-12-31".to_string()
+    );
+    assert_eq!(
+        amounts(transaction),
+        vec![
+            ("Assets:Bank".to_string(), Decimal::new(-1250, 2)),
+            ("Expenses:Food:Coffee".to_string(), Decimal::new(1250, 2)),
+        ]
+    );
+}
@@ -0,0 +1,334 @@
+use chrono::NaiveDate;
+use codes_iso_4217::CurrencyCode;
+use roxmltree::{Document, Node};
+use rust_decimal::Decimal;
+use sledge_formats::saft::{write_saft, SaftExport};
+use sledge_model::commodity::{CommodityId, Quantity, RatedQuantity};
+use sledge_model::journal::{Journal, Split, Transaction, TransactionId};
+use sledge_model::ledger::{
+    Account, AccountId, AccountKind, AccountRepresents, Customer, Ledger, LedgerKind, Supplier,
+};
+use sledge_model::party::{Address, CountryCode, LegalEntityId, Party, PartyId};
+use sledge_model::tax::{TaxCode, TaxCodeId, TaxRate, TaxTable, TaxTreatment};
+
+pub mod common;
+
+use common::{date, nok};
+
+fn party(id: &str, name: &str) -> Party {
+    Party::new(
+        PartyId::LegalEntity(LegalEntityId::from(id)),
+        name,
+        Address::new("1", "Storgata", "Oslo", "", "0155", CountryCode::from("NO")),
+    )
+}
+
+fn company() -> Party {
+    party("5493001KJTIIGC8Y1R12", "Example AS")
+}
+
+fn parties() -> Vec<Party> {
+    vec![
+        party("529900T8BM49AURSDO55", "Acme AS"),
+        party("549300MLUDYVRQOOXS22", "Paper & Ink AS"),
+    ]
+}
+
+fn ledger() -> Ledger {
+    let account = |id: &str, kind: AccountKind| {
+        Account::new(
+            AccountId::from(id),
+            kind,
+            id,
+            CommodityId::Currency(CurrencyCode::NOK),
+        )
+    };
+    let acme = PartyId::LegalEntity(LegalEntityId::from("529900T8BM49AURSDO55"));
+    let paper = PartyId::LegalEntity(LegalEntityId::from("549300MLUDYVRQOOXS22"));
+    Ledger::new(LedgerKind::General, "General", CurrencyCode::NOK)
+        .with_account(
+            account("1500", AccountKind::Asset)
+                .with_represents(AccountRepresents::Customer(Customer::new(acme))),
+        )
+        .with_account(account("1920", AccountKind::Asset))
+        .with_account(account("2050", AccountKind::Equity))
+        .with_account(
+            account("2400", AccountKind::Liability)
+                .with_represents(AccountRepresents::Supplier(Supplier::new(paper))),
+        )
+        .with_account(account("2700", AccountKind::Liability))
+        .with_account(account("2710", AccountKind::Asset))
+        .with_account(account("3000", AccountKind::Income))
+        .with_account(account("6500", AccountKind::Expense))
+}
+
+fn tax_table() -> TaxTable {
+    [
+        TaxCode::new(
+            TaxCodeId::from("3"),
+            "Output, high rate",
+            TaxTreatment::Standard,
+        )
+        .with_rate(TaxRate::new(date(2005, 1, 1), Decimal::new(25, 2)))
+        .with_output_account(AccountId::from("2700"))
+        .with_input_account(AccountId::from("2710")),
+        TaxCode::new(TaxCodeId::from("0"), "Exempt", TaxTreatment::Exempt),
+    ]
+    .into_iter()
+    .collect()
+}
+
+fn transaction(id: &str, month: u32, day: u32, splits: Vec<(&str, i64, bool)>) -> Transaction {
+    let id = TransactionId::from(id);
+    let posted = date(2023, month, day);
+    splits.into_iter().fold(
+        Transaction::new(id.clone(), posted).with_entered(posted),
+        |transaction, (account, units, taxed)| {
+            let split = Split::new(id.clone(), AccountId::from(account), nok(units));
+            transaction.with_split(if taxed {
+                split.with_tax_code(TaxCodeId::from("3"))
+            } else {
+                split
+            })
+        },
+    )
+}
+
+fn journals() -> Vec<Journal> {
+    let mut journal = Journal::new("general", CurrencyCode::NOK);
+    let opening = TransactionId::from("opening");
+    let posted = date(2022, 12, 31);
+    let id = TransactionId::from("pay-500");
+    let payment = Transaction::new(id.clone(), date(2023, 1, 25))
+        .with_entered(date(2023, 1, 24))
+        .with_split(Split::new(id.clone(), AccountId::from("2400"), nok(50000)))
+        .with_split(
+            Split::new(id, AccountId::from("1920"), nok(-50000)).with_exchanged_from(
+                RatedQuantity::new(
+                    Quantity::new(
+                        CommodityId::Currency(CurrencyCode::USD),
+                        Decimal::new(-625, 1),
+                    ),
+                    Decimal::new(80, 0),
+                ),
+            ),
+        );
+    for transaction in [
+        Transaction::new(opening.clone(), posted)
+            .with_entered(posted)
+            .with_description("Opening balance")
+            .with_split(Split::new(
+                opening.clone(),
+                AccountId::from("1920"),
+                nok(100000),
+            ))
+            .with_split(Split::new(opening, AccountId::from("2050"), nok(-100000))),
+        transaction(
+            "sale-1",
+            1,
+            5,
+            vec![
+                ("1500", 125000, false),
+                ("3000", -100000, true),
+                ("2700", -25000, true),
+            ],
+        )
+        .with_reference("INV-1")
+        .with_description("Consulting, January"),
+        transaction(
+            "bill-1",
+            1,
+            10,
+            vec![
+                ("6500", 40000, true),
+                ("2710", 10000, true),
+                ("2400", -50000, false),
+            ],
+        ),
+        transaction(
+            "receipt-1",
+            1,
+            20,
+            vec![("1920", 125000, false), ("1500", -125000, false)],
+        ),
+        payment,
+        transaction(
+            "sale-2",
+            2,
+            1,
+            vec![("1500", 100, false), ("3000", -100, false)],
+        ),
+    ] {
+        journal.add_transaction(transaction).unwrap();
+    }
+    vec![journal]
+}
+
+fn child<'a>(node: Node<'a, 'a>, path: &[&str]) -> Node<'a, 'a> {
+    path.iter().fold(node, |node, name| {
+        node.children()
+            .find(|child| child.has_tag_name(*name))
+            .unwrap_or_else(|| panic!("no {}", name))
+    })
+}
+
+fn text<'a>(node: Node<'a, 'a>, path: &[&str]) -> &'a str {
+    child(node, path).text().unwrap()
+}
+
+#[test]
+fn test_export_period() {
+    let (company, parties, ledger, journals, table) =
+        (company(), parties(), ledger(), journals(), tax_table());
+    let export = SaftExport::new(
+        &company,
+        &ledger,
+        &journals,
+        NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(),
+        NaiveDate::from_ymd_opt(2023, 1, 31).unwrap(),
+    )
+    .with_parties(&parties)
+    .with_tax_table(&table)
+    .with_created(date(2023, 2, 10));
+
+    let totals = export.control_totals().unwrap();
+    assert_eq!(totals.general_ledger.number_of_entries(), 4);
+    assert_eq!(totals.general_ledger.total_debit(), Decimal::new(350000, 2));
+    assert_eq!(
+        totals.general_ledger.total_credit(),
+        Decimal::new(350000, 2)
+    );
+    assert_eq!(totals.sales_invoices.number_of_entries(), 1);
+    assert_eq!(totals.sales_invoices.total_debit(), Decimal::ZERO);
+    assert_eq!(
+        totals.sales_invoices.total_credit(),
+        Decimal::new(100000, 2)
+    );
+    assert_eq!(
+        totals.purchase_invoices.total_debit(),
+        Decimal::new(40000, 2)
+    );
+    assert_eq!(totals.payments.number_of_entries(), 2);
+    assert_eq!(totals.payments.total_debit(), Decimal::new(175000, 2));
+    assert_eq!(totals.payments.total_credit(), Decimal::new(175000, 2));
+
+    let mut bytes: Vec<u8> = Default::default();
+    write_saft(&mut bytes, &export).unwrap();
+    let xml = String::from_utf8(bytes).unwrap();
+    let document = Document::parse(&xml).unwrap();
+    let root = document.root_element();
+
+    let header = child(root, &["Header"]);
+    assert_eq!(text(header, &["AuditFileCountry"]), "NO");
+    assert_eq!(text(header, &["Company", "Name"]), "Example AS");
+    assert_eq!(
+        text(header, &["SelectionCriteria", "SelectionEndDate"]),
+        "2023-01-31"
+    );
+
+    let master = child(root, &["MasterFiles"]);
+    let bank = child(master, &["GeneralLedgerAccounts"])
+        .children()
+        .find(|account| account.is_element() && text(*account, &["AccountID"]) == "1920")
+        .unwrap();
+    assert_eq!(text(bank, &["OpeningDebitBalance"]), "1000.00");
+    assert_eq!(text(bank, &["ClosingDebitBalance"]), "1750.00");
+    let supplier = child(master, &["Suppliers", "Supplier"]);
+    assert_eq!(text(supplier, &["Name"]), "Paper & Ink AS");
+    assert_eq!(text(supplier, &["SupplierID"]), "549300MLUDYVRQOOXS22");
+    assert_eq!(text(supplier, &["AccountID"]), "2400");
+    assert_eq!(
+        text(
+            child(master, &["Customers", "Customer"]),
+            &["ClosingDebitBalance"]
+        ),
+        "0.00"
+    );
+    let details: Vec<_> = child(master, &["TaxTable", "TaxTableEntry"])
+        .children()
+        .filter(|node| node.has_tag_name("TaxCodeDetails"))
+        .map(|node| text(node, &["TaxPercentage"]))
+        .collect();
+    assert_eq!(details, vec!["25", "0"]);
+
+    let entries = child(root, &["GeneralLedgerEntries"]);
+    assert_eq!(text(entries, &["NumberOfEntries"]), "4");
+    assert_eq!(text(entries, &["TotalDebit"]), "3500.00");
+    let sale = child(entries, &["Journal", "Transaction"]);
+    assert_eq!(text(sale, &["TransactionID"]), "sale-1");
+    assert_eq!(text(sale, &["Period"]), "1");
+    let lines: Vec<_> = sale
+        .children()
+        .filter(|node| node.has_tag_name("Line"))
+        .collect();
+    assert_eq!(text(lines[0], &["CustomerID"]), "529900T8BM49AURSDO55");
+    assert_eq!(text(lines[1], &["CreditAmount", "Amount"]), "1000.00");
+    assert_eq!(
+        text(lines[1], &["TaxInformation", "TaxAmount", "Amount"]),
+        "250.00"
+    );
+    assert!(lines[2]
+        .children()
+        .all(|node| !node.has_tag_name("TaxInformation")));
+    let payment = child(entries, &["Journal"])
+        .children()
+        .rfind(|node| node.has_tag_name("Transaction"))
+        .unwrap();
+    assert_eq!(text(payment, &["SystemEntryDate"]), "2023-01-24");
+    let foreign = child(payment, &["Line"]).next_sibling_element().unwrap();
+    assert_eq!(text(foreign, &["CreditAmount", "CurrencyCode"]), "USD");
+    assert_eq!(text(foreign, &["CreditAmount", "CurrencyAmount"]), "62.5");
+
+    let documents = child(root, &["SourceDocuments"]);
+    let invoice = child(documents, &["SalesInvoices", "Invoice"]);
+    assert_eq!(text(invoice, &["InvoiceNo"]), "INV-1");
+    assert_eq!(
+        text(invoice, &["CustomerInfo", "CustomerID"]),
+        "529900T8BM49AURSDO55"
+    );
+    assert_eq!(text(invoice, &["Line", "DebitCreditIndicator"]), "C");
+    let totals = child(invoice, &["DocumentTotals"]);
+    assert_eq!(
+        text(totals, &["TaxInformationTotals", "TaxAmount", "Amount"]),
+        "250.00"
+    );
+    assert_eq!(text(totals, &["NetTotal"]), "1000.00");
+    assert_eq!(text(totals, &["GrossTotal"]), "1250.00");
+    assert_eq!(
+        text(
+            documents,
+            &["PurchaseInvoices", "Invoice", "SupplierInfo", "SupplierID"]
+        ),
+        "549300MLUDYVRQOOXS22"
+    );
+    assert_eq!(text(documents, &["Payments", "NumberOfEntries"]), "2");
+}
+
+#[test]
+fn test_export_needs_parties_and_tax_codes() {
+    let (company, parties, ledger, journals, table) =
+        (company(), parties(), ledger(), journals(), tax_table());
+    let start = NaiveDate::from_ymd_opt(2023, 1, 1).unwrap();
+    let end = NaiveDate::from_ymd_opt(2023, 1, 31).unwrap();
+
+    let mut bytes: Vec<u8> = Default::default();
+    let export = SaftExport::new(&company, &ledger, &journals, start, end).with_tax_table(&table);
+    assert!(write_saft(&mut bytes, &export).is_err());
+    let export = SaftExport::new(&company, &ledger, &journals, start, end).with_parties(&parties);
+    assert!(write_saft(&mut bytes, &export).is_err());
+    assert!(bytes.is_empty());
+
+    // Nothing taxed or owed in February but one sale.
+    let export = SaftExport::new(
+        &company,
+        &ledger,
+        &journals,
+        NaiveDate::from_ymd_opt(2023, 2, 1).unwrap(),
+        NaiveDate::from_ymd_opt(2023, 2, 28).unwrap(),
+    )
+    .with_parties(&parties);
+    let totals = export.control_totals().unwrap();
+    assert_eq!(totals.general_ledger.number_of_entries(), 1);
+    assert_eq!(totals.sales_invoices.total_credit(), Decimal::new(100, 2));
+    assert_eq!(totals.payments.number_of_entries(), 0);
+}
@@ -0,0 +1,425 @@
+use chrono::{NaiveDate, TimeZone, Utc};
+use codes_iso_4217::CurrencyCode;
+use roxmltree::{Document, Node};
+use rust_decimal::Decimal;
+use sledge_formats::error::Error;
+use sledge_formats::statement::IMPORT_ID;
+use sledge_formats::ubl::{
+    draft_bill, read_ubl_invoice, write_ubl_invoice, BillOptions, InvoiceParty, SalesInvoice,
+    SalesInvoiceLine, PEPPOL_CUSTOMIZATION_ID, UBL_INVOICE_NAMESPACE,
+};
+use sledge_model::commodity::CommodityId;
+use sledge_model::ledger::{
+    Account, AccountId, AccountKind, AccountRepresents, Ledger, LedgerKind, Supplier,
+};
+use sledge_model::party::{
+    Address, CountryCode, GlobalLocationNumber, LegalEntityId, Party, PartyId, PartyIdentifier,
+    VatNumber,
+};
+use sledge_model::tax::{TaxCode, TaxCodeId, TaxRate, TaxTable, TaxTreatment};
+
+const CBC: &str = "urn:oasis:names:specification:ubl:schema:xsd:CommonBasicComponents-2";
+
+const SELLER_LEI: &str = "724500VKKSH9QOLTFR81";
+
+const SUPPLIER_LEI: &str = "529900T8BM49AURSDO55";
+
+fn party(lei: &str, name: &str, city: &str, country: &str) -> Party {
+    Party::new(
+        PartyId::LegalEntity(LegalEntityId::from(lei)),
+        name,
+        Address::new(
+            "12",
+            "Keizersgracht",
+            city,
+            "",
+            "1015 CJ",
+            CountryCode::from(country),
+        ),
+    )
+}
+
+fn tax_table() -> TaxTable {
+    let rate = |percent: i64| {
+        TaxRate::new(
+            Utc.with_ymd_and_hms(2012, 10, 1, 0, 0, 0).unwrap(),
+            Decimal::new(percent, 2),
+        )
+    };
+    [
+        TaxCode::new(TaxCodeId::from("S21"), "High rate", TaxTreatment::Standard)
+            .with_rate(rate(21))
+            .with_output_account(AccountId::from("2700"))
+            .with_input_account(AccountId::from("2710")),
+        TaxCode::new(TaxCodeId::from("Z"), "Zero rated", TaxTreatment::ZeroRated),
+        TaxCode::new(
+            TaxCodeId::from("RC"),
+            "Reverse charge",
+            TaxTreatment::ReverseCharge,
+        )
+        .with_rate(rate(21))
+        .with_output_account(AccountId::from("2700"))
+        .with_input_account(AccountId::from("2710")),
+    ]
+    .into_iter()
+    .collect()
+}
+
+fn ledger() -> Ledger {
+    let account = |id: &str, kind: AccountKind| {
+        Account::new(
+            AccountId::from(id),
+            kind,
+            id,
+            CommodityId::Currency(CurrencyCode::EUR),
+        )
+    };
+    Ledger::new(LedgerKind::Purchase, "Purchases", CurrencyCode::EUR)
+        .with_account(account("2400", AccountKind::Liability).with_represents(
+            AccountRepresents::Supplier(Supplier::new(PartyId::LegalEntity(LegalEntityId::from(
+                SUPPLIER_LEI,
+            )))),
+        ))
+        .with_account(account("2410", AccountKind::Liability).with_represents(
+            AccountRepresents::Supplier(Supplier::new(PartyId::LegalEntity(LegalEntityId::from(
+                SELLER_LEI,
+            )))),
+        ))
+        .with_account(account("2700", AccountKind::Liability))
+        .with_account(account("2710", AccountKind::Asset))
+        .with_account(account("6500", AccountKind::Expense))
+}
+
+fn splits(transaction: &sledge_model::journal::Transaction) -> Vec<(String, Decimal, String)> {
+    transaction
+        .splits()
+        .map(|split| {
+            (
+                split.account().to_string(),
+                split.quantity().quantity(),
+                split
+                    .tax_code()
+                    .map(|code| code.to_string())
+                    .unwrap_or_default(),
+            )
+        })
+        .collect()
+}
+
+fn text<'a>(node: Node<'a, '_>, path: &[&str]) -> Option<&'a str> {
+    path.iter()
+        .try_fold(node, |node, name| {
+            node.children()
+                .find(|child| child.is_element() && child.tag_name().name() == *name)
+        })
+        .and_then(|node| node.text())
+}
+
+#[test]
+fn test_party_identifiers() {
+    let vat = VatNumber::new("de 123.456.789");
+    assert_eq!(vat.as_str(), "DE123456789");
+    let identifier = PartyIdentifier::from_vat_number(&vat).unwrap();
+    assert_eq!(identifier.to_string(), "9930:DE123456789");
+    assert!(PartyIdentifier::from_vat_number(&VatNumber::from("NO999999999MVA")).is_none());
+
+    let gln = PartyIdentifier::from(&GlobalLocationNumber::from("5790000435975"));
+    assert_eq!(gln.scheme().as_str(), "0088");
+    assert!(gln.scheme().is_valid());
+    assert!(gln.to_party_id().is_none());
+
+    let lei = PartyIdentifier::from(&LegalEntityId::from(SELLER_LEI));
+    assert_eq!(lei.to_string(), format!("0199:{}", SELLER_LEI));
+    assert_eq!(
+        lei.to_party_id(),
+        Some(PartyId::LegalEntity(LegalEntityId::from(SELLER_LEI)))
+    );
+}
+
+#[test]
+fn test_write_sales_invoice_and_read_it_back() {
+    let seller = party(SELLER_LEI, "Example B.V.", "Amsterdam", "NL");
+    let buyer = party("5493001KJTIIGC8Y1R12", "Buyer GmbH", "Berlin", "DE");
+    let seller_vat = VatNumber::from("NL123456789B01");
+    let invoice = SalesInvoice::new(
+        "INV-2023-0042",
+        NaiveDate::from_ymd_opt(2023, 3, 14).unwrap(),
+        CurrencyCode::EUR,
+        InvoiceParty::new(
+            &seller,
+            PartyIdentifier::from_vat_number(&seller_vat).unwrap(),
+        )
+        .with_vat_number(seller_vat),
+        InvoiceParty::new(
+            &buyer,
+            PartyIdentifier::from(&GlobalLocationNumber::from("4000001123452")),
+        )
+        .with_vat_number(VatNumber::from("DE123456789")),
+    )
+    .with_due_date(NaiveDate::from_ymd_opt(2023, 4, 13).unwrap())
+    .with_buyer_reference("PO-7781")
+    .with_payment("NL91ABNA0417164300", Some("INV-2023-0042".to_string()))
+    .with_line(
+        SalesInvoiceLine::new(
+            "1",
+            "Consulting",
+            Decimal::new(3, 0),
+            Decimal::new(10000, 2),
+            TaxCodeId::from("S21"),
+        )
+        .with_unit_code("HUR"),
+    )
+    .with_line(SalesInvoiceLine::new(
+        "2",
+        "Travel",
+        Decimal::ONE,
+        Decimal::new(4999, 2),
+        TaxCodeId::from("S21"),
+    ))
+    .with_line(SalesInvoiceLine::new(
+        "3",
+        "Books",
+        Decimal::new(2, 0),
+        Decimal::new(10, 0),
+        TaxCodeId::from("Z"),
+    ));
+    let tax_table = tax_table();
+
+    let mut buffer = Vec::new();
+    write_ubl_invoice(&mut buffer, &invoice, &tax_table).unwrap();
+    let source = String::from_utf8(buffer).unwrap();
+    let document = Document::parse(&source).unwrap();
+    let root = document.root_element();
+    assert_eq!(root.tag_name().namespace(), Some(UBL_INVOICE_NAMESPACE));
+    assert_eq!(
+        text(root, &["CustomizationID"]),
+        Some(PEPPOL_CUSTOMIZATION_ID)
+    );
+    assert_eq!(text(root, &["InvoiceTypeCode"]), Some("380"));
+
+    let endpoints: Vec<_> = root
+        .descendants()
+        .filter(|node| node.has_tag_name((CBC, "EndpointID")))
+        .map(|node| (node.attribute("schemeID").unwrap(), node.text().unwrap()))
+        .collect();
+    assert_eq!(
+        endpoints,
+        vec![("9944", "NL123456789B01"), ("0088", "4000001123452")]
+    );
+    let seller_node = root
+        .descendants()
+        .find(|node| node.tag_name().name() == "AccountingSupplierParty")
+        .unwrap();
+    assert_eq!(
+        text(seller_node, &["Party", "PartyLegalEntity", "CompanyID"]),
+        Some(SELLER_LEI)
+    );
+    assert_eq!(
+        text(
+            seller_node,
+            &["Party", "PostalAddress", "Country", "IdentificationCode"]
+        ),
+        Some("NL")
+    );
+
+    let tax_total = root
+        .children()
+        .find(|node| node.tag_name().name() == "TaxTotal")
+        .unwrap();
+    assert_eq!(text(tax_total, &["TaxAmount"]), Some("73.50"));
+    let subtotals: Vec<_> = tax_total
+        .children()
+        .filter(|node| node.tag_name().name() == "TaxSubtotal")
+        .map(|node| {
+            (
+                text(node, &["TaxCategory", "ID"]).unwrap(),
+                text(node, &["TaxCategory", "Percent"]).unwrap(),
+                text(node, &["TaxableAmount"]).unwrap(),
+                text(node, &["TaxAmount"]).unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        subtotals,
+        vec![("S", "21", "349.99", "73.50"), ("Z", "0", "20.00", "0.00")]
+    );
+    assert_eq!(
+        text(root, &["LegalMonetaryTotal", "PayableAmount"]),
+        Some("443.49")
+    );
+    let quantity = root
+        .descendants()
+        .find(|node| node.has_tag_name((CBC, "InvoicedQuantity")))
+        .unwrap();
+    assert_eq!(quantity.attribute("unitCode"), Some("HUR"));
+
+    // The same document, received by the buyer, drafts a bill from the seller.
+    let received = read_ubl_invoice(source.as_bytes()).unwrap();
+    assert_eq!(received.id(), "INV-2023-0042");
+    assert_eq!(received.supplier().name(), "Example B.V.");
+    assert_eq!(
+        received.supplier().party_id(),
+        Some(PartyId::LegalEntity(LegalEntityId::from(SELLER_LEI)))
+    );
+    assert_eq!(received.payable(), Decimal::new(44349, 2));
+
+    let bill = draft_bill(
+        &received,
+        &ledger(),
+        &tax_table,
+        &BillOptions::new(AccountId::from("6500")),
+    )
+    .unwrap();
+    assert!(bill.is_balanced());
+    assert_eq!(bill.metadata_value(IMPORT_ID).unwrap(), "INV-2023-0042");
+    assert_eq!(bill.metadata_value("due_date").unwrap(), "2023-04-13");
+    let s = |account: &str, amount: i64, code: &str| {
+        (
+            account.to_string(),
+            Decimal::new(amount, 2),
+            code.to_string(),
+        )
+    };
+    assert_eq!(
+        splits(&bill),
+        vec![
+            s("6500", 30000, "S21"),
+            s("6500", 4999, "S21"),
+            s("6500", 2000, "Z"),
+            s("2710", 7350, "S21"),
+            s("2410", -44349, ""),
+        ]
+    );
+
+    let unreferenced = SalesInvoice::new(
+        "INV-2023-0043",
+        NaiveDate::from_ymd_opt(2023, 3, 14).unwrap(),
+        CurrencyCode::EUR,
+        invoice.seller().clone(),
+        invoice.buyer().clone(),
+    )
+    .with_line(invoice.lines().next().unwrap().clone());
+    let mut buffer = Vec::new();
+    assert!(matches!(
+        write_ubl_invoice(&mut buffer, &unreferenced, &tax_table),
+        Err(Error::MissingElement { name, .. }) if name == "BuyerReference"
+    ));
+    assert!(buffer.is_empty());
+}
+
+#[test]
+fn test_draft_reverse_charge_bill() {
+    let source = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<Invoice xmlns="urn:oasis:names:specification:ubl:schema:xsd:Invoice-2"
+         xmlns:cac="urn:oasis:names:specification:ubl:schema:xsd:CommonAggregateComponents-2"
+         xmlns:cbc="urn:oasis:names:specification:ubl:schema:xsd:CommonBasicComponents-2">
+  <cbc:CustomizationID>{}</cbc:CustomizationID>
+  <cbc:ID>R-1001</cbc:ID>
+  <cbc:IssueDate>2023-05-02</cbc:IssueDate>
+  <cbc:InvoiceTypeCode>380</cbc:InvoiceTypeCode>
+  <cbc:DocumentCurrencyCode>EUR</cbc:DocumentCurrencyCode>
+  <cbc:BuyerReference>PO-9</cbc:BuyerReference>
+  <cac:AccountingSupplierParty>
+    <cac:Party>
+      <cbc:EndpointID schemeID="9930">DE987654321</cbc:EndpointID>
+      <cac:PartyIdentification><cbc:ID schemeID="0199">{}</cbc:ID></cac:PartyIdentification>
+      <cac:PartyName><cbc:Name>Lieferant</cbc:Name></cac:PartyName>
+      <cac:PartyTaxScheme>
+        <cbc:CompanyID>DE987654321</cbc:CompanyID>
+        <cac:TaxScheme><cbc:ID>VAT</cbc:ID></cac:TaxScheme>
+      </cac:PartyTaxScheme>
+      <cac:PartyLegalEntity><cbc:RegistrationName>Lieferant GmbH</cbc:RegistrationName></cac:PartyLegalEntity>
+    </cac:Party>
+  </cac:AccountingSupplierParty>
+  <cac:AllowanceCharge>
+    <cbc:ChargeIndicator>false</cbc:ChargeIndicator>
+    <cbc:AllowanceChargeReason>Discount</cbc:AllowanceChargeReason>
+    <cbc:Amount currencyID="EUR">50.00</cbc:Amount>
+    <cac:TaxCategory><cbc:ID>AE</cbc:ID><cbc:Percent>0</cbc:Percent></cac:TaxCategory>
+  </cac:AllowanceCharge>
+  <cac:TaxTotal>
+    <cbc:TaxAmount currencyID="EUR">0.00</cbc:TaxAmount>
+    <cac:TaxSubtotal>
+      <cbc:TaxableAmount currencyID="EUR">950.00</cbc:TaxableAmount>
+      <cbc:TaxAmount currencyID="EUR">0.00</cbc:TaxAmount>
+      <cac:TaxCategory><cbc:ID>AE</cbc:ID><cbc:Percent>0</cbc:Percent></cac:TaxCategory>
+    </cac:TaxSubtotal>
+  </cac:TaxTotal>
+  <cac:LegalMonetaryTotal>
+    <cbc:LineExtensionAmount currencyID="EUR">1000.00</cbc:LineExtensionAmount>
+    <cbc:TaxExclusiveAmount currencyID="EUR">950.00</cbc:TaxExclusiveAmount>
+    <cbc:TaxInclusiveAmount currencyID="EUR">950.00</cbc:TaxInclusiveAmount>
+    <cbc:PayableAmount currencyID="EUR">950.00</cbc:PayableAmount>
+  </cac:LegalMonetaryTotal>
+  <cac:InvoiceLine>
+    <cbc:ID>1</cbc:ID>
+    <cbc:InvoicedQuantity unitCode="C62">4</cbc:InvoicedQuantity>
+    <cbc:LineExtensionAmount currencyID="EUR">1000.00</cbc:LineExtensionAmount>
+    <cac:Item>
+      <cbc:Name>Machine parts</cbc:Name>
+      <cac:ClassifiedTaxCategory><cbc:ID>AE</cbc:ID><cbc:Percent>0</cbc:Percent></cac:ClassifiedTaxCategory>
+    </cac:Item>
+    <cac:Price><cbc:PriceAmount currencyID="EUR">250.00</cbc:PriceAmount></cac:Price>
+  </cac:InvoiceLine>
+</Invoice>
+"#,
+        PEPPOL_CUSTOMIZATION_ID, SUPPLIER_LEI
+    );
+    let invoice = read_ubl_invoice(source.as_bytes()).unwrap();
+    assert_eq!(invoice.supplier().name(), "Lieferant GmbH");
+    assert_eq!(
+        invoice.supplier().vat_number().map(VatNumber::as_str),
+        Some("DE987654321")
+    );
+    assert_eq!(
+        invoice.supplier().endpoint().unwrap().scheme().as_str(),
+        "9930"
+    );
+    assert_eq!(invoice.lines().count(), 2);
+
+    let bill = draft_bill(
+        &invoice,
+        &ledger(),
+        &tax_table(),
+        &BillOptions::new(AccountId::from("6500")),
+    )
+    .unwrap();
+    let s = |account: &str, amount: i64, code: &str| {
+        (
+            account.to_string(),
+            Decimal::new(amount, 2),
+            code.to_string(),
+        )
+    };
+    assert_eq!(
+        splits(&bill),
+        vec![
+            s("6500", 100000, "RC"),
+            s("6500", -5000, "RC"),
+            s("2710", 19950, "RC"),
+            s("2700", -19950, "RC"),
+            s("2400", -95000, ""),
+        ]
+    );
+
+    let unknown = source.replace(SUPPLIER_LEI, "5493001KJTIIGC8Y1R12");
+    let invoice = read_ubl_invoice(unknown.as_bytes()).unwrap();
+    assert!(matches!(
+        draft_bill(
+            &invoice,
+            &ledger(),
+            &tax_table(),
+            &BillOptions::new(AccountId::from("6500")),
+        ),
+        Err(Error::InvalidValue { name, .. }) if name == "AccountingSupplierParty"
+    ));
+    let bill = draft_bill(
+        &invoice,
+        &ledger(),
+        &tax_table(),
+        &BillOptions::new(AccountId::from("6500")).with_supplier(AccountId::from("2400")),
+    )
+    .unwrap();
+    assert!(bill.is_balanced());
+}
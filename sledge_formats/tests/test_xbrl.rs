@@ -0,0 +1,248 @@
+use codes_iso_4217::CurrencyCode;
+use roxmltree::Document;
+use rust_decimal::Decimal;
+use sledge_formats::error::Error;
+use sledge_formats::xbrl::{
+    write_ixbrl, write_xbrl, XbrlReport, XbrlSection, IX_NAMESPACE, XBRLI_NAMESPACE,
+};
+use sledge_model::commodity::CommodityId;
+use sledge_model::journal::{Journal, Split, Transaction, TransactionId};
+use sledge_model::ledger::{Account, AccountId, AccountKind, Ledger, LedgerKind};
+use sledge_model::party::{Address, CountryCode, LegalEntityId, Party, PartyId};
+use sledge_model::statement::{BalanceSheet, ConceptMapping, IncomeStatement};
+
+pub mod common;
+
+use common::{date, eur};
+
+const NAMESPACE: &str = "https://xbrl.ifrs.org/taxonomy/2023-03-23/ifrs-full";
+
+fn company() -> Party {
+    Party::new(
+        PartyId::LegalEntity(LegalEntityId::from("5493001KJTIIGC8Y1R12")),
+        "Example B.V.",
+        Address::new(
+            "1",
+            "Damrak",
+            "Amsterdam",
+            "",
+            "1012",
+            CountryCode::from("NL"),
+        ),
+    )
+}
+
+fn mapping() -> ConceptMapping {
+    ConceptMapping::new(
+        "ifrs-full",
+        NAMESPACE,
+        "https://xbrl.ifrs.org/taxonomy/2023-03-23/full_ifrs_entry_point_2023-03-23.xsd",
+    )
+    .with_concept(
+        "CashAndCashEquivalents",
+        [AccountId::from("1010"), AccountId::from("1020")],
+    )
+    .with_concept("TradeAndOtherCurrentReceivables", [AccountId::from("1100")])
+    .with_concept("TradeAndOtherCurrentPayables", [AccountId::from("2100")])
+    .with_concept("IssuedCapital", [AccountId::from("3000")])
+    .with_concept("Revenue", [AccountId::from("4000")])
+    .with_concept("AdministrativeExpense", [AccountId::from("6000")])
+    .with_current_earnings("RetainedEarnings")
+}
+
+fn ledger() -> Ledger {
+    let account = |id: &str, kind: AccountKind| {
+        Account::new(
+            AccountId::from(id),
+            kind,
+            format!("Account {}", id),
+            CommodityId::Currency(CurrencyCode::EUR),
+        )
+    };
+    Ledger::new(LedgerKind::General, "General", CurrencyCode::EUR)
+        .with_account(account("1010", AccountKind::Asset))
+        .with_account(account("1020", AccountKind::Asset))
+        .with_account(account("1100", AccountKind::Asset))
+        .with_account(account("2100", AccountKind::Liability))
+        .with_account(account("3000", AccountKind::Equity))
+        .with_account(account("4000", AccountKind::Income))
+        .with_account(account("6000", AccountKind::Expense))
+}
+
+fn journals() -> Vec<Journal> {
+    let mut journal = Journal::new("general", CurrencyCode::EUR);
+    for (id, posted, debit, credit, amount) in [
+        ("T0", date(2021, 12, 1), "1010", "3000", 10000),
+        ("T1", date(2022, 1, 10), "1100", "4000", 5000),
+        ("T2", date(2022, 2, 10), "1020", "1100", 3000),
+        ("T3", date(2022, 5, 10), "6000", "2100", 1000),
+        ("T4", date(2022, 6, 10), "4000", "1100", 250),
+    ] {
+        let id = TransactionId::from(id);
+        journal
+            .add_transaction(
+                Transaction::new(id.clone(), posted)
+                    .with_split(Split::new(
+                        id.clone(),
+                        AccountId::from(debit),
+                        eur(amount * 100),
+                    ))
+                    .with_split(Split::new(id, AccountId::from(credit), eur(-amount * 100))),
+            )
+            .unwrap();
+    }
+    vec![journal]
+}
+
+#[test]
+fn test_write_xbrl_instance() {
+    let company = company();
+    let ledger = ledger().with_concept_mapping(mapping());
+    let journals = journals();
+    let balance_sheet = BalanceSheet::prepare(&ledger, &journals, date(2023, 1, 1));
+    let income_statement =
+        IncomeStatement::prepare(&ledger, &journals, date(2022, 1, 1), date(2023, 1, 1));
+    let report = XbrlReport::new(&company, &ledger, &balance_sheet, &income_statement);
+
+    let facts = report.facts().unwrap();
+    let cash = facts
+        .iter()
+        .find(|fact| fact.concept() == "CashAndCashEquivalents")
+        .unwrap();
+    assert_eq!(cash.amount(), Decimal::new(13000, 0));
+    assert_eq!(cash.accounts().count(), 2);
+    assert_eq!(cash.label(), "Account 1010; Account 1020");
+    assert!(facts.iter().any(|fact| fact.concept() == "RetainedEarnings"
+        && fact.section() == XbrlSection::Equity
+        && fact.amount() == Decimal::new(3750, 0)));
+
+    let mut buffer = Vec::new();
+    write_xbrl(&mut buffer, &report).unwrap();
+    let text = String::from_utf8(buffer).unwrap();
+    let document = Document::parse(&text).unwrap();
+    let root = document.root_element();
+    assert_eq!(root.tag_name().namespace(), Some(XBRLI_NAMESPACE));
+
+    let contexts: Vec<_> = root
+        .children()
+        .filter(|node| node.has_tag_name((XBRLI_NAMESPACE, "context")))
+        .collect();
+    assert_eq!(contexts.len(), 2);
+    let instant = contexts[0]
+        .descendants()
+        .find(|node| node.has_tag_name((XBRLI_NAMESPACE, "instant")))
+        .unwrap();
+    assert_eq!(instant.text(), Some("2022-12-31"));
+    let identifier = contexts[0]
+        .descendants()
+        .find(|node| node.has_tag_name((XBRLI_NAMESPACE, "identifier")))
+        .unwrap();
+    assert_eq!(
+        identifier.attribute("scheme"),
+        Some("http://standards.iso.org/iso/17442")
+    );
+    assert_eq!(identifier.text(), Some("5493001KJTIIGC8Y1R12"));
+
+    let facts: Vec<_> = root
+        .children()
+        .filter(|node| node.tag_name().namespace() == Some(NAMESPACE))
+        .map(|node| {
+            (
+                node.tag_name().name().to_string(),
+                node.attribute("contextRef").unwrap().to_string(),
+                node.text().unwrap().to_string(),
+            )
+        })
+        .collect();
+    let fact = |name: &str, context: &str, value: &str| {
+        (name.to_string(), context.to_string(), value.to_string())
+    };
+    assert_eq!(
+        facts,
+        vec![
+            fact("CashAndCashEquivalents", "I2022-12-31", "13000.00"),
+            fact("TradeAndOtherCurrentReceivables", "I2022-12-31", "1750.00"),
+            fact("TradeAndOtherCurrentPayables", "I2022-12-31", "1000.00"),
+            fact("IssuedCapital", "I2022-12-31", "10000.00"),
+            fact("RetainedEarnings", "I2022-12-31", "3750.00"),
+            fact("Revenue", "D2022-01-01_2022-12-31", "4750.00"),
+            fact("AdministrativeExpense", "D2022-01-01_2022-12-31", "1000.00"),
+        ]
+    );
+    assert!(root
+        .children()
+        .filter(|node| node.tag_name().namespace() == Some(NAMESPACE))
+        .all(|node| node.attribute("unitRef") == Some("EUR")
+            && node.attribute("decimals") == Some("2")));
+}
+
+#[test]
+fn test_write_ixbrl_and_validation() {
+    let company = company();
+    let ledger = ledger().with_concept_mapping(mapping());
+    let mut journals = journals();
+    let id = TransactionId::from("T5");
+    journals[0]
+        .add_transaction(
+            Transaction::new(id.clone(), date(2022, 7, 1))
+                .with_split(Split::new(id.clone(), AccountId::from("2100"), eur(150000)))
+                .with_split(Split::new(id, AccountId::from("1010"), eur(-150000))),
+        )
+        .unwrap();
+    let balance_sheet = BalanceSheet::prepare(&ledger, &journals, date(2023, 1, 1));
+    let income_statement =
+        IncomeStatement::prepare(&ledger, &journals, date(2022, 1, 1), date(2023, 1, 1));
+    let report = XbrlReport::new(&company, &ledger, &balance_sheet, &income_statement)
+        .with_title("Annual report 2022");
+
+    let mut buffer = Vec::new();
+    write_ixbrl(&mut buffer, &report).unwrap();
+    let text = String::from_utf8(buffer).unwrap();
+    let document = Document::parse(&text).unwrap();
+    let root = document.root_element();
+    assert_eq!(root.tag_name().name(), "html");
+
+    let header = root
+        .descendants()
+        .find(|node| node.has_tag_name((IX_NAMESPACE, "header")))
+        .unwrap();
+    assert_eq!(
+        header.parent_element().unwrap().attribute("style"),
+        Some("display:none")
+    );
+    assert_eq!(
+        header
+            .descendants()
+            .filter(|node| node.has_tag_name((XBRLI_NAMESPACE, "context")))
+            .count(),
+        2
+    );
+
+    let payables = root
+        .descendants()
+        .find(|node| {
+            node.has_tag_name((IX_NAMESPACE, "nonFraction"))
+                && node.attribute("name") == Some("ifrs-full:TradeAndOtherCurrentPayables")
+        })
+        .unwrap();
+    assert_eq!(payables.text(), Some("500.00"));
+    assert_eq!(payables.attribute("sign"), Some("-"));
+    assert_eq!(payables.attribute("format"), Some("ixt:num-dot-decimal"));
+    assert_eq!(
+        root.descendants()
+            .filter(|node| node.has_tag_name((IX_NAMESPACE, "nonFraction")))
+            .count(),
+        7
+    );
+
+    let unmapped = ledger
+        .clone()
+        .with_concept_mapping(mapping().with_concept("Revenue", [AccountId::from("1100")]));
+    let report = XbrlReport::new(&company, &unmapped, &balance_sheet, &income_statement);
+    let mut buffer = Vec::new();
+    assert!(matches!(
+        write_xbrl(&mut buffer, &report),
+        Err(Error::ModelError { .. })
+    ));
+    assert!(buffer.is_empty());
+}
@@ -0,0 +1,330 @@
+/*!
+Budgets; planned amounts per account for each period of a budget year, and the report that
+compares them with the actual balances.
+
+A [`Budget`] holds one amount per period for each budgeted account, in the natural sign of the
+account's kind so that both planned income and planned expenses are positive. An annual total
+can be spread across the periods evenly or by a seasonal [`Distribution`], and the next year's
+budget can be started by copying either this year's budget or this year's actual balances with
+a percentage uplift.
+
+*/
+
+use crate::commodity::CommodityId;
+use crate::error::{invalid_distribution, no_such_budget_period, Result};
+use crate::journal::Journal;
+use crate::ledger::{AccountId, Balances, Ledger};
+use chrono::{DateTime, Months, Utc};
+use codes_iso_4217::CurrencyCode;
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BudgetInterval {
+    Monthly,
+    Quarterly,
+}
+
+///
+/// How an annual amount is spread across the periods of a budget.
+///
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Distribution {
+    /// The same amount in every period.
+    Even,
+    /// In proportion to the weights, one for each period.
+    Seasonal(Vec<Decimal>),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Budget {
+    name: String,
+    start: DateTime<Utc>,
+    interval: BudgetInterval,
+    periods: usize,
+    currency: CurrencyCode,
+    amounts: BTreeMap<AccountId, Vec<Decimal>>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl BudgetInterval {
+    pub fn months(&self) -> u32 {
+        match self {
+            Self::Monthly => 1,
+            Self::Quarterly => 3,
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl Distribution {
+    ///
+    /// Spread `total` across `periods` periods, rounding each to `scale` decimal places; the
+    /// last period absorbs any rounding difference so that the parts add up to the total.
+    ///
+    pub fn spread(&self, total: Decimal, periods: usize, scale: u32) -> Result<Vec<Decimal>> {
+        let weights = match self {
+            Self::Even => vec![Decimal::ONE; periods],
+            Self::Seasonal(weights) if weights.len() == periods => weights.clone(),
+            Self::Seasonal(weights) => return Err(invalid_distribution(periods, weights.len())),
+        };
+        let sum: Decimal = weights.iter().sum();
+        let mut amounts: Vec<Decimal> = weights
+            .iter()
+            .map(|weight| {
+                if sum.is_zero() {
+                    Decimal::ZERO
+                } else {
+                    (total * weight / sum)
+                        .round_dp_with_strategy(scale, RoundingStrategy::MidpointAwayFromZero)
+                }
+            })
+            .collect();
+        if let Some((last, rest)) = amounts.split_last_mut() {
+            *last = total - rest.iter().sum::<Decimal>();
+        }
+        Ok(amounts)
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl Budget {
+    pub fn new<S: Into<String>>(
+        name: S,
+        start: DateTime<Utc>,
+        interval: BudgetInterval,
+        periods: usize,
+        currency: CurrencyCode,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            start,
+            interval,
+            periods,
+            currency,
+            amounts: Default::default(),
+        }
+    }
+
+    ///
+    /// A twelve-month budget in monthly periods.
+    ///
+    pub fn annual<S: Into<String>>(name: S, start: DateTime<Utc>, currency: CurrencyCode) -> Self {
+        Self::new(name, start, BudgetInterval::Monthly, 12, currency)
+    }
+
+    ///
+    /// Start next year's budget from the actual balances of the year that precedes `start`,
+    /// for every income and expense account in the ledger, increased by `uplift_percent`.
+    ///
+    pub fn from_actuals<S: Into<String>>(
+        name: S,
+        start: DateTime<Utc>,
+        interval: BudgetInterval,
+        periods: usize,
+        ledger: &Ledger,
+        journals: &[Journal],
+        uplift_percent: Decimal,
+    ) -> Self {
+        let mut budget = Self::new(name, start, interval, periods, ledger.currency());
+        let span = Months::new(interval.months() * periods as u32);
+        let previous = Self::new("", start - span, interval, periods, ledger.currency());
+        let balances: Vec<Balances> = (0..periods)
+            .map(|period| {
+                Balances::calculate(
+                    journals,
+                    Some(previous.period_start(period)),
+                    previous.period_end(period),
+                )
+            })
+            .collect();
+        for account in ledger.book().filter(|a| !a.kind().is_balance_sheet()) {
+            let amounts: Vec<Decimal> = balances
+                .iter()
+                .map(|balances| {
+                    let net = balances.balance(account.id());
+                    let natural = if account.kind().is_debit_normal() {
+                        net
+                    } else {
+                        -net
+                    };
+                    budget.uplift(natural, uplift_percent)
+                })
+                .collect();
+            if amounts.iter().any(|amount| !amount.is_zero()) {
+                budget.amounts.insert(account.id().clone(), amounts);
+            }
+        }
+        budget
+    }
+
+    ///
+    /// Copy this budget into the following year, each amount increased by `uplift_percent`.
+    ///
+    pub fn copy_to_next_year<S: Into<String>>(&self, name: S, uplift_percent: Decimal) -> Self {
+        let span = Months::new(self.interval.months() * self.periods as u32);
+        Self {
+            name: name.into(),
+            start: self.start + span,
+            interval: self.interval,
+            periods: self.periods,
+            currency: self.currency,
+            amounts: self
+                .amounts
+                .iter()
+                .map(|(account, amounts)| {
+                    (
+                        account.clone(),
+                        amounts
+                            .iter()
+                            .map(|amount| self.uplift(*amount, uplift_percent))
+                            .collect(),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn start(&self) -> DateTime<Utc> {
+        self.start
+    }
+
+    pub fn end(&self) -> DateTime<Utc> {
+        self.period_end(self.periods - 1)
+    }
+
+    pub fn interval(&self) -> BudgetInterval {
+        self.interval
+    }
+
+    pub fn periods(&self) -> usize {
+        self.periods
+    }
+
+    pub fn currency(&self) -> CurrencyCode {
+        self.currency
+    }
+
+    pub fn period_start(&self, period: usize) -> DateTime<Utc> {
+        self.start + Months::new(self.interval.months() * period as u32)
+    }
+
+    pub fn period_end(&self, period: usize) -> DateTime<Utc> {
+        self.period_start(period + 1)
+    }
+
+    ///
+    /// The index of the period that contains `date`, if any.
+    ///
+    pub fn period_of(&self, date: DateTime<Utc>) -> Option<usize> {
+        (0..self.periods)
+            .find(|period| date >= self.period_start(*period) && date < self.period_end(*period))
+    }
+
+    pub fn accounts(&self) -> impl Iterator<Item = &AccountId> {
+        self.amounts.keys()
+    }
+
+    pub fn amounts(&self, account: &AccountId) -> Option<&Vec<Decimal>> {
+        self.amounts.get(account)
+    }
+
+    pub fn amount(&self, account: &AccountId, period: usize) -> Decimal {
+        self.amounts
+            .get(account)
+            .and_then(|amounts| amounts.get(period))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    ///
+    /// The total budgeted for the account over the periods in `[from, until)`.
+    ///
+    pub fn total_between(&self, account: &AccountId, from: usize, until: usize) -> Decimal {
+        (from..until)
+            .map(|period| self.amount(account, period))
+            .sum()
+    }
+
+    pub fn total(&self, account: &AccountId) -> Decimal {
+        self.total_between(account, 0, self.periods)
+    }
+
+    pub fn set_amount(&mut self, account: AccountId, period: usize, amount: Decimal) -> Result<()> {
+        if period >= self.periods {
+            return Err(no_such_budget_period(self.name.clone(), period));
+        }
+        let periods = self.periods;
+        self.amounts
+            .entry(account)
+            .or_insert_with(|| vec![Decimal::ZERO; periods])[period] = amount;
+        Ok(())
+    }
+
+    ///
+    /// Set the account's budget by spreading an annual `total` across all periods.
+    ///
+    pub fn set_total(
+        &mut self,
+        account: AccountId,
+        total: Decimal,
+        distribution: &Distribution,
+    ) -> Result<()> {
+        let amounts = distribution.spread(total, self.periods, self.scale())?;
+        let _ = self.amounts.insert(account, amounts);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, account: &AccountId) -> Option<Vec<Decimal>> {
+        self.amounts.remove(account)
+    }
+
+    fn scale(&self) -> u32 {
+        CommodityId::Currency(self.currency)
+            .minor_units()
+            .unwrap_or(2)
+    }
+
+    fn uplift(&self, amount: Decimal, uplift_percent: Decimal) -> Decimal {
+        (amount * (Decimal::ONE_HUNDRED + uplift_percent) / Decimal::ONE_HUNDRED)
+            .round_dp_with_strategy(self.scale(), RoundingStrategy::MidpointAwayFromZero)
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
+
+#[doc(hidden)]
+mod report;
+pub use report::{BudgetReport, BudgetReportLine};
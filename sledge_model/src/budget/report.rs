@@ -0,0 +1,226 @@
+/*!
+Budget versus actual; the budget for a range of periods compared with the actual balances.
+
+*/
+
+use crate::budget::Budget;
+use crate::error::{no_such_budget_period, Result};
+use crate::journal::Journal;
+use crate::ledger::{Account, AccountId, Balances, Ledger};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// One account in the report; the budget and actual amounts include all of the account's
+/// descendants and are in the natural sign of its kind.
+///
+#[derive(Clone, Debug)]
+pub struct BudgetReportLine {
+    account: AccountId,
+    description: String,
+    depth: usize,
+    is_debit_normal: bool,
+    budget: Decimal,
+    actual: Decimal,
+}
+
+#[derive(Clone, Debug)]
+pub struct BudgetReport {
+    budget: String,
+    from: DateTime<Utc>,
+    until: DateTime<Utc>,
+    lines: Vec<BudgetReportLine>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl BudgetReportLine {
+    pub fn account(&self) -> &AccountId {
+        &self.account
+    }
+
+    pub fn description(&self) -> &String {
+        &self.description
+    }
+
+    ///
+    /// The account's depth in the hierarchy, zero for a top-level account.
+    ///
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn budget(&self) -> Decimal {
+        self.budget
+    }
+
+    pub fn actual(&self) -> Decimal {
+        self.actual
+    }
+
+    ///
+    /// Actual less budget.
+    ///
+    pub fn variance(&self) -> Decimal {
+        self.actual - self.budget
+    }
+
+    ///
+    /// The variance as a percentage of the budget, to two decimal places; `None` if nothing
+    /// was budgeted.
+    ///
+    pub fn variance_percent(&self) -> Option<Decimal> {
+        if self.budget.is_zero() {
+            None
+        } else {
+            Some((self.variance() * Decimal::ONE_HUNDRED / self.budget).round_dp(2))
+        }
+    }
+
+    ///
+    /// Returns `true` if spending is at or under budget, or income at or over it.
+    ///
+    pub fn is_favourable(&self) -> bool {
+        if self.is_debit_normal {
+            self.variance() <= Decimal::ZERO
+        } else {
+            self.variance() >= Decimal::ZERO
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl BudgetReport {
+    ///
+    /// Compare the budget for the periods in `[from, until)` with the actual balances posted
+    /// to `journals` over the same dates. Accounts are listed in hierarchy order, each parent
+    /// followed by its children, and any account with neither a budget nor an actual balance
+    /// in its sub-tree is omitted.
+    ///
+    pub fn prepare(
+        budget: &Budget,
+        ledger: &Ledger,
+        journals: &[Journal],
+        from: usize,
+        until: usize,
+    ) -> Result<Self> {
+        if from >= until || until > budget.periods() {
+            return Err(no_such_budget_period(
+                budget.name().clone(),
+                until.max(from),
+            ));
+        }
+        let start = budget.period_start(from);
+        let end = budget.period_start(until);
+        let balances = Balances::calculate(journals, Some(start), end);
+
+        let mut lines: Vec<BudgetReportLine> = Default::default();
+        for root in ledger.book().filter(|a| a.parent_id().is_none()) {
+            add_lines(budget, ledger, &balances, from, until, root, 0, &mut lines);
+        }
+
+        Ok(Self {
+            budget: budget.name().clone(),
+            from: start,
+            until: end,
+            lines,
+        })
+    }
+
+    pub fn budget(&self) -> &String {
+        &self.budget
+    }
+
+    pub fn from(&self) -> DateTime<Utc> {
+        self.from
+    }
+
+    pub fn until(&self) -> DateTime<Utc> {
+        self.until
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = &BudgetReportLine> {
+        self.lines.iter()
+    }
+
+    pub fn line(&self, account: &AccountId) -> Option<&BudgetReportLine> {
+        self.lines.iter().find(|line| &line.account == account)
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+#[allow(clippy::too_many_arguments)]
+fn add_lines(
+    budget: &Budget,
+    ledger: &Ledger,
+    balances: &Balances,
+    from: usize,
+    until: usize,
+    account: &Account,
+    depth: usize,
+    lines: &mut Vec<BudgetReportLine>,
+) {
+    if lines.iter().any(|line| &line.account == account.id()) {
+        return;
+    }
+    let budgeted = budget.total_between(account.id(), from, until)
+        + ledger
+            .descendants(account.id())
+            .iter()
+            .map(|descendant| budget.total_between(descendant.id(), from, until))
+            .sum::<Decimal>();
+    let has_actual = balances.get(account.id()).is_some()
+        || ledger
+            .descendants(account.id())
+            .iter()
+            .any(|descendant| balances.get(descendant.id()).is_some());
+    if budgeted.is_zero() && !has_actual {
+        return;
+    }
+    lines.push(BudgetReportLine {
+        account: account.id().clone(),
+        description: account.description().clone(),
+        depth,
+        is_debit_normal: account.kind().is_debit_normal(),
+        budget: budgeted,
+        actual: balances.natural_balance(ledger, account.id()),
+    });
+    for child in ledger.children(account.id()) {
+        add_lines(
+            budget,
+            ledger,
+            balances,
+            from,
+            until,
+            child,
+            depth + 1,
+            lines,
+        );
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
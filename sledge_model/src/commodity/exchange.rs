@@ -12,6 +12,7 @@ YYYYY
 use crate::commodity::{CommodityId, Quantity};
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
 
 // ------------------------------------------------------------------------------------------------
 // Public Macros
@@ -50,6 +51,70 @@ pub struct RatedQuantity {
 // Implementations
 // ------------------------------------------------------------------------------------------------
 
+impl RateRecord {
+    ///
+    /// One unit of `from` is worth `rate` units of `into` on `date`.
+    ///
+    pub fn new<S>(
+        from: CommodityId,
+        into: CommodityId,
+        rate: Decimal,
+        date: DateTime<Utc>,
+        source: S,
+    ) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            from,
+            into,
+            rate,
+            date,
+            source: source.into(),
+        }
+    }
+
+    pub fn from(&self) -> &CommodityId {
+        &self.from
+    }
+
+    pub fn into(&self) -> &CommodityId {
+        &self.into
+    }
+
+    pub fn rate(&self) -> Decimal {
+        self.rate
+    }
+
+    pub fn date(&self) -> DateTime<Utc> {
+        self.date
+    }
+
+    pub fn source(&self) -> &String {
+        &self.source
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl RatedQuantity {
+    ///
+    /// The original `quantity`, which was exchanged at `rate` units of the new commodity for
+    /// each unit of the original.
+    ///
+    pub fn new(quantity: Quantity, rate: Rate) -> Self {
+        Self { quantity, rate }
+    }
+
+    pub fn quantity(&self) -> &Quantity {
+        &self.quantity
+    }
+
+    pub fn rate(&self) -> Rate {
+        self.rate
+    }
+}
+
 // ------------------------------------------------------------------------------------------------
 // Private Functions
 // ------------------------------------------------------------------------------------------------
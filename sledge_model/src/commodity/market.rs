@@ -20,11 +20,13 @@ YYYYY
 // ------------------------------------------------------------------------------------------------
 
 use crate::party::CountryCode;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
 
 /// Market identifier codes (MIC)
 ///
 /// ISO-10383
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct MarketIdentifierCode(String);
 
 #[derive(Debug)]
@@ -48,6 +50,24 @@ pub struct Market {
 // Implementations
 // ------------------------------------------------------------------------------------------------
 
+impl Display for MarketIdentifierCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<&str> for MarketIdentifierCode {
+    fn from(v: &str) -> Self {
+        Self(v.to_string())
+    }
+}
+
+impl MarketIdentifierCode {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
 // ------------------------------------------------------------------------------------------------
 // Private Functions
 // ------------------------------------------------------------------------------------------------
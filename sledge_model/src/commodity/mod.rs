@@ -10,7 +10,7 @@ YYYYY
 */
 
 use codes_iso_4217::CurrencyCode;
-use rust_decimal::Decimal;
+use rust_decimal::{Decimal, RoundingStrategy};
 
 // ------------------------------------------------------------------------------------------------
 // Public Macros
@@ -44,7 +44,18 @@ pub struct Quantity {
 // Implementations
 // ------------------------------------------------------------------------------------------------
 
-impl CommodityId {}
+impl CommodityId {
+    ///
+    /// The number of decimal places amounts of this commodity are rounded to, if the commodity
+    /// is a currency with defined monetary units.
+    ///
+    pub fn minor_units(&self) -> Option<u32> {
+        match self {
+            Self::Currency(currency) => currency.monetary_units().map(u32::from),
+            Self::Security(_) => None,
+        }
+    }
+}
 
 impl From<CurrencyCode> for CommodityId {
     fn from(v: CurrencyCode) -> Self {
@@ -58,6 +69,54 @@ impl From<InternationalSecuritiesId> for CommodityId {
     }
 }
 
+// ------------------------------------------------------------------------------------------------
+
+impl Quantity {
+    pub fn new(commodity: CommodityId, quantity: Decimal) -> Self {
+        Self {
+            commodity,
+            quantity,
+        }
+    }
+
+    pub fn zero(commodity: CommodityId) -> Self {
+        Self::new(commodity, Decimal::ZERO)
+    }
+
+    pub fn commodity(&self) -> &CommodityId {
+        &self.commodity
+    }
+
+    pub fn quantity(&self) -> Decimal {
+        self.quantity
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.quantity.is_zero()
+    }
+
+    ///
+    /// Return a new quantity of the same commodity with the given value.
+    ///
+    pub fn with_quantity(&self, quantity: Decimal) -> Self {
+        Self::new(self.commodity.clone(), quantity)
+    }
+
+    ///
+    /// Round the quantity to the commodity's minor units, midpoint values are rounded away
+    /// from zero. Commodities without minor units are returned unchanged.
+    ///
+    pub fn rounded(&self) -> Self {
+        match self.commodity.minor_units() {
+            Some(dp) => self.with_quantity(
+                self.quantity
+                    .round_dp_with_strategy(dp, RoundingStrategy::MidpointAwayFromZero),
+            ),
+            None => self.clone(),
+        }
+    }
+}
+
 // ------------------------------------------------------------------------------------------------
 // Private Functions
 // ------------------------------------------------------------------------------------------------
@@ -11,6 +11,8 @@ YYYYY
 
 use crate::commodity::MarketIdentifierCode;
 use codes_iso_4217::CurrencyCode;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
 
 // ------------------------------------------------------------------------------------------------
 // Public Macros
@@ -57,16 +59,16 @@ use codes_iso_4217::CurrencyCode;
 ///     10))
 /// ```
 ///
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct InternationalSecuritiesId(String);
 
 ///
 /// National Securities Identifying Number (NSIN)
 ///
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct NationalSecuritiesId(String);
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Security {
     market: MarketIdentifierCode,
     symbol: Option<String>,
@@ -87,6 +89,159 @@ pub struct Security {
 // Implementations
 // ------------------------------------------------------------------------------------------------
 
+impl Display for InternationalSecuritiesId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<&str> for InternationalSecuritiesId {
+    fn from(v: &str) -> Self {
+        Self(v.to_string())
+    }
+}
+
+impl InternationalSecuritiesId {
+    pub fn new<S>(id: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self(id.into())
+    }
+
+    ///
+    /// The ISIN for a national number, such as a CUSIP in the `"US"`, with its check digit;
+    /// `None` if the country code or number are not valid.
+    ///
+    pub fn from_national<S: AsRef<str>>(country: &str, national: S) -> Option<Self> {
+        let national = national.as_ref();
+        if national.is_empty() || national.len() > 9 {
+            return None;
+        }
+        (0..10)
+            .map(|check| Self(format!("{}{:0>9}{}", country, national, check)))
+            .find(Self::is_valid)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    ///
+    /// Returns `true` if the identifier is a two-letter country code, nine alphanumeric
+    /// characters, and a correct check digit.
+    ///
+    pub fn is_valid(&self) -> bool {
+        let bytes = self.0.as_bytes();
+        if bytes.len() != 12
+            || !bytes[..2].iter().all(u8::is_ascii_uppercase)
+            || !bytes[2..11]
+                .iter()
+                .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit())
+            || !bytes[11].is_ascii_digit()
+        {
+            return false;
+        }
+        let digits: Vec<u32> = bytes
+            .iter()
+            .flat_map(|b| {
+                let value = if b.is_ascii_digit() {
+                    u32::from(b - b'0')
+                } else {
+                    u32::from(b - b'A') + 10
+                };
+                if value > 9 {
+                    vec![value / 10, value % 10]
+                } else {
+                    vec![value]
+                }
+            })
+            .collect();
+        let sum: u32 = digits
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(i, digit)| {
+                if i % 2 == 1 {
+                    let doubled = digit * 2;
+                    doubled / 10 + doubled % 10
+                } else {
+                    *digit
+                }
+            })
+            .sum();
+        sum.is_multiple_of(10)
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl Security {
+    pub fn new(market: MarketIdentifierCode) -> Self {
+        Self {
+            market,
+            symbol: None,
+            isin: None,
+            name: None,
+            base_currency: None,
+        }
+    }
+
+    pub fn with_symbol<S>(self, symbol: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            symbol: Some(symbol.into()),
+            ..self
+        }
+    }
+
+    pub fn with_isin(self, isin: InternationalSecuritiesId) -> Self {
+        Self {
+            isin: Some(isin),
+            ..self
+        }
+    }
+
+    pub fn with_name<S>(self, name: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            name: Some(name.into()),
+            ..self
+        }
+    }
+
+    pub fn with_base_currency(self, base_currency: CurrencyCode) -> Self {
+        Self {
+            base_currency: Some(base_currency),
+            ..self
+        }
+    }
+
+    pub fn market(&self) -> &MarketIdentifierCode {
+        &self.market
+    }
+
+    pub fn symbol(&self) -> Option<&String> {
+        self.symbol.as_ref()
+    }
+
+    pub fn isin(&self) -> Option<&InternationalSecuritiesId> {
+        self.isin.as_ref()
+    }
+
+    pub fn name(&self) -> Option<&String> {
+        self.name.as_ref()
+    }
+
+    pub fn base_currency(&self) -> Option<CurrencyCode> {
+        self.base_currency
+    }
+}
+
 // ------------------------------------------------------------------------------------------------
 // Private Functions
 // ------------------------------------------------------------------------------------------------
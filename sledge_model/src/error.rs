@@ -0,0 +1,211 @@
+/*!
+Provides the crate's Error and Result types as well as helper
+functions.
+
+ */
+
+use crate::commodity::CommodityId;
+use crate::journal::TransactionId;
+use crate::ledger::AccountId;
+use crate::party::PartyId;
+use crate::tax::TaxCodeId;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use std::fmt::{Debug, Display};
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// The Error type for this crate.
+///
+#[derive(Debug)]
+pub enum Error {
+    /// A tax code was referenced that is not present in the tax table.
+    UnknownTaxCode { id: TaxCodeId },
+    /// The tax code has no rate in effect on the requested date.
+    NoTaxRateInEffect { id: TaxCodeId, on: DateTime<Utc> },
+    /// Tax was calculated for a code that has no account to post it to.
+    MissingTaxAccount { id: TaxCodeId },
+    /// A transaction was posted into a period that is read-only.
+    PeriodLocked {
+        id: TransactionId,
+        posted: DateTime<Utc>,
+    },
+    /// The party identifier must identify a person.
+    NotAPerson { party: PartyId },
+    /// A calculation was asked to combine quantities of different commodities.
+    MixedCommodities {
+        expected: CommodityId,
+        found: CommodityId,
+    },
+    /// The budget does not have the requested period.
+    NoSuchBudgetPeriod { budget: String, period: usize },
+    /// A seasonal distribution must have one weight for each budget period.
+    InvalidDistribution { expected: usize, found: usize },
+    /// The account's balance differs from the balance on the statement being reconciled.
+    UnreconciledDifference {
+        account: AccountId,
+        difference: Decimal,
+    },
+    /// An account with a balance to report is not mapped to a taxonomy concept.
+    UnmappedAccount { account: AccountId },
+    /// An account is mapped to more than one taxonomy concept.
+    AmbiguousConcept {
+        account: AccountId,
+        concepts: Vec<String>,
+    },
+    /// There are current earnings to report but no taxonomy concept to report them with.
+    UnmappedEarnings,
+}
+
+///
+/// A Result type that specifically uses this crate's Error.
+///
+pub type Result<T> = std::result::Result<T, Error>;
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+/// Construct an Error from the provided tax code.
+#[inline]
+pub fn unknown_tax_code(id: TaxCodeId) -> Error {
+    Error::UnknownTaxCode { id }
+}
+
+/// Construct an Error from the provided tax code and date.
+#[inline]
+pub fn no_tax_rate_in_effect(id: TaxCodeId, on: DateTime<Utc>) -> Error {
+    Error::NoTaxRateInEffect { id, on }
+}
+
+/// Construct an Error from the provided tax code.
+#[inline]
+pub fn missing_tax_account(id: TaxCodeId) -> Error {
+    Error::MissingTaxAccount { id }
+}
+
+/// Construct an Error from the provided transaction and posted date.
+#[inline]
+pub fn period_locked(id: TransactionId, posted: DateTime<Utc>) -> Error {
+    Error::PeriodLocked { id, posted }
+}
+
+/// Construct an Error from the provided party.
+#[inline]
+pub fn not_a_person(party: PartyId) -> Error {
+    Error::NotAPerson { party }
+}
+
+/// Construct an Error from the expected and found commodities.
+#[inline]
+pub fn mixed_commodities(expected: CommodityId, found: CommodityId) -> Error {
+    Error::MixedCommodities { expected, found }
+}
+
+/// Construct an Error from the provided budget name and period index.
+#[inline]
+pub fn no_such_budget_period<S: Into<String>>(budget: S, period: usize) -> Error {
+    Error::NoSuchBudgetPeriod {
+        budget: budget.into(),
+        period,
+    }
+}
+
+/// Construct an Error from the expected and actual number of weights.
+#[inline]
+pub fn invalid_distribution(expected: usize, found: usize) -> Error {
+    Error::InvalidDistribution { expected, found }
+}
+
+/// Construct an Error from the provided account and the difference from its statement.
+#[inline]
+pub fn unreconciled_difference(account: AccountId, difference: Decimal) -> Error {
+    Error::UnreconciledDifference {
+        account,
+        difference,
+    }
+}
+
+/// Construct an Error from the provided account.
+#[inline]
+pub fn unmapped_account(account: AccountId) -> Error {
+    Error::UnmappedAccount { account }
+}
+
+/// Construct an Error from the provided account and the concepts it maps to.
+#[inline]
+pub fn ambiguous_concept(account: AccountId, concepts: Vec<String>) -> Error {
+    Error::AmbiguousConcept { account, concepts }
+}
+
+/// Construct an Error for unmapped current earnings.
+#[inline]
+pub fn unmapped_earnings() -> Error {
+    Error::UnmappedEarnings
+}
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Error::UnknownTaxCode { id } =>
+                    format!("The tax code {:?} is not in the tax table", id.as_str()),
+                Error::NoTaxRateInEffect { id, on } => format!(
+                    "The tax code {:?} has no rate in effect on {}",
+                    id.as_str(),
+                    on
+                ),
+                Error::MissingTaxAccount { id } => format!(
+                    "The tax code {:?} has no account to post the calculated tax to",
+                    id.as_str()
+                ),
+                Error::PeriodLocked { id, posted } => format!(
+                    "The transaction {:?} is posted on {}, in a period that is read-only",
+                    id.as_str(),
+                    posted
+                ),
+                Error::NotAPerson { party } =>
+                    format!("The party {} does not identify a person", party),
+                Error::MixedCommodities { expected, found } => format!(
+                    "Expected quantities of {:?}, found a quantity of {:?}",
+                    expected, found
+                ),
+                Error::NoSuchBudgetPeriod { budget, period } =>
+                    format!("The budget {:?} has no period {}", budget, period),
+                Error::InvalidDistribution { expected, found } => format!(
+                    "Expected a distribution with {} weights, found {}",
+                    expected, found
+                ),
+                Error::UnreconciledDifference {
+                    account,
+                    difference,
+                } => format!(
+                    "The balance of account {} differs from its statement by {}",
+                    account, difference
+                ),
+                Error::UnmappedAccount { account } => format!(
+                    "The account {} is not mapped to a taxonomy concept",
+                    account
+                ),
+                Error::AmbiguousConcept { account, concepts } => format!(
+                    "The account {} is mapped to more than one taxonomy concept: {}",
+                    account,
+                    concepts.join(", ")
+                ),
+                Error::UnmappedEarnings =>
+                    "Current earnings are not mapped to a taxonomy concept".to_string(),
+            }
+        )
+    }
+}
+
+impl std::error::Error for Error {}
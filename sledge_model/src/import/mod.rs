@@ -0,0 +1,282 @@
+/*!
+Saved profiles that describe how the statement files of a particular bank are laid out, so that
+they can be imported again each month without describing them again.
+
+A [`CsvProfile`] maps the columns of a bank's CSV export onto the parts of a statement entry:
+the date, in the bank's own format, the amount, as one signed column or separate debit and
+credit columns, and optionally the description, reference, counterparty, and the bank's own
+identifier for the entry. Columns are identified by position or, for files with a header row,
+by name. Profiles are kept in the data store, under their name.
+
+*/
+
+use chrono::{DateTime, Utc};
+use codes_iso_4217::CurrencyCode;
+use serde::{Deserialize, Serialize};
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TextEncoding {
+    #[default]
+    Utf8,
+    /// ISO 8859-1.
+    Latin1,
+    /// The Windows code page most often used by Western European banks, a superset of Latin-1.
+    Windows1252,
+}
+
+///
+/// A column in a CSV file; either its position, counting from zero, or its name in the header
+/// row.
+///
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CsvColumn {
+    Index(usize),
+    Header(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CsvAmount {
+    /// One column, positive for money in to the account and negative for money out.
+    Signed(CsvColumn),
+    /// Separate columns for money out, the debits, and money in, the credits, of the account
+    /// holder; either may be empty, and both are read without their sign.
+    DebitCredit { debit: CsvColumn, credit: CsvColumn },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CsvProfile {
+    name: String,
+    description: String,
+    created: DateTime<Utc>,
+    currency: CurrencyCode,
+    delimiter: char,
+    encoding: TextEncoding,
+    skip_rows: usize,
+    has_header: bool,
+    date: CsvColumn,
+    date_format: String,
+    amount: CsvAmount,
+    decimal_separator: char,
+    entry_description: Option<CsvColumn>,
+    reference: Option<CsvColumn>,
+    counterparty: Option<CsvColumn>,
+    entry_id: Option<CsvColumn>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl From<usize> for CsvColumn {
+    fn from(v: usize) -> Self {
+        Self::Index(v)
+    }
+}
+
+impl From<&str> for CsvColumn {
+    fn from(v: &str) -> Self {
+        Self::Header(v.to_string())
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl CsvProfile {
+    ///
+    /// A profile for comma separated UTF-8 files with a header row and ISO 8601 dates, such as
+    /// `2022-10-31`; each can be changed with the builder functions.
+    ///
+    pub fn new<S>(name: S, currency: CurrencyCode, date: CsvColumn, amount: CsvAmount) -> Self
+    where
+        S: Into<String>,
+    {
+        let name = name.into();
+        Self {
+            description: name.clone(),
+            name,
+            created: Utc::now(),
+            currency,
+            delimiter: ',',
+            encoding: Default::default(),
+            skip_rows: 0,
+            has_header: true,
+            date,
+            date_format: "%Y-%m-%d".to_string(),
+            amount,
+            decimal_separator: '.',
+            entry_description: None,
+            reference: None,
+            counterparty: None,
+            entry_id: None,
+        }
+    }
+
+    pub fn with_description<S: Into<String>>(self, description: S) -> Self {
+        Self {
+            description: description.into(),
+            ..self
+        }
+    }
+
+    pub fn with_delimiter(self, delimiter: char) -> Self {
+        Self { delimiter, ..self }
+    }
+
+    pub fn with_encoding(self, encoding: TextEncoding) -> Self {
+        Self { encoding, ..self }
+    }
+
+    ///
+    /// Skip `skip_rows` lines, such as an account summary, before the header row or, if there
+    /// is none, the first entry.
+    ///
+    pub fn with_skip_rows(self, skip_rows: usize) -> Self {
+        Self { skip_rows, ..self }
+    }
+
+    pub fn with_header(self, has_header: bool) -> Self {
+        Self { has_header, ..self }
+    }
+
+    ///
+    /// The format of dates, as used by [`chrono::format::strftime`], such as `"%d/%m/%Y"`.
+    ///
+    pub fn with_date_format<S: Into<String>>(self, date_format: S) -> Self {
+        Self {
+            date_format: date_format.into(),
+            ..self
+        }
+    }
+
+    ///
+    /// The decimal separator in amounts, `'.'` or `','`; the other is taken to separate
+    /// thousands.
+    ///
+    pub fn with_decimal_separator(self, decimal_separator: char) -> Self {
+        Self {
+            decimal_separator,
+            ..self
+        }
+    }
+
+    pub fn with_entry_description(self, column: CsvColumn) -> Self {
+        Self {
+            entry_description: Some(column),
+            ..self
+        }
+    }
+
+    pub fn with_reference(self, column: CsvColumn) -> Self {
+        Self {
+            reference: Some(column),
+            ..self
+        }
+    }
+
+    pub fn with_counterparty(self, column: CsvColumn) -> Self {
+        Self {
+            counterparty: Some(column),
+            ..self
+        }
+    }
+
+    ///
+    /// The column holding the bank's own identifier for each entry, used to recognize entries
+    /// already imported; without one, entries are recognized by their date, amount, and
+    /// description.
+    ///
+    pub fn with_entry_id(self, column: CsvColumn) -> Self {
+        Self {
+            entry_id: Some(column),
+            ..self
+        }
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn description(&self) -> &String {
+        &self.description
+    }
+
+    pub fn created(&self) -> DateTime<Utc> {
+        self.created
+    }
+
+    pub fn currency(&self) -> CurrencyCode {
+        self.currency
+    }
+
+    pub fn delimiter(&self) -> char {
+        self.delimiter
+    }
+
+    pub fn encoding(&self) -> TextEncoding {
+        self.encoding
+    }
+
+    pub fn skip_rows(&self) -> usize {
+        self.skip_rows
+    }
+
+    pub fn has_header(&self) -> bool {
+        self.has_header
+    }
+
+    pub fn date(&self) -> &CsvColumn {
+        &self.date
+    }
+
+    pub fn date_format(&self) -> &String {
+        &self.date_format
+    }
+
+    pub fn amount(&self) -> &CsvAmount {
+        &self.amount
+    }
+
+    pub fn decimal_separator(&self) -> char {
+        self.decimal_separator
+    }
+
+    pub fn entry_description(&self) -> Option<&CsvColumn> {
+        self.entry_description.as_ref()
+    }
+
+    pub fn reference(&self) -> Option<&CsvColumn> {
+        self.reference.as_ref()
+    }
+
+    pub fn counterparty(&self) -> Option<&CsvColumn> {
+        self.counterparty.as_ref()
+    }
+
+    pub fn entry_id(&self) -> Option<&CsvColumn> {
+        self.entry_id.as_ref()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
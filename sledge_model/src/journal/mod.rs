@@ -10,8 +10,10 @@ YYYYY
 */
 
 use crate::audit::Signature;
+use crate::error::{period_locked, Result};
 use chrono::{DateTime, Duration, Utc};
 use codes_iso_4217::CurrencyCode;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 // ------------------------------------------------------------------------------------------------
 // Public Macros
@@ -21,11 +23,22 @@ use codes_iso_4217::CurrencyCode;
 // Public Types
 // ------------------------------------------------------------------------------------------------
 
-#[derive(Debug)]
+///
+/// A journal records transactions in the order they are posted. Transactions become read-only
+/// either once `read_only_after` has passed since they were posted, or when they are posted
+/// before the date the journal has been locked until, for example once a tax return or the
+/// year-end accounts for that period are filed.
+///
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Journal {
     name: String,
     created: DateTime<Utc>,
+    #[serde(
+        serialize_with = "serialize_seconds",
+        deserialize_with = "deserialize_seconds"
+    )]
     read_only_after: Option<Duration>,
+    locked_until: Option<DateTime<Utc>>,
     transactions: Vec<Transaction>,
     currency: CurrencyCode,
     signature: Option<Signature>,
@@ -43,10 +56,164 @@ pub struct Journal {
 // Implementations
 // ------------------------------------------------------------------------------------------------
 
+impl Journal {
+    pub fn new<S>(name: S, currency: CurrencyCode) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            name: name.into(),
+            created: Utc::now(),
+            read_only_after: None,
+            locked_until: None,
+            transactions: Default::default(),
+            currency,
+            signature: None,
+        }
+    }
+
+    pub fn with_created(self, created: DateTime<Utc>) -> Self {
+        Self { created, ..self }
+    }
+
+    pub fn with_read_only_after(self, read_only_after: Duration) -> Self {
+        Self {
+            read_only_after: Some(read_only_after),
+            ..self
+        }
+    }
+
+    pub fn with_signature(self, signature: Signature) -> Self {
+        Self {
+            signature: Some(signature),
+            ..self
+        }
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn created(&self) -> DateTime<Utc> {
+        self.created
+    }
+
+    pub fn read_only_after(&self) -> Option<Duration> {
+        self.read_only_after
+    }
+
+    pub fn locked_until(&self) -> Option<DateTime<Utc>> {
+        self.locked_until
+    }
+
+    pub fn currency(&self) -> CurrencyCode {
+        self.currency
+    }
+
+    pub fn signature(&self) -> Option<&Signature> {
+        self.signature.as_ref()
+    }
+
+    pub fn transactions(&self) -> impl Iterator<Item = &Transaction> {
+        self.transactions.iter()
+    }
+
+    ///
+    /// Transactions posted in the half-open range `[from, until)`.
+    ///
+    pub fn transactions_between(
+        &self,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> impl Iterator<Item = &Transaction> {
+        self.transactions
+            .iter()
+            .filter(move |t| t.posted() >= from && t.posted() < until)
+    }
+
+    ///
+    /// Lock every transaction posted before `until`; a lock can only be moved forward, an
+    /// earlier date than the current lock is ignored.
+    ///
+    pub fn lock_until(&mut self, until: DateTime<Utc>) {
+        if self
+            .locked_until
+            .map(|current| until > current)
+            .unwrap_or(true)
+        {
+            self.locked_until = Some(until);
+        }
+    }
+
+    ///
+    /// Returns `true` if a transaction posted at `posted` can no longer be added or changed
+    /// at the time `as_of`.
+    ///
+    pub fn is_read_only(&self, posted: DateTime<Utc>, as_of: DateTime<Utc>) -> bool {
+        self.locked_until
+            .map(|until| posted < until)
+            .unwrap_or_default()
+            || self
+                .read_only_after
+                .map(|after| posted + after <= as_of)
+                .unwrap_or_default()
+    }
+
+    ///
+    /// Add a transaction, keeping transactions in posted order. The transaction is rejected if
+    /// its period is already read-only now.
+    ///
+    pub fn add_transaction(&mut self, transaction: Transaction) -> Result<()> {
+        self.add_transaction_as_of(transaction, Utc::now())
+    }
+
+    ///
+    /// Add a transaction as [`Journal::add_transaction`] does, deciding whether its period is
+    /// read-only at the time `as_of`. The transaction's own entered date is not trusted for
+    /// this, a backdated entry must not reopen a closed period.
+    ///
+    pub fn add_transaction_as_of(
+        &mut self,
+        transaction: Transaction,
+        as_of: DateTime<Utc>,
+    ) -> Result<()> {
+        if self.is_read_only(transaction.posted(), as_of) {
+            return Err(period_locked(
+                transaction.id().clone(),
+                transaction.posted(),
+            ));
+        }
+        let index = self
+            .transactions
+            .partition_point(|t| t.posted() <= transaction.posted());
+        self.transactions.insert(index, transaction);
+        Ok(())
+    }
+}
+
 // ------------------------------------------------------------------------------------------------
 // Private Functions
 // ------------------------------------------------------------------------------------------------
 
+fn serialize_seconds<S>(
+    duration: &Option<Duration>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    duration
+        .map(|duration| duration.num_seconds())
+        .serialize(serializer)
+}
+
+fn deserialize_seconds<'de, D>(deserializer: D) -> std::result::Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(Option::<i64>::deserialize(deserializer)?.map(Duration::seconds))
+}
+
 // ------------------------------------------------------------------------------------------------
 // Modules
 // ------------------------------------------------------------------------------------------------
@@ -61,4 +228,4 @@ pub use transaction::{Action, ActionId, Transaction, TransactionId};
 
 #[doc(hidden)]
 mod reconcile;
-pub use reconcile::Reconciled;
+pub use reconcile::{Reconciled, Reconciliation};
@@ -9,8 +9,14 @@ YYYYY
 
 */
 
-use crate::journal::transaction::TransactionId;
+use crate::commodity::CommodityId;
+use crate::error::{period_locked, unreconciled_difference, Result};
+use crate::journal::transaction::{Transaction, TransactionId};
+use crate::journal::Journal;
+use crate::ledger::AccountId;
 use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
 
 // ------------------------------------------------------------------------------------------------
 // Public Macros
@@ -20,13 +26,34 @@ use chrono::{DateTime, Utc};
 // Public Types
 // ------------------------------------------------------------------------------------------------
 
-#[derive(Debug)]
+///
+/// Records that a split, identified by its transaction and account, was reconciled against a
+/// statement.
+///
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Reconciled {
     split_id: TransactionId,
+    account: AccountId,
     reference: String,
     reconciled: DateTime<Utc>,
 }
 
+///
+/// A statement being reconciled against an account. Transactions imported from the statement
+/// wait here as drafts until they are reviewed and posted to a journal, and the statement's
+/// closing balance is compared with the account's balance, in the statement's commodity,
+/// from the splits posted before the statement's end.
+///
+#[derive(Debug)]
+pub struct Reconciliation {
+    account: AccountId,
+    commodity: CommodityId,
+    until: DateTime<Utc>,
+    opening_balance: Option<Decimal>,
+    statement_balance: Option<Decimal>,
+    drafts: Vec<Transaction>,
+}
+
 // ------------------------------------------------------------------------------------------------
 // Public Functions
 // ------------------------------------------------------------------------------------------------
@@ -39,6 +66,200 @@ pub struct Reconciled {
 // Implementations
 // ------------------------------------------------------------------------------------------------
 
+impl Reconciled {
+    pub fn new<S>(
+        split_id: TransactionId,
+        account: AccountId,
+        reference: S,
+        reconciled: DateTime<Utc>,
+    ) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            split_id,
+            account,
+            reference: reference.into(),
+            reconciled,
+        }
+    }
+
+    pub fn split_id(&self) -> &TransactionId {
+        &self.split_id
+    }
+
+    pub fn account(&self) -> &AccountId {
+        &self.account
+    }
+
+    pub fn reference(&self) -> &String {
+        &self.reference
+    }
+
+    pub fn reconciled(&self) -> DateTime<Utc> {
+        self.reconciled
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl Reconciliation {
+    pub fn new(account: AccountId, commodity: CommodityId, until: DateTime<Utc>) -> Self {
+        Self {
+            account,
+            commodity,
+            until,
+            opening_balance: None,
+            statement_balance: None,
+            drafts: Default::default(),
+        }
+    }
+
+    pub fn with_opening_balance(self, opening_balance: Decimal) -> Self {
+        Self {
+            opening_balance: Some(opening_balance),
+            ..self
+        }
+    }
+
+    pub fn with_statement_balance(self, statement_balance: Decimal) -> Self {
+        Self {
+            statement_balance: Some(statement_balance),
+            ..self
+        }
+    }
+
+    pub fn account(&self) -> &AccountId {
+        &self.account
+    }
+
+    pub fn commodity(&self) -> &CommodityId {
+        &self.commodity
+    }
+
+    pub fn until(&self) -> DateTime<Utc> {
+        self.until
+    }
+
+    pub fn opening_balance(&self) -> Option<Decimal> {
+        self.opening_balance
+    }
+
+    pub fn statement_balance(&self) -> Option<Decimal> {
+        self.statement_balance
+    }
+
+    pub fn drafts(&self) -> impl Iterator<Item = &Transaction> {
+        self.drafts.iter()
+    }
+
+    ///
+    /// The drafts, to be completed before they are posted; for example by categorizing them.
+    ///
+    pub fn drafts_mut(&mut self) -> impl Iterator<Item = &mut Transaction> {
+        self.drafts.iter_mut()
+    }
+
+    pub fn add_draft(&mut self, transaction: Transaction) {
+        self.drafts.push(transaction);
+    }
+
+    ///
+    /// Remove a draft, for example one that duplicates a transaction already entered by hand.
+    ///
+    pub fn discard_draft(&mut self, id: &TransactionId) -> Option<Transaction> {
+        let index = self.drafts.iter().position(|t| t.id() == id)?;
+        Some(self.drafts.remove(index))
+    }
+
+    ///
+    /// Post every draft to `journal`. If a draft falls in a read-only period it, and the drafts
+    /// after it, are kept as drafts.
+    ///
+    pub fn post_drafts(&mut self, journal: &mut Journal) -> Result<()> {
+        let now = Utc::now();
+        let mut drafts = std::mem::take(&mut self.drafts).into_iter();
+        while let Some(draft) = drafts.next() {
+            if journal.is_read_only(draft.posted(), now) {
+                self.drafts = std::iter::once(draft).chain(drafts).collect();
+                return Err(period_locked(
+                    self.drafts[0].id().clone(),
+                    self.drafts[0].posted(),
+                ));
+            }
+            journal.add_transaction_as_of(draft, now)?;
+        }
+        Ok(())
+    }
+
+    ///
+    /// The account's balance in the statement's commodity, from the splits posted before the
+    /// statement's end.
+    ///
+    pub fn account_balance(&self, journals: &[Journal]) -> Decimal {
+        journals
+            .iter()
+            .flat_map(|journal| journal.transactions())
+            .filter(|transaction| transaction.posted() < self.until)
+            .flat_map(|transaction| transaction.splits())
+            .filter(|split| {
+                split.account() == &self.account && split.quantity().commodity() == &self.commodity
+            })
+            .map(|split| split.quantity().quantity())
+            .sum()
+    }
+
+    ///
+    /// The statement balance less the account's balance, if the statement has a balance.
+    ///
+    pub fn difference(&self, journals: &[Journal]) -> Option<Decimal> {
+        self.statement_balance
+            .map(|balance| balance - self.account_balance(journals))
+    }
+
+    ///
+    /// Once the account agrees with the statement, record every split of the account posted
+    /// before the statement's end, and not in `already`, as reconciled with `reference`.
+    ///
+    pub fn reconcile<S>(
+        &self,
+        journals: &[Journal],
+        reference: S,
+        already: &[Reconciled],
+    ) -> Result<Vec<Reconciled>>
+    where
+        S: Into<String>,
+    {
+        if let Some(difference) = self.difference(journals).filter(|d| !d.is_zero()) {
+            return Err(unreconciled_difference(self.account.clone(), difference));
+        }
+        let reference = reference.into();
+        Ok(journals
+            .iter()
+            .flat_map(|journal| journal.transactions())
+            .filter(|transaction| transaction.posted() < self.until)
+            .filter(|transaction| {
+                transaction
+                    .splits()
+                    .any(|split| split.account() == &self.account)
+            })
+            .filter(|transaction| {
+                !already
+                    .iter()
+                    .any(|r| r.split_id() == transaction.id() && r.account() == &self.account)
+            })
+            .map(|transaction| {
+                Reconciled::new(
+                    transaction.id().clone(),
+                    self.account.clone(),
+                    reference.clone(),
+                    self.until,
+                )
+            })
+            .collect())
+    }
+}
+
 // ------------------------------------------------------------------------------------------------
 // Private Functions
 // ------------------------------------------------------------------------------------------------
@@ -12,6 +12,8 @@ YYYYY
 use crate::commodity::{Quantity, RatedQuantity};
 use crate::journal::transaction::TransactionId;
 use crate::ledger::AccountId;
+use crate::tax::TaxCodeId;
+use serde::{Deserialize, Serialize};
 
 // ------------------------------------------------------------------------------------------------
 // Public Macros
@@ -21,13 +23,18 @@ use crate::ledger::AccountId;
 // Public Types
 // ------------------------------------------------------------------------------------------------
 
-#[derive(Debug)]
+///
+/// A single leg of a transaction, moving a quantity into, or out of, an account. By convention
+/// a positive quantity is a debit to the account and a negative quantity is a credit.
+///
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Split {
     id: TransactionId,
     account: AccountId,
     quantity: Quantity,
     exchanged_from: Option<RatedQuantity>,
     description: Option<String>,
+    tax_code: Option<TaxCodeId>,
 }
 
 // ------------------------------------------------------------------------------------------------
@@ -42,6 +49,79 @@ pub struct Split {
 // Implementations
 // ------------------------------------------------------------------------------------------------
 
+impl Split {
+    pub fn new(id: TransactionId, account: AccountId, quantity: Quantity) -> Self {
+        Self {
+            id,
+            account,
+            quantity,
+            exchanged_from: None,
+            description: None,
+            tax_code: None,
+        }
+    }
+
+    pub fn with_exchanged_from(self, exchanged_from: RatedQuantity) -> Self {
+        Self {
+            exchanged_from: Some(exchanged_from),
+            ..self
+        }
+    }
+
+    pub fn with_description<S>(self, description: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            description: Some(description.into()),
+            ..self
+        }
+    }
+
+    pub fn with_tax_code(self, tax_code: TaxCodeId) -> Self {
+        Self {
+            tax_code: Some(tax_code),
+            ..self
+        }
+    }
+
+    pub fn id(&self) -> &TransactionId {
+        &self.id
+    }
+
+    pub fn account(&self) -> &AccountId {
+        &self.account
+    }
+
+    pub fn quantity(&self) -> &Quantity {
+        &self.quantity
+    }
+
+    pub fn exchanged_from(&self) -> Option<&RatedQuantity> {
+        self.exchanged_from.as_ref()
+    }
+
+    pub fn description(&self) -> Option<&String> {
+        self.description.as_ref()
+    }
+
+    ///
+    /// The tax code this split was posted under, either as the taxable amount or as the tax
+    /// itself.
+    ///
+    pub fn tax_code(&self) -> Option<&TaxCodeId> {
+        self.tax_code.as_ref()
+    }
+
+    pub fn is_debit(&self) -> bool {
+        self.quantity.quantity().is_sign_positive() && !self.quantity.is_zero()
+    }
+
+    pub fn is_credit(&self) -> bool {
+        self.quantity.quantity().is_sign_negative() && !self.quantity.is_zero()
+    }
+}
+
 // ------------------------------------------------------------------------------------------------
 // Private Functions
 // ------------------------------------------------------------------------------------------------
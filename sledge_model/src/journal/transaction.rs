@@ -10,8 +10,14 @@ YYYYY
 */
 
 use crate::audit::Signature;
+use crate::commodity::CommodityId;
 use crate::journal::split::Split;
+use crate::ledger::AccountId;
 use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Display;
 
 // ------------------------------------------------------------------------------------------------
 // Public Macros
@@ -21,20 +27,20 @@ use chrono::{DateTime, Utc};
 // Public Types
 // ------------------------------------------------------------------------------------------------
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct ActionId(String);
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Action {
     id: ActionId,
     name: String,
     requires_reference: bool,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct TransactionId(String);
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Transaction {
     id: TransactionId,
     posted: DateTime<Utc>,
@@ -43,6 +49,7 @@ pub struct Transaction {
     reference: Option<String>,
     description: Option<String>,
     splits: Vec<Split>,
+    metadata: BTreeMap<String, String>,
     signature: Option<Signature>,
 }
 
@@ -58,6 +65,245 @@ pub struct Transaction {
 // Implementations
 // ------------------------------------------------------------------------------------------------
 
+impl Display for TransactionId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<&str> for TransactionId {
+    fn from(v: &str) -> Self {
+        Self(v.to_string())
+    }
+}
+
+impl TransactionId {
+    pub fn new<S>(id: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self(id.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl Display for ActionId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<&str> for ActionId {
+    fn from(v: &str) -> Self {
+        Self(v.to_string())
+    }
+}
+
+impl ActionId {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Action {
+    pub fn new<S>(id: ActionId, name: S, requires_reference: bool) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            id,
+            name: name.into(),
+            requires_reference,
+        }
+    }
+
+    pub fn id(&self) -> &ActionId {
+        &self.id
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn requires_reference(&self) -> bool {
+        self.requires_reference
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl Transaction {
+    pub fn new(id: TransactionId, posted: DateTime<Utc>) -> Self {
+        Self {
+            id,
+            posted,
+            entered: Utc::now(),
+            action: None,
+            reference: None,
+            description: None,
+            splits: Default::default(),
+            metadata: Default::default(),
+            signature: None,
+        }
+    }
+
+    pub fn with_entered(self, entered: DateTime<Utc>) -> Self {
+        Self { entered, ..self }
+    }
+
+    pub fn with_action(self, action: Action) -> Self {
+        Self {
+            action: Some(action),
+            ..self
+        }
+    }
+
+    pub fn with_reference<S>(self, reference: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            reference: Some(reference.into()),
+            ..self
+        }
+    }
+
+    pub fn with_description<S>(self, description: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            description: Some(description.into()),
+            ..self
+        }
+    }
+
+    pub fn with_split(mut self, split: Split) -> Self {
+        self.add_split(split);
+        self
+    }
+
+    pub fn with_splits<I>(mut self, splits: I) -> Self
+    where
+        I: IntoIterator<Item = Split>,
+    {
+        self.splits.extend(splits);
+        self
+    }
+
+    ///
+    /// Add a metadata value, such as a tag or link kept from an imported file, replacing any
+    /// existing value for `key`.
+    ///
+    pub fn with_metadata<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let _ = self.metadata.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn with_signature(self, signature: Signature) -> Self {
+        Self {
+            signature: Some(signature),
+            ..self
+        }
+    }
+
+    pub fn id(&self) -> &TransactionId {
+        &self.id
+    }
+
+    pub fn posted(&self) -> DateTime<Utc> {
+        self.posted
+    }
+
+    pub fn entered(&self) -> DateTime<Utc> {
+        self.entered
+    }
+
+    pub fn action(&self) -> Option<&Action> {
+        self.action.as_ref()
+    }
+
+    pub fn reference(&self) -> Option<&String> {
+        self.reference.as_ref()
+    }
+
+    pub fn description(&self) -> Option<&String> {
+        self.description.as_ref()
+    }
+
+    pub fn splits(&self) -> impl Iterator<Item = &Split> {
+        self.splits.iter()
+    }
+
+    pub fn metadata(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.metadata.iter()
+    }
+
+    pub fn metadata_value(&self, key: &str) -> Option<&String> {
+        self.metadata.get(key)
+    }
+
+    pub fn signature(&self) -> Option<&Signature> {
+        self.signature.as_ref()
+    }
+
+    pub fn add_split(&mut self, split: Split) {
+        self.splits.push(split);
+    }
+
+    ///
+    /// Remove, and return, the splits posted to `account`, for example to post them to other
+    /// accounts instead.
+    ///
+    pub fn remove_splits_for(&mut self, account: &AccountId) -> Vec<Split> {
+        let (removed, kept) = std::mem::take(&mut self.splits)
+            .into_iter()
+            .partition(|split| split.account() == account);
+        self.splits = kept;
+        removed
+    }
+
+    pub fn set_action(&mut self, action: Action) {
+        self.action = Some(action);
+    }
+
+    ///
+    /// Add a metadata value, replacing any existing value for `key`.
+    ///
+    pub fn add_metadata<K, V>(&mut self, key: K, value: V)
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let _ = self.metadata.insert(key.into(), value.into());
+    }
+
+    ///
+    /// A transaction is balanced when, for each commodity, the debits and credits of its
+    /// splits sum to zero.
+    ///
+    pub fn is_balanced(&self) -> bool {
+        let mut totals: Vec<(&CommodityId, Decimal)> = Default::default();
+        for split in &self.splits {
+            let quantity = split.quantity();
+            match totals.iter_mut().find(|(c, _)| *c == quantity.commodity()) {
+                Some((_, total)) => *total += quantity.quantity(),
+                None => totals.push((quantity.commodity(), quantity.quantity())),
+            }
+        }
+        totals.iter().all(|(_, total)| total.is_zero())
+    }
+}
+
 // ------------------------------------------------------------------------------------------------
 // Private Functions
 // ------------------------------------------------------------------------------------------------
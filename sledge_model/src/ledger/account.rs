@@ -15,6 +15,8 @@ use crate::{
 };
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
 
 // ------------------------------------------------------------------------------------------------
 // Public Macros
@@ -24,10 +26,10 @@ use rust_decimal::Decimal;
 // Public Types
 // ------------------------------------------------------------------------------------------------
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct AccountId(String);
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum AccountKind {
     Asset,
     Liability,
@@ -36,19 +38,45 @@ pub enum AccountKind {
     Expense, // Operating Expenses
 }
 
-#[derive(Debug)]
+///
+/// An account in a ledger's book. Accounts form a hierarchy through their parent, and only
+/// recording accounts have transactions posted to them; the others only total their children.
+///
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Account {
     id: AccountId,
     created: DateTime<Utc>,
     is_active: bool,
-    parent_id: Option<String>,
+    parent_id: Option<AccountId>,
     kind: AccountKind,
     description: String,
     commodity: CommodityId,
     is_recording: bool,
+    represents: Option<AccountRepresents>,
 }
 
+///
+/// The real-world thing an account represents, with the details needed to identify it.
+///
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum AccountRepresents {
+    BankAccount(BankAccount),
+    BrokerageAccount(BrokerageAccount),
+    CreditCard(CreditCard),
+    Customer(Customer),
+    Equipment(Equipment),
+    Loan(Loan),
+    Supplier(Supplier),
+    Salary(Salary),
+    Tax(Tax),
+    UtilityService(UtilityService),
+}
+
+///
+/// The kind of thing an account represents, without its details.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AccountRepresentsKind {
     BankAccount,
     BrokerageAccount,
     CreditCard,
@@ -61,20 +89,20 @@ pub enum AccountRepresents {
     UtilityService,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct BankAccount {
     institution: PartyId,
     account_number: String,
     interest_apr: Option<Decimal>,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct BrokerageAccount {
     institution: PartyId,
     account_number: String,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CreditCard {
     institution: PartyId,
     account_number: String,
@@ -84,15 +112,15 @@ pub struct CreditCard {
     annual_fee: Quantity,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Customer {
     party: PartyId,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Equipment {}
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Loan {
     institution: PartyId,
     account_number: String,
@@ -101,18 +129,24 @@ pub struct Loan {
     //    against:
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Supplier {
     party: PartyId,
 }
 
-#[derive(Debug)]
-pub struct Salary {}
+///
+/// A salary expense account, either shared by all employees or, when `employee` is set,
+/// used only for that employee's pay.
+///
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Salary {
+    employee: Option<PartyId>,
+}
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Tax {}
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct UtilityService {
     provider: PartyId,
     account_number: String,
@@ -130,6 +164,361 @@ pub struct UtilityService {
 // Implementations
 // ------------------------------------------------------------------------------------------------
 
+impl Display for AccountId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<&str> for AccountId {
+    fn from(v: &str) -> Self {
+        Self(v.to_string())
+    }
+}
+
+impl AccountId {
+    pub fn new<S>(id: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self(id.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl AccountKind {
+    ///
+    /// Returns `true` for the kinds whose balance normally increases with a debit.
+    ///
+    pub fn is_debit_normal(&self) -> bool {
+        matches!(self, Self::Asset | Self::Expense)
+    }
+
+    ///
+    /// Returns `true` for the kinds reported on the balance sheet, rather than the income
+    /// statement.
+    ///
+    pub fn is_balance_sheet(&self) -> bool {
+        matches!(self, Self::Asset | Self::Liability | Self::Equity)
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl Account {
+    pub fn new<S>(id: AccountId, kind: AccountKind, description: S, commodity: CommodityId) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            id,
+            created: Utc::now(),
+            is_active: true,
+            parent_id: None,
+            kind,
+            description: description.into(),
+            commodity,
+            is_recording: true,
+            represents: None,
+        }
+    }
+
+    pub fn with_created(self, created: DateTime<Utc>) -> Self {
+        Self { created, ..self }
+    }
+
+    pub fn with_parent(self, parent_id: AccountId) -> Self {
+        Self {
+            parent_id: Some(parent_id),
+            ..self
+        }
+    }
+
+    pub fn with_represents(self, represents: AccountRepresents) -> Self {
+        Self {
+            represents: Some(represents),
+            ..self
+        }
+    }
+
+    ///
+    /// Mark the account as a placeholder that only totals its children.
+    ///
+    pub fn non_recording(self) -> Self {
+        Self {
+            is_recording: false,
+            ..self
+        }
+    }
+
+    pub fn id(&self) -> &AccountId {
+        &self.id
+    }
+
+    pub fn created(&self) -> DateTime<Utc> {
+        self.created
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.is_active
+    }
+
+    pub fn set_active(&mut self, is_active: bool) {
+        self.is_active = is_active;
+    }
+
+    pub fn parent_id(&self) -> Option<&AccountId> {
+        self.parent_id.as_ref()
+    }
+
+    pub fn kind(&self) -> AccountKind {
+        self.kind
+    }
+
+    pub fn description(&self) -> &String {
+        &self.description
+    }
+
+    pub fn commodity(&self) -> &CommodityId {
+        &self.commodity
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.is_recording
+    }
+
+    pub fn represents(&self) -> Option<&AccountRepresents> {
+        self.represents.as_ref()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl AccountRepresents {
+    pub fn kind(&self) -> AccountRepresentsKind {
+        match self {
+            Self::BankAccount(_) => AccountRepresentsKind::BankAccount,
+            Self::BrokerageAccount(_) => AccountRepresentsKind::BrokerageAccount,
+            Self::CreditCard(_) => AccountRepresentsKind::CreditCard,
+            Self::Customer(_) => AccountRepresentsKind::Customer,
+            Self::Equipment(_) => AccountRepresentsKind::Equipment,
+            Self::Loan(_) => AccountRepresentsKind::Loan,
+            Self::Supplier(_) => AccountRepresentsKind::Supplier,
+            Self::Salary(_) => AccountRepresentsKind::Salary,
+            Self::Tax(_) => AccountRepresentsKind::Tax,
+            Self::UtilityService(_) => AccountRepresentsKind::UtilityService,
+        }
+    }
+
+    ///
+    /// The number identifying the account at its institution or provider, if it has one.
+    ///
+    pub fn account_number(&self) -> Option<&String> {
+        match self {
+            Self::BankAccount(v) => Some(v.account_number()),
+            Self::BrokerageAccount(v) => Some(v.account_number()),
+            Self::CreditCard(v) => Some(v.account_number()),
+            Self::Loan(v) => Some(v.account_number()),
+            Self::UtilityService(v) => Some(&v.account_number),
+            _ => None,
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl BankAccount {
+    pub fn new<S: Into<String>>(institution: PartyId, account_number: S) -> Self {
+        Self {
+            institution,
+            account_number: account_number.into(),
+            interest_apr: None,
+        }
+    }
+
+    pub fn with_interest_apr(self, interest_apr: Decimal) -> Self {
+        Self {
+            interest_apr: Some(interest_apr),
+            ..self
+        }
+    }
+
+    pub fn institution(&self) -> &PartyId {
+        &self.institution
+    }
+
+    pub fn account_number(&self) -> &String {
+        &self.account_number
+    }
+
+    pub fn interest_apr(&self) -> Option<Decimal> {
+        self.interest_apr
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl BrokerageAccount {
+    pub fn new<S: Into<String>>(institution: PartyId, account_number: S) -> Self {
+        Self {
+            institution,
+            account_number: account_number.into(),
+        }
+    }
+
+    pub fn institution(&self) -> &PartyId {
+        &self.institution
+    }
+
+    pub fn account_number(&self) -> &String {
+        &self.account_number
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl CreditCard {
+    ///
+    /// A card whose statement closes each month on `close_day`, and whose annual fee is
+    /// charged in `close_month`.
+    ///
+    pub fn new<S: Into<String>>(
+        institution: PartyId,
+        account_number: S,
+        close_month: u8,
+        close_day: u8,
+        interest_apr: Decimal,
+        annual_fee: Quantity,
+    ) -> Self {
+        Self {
+            institution,
+            account_number: account_number.into(),
+            close_month,
+            close_day,
+            interest_apr,
+            annual_fee,
+        }
+    }
+
+    pub fn institution(&self) -> &PartyId {
+        &self.institution
+    }
+
+    pub fn account_number(&self) -> &String {
+        &self.account_number
+    }
+
+    pub fn close_month(&self) -> u8 {
+        self.close_month
+    }
+
+    pub fn close_day(&self) -> u8 {
+        self.close_day
+    }
+
+    pub fn interest_apr(&self) -> Decimal {
+        self.interest_apr
+    }
+
+    pub fn annual_fee(&self) -> &Quantity {
+        &self.annual_fee
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl Loan {
+    pub fn new<S: Into<String>>(
+        institution: PartyId,
+        account_number: S,
+        term_in_months: u8,
+    ) -> Self {
+        Self {
+            institution,
+            account_number: account_number.into(),
+            interest_apr: None,
+            term_in_months,
+        }
+    }
+
+    pub fn with_interest_apr(self, interest_apr: Decimal) -> Self {
+        Self {
+            interest_apr: Some(interest_apr),
+            ..self
+        }
+    }
+
+    pub fn institution(&self) -> &PartyId {
+        &self.institution
+    }
+
+    pub fn account_number(&self) -> &String {
+        &self.account_number
+    }
+
+    pub fn interest_apr(&self) -> Option<Decimal> {
+        self.interest_apr
+    }
+
+    pub fn term_in_months(&self) -> u8 {
+        self.term_in_months
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl Customer {
+    pub fn new(party: PartyId) -> Self {
+        Self { party }
+    }
+
+    pub fn party(&self) -> &PartyId {
+        &self.party
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl Supplier {
+    pub fn new(party: PartyId) -> Self {
+        Self { party }
+    }
+
+    pub fn party(&self) -> &PartyId {
+        &self.party
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl Salary {
+    pub fn for_employee(employee: PartyId) -> Self {
+        Self {
+            employee: Some(employee),
+        }
+    }
+
+    pub fn employee(&self) -> Option<&PartyId> {
+        self.employee.as_ref()
+    }
+
+    ///
+    /// Returns `true` if this account should receive the pay of `employee`; a shared salary
+    /// account accepts every employee.
+    ///
+    pub fn is_for(&self, employee: &PartyId) -> bool {
+        self.employee
+            .as_ref()
+            .map(|e| e == employee)
+            .unwrap_or(true)
+    }
+}
+
 // ------------------------------------------------------------------------------------------------
 // Private Functions
 // ------------------------------------------------------------------------------------------------
@@ -0,0 +1,176 @@
+/*!
+The balance engine; account balances summarized from journal transactions.
+
+*/
+
+use crate::journal::Journal;
+use crate::ledger::{AccountId, Ledger};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// The total debits and credits posted to a single account.
+///
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AccountBalance {
+    debits: Decimal,
+    credits: Decimal,
+}
+
+///
+/// Account balances for the transactions posted in `[from, until)`; without a `from` date
+/// these are the closing balances as at `until`.
+///
+#[derive(Clone, Debug)]
+pub struct Balances {
+    from: Option<DateTime<Utc>>,
+    until: DateTime<Utc>,
+    accounts: HashMap<AccountId, AccountBalance>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl AccountBalance {
+    pub fn debits(&self) -> Decimal {
+        self.debits
+    }
+
+    pub fn credits(&self) -> Decimal {
+        self.credits
+    }
+
+    ///
+    /// Debits less credits; positive for a debit balance.
+    ///
+    pub fn net(&self) -> Decimal {
+        self.debits - self.credits
+    }
+
+    fn add(&mut self, quantity: Decimal) {
+        if quantity.is_sign_negative() {
+            self.credits -= quantity;
+        } else {
+            self.debits += quantity;
+        }
+    }
+
+    fn merge(&mut self, other: &AccountBalance) {
+        self.debits += other.debits;
+        self.credits += other.credits;
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl Balances {
+    ///
+    /// Summarize the splits of every transaction in `journals` posted before `until` and, if
+    /// given, on or after `from`.
+    ///
+    pub fn calculate(
+        journals: &[Journal],
+        from: Option<DateTime<Utc>>,
+        until: DateTime<Utc>,
+    ) -> Self {
+        let mut accounts: HashMap<AccountId, AccountBalance> = Default::default();
+        for transaction in journals.iter().flat_map(|journal| journal.transactions()) {
+            let posted = transaction.posted();
+            if posted >= until || from.map(|from| posted < from).unwrap_or_default() {
+                continue;
+            }
+            for split in transaction.splits() {
+                accounts
+                    .entry(split.account().clone())
+                    .or_default()
+                    .add(split.quantity().quantity());
+            }
+        }
+        Self {
+            from,
+            until,
+            accounts,
+        }
+    }
+
+    ///
+    /// Closing balances, all transactions posted before `until`.
+    ///
+    pub fn as_at(journals: &[Journal], until: DateTime<Utc>) -> Self {
+        Self::calculate(journals, None, until)
+    }
+
+    pub fn from(&self) -> Option<DateTime<Utc>> {
+        self.from
+    }
+
+    pub fn until(&self) -> DateTime<Utc> {
+        self.until
+    }
+
+    pub fn accounts(&self) -> impl Iterator<Item = (&AccountId, &AccountBalance)> {
+        self.accounts.iter()
+    }
+
+    pub fn get(&self, id: &AccountId) -> Option<&AccountBalance> {
+        self.accounts.get(id)
+    }
+
+    ///
+    /// The net balance of the account itself, excluding any child accounts.
+    ///
+    pub fn balance(&self, id: &AccountId) -> Decimal {
+        self.get(id).map(AccountBalance::net).unwrap_or_default()
+    }
+
+    ///
+    /// The balance of the account and all of its descendants in the ledger's hierarchy.
+    ///
+    pub fn rolled_up(&self, ledger: &Ledger, id: &AccountId) -> AccountBalance {
+        let mut total = self.get(id).cloned().unwrap_or_default();
+        for descendant in ledger.descendants(id) {
+            if let Some(balance) = self.get(descendant.id()) {
+                total.merge(balance);
+            }
+        }
+        total
+    }
+
+    ///
+    /// The rolled-up balance in the account kind's normal sign, so that income and liability
+    /// accounts with a credit balance are positive.
+    ///
+    pub fn natural_balance(&self, ledger: &Ledger, id: &AccountId) -> Decimal {
+        let net = self.rolled_up(ledger, id).net();
+        match ledger.account(id) {
+            Some(account) if !account.kind().is_debit_normal() => -net,
+            _ => net,
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
@@ -9,8 +9,11 @@ YYYYY
 
 */
 
+use crate::statement::ConceptMapping;
 use chrono::{DateTime, Utc};
 use codes_iso_4217::CurrencyCode;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
 
 // ------------------------------------------------------------------------------------------------
 // Public Macros
@@ -20,7 +23,7 @@ use codes_iso_4217::CurrencyCode;
 // Public Types
 // ------------------------------------------------------------------------------------------------
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum LedgerKind {
     General,
     Sales,
@@ -28,13 +31,14 @@ pub enum LedgerKind {
     Other(String),
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Ledger {
     kind: LedgerKind,
     created: DateTime<Utc>,
     description: String,
     currency: CurrencyCode,
     book: Vec<Account>,
+    concept_mapping: Option<ConceptMapping>,
 }
 
 // ------------------------------------------------------------------------------------------------
@@ -49,6 +53,159 @@ pub struct Ledger {
 // Implementations
 // ------------------------------------------------------------------------------------------------
 
+impl Display for LedgerKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::General => write!(f, "general"),
+            Self::Sales => write!(f, "sales"),
+            Self::Purchase => write!(f, "purchase"),
+            Self::Other(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl Ledger {
+    pub fn new<S>(kind: LedgerKind, description: S, currency: CurrencyCode) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            kind,
+            created: Utc::now(),
+            description: description.into(),
+            currency,
+            book: Default::default(),
+            concept_mapping: None,
+        }
+    }
+
+    pub fn with_created(self, created: DateTime<Utc>) -> Self {
+        Self { created, ..self }
+    }
+
+    pub fn with_account(mut self, account: Account) -> Self {
+        self.add_account(account);
+        self
+    }
+
+    pub fn with_concept_mapping(self, concept_mapping: ConceptMapping) -> Self {
+        Self {
+            concept_mapping: Some(concept_mapping),
+            ..self
+        }
+    }
+
+    pub fn kind(&self) -> &LedgerKind {
+        &self.kind
+    }
+
+    pub fn created(&self) -> DateTime<Utc> {
+        self.created
+    }
+
+    pub fn description(&self) -> &String {
+        &self.description
+    }
+
+    pub fn currency(&self) -> CurrencyCode {
+        self.currency
+    }
+
+    ///
+    /// How the ledger's accounts are reported in tagged financial statements.
+    ///
+    pub fn concept_mapping(&self) -> Option<&ConceptMapping> {
+        self.concept_mapping.as_ref()
+    }
+
+    pub fn set_concept_mapping(&mut self, concept_mapping: ConceptMapping) {
+        self.concept_mapping = Some(concept_mapping);
+    }
+
+    ///
+    /// The chart of accounts.
+    ///
+    pub fn book(&self) -> impl Iterator<Item = &Account> {
+        self.book.iter()
+    }
+
+    pub fn account(&self, id: &AccountId) -> Option<&Account> {
+        self.book.iter().find(|account| account.id() == id)
+    }
+
+    pub fn account_mut(&mut self, id: &AccountId) -> Option<&mut Account> {
+        self.book.iter_mut().find(|account| account.id() == id)
+    }
+
+    ///
+    /// Add an account, replacing any existing account with the same identifier.
+    ///
+    pub fn add_account(&mut self, account: Account) {
+        match self.book.iter().position(|a| a.id() == account.id()) {
+            Some(index) => self.book[index] = account,
+            None => self.book.push(account),
+        }
+    }
+
+    pub fn children<'a>(&'a self, id: &'a AccountId) -> impl Iterator<Item = &'a Account> {
+        self.book
+            .iter()
+            .filter(move |account| account.parent_id() == Some(id))
+    }
+
+    ///
+    /// The account's parent, grand-parent, and so on up to the root of the hierarchy.
+    ///
+    pub fn ancestors(&self, id: &AccountId) -> Vec<&Account> {
+        let mut ancestors = Vec::new();
+        let mut current = self.account(id).and_then(|a| a.parent_id());
+        while let Some(parent_id) = current {
+            match self.account(parent_id) {
+                Some(parent) if !ancestors.iter().any(|a: &&Account| a.id() == parent_id) => {
+                    ancestors.push(parent);
+                    current = parent.parent_id();
+                }
+                _ => break,
+            }
+        }
+        ancestors
+    }
+
+    ///
+    /// The account's children, grand-children, and so on, in depth-first order.
+    ///
+    pub fn descendants(&self, id: &AccountId) -> Vec<&Account> {
+        let mut descendants: Vec<&Account> = Vec::new();
+        let mut pending: Vec<&Account> = self
+            .book
+            .iter()
+            .filter(|account| account.parent_id() == Some(id))
+            .collect();
+        pending.reverse();
+        while let Some(account) = pending.pop() {
+            if descendants.iter().any(|a| a.id() == account.id()) {
+                continue;
+            }
+            descendants.push(account);
+            let mut children: Vec<&Account> = self.children(account.id()).collect();
+            children.reverse();
+            pending.extend(children);
+        }
+        descendants
+    }
+
+    pub fn accounts_representing<'a, F>(&'a self, predicate: F) -> impl Iterator<Item = &'a Account>
+    where
+        F: Fn(&AccountRepresents) -> bool + 'a,
+    {
+        self.book
+            .iter()
+            .filter(move |account| account.represents().map(&predicate).unwrap_or_default())
+    }
+}
+
 // ------------------------------------------------------------------------------------------------
 // Private Functions
 // ------------------------------------------------------------------------------------------------
@@ -59,4 +216,11 @@ pub struct Ledger {
 
 #[doc(hidden)]
 mod account;
-pub use account::{Account, AccountId, AccountKind};
+pub use account::{
+    Account, AccountId, AccountKind, AccountRepresents, AccountRepresentsKind, BankAccount,
+    BrokerageAccount, CreditCard, Customer, Equipment, Loan, Salary, Supplier, Tax, UtilityService,
+};
+
+#[doc(hidden)]
+mod balance;
+pub use balance::{AccountBalance, Balances};
@@ -67,14 +67,20 @@ YYYYY
 // Modules
 // ------------------------------------------------------------------------------------------------
 
+pub mod error;
+
 pub mod audit;
 
+pub mod budget;
+
 pub mod catalog;
 
 pub mod commodity;
 
 pub mod identity;
 
+pub mod import;
+
 pub mod inventory;
 
 pub mod journal;
@@ -83,4 +89,8 @@ pub mod ledger;
 
 pub mod party;
 
+pub mod payroll;
+
+pub mod statement;
+
 pub mod tax;
@@ -1,15 +1,14 @@
 /*!
-One-line description.
-
-More detailed description, with
-
-# Example
-
-YYYYY
+Employees, how often they are paid, and what they are paid.
 
 */
 
-// use ...
+use crate::error::{not_a_person, Result};
+use crate::party::{PartyId, PersonId};
+use chrono::{DateTime, Datelike, Duration, Months, Utc};
+use codes_iso_4217::CurrencyCode;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
 
 // ------------------------------------------------------------------------------------------------
 // Public Macros
@@ -19,6 +18,40 @@ YYYYY
 // Public Types
 // ------------------------------------------------------------------------------------------------
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PaySchedule {
+    Weekly,
+    Fortnightly,
+    /// Paid on the 1st and the 16th of each month.
+    SemiMonthly,
+    Monthly,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Compensation {
+    /// An annual salary, paid in equal amounts each pay period.
+    Salary { annual: Decimal },
+    /// An hourly rate, paid for the standard number of hours each pay period.
+    Hourly {
+        rate: Decimal,
+        hours_per_period: Decimal,
+    },
+}
+
+///
+/// An employee is always a person, identified by a [`PartyId::Person`].
+///
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Employee {
+    party: PartyId,
+    employee_number: String,
+    hired: DateTime<Utc>,
+    terminated: Option<DateTime<Utc>>,
+    schedule: PaySchedule,
+    compensation: Compensation,
+    currency: CurrencyCode,
+}
+
 // ------------------------------------------------------------------------------------------------
 // Public Functions
 // ------------------------------------------------------------------------------------------------
@@ -31,6 +64,148 @@ YYYYY
 // Implementations
 // ------------------------------------------------------------------------------------------------
 
+impl PaySchedule {
+    pub fn periods_per_year(&self) -> u32 {
+        match self {
+            Self::Weekly => 52,
+            Self::Fortnightly => 26,
+            Self::SemiMonthly => 24,
+            Self::Monthly => 12,
+        }
+    }
+
+    ///
+    /// The end, exclusive, of the pay period that starts on `start`.
+    ///
+    pub fn period_end(&self, start: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            Self::Weekly => start + Duration::weeks(1),
+            Self::Fortnightly => start + Duration::weeks(2),
+            Self::SemiMonthly => {
+                if start.day() < 16 {
+                    start.with_day(16).unwrap()
+                } else {
+                    start.with_day(1).unwrap() + Months::new(1)
+                }
+            }
+            Self::Monthly => start + Months::new(1),
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl Compensation {
+    ///
+    /// The gross pay for a single period of `schedule`, before rounding.
+    ///
+    pub fn gross_per_period(&self, schedule: PaySchedule) -> Decimal {
+        match self {
+            Self::Salary { annual } => annual / Decimal::from(schedule.periods_per_year()),
+            Self::Hourly {
+                rate,
+                hours_per_period,
+            } => rate * hours_per_period,
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl Employee {
+    pub fn new<S>(
+        person: PersonId,
+        employee_number: S,
+        hired: DateTime<Utc>,
+        schedule: PaySchedule,
+        compensation: Compensation,
+        currency: CurrencyCode,
+    ) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            party: PartyId::Person(person),
+            employee_number: employee_number.into(),
+            hired,
+            terminated: None,
+            schedule,
+            compensation,
+            currency,
+        }
+    }
+
+    ///
+    /// Create an employee from an existing party identifier, which must identify a person.
+    ///
+    pub fn for_party<S>(
+        party: PartyId,
+        employee_number: S,
+        hired: DateTime<Utc>,
+        schedule: PaySchedule,
+        compensation: Compensation,
+        currency: CurrencyCode,
+    ) -> Result<Self>
+    where
+        S: Into<String>,
+    {
+        match party {
+            PartyId::Person(person) => Ok(Self::new(
+                person,
+                employee_number,
+                hired,
+                schedule,
+                compensation,
+                currency,
+            )),
+            party => Err(not_a_person(party)),
+        }
+    }
+
+    pub fn party(&self) -> &PartyId {
+        &self.party
+    }
+
+    pub fn employee_number(&self) -> &String {
+        &self.employee_number
+    }
+
+    pub fn hired(&self) -> DateTime<Utc> {
+        self.hired
+    }
+
+    pub fn terminated(&self) -> Option<DateTime<Utc>> {
+        self.terminated
+    }
+
+    pub fn terminate(&mut self, on: DateTime<Utc>) {
+        self.terminated = Some(on);
+    }
+
+    pub fn schedule(&self) -> PaySchedule {
+        self.schedule
+    }
+
+    pub fn compensation(&self) -> &Compensation {
+        &self.compensation
+    }
+
+    pub fn set_compensation(&mut self, compensation: Compensation) {
+        self.compensation = compensation;
+    }
+
+    pub fn currency(&self) -> CurrencyCode {
+        self.currency
+    }
+
+    ///
+    /// Returns `true` if the employee was employed at any time in `[start, end)`.
+    ///
+    pub fn is_employed_between(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> bool {
+        self.hired < end && self.terminated.map(|t| t >= start).unwrap_or(true)
+    }
+}
+
 // ------------------------------------------------------------------------------------------------
 // Private Functions
 // ------------------------------------------------------------------------------------------------
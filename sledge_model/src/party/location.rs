@@ -10,6 +10,7 @@ YYYYY
 */
 
 use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
 
 // ------------------------------------------------------------------------------------------------
 // Public Macros
@@ -20,15 +21,15 @@ use rust_decimal::Decimal;
 // ------------------------------------------------------------------------------------------------
 
 /// ISO-3166 2-character country code
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CountryCode(String);
 
 /// GS1/GLN
 /// 13 digit number, with structure.
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct GlobalLocationNumber(String);
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Location {
     Address(Address),
     Gln(GlobalLocationNumber),
@@ -36,7 +37,7 @@ pub enum Location {
 }
 
 /// https://en.wikipedia.org/wiki/Address
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Address {
     po_box: Option<String>,
     street_number: String,
@@ -51,7 +52,7 @@ pub struct Address {
     geo: Option<GeoLocation>,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct GeoLocation {
     lat: Decimal,                 // `-90.0..=90.0` (WGS-84)
     long: Decimal,                // `-180.0..=180.0` (WGS-84)
@@ -71,6 +72,140 @@ pub struct GeoLocation {
 // Implementations
 // ------------------------------------------------------------------------------------------------
 
+impl From<&str> for CountryCode {
+    fn from(v: &str) -> Self {
+        Self(v.to_string())
+    }
+}
+
+impl CountryCode {
+    pub fn new<S>(code: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self(code.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl From<&str> for GlobalLocationNumber {
+    fn from(v: &str) -> Self {
+        Self(v.to_string())
+    }
+}
+
+impl GlobalLocationNumber {
+    pub fn new<S>(id: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self(id.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl Address {
+    pub fn new<S1, S2, S3, S4, S5>(
+        street_number: S1,
+        street_name: S2,
+        city_or_town: S3,
+        province_or_state: S4,
+        postal_code: S5,
+        country: CountryCode,
+    ) -> Self
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+        S3: Into<String>,
+        S4: Into<String>,
+        S5: Into<String>,
+    {
+        Self {
+            po_box: None,
+            street_number: street_number.into(),
+            street_name: street_name.into(),
+            unit_number: None,
+            neighborhood_or_district: None,
+            city_or_town: city_or_town.into(),
+            province_or_state: province_or_state.into(),
+            postal_code: postal_code.into(),
+            country,
+            gln: None,
+            geo: None,
+        }
+    }
+
+    pub fn with_po_box<S: Into<String>>(self, po_box: S) -> Self {
+        Self {
+            po_box: Some(po_box.into()),
+            ..self
+        }
+    }
+
+    pub fn with_unit_number<S: Into<String>>(self, unit_number: S) -> Self {
+        Self {
+            unit_number: Some(unit_number.into()),
+            ..self
+        }
+    }
+
+    pub fn with_neighborhood_or_district<S: Into<String>>(
+        self,
+        neighborhood_or_district: S,
+    ) -> Self {
+        Self {
+            neighborhood_or_district: Some(neighborhood_or_district.into()),
+            ..self
+        }
+    }
+
+    pub fn po_box(&self) -> Option<&String> {
+        self.po_box.as_ref()
+    }
+
+    pub fn street_number(&self) -> &String {
+        &self.street_number
+    }
+
+    pub fn street_name(&self) -> &String {
+        &self.street_name
+    }
+
+    pub fn unit_number(&self) -> Option<&String> {
+        self.unit_number.as_ref()
+    }
+
+    pub fn neighborhood_or_district(&self) -> Option<&String> {
+        self.neighborhood_or_district.as_ref()
+    }
+
+    pub fn city_or_town(&self) -> &String {
+        &self.city_or_town
+    }
+
+    pub fn province_or_state(&self) -> &String {
+        &self.province_or_state
+    }
+
+    pub fn postal_code(&self) -> &String {
+        &self.postal_code
+    }
+
+    pub fn country(&self) -> &CountryCode {
+        &self.country
+    }
+}
+
 // ------------------------------------------------------------------------------------------------
 // Private Functions
 // ------------------------------------------------------------------------------------------------
@@ -9,7 +9,9 @@ YYYYY
 
 */
 
-// use ...
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
 
 // ------------------------------------------------------------------------------------------------
 // Public Macros
@@ -21,22 +23,33 @@ YYYYY
 
 /// ICD from ISO/IEC 6523-1:1998
 /// 4 digit numeric
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct InternationalCodeDesignator(String);
 
-#[derive(Debug)]
+///
+/// An identifier for a party qualified by the scheme that issued it, as used to address
+/// parties in electronic invoicing; for example `0088:5790000435975` for a GLN.
+///
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PartyIdentifier {
+    scheme: InternationalCodeDesignator,
+    id: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum PartyId {
     LegalEntity(LegalEntityId),
     Business(DunsNumber),
     Person(PersonId),
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct PersonId(String);
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Party {
     id: PartyId,
+    created: DateTime<Utc>,
     active: bool,
     name: String,
     notes: String,
@@ -51,10 +64,272 @@ pub struct Party {
 // Private Types
 // ------------------------------------------------------------------------------------------------
 
+const ICD_DUNS: &str = "0060";
+
+const ICD_GLN: &str = "0088";
+
+const ICD_LEI: &str = "0199";
+
 // ------------------------------------------------------------------------------------------------
 // Implementations
 // ------------------------------------------------------------------------------------------------
 
+impl From<&str> for InternationalCodeDesignator {
+    fn from(v: &str) -> Self {
+        Self(v.to_string())
+    }
+}
+
+impl Display for InternationalCodeDesignator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl InternationalCodeDesignator {
+    pub fn new<S>(code: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self(code.into())
+    }
+
+    /// GS1 Global Location Number.
+    pub fn gln() -> Self {
+        Self::from(ICD_GLN)
+    }
+
+    /// Dun & Bradstreet D-U-N-S number.
+    pub fn duns() -> Self {
+        Self::from(ICD_DUNS)
+    }
+
+    /// ISO 17442 Legal Entity Identifier.
+    pub fn lei() -> Self {
+        Self::from(ICD_LEI)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.0.len() == 4 && self.0.chars().all(|c| c.is_ascii_digit())
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl From<&LegalEntityId> for PartyIdentifier {
+    fn from(v: &LegalEntityId) -> Self {
+        Self::new(InternationalCodeDesignator::lei(), v.as_str())
+    }
+}
+
+impl From<&DunsNumber> for PartyIdentifier {
+    fn from(v: &DunsNumber) -> Self {
+        Self::new(InternationalCodeDesignator::duns(), v.as_str())
+    }
+}
+
+impl From<&GlobalLocationNumber> for PartyIdentifier {
+    fn from(v: &GlobalLocationNumber) -> Self {
+        Self::new(InternationalCodeDesignator::gln(), v.as_str())
+    }
+}
+
+impl Display for PartyIdentifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.scheme, self.id)
+    }
+}
+
+impl PartyIdentifier {
+    pub fn new<S>(scheme: InternationalCodeDesignator, id: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            scheme,
+            id: id.into(),
+        }
+    }
+
+    ///
+    /// The identifier of a party identified by its LEI or D-U-N-S number; a person has none.
+    ///
+    pub fn from_party_id(id: &PartyId) -> Option<Self> {
+        match id {
+            PartyId::LegalEntity(id) => Some(id.into()),
+            PartyId::Business(id) => Some(id.into()),
+            PartyId::Person(_) => None,
+        }
+    }
+
+    ///
+    /// The identifier of a VAT number, if its country has a scheme for them.
+    ///
+    pub fn from_vat_number(vat_number: &VatNumber) -> Option<Self> {
+        vat_number
+            .scheme()
+            .map(|scheme| Self::new(scheme, vat_number.as_str()))
+    }
+
+    pub fn scheme(&self) -> &InternationalCodeDesignator {
+        &self.scheme
+    }
+
+    pub fn id(&self) -> &String {
+        &self.id
+    }
+
+    ///
+    /// The party this identifies, if the scheme is one that party identifiers use.
+    ///
+    pub fn to_party_id(&self) -> Option<PartyId> {
+        match self.scheme.as_str() {
+            ICD_LEI => Some(PartyId::LegalEntity(LegalEntityId::new(self.id.clone()))),
+            ICD_DUNS => Some(PartyId::Business(DunsNumber::new(self.id.clone()))),
+            _ => None,
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl Display for PartyId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::LegalEntity(id) => write!(f, "lei:{}", id.as_str()),
+            Self::Business(id) => write!(f, "duns:{}", id.as_str()),
+            Self::Person(id) => write!(f, "person:{}", id.as_str()),
+        }
+    }
+}
+
+impl From<PersonId> for PartyId {
+    fn from(v: PersonId) -> Self {
+        Self::Person(v)
+    }
+}
+
+impl From<LegalEntityId> for PartyId {
+    fn from(v: LegalEntityId) -> Self {
+        Self::LegalEntity(v)
+    }
+}
+
+impl From<DunsNumber> for PartyId {
+    fn from(v: DunsNumber) -> Self {
+        Self::Business(v)
+    }
+}
+
+impl PartyId {
+    pub fn is_person(&self) -> bool {
+        matches!(self, Self::Person(_))
+    }
+
+    pub fn as_person(&self) -> Option<&PersonId> {
+        match self {
+            Self::Person(id) => Some(id),
+            _ => None,
+        }
+    }
+
+    ///
+    /// The identifier itself, without the scheme prefix used by `Display`.
+    ///
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::LegalEntity(id) => id.as_str(),
+            Self::Business(id) => id.as_str(),
+            Self::Person(id) => id.as_str(),
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl From<&str> for PersonId {
+    fn from(v: &str) -> Self {
+        Self(v.to_string())
+    }
+}
+
+impl PersonId {
+    pub fn new<S>(id: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self(id.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl Party {
+    pub fn new<S>(id: PartyId, name: S, mailing_address: Address) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            id,
+            created: Utc::now(),
+            active: true,
+            name: name.into(),
+            notes: Default::default(),
+            mailing_address,
+        }
+    }
+
+    pub fn with_created(self, created: DateTime<Utc>) -> Self {
+        Self { created, ..self }
+    }
+
+    pub fn with_notes<S>(self, notes: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            notes: notes.into(),
+            ..self
+        }
+    }
+
+    pub fn id(&self) -> &PartyId {
+        &self.id
+    }
+
+    pub fn created(&self) -> DateTime<Utc> {
+        self.created
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn set_active(&mut self, active: bool) {
+        self.active = active;
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn notes(&self) -> &String {
+        &self.notes
+    }
+
+    pub fn mailing_address(&self) -> &Address {
+        &self.mailing_address
+    }
+}
+
 // ------------------------------------------------------------------------------------------------
 // Private Functions
 // ------------------------------------------------------------------------------------------------
@@ -75,10 +350,11 @@ mod customer;
 
 #[doc(hidden)]
 mod organization;
-pub use organization::{DunsNumber, Isic, LegalEntityId};
+pub use organization::{DunsNumber, Isic, LegalEntityId, VatNumber};
 
 #[doc(hidden)]
 mod employee;
+pub use employee::{Compensation, Employee, PaySchedule};
 
 #[doc(hidden)]
 mod person;
@@ -9,7 +9,8 @@ YYYYY
 
 */
 
-// use ...
+use super::InternationalCodeDesignator;
+use serde::{Deserialize, Serialize};
 
 // ------------------------------------------------------------------------------------------------
 // Public Macros
@@ -21,13 +22,18 @@ YYYYY
 
 /// Data Universal Numbering System (DUNS)
 /// 9 digit number, randomly assigned.
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct DunsNumber(String);
 
 /// ISO 17442-1:2020
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct LegalEntityId(String);
 
+/// A VAT identification number, starting with the 2-character prefix of the country that
+/// issued it; `EL` for Greece, otherwise the ISO-3166 code.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct VatNumber(String);
+
 /// International Standard of Industrial Classification of All Economic Activities (ISIC)
 #[derive(Debug)]
 pub struct Isic(String);
@@ -40,10 +46,136 @@ pub struct Isic(String);
 // Private Types
 // ------------------------------------------------------------------------------------------------
 
+///
+/// The Peppol electronic address scheme codes for VAT numbers, by prefix. Peppol extends the
+/// ICD list with these codes, as not every country's VAT number has an ICD of its own.
+///
+const VAT_SCHEMES: &[(&str, &str)] = &[
+    ("AT", "9914"),
+    ("BE", "9925"),
+    ("BG", "9926"),
+    ("CH", "9927"),
+    ("CY", "9928"),
+    ("CZ", "9929"),
+    ("DE", "9930"),
+    ("EE", "9931"),
+    ("EL", "9933"),
+    ("ES", "9920"),
+    ("FR", "9957"),
+    ("GB", "9932"),
+    ("HR", "9934"),
+    ("HU", "9910"),
+    ("IE", "9935"),
+    ("IT", "9906"),
+    ("LI", "9936"),
+    ("LT", "9937"),
+    ("LU", "9938"),
+    ("LV", "9939"),
+    ("MC", "9940"),
+    ("ME", "9941"),
+    ("MK", "9942"),
+    ("MT", "9943"),
+    ("NL", "9944"),
+    ("PL", "9945"),
+    ("PT", "9946"),
+    ("RO", "9947"),
+    ("RS", "9948"),
+    ("SI", "9949"),
+    ("SK", "9950"),
+    ("SM", "9951"),
+    ("TR", "9952"),
+    ("VA", "9953"),
+];
+
 // ------------------------------------------------------------------------------------------------
 // Implementations
 // ------------------------------------------------------------------------------------------------
 
+impl From<&str> for DunsNumber {
+    fn from(v: &str) -> Self {
+        Self(v.to_string())
+    }
+}
+
+impl DunsNumber {
+    pub fn new<S>(id: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self(id.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl From<&str> for LegalEntityId {
+    fn from(v: &str) -> Self {
+        Self(v.to_string())
+    }
+}
+
+impl LegalEntityId {
+    pub fn new<S>(id: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self(id.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl From<&str> for VatNumber {
+    fn from(v: &str) -> Self {
+        Self::new(v)
+    }
+}
+
+impl VatNumber {
+    ///
+    /// The number is kept without spaces, dots or dashes, and upper-cased.
+    ///
+    pub fn new<S>(id: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self(
+            id.into()
+                .chars()
+                .filter(|c| c.is_ascii_alphanumeric())
+                .map(|c| c.to_ascii_uppercase())
+                .collect(),
+        )
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn country_prefix(&self) -> &str {
+        self.0.get(..2).unwrap_or_default()
+    }
+
+    ///
+    /// The Peppol scheme for VAT numbers issued by this number's country, if it has one.
+    ///
+    pub fn scheme(&self) -> Option<InternationalCodeDesignator> {
+        let prefix = self.country_prefix();
+        VAT_SCHEMES
+            .iter()
+            .find(|(country, _)| *country == prefix)
+            .map(|(_, scheme)| InternationalCodeDesignator::from(*scheme))
+    }
+}
+
 // ------------------------------------------------------------------------------------------------
 // Private Functions
 // ------------------------------------------------------------------------------------------------
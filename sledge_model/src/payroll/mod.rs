@@ -0,0 +1,50 @@
+/*!
+Payroll for employees; gross-to-net pay calculation and the journal transactions it posts.
+
+Each pay run takes the [`Employee`](crate::party::Employee)s paid on a
+[`PaySchedule`](crate::party::PaySchedule), calculates their gross pay for the period, and
+applies the [`PayrollRules`] in order: pre-tax deductions, withholdings, post-tax deductions,
+and finally employer contributions. Rules are pluggable through the [`PayrollRule`] trait, and
+the common percentage, fixed-amount and bracketed rules can be described as data with a
+[`RuleDefinition`] and so loaded from a local file.
+
+*/
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
+
+#[doc(hidden)]
+mod rule;
+pub use rule::{
+    PayContext, PayItemKind, PayrollRule, PayrollRules, RuleBasis, RuleBracket, RuleDefinition,
+    RuleMethod,
+};
+
+#[doc(hidden)]
+mod run;
+pub use run::{PayItem, PayRun, PayrollAccounts, Payslip};
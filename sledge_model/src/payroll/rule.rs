@@ -0,0 +1,383 @@
+/*!
+Payroll rules; the deductions, withholdings and employer contributions calculated from gross
+pay.
+
+*/
+
+use crate::ledger::AccountId;
+use crate::party::Employee;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// The kind of item a rule produces; rules are applied in the order of these kinds so that,
+/// for example, withholding is calculated after pre-tax deductions reduce taxable pay.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum PayItemKind {
+    /// Deducted from gross pay before withholding is calculated, such as a pension.
+    PreTaxDeduction,
+    /// Tax withheld from pay and owed to a tax authority.
+    Withholding,
+    /// Deducted from pay after tax.
+    Deduction,
+    /// Paid by the employer on top of gross pay, and not deducted from the employee.
+    EmployerContribution,
+}
+
+///
+/// The amount a rule is calculated on.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RuleBasis {
+    Gross,
+    /// Gross pay less any pre-tax deductions.
+    Taxable,
+}
+
+///
+/// The values available to a rule when calculating its amount for one employee's pay.
+///
+#[derive(Clone, Copy, Debug)]
+pub struct PayContext<'a> {
+    employee: &'a Employee,
+    pay_date: DateTime<Utc>,
+    gross: Decimal,
+    taxable: Decimal,
+}
+
+///
+/// A rule that calculates a single item on a payslip. Implement this trait to add rules that
+/// cannot be described by a [`RuleDefinition`].
+///
+pub trait PayrollRule: Debug {
+    fn id(&self) -> &str;
+
+    fn name(&self) -> &str;
+
+    fn kind(&self) -> PayItemKind;
+
+    ///
+    /// The liability account the amount is owed to until it is paid over.
+    ///
+    fn liability_account(&self) -> &AccountId;
+
+    ///
+    /// The account the employer's cost is posted to, only used for employer contributions.
+    ///
+    fn expense_account(&self) -> Option<&AccountId> {
+        None
+    }
+
+    fn applies_to(&self, _employee: &Employee) -> bool {
+        true
+    }
+
+    ///
+    /// The amount for this pay period, unrounded.
+    ///
+    fn calculate(&self, context: &PayContext<'_>) -> Decimal;
+}
+
+///
+/// A band of a progressive schedule; `rate` applies to annual amounts from `from` up to the
+/// start of the next bracket.
+///
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuleBracket {
+    from: Decimal,
+    rate: Decimal,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RuleMethod {
+    /// A rate applied to the basis, above an annual threshold and up to an annual cap.
+    Percentage {
+        rate: Decimal,
+        #[serde(default)]
+        annual_threshold: Option<Decimal>,
+        #[serde(default)]
+        annual_cap: Option<Decimal>,
+    },
+    /// The same amount each pay period.
+    Fixed { amount: Decimal },
+    /// A progressive schedule applied to the annualized basis.
+    Brackets { brackets: Vec<RuleBracket> },
+}
+
+///
+/// A payroll rule described entirely by data, so that it can be read from a local file.
+///
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuleDefinition {
+    id: String,
+    name: String,
+    kind: PayItemKind,
+    basis: RuleBasis,
+    method: RuleMethod,
+    liability_account: AccountId,
+    #[serde(default)]
+    expense_account: Option<AccountId>,
+    /// The employee numbers this rule applies to, or all employees if absent.
+    #[serde(default)]
+    employees: Option<Vec<String>>,
+}
+
+///
+/// The set of rules applied in a pay run.
+///
+#[derive(Debug, Default)]
+pub struct PayrollRules {
+    rules: Vec<Box<dyn PayrollRule>>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl<'a> PayContext<'a> {
+    pub(crate) fn new(
+        employee: &'a Employee,
+        pay_date: DateTime<Utc>,
+        gross: Decimal,
+        taxable: Decimal,
+    ) -> Self {
+        Self {
+            employee,
+            pay_date,
+            gross,
+            taxable,
+        }
+    }
+
+    pub fn employee(&self) -> &'a Employee {
+        self.employee
+    }
+
+    pub fn pay_date(&self) -> DateTime<Utc> {
+        self.pay_date
+    }
+
+    pub fn gross(&self) -> Decimal {
+        self.gross
+    }
+
+    pub fn taxable(&self) -> Decimal {
+        self.taxable
+    }
+
+    pub fn basis(&self, basis: RuleBasis) -> Decimal {
+        match basis {
+            RuleBasis::Gross => self.gross,
+            RuleBasis::Taxable => self.taxable,
+        }
+    }
+
+    pub fn periods_per_year(&self) -> Decimal {
+        Decimal::from(self.employee.schedule().periods_per_year())
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl RuleBracket {
+    pub fn new(from: Decimal, rate: Decimal) -> Self {
+        Self { from, rate }
+    }
+
+    pub fn from(&self) -> Decimal {
+        self.from
+    }
+
+    pub fn rate(&self) -> Decimal {
+        self.rate
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl RuleMethod {
+    ///
+    /// Apply the method to the basis for a single period of a schedule with `periods` pay
+    /// periods each year.
+    ///
+    pub fn apply(&self, basis: Decimal, periods: Decimal) -> Decimal {
+        match self {
+            Self::Percentage {
+                rate,
+                annual_threshold,
+                annual_cap,
+            } => {
+                let capped = annual_cap
+                    .map(|cap| basis.min(cap / periods))
+                    .unwrap_or(basis);
+                let above = capped - annual_threshold.unwrap_or_default() / periods;
+                above.max(Decimal::ZERO) * rate
+            }
+            Self::Fixed { amount } => *amount,
+            Self::Brackets { brackets } => {
+                let annual = basis * periods;
+                let mut tax = Decimal::ZERO;
+                for (i, bracket) in brackets.iter().enumerate() {
+                    if annual <= bracket.from {
+                        break;
+                    }
+                    let upper = brackets
+                        .get(i + 1)
+                        .map(|next| annual.min(next.from))
+                        .unwrap_or(annual);
+                    tax += (upper - bracket.from) * bracket.rate;
+                }
+                tax / periods
+            }
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl RuleDefinition {
+    pub fn new<S1, S2>(
+        id: S1,
+        name: S2,
+        kind: PayItemKind,
+        basis: RuleBasis,
+        method: RuleMethod,
+        liability_account: AccountId,
+    ) -> Self
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+    {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            kind,
+            basis,
+            method,
+            liability_account,
+            expense_account: None,
+            employees: None,
+        }
+    }
+
+    pub fn with_expense_account(self, account: AccountId) -> Self {
+        Self {
+            expense_account: Some(account),
+            ..self
+        }
+    }
+
+    pub fn for_employees<I, S>(self, employee_numbers: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            employees: Some(employee_numbers.into_iter().map(Into::into).collect()),
+            ..self
+        }
+    }
+
+    pub fn basis(&self) -> RuleBasis {
+        self.basis
+    }
+
+    pub fn method(&self) -> &RuleMethod {
+        &self.method
+    }
+}
+
+impl PayrollRule for RuleDefinition {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn kind(&self) -> PayItemKind {
+        self.kind
+    }
+
+    fn liability_account(&self) -> &AccountId {
+        &self.liability_account
+    }
+
+    fn expense_account(&self) -> Option<&AccountId> {
+        self.expense_account.as_ref()
+    }
+
+    fn applies_to(&self, employee: &Employee) -> bool {
+        self.employees
+            .as_ref()
+            .map(|numbers| numbers.contains(employee.employee_number()))
+            .unwrap_or(true)
+    }
+
+    fn calculate(&self, context: &PayContext<'_>) -> Decimal {
+        self.method
+            .apply(context.basis(self.basis), context.periods_per_year())
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl FromIterator<RuleDefinition> for PayrollRules {
+    fn from_iter<T: IntoIterator<Item = RuleDefinition>>(iter: T) -> Self {
+        let mut rules = Self::default();
+        iter.into_iter()
+            .for_each(|definition| rules.add(Box::new(definition)));
+        rules
+    }
+}
+
+impl PayrollRules {
+    pub fn add(&mut self, rule: Box<dyn PayrollRule>) {
+        self.rules.push(rule);
+    }
+
+    ///
+    /// The rules in the order they are applied: by kind, then in the order they were added.
+    ///
+    pub fn in_order(&self) -> Vec<&dyn PayrollRule> {
+        let mut rules: Vec<&dyn PayrollRule> = self.rules.iter().map(AsRef::as_ref).collect();
+        rules.sort_by_key(|rule| rule.kind());
+        rules
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
@@ -0,0 +1,366 @@
+/*!
+Pay runs; the gross-to-net calculation for each employee paid in a period, and the journal
+transactions that record it.
+
+*/
+
+use crate::commodity::{CommodityId, Quantity};
+use crate::journal::{Split, Transaction, TransactionId};
+use crate::ledger::{AccountId, AccountRepresents, Ledger};
+use crate::party::{Employee, PartyId, PaySchedule};
+use crate::payroll::{PayContext, PayItemKind, PayrollRules};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// The accounts a pay run posts to, other than the liability and expense accounts of each
+/// rule.
+///
+#[derive(Clone, Debug)]
+pub struct PayrollAccounts {
+    salary_expense: AccountId,
+    net_pay: AccountId,
+}
+
+#[derive(Clone, Debug)]
+pub struct PayItem {
+    rule_id: String,
+    name: String,
+    kind: PayItemKind,
+    amount: Quantity,
+    liability_account: AccountId,
+    expense_account: Option<AccountId>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Payslip {
+    employee: PartyId,
+    employee_number: String,
+    salary_account: AccountId,
+    gross: Quantity,
+    items: Vec<PayItem>,
+    net: Quantity,
+}
+
+#[derive(Clone, Debug)]
+pub struct PayRun {
+    schedule: PaySchedule,
+    period_start: DateTime<Utc>,
+    period_end: DateTime<Utc>,
+    pay_date: DateTime<Utc>,
+    net_pay_account: AccountId,
+    payslips: Vec<Payslip>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl PayrollAccounts {
+    ///
+    /// `salary_expense` receives gross pay unless the ledger has a salary account for the
+    /// specific employee, `net_pay` is the bank account that net pay is paid from.
+    ///
+    pub fn new(salary_expense: AccountId, net_pay: AccountId) -> Self {
+        Self {
+            salary_expense,
+            net_pay,
+        }
+    }
+
+    pub fn salary_expense(&self) -> &AccountId {
+        &self.salary_expense
+    }
+
+    pub fn net_pay(&self) -> &AccountId {
+        &self.net_pay
+    }
+
+    ///
+    /// The salary account for `employee`; an account in `ledger` that represents the salary
+    /// of that specific employee is preferred over the shared salary expense account.
+    ///
+    pub fn salary_account_for(&self, ledger: &Ledger, employee: &PartyId) -> AccountId {
+        ledger
+            .accounts_representing(|represents| match represents {
+                AccountRepresents::Salary(salary) => salary.employee() == Some(employee),
+                _ => false,
+            })
+            .map(|account| account.id().clone())
+            .next()
+            .unwrap_or_else(|| self.salary_expense.clone())
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl PayItem {
+    pub fn rule_id(&self) -> &String {
+        &self.rule_id
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn kind(&self) -> PayItemKind {
+        self.kind
+    }
+
+    pub fn amount(&self) -> &Quantity {
+        &self.amount
+    }
+
+    pub fn liability_account(&self) -> &AccountId {
+        &self.liability_account
+    }
+
+    pub fn expense_account(&self) -> Option<&AccountId> {
+        self.expense_account.as_ref()
+    }
+
+    ///
+    /// Returns `true` if the item reduces the employee's net pay.
+    ///
+    pub fn is_deducted(&self) -> bool {
+        self.kind != PayItemKind::EmployerContribution
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl Payslip {
+    pub fn employee(&self) -> &PartyId {
+        &self.employee
+    }
+
+    pub fn employee_number(&self) -> &String {
+        &self.employee_number
+    }
+
+    pub fn salary_account(&self) -> &AccountId {
+        &self.salary_account
+    }
+
+    pub fn gross(&self) -> &Quantity {
+        &self.gross
+    }
+
+    pub fn items(&self) -> impl Iterator<Item = &PayItem> {
+        self.items.iter()
+    }
+
+    pub fn net(&self) -> &Quantity {
+        &self.net
+    }
+
+    ///
+    /// Gross pay plus employer contributions.
+    ///
+    pub fn employer_cost(&self) -> Quantity {
+        self.gross.with_quantity(
+            self.gross.quantity()
+                + self
+                    .items
+                    .iter()
+                    .filter(|item| !item.is_deducted())
+                    .map(|item| item.amount.quantity())
+                    .sum::<Decimal>(),
+        )
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl PayRun {
+    ///
+    /// Calculate pay for every employee on `schedule` who was employed during the period
+    /// starting on `period_start`.
+    ///
+    pub fn calculate(
+        employees: &[Employee],
+        rules: &PayrollRules,
+        accounts: &PayrollAccounts,
+        ledger: &Ledger,
+        schedule: PaySchedule,
+        period_start: DateTime<Utc>,
+        pay_date: DateTime<Utc>,
+    ) -> Self {
+        let period_end = schedule.period_end(period_start);
+        let payslips = employees
+            .iter()
+            .filter(|employee| {
+                employee.schedule() == schedule
+                    && employee.is_employed_between(period_start, period_end)
+            })
+            .map(|employee| {
+                calculate_payslip(
+                    employee,
+                    rules,
+                    accounts.salary_account_for(ledger, employee.party()),
+                    pay_date,
+                )
+            })
+            .collect();
+
+        Self {
+            schedule,
+            period_start,
+            period_end,
+            pay_date,
+            net_pay_account: accounts.net_pay.clone(),
+            payslips,
+        }
+    }
+
+    pub fn schedule(&self) -> PaySchedule {
+        self.schedule
+    }
+
+    pub fn period_start(&self) -> DateTime<Utc> {
+        self.period_start
+    }
+
+    pub fn period_end(&self) -> DateTime<Utc> {
+        self.period_end
+    }
+
+    pub fn pay_date(&self) -> DateTime<Utc> {
+        self.pay_date
+    }
+
+    pub fn payslips(&self) -> impl Iterator<Item = &Payslip> {
+        self.payslips.iter()
+    }
+
+    ///
+    /// One balanced transaction per payslip, posted on the pay date: gross pay and employer
+    /// contributions are debited to expense, deductions and contributions credited to their
+    /// liability accounts, and net pay credited to the bank account. Transaction identifiers
+    /// are `{prefix}-{employee_number}`.
+    ///
+    pub fn transactions(&self, prefix: &str) -> Vec<Transaction> {
+        self.payslips
+            .iter()
+            .map(|payslip| {
+                let id = TransactionId::new(format!("{}-{}", prefix, payslip.employee_number));
+                let mut transaction = Transaction::new(id.clone(), self.pay_date)
+                    .with_description(format!(
+                        "Pay for {}, {} to {}",
+                        payslip.employee_number,
+                        self.period_start.date_naive(),
+                        self.period_end.date_naive()
+                    ))
+                    .with_split(Split::new(
+                        id.clone(),
+                        payslip.salary_account.clone(),
+                        payslip.gross.clone(),
+                    ));
+                for item in &payslip.items {
+                    let credit = item.amount.with_quantity(-item.amount.quantity());
+                    if !item.is_deducted() {
+                        transaction.add_split(
+                            Split::new(
+                                id.clone(),
+                                item.expense_account
+                                    .clone()
+                                    .unwrap_or_else(|| payslip.salary_account.clone()),
+                                item.amount.clone(),
+                            )
+                            .with_description(item.name.clone()),
+                        );
+                    }
+                    transaction.add_split(
+                        Split::new(id.clone(), item.liability_account.clone(), credit)
+                            .with_description(item.name.clone()),
+                    );
+                }
+                transaction.with_split(Split::new(
+                    id,
+                    self.net_pay_account.clone(),
+                    payslip.net.with_quantity(-payslip.net.quantity()),
+                ))
+            })
+            .collect()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+fn calculate_payslip(
+    employee: &Employee,
+    rules: &PayrollRules,
+    salary_account: AccountId,
+    pay_date: DateTime<Utc>,
+) -> Payslip {
+    let commodity = CommodityId::Currency(employee.currency());
+    let gross = Quantity::new(
+        commodity,
+        employee
+            .compensation()
+            .gross_per_period(employee.schedule()),
+    )
+    .rounded();
+
+    let mut taxable = gross.quantity();
+    let mut net = gross.quantity();
+    let mut items = Vec::new();
+    for rule in rules
+        .in_order()
+        .into_iter()
+        .filter(|rule| rule.applies_to(employee))
+    {
+        let context = PayContext::new(employee, pay_date, gross.quantity(), taxable);
+        let amount = gross.with_quantity(rule.calculate(&context)).rounded();
+        if amount.is_zero() {
+            continue;
+        }
+        match rule.kind() {
+            PayItemKind::PreTaxDeduction => {
+                taxable -= amount.quantity();
+                net -= amount.quantity();
+            }
+            PayItemKind::Withholding | PayItemKind::Deduction => net -= amount.quantity(),
+            PayItemKind::EmployerContribution => {}
+        }
+        items.push(PayItem {
+            rule_id: rule.id().to_string(),
+            name: rule.name().to_string(),
+            kind: rule.kind(),
+            amount,
+            liability_account: rule.liability_account().clone(),
+            expense_account: rule.expense_account().cloned(),
+        });
+    }
+
+    Payslip {
+        employee: employee.party().clone(),
+        employee_number: employee.employee_number().clone(),
+        salary_account,
+        net: gross.with_quantity(net),
+        gross,
+        items,
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
@@ -0,0 +1,159 @@
+/*!
+The balance sheet, the position of every asset, liability and equity account at a point in
+time.
+
+Income and expense accounts are not closed into equity by the balance engine, so their
+combined balance is reported as current earnings within equity; with that included, assets
+always equal liabilities plus equity.
+
+*/
+
+use super::{total, StatementLine};
+use crate::journal::Journal;
+use crate::ledger::{AccountKind, Balances, Ledger};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Clone, Debug)]
+pub struct BalanceSheet {
+    as_at: DateTime<Utc>,
+    assets: Vec<StatementLine>,
+    liabilities: Vec<StatementLine>,
+    equity: Vec<StatementLine>,
+    current_earnings: Decimal,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl BalanceSheet {
+    ///
+    /// Prepare the balance sheet from the transactions in `journals` posted before `as_at`.
+    /// Splits posted to accounts that are not in the ledger are reported as assets.
+    ///
+    pub fn prepare(ledger: &Ledger, journals: &[Journal], as_at: DateTime<Utc>) -> Self {
+        let balances = Balances::as_at(journals, as_at);
+        let mut statement = Self {
+            as_at,
+            assets: Default::default(),
+            liabilities: Default::default(),
+            equity: Default::default(),
+            current_earnings: Decimal::ZERO,
+        };
+
+        for (id, balance) in balances.accounts() {
+            let account = ledger.account(id);
+            let kind = account.map(|a| a.kind()).unwrap_or(AccountKind::Asset);
+            let line = StatementLine {
+                account: id.clone(),
+                description: account
+                    .map(|a| a.description().clone())
+                    .unwrap_or_else(|| id.to_string()),
+                amount: if kind.is_debit_normal() {
+                    balance.net()
+                } else {
+                    -balance.net()
+                },
+            };
+            match kind {
+                AccountKind::Asset => statement.assets.push(line),
+                AccountKind::Liability => statement.liabilities.push(line),
+                AccountKind::Equity => statement.equity.push(line),
+                AccountKind::Income => statement.current_earnings += line.amount,
+                AccountKind::Expense => statement.current_earnings -= line.amount,
+            }
+        }
+        for lines in [
+            &mut statement.assets,
+            &mut statement.liabilities,
+            &mut statement.equity,
+        ] {
+            lines.retain(|line| !line.amount.is_zero());
+            lines.sort_by(|lhs, rhs| lhs.account.cmp(&rhs.account));
+        }
+        statement
+    }
+
+    pub fn as_at(&self) -> DateTime<Utc> {
+        self.as_at
+    }
+
+    pub fn assets(&self) -> impl Iterator<Item = &StatementLine> {
+        self.assets.iter()
+    }
+
+    pub fn liabilities(&self) -> impl Iterator<Item = &StatementLine> {
+        self.liabilities.iter()
+    }
+
+    ///
+    /// The equity accounts, not including current earnings.
+    ///
+    pub fn equity(&self) -> impl Iterator<Item = &StatementLine> {
+        self.equity.iter()
+    }
+
+    ///
+    /// Every line on the statement, assets then liabilities then equity.
+    ///
+    pub fn lines(&self) -> impl Iterator<Item = &StatementLine> {
+        self.assets
+            .iter()
+            .chain(self.liabilities.iter())
+            .chain(self.equity.iter())
+    }
+
+    ///
+    /// Income less expenses for all transactions posted before the balance sheet date.
+    ///
+    pub fn current_earnings(&self) -> Decimal {
+        self.current_earnings
+    }
+
+    pub fn total_assets(&self) -> Decimal {
+        total(&self.assets)
+    }
+
+    pub fn total_liabilities(&self) -> Decimal {
+        total(&self.liabilities)
+    }
+
+    ///
+    /// The equity accounts plus current earnings.
+    ///
+    pub fn total_equity(&self) -> Decimal {
+        total(&self.equity) + self.current_earnings
+    }
+
+    ///
+    /// Returns `true` if assets equal liabilities plus equity.
+    ///
+    pub fn is_balanced(&self) -> bool {
+        self.total_assets() == self.total_liabilities() + self.total_equity()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
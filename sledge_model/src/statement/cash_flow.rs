@@ -0,0 +1,344 @@
+/*!
+The cash flow statement, prepared with the indirect method.
+
+The statement starts from the period's net income, the balance of every income and expense
+account, and explains the change in cash through the movement on each other balance sheet
+account. Those movements are classified, per account or per the kind of thing the account
+represents, as non-cash items that are added back to net income, as operating working capital,
+or as investing or financing activity. Because every transaction balances, the net change in
+cash always reconciles to the difference between the opening and closing cash balances.
+
+*/
+
+use crate::journal::Journal;
+use crate::ledger::{Account, AccountId, AccountKind, AccountRepresentsKind, Balances, Ledger};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CashFlowActivity {
+    /// Cash and cash equivalents, whose change the statement explains.
+    Cash,
+    /// Balances that only move with non-cash income or expense, such as accumulated
+    /// depreciation, and so are added back to net income.
+    NonCash,
+    Operating,
+    Investing,
+    Financing,
+}
+
+///
+/// How balance sheet accounts are classified. An account's own classification takes
+/// precedence, then that of its nearest ancestor, then that of the thing it represents, and
+/// finally a default for its kind; assets and liabilities are operating and equity is
+/// financing.
+///
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CashFlowClassification {
+    accounts: HashMap<AccountId, CashFlowActivity>,
+    represents: HashMap<AccountRepresentsKind, CashFlowActivity>,
+}
+
+///
+/// The cash effect of the movement on one account; positive amounts are an inflow.
+///
+#[derive(Clone, Debug)]
+pub struct CashFlowLine {
+    account: AccountId,
+    description: String,
+    amount: Decimal,
+}
+
+#[derive(Clone, Debug)]
+pub struct CashFlowStatement {
+    from: DateTime<Utc>,
+    until: DateTime<Utc>,
+    net_income: Decimal,
+    non_cash: Vec<CashFlowLine>,
+    operating: Vec<CashFlowLine>,
+    investing: Vec<CashFlowLine>,
+    financing: Vec<CashFlowLine>,
+    opening_cash: Decimal,
+    closing_cash: Decimal,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl Default for CashFlowClassification {
+    fn default() -> Self {
+        Self {
+            accounts: Default::default(),
+            represents: [
+                (AccountRepresentsKind::BankAccount, CashFlowActivity::Cash),
+                (
+                    AccountRepresentsKind::BrokerageAccount,
+                    CashFlowActivity::Investing,
+                ),
+                (
+                    AccountRepresentsKind::CreditCard,
+                    CashFlowActivity::Operating,
+                ),
+                (AccountRepresentsKind::Customer, CashFlowActivity::Operating),
+                (
+                    AccountRepresentsKind::Equipment,
+                    CashFlowActivity::Investing,
+                ),
+                (AccountRepresentsKind::Loan, CashFlowActivity::Financing),
+                (AccountRepresentsKind::Supplier, CashFlowActivity::Operating),
+                (AccountRepresentsKind::Salary, CashFlowActivity::Operating),
+                (AccountRepresentsKind::Tax, CashFlowActivity::Operating),
+                (
+                    AccountRepresentsKind::UtilityService,
+                    CashFlowActivity::Operating,
+                ),
+            ]
+            .into_iter()
+            .collect(),
+        }
+    }
+}
+
+impl CashFlowClassification {
+    pub fn with_account(mut self, account: AccountId, activity: CashFlowActivity) -> Self {
+        self.set_account(account, activity);
+        self
+    }
+
+    pub fn with_represents(
+        mut self,
+        represents: AccountRepresentsKind,
+        activity: CashFlowActivity,
+    ) -> Self {
+        self.set_represents(represents, activity);
+        self
+    }
+
+    pub fn set_account(&mut self, account: AccountId, activity: CashFlowActivity) {
+        let _ = self.accounts.insert(account, activity);
+    }
+
+    pub fn set_represents(
+        &mut self,
+        represents: AccountRepresentsKind,
+        activity: CashFlowActivity,
+    ) {
+        let _ = self.represents.insert(represents, activity);
+    }
+
+    ///
+    /// The activity for a balance sheet account; income and expense accounts are part of net
+    /// income and so return `None`.
+    ///
+    pub fn classify(&self, ledger: &Ledger, account: &Account) -> Option<CashFlowActivity> {
+        if !account.kind().is_balance_sheet() {
+            return None;
+        }
+        let own = self.accounts.get(account.id()).or_else(|| {
+            ledger
+                .ancestors(account.id())
+                .into_iter()
+                .find_map(|ancestor| self.accounts.get(ancestor.id()))
+        });
+        let represents = account
+            .represents()
+            .and_then(|represents| self.represents.get(&represents.kind()));
+        Some(
+            own.or(represents)
+                .cloned()
+                .unwrap_or_else(|| match account.kind() {
+                    AccountKind::Equity => CashFlowActivity::Financing,
+                    _ => CashFlowActivity::Operating,
+                }),
+        )
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl CashFlowLine {
+    pub fn account(&self) -> &AccountId {
+        &self.account
+    }
+
+    pub fn description(&self) -> &String {
+        &self.description
+    }
+
+    pub fn amount(&self) -> Decimal {
+        self.amount
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl CashFlowStatement {
+    ///
+    /// Prepare the statement for the transactions in `journals` posted in `[from, until)`.
+    /// Splits posted to accounts that are not in the ledger are treated as operating.
+    ///
+    pub fn prepare(
+        ledger: &Ledger,
+        journals: &[Journal],
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+        classification: &CashFlowClassification,
+    ) -> Self {
+        let movements = Balances::calculate(journals, Some(from), until);
+        let mut statement = Self {
+            from,
+            until,
+            net_income: Decimal::ZERO,
+            non_cash: Default::default(),
+            operating: Default::default(),
+            investing: Default::default(),
+            financing: Default::default(),
+            opening_cash: Decimal::ZERO,
+            closing_cash: Decimal::ZERO,
+        };
+
+        for (id, balance) in movements.accounts() {
+            let account = ledger.account(id);
+            let activity = match account {
+                Some(account) => classification.classify(ledger, account),
+                None => Some(CashFlowActivity::Operating),
+            };
+            let line = CashFlowLine {
+                account: id.clone(),
+                description: account
+                    .map(|a| a.description().clone())
+                    .unwrap_or_else(|| id.to_string()),
+                amount: -balance.net(),
+            };
+            match activity {
+                None => statement.net_income += line.amount,
+                Some(CashFlowActivity::Cash) => {}
+                Some(CashFlowActivity::NonCash) => statement.non_cash.push(line),
+                Some(CashFlowActivity::Operating) => statement.operating.push(line),
+                Some(CashFlowActivity::Investing) => statement.investing.push(line),
+                Some(CashFlowActivity::Financing) => statement.financing.push(line),
+            }
+        }
+        for lines in [
+            &mut statement.non_cash,
+            &mut statement.operating,
+            &mut statement.investing,
+            &mut statement.financing,
+        ] {
+            lines.retain(|line| !line.amount.is_zero());
+            lines.sort_by(|lhs, rhs| lhs.account.cmp(&rhs.account));
+        }
+
+        let opening = Balances::as_at(journals, from);
+        let closing = Balances::as_at(journals, until);
+        for account in ledger.book().filter(|account| {
+            classification.classify(ledger, account) == Some(CashFlowActivity::Cash)
+        }) {
+            statement.opening_cash += opening.balance(account.id());
+            statement.closing_cash += closing.balance(account.id());
+        }
+        statement
+    }
+
+    pub fn from(&self) -> DateTime<Utc> {
+        self.from
+    }
+
+    pub fn until(&self) -> DateTime<Utc> {
+        self.until
+    }
+
+    pub fn net_income(&self) -> Decimal {
+        self.net_income
+    }
+
+    ///
+    /// Non-cash items added back to net income.
+    ///
+    pub fn non_cash_items(&self) -> impl Iterator<Item = &CashFlowLine> {
+        self.non_cash.iter()
+    }
+
+    ///
+    /// Changes in operating assets and liabilities.
+    ///
+    pub fn operating(&self) -> impl Iterator<Item = &CashFlowLine> {
+        self.operating.iter()
+    }
+
+    pub fn investing(&self) -> impl Iterator<Item = &CashFlowLine> {
+        self.investing.iter()
+    }
+
+    pub fn financing(&self) -> impl Iterator<Item = &CashFlowLine> {
+        self.financing.iter()
+    }
+
+    ///
+    /// Net income, plus the non-cash items and changes in working capital.
+    ///
+    pub fn net_cash_from_operating(&self) -> Decimal {
+        self.net_income + total(&self.non_cash) + total(&self.operating)
+    }
+
+    pub fn net_cash_from_investing(&self) -> Decimal {
+        total(&self.investing)
+    }
+
+    pub fn net_cash_from_financing(&self) -> Decimal {
+        total(&self.financing)
+    }
+
+    pub fn net_change_in_cash(&self) -> Decimal {
+        self.net_cash_from_operating()
+            + self.net_cash_from_investing()
+            + self.net_cash_from_financing()
+    }
+
+    pub fn opening_cash(&self) -> Decimal {
+        self.opening_cash
+    }
+
+    pub fn closing_cash(&self) -> Decimal {
+        self.closing_cash
+    }
+
+    ///
+    /// Returns `true` if the net change in cash equals the change in the cash and bank
+    /// balances over the period.
+    ///
+    pub fn is_reconciled(&self) -> bool {
+        self.closing_cash - self.opening_cash == self.net_change_in_cash()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+fn total(lines: &[CashFlowLine]) -> Decimal {
+    lines.iter().map(|line| line.amount).sum()
+}
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
@@ -0,0 +1,170 @@
+/*!
+The mapping from a ledger's accounts to the concepts of a reporting taxonomy, such as an XBRL
+taxonomy, used when financial statements are filed in a tagged format.
+
+Each concept lists the accounts whose balances it reports; listing an account also maps all
+of its descendants. Before statements are exported the mapping is validated so that every
+account with a balance on them maps to exactly one concept.
+
+*/
+
+use super::{BalanceSheet, IncomeStatement};
+use crate::error::{ambiguous_concept, unmapped_account, unmapped_earnings, Result};
+use crate::ledger::{AccountId, Ledger};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// Concepts are named by their local name within the taxonomy's namespace, and `schema_ref`
+/// is the location of the taxonomy entry point that instance documents refer to.
+///
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConceptMapping {
+    prefix: String,
+    namespace: String,
+    schema_ref: String,
+    concepts: BTreeMap<String, Vec<AccountId>>,
+    current_earnings: Option<String>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl ConceptMapping {
+    pub fn new<S1, S2, S3>(prefix: S1, namespace: S2, schema_ref: S3) -> Self
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+        S3: Into<String>,
+    {
+        Self {
+            prefix: prefix.into(),
+            namespace: namespace.into(),
+            schema_ref: schema_ref.into(),
+            concepts: Default::default(),
+            current_earnings: None,
+        }
+    }
+
+    pub fn with_concept<S, I>(mut self, concept: S, accounts: I) -> Self
+    where
+        S: Into<String>,
+        I: IntoIterator<Item = AccountId>,
+    {
+        let concept = concept.into();
+        for account in accounts {
+            self.add_account(concept.clone(), account);
+        }
+        self
+    }
+
+    ///
+    /// The concept that reports current earnings within equity on the balance sheet.
+    ///
+    pub fn with_current_earnings<S: Into<String>>(self, concept: S) -> Self {
+        Self {
+            current_earnings: Some(concept.into()),
+            ..self
+        }
+    }
+
+    pub fn add_account<S: Into<String>>(&mut self, concept: S, account: AccountId) {
+        let accounts = self.concepts.entry(concept.into()).or_default();
+        if !accounts.contains(&account) {
+            accounts.push(account);
+        }
+    }
+
+    pub fn prefix(&self) -> &String {
+        &self.prefix
+    }
+
+    pub fn namespace(&self) -> &String {
+        &self.namespace
+    }
+
+    pub fn schema_ref(&self) -> &String {
+        &self.schema_ref
+    }
+
+    pub fn concepts(&self) -> impl Iterator<Item = (&String, &Vec<AccountId>)> {
+        self.concepts.iter()
+    }
+
+    pub fn current_earnings(&self) -> Option<&String> {
+        self.current_earnings.as_ref()
+    }
+
+    ///
+    /// The single concept that reports `account`, either directly or through one of its
+    /// ancestors in the ledger.
+    ///
+    pub fn concept_for(&self, ledger: &Ledger, account: &AccountId) -> Result<&String> {
+        let mapped: Vec<&String> = self
+            .concepts
+            .iter()
+            .filter(|(_, accounts)| {
+                accounts.contains(account)
+                    || ledger
+                        .ancestors(account)
+                        .into_iter()
+                        .any(|ancestor| accounts.contains(ancestor.id()))
+            })
+            .map(|(concept, _)| concept)
+            .collect();
+        match mapped.as_slice() {
+            [concept] => Ok(concept),
+            [] => Err(unmapped_account(account.clone())),
+            _ => Err(ambiguous_concept(
+                account.clone(),
+                mapped.into_iter().cloned().collect(),
+            )),
+        }
+    }
+
+    ///
+    /// Check that every non-zero line of both statements, and any current earnings, maps to
+    /// exactly one concept.
+    ///
+    pub fn validate(
+        &self,
+        ledger: &Ledger,
+        balance_sheet: &BalanceSheet,
+        income_statement: &IncomeStatement,
+    ) -> Result<()> {
+        for line in balance_sheet.lines().chain(income_statement.lines()) {
+            if !line.amount().is_zero() {
+                let _ = self.concept_for(ledger, line.account())?;
+            }
+        }
+        if !balance_sheet.current_earnings().is_zero() && self.current_earnings.is_none() {
+            return Err(unmapped_earnings());
+        }
+        Ok(())
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
@@ -0,0 +1,126 @@
+/*!
+The income statement, the income earned and expenses incurred over a period.
+
+*/
+
+use super::{total, StatementLine};
+use crate::journal::Journal;
+use crate::ledger::{AccountKind, Balances, Ledger};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Clone, Debug)]
+pub struct IncomeStatement {
+    from: DateTime<Utc>,
+    until: DateTime<Utc>,
+    income: Vec<StatementLine>,
+    expenses: Vec<StatementLine>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl IncomeStatement {
+    ///
+    /// Prepare the statement for the transactions in `journals` posted in `[from, until)`.
+    /// Only splits posted to the ledger's income and expense accounts are included.
+    ///
+    pub fn prepare(
+        ledger: &Ledger,
+        journals: &[Journal],
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Self {
+        let movements = Balances::calculate(journals, Some(from), until);
+        let mut statement = Self {
+            from,
+            until,
+            income: Default::default(),
+            expenses: Default::default(),
+        };
+
+        for (id, balance) in movements.accounts() {
+            if let Some(account) = ledger.account(id) {
+                let line = StatementLine {
+                    account: id.clone(),
+                    description: account.description().clone(),
+                    amount: if account.kind().is_debit_normal() {
+                        balance.net()
+                    } else {
+                        -balance.net()
+                    },
+                };
+                match account.kind() {
+                    AccountKind::Income => statement.income.push(line),
+                    AccountKind::Expense => statement.expenses.push(line),
+                    _ => {}
+                }
+            }
+        }
+        for lines in [&mut statement.income, &mut statement.expenses] {
+            lines.retain(|line| !line.amount.is_zero());
+            lines.sort_by(|lhs, rhs| lhs.account.cmp(&rhs.account));
+        }
+        statement
+    }
+
+    pub fn from(&self) -> DateTime<Utc> {
+        self.from
+    }
+
+    pub fn until(&self) -> DateTime<Utc> {
+        self.until
+    }
+
+    pub fn income(&self) -> impl Iterator<Item = &StatementLine> {
+        self.income.iter()
+    }
+
+    pub fn expenses(&self) -> impl Iterator<Item = &StatementLine> {
+        self.expenses.iter()
+    }
+
+    ///
+    /// Every line on the statement, income then expenses.
+    ///
+    pub fn lines(&self) -> impl Iterator<Item = &StatementLine> {
+        self.income.iter().chain(self.expenses.iter())
+    }
+
+    pub fn total_income(&self) -> Decimal {
+        total(&self.income)
+    }
+
+    pub fn total_expenses(&self) -> Decimal {
+        total(&self.expenses)
+    }
+
+    pub fn net_income(&self) -> Decimal {
+        self.total_income() - self.total_expenses()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
@@ -0,0 +1,80 @@
+/*!
+Financial statements prepared from the balance engine.
+
+*/
+
+use crate::ledger::AccountId;
+use rust_decimal::Decimal;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// The balance of one account as reported on the balance sheet or income statement, in the
+/// account kind's normal sign.
+///
+#[derive(Clone, Debug)]
+pub struct StatementLine {
+    account: AccountId,
+    description: String,
+    amount: Decimal,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl StatementLine {
+    pub fn account(&self) -> &AccountId {
+        &self.account
+    }
+
+    pub fn description(&self) -> &String {
+        &self.description
+    }
+
+    pub fn amount(&self) -> Decimal {
+        self.amount
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+fn total(lines: &[StatementLine]) -> Decimal {
+    lines.iter().map(|line| line.amount).sum()
+}
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
+
+#[doc(hidden)]
+mod balance_sheet;
+pub use balance_sheet::BalanceSheet;
+
+#[doc(hidden)]
+mod cash_flow;
+pub use cash_flow::{CashFlowActivity, CashFlowClassification, CashFlowLine, CashFlowStatement};
+
+#[doc(hidden)]
+mod concept;
+pub use concept::ConceptMapping;
+
+#[doc(hidden)]
+mod income_statement;
+pub use income_statement::IncomeStatement;
@@ -0,0 +1,520 @@
+/*!
+Calculation of the tax due on a set of invoice or bill lines.
+
+*/
+
+use crate::commodity::{CommodityId, Quantity};
+use crate::error::{missing_tax_account, mixed_commodities, Result};
+use crate::journal::{Split, TransactionId};
+use crate::ledger::AccountId;
+use crate::tax::{sum_quantities, TaxCode, TaxCodeId, TaxTable, TaxTreatment};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// Whether line amounts already include tax.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TaxPricing {
+    Exclusive,
+    Inclusive,
+}
+
+///
+/// Output tax is collected on sales, input tax is paid on purchases.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TaxDirection {
+    Output,
+    Input,
+}
+
+///
+/// A single invoice or bill line; the account is the income or expense account the net amount
+/// is posted to.
+///
+#[derive(Clone, Debug)]
+pub struct TaxableLine {
+    account: AccountId,
+    amount: Quantity,
+    tax_codes: Vec<TaxCodeId>,
+}
+
+#[derive(Clone, Debug)]
+pub struct LineTax {
+    tax_code: TaxCodeId,
+    rate: Decimal,
+    taxable: Quantity,
+    tax: Quantity,
+    is_charged: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct TaxedLine {
+    account: AccountId,
+    net: Quantity,
+    taxes: Vec<LineTax>,
+    gross: Quantity,
+}
+
+#[derive(Clone, Debug)]
+pub struct TaxTotal {
+    tax_code: TaxCodeId,
+    taxable: Quantity,
+    tax: Quantity,
+}
+
+///
+/// A single amount to be posted to a tax, or non-recoverable cost, account. The quantity is
+/// signed as a split would be, positive for a debit.
+///
+#[derive(Clone, Debug)]
+pub struct TaxPosting {
+    account: AccountId,
+    tax_code: TaxCodeId,
+    quantity: Quantity,
+}
+
+#[derive(Clone, Debug)]
+pub struct TaxCalculation {
+    direction: TaxDirection,
+    on: DateTime<Utc>,
+    lines: Vec<TaxedLine>,
+    postings: Vec<TaxPosting>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl TaxableLine {
+    pub fn new(account: AccountId, amount: Quantity) -> Self {
+        Self {
+            account,
+            amount,
+            tax_codes: Default::default(),
+        }
+    }
+
+    pub fn with_tax_code(mut self, tax_code: TaxCodeId) -> Self {
+        self.tax_codes.push(tax_code);
+        self
+    }
+
+    pub fn account(&self) -> &AccountId {
+        &self.account
+    }
+
+    pub fn amount(&self) -> &Quantity {
+        &self.amount
+    }
+
+    pub fn tax_codes(&self) -> impl Iterator<Item = &TaxCodeId> {
+        self.tax_codes.iter()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl LineTax {
+    pub fn tax_code(&self) -> &TaxCodeId {
+        &self.tax_code
+    }
+
+    pub fn rate(&self) -> Decimal {
+        self.rate
+    }
+
+    pub fn taxable(&self) -> &Quantity {
+        &self.taxable
+    }
+
+    pub fn tax(&self) -> &Quantity {
+        &self.tax
+    }
+
+    ///
+    /// Returns `false` for reverse-charge taxes, which are calculated and reported but are
+    /// not charged by the supplier.
+    ///
+    pub fn is_charged(&self) -> bool {
+        self.is_charged
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl TaxedLine {
+    pub fn account(&self) -> &AccountId {
+        &self.account
+    }
+
+    pub fn net(&self) -> &Quantity {
+        &self.net
+    }
+
+    pub fn taxes(&self) -> impl Iterator<Item = &LineTax> {
+        self.taxes.iter()
+    }
+
+    pub fn gross(&self) -> &Quantity {
+        &self.gross
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl TaxTotal {
+    pub fn tax_code(&self) -> &TaxCodeId {
+        &self.tax_code
+    }
+
+    pub fn taxable(&self) -> &Quantity {
+        &self.taxable
+    }
+
+    pub fn tax(&self) -> &Quantity {
+        &self.tax
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl TaxPosting {
+    pub fn account(&self) -> &AccountId {
+        &self.account
+    }
+
+    pub fn tax_code(&self) -> &TaxCodeId {
+        &self.tax_code
+    }
+
+    pub fn quantity(&self) -> &Quantity {
+        &self.quantity
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl TaxCalculation {
+    pub fn direction(&self) -> TaxDirection {
+        self.direction
+    }
+
+    pub fn tax_point(&self) -> DateTime<Utc> {
+        self.on
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = &TaxedLine> {
+        self.lines.iter()
+    }
+
+    pub fn postings(&self) -> impl Iterator<Item = &TaxPosting> {
+        self.postings.iter()
+    }
+
+    pub fn net_total(&self) -> Option<Quantity> {
+        sum_quantities(self.lines.iter().map(|line| line.net.clone()))
+    }
+
+    pub fn gross_total(&self) -> Option<Quantity> {
+        sum_quantities(self.lines.iter().map(|line| line.gross.clone()))
+    }
+
+    pub fn tax_total(&self) -> Option<Quantity> {
+        sum_quantities(
+            self.lines
+                .iter()
+                .flat_map(|line| line.taxes.iter())
+                .filter(|tax| tax.is_charged)
+                .map(|tax| tax.tax.clone()),
+        )
+    }
+
+    ///
+    /// Taxable amount and tax per code, in the order codes were first seen.
+    ///
+    pub fn totals(&self) -> Vec<TaxTotal> {
+        let mut totals: Vec<TaxTotal> = Default::default();
+        for tax in self.lines.iter().flat_map(|line| line.taxes.iter()) {
+            match totals.iter_mut().find(|t| t.tax_code == tax.tax_code) {
+                Some(total) => {
+                    total.taxable = total
+                        .taxable
+                        .with_quantity(total.taxable.quantity() + tax.taxable.quantity());
+                    total.tax = total
+                        .tax
+                        .with_quantity(total.tax.quantity() + tax.tax.quantity());
+                }
+                None => totals.push(TaxTotal {
+                    tax_code: tax.tax_code.clone(),
+                    taxable: tax.taxable.clone(),
+                    tax: tax.tax.clone(),
+                }),
+            }
+        }
+        totals
+    }
+
+    ///
+    /// The splits to post for the net amount of each line, tagged with the line's first tax
+    /// code so that the taxable amount appears on a tax return. Sales lines are credited to
+    /// their account, purchase lines debited.
+    ///
+    pub fn line_splits(&self, transaction: &TransactionId) -> Vec<Split> {
+        self.lines
+            .iter()
+            .map(|line| {
+                let quantity = match self.direction {
+                    TaxDirection::Output => line.net.with_quantity(-line.net.quantity()),
+                    TaxDirection::Input => line.net.clone(),
+                };
+                let split = Split::new(transaction.clone(), line.account.clone(), quantity);
+                match line.taxes.first() {
+                    Some(tax) => split.with_tax_code(tax.tax_code.clone()),
+                    None => split,
+                }
+            })
+            .collect()
+    }
+
+    ///
+    /// The splits to post for the tax calculated, each tagged with its tax code. The splits
+    /// for the net line amounts, and for the receivable or payable, are left to the caller.
+    ///
+    pub fn splits(&self, transaction: &TransactionId) -> Vec<Split> {
+        self.postings
+            .iter()
+            .map(|posting| {
+                Split::new(
+                    transaction.clone(),
+                    posting.account.clone(),
+                    posting.quantity.clone(),
+                )
+                .with_tax_code(posting.tax_code.clone())
+            })
+            .collect()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl TaxTable {
+    ///
+    /// Calculate the tax due on `lines` using the rates in effect on the tax point `on`.
+    ///
+    /// Non-compound taxes are calculated on the net amount of each line, compound taxes are
+    /// then applied in the order listed on the line to the net amount plus all taxes before
+    /// them. Each tax is rounded to the minor units of the line's currency; for inclusive
+    /// pricing the net amount absorbs any rounding difference so that net plus tax always
+    /// equals the amount entered.
+    ///
+    pub fn calculate(
+        &self,
+        lines: &[TaxableLine],
+        on: DateTime<Utc>,
+        pricing: TaxPricing,
+        direction: TaxDirection,
+    ) -> Result<TaxCalculation> {
+        let mut commodity: Option<&CommodityId> = None;
+        let mut taxed_lines = Vec::with_capacity(lines.len());
+        for line in lines {
+            match commodity {
+                None => commodity = Some(line.amount.commodity()),
+                Some(expected) if expected != line.amount.commodity() => {
+                    return Err(mixed_commodities(
+                        expected.clone(),
+                        line.amount.commodity().clone(),
+                    ))
+                }
+                _ => {}
+            }
+            taxed_lines.push(self.calculate_line(line, on, pricing)?);
+        }
+
+        let postings = make_postings(self, &taxed_lines, direction)?;
+
+        Ok(TaxCalculation {
+            direction,
+            on,
+            lines: taxed_lines,
+            postings,
+        })
+    }
+
+    fn calculate_line(
+        &self,
+        line: &TaxableLine,
+        on: DateTime<Utc>,
+        pricing: TaxPricing,
+    ) -> Result<TaxedLine> {
+        let mut codes: Vec<(&TaxCode, Decimal)> = Vec::with_capacity(line.tax_codes.len());
+        for id in &line.tax_codes {
+            let code = self.get_required(id)?;
+            codes.push((code, code.rate_on(on)?));
+        }
+        // Simple taxes first, then compound taxes in the order given; sort is stable.
+        codes.sort_by_key(|(code, _)| code.is_compound());
+
+        let net_estimate = match pricing {
+            TaxPricing::Exclusive => line.amount.quantity(),
+            TaxPricing::Inclusive => {
+                let simple: Decimal = codes
+                    .iter()
+                    .filter(|(code, _)| is_charged(code) && !code.is_compound())
+                    .map(|(_, rate)| *rate)
+                    .sum();
+                let factor = codes
+                    .iter()
+                    .filter(|(code, _)| is_charged(code) && code.is_compound())
+                    .fold(Decimal::ONE + simple, |factor, (_, rate)| {
+                        factor * (Decimal::ONE + rate)
+                    });
+                line.amount.quantity() / factor
+            }
+        };
+
+        let mut taxes = Vec::with_capacity(codes.len());
+        let mut simple = Vec::with_capacity(codes.len());
+        let mut compound_base = net_estimate;
+        for (code, rate) in codes {
+            let base = if code.is_compound() {
+                compound_base
+            } else {
+                net_estimate
+            };
+            let tax = line.amount.with_quantity(base * rate).rounded();
+            let is_charged = is_charged(code);
+            if is_charged {
+                compound_base += tax.quantity();
+            }
+            simple.push(!code.is_compound());
+            taxes.push(LineTax {
+                tax_code: code.id().clone(),
+                rate,
+                taxable: line.amount.with_quantity(base).rounded(),
+                tax,
+                is_charged,
+            });
+        }
+
+        let charged: Decimal = taxes
+            .iter()
+            .filter(|tax| tax.is_charged)
+            .map(|tax| tax.tax.quantity())
+            .sum();
+        let (net, gross) = match pricing {
+            TaxPricing::Exclusive => (
+                line.amount.clone(),
+                line.amount.with_quantity(line.amount.quantity() + charged),
+            ),
+            TaxPricing::Inclusive => (
+                line.amount.with_quantity(line.amount.quantity() - charged),
+                line.amount.clone(),
+            ),
+        };
+        // The taxable amount of a simple tax is the net itself, keep them identical.
+        for (tax, _) in taxes.iter_mut().zip(simple).filter(|(_, simple)| *simple) {
+            tax.taxable = net.clone();
+        }
+
+        Ok(TaxedLine {
+            account: line.account.clone(),
+            net,
+            taxes,
+            gross,
+        })
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+#[inline]
+fn is_charged(code: &TaxCode) -> bool {
+    code.treatment() != TaxTreatment::ReverseCharge
+}
+
+fn make_postings(
+    table: &TaxTable,
+    lines: &[TaxedLine],
+    direction: TaxDirection,
+) -> Result<Vec<TaxPosting>> {
+    let mut postings: Vec<TaxPosting> = Default::default();
+    let mut post = |account: &AccountId, tax_code: &TaxCodeId, quantity: &Quantity| {
+        if quantity.is_zero() {
+            return;
+        }
+        match postings
+            .iter_mut()
+            .find(|p| &p.account == account && &p.tax_code == tax_code)
+        {
+            Some(posting) => {
+                posting.quantity = posting
+                    .quantity
+                    .with_quantity(posting.quantity.quantity() + quantity.quantity())
+            }
+            None => postings.push(TaxPosting {
+                account: account.clone(),
+                tax_code: tax_code.clone(),
+                quantity: quantity.clone(),
+            }),
+        }
+    };
+
+    for line in lines {
+        for tax in &line.taxes {
+            if tax.tax.is_zero() {
+                continue;
+            }
+            let code = table.get_required(&tax.tax_code)?;
+            let credit = tax.tax.with_quantity(-tax.tax.quantity());
+            match (direction, code.treatment()) {
+                (TaxDirection::Output, TaxTreatment::ReverseCharge) => {
+                    // The customer accounts for the tax, nothing is posted by the supplier.
+                }
+                (TaxDirection::Output, _) => {
+                    let account = code
+                        .output_account()
+                        .ok_or_else(|| missing_tax_account(code.id().clone()))?;
+                    post(account, code.id(), &credit);
+                }
+                (TaxDirection::Input, TaxTreatment::ReverseCharge) => {
+                    // Self-assessed: account for the output tax and reclaim it as input tax.
+                    let output = code
+                        .output_account()
+                        .ok_or_else(|| missing_tax_account(code.id().clone()))?;
+                    let input = code.input_account().unwrap_or(&line.account);
+                    post(input, code.id(), &tax.tax);
+                    post(output, code.id(), &credit);
+                }
+                (TaxDirection::Input, _) => {
+                    let account = code.input_account().unwrap_or(&line.account);
+                    post(account, code.id(), &tax.tax);
+                }
+            }
+        }
+    }
+    Ok(postings)
+}
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
@@ -0,0 +1,303 @@
+/*!
+Tax codes, the rates they carry over time, and the table that holds them.
+
+*/
+
+use crate::error::{no_tax_rate_in_effect, unknown_tax_code, Result};
+use crate::ledger::AccountId;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct TaxCodeId(String);
+
+///
+/// How a supply is treated for tax purposes; this determines both whether tax is charged and
+/// where the supply is reported on a tax return.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TaxTreatment {
+    /// Taxable at the rate in effect on the tax point.
+    Standard,
+    /// Taxable, but at a rate of zero; still reported as a taxable supply.
+    ZeroRated,
+    /// Outside the tax system entirely; no tax and no recovery of input tax.
+    Exempt,
+    /// Taxable, but the purchaser accounts for the tax rather than the supplier.
+    ReverseCharge,
+}
+
+///
+/// A rate, as a fraction (so `0.2` is 20%), that applies from `effective_from` until the next
+/// rate for the same code takes effect.
+///
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaxRate {
+    effective_from: DateTime<Utc>,
+    rate: Decimal,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaxCode {
+    id: TaxCodeId,
+    name: String,
+    treatment: TaxTreatment,
+    rates: Vec<TaxRate>,
+    is_compound: bool,
+    output_account: Option<AccountId>,
+    input_account: Option<AccountId>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaxTable {
+    codes: Vec<TaxCode>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl Display for TaxCodeId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<&str> for TaxCodeId {
+    fn from(v: &str) -> Self {
+        Self(v.to_string())
+    }
+}
+
+impl TaxCodeId {
+    pub fn new<S>(id: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self(id.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl TaxTreatment {
+    ///
+    /// Returns `true` if tax is calculated at all for this treatment.
+    ///
+    pub fn is_taxable(&self) -> bool {
+        !matches!(self, Self::Exempt)
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl TaxRate {
+    pub fn new(effective_from: DateTime<Utc>, rate: Decimal) -> Self {
+        Self {
+            effective_from,
+            rate,
+        }
+    }
+
+    pub fn effective_from(&self) -> DateTime<Utc> {
+        self.effective_from
+    }
+
+    pub fn rate(&self) -> Decimal {
+        self.rate
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl TaxCode {
+    pub fn new<S>(id: TaxCodeId, name: S, treatment: TaxTreatment) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            id,
+            name: name.into(),
+            treatment,
+            rates: Default::default(),
+            is_compound: false,
+            output_account: None,
+            input_account: None,
+        }
+    }
+
+    pub fn with_rate(mut self, rate: TaxRate) -> Self {
+        self.add_rate(rate);
+        self
+    }
+
+    ///
+    /// A compound tax is calculated on the net amount plus any non-compound taxes on the same
+    /// line, rather than on the net amount alone.
+    ///
+    pub fn compound(self) -> Self {
+        Self {
+            is_compound: true,
+            ..self
+        }
+    }
+
+    ///
+    /// The liability account that tax collected on sales is posted to.
+    ///
+    pub fn with_output_account(self, account: AccountId) -> Self {
+        Self {
+            output_account: Some(account),
+            ..self
+        }
+    }
+
+    ///
+    /// The account that recoverable tax paid on purchases is posted to. Without one, input tax
+    /// is treated as non-recoverable and is added to the cost of the purchase line.
+    ///
+    pub fn with_input_account(self, account: AccountId) -> Self {
+        Self {
+            input_account: Some(account),
+            ..self
+        }
+    }
+
+    pub fn id(&self) -> &TaxCodeId {
+        &self.id
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn treatment(&self) -> TaxTreatment {
+        self.treatment
+    }
+
+    pub fn is_compound(&self) -> bool {
+        self.is_compound
+    }
+
+    pub fn output_account(&self) -> Option<&AccountId> {
+        self.output_account.as_ref()
+    }
+
+    pub fn input_account(&self) -> Option<&AccountId> {
+        self.input_account.as_ref()
+    }
+
+    pub fn rates(&self) -> impl Iterator<Item = &TaxRate> {
+        self.rates.iter()
+    }
+
+    ///
+    /// Add a rate, keeping the rates ordered by their effective date. A rate with the same
+    /// effective date as an existing one replaces it.
+    ///
+    pub fn add_rate(&mut self, rate: TaxRate) {
+        match self
+            .rates
+            .binary_search_by_key(&rate.effective_from, |r| r.effective_from)
+        {
+            Ok(index) => self.rates[index] = rate,
+            Err(index) => self.rates.insert(index, rate),
+        }
+    }
+
+    ///
+    /// The rate in effect on the given date. Zero-rated and exempt codes always return zero,
+    /// they do not need any rates of their own.
+    ///
+    pub fn rate_on(&self, on: DateTime<Utc>) -> Result<Decimal> {
+        match self.treatment {
+            TaxTreatment::ZeroRated | TaxTreatment::Exempt => Ok(Decimal::ZERO),
+            TaxTreatment::Standard | TaxTreatment::ReverseCharge => self
+                .rates
+                .iter()
+                .rev()
+                .find(|r| r.effective_from <= on)
+                .map(|r| r.rate)
+                .ok_or_else(|| no_tax_rate_in_effect(self.id.clone(), on)),
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl FromIterator<TaxCode> for TaxTable {
+    fn from_iter<T: IntoIterator<Item = TaxCode>>(iter: T) -> Self {
+        let mut table = Self::default();
+        iter.into_iter().for_each(|code| table.insert(code));
+        table
+    }
+}
+
+impl TaxTable {
+    pub fn codes(&self) -> impl Iterator<Item = &TaxCode> {
+        self.codes.iter()
+    }
+
+    pub fn get(&self, id: &TaxCodeId) -> Option<&TaxCode> {
+        self.codes.iter().find(|code| &code.id == id)
+    }
+
+    pub fn get_required(&self, id: &TaxCodeId) -> Result<&TaxCode> {
+        self.get(id).ok_or_else(|| unknown_tax_code(id.clone()))
+    }
+
+    ///
+    /// Insert a code, replacing any existing code with the same identifier.
+    ///
+    pub fn insert(&mut self, code: TaxCode) {
+        match self.codes.iter().position(|c| c.id == code.id) {
+            Some(index) => self.codes[index] = code,
+            None => self.codes.push(code),
+        }
+    }
+
+    pub fn remove(&mut self, id: &TaxCodeId) -> Option<TaxCode> {
+        self.codes
+            .iter()
+            .position(|c| &c.id == id)
+            .map(|index| self.codes.remove(index))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.codes.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.codes.len()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
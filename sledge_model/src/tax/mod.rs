@@ -1,15 +1,50 @@
 /*!
-One-line description.
+Tax codes, and the calculation of sales taxes such as VAT and GST.
 
-More detailed description, with
+A [`TaxCode`] describes how a supply is treated, the rates that apply to it over time, and the
+accounts that output (collected) and input (paid) tax are posted to. Codes are held in a
+[`TaxTable`] which calculates the tax due on a set of [`TaxableLine`]s, either tax-inclusive or
+tax-exclusive, and returns both the amounts per line and the [`Split`](crate::journal::Split)s
+to post to the tax accounts.
 
 # Example
 
-YYYYY
+```rust
+use chrono::{TimeZone, Utc};
+use codes_iso_4217::CurrencyCode;
+use rust_decimal::Decimal;
+use sledge_model::commodity::Quantity;
+use sledge_model::ledger::AccountId;
+use sledge_model::tax::{
+    TaxCode, TaxCodeId, TaxDirection, TaxPricing, TaxRate, TaxTable, TaxTreatment, TaxableLine,
+};
+
+let table: TaxTable = [TaxCode::new(TaxCodeId::from("S"), "Standard", TaxTreatment::Standard)
+    .with_rate(TaxRate::new(
+        Utc.with_ymd_and_hms(2011, 1, 4, 0, 0, 0).unwrap(),
+        Decimal::new(2, 1),
+    ))
+    .with_output_account(AccountId::from("2200"))]
+.into_iter()
+.collect();
+
+let line = TaxableLine::new(
+    AccountId::from("4000"),
+    Quantity::new(CurrencyCode::GBP.into(), Decimal::new(12000, 2)),
+)
+.with_tax_code(TaxCodeId::from("S"));
+
+let calculation = table
+    .calculate(&[line], Utc::now(), TaxPricing::Inclusive, TaxDirection::Output)
+    .unwrap();
+
+assert_eq!(calculation.net_total().unwrap().quantity(), Decimal::new(10000, 2));
+assert_eq!(calculation.tax_total().unwrap().quantity(), Decimal::new(2000, 2));
+```
 
 */
 
-// use ...
+use crate::commodity::Quantity;
 
 // ------------------------------------------------------------------------------------------------
 // Public Macros
@@ -35,6 +70,27 @@ YYYYY
 // Private Functions
 // ------------------------------------------------------------------------------------------------
 
+fn sum_quantities(mut quantities: impl Iterator<Item = Quantity>) -> Option<Quantity> {
+    let first = quantities.next()?;
+    Some(quantities.fold(first, |total, q| {
+        total.with_quantity(total.quantity() + q.quantity())
+    }))
+}
+
 // ------------------------------------------------------------------------------------------------
 // Modules
 // ------------------------------------------------------------------------------------------------
+
+#[doc(hidden)]
+mod code;
+pub use code::{TaxCode, TaxCodeId, TaxRate, TaxTable, TaxTreatment};
+
+#[doc(hidden)]
+mod calculate;
+pub use calculate::{
+    LineTax, TaxCalculation, TaxDirection, TaxPosting, TaxPricing, TaxTotal, TaxableLine, TaxedLine,
+};
+
+#[doc(hidden)]
+mod report;
+pub use report::{TaxPeriod, TaxReturn, TaxReturnEntry, TaxReturnLine};
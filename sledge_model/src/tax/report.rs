@@ -0,0 +1,437 @@
+/*!
+Tax returns; the output and input tax for a period, per tax code.
+
+*/
+
+use crate::commodity::Quantity;
+use crate::error::{mixed_commodities, Result};
+use crate::journal::{Journal, Split, Transaction, TransactionId};
+use crate::ledger::AccountId;
+use crate::tax::{sum_quantities, TaxCode, TaxCodeId, TaxDirection, TaxTable, TaxTreatment};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// A tax period, the half-open range `[start, end)`, and the date its return was filed.
+///
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaxPeriod {
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    filed: Option<DateTime<Utc>>,
+}
+
+///
+/// The tax effect of a single transaction, for one tax code, in one direction.
+///
+#[derive(Clone, Debug)]
+pub struct TaxReturnEntry {
+    transaction: TransactionId,
+    journal: String,
+    posted: DateTime<Utc>,
+    entered: DateTime<Utc>,
+    direction: TaxDirection,
+    taxable: Quantity,
+    tax: Quantity,
+    is_late: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct TaxReturnLine {
+    tax_code: TaxCodeId,
+    treatment: TaxTreatment,
+    output_taxable: Quantity,
+    output_tax: Quantity,
+    input_taxable: Quantity,
+    input_tax: Quantity,
+    entries: Vec<TaxReturnEntry>,
+}
+
+#[derive(Clone, Debug)]
+pub struct TaxReturn {
+    period: TaxPeriod,
+    lines: Vec<TaxReturnLine>,
+    late_entries: Vec<TaxReturnEntry>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl TaxPeriod {
+    pub fn new(start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
+        Self {
+            start,
+            end,
+            filed: None,
+        }
+    }
+
+    pub fn start(&self) -> DateTime<Utc> {
+        self.start
+    }
+
+    pub fn end(&self) -> DateTime<Utc> {
+        self.end
+    }
+
+    pub fn filed(&self) -> Option<DateTime<Utc>> {
+        self.filed
+    }
+
+    pub fn is_filed(&self) -> bool {
+        self.filed.is_some()
+    }
+
+    pub fn contains(&self, date: DateTime<Utc>) -> bool {
+        date >= self.start && date < self.end
+    }
+
+    ///
+    /// Returns `true` if a transaction posted in this period was entered after the period's
+    /// return was filed, and so is missing from that return.
+    ///
+    pub fn is_late(&self, posted: DateTime<Utc>, entered: DateTime<Utc>) -> bool {
+        self.contains(posted) && self.filed.map(|filed| entered > filed).unwrap_or_default()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl TaxReturnEntry {
+    pub fn transaction(&self) -> &TransactionId {
+        &self.transaction
+    }
+
+    pub fn journal(&self) -> &String {
+        &self.journal
+    }
+
+    pub fn posted(&self) -> DateTime<Utc> {
+        self.posted
+    }
+
+    pub fn entered(&self) -> DateTime<Utc> {
+        self.entered
+    }
+
+    pub fn direction(&self) -> TaxDirection {
+        self.direction
+    }
+
+    pub fn taxable(&self) -> &Quantity {
+        &self.taxable
+    }
+
+    pub fn tax(&self) -> &Quantity {
+        &self.tax
+    }
+
+    ///
+    /// Returns `true` if the transaction was posted into a period whose return had already
+    /// been filed when it was entered.
+    ///
+    pub fn is_late(&self) -> bool {
+        self.is_late
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl TaxReturnLine {
+    pub fn tax_code(&self) -> &TaxCodeId {
+        &self.tax_code
+    }
+
+    pub fn treatment(&self) -> TaxTreatment {
+        self.treatment
+    }
+
+    pub fn output_taxable(&self) -> &Quantity {
+        &self.output_taxable
+    }
+
+    pub fn output_tax(&self) -> &Quantity {
+        &self.output_tax
+    }
+
+    pub fn input_taxable(&self) -> &Quantity {
+        &self.input_taxable
+    }
+
+    pub fn input_tax(&self) -> &Quantity {
+        &self.input_tax
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &TaxReturnEntry> {
+        self.entries.iter()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl TaxReturn {
+    ///
+    /// Prepare the return for `period` from the tax-coded splits in `journals`.
+    ///
+    /// Splits posted to a code's output or input account are the tax itself, any other split
+    /// carrying a tax code is the taxable amount; credits are sales and debits purchases.
+    /// Transactions posted into any of the `filed` periods, or into `period` itself if it has
+    /// been filed, after the return was filed are flagged as late. Those in other periods are
+    /// listed in [`TaxReturn::late_entries`] so they can be declared as adjustments.
+    ///
+    pub fn prepare(
+        table: &TaxTable,
+        period: &TaxPeriod,
+        journals: &[Journal],
+        filed: &[TaxPeriod],
+    ) -> Result<Self> {
+        let mut lines: Vec<TaxReturnLine> = Default::default();
+        let mut late_entries: Vec<TaxReturnEntry> = Default::default();
+
+        for journal in journals {
+            for transaction in journal.transactions() {
+                let posted = transaction.posted();
+                if period.contains(posted) {
+                    let is_late = period.is_late(posted, transaction.entered());
+                    for (tax_code, entry) in entries_for(table, journal, transaction, is_late)? {
+                        add_entry(table, &mut lines, tax_code, entry)?;
+                    }
+                } else if filed
+                    .iter()
+                    .any(|p| p.is_late(posted, transaction.entered()))
+                {
+                    late_entries.extend(
+                        entries_for(table, journal, transaction, true)?
+                            .into_iter()
+                            .map(|(_, entry)| entry),
+                    );
+                }
+            }
+        }
+
+        Ok(Self {
+            period: period.clone(),
+            lines,
+            late_entries,
+        })
+    }
+
+    pub fn period(&self) -> &TaxPeriod {
+        &self.period
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = &TaxReturnLine> {
+        self.lines.iter()
+    }
+
+    pub fn late_entries(&self) -> impl Iterator<Item = &TaxReturnEntry> {
+        self.late_entries.iter()
+    }
+
+    pub fn has_late_entries(&self) -> bool {
+        !self.late_entries.is_empty()
+            || self
+                .lines
+                .iter()
+                .any(|l| l.entries.iter().any(|e| e.is_late))
+    }
+
+    pub fn output_tax(&self) -> Option<Quantity> {
+        sum_quantities(self.lines.iter().map(|line| line.output_tax.clone()))
+    }
+
+    pub fn input_tax(&self) -> Option<Quantity> {
+        sum_quantities(self.lines.iter().map(|line| line.input_tax.clone()))
+    }
+
+    ///
+    /// Output tax less input tax; a negative amount is a repayment due.
+    ///
+    pub fn net_tax(&self) -> Option<Quantity> {
+        match (self.output_tax(), self.input_tax()) {
+            (Some(output), Some(input)) => {
+                Some(output.with_quantity(output.quantity() - input.quantity()))
+            }
+            _ => None,
+        }
+    }
+
+    ///
+    /// Record the return as filed on `on` and lock each journal so that nothing more can be
+    /// posted into the period.
+    ///
+    pub fn file(&mut self, on: DateTime<Utc>, journals: &mut [Journal]) {
+        self.period.filed = Some(on);
+        for journal in journals {
+            journal.lock_until(self.period.end);
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+fn entries_for(
+    table: &TaxTable,
+    journal: &Journal,
+    transaction: &Transaction,
+    is_late: bool,
+) -> Result<Vec<(TaxCodeId, TaxReturnEntry)>> {
+    let mut entries: Vec<(TaxCodeId, TaxReturnEntry)> = Default::default();
+    for split in transaction.splits() {
+        if let Some(tax_code) = split.tax_code() {
+            let (direction, is_tax, amount) = classify(table, tax_code, transaction, split)?;
+            let entry = match entries
+                .iter_mut()
+                .find(|(code, entry)| code == tax_code && entry.direction == direction)
+            {
+                Some((_, entry)) => entry,
+                None => {
+                    let zero = Quantity::zero(amount.commodity().clone());
+                    entries.push((
+                        tax_code.clone(),
+                        TaxReturnEntry {
+                            transaction: transaction.id().clone(),
+                            journal: journal.name().clone(),
+                            posted: transaction.posted(),
+                            entered: transaction.entered(),
+                            direction,
+                            taxable: zero.clone(),
+                            tax: zero,
+                            is_late,
+                        },
+                    ));
+                    &mut entries.last_mut().unwrap().1
+                }
+            };
+            if is_tax {
+                entry.tax = add(&entry.tax, &amount)?;
+            } else {
+                entry.taxable = add(&entry.taxable, &amount)?;
+            }
+        }
+    }
+    Ok(entries)
+}
+
+///
+/// The direction, whether it is tax, and the amount a split adds to its box on the return.
+/// Output amounts are credits and input amounts debits, so that a credit note reduces the same
+/// box the invoice it corrects added to.
+///
+fn classify(
+    table: &TaxTable,
+    tax_code: &TaxCodeId,
+    transaction: &Transaction,
+    split: &Split,
+) -> Result<(TaxDirection, bool, Quantity)> {
+    let code = table.get_required(tax_code)?;
+    let is_tax = code.output_account() == Some(split.account())
+        || code.input_account() == Some(split.account());
+    let quantity = split.quantity();
+    Ok(match direction(code, transaction, split) {
+        TaxDirection::Output => (
+            TaxDirection::Output,
+            is_tax,
+            quantity.with_quantity(-quantity.quantity()),
+        ),
+        TaxDirection::Input => (TaxDirection::Input, is_tax, quantity.clone()),
+    })
+}
+
+///
+/// Whether a split falls in the output or input boxes; by the tax account the transaction
+/// posts the code's tax to, else by the only tax account the code has. Only a code with both
+/// accounts and no tax posted, such as a zero-rated supply, is left to the split's sign, as a
+/// sale credits its income account.
+///
+fn direction(code: &TaxCode, transaction: &Transaction, split: &Split) -> TaxDirection {
+    let posts_to = |account: Option<&AccountId>| {
+        account.is_some_and(|account| {
+            transaction
+                .splits()
+                .any(|s| s.tax_code() == Some(code.id()) && s.account() == account)
+        })
+    };
+    if posts_to(code.output_account()) {
+        TaxDirection::Output
+    } else if posts_to(code.input_account()) {
+        TaxDirection::Input
+    } else {
+        match (code.output_account(), code.input_account()) {
+            (Some(_), None) => TaxDirection::Output,
+            (None, Some(_)) => TaxDirection::Input,
+            _ if split.is_credit() => TaxDirection::Output,
+            _ => TaxDirection::Input,
+        }
+    }
+}
+
+fn add_entry(
+    table: &TaxTable,
+    lines: &mut Vec<TaxReturnLine>,
+    tax_code: TaxCodeId,
+    entry: TaxReturnEntry,
+) -> Result<()> {
+    let line = match lines.iter_mut().position(|l| l.tax_code == tax_code) {
+        Some(index) => &mut lines[index],
+        None => {
+            let zero = Quantity::zero(entry.tax.commodity().clone());
+            lines.push(TaxReturnLine {
+                treatment: table.get_required(&tax_code)?.treatment(),
+                tax_code,
+                output_taxable: zero.clone(),
+                output_tax: zero.clone(),
+                input_taxable: zero.clone(),
+                input_tax: zero,
+                entries: Default::default(),
+            });
+            lines.last_mut().unwrap()
+        }
+    };
+    match entry.direction {
+        TaxDirection::Output => {
+            line.output_taxable = add(&line.output_taxable, &entry.taxable)?;
+            line.output_tax = add(&line.output_tax, &entry.tax)?;
+        }
+        TaxDirection::Input => {
+            line.input_taxable = add(&line.input_taxable, &entry.taxable)?;
+            line.input_tax = add(&line.input_tax, &entry.tax)?;
+        }
+    }
+    line.entries.push(entry);
+    Ok(())
+}
+
+fn add(lhs: &Quantity, rhs: &Quantity) -> Result<Quantity> {
+    if lhs.commodity() != rhs.commodity() {
+        Err(mixed_commodities(
+            lhs.commodity().clone(),
+            rhs.commodity().clone(),
+        ))
+    } else {
+        Ok(lhs.with_quantity(lhs.quantity() + rhs.quantity()))
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
@@ -0,0 +1,34 @@
+use chrono::{DateTime, TimeZone, Utc};
+use codes_iso_4217::CurrencyCode;
+use rust_decimal::Decimal;
+use sledge_model::commodity::{CommodityId, Quantity};
+use sledge_model::ledger::{Account, AccountId, AccountKind};
+
+pub fn date(y: i32, m: u32, d: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(y, m, d, 0, 0, 0).unwrap()
+}
+
+pub fn eur(cents: i64) -> Quantity {
+    money(CurrencyCode::EUR, cents)
+}
+
+pub fn gbp(cents: i64) -> Quantity {
+    money(CurrencyCode::GBP, cents)
+}
+
+pub fn usd(cents: i64) -> Quantity {
+    money(CurrencyCode::USD, cents)
+}
+
+fn money(currency: CurrencyCode, cents: i64) -> Quantity {
+    Quantity::new(currency.into(), Decimal::new(cents, 2))
+}
+
+pub fn account(id: &str, kind: AccountKind, currency: CurrencyCode) -> Account {
+    Account::new(
+        AccountId::from(id),
+        kind,
+        id,
+        CommodityId::Currency(currency),
+    )
+}
@@ -0,0 +1,194 @@
+use chrono::{DateTime, Utc};
+use codes_iso_4217::CurrencyCode;
+use rust_decimal::Decimal;
+use sledge_model::budget::{Budget, BudgetReport, Distribution};
+use sledge_model::error::Error;
+use sledge_model::journal::{Journal, Split, Transaction, TransactionId};
+use sledge_model::ledger::{AccountId, AccountKind, Balances, Ledger, LedgerKind};
+
+pub mod common;
+
+use common::{account, date, usd};
+
+fn ledger() -> Ledger {
+    Ledger::new(LedgerKind::General, "General", CurrencyCode::USD)
+        .with_account(account("1000", AccountKind::Asset, CurrencyCode::USD))
+        .with_account(account("4000", AccountKind::Income, CurrencyCode::USD))
+        .with_account(account("6000", AccountKind::Expense, CurrencyCode::USD).non_recording())
+        .with_account(
+            account("6100", AccountKind::Expense, CurrencyCode::USD)
+                .with_parent(AccountId::from("6000")),
+        )
+        .with_account(
+            account("6200", AccountKind::Expense, CurrencyCode::USD)
+                .with_parent(AccountId::from("6000")),
+        )
+}
+
+fn transaction(id: &str, posted: DateTime<Utc>, account: &str, cents: i64) -> Transaction {
+    let id = TransactionId::from(id);
+    Transaction::new(id.clone(), posted)
+        .with_split(Split::new(id.clone(), AccountId::from(account), usd(cents)))
+        .with_split(Split::new(id, AccountId::from("1000"), usd(-cents)))
+}
+
+fn journal() -> Journal {
+    let mut journal = Journal::new("general", CurrencyCode::USD);
+    for t in [
+        transaction("T1", date(2021, 1, 15), "4000", -100000),
+        transaction("T2", date(2021, 1, 20), "6100", 30000),
+        transaction("T3", date(2022, 1, 15), "4000", -120000),
+        transaction("T4", date(2022, 1, 20), "6100", 45000),
+        transaction("T5", date(2022, 2, 20), "6200", 10000),
+    ] {
+        journal.add_transaction(t).unwrap();
+    }
+    journal
+}
+
+#[test]
+fn test_rolled_up_balances() {
+    let ledger = ledger();
+    let balances = Balances::calculate(&[journal()], Some(date(2022, 1, 1)), date(2023, 1, 1));
+
+    assert_eq!(balances.balance(&AccountId::from("6000")), Decimal::ZERO);
+    assert_eq!(
+        balances.rolled_up(&ledger, &AccountId::from("6000")).net(),
+        Decimal::new(55000, 2)
+    );
+    assert_eq!(
+        balances.natural_balance(&ledger, &AccountId::from("4000")),
+        Decimal::new(120000, 2)
+    );
+}
+
+#[test]
+fn test_seasonal_distribution() {
+    let mut budget = Budget::annual("2022", date(2022, 1, 1), CurrencyCode::USD);
+    budget
+        .set_total(
+            AccountId::from("6100"),
+            Decimal::new(1000, 0),
+            &Distribution::Even,
+        )
+        .unwrap();
+    let amounts = budget.amounts(&AccountId::from("6100")).unwrap();
+    assert_eq!(amounts[0], Decimal::new(8333, 2));
+    assert_eq!(amounts[11], Decimal::new(8337, 2));
+    assert_eq!(
+        budget.total(&AccountId::from("6100")),
+        Decimal::new(1000, 0)
+    );
+
+    let mut weights = vec![Decimal::ONE; 12];
+    weights[11] = Decimal::new(3, 0);
+    budget
+        .set_total(
+            AccountId::from("4000"),
+            Decimal::new(14000, 0),
+            &Distribution::Seasonal(weights),
+        )
+        .unwrap();
+    assert_eq!(
+        budget.amount(&AccountId::from("4000"), 0),
+        Decimal::new(1000, 0)
+    );
+    assert_eq!(
+        budget.amount(&AccountId::from("4000"), 11),
+        Decimal::new(3000, 0)
+    );
+
+    let result = budget.set_total(
+        AccountId::from("4000"),
+        Decimal::ONE,
+        &Distribution::Seasonal(vec![Decimal::ONE]),
+    );
+    assert!(matches!(result, Err(Error::InvalidDistribution { .. })));
+}
+
+#[test]
+fn test_copy_from_last_year() {
+    let ledger = ledger();
+    let budget = Budget::annual("2021", date(2021, 1, 1), CurrencyCode::USD);
+    let mut budget = budget.copy_to_next_year("2022", Decimal::new(10, 0));
+    assert_eq!(budget.start(), date(2022, 1, 1));
+    assert_eq!(budget.end(), date(2023, 1, 1));
+    budget
+        .set_amount(AccountId::from("6100"), 0, Decimal::new(400, 0))
+        .unwrap();
+    let next = budget.copy_to_next_year("2023", Decimal::new(25, 1));
+    assert_eq!(
+        next.amount(&AccountId::from("6100"), 0),
+        Decimal::new(410, 0)
+    );
+
+    let from_actuals = Budget::from_actuals(
+        "2022",
+        date(2022, 1, 1),
+        budget.interval(),
+        12,
+        &ledger,
+        &[journal()],
+        Decimal::new(10, 0),
+    );
+    assert_eq!(
+        from_actuals.accounts().collect::<Vec<_>>(),
+        vec![&AccountId::from("4000"), &AccountId::from("6100")]
+    );
+    assert_eq!(
+        from_actuals.amount(&AccountId::from("4000"), 0),
+        Decimal::new(110000, 2)
+    );
+    assert_eq!(
+        from_actuals.amount(&AccountId::from("6100"), 0),
+        Decimal::new(33000, 2)
+    );
+}
+
+#[test]
+fn test_budget_versus_actual() {
+    let ledger = ledger();
+    let mut budget = Budget::annual("2022", date(2022, 1, 1), CurrencyCode::USD);
+    for (account, amount) in [("4000", 1000), ("6100", 400), ("6200", 150)] {
+        budget
+            .set_total(
+                AccountId::from(account),
+                Decimal::new(amount * 12, 0),
+                &Distribution::Even,
+            )
+            .unwrap();
+    }
+
+    let report = BudgetReport::prepare(&budget, &ledger, &[journal()], 0, 2).unwrap();
+    assert_eq!(report.from(), date(2022, 1, 1));
+    assert_eq!(report.until(), date(2022, 3, 1));
+    assert_eq!(
+        report
+            .lines()
+            .map(|line| (line.account().as_str(), line.depth()))
+            .collect::<Vec<_>>(),
+        vec![
+            ("1000", 0),
+            ("4000", 0),
+            ("6000", 0),
+            ("6100", 1),
+            ("6200", 1)
+        ]
+    );
+
+    let income = report.line(&AccountId::from("4000")).unwrap();
+    assert_eq!(income.budget(), Decimal::new(2000, 0));
+    assert_eq!(income.actual(), Decimal::new(1200, 0));
+    assert_eq!(income.variance(), Decimal::new(-800, 0));
+    assert_eq!(income.variance_percent(), Some(Decimal::new(-40, 0)));
+    assert!(!income.is_favourable());
+
+    let expenses = report.line(&AccountId::from("6000")).unwrap();
+    assert_eq!(expenses.budget(), Decimal::new(1100, 0));
+    assert_eq!(expenses.actual(), Decimal::new(550, 0));
+    assert_eq!(expenses.variance_percent(), Some(Decimal::new(-50, 0)));
+    assert!(expenses.is_favourable());
+
+    let result = BudgetReport::prepare(&budget, &ledger, &[journal()], 0, 13);
+    assert!(matches!(result, Err(Error::NoSuchBudgetPeriod { .. })));
+}
@@ -0,0 +1,136 @@
+use codes_iso_4217::CurrencyCode;
+use rust_decimal::Decimal;
+use sledge_model::journal::{Journal, Split, Transaction, TransactionId};
+use sledge_model::ledger::{
+    AccountId, AccountKind, AccountRepresents, AccountRepresentsKind, BankAccount, Equipment,
+    Ledger, LedgerKind, Loan,
+};
+use sledge_model::party::{LegalEntityId, PartyId};
+use sledge_model::statement::{
+    CashFlowActivity, CashFlowClassification, CashFlowLine, CashFlowStatement,
+};
+
+pub mod common;
+
+use common::{account, date, eur};
+
+fn ledger() -> Ledger {
+    let bank = PartyId::LegalEntity(LegalEntityId::from("5493001KJTIIGC8Y1R12"));
+    Ledger::new(LedgerKind::General, "General", CurrencyCode::EUR)
+        .with_account(
+            account("1010", AccountKind::Asset, CurrencyCode::EUR).with_represents(
+                AccountRepresents::BankAccount(BankAccount::new(bank.clone(), "12345678")),
+            ),
+        )
+        .with_account(account("1100", AccountKind::Asset, CurrencyCode::EUR))
+        .with_account(
+            account("1500", AccountKind::Asset, CurrencyCode::EUR)
+                .with_represents(AccountRepresents::Equipment(Equipment {})),
+        )
+        .with_account(
+            account("1590", AccountKind::Asset, CurrencyCode::EUR)
+                .with_parent(AccountId::from("1500")),
+        )
+        .with_account(account("2100", AccountKind::Liability, CurrencyCode::EUR))
+        .with_account(
+            account("2500", AccountKind::Liability, CurrencyCode::EUR)
+                .with_represents(AccountRepresents::Loan(Loan::new(bank, "L-99", 60))),
+        )
+        .with_account(account("3000", AccountKind::Equity, CurrencyCode::EUR))
+        .with_account(account("4000", AccountKind::Income, CurrencyCode::EUR))
+        .with_account(account("6000", AccountKind::Expense, CurrencyCode::EUR))
+        .with_account(account("6500", AccountKind::Expense, CurrencyCode::EUR))
+}
+
+fn journal() -> Journal {
+    let mut journal = Journal::new("general", CurrencyCode::EUR);
+    for (id, posted, debit, credit, amount) in [
+        ("T0", date(2021, 12, 1), "1010", "3000", 10000),
+        ("T1", date(2022, 1, 10), "1100", "4000", 5000),
+        ("T2", date(2022, 2, 10), "1010", "1100", 3000),
+        ("T3", date(2022, 3, 10), "1500", "1010", 4000),
+        ("T4", date(2022, 4, 10), "1010", "2500", 2000),
+        ("T5", date(2022, 5, 10), "6000", "2100", 1000),
+        ("T6", date(2022, 12, 31), "6500", "1590", 400),
+    ] {
+        let id = TransactionId::from(id);
+        journal
+            .add_transaction(
+                Transaction::new(id.clone(), posted)
+                    .with_split(Split::new(
+                        id.clone(),
+                        AccountId::from(debit),
+                        eur(amount * 100),
+                    ))
+                    .with_split(Split::new(id, AccountId::from(credit), eur(-amount * 100))),
+            )
+            .unwrap();
+    }
+    journal
+}
+
+fn amounts<'a>(lines: impl Iterator<Item = &'a CashFlowLine>) -> Vec<(String, Decimal)> {
+    lines
+        .map(|line| (line.account().to_string(), line.amount()))
+        .collect()
+}
+
+#[test]
+fn test_indirect_method_reconciles() {
+    let ledger = ledger();
+    let classification = CashFlowClassification::default()
+        .with_account(AccountId::from("1590"), CashFlowActivity::NonCash);
+    let statement = CashFlowStatement::prepare(
+        &ledger,
+        &[journal()],
+        date(2022, 1, 1),
+        date(2023, 1, 1),
+        &classification,
+    );
+
+    assert_eq!(statement.net_income(), Decimal::new(3600, 0));
+    assert_eq!(
+        amounts(statement.non_cash_items()),
+        vec![("1590".to_string(), Decimal::new(400, 0))]
+    );
+    assert_eq!(
+        amounts(statement.operating()),
+        vec![
+            ("1100".to_string(), Decimal::new(-2000, 0)),
+            ("2100".to_string(), Decimal::new(1000, 0)),
+        ]
+    );
+    assert_eq!(statement.net_cash_from_operating(), Decimal::new(3000, 0));
+    assert_eq!(statement.net_cash_from_investing(), Decimal::new(-4000, 0));
+    assert_eq!(statement.net_cash_from_financing(), Decimal::new(2000, 0));
+    assert_eq!(statement.net_change_in_cash(), Decimal::new(1000, 0));
+    assert_eq!(statement.opening_cash(), Decimal::new(10000, 0));
+    assert_eq!(statement.closing_cash(), Decimal::new(11000, 0));
+    assert!(statement.is_reconciled());
+}
+
+#[test]
+fn test_classification_precedence() {
+    let ledger = ledger();
+    let classification = CashFlowClassification::default()
+        .with_represents(AccountRepresentsKind::Loan, CashFlowActivity::Operating)
+        .with_account(AccountId::from("1500"), CashFlowActivity::Financing);
+    let classify =
+        |id: &str| classification.classify(&ledger, ledger.account(&AccountId::from(id)).unwrap());
+
+    assert_eq!(classify("1010"), Some(CashFlowActivity::Cash));
+    assert_eq!(classify("1500"), Some(CashFlowActivity::Financing));
+    assert_eq!(classify("1590"), Some(CashFlowActivity::Financing));
+    assert_eq!(classify("2500"), Some(CashFlowActivity::Operating));
+    assert_eq!(classify("3000"), Some(CashFlowActivity::Financing));
+    assert_eq!(classify("4000"), None);
+
+    let statement = CashFlowStatement::prepare(
+        &ledger,
+        &[journal()],
+        date(2022, 1, 1),
+        date(2023, 1, 1),
+        &classification,
+    );
+    assert!(statement.is_reconciled());
+}
@@ -0,0 +1,132 @@
+use codes_iso_4217::CurrencyCode;
+use rust_decimal::Decimal;
+use sledge_model::commodity::CommodityId;
+use sledge_model::ledger::{
+    Account, AccountId, AccountKind, AccountRepresents, Ledger, LedgerKind, Salary,
+};
+use sledge_model::party::{Compensation, Employee, PartyId, PaySchedule, PersonId};
+use sledge_model::payroll::{PayRun, PayrollAccounts, PayrollRules, RuleDefinition};
+use std::fs::File;
+
+pub mod common;
+
+use common::{date, usd};
+
+fn rules() -> PayrollRules {
+    let file = File::open("tests/payroll/rules.json").unwrap();
+    let definitions: Vec<RuleDefinition> = serde_json::from_reader(file).unwrap();
+    definitions.into_iter().collect()
+}
+
+fn employees() -> Vec<Employee> {
+    vec![
+        Employee::new(
+            PersonId::from("p-1"),
+            "E001",
+            date(2020, 1, 1),
+            PaySchedule::Monthly,
+            Compensation::Salary {
+                annual: Decimal::new(60000, 0),
+            },
+            CurrencyCode::USD,
+        ),
+        Employee::new(
+            PersonId::from("p-2"),
+            "E002",
+            date(2021, 1, 1),
+            PaySchedule::Monthly,
+            Compensation::Hourly {
+                rate: Decimal::new(20, 0),
+                hours_per_period: Decimal::new(160, 0),
+            },
+            CurrencyCode::USD,
+        ),
+        Employee::new(
+            PersonId::from("p-3"),
+            "E003",
+            date(2021, 1, 1),
+            PaySchedule::Weekly,
+            Compensation::Salary {
+                annual: Decimal::new(52000, 0),
+            },
+            CurrencyCode::USD,
+        ),
+    ]
+}
+
+fn ledger() -> Ledger {
+    Ledger::new(LedgerKind::General, "General", CurrencyCode::USD).with_account(
+        Account::new(
+            AccountId::from("6001"),
+            AccountKind::Expense,
+            "Salary, E002",
+            CommodityId::Currency(CurrencyCode::USD),
+        )
+        .with_represents(AccountRepresents::Salary(Salary::for_employee(
+            PartyId::Person(PersonId::from("p-2")),
+        ))),
+    )
+}
+
+fn pay_run() -> PayRun {
+    PayRun::calculate(
+        &employees(),
+        &rules(),
+        &PayrollAccounts::new(AccountId::from("6000"), AccountId::from("1010")),
+        &ledger(),
+        PaySchedule::Monthly,
+        date(2022, 6, 1),
+        date(2022, 6, 28),
+    )
+}
+
+#[test]
+fn test_gross_to_net() {
+    let pay_run = pay_run();
+    assert_eq!(pay_run.period_end(), date(2022, 7, 1));
+
+    let payslips: Vec<_> = pay_run.payslips().collect();
+    assert_eq!(payslips.len(), 2);
+
+    let first = payslips[0];
+    assert_eq!(first.salary_account(), &AccountId::from("6000"));
+    assert_eq!(first.gross(), &usd(500000));
+    let amounts: Vec<_> = first
+        .items()
+        .map(|item| (item.rule_id().as_str(), item.amount().clone()))
+        .collect();
+    assert_eq!(
+        amounts,
+        vec![
+            ("pension", usd(25000)),
+            ("income-tax", usd(86667)),
+            ("social-security", usd(31667)),
+            ("employer-social-security", usd(48000)),
+        ]
+    );
+    assert_eq!(first.net(), &usd(356666));
+    assert_eq!(first.employer_cost(), usd(548000));
+
+    let second = payslips[1];
+    assert_eq!(second.salary_account(), &AccountId::from("6001"));
+    assert_eq!(second.gross(), &usd(320000));
+    assert_eq!(second.items().count(), 3);
+    assert_eq!(second.net(), &usd(254000));
+}
+
+#[test]
+fn test_pay_run_transactions_balance() {
+    let transactions = pay_run().transactions("PAY-2022-06");
+    assert_eq!(transactions.len(), 2);
+    assert!(transactions.iter().all(|t| t.is_balanced()));
+
+    let first = &transactions[0];
+    assert_eq!(first.id().as_str(), "PAY-2022-06-E001");
+    assert_eq!(first.posted(), date(2022, 6, 28));
+    let bank: Vec<_> = first
+        .splits()
+        .filter(|split| split.account() == &AccountId::from("1010"))
+        .collect();
+    assert_eq!(bank.len(), 1);
+    assert_eq!(bank[0].quantity(), &usd(-356666));
+}
@@ -0,0 +1,154 @@
+use codes_iso_4217::CurrencyCode;
+use rust_decimal::Decimal;
+use sledge_model::error::Error;
+use sledge_model::journal::{Journal, Split, Transaction, TransactionId};
+use sledge_model::ledger::{AccountId, AccountKind, Ledger, LedgerKind};
+use sledge_model::statement::{BalanceSheet, ConceptMapping, IncomeStatement, StatementLine};
+
+pub mod common;
+
+use common::{account, date, eur};
+
+fn ledger() -> Ledger {
+    Ledger::new(LedgerKind::General, "General", CurrencyCode::EUR)
+        .with_account(account("1000", AccountKind::Asset, CurrencyCode::EUR))
+        .with_account(
+            account("1010", AccountKind::Asset, CurrencyCode::EUR)
+                .with_parent(AccountId::from("1000")),
+        )
+        .with_account(account("1100", AccountKind::Asset, CurrencyCode::EUR))
+        .with_account(account("2100", AccountKind::Liability, CurrencyCode::EUR))
+        .with_account(account("3000", AccountKind::Equity, CurrencyCode::EUR))
+        .with_account(account("4000", AccountKind::Income, CurrencyCode::EUR))
+        .with_account(account("6000", AccountKind::Expense, CurrencyCode::EUR))
+}
+
+fn journal() -> Journal {
+    let mut journal = Journal::new("general", CurrencyCode::EUR);
+    for (id, posted, debit, credit, amount) in [
+        ("T0", date(2021, 12, 1), "1010", "3000", 10000),
+        ("T1", date(2021, 12, 15), "1100", "4000", 800),
+        ("T2", date(2022, 1, 10), "1100", "4000", 5000),
+        ("T3", date(2022, 2, 10), "1010", "1100", 3000),
+        ("T4", date(2022, 5, 10), "6000", "2100", 1000),
+    ] {
+        let id = TransactionId::from(id);
+        journal
+            .add_transaction(
+                Transaction::new(id.clone(), posted)
+                    .with_split(Split::new(
+                        id.clone(),
+                        AccountId::from(debit),
+                        eur(amount * 100),
+                    ))
+                    .with_split(Split::new(id, AccountId::from(credit), eur(-amount * 100))),
+            )
+            .unwrap();
+    }
+    journal
+}
+
+fn mapping() -> ConceptMapping {
+    ConceptMapping::new(
+        "ifrs-full",
+        "https://xbrl.ifrs.org/taxonomy/2023-03-23/ifrs-full",
+        "https://xbrl.ifrs.org/taxonomy/2023-03-23/full_ifrs_entry_point_2023-03-23.xsd",
+    )
+    .with_concept("CashAndCashEquivalents", [AccountId::from("1000")])
+    .with_concept("TradeAndOtherCurrentReceivables", [AccountId::from("1100")])
+    .with_concept("TradeAndOtherCurrentPayables", [AccountId::from("2100")])
+    .with_concept("IssuedCapital", [AccountId::from("3000")])
+    .with_concept("Revenue", [AccountId::from("4000")])
+    .with_concept("AdministrativeExpense", [AccountId::from("6000")])
+    .with_current_earnings("RetainedEarnings")
+}
+
+fn amounts<'a>(lines: impl Iterator<Item = &'a StatementLine>) -> Vec<(String, Decimal)> {
+    lines
+        .map(|line| (line.account().to_string(), line.amount()))
+        .collect()
+}
+
+#[test]
+fn test_balance_sheet_and_income_statement() {
+    let ledger = ledger();
+    let journals = [journal()];
+
+    let balance_sheet = BalanceSheet::prepare(&ledger, &journals, date(2023, 1, 1));
+    assert_eq!(
+        amounts(balance_sheet.assets()),
+        vec![
+            ("1010".to_string(), Decimal::new(13000, 0)),
+            ("1100".to_string(), Decimal::new(2800, 0)),
+        ]
+    );
+    assert_eq!(
+        amounts(balance_sheet.liabilities()),
+        vec![("2100".to_string(), Decimal::new(1000, 0))]
+    );
+    assert_eq!(
+        amounts(balance_sheet.equity()),
+        vec![("3000".to_string(), Decimal::new(10000, 0))]
+    );
+    assert_eq!(balance_sheet.current_earnings(), Decimal::new(4800, 0));
+    assert_eq!(balance_sheet.total_equity(), Decimal::new(14800, 0));
+    assert!(balance_sheet.is_balanced());
+
+    let income_statement =
+        IncomeStatement::prepare(&ledger, &journals, date(2022, 1, 1), date(2023, 1, 1));
+    assert_eq!(
+        amounts(income_statement.income()),
+        vec![("4000".to_string(), Decimal::new(5000, 0))]
+    );
+    assert_eq!(
+        amounts(income_statement.expenses()),
+        vec![("6000".to_string(), Decimal::new(1000, 0))]
+    );
+    assert_eq!(income_statement.net_income(), Decimal::new(4000, 0));
+}
+
+#[test]
+fn test_concept_mapping_validation() {
+    let ledger = ledger().with_concept_mapping(mapping());
+    let journals = [journal()];
+    let balance_sheet = BalanceSheet::prepare(&ledger, &journals, date(2023, 1, 1));
+    let income_statement =
+        IncomeStatement::prepare(&ledger, &journals, date(2022, 1, 1), date(2023, 1, 1));
+
+    let mapping = ledger.concept_mapping().unwrap();
+    assert_eq!(
+        mapping
+            .concept_for(&ledger, &AccountId::from("1010"))
+            .unwrap(),
+        "CashAndCashEquivalents"
+    );
+    assert!(mapping
+        .validate(&ledger, &balance_sheet, &income_statement)
+        .is_ok());
+
+    let unmapped = ConceptMapping::new("p", "urn:p", "p.xsd")
+        .with_concept("Cash", [AccountId::from("1000")])
+        .with_current_earnings("RetainedEarnings");
+    assert!(matches!(
+        unmapped.validate(&ledger, &balance_sheet, &income_statement),
+        Err(Error::UnmappedAccount { account }) if account == AccountId::from("1100")
+    ));
+
+    let ambiguous = mapping
+        .clone()
+        .with_concept("OtherCurrentFinancialAssets", [AccountId::from("1010")]);
+    assert!(matches!(
+        ambiguous.validate(&ledger, &balance_sheet, &income_statement),
+        Err(Error::AmbiguousConcept { account, concepts })
+            if account == AccountId::from("1010") && concepts.len() == 2
+    ));
+
+    let no_earnings = ConceptMapping::new("p", "urn:p", "p.xsd").with_concept(
+        "Everything",
+        ["1000", "1100", "2100", "3000", "4000", "6000"].map(AccountId::from),
+    );
+    assert!(matches!(
+        no_earnings.validate(&ledger, &balance_sheet, &income_statement),
+        Err(Error::UnmappedEarnings)
+    ));
+}
@@ -0,0 +1,160 @@
+use rust_decimal::Decimal;
+use sledge_model::error::Error;
+use sledge_model::ledger::AccountId;
+use sledge_model::tax::{
+    TaxCode, TaxCodeId, TaxDirection, TaxPricing, TaxRate, TaxTable, TaxTreatment, TaxableLine,
+};
+
+pub mod common;
+
+use common::{date, gbp};
+
+fn table() -> TaxTable {
+    [
+        TaxCode::new(TaxCodeId::from("S"), "Standard", TaxTreatment::Standard)
+            .with_rate(TaxRate::new(date(2008, 12, 1), Decimal::new(15, 2)))
+            .with_rate(TaxRate::new(date(2010, 1, 1), Decimal::new(175, 3)))
+            .with_rate(TaxRate::new(date(2011, 1, 4), Decimal::new(20, 2)))
+            .with_output_account(AccountId::from("2200"))
+            .with_input_account(AccountId::from("1400")),
+        TaxCode::new(TaxCodeId::from("P"), "Provincial", TaxTreatment::Standard)
+            .with_rate(TaxRate::new(date(2000, 1, 1), Decimal::new(10, 2)))
+            .compound()
+            .with_output_account(AccountId::from("2210")),
+        TaxCode::new(
+            TaxCodeId::from("RC"),
+            "Reverse charge",
+            TaxTreatment::ReverseCharge,
+        )
+        .with_rate(TaxRate::new(date(2011, 1, 4), Decimal::new(20, 2)))
+        .with_output_account(AccountId::from("2200"))
+        .with_input_account(AccountId::from("1400")),
+        TaxCode::new(TaxCodeId::from("E"), "Exempt", TaxTreatment::Exempt),
+    ]
+    .into_iter()
+    .collect()
+}
+
+#[test]
+fn test_exclusive_output_tax() {
+    let line =
+        TaxableLine::new(AccountId::from("4000"), gbp(10000)).with_tax_code(TaxCodeId::from("S"));
+    let result = table()
+        .calculate(
+            &[line],
+            date(2022, 6, 1),
+            TaxPricing::Exclusive,
+            TaxDirection::Output,
+        )
+        .unwrap();
+
+    assert_eq!(result.net_total().unwrap(), gbp(10000));
+    assert_eq!(result.tax_total().unwrap(), gbp(2000));
+    assert_eq!(result.gross_total().unwrap(), gbp(12000));
+
+    let postings: Vec<_> = result.postings().collect();
+    assert_eq!(postings.len(), 1);
+    assert_eq!(postings[0].account(), &AccountId::from("2200"));
+    assert_eq!(postings[0].quantity(), &gbp(-2000));
+}
+
+#[test]
+fn test_rate_in_effect_on_tax_point() {
+    let line =
+        TaxableLine::new(AccountId::from("4000"), gbp(10000)).with_tax_code(TaxCodeId::from("S"));
+    let result = table()
+        .calculate(
+            &[line],
+            date(2010, 6, 1),
+            TaxPricing::Exclusive,
+            TaxDirection::Output,
+        )
+        .unwrap();
+    assert_eq!(result.tax_total().unwrap(), gbp(1750));
+
+    let line =
+        TaxableLine::new(AccountId::from("4000"), gbp(10000)).with_tax_code(TaxCodeId::from("S"));
+    let result = table().calculate(
+        &[line],
+        date(2001, 1, 1),
+        TaxPricing::Exclusive,
+        TaxDirection::Output,
+    );
+    assert!(matches!(result, Err(Error::NoTaxRateInEffect { .. })));
+}
+
+#[test]
+fn test_inclusive_compound_tax() {
+    let line = TaxableLine::new(AccountId::from("4000"), gbp(13200))
+        .with_tax_code(TaxCodeId::from("P"))
+        .with_tax_code(TaxCodeId::from("S"));
+    let result = table()
+        .calculate(
+            &[line],
+            date(2022, 6, 1),
+            TaxPricing::Inclusive,
+            TaxDirection::Output,
+        )
+        .unwrap();
+
+    let totals = result.totals();
+    assert_eq!(totals[0].tax_code(), &TaxCodeId::from("S"));
+    assert_eq!(totals[0].tax(), &gbp(2000));
+    assert_eq!(totals[1].tax_code(), &TaxCodeId::from("P"));
+    assert_eq!(totals[1].taxable(), &gbp(12000));
+    assert_eq!(totals[1].tax(), &gbp(1200));
+    assert_eq!(result.net_total().unwrap(), gbp(10000));
+    assert_eq!(result.gross_total().unwrap(), gbp(13200));
+}
+
+#[test]
+fn test_reverse_charge_purchase() {
+    let line =
+        TaxableLine::new(AccountId::from("5000"), gbp(10000)).with_tax_code(TaxCodeId::from("RC"));
+    let result = table()
+        .calculate(
+            &[line],
+            date(2022, 6, 1),
+            TaxPricing::Exclusive,
+            TaxDirection::Input,
+        )
+        .unwrap();
+
+    assert_eq!(result.gross_total().unwrap(), gbp(10000));
+    let postings: Vec<_> = result.postings().collect();
+    assert_eq!(postings.len(), 2);
+    assert_eq!(postings[0].account(), &AccountId::from("1400"));
+    assert_eq!(postings[0].quantity(), &gbp(2000));
+    assert_eq!(postings[1].account(), &AccountId::from("2200"));
+    assert_eq!(postings[1].quantity(), &gbp(-2000));
+}
+
+#[test]
+fn test_exempt_has_no_postings() {
+    let line =
+        TaxableLine::new(AccountId::from("4000"), gbp(10000)).with_tax_code(TaxCodeId::from("E"));
+    let result = table()
+        .calculate(
+            &[line],
+            date(2022, 6, 1),
+            TaxPricing::Exclusive,
+            TaxDirection::Output,
+        )
+        .unwrap();
+
+    assert_eq!(result.tax_total().unwrap(), gbp(0));
+    assert_eq!(result.postings().count(), 0);
+}
+
+#[test]
+fn test_unknown_tax_code() {
+    let line =
+        TaxableLine::new(AccountId::from("4000"), gbp(10000)).with_tax_code(TaxCodeId::from("X"));
+    let result = table().calculate(
+        &[line],
+        date(2022, 6, 1),
+        TaxPricing::Exclusive,
+        TaxDirection::Output,
+    );
+    assert!(matches!(result, Err(Error::UnknownTaxCode { .. })));
+}
@@ -0,0 +1,221 @@
+use chrono::{DateTime, Duration, Utc};
+use codes_iso_4217::CurrencyCode;
+use rust_decimal::Decimal;
+use sledge_model::error::Error;
+use sledge_model::journal::{Journal, Split, Transaction, TransactionId};
+use sledge_model::ledger::AccountId;
+use sledge_model::tax::{
+    TaxCode, TaxCodeId, TaxDirection, TaxPeriod, TaxPricing, TaxRate, TaxReturn, TaxTable,
+    TaxTreatment, TaxableLine,
+};
+
+pub mod common;
+
+use common::{date, gbp};
+
+fn table() -> TaxTable {
+    [
+        TaxCode::new(TaxCodeId::from("S"), "Standard", TaxTreatment::Standard)
+            .with_rate(TaxRate::new(date(2011, 1, 4), Decimal::new(20, 2)))
+            .with_output_account(AccountId::from("2200"))
+            .with_input_account(AccountId::from("1400")),
+    ]
+    .into_iter()
+    .collect()
+}
+
+fn invoice(
+    table: &TaxTable,
+    id: &str,
+    posted: DateTime<Utc>,
+    entered: DateTime<Utc>,
+    direction: TaxDirection,
+    cents: i64,
+) -> Transaction {
+    let (account, control_account) = match direction {
+        TaxDirection::Output => ("4000", "1100"),
+        TaxDirection::Input => ("5000", "2100"),
+    };
+    let line =
+        TaxableLine::new(AccountId::from(account), gbp(cents)).with_tax_code(TaxCodeId::from("S"));
+    let calculation = table
+        .calculate(&[line], posted, TaxPricing::Exclusive, direction)
+        .unwrap();
+    let id = TransactionId::from(id);
+    let gross = calculation.gross_total().unwrap();
+    let control = match direction {
+        TaxDirection::Output => gross,
+        TaxDirection::Input => gross.with_quantity(-gross.quantity()),
+    };
+    Transaction::new(id.clone(), posted)
+        .with_entered(entered)
+        .with_splits(calculation.line_splits(&id))
+        .with_splits(calculation.splits(&id))
+        .with_split(Split::new(id, AccountId::from(control_account), control))
+}
+
+#[test]
+fn test_return_totals_per_code() {
+    let table = table();
+    let mut journal = Journal::new("general", CurrencyCode::GBP);
+    journal
+        .add_transaction(invoice(
+            &table,
+            "INV-1",
+            date(2022, 1, 10),
+            date(2022, 1, 10),
+            TaxDirection::Output,
+            10000,
+        ))
+        .unwrap();
+    journal
+        .add_transaction(invoice(
+            &table,
+            "BILL-1",
+            date(2022, 2, 10),
+            date(2022, 2, 10),
+            TaxDirection::Input,
+            5000,
+        ))
+        .unwrap();
+    journal
+        .add_transaction(invoice(
+            &table,
+            "INV-2",
+            date(2022, 4, 10),
+            date(2022, 4, 10),
+            TaxDirection::Output,
+            10000,
+        ))
+        .unwrap();
+    assert!(journal.transactions().all(Transaction::is_balanced));
+
+    let period = TaxPeriod::new(date(2022, 1, 1), date(2022, 4, 1));
+    let tax_return = TaxReturn::prepare(&table, &period, &[journal], &[]).unwrap();
+
+    let lines: Vec<_> = tax_return.lines().collect();
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0].output_taxable(), &gbp(10000));
+    assert_eq!(lines[0].output_tax(), &gbp(2000));
+    assert_eq!(lines[0].input_taxable(), &gbp(5000));
+    assert_eq!(lines[0].input_tax(), &gbp(1000));
+    assert_eq!(lines[0].entries().count(), 2);
+    assert_eq!(tax_return.net_tax().unwrap(), gbp(1000));
+    assert!(!tax_return.has_late_entries());
+}
+
+#[test]
+fn test_credit_note_reduces_output() {
+    let table = table();
+    let mut journal = Journal::new("general", CurrencyCode::GBP);
+    journal
+        .add_transaction(invoice(
+            &table,
+            "INV-1",
+            date(2022, 1, 10),
+            date(2022, 1, 10),
+            TaxDirection::Output,
+            10000,
+        ))
+        .unwrap();
+    journal
+        .add_transaction(invoice(
+            &table,
+            "CN-1",
+            date(2022, 2, 10),
+            date(2022, 2, 10),
+            TaxDirection::Output,
+            -4000,
+        ))
+        .unwrap();
+    assert!(journal.transactions().all(Transaction::is_balanced));
+
+    let period = TaxPeriod::new(date(2022, 1, 1), date(2022, 4, 1));
+    let tax_return = TaxReturn::prepare(&table, &period, &[journal], &[]).unwrap();
+
+    let lines: Vec<_> = tax_return.lines().collect();
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0].output_taxable(), &gbp(6000));
+    assert_eq!(lines[0].output_tax(), &gbp(1200));
+    assert_eq!(lines[0].input_taxable(), &gbp(0));
+    assert_eq!(lines[0].input_tax(), &gbp(0));
+    assert_eq!(tax_return.net_tax().unwrap(), gbp(1200));
+}
+
+#[test]
+fn test_filing_locks_the_period() {
+    let table = table();
+    let mut journals = [Journal::new("general", CurrencyCode::GBP)];
+    let period = TaxPeriod::new(date(2022, 1, 1), date(2022, 4, 1));
+    let mut tax_return = TaxReturn::prepare(&table, &period, &journals, &[]).unwrap();
+    tax_return.file(date(2022, 5, 1), &mut journals);
+
+    assert!(tax_return.period().is_filed());
+    assert_eq!(journals[0].locked_until(), Some(date(2022, 4, 1)));
+    let result = journals[0].add_transaction(invoice(
+        &table,
+        "INV-3",
+        date(2022, 3, 31),
+        date(2022, 5, 2),
+        TaxDirection::Output,
+        10000,
+    ));
+    assert!(matches!(result, Err(Error::PeriodLocked { .. })));
+}
+
+#[test]
+fn test_backdated_entry_is_read_only() {
+    let table = table();
+    let mut journal =
+        Journal::new("general", CurrencyCode::GBP).with_read_only_after(Duration::days(30));
+    let backdated = || {
+        invoice(
+            &table,
+            "INV-5",
+            date(2022, 3, 1),
+            date(2022, 3, 2),
+            TaxDirection::Output,
+            10000,
+        )
+    };
+
+    let result = journal.add_transaction(backdated());
+    assert!(matches!(result, Err(Error::PeriodLocked { .. })));
+    let result = journal.add_transaction_as_of(backdated(), date(2022, 5, 1));
+    assert!(matches!(result, Err(Error::PeriodLocked { .. })));
+    journal
+        .add_transaction_as_of(backdated(), date(2022, 3, 15))
+        .unwrap();
+    assert_eq!(journal.transactions().count(), 1);
+}
+
+#[test]
+fn test_late_entries_are_flagged() {
+    let table = table();
+    let mut journal = Journal::new("general", CurrencyCode::GBP);
+    journal
+        .add_transaction(invoice(
+            &table,
+            "INV-4",
+            date(2022, 3, 20),
+            date(2022, 5, 2),
+            TaxDirection::Output,
+            10000,
+        ))
+        .unwrap();
+
+    let filed = TaxPeriod::new(date(2022, 1, 1), date(2022, 4, 1));
+    let mut filed_return = TaxReturn::prepare(&table, &filed, &[], &[]).unwrap();
+    filed_return.file(date(2022, 5, 1), &mut []);
+    let filed = filed_return.period().clone();
+
+    let period = TaxPeriod::new(date(2022, 4, 1), date(2022, 7, 1));
+    let tax_return = TaxReturn::prepare(&table, &period, &[journal], &[filed]).unwrap();
+
+    assert_eq!(tax_return.lines().count(), 0);
+    let late: Vec<_> = tax_return.late_entries().collect();
+    assert_eq!(late.len(), 1);
+    assert_eq!(late[0].transaction(), &TransactionId::from("INV-4"));
+    assert_eq!(late[0].tax(), &gbp(2000));
+    assert!(tax_return.has_late_entries());
+}
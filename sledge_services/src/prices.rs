@@ -9,9 +9,10 @@ YYYYY
 
 */
 
+use crate::Service;
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
-use sledge_model::{commodity::CommodityId, Service};
+use sledge_model::commodity::CommodityId;
 
 // ------------------------------------------------------------------------------------------------
 // Public Macros
@@ -0,0 +1,503 @@
+/*!
+Provides a portable archive of the complete contents of a data store.
+
+An archive holds every ledger, journal, party, commodity, price, import profile, reconciled
+split, the store permissions and its settings, and is tagged with the [`STORE_SCHEMA_VERSION`] of the store
+that wrote it. Any [`DataStore`] can be exported to an archive and an archive can be imported
+into any other, which is how books are moved between backends or backed up.
+
+An archive is written either as a single JSON document, or as JSON Lines where the first line
+holds the version, settings and permissions and each following line holds one record, for
+example `{"ledger":{...}}`.
+
+An archive can be read by a store whose schema version is compatible with the one that wrote
+it, in the semver sense; so an archive from `0.1.0` can be read by `0.1.2` but not by `0.2.0`.
+
+# Example
+
+```rust,no_run
+use sledge_store::archive::{read_archive, write_archive, ArchiveFormat, StoreArchive};
+use sledge_store::{create_datastore, get_current_datastore};
+use url::Url;
+
+let from = get_current_datastore(&Url::parse("fstore:///home/me/books").unwrap()).unwrap();
+let mut buffer = Vec::new();
+write_archive(&mut buffer, &StoreArchive::export_from(&*from).unwrap(), ArchiveFormat::JsonLines)
+    .unwrap();
+
+let into = create_datastore(
+    &Url::parse("fstore:///home/me/copy").unwrap(),
+    &Default::default(),
+)
+.unwrap();
+read_archive(buffer.as_slice(), ArchiveFormat::JsonLines)
+    .unwrap()
+    .import_into(&*into)
+    .unwrap();
+```
+
+*/
+
+use crate::error::{archive_file_error, archive_version, store_not_empty, Error};
+use crate::query::Query;
+use crate::{DataStore, Entity, StorePermissions, StoreSettings, STORE_SCHEMA_VERSION};
+use chrono::{DateTime, Utc};
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sledge_model::{
+    commodity::{RateRecord, Security},
+    import::CsvProfile,
+    journal::{Journal, Reconciled},
+    ledger::Ledger,
+    party::Party,
+};
+use std::fmt::Display;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::str::FromStr;
+use tracing::trace;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    #[default]
+    Json,
+    JsonLines,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StoreArchive {
+    version: Version,
+    exported: DateTime<Utc>,
+    settings: StoreSettings,
+    permissions: StorePermissions,
+    ledgers: Vec<Ledger>,
+    journals: Vec<Journal>,
+    parties: Vec<Party>,
+    commodities: Vec<Security>,
+    prices: Vec<RateRecord>,
+    import_profiles: Vec<CsvProfile>,
+    #[serde(default)]
+    reconciliations: Vec<Reconciled>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+pub fn write_archive<W>(
+    writer: W,
+    archive: &StoreArchive,
+    format: ArchiveFormat,
+) -> Result<(), Error>
+where
+    W: Write,
+{
+    match format {
+        ArchiveFormat::Json => write_json(writer, archive),
+        ArchiveFormat::JsonLines => write_json_lines(writer, archive),
+    }
+}
+
+pub fn read_archive<R>(reader: R, format: ArchiveFormat) -> Result<StoreArchive, Error>
+where
+    R: BufRead,
+{
+    match format {
+        ArchiveFormat::Json => read_json(reader),
+        ArchiveFormat::JsonLines => read_json_lines(reader),
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Serialize)]
+struct HeaderRef<'a> {
+    version: &'a Version,
+    exported: DateTime<Utc>,
+    settings: &'a StoreSettings,
+    permissions: &'a StorePermissions,
+}
+
+#[derive(Deserialize)]
+struct Header {
+    version: Version,
+    exported: DateTime<Utc>,
+    settings: StoreSettings,
+    permissions: StorePermissions,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum RecordRef<'a> {
+    Ledger(&'a Ledger),
+    Journal(&'a Journal),
+    Party(&'a Party),
+    Commodity(&'a Security),
+    Price(&'a RateRecord),
+    ImportProfile(&'a CsvProfile),
+    Reconciliation(&'a Reconciled),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Record {
+    Ledger(Ledger),
+    Journal(Journal),
+    Party(Party),
+    Commodity(Security),
+    Price(RateRecord),
+    ImportProfile(CsvProfile),
+    Reconciliation(Reconciled),
+}
+
+const JSON_LINES_EXTENSION: &str = "jsonl";
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl Display for ArchiveFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Json => write!(f, "json"),
+            Self::JsonLines => write!(f, "jsonl"),
+        }
+    }
+}
+
+impl FromStr for ArchiveFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Self::Json),
+            "jsonl" | "json-lines" => Ok(Self::JsonLines),
+            _ => Err(format!(
+                "Unknown archive format {:?}, expected json or jsonl",
+                s
+            )),
+        }
+    }
+}
+
+impl ArchiveFormat {
+    ///
+    /// JSON Lines for a file with the `.jsonl` extension, otherwise JSON.
+    ///
+    pub fn from_path<P>(path: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        match path.as_ref().extension().and_then(|ext| ext.to_str()) {
+            Some(JSON_LINES_EXTENSION) => Self::JsonLines,
+            _ => Self::Json,
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl StoreArchive {
+    ///
+    /// Read the complete contents of `store`.
+    ///
+    pub fn export_from(store: &dyn DataStore) -> Result<Self, Error> {
+        let archive = Self {
+            version: STORE_SCHEMA_VERSION,
+            exported: Utc::now(),
+            settings: store.settings()?,
+            permissions: store.permissions()?,
+            ledgers: store.ledgers()?.list(&Query::default())?.into_items(),
+            journals: store.journals()?.list(&Query::default())?.into_items(),
+            parties: store.parties()?.list(&Query::default())?.into_items(),
+            commodities: store.commodities()?.list()?,
+            prices: store.prices()?.list()?,
+            import_profiles: store
+                .import_profiles()?
+                .list(&Query::default())?
+                .into_items(),
+            reconciliations: store.reconciliations()?.list()?,
+        };
+        trace!(
+            "Exported {} ledgers, {} journals, {} parties, {} commodities, {} prices, {} import profiles, {} reconciliations",
+            archive.ledgers.len(),
+            archive.journals.len(),
+            archive.parties.len(),
+            archive.commodities.len(),
+            archive.prices.len(),
+            archive.import_profiles.len(),
+            archive.reconciliations.len()
+        );
+        Ok(archive)
+    }
+
+    ///
+    /// Write the complete contents of the archive into `store`, which must be empty. The
+    /// entities and records are written in a single unit of work, so that a failed import
+    /// leaves the store empty, and the settings and permissions only once that has been
+    /// committed. The store keeps its own schema version in its settings.
+    ///
+    pub fn import_into(self, store: &dyn DataStore) -> Result<(), Error> {
+        check_version(&self.version)?;
+        check_empty(store)?;
+
+        let work = store.begin()?;
+        let parties = work.parties()?;
+        for party in self.parties {
+            let id = party.identifier().clone();
+            parties.create_with_id(party, id)?;
+        }
+        work.commodities()?.append(self.commodities)?;
+        work.prices()?.append(self.prices)?;
+        let ledgers = work.ledgers()?;
+        for ledger in self.ledgers {
+            let id = ledger.identifier().clone();
+            ledgers.create_with_id(ledger, id)?;
+        }
+        let journals = work.journals()?;
+        for journal in self.journals {
+            let id = journal.identifier().clone();
+            journals.create_with_id(journal, id)?;
+        }
+        let import_profiles = work.import_profiles()?;
+        for profile in self.import_profiles {
+            let id = profile.identifier().clone();
+            import_profiles.create_with_id(profile, id)?;
+        }
+        work.reconciliations()?.append(self.reconciliations)?;
+        work.commit()?;
+
+        store.update_settings(&StoreSettings {
+            version: STORE_SCHEMA_VERSION,
+            ..self.settings
+        })?;
+        store.update_permissions(&self.permissions)
+    }
+
+    pub fn version(&self) -> &Version {
+        &self.version
+    }
+
+    pub fn exported(&self) -> DateTime<Utc> {
+        self.exported
+    }
+
+    pub fn settings(&self) -> &StoreSettings {
+        &self.settings
+    }
+
+    pub fn permissions(&self) -> &StorePermissions {
+        &self.permissions
+    }
+
+    pub fn ledgers(&self) -> impl Iterator<Item = &Ledger> {
+        self.ledgers.iter()
+    }
+
+    pub fn journals(&self) -> impl Iterator<Item = &Journal> {
+        self.journals.iter()
+    }
+
+    pub fn parties(&self) -> impl Iterator<Item = &Party> {
+        self.parties.iter()
+    }
+
+    pub fn commodities(&self) -> impl Iterator<Item = &Security> {
+        self.commodities.iter()
+    }
+
+    pub fn prices(&self) -> impl Iterator<Item = &RateRecord> {
+        self.prices.iter()
+    }
+
+    pub fn import_profiles(&self) -> impl Iterator<Item = &CsvProfile> {
+        self.import_profiles.iter()
+    }
+
+    pub fn reconciliations(&self) -> impl Iterator<Item = &Reconciled> {
+        self.reconciliations.iter()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+fn check_version(version: &Version) -> Result<(), Error> {
+    let compatible = VersionReq::parse(&format!("^{}", version))
+        .map(|requirement| requirement.matches(&STORE_SCHEMA_VERSION))
+        .unwrap_or_default();
+    if compatible {
+        Ok(())
+    } else {
+        Err(archive_version(version.clone()))
+    }
+}
+
+///
+/// Only the entities and records are checked; the settings and permissions of a new store are
+/// its defaults, which the import replaces.
+///
+fn check_empty(store: &dyn DataStore) -> Result<(), Error> {
+    let first = Query::default().with_limit(1);
+    let holds = [
+        ("ledgers", store.ledgers()?.list(&first)?.items().is_empty()),
+        (
+            "journals",
+            store.journals()?.list(&first)?.items().is_empty(),
+        ),
+        ("parties", store.parties()?.list(&first)?.items().is_empty()),
+        (
+            "import profiles",
+            store.import_profiles()?.list(&first)?.items().is_empty(),
+        ),
+        ("commodities", store.commodities()?.list()?.is_empty()),
+        ("prices", store.prices()?.list()?.is_empty()),
+        (
+            "reconciliations",
+            store.reconciliations()?.list()?.is_empty(),
+        ),
+    ];
+    match holds.iter().find(|(_, empty)| !empty) {
+        Some((kind, _)) => Err(store_not_empty(*kind)),
+        None => Ok(()),
+    }
+}
+
+///
+/// The version is checked before the rest of the archive is read, so that an archive from an
+/// incompatible schema is reported as such rather than as a syntax error.
+///
+fn versioned<T>(value: Value) -> Result<T, Error>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    let version = value
+        .get("version")
+        .cloned()
+        .ok_or_else(|| archive_file_error(serde::de::Error::missing_field("version")))?;
+    check_version(&serde_json::from_value(version).map_err(archive_file_error)?)?;
+    serde_json::from_value(value).map_err(archive_file_error)
+}
+
+fn write_json<W>(writer: W, archive: &StoreArchive) -> Result<(), Error>
+where
+    W: Write,
+{
+    serde_json::to_writer_pretty(writer, archive).map_err(archive_file_error)
+}
+
+fn read_json<R>(reader: R) -> Result<StoreArchive, Error>
+where
+    R: BufRead,
+{
+    versioned(serde_json::from_reader(reader).map_err(archive_file_error)?)
+}
+
+fn write_json_lines<W>(mut writer: W, archive: &StoreArchive) -> Result<(), Error>
+where
+    W: Write,
+{
+    write_line(
+        &mut writer,
+        &HeaderRef {
+            version: &archive.version,
+            exported: archive.exported,
+            settings: &archive.settings,
+            permissions: &archive.permissions,
+        },
+    )?;
+    for party in &archive.parties {
+        write_line(&mut writer, &RecordRef::Party(party))?;
+    }
+    for commodity in &archive.commodities {
+        write_line(&mut writer, &RecordRef::Commodity(commodity))?;
+    }
+    for price in &archive.prices {
+        write_line(&mut writer, &RecordRef::Price(price))?;
+    }
+    for ledger in &archive.ledgers {
+        write_line(&mut writer, &RecordRef::Ledger(ledger))?;
+    }
+    for journal in &archive.journals {
+        write_line(&mut writer, &RecordRef::Journal(journal))?;
+    }
+    for profile in &archive.import_profiles {
+        write_line(&mut writer, &RecordRef::ImportProfile(profile))?;
+    }
+    for reconciled in &archive.reconciliations {
+        write_line(&mut writer, &RecordRef::Reconciliation(reconciled))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_line<W, T>(writer: &mut W, value: &T) -> Result<(), Error>
+where
+    W: Write,
+    T: Serialize,
+{
+    serde_json::to_writer(&mut *writer, value).map_err(archive_file_error)?;
+    writer.write_all(b"\n")?;
+    Ok(())
+}
+
+fn read_json_lines<R>(reader: R) -> Result<StoreArchive, Error>
+where
+    R: BufRead,
+{
+    let mut lines = reader.lines();
+    let header = match lines.next() {
+        Some(line) => line?,
+        None => {
+            return Err(archive_file_error(serde::de::Error::missing_field(
+                "version",
+            )))
+        }
+    };
+    let header: Header = versioned(serde_json::from_str(&header).map_err(archive_file_error)?)?;
+    let mut archive = StoreArchive {
+        version: header.version,
+        exported: header.exported,
+        settings: header.settings,
+        permissions: header.permissions,
+        ledgers: Default::default(),
+        journals: Default::default(),
+        parties: Default::default(),
+        commodities: Default::default(),
+        prices: Default::default(),
+        import_profiles: Default::default(),
+        reconciliations: Default::default(),
+    };
+    for line in lines {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line).map_err(archive_file_error)? {
+            Record::Ledger(ledger) => archive.ledgers.push(ledger),
+            Record::Journal(journal) => archive.journals.push(journal),
+            Record::Party(party) => archive.parties.push(party),
+            Record::Commodity(commodity) => archive.commodities.push(commodity),
+            Record::Price(price) => archive.prices.push(price),
+            Record::ImportProfile(profile) => archive.import_profiles.push(profile),
+            Record::Reconciliation(reconciled) => archive.reconciliations.push(reconciled),
+        }
+    }
+    Ok(archive)
+}
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
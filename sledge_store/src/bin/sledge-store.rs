@@ -1,8 +1,13 @@
 use sledge_config::error::Error as ConfigError;
 use sledge_config::get_config;
+use sledge_store::archive::{read_archive, write_archive, ArchiveFormat, StoreArchive};
 use sledge_store::error::Error as StoreError;
-use sledge_store::{create_datastore, get_current_datastore};
+use sledge_store::{create_datastore, get_current_datastore, DataStore};
 use std::fmt::Display;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::PathBuf;
+use std::sync::Arc;
 use structopt::StructOpt;
 use tracing::info;
 use tracing::subscriber::SetGlobalDefaultError;
@@ -32,6 +37,30 @@ enum Command {
     New,
     /// Verify an existing thing
     Verify,
+    /// Export the entire store into a single archive
+    Export {
+        /// The archive file to write, or standard output if not given
+        #[structopt(long, short, parse(from_os_str))]
+        output: Option<PathBuf>,
+        /// The archive format, `json` or `jsonl`; taken from the file extension if not given
+        #[structopt(long, short)]
+        format: Option<ArchiveFormat>,
+        /// The store to export, instead of the configured one
+        #[structopt(long)]
+        store: Option<Url>,
+    },
+    /// Import an archive into a store, creating the store if it does not exist
+    Import {
+        /// The archive file to read
+        #[structopt(parse(from_os_str))]
+        input: PathBuf,
+        /// The archive format, `json` or `jsonl`; taken from the file extension if not given
+        #[structopt(long, short)]
+        format: Option<ArchiveFormat>,
+        /// The store to import into, instead of the configured one
+        #[structopt(long)]
+        store: Option<Url>,
+    },
 }
 
 // ------------------------------------------------------------------------------------------------
@@ -149,6 +178,16 @@ fn main() -> Result<(), ToolError> {
     match args.cmd {
         Command::New => do_init(connection_uri),
         Command::Verify => do_verify(connection_uri),
+        Command::Export {
+            output,
+            format,
+            store,
+        } => do_export(store.as_ref().unwrap_or(connection_uri), output, format),
+        Command::Import {
+            input,
+            format,
+            store,
+        } => do_import(store.as_ref().unwrap_or(connection_uri), input, format),
     }
 }
 
@@ -212,3 +251,57 @@ fn do_verify(connection_uri: &Url) -> Result<(), ToolError> {
 
     Ok(())
 }
+
+fn do_export(
+    connection_uri: &Url,
+    output: Option<PathBuf>,
+    format: Option<ArchiveFormat>,
+) -> Result<(), ToolError> {
+    info!(
+        "Exporting data store using connection: <{}>",
+        connection_uri
+    );
+
+    let store = get_current_datastore(connection_uri)?;
+    let archive = StoreArchive::export_from(&*store)?;
+
+    match output {
+        Some(path) => {
+            let format = format.unwrap_or_else(|| ArchiveFormat::from_path(&path));
+            let file = File::create(&path).map_err(StoreError::from)?;
+            write_archive(BufWriter::new(file), &archive, format)?;
+        }
+        None => write_archive(
+            std::io::stdout().lock(),
+            &archive,
+            format.unwrap_or_default(),
+        )?,
+    }
+
+    Ok(())
+}
+
+fn do_import(
+    connection_uri: &Url,
+    input: PathBuf,
+    format: Option<ArchiveFormat>,
+) -> Result<(), ToolError> {
+    info!(
+        "Importing {:?} into data store using connection: <{}>",
+        input, connection_uri
+    );
+
+    let format = format.unwrap_or_else(|| ArchiveFormat::from_path(&input));
+    let file = File::open(&input).map_err(StoreError::from)?;
+    let archive = read_archive(BufReader::new(file), format)?;
+
+    let store: Arc<dyn DataStore> = match get_current_datastore(connection_uri) {
+        Err(StoreError::StoreDoesNotExist { .. }) => {
+            create_datastore(connection_uri, &Default::default())?
+        }
+        store => store?,
+    };
+    archive.import_into(&*store)?;
+
+    Ok(())
+}
@@ -4,6 +4,7 @@ functions.
 
  */
 
+use semver::Version;
 use serde_json::Error as JsonError;
 use std::fmt::{Debug, Display};
 use url::Url;
@@ -33,6 +34,38 @@ pub enum Error {
     SettingsFileError {
         source: JsonError,
     },
+    EntityExists {
+        id: String,
+    },
+    EntityNotFound {
+        id: String,
+    },
+    EntityFileError {
+        source: JsonError,
+    },
+    /// The archive was written with a store schema this version cannot read.
+    ArchiveVersion {
+        version: Version,
+    },
+    ArchiveFileError {
+        source: JsonError,
+    },
+    /// An archive can only be imported into a store that holds nothing of the kind named.
+    StoreNotEmpty {
+        holds: String,
+    },
+    /// An error was signaled by the database of a `sqlite` store.
+    SqlError {
+        source: rusqlite::Error,
+    },
+    /// The cursor could not be read, or was returned by a query with a different sort.
+    InvalidCursor {
+        cursor: String,
+    },
+    /// The filter cannot be applied to the kind of entity being listed.
+    UnsupportedFilter {
+        filter: String,
+    },
 }
 
 ///
@@ -56,10 +89,70 @@ pub fn settings_file_error(source: JsonError) -> Error {
     Error::SettingsFileError { source }
 }
 
+/// Construct an Error from the provided source.
+#[inline]
+pub fn entity_file_error(source: JsonError) -> Error {
+    Error::EntityFileError { source }
+}
+
+/// Construct an Error from the provided source.
+#[inline]
+pub fn archive_file_error(source: JsonError) -> Error {
+    Error::ArchiveFileError { source }
+}
+
+/// Construct an Error from the provided source.
+#[inline]
+pub fn sql_error(source: rusqlite::Error) -> Error {
+    Error::SqlError { source }
+}
+
+/// Construct an Error from the provided version.
+#[inline]
+pub fn archive_version(version: Version) -> Error {
+    Error::ArchiveVersion { version }
+}
+
+/// Construct an Error from the provided kind of content.
+#[inline]
+pub fn store_not_empty<S: Into<String>>(holds: S) -> Error {
+    Error::StoreNotEmpty {
+        holds: holds.into(),
+    }
+}
+
+/// Construct an Error from the provided identifier.
+#[inline]
+pub fn entity_exists<S: Into<String>>(id: S) -> Error {
+    Error::EntityExists { id: id.into() }
+}
+
+/// Construct an Error from the provided identifier.
+#[inline]
+pub fn entity_not_found<S: Into<String>>(id: S) -> Error {
+    Error::EntityNotFound { id: id.into() }
+}
+
+/// Construct an Error from the provided cursor.
+#[inline]
+pub fn invalid_cursor<S: Into<String>>(cursor: S) -> Error {
+    Error::InvalidCursor {
+        cursor: cursor.into(),
+    }
+}
+
+/// Construct an Error from the provided filter.
+#[inline]
+pub fn unsupported_filter<S: Into<String>>(filter: S) -> Error {
+    Error::UnsupportedFilter {
+        filter: filter.into(),
+    }
+}
+
 /// Construct an Error from the provided path.
 #[inline]
 pub fn unknown_store_scheme(uri: Url) -> Error {
-    Error::StoreExists { uri }
+    Error::UnknownStoreSchema { uri }
 }
 
 /// Construct an Error from the provided path.
@@ -97,6 +190,33 @@ impl Display for Error {
                     "Could not read or write the settings file; error: {:?}",
                     source
                 ),
+                Error::EntityExists { id } =>
+                    format!("An entity with the identifier {:?} already exists", id),
+                Error::EntityNotFound { id } =>
+                    format!("No entity with the identifier {:?} was found", id),
+                Error::EntityFileError { source } => format!(
+                    "Could not read or write the entity file; error: {:?}",
+                    source
+                ),
+                Error::ArchiveVersion { version } => format!(
+                    "The archive schema version {} is not supported by store schema version {}",
+                    version,
+                    crate::STORE_SCHEMA_VERSION
+                ),
+                Error::ArchiveFileError { source } =>
+                    format!("Could not read or write the archive; error: {:?}", source),
+                Error::StoreNotEmpty { holds } => format!(
+                    "An archive can only be imported into an empty store, this one holds {}",
+                    holds
+                ),
+                Error::SqlError { source } => format!(
+                    "An error occurred in the store database; error: {:?}",
+                    source
+                ),
+                Error::InvalidCursor { cursor } =>
+                    format!("The cursor {:?} is not valid for this query", cursor),
+                Error::UnsupportedFilter { filter } =>
+                    format!("The filter {} cannot be applied to these entities", filter),
             }
         )
     }
@@ -107,6 +227,9 @@ impl std::error::Error for Error {
         match self {
             Error::IoError { source } => Some(source),
             Error::SettingsFileError { source } => Some(source),
+            Error::EntityFileError { source } => Some(source),
+            Error::ArchiveFileError { source } => Some(source),
+            Error::SqlError { source } => Some(source),
             _ => None,
         }
     }
@@ -123,3 +246,9 @@ impl From<JsonError> for Error {
         settings_file_error(source)
     }
 }
+
+impl From<rusqlite::Error> for Error {
+    fn from(source: rusqlite::Error) -> Self {
+        sql_error(source)
+    }
+}
@@ -0,0 +1,212 @@
+/*!
+Stores entities as one JSON file per entity in a sub-directory of the store.
+
+The file name is the entity's identifier with any character other than an ASCII letter, digit,
+`-`, or `_` written as `%` and its hexadecimal UTF-8 bytes, so that any identifier may be used.
+
+*/
+
+use super::wal::{create_file, replace_file, write_locked};
+use crate::error::{entity_exists, entity_file_error, entity_not_found, Error};
+use crate::query::{select_entities, Page, Query};
+use crate::{Entity, EntityStore};
+use serde::{de::DeserializeOwned, Serialize};
+use std::fmt::{Display, Write};
+use std::fs;
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use tracing::trace;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Debug)]
+pub(super) struct FsEntityStore<I, E> {
+    root_path: PathBuf,
+    path: PathBuf,
+    entity: PhantomData<fn() -> (I, E)>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+///
+/// The name of the file, within its sub-directory, that holds the entity with `id`.
+///
+pub(super) fn entity_file_name(id: &str) -> String {
+    format!("{}.{}", file_name(id), ENTITY_FILE_EXTENSION)
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+const ENTITY_FILE_EXTENSION: &str = "json";
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl<I, E> FsEntityStore<I, E>
+where
+    I: Display + Eq + Hash,
+    E: Entity<I> + Serialize + DeserializeOwned,
+{
+    pub(super) fn open<P>(root_path: P, sub_dir: &str) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let path = root_path.as_ref().join(sub_dir);
+        fs::create_dir_all(&path)?;
+        Ok(Self {
+            root_path: root_path.as_ref().to_path_buf(),
+            path,
+            entity: PhantomData,
+        })
+    }
+
+    ///
+    /// Write a new entity under its own identifier, without taking ownership of it.
+    ///
+    pub(super) fn insert(&self, entity: &E) -> Result<(), Error> {
+        self.write_new(entity, entity.identifier())
+    }
+
+    ///
+    /// Read the file for an entity as some other type, such as a part of the entity.
+    ///
+    pub(super) fn read_as<T>(&self, id: &I) -> Result<Option<T>, Error>
+    where
+        T: DeserializeOwned,
+    {
+        let file_path = self.file_path(id);
+        if file_path.is_file() {
+            trace!("Reading part of entity from {:?}", file_path);
+            let file = fs::File::open(file_path)?;
+            Ok(Some(
+                serde_json::from_reader(file).map_err(entity_file_error)?,
+            ))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn file_path(&self, id: &I) -> PathBuf {
+        self.path.join(entity_file_name(&id.to_string()))
+    }
+
+    fn read(&self, file_path: &Path) -> Result<E, Error> {
+        trace!("Reading entity from {:?}", file_path);
+        let file = fs::File::open(file_path)?;
+        serde_json::from_reader(file).map_err(entity_file_error)
+    }
+
+    ///
+    /// Write an entity only if no file exists for its identifier; the check and the file's
+    /// creation are a single step so that of two concurrent creates only one succeeds.
+    ///
+    fn write_new(&self, entity: &E, id: &I) -> Result<(), Error> {
+        let file_path = self.file_path(id);
+        trace!("Writing new entity to {:?}", file_path);
+        let contents = serde_json::to_vec_pretty(entity).map_err(entity_file_error)?;
+        if write_locked(&self.root_path, || create_file(&file_path, &contents))? {
+            Ok(())
+        } else {
+            Err(entity_exists(id.to_string()))
+        }
+    }
+}
+
+impl<I, E> EntityStore<I, E> for FsEntityStore<I, E>
+where
+    I: Clone + Display + Eq + Hash,
+    E: Entity<I> + Serialize + DeserializeOwned,
+{
+    fn create(&self, entity: E) -> Result<I, Error> {
+        let id = entity.identifier().clone();
+        self.create_with_id(entity, id.clone())?;
+        Ok(id)
+    }
+
+    fn create_with_id(&self, entity: E, id: I) -> Result<(), Error> {
+        self.write_new(&entity, &id)
+    }
+
+    fn list(&self, query: &Query) -> Result<Page<E>, Error> {
+        let mut entities = Vec::new();
+        for entry in fs::read_dir(&self.path)? {
+            let file_path = entry?.path();
+            if file_path.is_file()
+                && file_path.extension().and_then(|ext| ext.to_str()) == Some(ENTITY_FILE_EXTENSION)
+            {
+                entities.push(self.read(&file_path)?);
+            }
+        }
+        select_entities(query, entities)
+    }
+
+    fn get_by_id(&self, id: &I) -> Result<Option<E>, Error> {
+        let file_path = self.file_path(id);
+        if file_path.is_file() {
+            Ok(Some(self.read(&file_path)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn update(&self, entity: E) -> Result<(), Error> {
+        let id = entity.identifier();
+        let file_path = self.file_path(id);
+        let contents = serde_json::to_vec_pretty(&entity).map_err(entity_file_error)?;
+        write_locked(&self.root_path, || {
+            if file_path.is_file() {
+                trace!("Writing entity to {:?}", file_path);
+                replace_file(&file_path, &contents)
+            } else {
+                Err(entity_not_found(id.to_string()))
+            }
+        })
+    }
+
+    fn delete(&self, id: &I) -> Result<(), Error> {
+        let file_path = self.file_path(id);
+        write_locked(&self.root_path, || {
+            if file_path.is_file() {
+                trace!("Removing entity file {:?}", file_path);
+                Ok(fs::remove_file(&file_path)?)
+            } else {
+                Err(entity_not_found(id.to_string()))
+            }
+        })
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+fn file_name(id: &str) -> String {
+    let mut name = String::with_capacity(id.len());
+    for c in id.chars() {
+        if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+            name.push(c);
+        } else {
+            let mut bytes = [0; 4];
+            for byte in c.encode_utf8(&mut bytes).bytes() {
+                let _ = write!(name, "%{:02X}", byte);
+            }
+        }
+    }
+    name
+}
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
@@ -1,17 +1,14 @@
 /*!
-One-line description.
-
-More detailed description, with
-
-# Example
-
-YYYYY
+Stores journals, one JSON file per journal, including its transactions, in the `journals/`
+sub-directory of the store.
 
 */
 
 use crate::error::Error;
+use crate::fs::entity::FsEntityStore;
 use sledge_model::journal::Journal;
 use std::path::Path;
+use tracing::trace;
 
 // ------------------------------------------------------------------------------------------------
 // Public Macros
@@ -21,6 +18,10 @@ use std::path::Path;
 // Public Types
 // ------------------------------------------------------------------------------------------------
 
+pub(super) const JOURNAL_SUB_DIR: &str = "journals/";
+
+pub(super) type FsJournalStore = FsEntityStore<String, Journal>;
+
 // ------------------------------------------------------------------------------------------------
 // Public Functions
 // ------------------------------------------------------------------------------------------------
@@ -29,7 +30,16 @@ pub(super) fn create_journals<P>(root_path: P, journals: &[Journal]) -> Result<(
 where
     P: AsRef<Path>,
 {
-    todo!()
+    trace!(
+        "Create {} journals in sub-directory {}",
+        journals.len(),
+        JOURNAL_SUB_DIR
+    );
+    let store = FsJournalStore::open(root_path, JOURNAL_SUB_DIR)?;
+    for entity in journals {
+        store.insert(entity)?;
+    }
+    Ok(())
 }
 
 // ------------------------------------------------------------------------------------------------
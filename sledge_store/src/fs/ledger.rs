@@ -1,16 +1,11 @@
 /*!
-One-line description.
-
-More detailed description, with
-
-# Example
-
-YYYYY
+Stores ledgers, one JSON file per ledger kind in the `ledgers/` sub-directory of the store.
 
 */
 
 use crate::error::Error;
-use sledge_model::ledger::Ledger;
+use crate::fs::entity::FsEntityStore;
+use sledge_model::ledger::{Ledger, LedgerKind};
 use std::path::Path;
 use tracing::trace;
 
@@ -24,6 +19,8 @@ use tracing::trace;
 
 pub(super) const LEDGER_SUB_DIR: &str = "ledgers/";
 
+pub(super) type FsLedgerStore = FsEntityStore<LedgerKind, Ledger>;
+
 // ------------------------------------------------------------------------------------------------
 // Public Functions
 // ------------------------------------------------------------------------------------------------
@@ -34,11 +31,14 @@ where
 {
     trace!(
         "Create {} ledgers in sub-directory {}",
-        content.ledgers.len(),
+        ledgers.len(),
         LEDGER_SUB_DIR
     );
-    let path = root_path.as_ref().join(LEDGER_SUB_DIR);
-    fs::create_dir_all(&path)?;
+    let store = FsLedgerStore::open(root_path, LEDGER_SUB_DIR)?;
+    for entity in ledgers {
+        store.insert(entity)?;
+    }
+    Ok(())
 }
 
 // ------------------------------------------------------------------------------------------------
@@ -9,17 +9,31 @@ YYYYY
 
 */
 
-use self::settings::{
-    read_settings_or_default, write_settings, FsStoreSettings, FS_STORE_SETTINGS_FILE,
+use self::entity::entity_file_name;
+use self::permissions::{read_permissions_or_default, write_permissions};
+use self::settings::{read_settings, write_settings, FS_STORE_SETTINGS_FILE};
+use self::wal::{recover, LogEntry, WriteAheadLog};
+use crate::error::{
+    entity_file_error, entity_not_found, store_does_not_exist, store_exists, Error,
 };
-use crate::error::{store_does_not_exist, store_exists, Error};
-use crate::fs::journal::create_journals;
-use crate::fs::ledger::create_ledgers;
-use crate::CreateDatastoreContents;
-use crate::DataStore;
+use crate::fs::journal::{create_journals, FsJournalStore, JOURNAL_SUB_DIR};
+use crate::fs::ledger::{create_ledgers, FsLedgerStore, LEDGER_SUB_DIR};
+use crate::fs::party::{FsPartyStore, PARTY_SUB_DIR};
+use crate::fs::profile::{FsProfileStore, PROFILE_SUB_DIR};
+use crate::fs::record::{FsRecordStore, COMMODITIES_FILE, PRICES_FILE, RECONCILIATIONS_FILE};
+use crate::query::{select_transactions, JournalTransactions, Page, Query};
+use crate::staged::{ApplyChanges, Change, Changes, StagedUnitOfWork, Table};
+use crate::{
+    CreateDatastoreContents, DataStore, EntityStore, RecordStore, StorePermissions, StoreSettings,
+    UnitOfWork,
+};
+use serde_json::Value;
 use sledge_model::{
-    journal::Journal,
+    commodity::{RateRecord, Security},
+    import::CsvProfile,
+    journal::{Journal, Reconciled, Transaction},
     ledger::{Ledger, LedgerKind},
+    party::{Party, PartyId},
 };
 use std::fs;
 use std::path::PathBuf;
@@ -36,10 +50,10 @@ use url::Url;
 
 pub(super) const FS_STORE_SCHEME: &str = "fstore";
 
+#[derive(Clone, Debug)]
 pub(super) struct FileSystemStore {
     from_uri: Url,
     root_path: PathBuf,
-    settings: FsStoreSettings,
 }
 
 // ------------------------------------------------------------------------------------------------
@@ -62,10 +76,10 @@ impl DataStore for FileSystemStore {
         if Self::exists(connection_uri) {
             trace!("Opening existing store @ <{}>", connection_uri);
             let root_path = PathBuf::from(connection_uri.path());
+            recover(&root_path)?;
             let store = FileSystemStore {
                 from_uri: connection_uri.clone(),
-                root_path: root_path.clone(),
-                settings: read_settings_or_default(&root_path),
+                root_path,
             };
             Ok(store)
         } else {
@@ -82,7 +96,7 @@ impl DataStore for FileSystemStore {
         Self: Sized,
     {
         let root_path = PathBuf::from(connection_uri.path());
-        [FS_STORE_SETTINGS_FILE, "journals/", "ledgers/"]
+        [FS_STORE_SETTINGS_FILE, JOURNAL_SUB_DIR, LEDGER_SUB_DIR]
             .iter()
             .all(|f| {
                 let mut target = root_path.clone();
@@ -104,24 +118,26 @@ impl DataStore for FileSystemStore {
             fs::create_dir_all(&root_path)?;
 
             trace!("Write store settings file");
-            write_settings(&root_path, &Default::default())?;
-
-            trace!("Creating {} journals", content.journals.len());
-            let mut path = root_path.clone();
-            path.push("journals");
-            fs::create_dir_all(&path)?;
-            if !content.journals.is_empty() {
-                create_journals(&root_path, &content.journals)?;
-            }
+            write_settings(&root_path, &StoreSettings::default())?;
+            write_permissions(&root_path, &StorePermissions::default())?;
 
+            create_journals(&root_path, &content.journals)?;
             create_ledgers(&root_path, &content.ledgers)?;
 
-            trace!("Store @ <{}> created", connection_uri);
             let store = FileSystemStore {
                 from_uri: connection_uri.clone(),
-                root_path: root_path.clone(),
-                settings: read_settings_or_default(&root_path),
+                root_path,
             };
+            if !content.prices.is_empty() {
+                store.prices()?.append(content.prices.clone())?;
+            }
+            if !content.reconciliations.is_empty() {
+                store
+                    .reconciliations()?
+                    .append(content.reconciliations.clone())?;
+            }
+
+            trace!("Store @ <{}> created", connection_uri);
             Ok(store)
         } else {
             error!(
@@ -132,33 +148,165 @@ impl DataStore for FileSystemStore {
         }
     }
 
-    fn ledgers(&self) -> Result<Box<dyn crate::EntityStore<LedgerKind, Ledger>>, Error> {
-        todo!()
+    fn ledgers(&self) -> Result<Box<dyn EntityStore<LedgerKind, Ledger>>, Error> {
+        Ok(Box::new(FsLedgerStore::open(
+            &self.root_path,
+            LEDGER_SUB_DIR,
+        )?))
+    }
+
+    fn journals(&self) -> Result<Box<dyn EntityStore<String, Journal>>, Error> {
+        Ok(Box::new(FsJournalStore::open(
+            &self.root_path,
+            JOURNAL_SUB_DIR,
+        )?))
+    }
+
+    fn import_profiles(&self) -> Result<Box<dyn EntityStore<String, CsvProfile>>, Error> {
+        Ok(Box::new(FsProfileStore::open(
+            &self.root_path,
+            PROFILE_SUB_DIR,
+        )?))
+    }
+
+    fn parties(&self) -> Result<Box<dyn EntityStore<PartyId, Party>>, Error> {
+        Ok(Box::new(FsPartyStore::open(
+            &self.root_path,
+            PARTY_SUB_DIR,
+        )?))
+    }
+
+    fn commodities(&self) -> Result<Box<dyn RecordStore<Security>>, Error> {
+        Ok(Box::new(FsRecordStore::open(
+            &self.root_path,
+            COMMODITIES_FILE,
+        )))
+    }
+
+    fn prices(&self) -> Result<Box<dyn RecordStore<RateRecord>>, Error> {
+        Ok(Box::new(FsRecordStore::open(&self.root_path, PRICES_FILE)))
+    }
+
+    fn reconciliations(&self) -> Result<Box<dyn RecordStore<Reconciled>>, Error> {
+        Ok(Box::new(FsRecordStore::open(
+            &self.root_path,
+            RECONCILIATIONS_FILE,
+        )))
+    }
+
+    fn transactions(&self, journal: &str, query: &Query) -> Result<Page<Transaction>, Error> {
+        let journals = FsJournalStore::open(&self.root_path, JOURNAL_SUB_DIR)?;
+        let JournalTransactions { transactions } = journals
+            .read_as(&journal.to_string())?
+            .ok_or_else(|| entity_not_found(journal))?;
+        select_transactions(query, transactions, || {
+            Ok(self.ledgers()?.list(&Query::default())?.into_items())
+        })
+    }
+
+    fn settings(&self) -> Result<StoreSettings, Error> {
+        read_settings(&self.root_path)
     }
 
-    fn journals(&self) -> Result<Box<dyn crate::EntityStore<String, Journal>>, Error> {
-        todo!()
+    fn update_settings(&self, settings: &StoreSettings) -> Result<(), Error> {
+        write_settings(&self.root_path, settings)
     }
 
-    fn disconnect(self) -> Result<(), Error> {
+    fn permissions(&self) -> Result<StorePermissions, Error> {
+        Ok(read_permissions_or_default(&self.root_path))
+    }
+
+    fn update_permissions(&self, permissions: &StorePermissions) -> Result<(), Error> {
+        write_permissions(&self.root_path, permissions)
+    }
+
+    fn begin(&self) -> Result<Box<dyn UnitOfWork>, Error> {
+        Ok(Box::new(StagedUnitOfWork::new(self.clone())))
+    }
+
+    fn disconnect(&self) -> Result<(), Error> {
         Ok(())
     }
 }
 
+impl ApplyChanges for FileSystemStore {
+    fn apply(&self, changes: Changes) -> Result<(), Error> {
+        let wal = WriteAheadLog::claim(&self.root_path)?;
+        changes.validate(|table, id| Ok(self.root_path.join(entity_path(table, id)).is_file()))?;
+        let mut entries = Vec::new();
+        for ((table, id), change) in changes.entities {
+            entries.push(LogEntry {
+                path: entity_path(table, &id),
+                contents: match change {
+                    Change::Create(value) | Change::Update(value) => {
+                        Some(serde_json::to_string_pretty(&value).map_err(entity_file_error)?)
+                    }
+                    Change::Delete => None,
+                },
+            });
+        }
+        for (table, values) in changes.records {
+            let file_name = record_file_name(table);
+            let mut records = FsRecordStore::<Value>::open(&self.root_path, file_name).list()?;
+            records.extend(values);
+            entries.push(LogEntry {
+                path: file_name.to_string(),
+                contents: Some(serde_json::to_string_pretty(&records).map_err(entity_file_error)?),
+            });
+        }
+        wal.commit(entries)
+    }
+}
+
 // ------------------------------------------------------------------------------------------------
 // Private Functions
 // ------------------------------------------------------------------------------------------------
 
+///
+/// The path, relative to the root of the store, of the file for an entity.
+///
+fn entity_path(table: Table, id: &str) -> String {
+    let sub_dir = match table {
+        Table::Ledgers => LEDGER_SUB_DIR,
+        Table::Journals => JOURNAL_SUB_DIR,
+        Table::ImportProfiles => PROFILE_SUB_DIR,
+        Table::Parties => PARTY_SUB_DIR,
+        Table::Commodities | Table::Prices | Table::Reconciliations => {
+            unreachable!("records are not stored as entities")
+        }
+    };
+    format!("{}{}", sub_dir, entity_file_name(id))
+}
+
+fn record_file_name(table: Table) -> &'static str {
+    match table {
+        Table::Commodities => COMMODITIES_FILE,
+        Table::Prices => PRICES_FILE,
+        Table::Reconciliations => RECONCILIATIONS_FILE,
+        _ => unreachable!("entities are not stored as records"),
+    }
+}
+
 // ------------------------------------------------------------------------------------------------
 // Modules
 // ------------------------------------------------------------------------------------------------
 
 pub mod customer;
 
+mod entity;
+
 pub mod journal;
 
 pub mod ledger;
 
+pub mod party;
+
 pub mod permissions;
 
+pub mod profile;
+
+mod record;
+
 pub mod settings;
+
+mod wal;
@@ -0,0 +1,39 @@
+/*!
+Stores parties, one JSON file per party in the `parties/` sub-directory of the store.
+
+*/
+
+use crate::fs::entity::FsEntityStore;
+use sledge_model::party::{Party, PartyId};
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+pub(super) const PARTY_SUB_DIR: &str = "parties/";
+
+pub(super) type FsPartyStore = FsEntityStore<PartyId, Party>;
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
@@ -9,15 +9,9 @@ YYYYY
 
 */
 
-use crate::error::Error;
-use chrono::{DateTime, Utc};
-use semver::Version;
-use serde::{Deserialize, Serialize};
-use sledge_model::audit::{Action, Resource, RoleId};
-use std::{
-    collections::{HashMap, HashSet},
-    path::Path,
-};
+use super::wal::{replace_file, write_locked};
+use crate::{error::Error, StorePermissions};
+use std::path::Path;
 use tracing::{trace, warn};
 
 // ------------------------------------------------------------------------------------------------
@@ -30,35 +24,27 @@ use tracing::{trace, warn};
 
 pub(super) const FS_STORE_PERMISSIONS_FILE: &str = "permissions.json";
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
-pub(super) struct FsStorePermissions {
-    version: Version,
-    created: DateTime<Utc>,
-    roles: HashMap<RoleId, HashMap<Resource, HashSet<Action>>>,
-    users: HashMap<UserId, HashSet<RoleId>>,
-}
-
 // ------------------------------------------------------------------------------------------------
 // Public Functions
 // ------------------------------------------------------------------------------------------------
 
-pub(super) fn read_settings<P>(root_path: P) -> Result<FsStoreSettings, Error>
+pub(super) fn read_permissions<P>(root_path: P) -> Result<StorePermissions, Error>
 where
     P: AsRef<Path>,
 {
     let file_path = root_path.as_ref().join(FS_STORE_PERMISSIONS_FILE);
     trace!("Reading store permissions from {:?}", file_path);
     let file = std::fs::File::options().read(true).open(file_path)?;
-    let settings: FsStorePermissions = serde_json::from_reader(file)?;
-    Ok(settings)
+    let permissions: StorePermissions = serde_json::from_reader(file)?;
+    Ok(permissions)
 }
 
-pub(super) fn read_settings_or_default<P>(root_path: P) -> FsStoreSettings
+pub(super) fn read_permissions_or_default<P>(root_path: P) -> StorePermissions
 where
     P: AsRef<Path>,
 {
-    match read_settings(root_path) {
-        Ok(settings) => settings,
+    match read_permissions(root_path) {
+        Ok(permissions) => permissions,
         Err(e) => {
             warn!("Error reading store permissions; error: {:?}", e);
             Default::default()
@@ -66,18 +52,17 @@ where
     }
 }
 
-pub(super) fn write_settings<P>(root_path: P, settings: &FsStoreSettings) -> Result<(), Error>
+pub(super) fn write_permissions<P>(
+    root_path: P,
+    permissions: &StorePermissions,
+) -> Result<(), Error>
 where
     P: AsRef<Path>,
 {
     let file_path = root_path.as_ref().join(FS_STORE_PERMISSIONS_FILE);
     trace!("Writing store permissions to {:?}", file_path);
-    let file = std::fs::File::options()
-        .write(true)
-        .create(true)
-        .open(file_path)?;
-    serde_json::to_writer_pretty(file, settings)?;
-    Ok(())
+    let contents = serde_json::to_vec_pretty(permissions)?;
+    write_locked(root_path.as_ref(), || replace_file(&file_path, &contents))
 }
 
 // ------------------------------------------------------------------------------------------------
@@ -0,0 +1,39 @@
+/*!
+Stores import profiles, one JSON file per profile in the `profiles/` sub-directory of the store.
+
+*/
+
+use crate::fs::entity::FsEntityStore;
+use sledge_model::import::CsvProfile;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+pub(super) const PROFILE_SUB_DIR: &str = "profiles/";
+
+pub(super) type FsProfileStore = FsEntityStore<String, CsvProfile>;
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
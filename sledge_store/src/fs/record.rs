@@ -0,0 +1,93 @@
+/*!
+Stores records without an identity of their own, such as prices, as a single JSON array per
+kind of record in the root of the store.
+
+*/
+
+use super::wal::{replace_file, write_locked};
+use crate::error::{entity_file_error, Error};
+use crate::RecordStore;
+use serde::{de::DeserializeOwned, Serialize};
+use std::fs;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use tracing::trace;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+pub(super) const COMMODITIES_FILE: &str = "commodities.json";
+
+pub(super) const PRICES_FILE: &str = "prices.json";
+
+pub(super) const RECONCILIATIONS_FILE: &str = "reconciliations.json";
+
+#[derive(Debug)]
+pub(super) struct FsRecordStore<R> {
+    root_path: PathBuf,
+    file_path: PathBuf,
+    record: PhantomData<fn() -> R>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl<R> FsRecordStore<R> {
+    pub(super) fn open<P>(root_path: P, file_name: &str) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        Self {
+            root_path: root_path.as_ref().to_path_buf(),
+            file_path: root_path.as_ref().join(file_name),
+            record: PhantomData,
+        }
+    }
+}
+
+impl<R> RecordStore<R> for FsRecordStore<R>
+where
+    R: Serialize + DeserializeOwned,
+{
+    fn list(&self) -> Result<Vec<R>, Error> {
+        if self.file_path.is_file() {
+            trace!("Reading records from {:?}", self.file_path);
+            let file = fs::File::open(&self.file_path)?;
+            serde_json::from_reader(file).map_err(entity_file_error)
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    fn append(&self, records: Vec<R>) -> Result<(), Error> {
+        write_locked(&self.root_path, || {
+            let mut all = self.list()?;
+            all.extend(records);
+            trace!("Writing {} records to {:?}", all.len(), self.file_path);
+            let contents = serde_json::to_vec_pretty(&all).map_err(entity_file_error)?;
+            replace_file(&self.file_path, &contents)
+        })
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
@@ -0,0 +1,289 @@
+/*!
+Commits a unit of work to the file system store through a write-ahead log, so that a crash
+part way through leaves either none of its changes or a log from which all of them are made,
+and provides the single-step file replacement every write to the store is made with.
+
+A unit of work claims the store by taking an exclusive lock on the file `transaction.lock` in
+the root of the store, before the changes are checked against the files on disk, and holds it
+until every change has been made; while it is held no other unit of work may commit. A write
+made directly to the store, outside any unit of work, holds the same lock while it reads and
+replaces its file, so that it can neither land between the checks and changes of a unit of
+work nor interleave with another write. As the lock is held by an open file the operating
+system releases it when the process holding it stops, however that happens.
+
+The log is the file `transaction.wal` beside the lock. It is written to a temporary file,
+flushed, and renamed into place, which is the point at which the unit of work is committed.
+It is a JSON array with one object per file to change, in the form
+
+```json
+[
+  { "path": "journals/sales.json", "contents": "{ ... }" },
+  { "path": "parties/P-1.json", "contents": null }
+]
+```
+
+where `path` is relative to the root of the store and `contents` is the whole new contents of
+the file, or `null` if the file is to be removed. Each file is replaced as any other write to
+the store is, every directory changed is flushed, and only then is the log removed. As every
+change replaces a whole file, making the changes of a log a second time has no further effect.
+
+A log found by whoever next takes the lock was left by a process that stopped committing, and
+its changes are made before it is removed. This is done when a store is connected to, and by
+each unit of work as it claims the store.
+
+*/
+
+use crate::error::{entity_file_error, Error};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tracing::{trace, warn};
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// One file to change when a log is committed.
+///
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct LogEntry {
+    pub(super) path: String,
+    pub(super) contents: Option<String>,
+}
+
+///
+/// The claim of a unit of work on the store, held from before its changes are checked until
+/// they have all been made.
+///
+#[derive(Debug)]
+pub(super) struct WriteAheadLog {
+    root_path: PathBuf,
+    _lock: fs::File,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+///
+/// Wait for any unit of work committing to the store to finish, making the changes of a log
+/// left by one that stopped.
+///
+pub(super) fn recover(root_path: &Path) -> Result<(), Error> {
+    let _lock = lock(root_path)?;
+    recover_locked(root_path)
+}
+
+///
+/// Make a write directly to the store, outside any unit of work, while holding the store's
+/// lock; any log left by a unit of work that stopped is completed first.
+///
+pub(super) fn write_locked<T, F>(root_path: &Path, write: F) -> Result<T, Error>
+where
+    F: FnOnce() -> Result<T, Error>,
+{
+    let _lock = lock(root_path)?;
+    recover_locked(root_path)?;
+    write()
+}
+
+///
+/// Replace the contents of a file in a single step, by writing and flushing a temporary file
+/// beside it and renaming that over it, and then flushing the directory that holds it.
+///
+pub(super) fn replace_file(file_path: &Path, contents: &[u8]) -> Result<(), Error> {
+    write_and_rename(file_path, contents)?;
+    sync_dir(parent_dir(file_path))
+}
+
+///
+/// Create a file with the given contents in a single step, as for [`replace_file`], returning
+/// `false` and writing nothing if a file already exists at `file_path`. The temporary file is
+/// linked into place, which fails if a file exists, so that of two concurrent creates only one
+/// succeeds.
+///
+pub(super) fn create_file(file_path: &Path, contents: &[u8]) -> Result<bool, Error> {
+    let temp_path = write_temp_file(file_path, contents)?;
+    let result = fs::hard_link(&temp_path, file_path);
+    let _ = fs::remove_file(&temp_path);
+    match result {
+        Ok(()) => sync_dir(parent_dir(file_path)).map(|_| true),
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+const WAL_FILE: &str = "transaction.wal";
+
+const LOCK_FILE: &str = "transaction.lock";
+
+static TEMP_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl WriteAheadLog {
+    ///
+    /// Claim the store, waiting for any other unit of work to finish committing first.
+    ///
+    pub(super) fn claim(root_path: &Path) -> Result<Self, Error> {
+        let lock = lock(root_path)?;
+        trace!("Claimed store {:?}", root_path);
+        recover_locked(root_path)?;
+        Ok(Self {
+            root_path: root_path.to_path_buf(),
+            _lock: lock,
+        })
+    }
+
+    ///
+    /// Write the log, make each change in it, and then remove it. Should a change fail the
+    /// log is left in place, to be completed by the next to claim the store.
+    ///
+    pub(super) fn commit(self, entries: Vec<LogEntry>) -> Result<(), Error> {
+        let log = serde_json::to_vec_pretty(&entries).map_err(entity_file_error)?;
+        let wal_path = self.root_path.join(WAL_FILE);
+        replace_file(&wal_path, &log)?;
+        trace!("Committed {} changes to {:?}", entries.len(), wal_path);
+        apply(&self.root_path, &entries)?;
+        remove_if_present(&wal_path)?;
+        sync_dir(&self.root_path)
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+///
+/// Take the exclusive lock on the store, blocking until any other holder releases it.
+///
+fn lock(root_path: &Path) -> Result<fs::File, Error> {
+    let file = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(root_path.join(LOCK_FILE))?;
+    file.lock()?;
+    Ok(file)
+}
+
+fn recover_locked(root_path: &Path) -> Result<(), Error> {
+    let wal_path = root_path.join(WAL_FILE);
+    let log = match fs::read(&wal_path) {
+        Ok(log) => log,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+    warn!("Recovering write-ahead log {:?}", wal_path);
+    let entries: Vec<LogEntry> = serde_json::from_slice(&log).map_err(entity_file_error)?;
+    apply(root_path, &entries)?;
+    remove_if_present(&wal_path)?;
+    sync_dir(root_path)
+}
+
+///
+/// Make each change in a log, then flush every directory changed so that no change can be
+/// lost once the log is removed.
+///
+fn apply(root_path: &Path, entries: &[LogEntry]) -> Result<(), Error> {
+    let mut dirs = BTreeSet::new();
+    for entry in entries {
+        let file_path = root_path.join(&entry.path);
+        match &entry.contents {
+            Some(contents) => {
+                trace!("Writing {:?} from write-ahead log", file_path);
+                if let Some(parent) = file_path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                write_and_rename(&file_path, contents.as_bytes())?;
+            }
+            None => {
+                trace!("Removing {:?} from write-ahead log", file_path);
+                remove_if_present(&file_path)?;
+            }
+        }
+        let _ = dirs.insert(parent_dir(&file_path).to_path_buf());
+    }
+    for dir in dirs {
+        sync_dir(&dir)?;
+    }
+    Ok(())
+}
+
+fn write_and_rename(file_path: &Path, contents: &[u8]) -> Result<(), Error> {
+    let temp_path = write_temp_file(file_path, contents)?;
+    fs::rename(&temp_path, file_path).map_err(|e| {
+        let _ = fs::remove_file(&temp_path);
+        e.into()
+    })
+}
+
+///
+/// Write and flush a temporary file beside `file_path`, named for this process so that no two
+/// writers share one.
+///
+fn write_temp_file(file_path: &Path, contents: &[u8]) -> Result<PathBuf, Error> {
+    let mut temp_name = file_path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(format!(
+        ".{}-{}.tmp",
+        process::id(),
+        TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let temp_path = file_path.with_file_name(temp_name);
+    let result = fs::File::create(&temp_path).and_then(|mut file| {
+        file.write_all(contents)?;
+        file.sync_all()
+    });
+    match result {
+        Ok(()) => Ok(temp_path),
+        Err(e) => {
+            let _ = fs::remove_file(&temp_path);
+            Err(e.into())
+        }
+    }
+}
+
+///
+/// Flush a directory, so that the files renamed into or removed from it are durable. Not every
+/// platform can open a directory to flush it; there the rename is as durable as it can be made.
+///
+fn sync_dir(dir_path: &Path) -> Result<(), Error> {
+    match fs::File::open(dir_path).and_then(|dir| dir.sync_all()) {
+        Err(e) if cfg!(unix) => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+fn parent_dir(file_path: &Path) -> &Path {
+    match file_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
+
+fn remove_if_present(file_path: &Path) -> Result<(), Error> {
+    match fs::remove_file(file_path) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
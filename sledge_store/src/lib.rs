@@ -55,12 +55,25 @@ use crate::error::Error;
 use chrono::{DateTime, Utc};
 use error::unknown_store_scheme;
 use fs::{FileSystemStore, FS_STORE_SCHEME};
+use memory::{MemoryStore, MEMORY_STORE_SCHEME};
+use query::{Page, Query};
 use semver::Version;
+use serde::{Deserialize, Serialize};
 use sledge_model::{
-    journal::Journal,
+    audit::{Action, Authenticator, Resource, RoleId, UserId},
+    commodity::{CommodityId, RateRecord, Security},
+    import::CsvProfile,
+    journal::{Journal, Reconciled, Transaction},
     ledger::{Ledger, LedgerKind},
+    party::{Party, PartyId},
+};
+use sqlite::{SqliteStore, SQLITE_STORE_SCHEME};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+    hash::Hash,
+    sync::Arc,
 };
-use std::{fmt::Display, hash::Hash, sync::Arc};
 use tracing::trace_span;
 use url::Url;
 
@@ -87,7 +100,70 @@ pub trait DataStore {
 
     fn journals(&self) -> Result<Box<dyn EntityStore<String, Journal>>, Error>;
 
-    fn disconnect(self) -> Result<(), Error>;
+    fn import_profiles(&self) -> Result<Box<dyn EntityStore<String, CsvProfile>>, Error>;
+
+    fn parties(&self) -> Result<Box<dyn EntityStore<PartyId, Party>>, Error>;
+
+    fn commodities(&self) -> Result<Box<dyn RecordStore<Security>>, Error>;
+
+    fn prices(&self) -> Result<Box<dyn RecordStore<RateRecord>>, Error>;
+
+    fn reconciliations(&self) -> Result<Box<dyn RecordStore<Reconciled>>, Error>;
+
+    ///
+    /// List a page of the transactions in `journal` selected by `query`, without reading the
+    /// rest of the journal where the backend allows.
+    ///
+    fn transactions(&self, journal: &str, query: &Query) -> Result<Page<Transaction>, Error>;
+
+    fn settings(&self) -> Result<StoreSettings, Error>;
+
+    fn update_settings(&self, settings: &StoreSettings) -> Result<(), Error>;
+
+    fn permissions(&self) -> Result<StorePermissions, Error>;
+
+    fn update_permissions(&self, permissions: &StorePermissions) -> Result<(), Error>;
+
+    ///
+    /// Begin a unit of work; changes made through the stores it returns are only seen by
+    /// other connections once it is committed, and then all of them are.
+    ///
+    fn begin(&self) -> Result<Box<dyn UnitOfWork>, Error>;
+
+    ///
+    /// Disconnect from the store, writing out anything the backend still holds only in
+    /// memory; an error here means changes may not have been saved.
+    ///
+    fn disconnect(&self) -> Result<(), Error>;
+}
+
+///
+/// A set of changes across several entity and record stores that is made as a whole or not
+/// at all. Reads through the stores of a unit of work see its own changes; the stores should
+/// not be used once the unit of work is committed or rolled back. A unit of work dropped
+/// without being committed is rolled back.
+///
+/// A commit fails, and makes none of the changes, if another connection has since created
+/// an entity this unit of work creates, or removed one it updates or deletes.
+///
+pub trait UnitOfWork {
+    fn ledgers(&self) -> Result<Box<dyn EntityStore<LedgerKind, Ledger>>, Error>;
+
+    fn journals(&self) -> Result<Box<dyn EntityStore<String, Journal>>, Error>;
+
+    fn import_profiles(&self) -> Result<Box<dyn EntityStore<String, CsvProfile>>, Error>;
+
+    fn parties(&self) -> Result<Box<dyn EntityStore<PartyId, Party>>, Error>;
+
+    fn commodities(&self) -> Result<Box<dyn RecordStore<Security>>, Error>;
+
+    fn prices(&self) -> Result<Box<dyn RecordStore<RateRecord>>, Error>;
+
+    fn reconciliations(&self) -> Result<Box<dyn RecordStore<Reconciled>>, Error>;
+
+    fn commit(self: Box<Self>) -> Result<(), Error>;
+
+    fn rollback(self: Box<Self>) -> Result<(), Error>;
 }
 
 pub trait Entity<I>
@@ -110,19 +186,52 @@ where
 
     fn create_with_id(&self, entity: E, id: I) -> Result<(), Error>;
 
-    fn list(&self, page: Option<String>) -> Result<Vec<E>, Error>;
+    ///
+    /// List a page of the entities selected by `query`, ordered by the string form of their
+    /// identifier unless the query sorts by another key.
+    ///
+    fn list(&self, query: &Query) -> Result<Page<E>, Error>;
 
-    fn get_by_id(&self, id: &I) -> Result<Option<&E>, Error>;
+    fn get_by_id(&self, id: &I) -> Result<Option<E>, Error>;
 
     fn update(&self, entity: E) -> Result<(), Error>;
 
     fn delete(&self, id: &I) -> Result<(), Error>;
 }
 
+///
+/// Records that have no identity of their own, such as prices, are kept in the order they
+/// were added.
+///
+pub trait RecordStore<R> {
+    fn list(&self) -> Result<Vec<R>, Error>;
+
+    fn append(&self, records: Vec<R>) -> Result<(), Error>;
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoreSettings {
+    version: Version,
+    created: DateTime<Utc>,
+    default_commodity: Option<CommodityId>,
+}
+
+///
+/// The actions each role may perform on each kind of resource, and the roles granted to each
+/// user.
+///
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorePermissions {
+    roles: BTreeMap<RoleId, BTreeMap<Resource, BTreeSet<Action>>>,
+    users: BTreeMap<UserId, BTreeSet<RoleId>>,
+}
+
 #[derive(Debug)]
 pub struct CreateDatastoreContents {
     pub ledgers: Vec<Ledger>,
     pub journals: Vec<Journal>,
+    pub prices: Vec<RateRecord>,
+    pub reconciliations: Vec<Reconciled>,
 }
 
 // ------------------------------------------------------------------------------------------------
@@ -135,10 +244,21 @@ pub fn get_current_datastore(connection_uri: &Url) -> Result<Arc<dyn DataStore>,
 
     match connection_uri.scheme() {
         FS_STORE_SCHEME => Ok(Arc::new(FileSystemStore::connect(connection_uri)?)),
+        MEMORY_STORE_SCHEME => Ok(Arc::new(MemoryStore::connect(connection_uri)?)),
+        SQLITE_STORE_SCHEME => Ok(Arc::new(SqliteStore::connect(connection_uri)?)),
         _ => Err(unknown_store_scheme(connection_uri.clone())),
     }
 }
 
+pub fn datastore_exists(connection_uri: &Url) -> bool {
+    match connection_uri.scheme() {
+        FS_STORE_SCHEME => FileSystemStore::exists(connection_uri),
+        MEMORY_STORE_SCHEME => MemoryStore::exists(connection_uri),
+        SQLITE_STORE_SCHEME => SqliteStore::exists(connection_uri),
+        _ => false,
+    }
+}
+
 pub fn create_datastore(
     connection_uri: &Url,
     initial_content: &CreateDatastoreContents,
@@ -151,6 +271,14 @@ pub fn create_datastore(
             connection_uri,
             initial_content,
         )?)),
+        MEMORY_STORE_SCHEME => Ok(Arc::new(MemoryStore::create(
+            connection_uri,
+            initial_content,
+        )?)),
+        SQLITE_STORE_SCHEME => Ok(Arc::new(SqliteStore::create(
+            connection_uri,
+            initial_content,
+        )?)),
         _ => Err(unknown_store_scheme(connection_uri.clone())),
     }
 }
@@ -164,10 +292,166 @@ impl Default for CreateDatastoreContents {
         Self {
             ledgers: Default::default(),
             journals: Default::default(),
+            prices: Default::default(),
+            reconciliations: Default::default(),
+        }
+    }
+}
+
+impl Default for StoreSettings {
+    fn default() -> Self {
+        Self {
+            version: STORE_SCHEMA_VERSION,
+            created: Utc::now(),
+            default_commodity: None,
         }
     }
 }
 
+impl StoreSettings {
+    pub fn with_default_commodity(self, default_commodity: CommodityId) -> Self {
+        Self {
+            default_commodity: Some(default_commodity),
+            ..self
+        }
+    }
+
+    pub fn version(&self) -> &Version {
+        &self.version
+    }
+
+    pub fn created(&self) -> DateTime<Utc> {
+        self.created
+    }
+
+    pub fn default_commodity(&self) -> Option<&CommodityId> {
+        self.default_commodity.as_ref()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl Authenticator for StorePermissions {
+    fn user_has_role(&self, user: UserId, role: RoleId) -> bool {
+        self.users
+            .get(&user)
+            .map(|roles| roles.contains(&role))
+            .unwrap_or_default()
+    }
+
+    fn role_can_perform(&self, role: RoleId, action: Action, resource: Resource) -> bool {
+        self.roles
+            .get(&role)
+            .and_then(|resources| resources.get(&resource))
+            .map(|actions| actions.contains(&action))
+            .unwrap_or_default()
+    }
+
+    fn user_can_perform(&self, user: UserId, action: Action, resource: Resource) -> bool {
+        self.users
+            .get(&user)
+            .map(|roles| {
+                roles
+                    .iter()
+                    .any(|role| self.role_can_perform(role.clone(), action, resource.clone()))
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl StorePermissions {
+    pub fn with_grant(mut self, role: RoleId, resource: Resource, action: Action) -> Self {
+        self.grant(role, resource, action);
+        self
+    }
+
+    pub fn with_user_role(mut self, user: UserId, role: RoleId) -> Self {
+        self.add_user_role(user, role);
+        self
+    }
+
+    pub fn grant(&mut self, role: RoleId, resource: Resource, action: Action) {
+        self.roles
+            .entry(role)
+            .or_default()
+            .entry(resource)
+            .or_default()
+            .insert(action);
+    }
+
+    pub fn add_user_role(&mut self, user: UserId, role: RoleId) {
+        self.users.entry(user).or_default().insert(role);
+    }
+
+    pub fn roles(&self) -> impl Iterator<Item = &RoleId> {
+        self.roles.keys()
+    }
+
+    pub fn users(&self) -> impl Iterator<Item = (&UserId, &BTreeSet<RoleId>)> {
+        self.users.iter()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl Entity<LedgerKind> for Ledger {
+    fn identifier(&self) -> &LedgerKind {
+        self.kind()
+    }
+
+    fn label(&self) -> &String {
+        self.description()
+    }
+
+    fn created(&self) -> DateTime<Utc> {
+        Ledger::created(self)
+    }
+}
+
+impl Entity<String> for Journal {
+    fn identifier(&self) -> &String {
+        self.name()
+    }
+
+    fn label(&self) -> &String {
+        self.name()
+    }
+
+    fn created(&self) -> DateTime<Utc> {
+        Journal::created(self)
+    }
+}
+
+impl Entity<PartyId> for Party {
+    fn identifier(&self) -> &PartyId {
+        self.id()
+    }
+
+    fn label(&self) -> &String {
+        self.name()
+    }
+
+    fn created(&self) -> DateTime<Utc> {
+        Party::created(self)
+    }
+}
+
+impl Entity<String> for CsvProfile {
+    fn identifier(&self) -> &String {
+        self.name()
+    }
+
+    fn label(&self) -> &String {
+        self.description()
+    }
+
+    fn created(&self) -> DateTime<Utc> {
+        CsvProfile::created(self)
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
 impl CreateDatastoreContents {
     pub fn personal_ledger(self) -> Self {
         self
@@ -202,6 +486,16 @@ impl CreateDatastoreContents {
 // Modules
 // ------------------------------------------------------------------------------------------------
 
+pub mod archive;
+
 pub mod error;
 
 pub mod fs;
+
+mod memory;
+
+pub mod query;
+
+mod sqlite;
+
+mod staged;
@@ -0,0 +1,309 @@
+/*!
+Stores entities as JSON values in a named table of the in-memory store, ordered by identifier.
+
+Each table also keeps the label and created date of every entity in an ordered index, so that
+a page in any order is read from the position of its cursor onward, and only the entities on
+the page are read back from their values.
+
+*/
+
+use super::MemoryData;
+use crate::error::{entity_exists, entity_file_error, entity_not_found, Error};
+use crate::query::{date_key, label_matches, Page, Query, Row, SortKey, SortOrder};
+use crate::{Entity, EntityStore};
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Display;
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::ops::Bound;
+use std::sync::Arc;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+pub(super) const LEDGERS: &str = "ledgers";
+
+pub(super) const JOURNALS: &str = "journals";
+
+pub(super) const PARTIES: &str = "parties";
+
+pub(super) const PROFILES: &str = "import_profiles";
+
+///
+/// The entities of one table, as JSON values by identifier, with an index of each of the other
+/// keys a query may sort them by.
+///
+#[derive(Debug, Default)]
+pub(super) struct EntityTable {
+    entities: BTreeMap<String, (SortKeys, Value)>,
+    by_label: BTreeSet<(String, String)>,
+    by_date: BTreeSet<(String, String)>,
+}
+
+///
+/// The label and created date of an entity, as it is indexed.
+///
+#[derive(Debug)]
+pub(super) struct SortKeys {
+    label: String,
+    created: DateTime<Utc>,
+}
+
+pub(super) struct MemoryEntityStore<I, E> {
+    data: Arc<MemoryData>,
+    table: &'static str,
+    entity: PhantomData<fn() -> (I, E)>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl EntityTable {
+    pub(super) fn get(&self, id: &str) -> Option<&Value> {
+        self.entities.get(id).map(|(_, value)| value)
+    }
+
+    pub(super) fn contains(&self, id: &str) -> bool {
+        self.entities.contains_key(id)
+    }
+
+    ///
+    /// Insert an entity, or replace the one with the same identifier.
+    ///
+    pub(super) fn insert(&mut self, id: String, keys: SortKeys, value: Value) {
+        let _ = self.remove(&id);
+        let _ = self.by_label.insert((keys.label.clone(), id.clone()));
+        let _ = self.by_date.insert((date_key(keys.created), id.clone()));
+        let _ = self.entities.insert(id, (keys, value));
+    }
+
+    pub(super) fn remove(&mut self, id: &str) -> Option<Value> {
+        let (keys, value) = self.entities.remove(id)?;
+        let _ = self.by_label.remove(&(keys.label, id.to_string()));
+        let _ = self
+            .by_date
+            .remove(&(date_key(keys.created), id.to_string()));
+        Some(value)
+    }
+
+    ///
+    /// The entities in the order of `query`, starting after its cursor.
+    ///
+    fn seek<'a>(
+        &'a self,
+        query: &Query,
+    ) -> Result<impl Iterator<Item = (&'a String, &'a SortKeys, &'a Value)> + 'a, Error> {
+        let after = query.checked_cursor()?;
+        let ids: Box<dyn DoubleEndedIterator<Item = &String>> = match query.sort() {
+            SortKey::Identifier => Box::new(
+                self.entities
+                    .range(bounds(
+                        after.map(|cursor| cursor.id().clone()),
+                        query.order(),
+                    ))
+                    .map(|(id, _)| id),
+            ),
+            SortKey::Label => Box::new(
+                self.by_label
+                    .range(bounds(
+                        after.map(|cursor| (cursor.key().clone(), cursor.id().clone())),
+                        query.order(),
+                    ))
+                    .map(|(_, id)| id),
+            ),
+            SortKey::Date => Box::new(
+                self.by_date
+                    .range(bounds(
+                        after.map(|cursor| (cursor.key().clone(), cursor.id().clone())),
+                        query.order(),
+                    ))
+                    .map(|(_, id)| id),
+            ),
+        };
+        let ids = match query.order() {
+            SortOrder::Ascending => ids,
+            SortOrder::Descending => Box::new(ids.rev()),
+        };
+        Ok(ids.filter_map(|id| {
+            self.entities
+                .get_key_value(id)
+                .map(|(id, (keys, value))| (id, keys, value))
+        }))
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl SortKeys {
+    pub(super) fn of<I, E>(entity: &E) -> Self
+    where
+        I: Display + Eq + Hash,
+        E: Entity<I>,
+    {
+        Self {
+            label: entity.label().clone(),
+            created: entity.created(),
+        }
+    }
+
+    ///
+    /// The keys of an entity held only as a JSON value, read by deserializing it.
+    ///
+    pub(super) fn read<I, E>(value: &Value) -> Result<Self, Error>
+    where
+        I: Display + Eq + Hash,
+        E: Entity<I> + DeserializeOwned,
+    {
+        Ok(Self::of(&E::deserialize(value).map_err(entity_file_error)?))
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl<I, E> MemoryEntityStore<I, E>
+where
+    I: Display + Eq + Hash,
+    E: Entity<I> + Serialize + DeserializeOwned,
+{
+    pub(super) fn open(data: Arc<MemoryData>, table: &'static str) -> Self {
+        Self {
+            data,
+            table,
+            entity: PhantomData,
+        }
+    }
+
+    ///
+    /// Write a new entity under its own identifier, without taking ownership of it.
+    ///
+    pub(super) fn insert(&self, entity: &E) -> Result<(), Error> {
+        self.insert_with_id(entity, entity.identifier())
+    }
+
+    fn insert_with_id(&self, entity: &E, id: &I) -> Result<(), Error> {
+        let value = serde_json::to_value(entity).map_err(entity_file_error)?;
+        let id = id.to_string();
+        let mut contents = self.data.write();
+        let entities = contents.entities.entry(self.table).or_default();
+        if entities.contains(&id) {
+            Err(entity_exists(id))
+        } else {
+            entities.insert(id, SortKeys::of(entity), value);
+            Ok(())
+        }
+    }
+}
+
+impl<I, E> EntityStore<I, E> for MemoryEntityStore<I, E>
+where
+    I: Clone + Display + Eq + Hash,
+    E: Entity<I> + Serialize + DeserializeOwned,
+{
+    fn create(&self, entity: E) -> Result<I, Error> {
+        let id = entity.identifier().clone();
+        self.insert_with_id(&entity, &id)?;
+        Ok(id)
+    }
+
+    fn create_with_id(&self, entity: E, id: I) -> Result<(), Error> {
+        self.insert_with_id(&entity, &id)
+    }
+
+    fn list(&self, query: &Query) -> Result<Page<E>, Error> {
+        let predicates = query.entity_predicates()?;
+        let contents = self.data.read();
+        let mut rows = Vec::new();
+        if let Some(entities) = contents.entities.get(self.table) {
+            for (id, keys, value) in entities.seek(query)? {
+                if !query.includes_date(keys.created)
+                    || !predicates
+                        .iter()
+                        .all(|predicate| label_matches(&keys.label, predicate))
+                {
+                    continue;
+                }
+                rows.push(Row {
+                    id: id.clone(),
+                    position: 0,
+                    label: keys.label.clone(),
+                    date: keys.created,
+                    item: E::deserialize(value).map_err(entity_file_error)?,
+                });
+                if query.limit().is_some_and(|limit| rows.len() > limit) {
+                    break;
+                }
+            }
+        }
+        Ok(query.page(rows))
+    }
+
+    fn get_by_id(&self, id: &I) -> Result<Option<E>, Error> {
+        self.data
+            .read()
+            .entities
+            .get(self.table)
+            .and_then(|entities| entities.get(&id.to_string()))
+            .map(|value| E::deserialize(value).map_err(entity_file_error))
+            .transpose()
+    }
+
+    fn update(&self, entity: E) -> Result<(), Error> {
+        let id = entity.identifier().to_string();
+        let value = serde_json::to_value(&entity).map_err(entity_file_error)?;
+        let mut contents = self.data.write();
+        match contents.entities.get_mut(self.table) {
+            Some(entities) if entities.contains(&id) => {
+                entities.insert(id, SortKeys::of(&entity), value);
+                Ok(())
+            }
+            _ => Err(entity_not_found(id)),
+        }
+    }
+
+    fn delete(&self, id: &I) -> Result<(), Error> {
+        let id = id.to_string();
+        self.data
+            .write()
+            .entities
+            .get_mut(self.table)
+            .and_then(|entities| entities.remove(&id))
+            .map(|_| ())
+            .ok_or_else(|| entity_not_found(id))
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+///
+/// The bounds of the range of an index that follows `after` in the given order.
+///
+fn bounds<K>(after: Option<K>, order: SortOrder) -> (Bound<K>, Bound<K>) {
+    match (after, order) {
+        (None, _) => (Bound::Unbounded, Bound::Unbounded),
+        (Some(after), SortOrder::Ascending) => (Bound::Excluded(after), Bound::Unbounded),
+        (Some(after), SortOrder::Descending) => (Bound::Unbounded, Bound::Excluded(after)),
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
@@ -0,0 +1,487 @@
+/*!
+Provides a data store held entirely in memory, connected to with a URI such as `memory:books`,
+for tests and for tools that embed the model without wanting any files written.
+
+A named store is shared by every connection to the same name within the process, for as long
+as any connection, or any entity store returned from one, is still held; a store created from
+the unnamed URI `memory:` can only be used through the value returned from `create`.
+
+Adding a `snapshot` query parameter, as in `memory:books?snapshot=/home/me/books.json`, loads
+the store from that archive file, when it exists, on the first connection and saves the store
+to it when a connection is disconnected. The snapshot is written with
+[`write_archive`](crate::archive::write_archive), in the format chosen by the file's extension.
+Changes not yet saved when the last handle is dropped are saved then, but as a drop cannot
+report an error any failure is only logged.
+
+Entities are kept as JSON values so that, as with the file system store, any entity that can
+be serialized can be stored, and a value read from the store never shares state with one
+still held by the caller. A unit of work stages its changes and applies them all under a
+single write lock when committed.
+
+*/
+
+use self::entity::{
+    EntityTable, MemoryEntityStore, SortKeys, JOURNALS, LEDGERS, PARTIES, PROFILES,
+};
+use self::record::{MemoryRecordStore, COMMODITIES, PRICES, RECONCILIATIONS};
+use crate::archive::{read_archive, write_archive, ArchiveFormat, StoreArchive};
+use crate::error::{
+    entity_file_error, entity_not_found, store_does_not_exist, store_exists, Error,
+};
+use crate::query::{select_transactions, JournalTransactions, Page, Query};
+use crate::staged::{ApplyChanges, Change, Changes, StagedUnitOfWork, Table};
+use crate::{
+    CreateDatastoreContents, DataStore, EntityStore, RecordStore, StorePermissions, StoreSettings,
+    UnitOfWork,
+};
+use lazy_static::lazy_static;
+use serde::Deserialize;
+use serde_json::Value;
+use sledge_model::{
+    commodity::{RateRecord, Security},
+    import::CsvProfile,
+    journal::{Journal, Reconciled, Transaction},
+    ledger::{Ledger, LedgerKind},
+    party::{Party, PartyId},
+};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{
+    Arc, Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard, Weak,
+};
+use tracing::{error, trace};
+use url::Url;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+pub(super) const MEMORY_STORE_SCHEME: &str = "memory";
+
+pub(super) struct MemoryStore {
+    data: Arc<MemoryData>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+const SNAPSHOT_PARAMETER: &str = "snapshot";
+
+static TEMP_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug)]
+struct MemoryData {
+    snapshot: Option<PathBuf>,
+    changed: AtomicBool,
+    contents: RwLock<MemoryContents>,
+}
+
+#[derive(Debug, Default)]
+struct MemoryContents {
+    settings: StoreSettings,
+    permissions: StorePermissions,
+    entities: BTreeMap<&'static str, EntityTable>,
+    records: BTreeMap<&'static str, Vec<Value>>,
+}
+
+lazy_static! {
+    static ref STORES: Mutex<HashMap<String, Weak<MemoryData>>> = Default::default();
+}
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl DataStore for MemoryStore {
+    fn connect(connection_uri: &Url) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        let mut stores = lock_stores();
+        if let Some(data) = registered(&stores, connection_uri) {
+            trace!("Connecting to existing store @ <{}>", connection_uri);
+            return Ok(MemoryStore { data });
+        }
+        match snapshot_path(connection_uri).filter(|path| path.is_file()) {
+            Some(path) if !connection_uri.path().is_empty() => {
+                trace!("Loading store @ <{}> from {:?}", connection_uri, path);
+                let store = MemoryStore::new(None);
+                let format = ArchiveFormat::from_path(&path);
+                read_archive(BufReader::new(fs::File::open(&path)?), format)?
+                    .import_into(&store)?;
+                let store = MemoryStore::new_with_contents(Some(path), store.take_contents());
+                register(&mut stores, connection_uri, &store.data);
+                Ok(store)
+            }
+            _ => {
+                error!(
+                    "Could not find the required content for a store @ <{}>",
+                    connection_uri
+                );
+                Err(store_does_not_exist(connection_uri.clone()))
+            }
+        }
+    }
+
+    fn exists(connection_uri: &Url) -> bool
+    where
+        Self: Sized,
+    {
+        exists(&lock_stores(), connection_uri)
+    }
+
+    fn create(connection_uri: &Url, content: &CreateDatastoreContents) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        let mut stores = lock_stores();
+        if !exists(&stores, connection_uri) {
+            let store = MemoryStore::new(snapshot_path(connection_uri));
+            let ledgers = MemoryEntityStore::open(store.data.clone(), LEDGERS);
+            for ledger in &content.ledgers {
+                ledgers.insert(ledger)?;
+            }
+            let journals = MemoryEntityStore::open(store.data.clone(), JOURNALS);
+            for journal in &content.journals {
+                journals.insert(journal)?;
+            }
+            if !content.prices.is_empty() {
+                store.prices()?.append(content.prices.clone())?;
+            }
+            if !content.reconciliations.is_empty() {
+                store
+                    .reconciliations()?
+                    .append(content.reconciliations.clone())?;
+            }
+            if !connection_uri.path().is_empty() {
+                register(&mut stores, connection_uri, &store.data);
+            }
+            trace!("Store @ <{}> created", connection_uri);
+            Ok(store)
+        } else {
+            error!(
+                "Could not create a store, one already exists @ <{}>",
+                connection_uri
+            );
+            Err(store_exists(connection_uri.clone()))
+        }
+    }
+
+    fn ledgers(&self) -> Result<Box<dyn EntityStore<LedgerKind, Ledger>>, Error> {
+        Ok(Box::new(MemoryEntityStore::open(
+            self.data.clone(),
+            LEDGERS,
+        )))
+    }
+
+    fn journals(&self) -> Result<Box<dyn EntityStore<String, Journal>>, Error> {
+        Ok(Box::new(MemoryEntityStore::open(
+            self.data.clone(),
+            JOURNALS,
+        )))
+    }
+
+    fn import_profiles(&self) -> Result<Box<dyn EntityStore<String, CsvProfile>>, Error> {
+        Ok(Box::new(MemoryEntityStore::open(
+            self.data.clone(),
+            PROFILES,
+        )))
+    }
+
+    fn parties(&self) -> Result<Box<dyn EntityStore<PartyId, Party>>, Error> {
+        Ok(Box::new(MemoryEntityStore::open(
+            self.data.clone(),
+            PARTIES,
+        )))
+    }
+
+    fn commodities(&self) -> Result<Box<dyn RecordStore<Security>>, Error> {
+        Ok(Box::new(MemoryRecordStore::open(
+            self.data.clone(),
+            COMMODITIES,
+        )))
+    }
+
+    fn prices(&self) -> Result<Box<dyn RecordStore<RateRecord>>, Error> {
+        Ok(Box::new(MemoryRecordStore::open(self.data.clone(), PRICES)))
+    }
+
+    fn reconciliations(&self) -> Result<Box<dyn RecordStore<Reconciled>>, Error> {
+        Ok(Box::new(MemoryRecordStore::open(
+            self.data.clone(),
+            RECONCILIATIONS,
+        )))
+    }
+
+    fn transactions(&self, journal: &str, query: &Query) -> Result<Page<Transaction>, Error> {
+        let JournalTransactions { transactions } = self
+            .data
+            .read()
+            .entities
+            .get(JOURNALS)
+            .and_then(|journals| journals.get(journal))
+            .map(|value| JournalTransactions::deserialize(value).map_err(entity_file_error))
+            .transpose()?
+            .ok_or_else(|| entity_not_found(journal))?;
+        select_transactions(query, transactions, || {
+            Ok(self.ledgers()?.list(&Query::default())?.into_items())
+        })
+    }
+
+    fn settings(&self) -> Result<StoreSettings, Error> {
+        Ok(self.data.read().settings.clone())
+    }
+
+    fn update_settings(&self, settings: &StoreSettings) -> Result<(), Error> {
+        self.data.write().settings = settings.clone();
+        Ok(())
+    }
+
+    fn permissions(&self) -> Result<StorePermissions, Error> {
+        Ok(self.data.read().permissions.clone())
+    }
+
+    fn update_permissions(&self, permissions: &StorePermissions) -> Result<(), Error> {
+        self.data.write().permissions = permissions.clone();
+        Ok(())
+    }
+
+    fn begin(&self) -> Result<Box<dyn UnitOfWork>, Error> {
+        Ok(Box::new(StagedUnitOfWork::new(MemoryStore {
+            data: self.data.clone(),
+        })))
+    }
+
+    fn disconnect(&self) -> Result<(), Error> {
+        match &self.data.snapshot {
+            Some(path) if self.data.changed.swap(false, Ordering::SeqCst) => {
+                save_snapshot(self, path).inspect_err(|_| {
+                    self.data.changed.store(true, Ordering::SeqCst);
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl MemoryStore {
+    fn new(snapshot: Option<PathBuf>) -> Self {
+        Self::new_with_contents(snapshot, Default::default())
+    }
+
+    fn new_with_contents(snapshot: Option<PathBuf>, contents: MemoryContents) -> Self {
+        Self {
+            data: Arc::new(MemoryData {
+                snapshot,
+                changed: AtomicBool::new(false),
+                contents: RwLock::new(contents),
+            }),
+        }
+    }
+
+    fn take_contents(&self) -> MemoryContents {
+        std::mem::take(&mut *self.data.write())
+    }
+}
+
+impl ApplyChanges for MemoryStore {
+    fn apply(&self, changes: Changes) -> Result<(), Error> {
+        let mut contents = self.data.write();
+        changes.validate(|table, id| {
+            Ok(contents
+                .entities
+                .get(table_name(table))
+                .map(|entities| entities.contains(id))
+                .unwrap_or_default())
+        })?;
+        let entities = changes
+            .entities
+            .into_iter()
+            .map(|((table, id), change)| {
+                let value = match change {
+                    Change::Create(value) | Change::Update(value) => {
+                        Some((sort_keys(table, &value)?, value))
+                    }
+                    Change::Delete => None,
+                };
+                Ok((table, id, value))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        for (table, id, value) in entities {
+            let entities = contents.entities.entry(table_name(table)).or_default();
+            match value {
+                Some((keys, value)) => entities.insert(id, keys, value),
+                None => {
+                    let _ = entities.remove(&id);
+                }
+            }
+        }
+        for (table, values) in changes.records {
+            contents
+                .records
+                .entry(table_name(table))
+                .or_default()
+                .extend(values);
+        }
+        Ok(())
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl Drop for MemoryData {
+    fn drop(&mut self) {
+        let changed = *self.changed.get_mut();
+        if let Some(path) = self.snapshot.take().filter(|_| changed) {
+            let contents = std::mem::take(
+                self.contents
+                    .get_mut()
+                    .unwrap_or_else(PoisonError::into_inner),
+            );
+            let store = MemoryStore::new_with_contents(None, contents);
+            if let Err(e) = save_snapshot(&store, &path) {
+                error!("Could not save store snapshot to {:?}; error: {}", path, e);
+            }
+        }
+    }
+}
+
+impl MemoryData {
+    ///
+    /// Lock the store's contents for reading. Every write replaces a whole value, so a panic
+    /// while the lock was held cannot leave a value half written and a poisoned lock is simply
+    /// taken over.
+    ///
+    fn read(&self) -> RwLockReadGuard<'_, MemoryContents> {
+        self.contents.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    ///
+    /// Lock the store's contents for writing, marking them as changed since the snapshot was
+    /// last saved.
+    ///
+    fn write(&self) -> RwLockWriteGuard<'_, MemoryContents> {
+        self.changed.store(true, Ordering::SeqCst);
+        self.contents
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+fn lock_stores() -> MutexGuard<'static, HashMap<String, Weak<MemoryData>>> {
+    STORES.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn registered(
+    stores: &HashMap<String, Weak<MemoryData>>,
+    connection_uri: &Url,
+) -> Option<Arc<MemoryData>> {
+    stores.get(connection_uri.path()).and_then(Weak::upgrade)
+}
+
+///
+/// Register a named store, dropping the names of stores that are no longer held.
+///
+fn register(
+    stores: &mut HashMap<String, Weak<MemoryData>>,
+    connection_uri: &Url,
+    data: &Arc<MemoryData>,
+) {
+    stores.retain(|_, data| data.strong_count() > 0);
+    let _ = stores.insert(connection_uri.path().to_string(), Arc::downgrade(data));
+}
+
+fn exists(stores: &HashMap<String, Weak<MemoryData>>, connection_uri: &Url) -> bool {
+    !connection_uri.path().is_empty()
+        && (registered(stores, connection_uri).is_some()
+            || snapshot_path(connection_uri)
+                .map(|path| path.is_file())
+                .unwrap_or_default())
+}
+
+fn table_name(table: Table) -> &'static str {
+    match table {
+        Table::Ledgers => LEDGERS,
+        Table::Journals => JOURNALS,
+        Table::ImportProfiles => PROFILES,
+        Table::Parties => PARTIES,
+        Table::Commodities => COMMODITIES,
+        Table::Prices => PRICES,
+        Table::Reconciliations => RECONCILIATIONS,
+    }
+}
+
+///
+/// The keys to index an entity staged as `value` by, read as the type `table` holds.
+///
+fn sort_keys(table: Table, value: &Value) -> Result<SortKeys, Error> {
+    match table {
+        Table::Ledgers => SortKeys::read::<LedgerKind, Ledger>(value),
+        Table::Journals => SortKeys::read::<String, Journal>(value),
+        Table::ImportProfiles => SortKeys::read::<String, CsvProfile>(value),
+        Table::Parties => SortKeys::read::<PartyId, Party>(value),
+        Table::Commodities | Table::Prices | Table::Reconciliations => {
+            unreachable!("records are not stored as entities")
+        }
+    }
+}
+
+fn snapshot_path(connection_uri: &Url) -> Option<PathBuf> {
+    connection_uri
+        .query_pairs()
+        .find(|(name, _)| name == SNAPSHOT_PARAMETER)
+        .map(|(_, path)| PathBuf::from(path.as_ref()))
+}
+
+///
+/// Save the store to its snapshot file. The archive is written and flushed to a temporary file
+/// beside the snapshot, which is then renamed over it, so that a failure part way through
+/// leaves the previous snapshot as it was.
+///
+fn save_snapshot(store: &MemoryStore, path: &Path) -> Result<(), Error> {
+    trace!("Saving store snapshot to {:?}", path);
+    let archive = StoreArchive::export_from(store)?;
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(format!(
+        ".{}-{}.tmp",
+        process::id(),
+        TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let temp_path = path.with_file_name(temp_name);
+    let result = fs::File::create(&temp_path)
+        .map_err(Error::from)
+        .and_then(|mut file| {
+            write_archive(&mut file, &archive, ArchiveFormat::from_path(path))?;
+            Ok(file.sync_all()?)
+        })
+        .and_then(|_| Ok(fs::rename(&temp_path, path)?));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
+
+mod entity;
+
+mod record;
@@ -0,0 +1,92 @@
+/*!
+Stores records without an identity of their own, such as prices, as JSON values in the order
+they were appended.
+
+*/
+
+use super::MemoryData;
+use crate::error::{entity_file_error, Error};
+use crate::RecordStore;
+use serde::{de::DeserializeOwned, Serialize};
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+pub(super) const COMMODITIES: &str = "commodities";
+
+pub(super) const PRICES: &str = "prices";
+
+pub(super) const RECONCILIATIONS: &str = "reconciliations";
+
+pub(super) struct MemoryRecordStore<R> {
+    data: Arc<MemoryData>,
+    table: &'static str,
+    record: PhantomData<fn() -> R>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl<R> MemoryRecordStore<R> {
+    pub(super) fn open(data: Arc<MemoryData>, table: &'static str) -> Self {
+        Self {
+            data,
+            table,
+            record: PhantomData,
+        }
+    }
+}
+
+impl<R> RecordStore<R> for MemoryRecordStore<R>
+where
+    R: Serialize + DeserializeOwned,
+{
+    fn list(&self) -> Result<Vec<R>, Error> {
+        self.data
+            .read()
+            .records
+            .get(self.table)
+            .into_iter()
+            .flatten()
+            .map(|value| R::deserialize(value).map_err(entity_file_error))
+            .collect()
+    }
+
+    fn append(&self, records: Vec<R>) -> Result<(), Error> {
+        let values = records
+            .iter()
+            .map(|record| serde_json::to_value(record).map_err(entity_file_error))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.data
+            .write()
+            .records
+            .entry(self.table)
+            .or_default()
+            .extend(values);
+        Ok(())
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
@@ -0,0 +1,582 @@
+/*!
+Provides the query used to list entities and transactions a page at a time.
+
+A [`Query`] selects items by a date range and any number of [`Filter`]s, orders them by a
+[`SortKey`], and returns at most `limit` of them as a [`Page`]. When more items remain, the
+page carries a [`Cursor`] that is given to the next query to continue after the last item
+returned. Items are always ordered by the sort key and then by identifier, so paging never
+returns an item twice or skips one that existed for the whole of the paging.
+
+For entities the date is the date the entity was created, the label is the entity's label, and
+only the [`Filter::Text`] filter applies. For transactions the date is the posted date, the
+label is the description, and the text filter also matches the reference.
+
+# Example
+
+```rust,no_run
+use sledge_model::ledger::AccountId;
+use sledge_store::get_current_datastore;
+use sledge_store::query::{Filter, Query, SortKey, SortOrder};
+use url::Url;
+
+let store = get_current_datastore(&Url::parse("sqlite:///home/me/books.db").unwrap()).unwrap();
+let mut query = Query::default()
+    .with_limit(100)
+    .sorted_by(SortKey::Date, SortOrder::Descending)
+    .with_filter(Filter::Account(AccountId::from("1000")));
+loop {
+    let page = store.transactions("general", &query).unwrap();
+    for transaction in page.items() {
+        println!("{} {:?}", transaction.posted(), transaction.description());
+    }
+    match page.next() {
+        Some(cursor) => query = query.after(cursor.clone()),
+        None => break,
+    }
+}
+```
+
+*/
+
+use crate::error::{invalid_cursor, unsupported_filter, Error};
+use crate::Entity;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use sledge_model::journal::Transaction;
+use sledge_model::ledger::{AccountId, AccountRepresents, Ledger};
+use sledge_model::party::PartyId;
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+use std::fmt::Display;
+use std::hash::Hash;
+use std::str::FromStr;
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SortKey {
+    #[default]
+    Identifier,
+    Label,
+    Date,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SortOrder {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+///
+/// A predicate that every selected item must satisfy.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Filter {
+    /// Transactions with a split posted to the account.
+    Account(AccountId),
+    /// Transactions with a split posted to an account representing the party, as a customer
+    /// or a supplier, in any ledger.
+    Party(PartyId),
+    /// Items whose label contains the text, ignoring case.
+    Text(String),
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Query {
+    limit: Option<usize>,
+    after: Option<Cursor>,
+    sort: SortKey,
+    order: SortOrder,
+    from: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    filters: Vec<Filter>,
+}
+
+///
+/// The position of the last item of a page, used to continue a query after it. The string form
+/// of a cursor may be handed to a client and parsed back, but should not be interpreted.
+///
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cursor {
+    sort: SortKey,
+    order: SortOrder,
+    key: String,
+    id: String,
+    position: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Page<T> {
+    items: Vec<T>,
+    next: Option<Cursor>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+///
+/// Does `value` contain `text`, ignoring case. This is the only comparison used for text
+/// filters; the SQLite backend registers it as a function so that every backend selects the
+/// same items.
+///
+pub(crate) fn contains_text(value: &str, text: &str) -> bool {
+    value.to_lowercase().contains(&text.to_lowercase())
+}
+
+///
+/// Does an account representing `represents` belong to `party`.
+///
+pub(crate) fn represents_party(represents: &AccountRepresents, party: &PartyId) -> bool {
+    match represents {
+        AccountRepresents::Customer(customer) => customer.party() == party,
+        AccountRepresents::Supplier(supplier) => supplier.party() == party,
+        _ => false,
+    }
+}
+
+///
+/// The identifiers of all accounts, in any of `ledgers`, that represent `party`.
+///
+pub(crate) fn party_accounts<'a, L>(ledgers: L, party: &PartyId) -> BTreeSet<String>
+where
+    L: IntoIterator<Item = &'a Ledger>,
+{
+    ledgers
+        .into_iter()
+        .flat_map(|ledger| {
+            ledger.accounts_representing(|represents| represents_party(represents, party))
+        })
+        .map(|account| account.id().to_string())
+        .collect()
+}
+
+///
+/// Select a page of `entities` in memory, for backends that cannot query their storage
+/// directly.
+///
+pub(crate) fn select_entities<I, E>(query: &Query, entities: Vec<E>) -> Result<Page<E>, Error>
+where
+    I: Display + Eq + Hash,
+    E: Entity<I>,
+{
+    let predicates = query.entity_predicates()?;
+    query.select(
+        entities.into_iter().map(|entity| Row {
+            id: entity.identifier().to_string(),
+            position: 0,
+            label: entity.label().clone(),
+            date: entity.created(),
+            item: entity,
+        }),
+        &predicates,
+        |row, predicate| label_matches(&row.label, predicate),
+    )
+}
+
+///
+/// Does an entity with `label` satisfy `predicate`; only text filters apply to entities.
+///
+pub(crate) fn label_matches(label: &str, predicate: &Predicate) -> bool {
+    match predicate {
+        Predicate::Text(text) => contains_text(label, text),
+        Predicate::Accounts(_) => false,
+    }
+}
+
+///
+/// A date as the string it is ordered by, written with a fixed width so that the order of the
+/// strings is the order of the dates.
+///
+pub(crate) fn date_key(date: DateTime<Utc>) -> String {
+    date.to_rfc3339_opts(SecondsFormat::Nanos, true)
+}
+
+///
+/// Select a page of a journal's `transactions` in memory, for backends that cannot query their
+/// storage directly; `ledgers` are only read when a party filter needs them.
+///
+pub(crate) fn select_transactions<L>(
+    query: &Query,
+    transactions: Vec<Transaction>,
+    ledgers: L,
+) -> Result<Page<Transaction>, Error>
+where
+    L: FnOnce() -> Result<Vec<Ledger>, Error>,
+{
+    let mut ledgers = Some(ledgers);
+    let mut read: Vec<Ledger> = Vec::new();
+    let predicates = query.transaction_predicates(|party| {
+        if let Some(ledgers) = ledgers.take() {
+            read = ledgers()?;
+        }
+        Ok(party_accounts(&read, party))
+    })?;
+    query.select(
+        transactions
+            .into_iter()
+            .enumerate()
+            .map(|(position, transaction)| Row {
+                id: transaction.id().to_string(),
+                position,
+                label: transaction.description().cloned().unwrap_or_default(),
+                date: transaction.posted(),
+                item: transaction,
+            }),
+        &predicates,
+        |row, predicate| match predicate {
+            Predicate::Accounts(accounts) => row
+                .item
+                .splits()
+                .any(|split| accounts.contains(split.account().as_str())),
+            Predicate::Text(text) => {
+                contains_text(&row.label, text)
+                    || row
+                        .item
+                        .reference()
+                        .map(|reference| contains_text(reference, text))
+                        .unwrap_or_default()
+            }
+        },
+    )
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// Only the transactions of a journal, read from its serialized form without the rest of it.
+///
+#[derive(Debug, Deserialize)]
+pub(crate) struct JournalTransactions {
+    pub(crate) transactions: Vec<Transaction>,
+}
+
+///
+/// A filter as a backend applies it, with any party already resolved to its accounts.
+///
+#[derive(Clone, Debug)]
+pub(crate) enum Predicate {
+    Accounts(BTreeSet<String>),
+    Text(String),
+}
+
+///
+/// An item together with the values a query selects and orders it by; `position` orders items
+/// with the same identifier, such as two transactions in one journal, and is zero for entities.
+///
+#[derive(Debug)]
+pub(crate) struct Row<T> {
+    pub(crate) id: String,
+    pub(crate) position: usize,
+    pub(crate) label: String,
+    pub(crate) date: DateTime<Utc>,
+    pub(crate) item: T,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl Query {
+    pub fn with_limit(self, limit: usize) -> Self {
+        Self {
+            limit: Some(limit),
+            ..self
+        }
+    }
+
+    ///
+    /// Continue from the cursor returned with a previous page; the query must otherwise be
+    /// the same as the one that returned the cursor.
+    ///
+    pub fn after(self, cursor: Cursor) -> Self {
+        Self {
+            after: Some(cursor),
+            ..self
+        }
+    }
+
+    pub fn sorted_by(self, sort: SortKey, order: SortOrder) -> Self {
+        Self {
+            sort,
+            order,
+            ..self
+        }
+    }
+
+    ///
+    /// Select items dated on or after `from` and before `until`.
+    ///
+    pub fn with_date_range(
+        self,
+        from: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+    ) -> Self {
+        Self {
+            from,
+            until,
+            ..self
+        }
+    }
+
+    pub fn with_filter(mut self, filter: Filter) -> Self {
+        self.filters.push(filter);
+        self
+    }
+
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    pub fn cursor(&self) -> Option<&Cursor> {
+        self.after.as_ref()
+    }
+
+    pub fn sort(&self) -> SortKey {
+        self.sort
+    }
+
+    pub fn order(&self) -> SortOrder {
+        self.order
+    }
+
+    pub fn from(&self) -> Option<DateTime<Utc>> {
+        self.from
+    }
+
+    pub fn until(&self) -> Option<DateTime<Utc>> {
+        self.until
+    }
+
+    pub fn filters(&self) -> impl Iterator<Item = &Filter> {
+        self.filters.iter()
+    }
+
+    ///
+    /// The cursor to continue after, checked to have come from a query with the same sort.
+    ///
+    pub(crate) fn checked_cursor(&self) -> Result<Option<&Cursor>, Error> {
+        match &self.after {
+            Some(cursor) if cursor.sort != self.sort || cursor.order != self.order => {
+                Err(invalid_cursor(cursor.to_string()))
+            }
+            cursor => Ok(cursor.as_ref()),
+        }
+    }
+
+    ///
+    /// The predicates for a list of entities, for which only text filters apply.
+    ///
+    pub(crate) fn entity_predicates(&self) -> Result<Vec<Predicate>, Error> {
+        self.filters
+            .iter()
+            .map(|filter| match filter {
+                Filter::Text(text) => Ok(Predicate::Text(text.clone())),
+                filter => Err(unsupported_filter(format!("{:?}", filter))),
+            })
+            .collect()
+    }
+
+    ///
+    /// The predicates for a list of transactions, using `party_accounts` to find the accounts
+    /// representing a party.
+    ///
+    pub(crate) fn transaction_predicates<F>(
+        &self,
+        mut party_accounts: F,
+    ) -> Result<Vec<Predicate>, Error>
+    where
+        F: FnMut(&PartyId) -> Result<BTreeSet<String>, Error>,
+    {
+        self.filters
+            .iter()
+            .map(|filter| match filter {
+                Filter::Account(account) => Ok(Predicate::Accounts(
+                    [account.to_string()].into_iter().collect(),
+                )),
+                Filter::Party(party) => Ok(Predicate::Accounts(party_accounts(party)?)),
+                Filter::Text(text) => Ok(Predicate::Text(text.clone())),
+            })
+            .collect()
+    }
+
+    ///
+    /// Is `date` within the date range of the query.
+    ///
+    pub(crate) fn includes_date(&self, date: DateTime<Utc>) -> bool {
+        self.from.map(|from| date >= from).unwrap_or(true)
+            && self.until.map(|until| date < until).unwrap_or(true)
+    }
+
+    ///
+    /// The value a row is ordered by before its identifier.
+    ///
+    pub(crate) fn sort_value<T>(&self, row: &Row<T>) -> String {
+        match self.sort {
+            SortKey::Identifier => row.id.clone(),
+            SortKey::Label => row.label.clone(),
+            SortKey::Date => date_key(row.date),
+        }
+    }
+
+    ///
+    /// Select, order, and page rows in memory, for backends that cannot query their storage
+    /// directly; `matches` applies a predicate to a row.
+    ///
+    pub(crate) fn select<T, M>(
+        &self,
+        rows: impl IntoIterator<Item = Row<T>>,
+        predicates: &[Predicate],
+        matches: M,
+    ) -> Result<Page<T>, Error>
+    where
+        M: Fn(&Row<T>, &Predicate) -> bool,
+    {
+        let after = self
+            .checked_cursor()?
+            .map(|cursor| (cursor.key.clone(), cursor.id.clone(), cursor.position));
+        let mut selected: Vec<((String, String, usize), Row<T>)> = rows
+            .into_iter()
+            .filter(|row| self.includes_date(row.date))
+            .filter(|row| predicates.iter().all(|predicate| matches(row, predicate)))
+            .map(|row| ((self.sort_value(&row), row.id.clone(), row.position), row))
+            .filter(|(key, _)| match &after {
+                Some(after) => self.is_after(key.cmp(after)),
+                None => true,
+            })
+            .collect();
+        selected.sort_by(|(lhs, _), (rhs, _)| match self.order {
+            SortOrder::Ascending => lhs.cmp(rhs),
+            SortOrder::Descending => rhs.cmp(lhs),
+        });
+        if let Some(limit) = self.limit {
+            selected.truncate(limit + 1);
+        }
+        Ok(self.page(selected.into_iter().map(|(_, row)| row).collect()))
+    }
+
+    ///
+    /// Make a page from rows already selected and ordered, of which there may be one more than
+    /// the limit to show that the query has more to return.
+    ///
+    pub(crate) fn page<T>(&self, mut rows: Vec<Row<T>>) -> Page<T> {
+        let next = match self.limit {
+            Some(limit) if rows.len() > limit => {
+                rows.truncate(limit);
+                rows.last().map(|row| Cursor {
+                    sort: self.sort,
+                    order: self.order,
+                    key: self.sort_value(row),
+                    id: row.id.clone(),
+                    position: row.position,
+                })
+            }
+            _ => None,
+        };
+        Page {
+            items: rows.into_iter().map(|row| row.item).collect(),
+            next,
+        }
+    }
+
+    fn is_after(&self, ordering: Ordering) -> bool {
+        match self.order {
+            SortOrder::Ascending => ordering == Ordering::Greater,
+            SortOrder::Descending => ordering == Ordering::Less,
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl Display for Cursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            serde_json::to_string(self).map_err(|_| std::fmt::Error)?
+        )
+    }
+}
+
+impl FromStr for Cursor {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s).map_err(|_| invalid_cursor(s))
+    }
+}
+
+impl Cursor {
+    pub(crate) fn key(&self) -> &String {
+        &self.key
+    }
+
+    pub(crate) fn id(&self) -> &String {
+        &self.id
+    }
+
+    pub(crate) fn position(&self) -> usize {
+        self.position
+    }
+
+    ///
+    /// The key of a cursor from a query sorted by date, as a date.
+    ///
+    pub(crate) fn date(&self) -> Result<DateTime<Utc>, Error> {
+        DateTime::parse_from_rfc3339(&self.key)
+            .map(|date| date.with_timezone(&Utc))
+            .map_err(|_| invalid_cursor(self.to_string()))
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl<T> Page<T> {
+    pub fn items(&self) -> &Vec<T> {
+        &self.items
+    }
+
+    pub fn into_items(self) -> Vec<T> {
+        self.items
+    }
+
+    ///
+    /// The cursor to continue after this page, if the query has more items to return.
+    ///
+    pub fn next(&self) -> Option<&Cursor> {
+        self.next.as_ref()
+    }
+
+    pub fn is_last(&self) -> bool {
+        self.next.is_none()
+    }
+
+    pub(crate) fn try_map<U, F>(self, f: F) -> Result<Page<U>, Error>
+    where
+        F: FnOnce(Vec<T>) -> Result<Vec<U>, Error>,
+    {
+        Ok(Page {
+            items: f(self.items)?,
+            next: self.next,
+        })
+    }
+}
+
+impl<T> IntoIterator for Page<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
@@ -0,0 +1,152 @@
+/*!
+Stores entities that are never queried by their content, such as parties, as one JSON
+document per row alongside the entity's identifier, label, and created date.
+
+*/
+
+use super::query::select_entity_ids;
+use super::{lock, SharedConnection};
+use crate::error::{entity_exists, entity_file_error, entity_not_found, Error};
+use crate::query::{Page, Query};
+use crate::{Entity, EntityStore};
+use rusqlite::{params, OptionalExtension};
+use serde::{de::DeserializeOwned, Serialize};
+use std::fmt::Display;
+use std::hash::Hash;
+use std::marker::PhantomData;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+pub(super) const PARTIES_TABLE: &str = "parties";
+
+pub(super) const PROFILES_TABLE: &str = "import_profiles";
+
+pub(super) struct SqliteEntityStore<I, E> {
+    connection: SharedConnection,
+    table: &'static str,
+    entity: PhantomData<fn() -> (I, E)>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl<I, E> SqliteEntityStore<I, E> {
+    pub(super) fn open(connection: SharedConnection, table: &'static str) -> Self {
+        Self {
+            connection,
+            table,
+            entity: PhantomData,
+        }
+    }
+}
+
+impl<I, E> EntityStore<I, E> for SqliteEntityStore<I, E>
+where
+    I: Clone + Display + Eq + Hash,
+    E: Entity<I> + Serialize + DeserializeOwned,
+{
+    fn create(&self, entity: E) -> Result<I, Error> {
+        let id = entity.identifier().clone();
+        self.create_with_id(entity, id.clone())?;
+        Ok(id)
+    }
+
+    fn create_with_id(&self, entity: E, id: I) -> Result<(), Error> {
+        let document = serde_json::to_string(&entity).map_err(entity_file_error)?;
+        let inserted = lock(&self.connection).execute(
+            &format!(
+                "INSERT INTO {} (id, label, created, document) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT (id) DO NOTHING",
+                self.table
+            ),
+            params![id.to_string(), entity.label(), entity.created(), document],
+        )?;
+        if inserted == 0 {
+            Err(entity_exists(id.to_string()))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn list(&self, query: &Query) -> Result<Page<E>, Error> {
+        let connection = lock(&self.connection);
+        let page = select_entity_ids(&connection, query, self.table, "id", "label")?;
+        let mut statement = connection.prepare(&format!(
+            "SELECT document FROM {} WHERE id = ?1",
+            self.table
+        ))?;
+        page.try_map(|ids| {
+            ids.iter()
+                .map(|id| {
+                    let document: String = statement.query_row([id], |row| row.get(0))?;
+                    serde_json::from_str(&document).map_err(entity_file_error)
+                })
+                .collect()
+        })
+    }
+
+    fn get_by_id(&self, id: &I) -> Result<Option<E>, Error> {
+        let document: Option<String> = lock(&self.connection)
+            .query_row(
+                &format!("SELECT document FROM {} WHERE id = ?1", self.table),
+                [id.to_string()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        document
+            .map(|document| serde_json::from_str(&document).map_err(entity_file_error))
+            .transpose()
+    }
+
+    fn update(&self, entity: E) -> Result<(), Error> {
+        let id = entity.identifier();
+        let document = serde_json::to_string(&entity).map_err(entity_file_error)?;
+        let updated = lock(&self.connection).execute(
+            &format!(
+                "UPDATE {} SET label = ?2, created = ?3, document = ?4 WHERE id = ?1",
+                self.table
+            ),
+            params![id.to_string(), entity.label(), entity.created(), document],
+        )?;
+        if updated == 0 {
+            Err(entity_not_found(id.to_string()))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn delete(&self, id: &I) -> Result<(), Error> {
+        let deleted = lock(&self.connection).execute(
+            &format!("DELETE FROM {} WHERE id = ?1", self.table),
+            [id.to_string()],
+        )?;
+        if deleted == 0 {
+            Err(entity_not_found(id.to_string()))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
@@ -0,0 +1,522 @@
+/*!
+Stores journals in the `journals` table, their transactions in the `transactions` table, and
+the splits of each transaction in the `splits` table.
+
+Each transaction keeps the position it was first written at for as long as it is in the
+journal, so that a cursor naming it stays valid, and transactions are read back in posted
+order, those posted at the same time in order of position. A journal is always written in one
+database transaction, so that a reader never sees a transaction without its splits; an update
+only writes the transactions that were added, changed, or removed.
+
+*/
+
+use super::query::{select_entity_ids, Selection};
+use super::SharedConnection;
+use super::{begin, json_column, lock, optional_text_column, text_column, to_json, to_text};
+use crate::error::{entity_exists, entity_file_error, entity_not_found, Error};
+use crate::query::{represents_party, Page, Predicate, Query};
+use crate::EntityStore;
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::types::Type;
+use rusqlite::{params, Connection};
+use rust_decimal::Decimal;
+use sledge_model::audit::Signature;
+use sledge_model::commodity::Quantity;
+use sledge_model::journal::{Journal, Split, Transaction};
+use sledge_model::ledger::AccountRepresents;
+use sledge_model::party::PartyId;
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+pub(super) struct SqliteJournalStore {
+    connection: SharedConnection,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+pub(super) fn insert_journal(connection: &Connection, journal: &Journal) -> Result<(), Error> {
+    insert_journal_with_id(connection, journal, journal.name())
+}
+
+///
+/// Select a page of the transactions of `journal`, reading only the rows of that page.
+///
+pub(super) fn select_transactions(
+    connection: &Connection,
+    journal: &str,
+    query: &Query,
+) -> Result<Page<Transaction>, Error> {
+    let exists: i64 = connection.query_row(
+        "SELECT COUNT(*) FROM journals WHERE name = ?1",
+        [journal],
+        |row| row.get(0),
+    )?;
+    if exists == 0 {
+        return Err(entity_not_found(journal));
+    }
+
+    let mut selection = Selection::new(
+        "transactions",
+        "id",
+        Some("position"),
+        "COALESCE(description, '')",
+        "posted",
+    )
+    .with_condition("journal = ?", vec![Box::new(journal.to_string())]);
+    for predicate in query.transaction_predicates(|party| party_accounts(connection, party))? {
+        selection = match predicate {
+            Predicate::Accounts(accounts) => selection.with_condition(
+                "EXISTS (
+                    SELECT 1 FROM splits
+                    WHERE splits.journal = transactions.journal
+                      AND splits.transaction_position = transactions.position
+                      AND splits.account IN (SELECT value FROM json_each(?))
+                 )",
+                vec![Box::new(to_json(Some(&accounts))?)],
+            ),
+            Predicate::Text(text) => selection.with_condition(
+                "contains_text(COALESCE(description, ''), ?)
+                 OR contains_text(COALESCE(reference, ''), ?)",
+                vec![Box::new(text.clone()), Box::new(text)],
+            ),
+        };
+    }
+
+    selection.select(connection, query)?.try_map(|rows| {
+        let positions: Vec<usize> = rows.iter().map(|(_, position)| *position).collect();
+        let mut transactions: BTreeMap<usize, Transaction> =
+            read_transactions(connection, journal, Some(&positions))?
+                .into_iter()
+                .collect();
+        Ok(positions
+            .iter()
+            .filter_map(|position| transactions.remove(position))
+            .collect())
+    })
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// A journal as read from its own row, before its transactions are added; the locks are only
+/// applied once all transactions are in place.
+///
+struct JournalRow {
+    journal: Journal,
+    read_only_after: Option<i64>,
+    locked_until: Option<DateTime<Utc>>,
+    signature: Option<Signature>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl SqliteJournalStore {
+    pub(super) fn open(connection: SharedConnection) -> Self {
+        Self { connection }
+    }
+}
+
+impl EntityStore<String, Journal> for SqliteJournalStore {
+    fn create(&self, entity: Journal) -> Result<String, Error> {
+        let id = entity.name().clone();
+        self.create_with_id(entity, id.clone())?;
+        Ok(id)
+    }
+
+    fn create_with_id(&self, entity: Journal, id: String) -> Result<(), Error> {
+        let mut connection = lock(&self.connection);
+        let transaction = begin(&mut connection)?;
+        insert_journal_with_id(&transaction, &entity, &id)?;
+        Ok(transaction.commit()?)
+    }
+
+    fn list(&self, query: &Query) -> Result<Page<Journal>, Error> {
+        let connection = lock(&self.connection);
+        select_entity_ids(&connection, query, "journals", "name", "name")?.try_map(|ids| {
+            ids.iter()
+                .map(|id| {
+                    read_journals(&connection, Some(id))?
+                        .pop()
+                        .ok_or_else(|| entity_not_found(id))
+                })
+                .collect()
+        })
+    }
+
+    fn get_by_id(&self, id: &String) -> Result<Option<Journal>, Error> {
+        Ok(read_journals(&lock(&self.connection), Some(id))?.pop())
+    }
+
+    fn update(&self, entity: Journal) -> Result<(), Error> {
+        let mut connection = lock(&self.connection);
+        let transaction = begin(&mut connection)?;
+        update_journal(&transaction, &entity)?;
+        Ok(transaction.commit()?)
+    }
+
+    fn delete(&self, id: &String) -> Result<(), Error> {
+        if lock(&self.connection).execute("DELETE FROM journals WHERE name = ?1", [id])? == 0 {
+            Err(entity_not_found(id))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+fn insert_journal_with_id(
+    connection: &Connection,
+    journal: &Journal,
+    id: &str,
+) -> Result<(), Error> {
+    let inserted = connection.execute(
+        "INSERT INTO journals (name, created, read_only_after, locked_until, currency, signature)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT (name) DO NOTHING",
+        params![
+            id,
+            journal.created(),
+            journal.read_only_after().map(|after| after.num_seconds()),
+            journal.locked_until(),
+            to_text(&journal.currency())?,
+            to_json(journal.signature())?,
+        ],
+    )?;
+    if inserted == 0 {
+        return Err(entity_exists(id));
+    }
+
+    for (position, transaction) in journal.transactions().enumerate() {
+        insert_transaction(connection, id, position, transaction)?;
+    }
+    Ok(())
+}
+
+///
+/// Update the row of a journal and the rows of only those of its transactions that differ
+/// from the stored ones. A stored transaction equal to one in the journal is kept as it is;
+/// one that is not, but shares an identifier with one in the journal, is rewritten in place;
+/// any others are removed, and the remaining transactions of the journal added after the last
+/// position in use.
+///
+fn update_journal(connection: &Connection, journal: &Journal) -> Result<(), Error> {
+    let id = journal.name();
+    let updated = connection.execute(
+        "UPDATE journals
+         SET created = ?2, read_only_after = ?3, locked_until = ?4, currency = ?5,
+             signature = ?6
+         WHERE name = ?1",
+        params![
+            id,
+            journal.created(),
+            journal.read_only_after().map(|after| after.num_seconds()),
+            journal.locked_until(),
+            to_text(&journal.currency())?,
+            to_json(journal.signature())?,
+        ],
+    )?;
+    if updated == 0 {
+        return Err(entity_not_found(id));
+    }
+
+    let stored = read_transactions(connection, id, None)?;
+    let mut next_position = stored
+        .iter()
+        .map(|(position, _)| position + 1)
+        .max()
+        .unwrap_or_default();
+    let mut unchanged: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for (position, transaction) in stored.iter().rev() {
+        unchanged
+            .entry(serde_json::to_string(transaction).map_err(entity_file_error)?)
+            .or_default()
+            .push(*position);
+    }
+    let mut changed = Vec::new();
+    for transaction in journal.transactions() {
+        let document = serde_json::to_string(transaction).map_err(entity_file_error)?;
+        if unchanged.get_mut(&document).and_then(Vec::pop).is_none() {
+            changed.push(transaction);
+        }
+    }
+
+    let mut stale: BTreeSet<usize> = unchanged.into_values().flatten().collect();
+    let mut stale_by_id: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for (position, transaction) in stored.iter().rev() {
+        if stale.contains(position) {
+            stale_by_id
+                .entry(transaction.id().as_str())
+                .or_default()
+                .push(*position);
+        }
+    }
+    for transaction in changed {
+        let position = match stale_by_id
+            .get_mut(transaction.id().as_str())
+            .and_then(Vec::pop)
+        {
+            Some(position) => {
+                let _ = stale.remove(&position);
+                delete_transaction(connection, id, position)?;
+                position
+            }
+            None => {
+                next_position += 1;
+                next_position - 1
+            }
+        };
+        insert_transaction(connection, id, position, transaction)?;
+    }
+    for position in stale {
+        delete_transaction(connection, id, position)?;
+    }
+    Ok(())
+}
+
+///
+/// Remove a transaction, and with it its splits.
+///
+fn delete_transaction(
+    connection: &Connection,
+    journal: &str,
+    position: usize,
+) -> Result<(), Error> {
+    let _ = connection
+        .prepare_cached("DELETE FROM transactions WHERE journal = ?1 AND position = ?2")?
+        .execute(params![journal, position])?;
+    Ok(())
+}
+
+fn insert_transaction(
+    connection: &Connection,
+    journal: &str,
+    position: usize,
+    transaction: &Transaction,
+) -> Result<(), Error> {
+    let metadata: BTreeMap<&String, &String> = transaction.metadata().collect();
+    let _ = connection
+        .prepare_cached(
+            "INSERT INTO transactions (
+                journal, position, id, posted, entered, action, reference, description,
+                metadata, signature
+             ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        )?
+        .execute(params![
+            journal,
+            position,
+            transaction.id().as_str(),
+            transaction.posted(),
+            transaction.entered(),
+            to_json(transaction.action())?,
+            transaction.reference(),
+            transaction.description(),
+            to_json(Some(&metadata))?,
+            to_json(transaction.signature())?,
+        ])?;
+    let mut splits = connection.prepare_cached(
+        "INSERT INTO splits (
+            journal, transaction_position, position, id, account, commodity, quantity,
+            exchanged_from, description, tax_code
+         ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+    )?;
+    for (split_position, split) in transaction.splits().enumerate() {
+        let _ = splits.execute(params![
+            journal,
+            position,
+            split_position,
+            split.id().as_str(),
+            split.account().as_str(),
+            to_text(split.quantity().commodity())?,
+            split.quantity().quantity().to_string(),
+            to_json(split.exchanged_from())?,
+            split.description(),
+            split.tax_code().map(to_text).transpose()?,
+        ])?;
+    }
+    Ok(())
+}
+
+///
+/// Read every journal in order of name, or only the one named `id`.
+///
+fn read_journals(connection: &Connection, id: Option<&String>) -> Result<Vec<Journal>, Error> {
+    let mut statement = connection.prepare(
+        "SELECT name, created, read_only_after, locked_until, currency, signature
+         FROM journals
+         WHERE ?1 IS NULL OR name = ?1
+         ORDER BY name",
+    )?;
+    let rows = statement
+        .query_map([id], |row| {
+            Ok(JournalRow {
+                journal: Journal::new(row.get::<_, String>(0)?, text_column(row, 4)?)
+                    .with_created(row.get(1)?),
+                read_only_after: row.get(2)?,
+                locked_until: row.get(3)?,
+                signature: json_column(row, 5)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut results = Vec::with_capacity(rows.len());
+    for row in rows {
+        let JournalRow {
+            mut journal,
+            read_only_after,
+            locked_until,
+            signature,
+        } = row;
+        for (_, transaction) in read_transactions(connection, journal.name(), None)? {
+            // The journal has no locks until all of its transactions have been added.
+            journal
+                .add_transaction(transaction)
+                .expect("an unlocked journal accepts every transaction");
+        }
+
+        if let Some(read_only_after) = read_only_after {
+            journal = journal.with_read_only_after(Duration::seconds(read_only_after));
+        }
+        if let Some(locked_until) = locked_until {
+            journal.lock_until(locked_until);
+        }
+        if let Some(signature) = signature {
+            journal = journal.with_signature(signature);
+        }
+        results.push(journal);
+    }
+    Ok(results)
+}
+
+///
+/// Read the transactions of a journal, with their splits, in posted order and then order of
+/// position; either all of them or only those at `positions`.
+///
+fn read_transactions(
+    connection: &Connection,
+    journal: &str,
+    positions: Option<&[usize]>,
+) -> Result<Vec<(usize, Transaction)>, Error> {
+    let positions = positions
+        .map(|positions| to_json(Some(&positions)))
+        .transpose()?;
+    let mut splits = connection.prepare_cached(
+        "SELECT transaction_position, id, account, commodity, quantity, exchanged_from,
+                description, tax_code
+         FROM splits
+         WHERE journal = ?1
+           AND (?2 IS NULL OR transaction_position IN (SELECT value FROM json_each(?2)))
+         ORDER BY transaction_position, position",
+    )?;
+    let splits = splits
+        .query_map(params![journal, positions], |row| {
+            let mut split = Split::new(
+                text_column(row, 1)?,
+                text_column(row, 2)?,
+                Quantity::new(text_column(row, 3)?, decimal_column(row, 4)?),
+            );
+            if let Some(exchanged_from) = json_column(row, 5)? {
+                split = split.with_exchanged_from(exchanged_from);
+            }
+            if let Some(description) = row.get::<_, Option<String>>(6)? {
+                split = split.with_description(description);
+            }
+            if let Some(tax_code) = optional_text_column(row, 7)? {
+                split = split.with_tax_code(tax_code);
+            }
+            Ok((row.get::<_, i64>(0)? as usize, split))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut splits_by_position: BTreeMap<usize, Vec<Split>> = BTreeMap::new();
+    for (position, split) in splits {
+        splits_by_position.entry(position).or_default().push(split);
+    }
+
+    let mut transactions = connection.prepare_cached(
+        "SELECT position, id, posted, entered, action, reference, description, metadata,
+                signature
+         FROM transactions
+         WHERE journal = ?1
+           AND (?2 IS NULL OR position IN (SELECT value FROM json_each(?2)))
+         ORDER BY posted, position",
+    )?;
+    let mut transactions = transactions
+        .query_map(params![journal, positions], |row| {
+            let mut transaction =
+                Transaction::new(text_column(row, 1)?, row.get(2)?).with_entered(row.get(3)?);
+            if let Some(action) = json_column(row, 4)? {
+                transaction = transaction.with_action(action);
+            }
+            if let Some(reference) = row.get::<_, Option<String>>(5)? {
+                transaction = transaction.with_reference(reference);
+            }
+            if let Some(description) = row.get::<_, Option<String>>(6)? {
+                transaction = transaction.with_description(description);
+            }
+            let metadata: BTreeMap<String, String> = json_column(row, 7)?.unwrap_or_default();
+            for (key, value) in metadata {
+                transaction = transaction.with_metadata(key, value);
+            }
+            if let Some(signature) = json_column(row, 8)? {
+                transaction = transaction.with_signature(signature);
+            }
+            Ok((row.get::<_, i64>(0)? as usize, transaction))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for (position, transaction) in &mut transactions {
+        for split in splits_by_position.remove(position).unwrap_or_default() {
+            transaction.add_split(split);
+        }
+    }
+    Ok(transactions)
+}
+
+///
+/// The identifiers of all accounts, in any ledger, that represent `party`.
+///
+fn party_accounts(connection: &Connection, party: &PartyId) -> Result<BTreeSet<String>, Error> {
+    let mut statement =
+        connection.prepare("SELECT id, represents FROM accounts WHERE represents IS NOT NULL")?;
+    let accounts = statement
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                json_column::<AccountRepresents>(row, 1)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(accounts
+        .into_iter()
+        .filter_map(|(id, represents)| {
+            represents
+                .filter(|represents| represents_party(represents, party))
+                .map(|_| id)
+        })
+        .collect())
+}
+
+fn decimal_column(row: &rusqlite::Row<'_>, index: usize) -> rusqlite::Result<Decimal> {
+    Decimal::from_str(&row.get::<_, String>(index)?)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, Type::Text, Box::new(e)))
+}
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
@@ -0,0 +1,215 @@
+/*!
+Stores ledgers in the `ledgers` table and their book of accounts in the `accounts` table, in the
+order the accounts were added to the ledger.
+
+*/
+
+use super::query::select_entity_ids;
+use super::SharedConnection;
+use super::{begin, json_column, lock, optional_text_column, text_column, to_json, to_text};
+use crate::error::{entity_exists, entity_not_found, Error};
+use crate::query::{Page, Query};
+use crate::EntityStore;
+use rusqlite::{params, Connection};
+use sledge_model::ledger::{Account, Ledger, LedgerKind};
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+pub(super) struct SqliteLedgerStore {
+    connection: SharedConnection,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+pub(super) fn insert_ledger(connection: &Connection, ledger: &Ledger) -> Result<(), Error> {
+    insert_ledger_with_id(connection, ledger, &ledger.kind().to_string())
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl SqliteLedgerStore {
+    pub(super) fn open(connection: SharedConnection) -> Self {
+        Self { connection }
+    }
+}
+
+impl EntityStore<LedgerKind, Ledger> for SqliteLedgerStore {
+    fn create(&self, entity: Ledger) -> Result<LedgerKind, Error> {
+        let id = entity.kind().clone();
+        self.create_with_id(entity, id.clone())?;
+        Ok(id)
+    }
+
+    fn create_with_id(&self, entity: Ledger, id: LedgerKind) -> Result<(), Error> {
+        let mut connection = lock(&self.connection);
+        let transaction = begin(&mut connection)?;
+        insert_ledger_with_id(&transaction, &entity, &id.to_string())?;
+        Ok(transaction.commit()?)
+    }
+
+    fn list(&self, query: &Query) -> Result<Page<Ledger>, Error> {
+        let connection = lock(&self.connection);
+        select_entity_ids(&connection, query, "ledgers", "id", "description")?.try_map(|ids| {
+            ids.iter()
+                .map(|id| {
+                    read_ledgers(&connection, Some(id))?
+                        .pop()
+                        .ok_or_else(|| entity_not_found(id))
+                })
+                .collect()
+        })
+    }
+
+    fn get_by_id(&self, id: &LedgerKind) -> Result<Option<Ledger>, Error> {
+        Ok(read_ledgers(&lock(&self.connection), Some(&id.to_string()))?.pop())
+    }
+
+    fn update(&self, entity: Ledger) -> Result<(), Error> {
+        let id = entity.kind().to_string();
+        let mut connection = lock(&self.connection);
+        let transaction = begin(&mut connection)?;
+        if transaction.execute("DELETE FROM ledgers WHERE id = ?1", [&id])? == 0 {
+            return Err(entity_not_found(id));
+        }
+        insert_ledger_with_id(&transaction, &entity, &id)?;
+        Ok(transaction.commit()?)
+    }
+
+    fn delete(&self, id: &LedgerKind) -> Result<(), Error> {
+        let id = id.to_string();
+        if lock(&self.connection).execute("DELETE FROM ledgers WHERE id = ?1", [&id])? == 0 {
+            Err(entity_not_found(id))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+fn insert_ledger_with_id(connection: &Connection, ledger: &Ledger, id: &str) -> Result<(), Error> {
+    let inserted = connection.execute(
+        "INSERT INTO ledgers (id, kind, created, description, currency, concept_mapping)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT (id) DO NOTHING",
+        params![
+            id,
+            to_text(ledger.kind())?,
+            ledger.created(),
+            ledger.description(),
+            to_text(&ledger.currency())?,
+            to_json(ledger.concept_mapping())?,
+        ],
+    )?;
+    if inserted == 0 {
+        return Err(entity_exists(id));
+    }
+
+    let mut statement = connection.prepare(
+        "INSERT INTO accounts (
+            ledger_id, id, position, created, is_active, parent_id, kind, description,
+            commodity, is_recording, represents
+         ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+    )?;
+    for (position, account) in ledger.book().enumerate() {
+        let _ = statement.execute(params![
+            id,
+            account.id().as_str(),
+            position,
+            account.created(),
+            account.is_active(),
+            account.parent_id().map(|parent_id| parent_id.as_str()),
+            to_text(&account.kind())?,
+            account.description(),
+            to_text(account.commodity())?,
+            account.is_recording(),
+            to_json(account.represents())?,
+        ])?;
+    }
+    Ok(())
+}
+
+///
+/// Read every ledger in order of identifier, or only the one with identifier `id`.
+///
+fn read_ledgers(connection: &Connection, id: Option<&str>) -> Result<Vec<Ledger>, Error> {
+    let mut statement = connection.prepare(
+        "SELECT id, kind, created, description, currency, concept_mapping
+         FROM ledgers
+         WHERE ?1 IS NULL OR id = ?1
+         ORDER BY id",
+    )?;
+    let ledgers = statement
+        .query_map([id], |row| {
+            let ledger = Ledger::new(
+                text_column(row, 1)?,
+                row.get::<_, String>(3)?,
+                text_column(row, 4)?,
+            )
+            .with_created(row.get(2)?);
+            let ledger = match json_column(row, 5)? {
+                Some(concept_mapping) => ledger.with_concept_mapping(concept_mapping),
+                None => ledger,
+            };
+            Ok((row.get::<_, String>(0)?, ledger))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut statement = connection.prepare(
+        "SELECT id, created, is_active, parent_id, kind, description, commodity, is_recording,
+                represents
+         FROM accounts
+         WHERE ledger_id = ?1
+         ORDER BY position",
+    )?;
+    let mut results = Vec::with_capacity(ledgers.len());
+    for (id, mut ledger) in ledgers {
+        let accounts = statement
+            .query_map([&id], |row| {
+                let mut account = Account::new(
+                    text_column(row, 0)?,
+                    text_column(row, 4)?,
+                    row.get::<_, String>(5)?,
+                    text_column(row, 6)?,
+                )
+                .with_created(row.get(1)?);
+                if let Some(parent_id) = optional_text_column(row, 3)? {
+                    account = account.with_parent(parent_id);
+                }
+                if let Some(represents) = json_column(row, 8)? {
+                    account = account.with_represents(represents);
+                }
+                if !row.get::<_, bool>(7)? {
+                    account = account.non_recording();
+                }
+                account.set_active(row.get(2)?);
+                Ok(account)
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        for account in accounts {
+            ledger.add_account(account);
+        }
+        results.push(ledger);
+    }
+    Ok(results)
+}
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
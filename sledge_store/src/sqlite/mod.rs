@@ -0,0 +1,615 @@
+/*!
+Provides a data store kept in a single SQLite database file, connected to with a URI such as
+`sqlite:///home/me/books.db`.
+
+Ledgers, accounts, journals, transactions, and splits are kept in their own tables, with
+indexes on the posted date of transactions and on the account of splits so that date-range
+and per-account queries do not need to read every journal. Parts of the model that are never
+queried on their own, such as signatures or what an account represents, are kept as JSON
+text in the row of the entity they belong to. Parties, import profiles, commodities, prices,
+and reconciled splits are kept as one JSON document per row.
+
+Every write that touches more than one row, for example a journal with its transactions and
+their splits, is made in a single database transaction so that a failed write leaves the
+store unchanged. A unit of work has a connection of its own, on which a database transaction
+is held open until it is committed; writes through its stores are then made within savepoints
+of that transaction. As the transaction takes the write lock as it begins, writes on other
+connections wait for the unit of work to finish.
+
+Text filters are applied by the function `contains_text`, registered on each connection, so
+that case is ignored by the same rule as in the other backends.
+
+*/
+
+use self::entity::{SqliteEntityStore, PARTIES_TABLE, PROFILES_TABLE};
+use self::journal::{insert_journal, select_transactions, SqliteJournalStore};
+use self::ledger::{insert_ledger, SqliteLedgerStore};
+use self::record::{
+    append_records, SqliteRecordStore, COMMODITIES_TABLE, PRICES_TABLE, RECONCILIATIONS_TABLE,
+};
+use crate::error::{entity_file_error, store_does_not_exist, store_exists, Error};
+use crate::query::{contains_text, Page, Query};
+use crate::{
+    CreateDatastoreContents, DataStore, EntityStore, RecordStore, StorePermissions, StoreSettings,
+    UnitOfWork,
+};
+use rusqlite::functions::FunctionFlags;
+use rusqlite::types::Type;
+use rusqlite::{params, Connection, OpenFlags, Row, Savepoint, TransactionBehavior};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use sledge_model::{
+    commodity::{RateRecord, Security},
+    import::CsvProfile,
+    journal::{Journal, Reconciled, Transaction},
+    ledger::{Ledger, LedgerKind},
+    party::{Party, PartyId},
+};
+use std::fs;
+use std::ops::Deref;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+use tracing::{error, trace};
+use url::Url;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+pub(super) const SQLITE_STORE_SCHEME: &str = "sqlite";
+
+pub(super) struct SqliteStore {
+    from_uri: Url,
+    connection: SharedConnection,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+type SharedConnection = Arc<Mutex<Connection>>;
+
+///
+/// A write made as a database transaction of its own or, within a unit of work, as a
+/// savepoint of the unit of work's transaction.
+///
+enum Write<'c> {
+    Transaction(rusqlite::Transaction<'c>),
+    Savepoint(Savepoint<'c>),
+}
+
+struct SqliteUnitOfWork {
+    connection: SharedConnection,
+}
+
+///
+/// How long a connection waits for another connection to the same file to finish writing.
+///
+const BUSY_TIMEOUT: Duration = Duration::from_secs(10);
+
+const SCHEMA: &str = r#"
+CREATE TABLE store (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    version TEXT NOT NULL,
+    created TEXT NOT NULL,
+    default_commodity TEXT,
+    permissions TEXT NOT NULL
+);
+
+CREATE TABLE ledgers (
+    id TEXT PRIMARY KEY,
+    kind TEXT NOT NULL,
+    created TEXT NOT NULL,
+    description TEXT NOT NULL,
+    currency TEXT NOT NULL,
+    concept_mapping TEXT
+);
+
+CREATE TABLE accounts (
+    ledger_id TEXT NOT NULL REFERENCES ledgers (id) ON DELETE CASCADE,
+    id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    created TEXT NOT NULL,
+    is_active INTEGER NOT NULL,
+    parent_id TEXT,
+    kind TEXT NOT NULL,
+    description TEXT NOT NULL,
+    commodity TEXT NOT NULL,
+    is_recording INTEGER NOT NULL,
+    represents TEXT,
+    PRIMARY KEY (ledger_id, id)
+);
+
+CREATE INDEX accounts_by_parent ON accounts (ledger_id, parent_id);
+
+CREATE TABLE journals (
+    name TEXT PRIMARY KEY,
+    created TEXT NOT NULL,
+    read_only_after INTEGER,
+    locked_until TEXT,
+    currency TEXT NOT NULL,
+    signature TEXT
+);
+
+CREATE TABLE transactions (
+    journal TEXT NOT NULL REFERENCES journals (name) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    id TEXT NOT NULL,
+    posted TEXT NOT NULL,
+    entered TEXT NOT NULL,
+    action TEXT,
+    reference TEXT,
+    description TEXT,
+    metadata TEXT NOT NULL,
+    signature TEXT,
+    PRIMARY KEY (journal, position)
+);
+
+CREATE INDEX transactions_by_posted ON transactions (journal, posted);
+
+CREATE INDEX transactions_by_id ON transactions (id);
+
+CREATE TABLE splits (
+    journal TEXT NOT NULL,
+    transaction_position INTEGER NOT NULL,
+    position INTEGER NOT NULL,
+    id TEXT NOT NULL,
+    account TEXT NOT NULL,
+    commodity TEXT NOT NULL,
+    quantity TEXT NOT NULL,
+    exchanged_from TEXT,
+    description TEXT,
+    tax_code TEXT,
+    PRIMARY KEY (journal, transaction_position, position),
+    FOREIGN KEY (journal, transaction_position)
+        REFERENCES transactions (journal, position) ON DELETE CASCADE
+);
+
+CREATE INDEX splits_by_account ON splits (account, journal, transaction_position);
+
+CREATE TABLE parties (
+    id TEXT PRIMARY KEY,
+    label TEXT NOT NULL,
+    created TEXT NOT NULL,
+    document TEXT NOT NULL
+);
+
+CREATE TABLE import_profiles (
+    id TEXT PRIMARY KEY,
+    label TEXT NOT NULL,
+    created TEXT NOT NULL,
+    document TEXT NOT NULL
+);
+
+CREATE TABLE commodities (
+    position INTEGER PRIMARY KEY,
+    document TEXT NOT NULL
+);
+
+CREATE TABLE prices (
+    position INTEGER PRIMARY KEY,
+    document TEXT NOT NULL
+);
+
+CREATE TABLE reconciliations (
+    position INTEGER PRIMARY KEY,
+    document TEXT NOT NULL
+);
+"#;
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl DataStore for SqliteStore {
+    fn connect(connection_uri: &Url) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        if Self::exists(connection_uri) {
+            trace!("Opening existing store @ <{}>", connection_uri);
+            let connection = open(Path::new(connection_uri.path()), OpenFlags::default())?;
+            Ok(SqliteStore {
+                from_uri: connection_uri.clone(),
+                connection: Arc::new(Mutex::new(connection)),
+            })
+        } else {
+            error!(
+                "Could not find the required content for a store @ <{}>",
+                connection_uri
+            );
+            Err(store_does_not_exist(connection_uri.clone()))
+        }
+    }
+
+    fn exists(connection_uri: &Url) -> bool
+    where
+        Self: Sized,
+    {
+        let path = Path::new(connection_uri.path());
+        path.is_file()
+            && open(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+                .and_then(|connection| {
+                    Ok(connection
+                        .query_row("SELECT COUNT(*) FROM store", [], |row| row.get::<_, i64>(0))?)
+                })
+                .map(|count| count == 1)
+                .unwrap_or_default()
+    }
+
+    fn create(connection_uri: &Url, content: &CreateDatastoreContents) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        if !Self::exists(connection_uri) {
+            let path = Path::new(connection_uri.path());
+            if let Some(parent) = path
+                .parent()
+                .filter(|parent| !parent.as_os_str().is_empty())
+            {
+                fs::create_dir_all(parent)?;
+            }
+            let mut connection = open(path, OpenFlags::default())?;
+
+            trace!("Create store schema");
+            let transaction = begin(&mut connection)?;
+            transaction.execute_batch(SCHEMA)?;
+            write_settings(&transaction, &StoreSettings::default())?;
+            write_permissions(&transaction, &StorePermissions::default())?;
+            for journal in &content.journals {
+                insert_journal(&transaction, journal)?;
+            }
+            for ledger in &content.ledgers {
+                insert_ledger(&transaction, ledger)?;
+            }
+            append_records(&transaction, PRICES_TABLE, &content.prices)?;
+            append_records(
+                &transaction,
+                RECONCILIATIONS_TABLE,
+                &content.reconciliations,
+            )?;
+            transaction.commit()?;
+
+            trace!("Store @ <{}> created", connection_uri);
+            Ok(SqliteStore {
+                from_uri: connection_uri.clone(),
+                connection: Arc::new(Mutex::new(connection)),
+            })
+        } else {
+            error!(
+                "Could not create a store, one already exists @ <{}>",
+                connection_uri
+            );
+            Err(store_exists(connection_uri.clone()))
+        }
+    }
+
+    fn ledgers(&self) -> Result<Box<dyn EntityStore<LedgerKind, Ledger>>, Error> {
+        Ok(Box::new(SqliteLedgerStore::open(self.connection.clone())))
+    }
+
+    fn journals(&self) -> Result<Box<dyn EntityStore<String, Journal>>, Error> {
+        Ok(Box::new(SqliteJournalStore::open(self.connection.clone())))
+    }
+
+    fn import_profiles(&self) -> Result<Box<dyn EntityStore<String, CsvProfile>>, Error> {
+        Ok(Box::new(SqliteEntityStore::open(
+            self.connection.clone(),
+            PROFILES_TABLE,
+        )))
+    }
+
+    fn parties(&self) -> Result<Box<dyn EntityStore<PartyId, Party>>, Error> {
+        Ok(Box::new(SqliteEntityStore::open(
+            self.connection.clone(),
+            PARTIES_TABLE,
+        )))
+    }
+
+    fn commodities(&self) -> Result<Box<dyn RecordStore<Security>>, Error> {
+        Ok(Box::new(SqliteRecordStore::open(
+            self.connection.clone(),
+            COMMODITIES_TABLE,
+        )))
+    }
+
+    fn prices(&self) -> Result<Box<dyn RecordStore<RateRecord>>, Error> {
+        Ok(Box::new(SqliteRecordStore::open(
+            self.connection.clone(),
+            PRICES_TABLE,
+        )))
+    }
+
+    fn reconciliations(&self) -> Result<Box<dyn RecordStore<Reconciled>>, Error> {
+        Ok(Box::new(SqliteRecordStore::open(
+            self.connection.clone(),
+            RECONCILIATIONS_TABLE,
+        )))
+    }
+
+    fn transactions(&self, journal: &str, query: &Query) -> Result<Page<Transaction>, Error> {
+        select_transactions(&lock(&self.connection), journal, query)
+    }
+
+    fn settings(&self) -> Result<StoreSettings, Error> {
+        let connection = lock(&self.connection);
+        Ok(connection.query_row(
+            "SELECT version, created, default_commodity FROM store",
+            [],
+            |row| {
+                Ok(StoreSettings {
+                    version: text_column(row, 0)?,
+                    created: row.get(1)?,
+                    default_commodity: optional_text_column(row, 2)?,
+                })
+            },
+        )?)
+    }
+
+    fn update_settings(&self, settings: &StoreSettings) -> Result<(), Error> {
+        write_settings(&lock(&self.connection), settings)
+    }
+
+    fn permissions(&self) -> Result<StorePermissions, Error> {
+        let connection = lock(&self.connection);
+        let permissions: String =
+            connection.query_row("SELECT permissions FROM store", [], |row| row.get(0))?;
+        Ok(serde_json::from_str(&permissions)?)
+    }
+
+    fn update_permissions(&self, permissions: &StorePermissions) -> Result<(), Error> {
+        write_permissions(&lock(&self.connection), permissions)
+    }
+
+    fn begin(&self) -> Result<Box<dyn UnitOfWork>, Error> {
+        trace!("Beginning unit of work @ <{}>", self.from_uri);
+        let connection = open(Path::new(self.from_uri.path()), OpenFlags::default())?;
+        connection.execute_batch("BEGIN IMMEDIATE")?;
+        Ok(Box::new(SqliteUnitOfWork {
+            connection: Arc::new(Mutex::new(connection)),
+        }))
+    }
+
+    fn disconnect(&self) -> Result<(), Error> {
+        trace!("Closing store @ <{}>", self.from_uri);
+        Ok(())
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl UnitOfWork for SqliteUnitOfWork {
+    fn ledgers(&self) -> Result<Box<dyn EntityStore<LedgerKind, Ledger>>, Error> {
+        Ok(Box::new(SqliteLedgerStore::open(self.connection.clone())))
+    }
+
+    fn journals(&self) -> Result<Box<dyn EntityStore<String, Journal>>, Error> {
+        Ok(Box::new(SqliteJournalStore::open(self.connection.clone())))
+    }
+
+    fn import_profiles(&self) -> Result<Box<dyn EntityStore<String, CsvProfile>>, Error> {
+        Ok(Box::new(SqliteEntityStore::open(
+            self.connection.clone(),
+            PROFILES_TABLE,
+        )))
+    }
+
+    fn parties(&self) -> Result<Box<dyn EntityStore<PartyId, Party>>, Error> {
+        Ok(Box::new(SqliteEntityStore::open(
+            self.connection.clone(),
+            PARTIES_TABLE,
+        )))
+    }
+
+    fn commodities(&self) -> Result<Box<dyn RecordStore<Security>>, Error> {
+        Ok(Box::new(SqliteRecordStore::open(
+            self.connection.clone(),
+            COMMODITIES_TABLE,
+        )))
+    }
+
+    fn prices(&self) -> Result<Box<dyn RecordStore<RateRecord>>, Error> {
+        Ok(Box::new(SqliteRecordStore::open(
+            self.connection.clone(),
+            PRICES_TABLE,
+        )))
+    }
+
+    fn reconciliations(&self) -> Result<Box<dyn RecordStore<Reconciled>>, Error> {
+        Ok(Box::new(SqliteRecordStore::open(
+            self.connection.clone(),
+            RECONCILIATIONS_TABLE,
+        )))
+    }
+
+    fn commit(self: Box<Self>) -> Result<(), Error> {
+        Ok(lock(&self.connection).execute_batch("COMMIT")?)
+    }
+
+    fn rollback(self: Box<Self>) -> Result<(), Error> {
+        Ok(lock(&self.connection).execute_batch("ROLLBACK")?)
+    }
+}
+
+impl Drop for SqliteUnitOfWork {
+    fn drop(&mut self) {
+        let connection = lock(&self.connection);
+        if !connection.is_autocommit() {
+            if let Err(e) = connection.execute_batch("ROLLBACK") {
+                error!("Could not roll back unit of work; error: {}", e);
+            }
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl Deref for Write<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::Transaction(transaction) => transaction,
+            Self::Savepoint(savepoint) => savepoint,
+        }
+    }
+}
+
+impl Write<'_> {
+    fn commit(self) -> rusqlite::Result<()> {
+        match self {
+            Self::Transaction(transaction) => transaction.commit(),
+            Self::Savepoint(savepoint) => savepoint.commit(),
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+fn open(path: &Path, flags: OpenFlags) -> Result<Connection, Error> {
+    let connection = Connection::open_with_flags(path, flags)?;
+    connection.pragma_update(None, "foreign_keys", true)?;
+    connection.busy_timeout(BUSY_TIMEOUT)?;
+    connection.create_scalar_function(
+        "contains_text",
+        2,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |context| {
+            Ok(contains_text(
+                &context.get::<String>(0)?,
+                &context.get::<String>(1)?,
+            ))
+        },
+    )?;
+    Ok(connection)
+}
+
+///
+/// Begin a database transaction that takes the write lock immediately, so that a writer on
+/// another connection to the same file waits for it rather than failing part way through; on
+/// the connection of a unit of work, which already holds the lock, begin a savepoint instead.
+///
+fn begin(connection: &mut Connection) -> rusqlite::Result<Write<'_>> {
+    if connection.is_autocommit() {
+        connection
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map(Write::Transaction)
+    } else {
+        connection.savepoint().map(Write::Savepoint)
+    }
+}
+
+///
+/// Lock the connection shared by a store and the entity stores it returned. A panic while the
+/// lock was held cannot leave a write half done, as an unfinished database transaction is
+/// rolled back when dropped, so a poisoned lock is simply taken over.
+///
+fn lock(connection: &SharedConnection) -> MutexGuard<'_, Connection> {
+    connection.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn write_settings(connection: &Connection, settings: &StoreSettings) -> Result<(), Error> {
+    let _ = connection.execute(
+        "INSERT INTO store (id, version, created, default_commodity, permissions)
+         VALUES (1, ?1, ?2, ?3, '{}')
+         ON CONFLICT (id) DO UPDATE SET
+            version = excluded.version,
+            created = excluded.created,
+            default_commodity = excluded.default_commodity",
+        params![
+            settings.version().to_string(),
+            settings.created(),
+            settings.default_commodity().map(to_text).transpose()?,
+        ],
+    )?;
+    Ok(())
+}
+
+fn write_permissions(connection: &Connection, permissions: &StorePermissions) -> Result<(), Error> {
+    let _ = connection.execute(
+        "UPDATE store SET permissions = ?1 WHERE id = 1",
+        [serde_json::to_string(permissions)?],
+    )?;
+    Ok(())
+}
+
+///
+/// Write a value as column text; values that serialize to a JSON string, such as identifiers
+/// and unit enum variants, are written without quotes so that they can be compared in queries.
+///
+fn to_text<T>(value: &T) -> Result<String, Error>
+where
+    T: Serialize,
+{
+    match serde_json::to_value(value).map_err(entity_file_error)? {
+        Value::String(text) => Ok(text),
+        value => Ok(value.to_string()),
+    }
+}
+
+fn to_json<T>(value: Option<&T>) -> Result<Option<String>, Error>
+where
+    T: Serialize,
+{
+    value
+        .map(|value| serde_json::to_string(value).map_err(entity_file_error))
+        .transpose()
+}
+
+fn text_column<T>(row: &Row<'_>, index: usize) -> rusqlite::Result<T>
+where
+    T: DeserializeOwned,
+{
+    let text: String = row.get(index)?;
+    serde_json::from_value(Value::String(text.clone()))
+        .or_else(|_| serde_json::from_str(&text))
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, Type::Text, Box::new(e)))
+}
+
+fn optional_text_column<T>(row: &Row<'_>, index: usize) -> rusqlite::Result<Option<T>>
+where
+    T: DeserializeOwned,
+{
+    match row.get_ref(index)? {
+        rusqlite::types::ValueRef::Null => Ok(None),
+        _ => text_column(row, index).map(Some),
+    }
+}
+
+fn json_column<T>(row: &Row<'_>, index: usize) -> rusqlite::Result<Option<T>>
+where
+    T: DeserializeOwned,
+{
+    row.get::<_, Option<String>>(index)?
+        .map(|text| {
+            serde_json::from_str(&text).map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(index, Type::Text, Box::new(e))
+            })
+        })
+        .transpose()
+}
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
+
+mod entity;
+
+mod journal;
+
+mod ledger;
+
+mod query;
+
+mod record;
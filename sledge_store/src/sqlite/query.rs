@@ -0,0 +1,207 @@
+/*!
+Executes a [`Query`] as SQL against one table, returning the identifier and position of each
+selected row so that the caller can read the page of items it needs and no more.
+
+The sort key, identifier, and position of a row are compared as a single row value against the
+cursor, so that SQLite can use an index on those columns to start a page; dates are compared
+as the text rusqlite writes them in, which sorts in time order.
+
+*/
+
+use crate::error::Error;
+use crate::query::{Page, Predicate, Query, Row, SortKey, SortOrder};
+use chrono::{DateTime, Utc};
+use rusqlite::{params_from_iter, Connection, ToSql};
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// The table, and the columns of it, that a query selects from.
+///
+pub(super) struct Selection {
+    table: &'static str,
+    id: &'static str,
+    position: Option<&'static str>,
+    label: &'static str,
+    date: &'static str,
+    conditions: Vec<String>,
+    parameters: Vec<Box<dyn ToSql>>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+///
+/// Select a page of identifiers from a table of entities; only text filters apply, and are
+/// matched against the label.
+///
+pub(super) fn select_entity_ids(
+    connection: &Connection,
+    query: &Query,
+    table: &'static str,
+    id: &'static str,
+    label: &'static str,
+) -> Result<Page<String>, Error> {
+    let mut selection = Selection::new(table, id, None, label, "created");
+    for predicate in query.entity_predicates()? {
+        if let Predicate::Text(text) = predicate {
+            selection = selection.with_condition(
+                &format!("contains_text({}, ?)", label),
+                vec![Box::new(text)],
+            );
+        }
+    }
+    selection
+        .select(connection, query)?
+        .try_map(|rows| Ok(rows.into_iter().map(|(id, _)| id).collect()))
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl Selection {
+    ///
+    /// Select from `table`, where `position` is the column, if any, ordering rows with the same
+    /// identifier.
+    ///
+    pub(super) fn new(
+        table: &'static str,
+        id: &'static str,
+        position: Option<&'static str>,
+        label: &'static str,
+        date: &'static str,
+    ) -> Self {
+        Self {
+            table,
+            id,
+            position,
+            label,
+            date,
+            conditions: Default::default(),
+            parameters: Default::default(),
+        }
+    }
+
+    ///
+    /// Add a condition, in which each `?` is bound to the next of `parameters`.
+    ///
+    pub(super) fn with_condition(
+        mut self,
+        condition: &str,
+        parameters: Vec<Box<dyn ToSql>>,
+    ) -> Self {
+        self.conditions.push(format!("({})", condition));
+        self.parameters.extend(parameters);
+        self
+    }
+
+    ///
+    /// Select a page of the identifier and position of each row.
+    ///
+    pub(super) fn select(
+        mut self,
+        connection: &Connection,
+        query: &Query,
+    ) -> Result<Page<(String, usize)>, Error> {
+        let (id, position, date) = (self.id, self.position, self.date);
+        let key = match query.sort() {
+            SortKey::Identifier => id,
+            SortKey::Label => self.label,
+            SortKey::Date => date,
+        };
+        let (compare, direction) = match query.order() {
+            SortOrder::Ascending => (">", "ASC"),
+            SortOrder::Descending => ("<", "DESC"),
+        };
+
+        if let Some(from) = query.from() {
+            self = self.with_condition(&format!("{} >= ?", date), vec![Box::new(from)]);
+        }
+        if let Some(until) = query.until() {
+            self = self.with_condition(&format!("{} < ?", date), vec![Box::new(until)]);
+        }
+        if let Some(cursor) = query.checked_cursor()? {
+            let mut columns = vec![key, id];
+            let mut parameters: Vec<Box<dyn ToSql>> = vec![
+                match query.sort() {
+                    SortKey::Date => Box::new(cursor.date()?),
+                    _ => Box::new(cursor.key().clone()),
+                },
+                Box::new(cursor.id().clone()),
+            ];
+            if let Some(position) = position {
+                columns.push(position);
+                parameters.push(Box::new(cursor.position() as i64));
+            }
+            self = self.with_condition(
+                &format!(
+                    "({}) {} ({})",
+                    columns.join(", "),
+                    compare,
+                    vec!["?"; columns.len()].join(", ")
+                ),
+                parameters,
+            );
+        }
+        let limit = query.limit().map(|limit| limit as i64 + 1).unwrap_or(-1);
+
+        let sql = format!(
+            "SELECT {id}, {position}, {label}, {date} FROM {table}
+             {where_clause}
+             ORDER BY {order_by}
+             LIMIT {limit}",
+            id = id,
+            position = position.unwrap_or("0"),
+            label = self.label,
+            date = self.date,
+            table = self.table,
+            where_clause = if self.conditions.is_empty() {
+                String::new()
+            } else {
+                format!("WHERE {}", self.conditions.join(" AND "))
+            },
+            order_by = [Some(key), Some(id), position]
+                .iter()
+                .flatten()
+                .map(|column| format!("{} {}", column, direction))
+                .collect::<Vec<_>>()
+                .join(", "),
+            limit = limit,
+        );
+        let mut statement = connection.prepare(&sql)?;
+        let rows = statement
+            .query_map(params_from_iter(self.parameters.iter()), |row| {
+                let id: String = row.get(0)?;
+                let position = row.get::<_, i64>(1)? as usize;
+                Ok(Row {
+                    id: id.clone(),
+                    position,
+                    label: row.get(2)?,
+                    date: row.get::<_, DateTime<Utc>>(3)?,
+                    item: (id, position),
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(query.page(rows))
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
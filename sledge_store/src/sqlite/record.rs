@@ -0,0 +1,105 @@
+/*!
+Stores records without an identity of their own, such as prices, as one JSON document per row
+in the order they were appended.
+
+*/
+
+use super::{begin, lock, SharedConnection};
+use crate::error::{entity_file_error, Error};
+use crate::RecordStore;
+use rusqlite::Connection;
+use serde::{de::DeserializeOwned, Serialize};
+use std::marker::PhantomData;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+pub(super) const COMMODITIES_TABLE: &str = "commodities";
+
+pub(super) const PRICES_TABLE: &str = "prices";
+
+pub(super) const RECONCILIATIONS_TABLE: &str = "reconciliations";
+
+pub(super) struct SqliteRecordStore<R> {
+    connection: SharedConnection,
+    table: &'static str,
+    record: PhantomData<fn() -> R>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+pub(super) fn append_records<R>(
+    connection: &Connection,
+    table: &str,
+    records: &[R],
+) -> Result<(), Error>
+where
+    R: Serialize,
+{
+    let mut statement =
+        connection.prepare(&format!("INSERT INTO {} (document) VALUES (?1)", table))?;
+    for record in records {
+        let _ = statement.execute([serde_json::to_string(record).map_err(entity_file_error)?])?;
+    }
+    Ok(())
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl<R> SqliteRecordStore<R> {
+    pub(super) fn open(connection: SharedConnection, table: &'static str) -> Self {
+        Self {
+            connection,
+            table,
+            record: PhantomData,
+        }
+    }
+}
+
+impl<R> RecordStore<R> for SqliteRecordStore<R>
+where
+    R: Serialize + DeserializeOwned,
+{
+    fn list(&self) -> Result<Vec<R>, Error> {
+        let connection = lock(&self.connection);
+        let mut statement = connection.prepare(&format!(
+            "SELECT document FROM {} ORDER BY position",
+            self.table
+        ))?;
+        let documents = statement
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        documents
+            .iter()
+            .map(|document| serde_json::from_str(document).map_err(entity_file_error))
+            .collect()
+    }
+
+    fn append(&self, records: Vec<R>) -> Result<(), Error> {
+        let mut connection = lock(&self.connection);
+        let transaction = begin(&mut connection)?;
+        append_records(&transaction, self.table, &records)?;
+        Ok(transaction.commit()?)
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
@@ -0,0 +1,405 @@
+/*!
+Provides a [`UnitOfWork`] for stores without transactions of their own, by staging every change
+in memory, over the stores of a connection, and handing the whole set to the store to apply on
+commit.
+
+Reads through a staged store see the staged changes laid over the connection's current
+entities and records. Nothing is checked against the store on commit beyond what
+[`Changes::validate`] checks, so that the last of two units of work to update an entity wins,
+as it would for two plain updates.
+
+*/
+
+use crate::error::{entity_exists, entity_file_error, entity_not_found, Error};
+use crate::query::{select_entities, Page, Query};
+use crate::{DataStore, Entity, EntityStore, RecordStore, UnitOfWork};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use sledge_model::{
+    commodity::{RateRecord, Security},
+    import::CsvProfile,
+    journal::{Journal, Reconciled},
+    ledger::{Ledger, LedgerKind},
+    party::{Party, PartyId},
+};
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// The entity or record stores a change may be made to.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Table {
+    Ledgers,
+    Journals,
+    ImportProfiles,
+    Parties,
+    Commodities,
+    Prices,
+    Reconciliations,
+}
+
+///
+/// A staged change to one entity, as the value the entity will have.
+///
+#[derive(Debug)]
+pub(crate) enum Change {
+    Create(Value),
+    Update(Value),
+    Delete,
+}
+
+///
+/// Every change staged by a unit of work, by table and entity identifier, and the records
+/// to append to each table of records.
+///
+#[derive(Debug, Default)]
+pub(crate) struct Changes {
+    pub(crate) entities: BTreeMap<(Table, String), Change>,
+    pub(crate) records: BTreeMap<Table, Vec<Value>>,
+}
+
+///
+/// Implemented by a store to make all of a unit of work's changes, or none of them.
+///
+pub(crate) trait ApplyChanges {
+    fn apply(&self, changes: Changes) -> Result<(), Error>;
+}
+
+pub(crate) struct StagedUnitOfWork<S> {
+    store: S,
+    changes: Arc<Mutex<Changes>>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+struct StagedEntityStore<I, E> {
+    base: Box<dyn EntityStore<I, E>>,
+    table: Table,
+    changes: Arc<Mutex<Changes>>,
+    entity: PhantomData<fn() -> (I, E)>,
+}
+
+struct StagedRecordStore<R> {
+    base: Box<dyn RecordStore<R>>,
+    table: Table,
+    changes: Arc<Mutex<Changes>>,
+}
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl Changes {
+    ///
+    /// Check that each entity to be created does not yet exist, and each entity to be updated
+    /// or deleted still does, where `present` reports whether the store holds an entity.
+    ///
+    pub(crate) fn validate<F>(&self, mut present: F) -> Result<(), Error>
+    where
+        F: FnMut(Table, &str) -> Result<bool, Error>,
+    {
+        for ((table, id), change) in &self.entities {
+            match (change, present(*table, id)?) {
+                (Change::Create(_), true) => return Err(entity_exists(id.as_str())),
+                (Change::Update(_), false) | (Change::Delete, false) => {
+                    return Err(entity_not_found(id.as_str()))
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl<S> StagedUnitOfWork<S>
+where
+    S: DataStore + ApplyChanges,
+{
+    pub(crate) fn new(store: S) -> Self {
+        Self {
+            store,
+            changes: Default::default(),
+        }
+    }
+
+    fn entities<I, E>(
+        &self,
+        base: Box<dyn EntityStore<I, E>>,
+        table: Table,
+    ) -> StagedEntityStore<I, E> {
+        StagedEntityStore {
+            base,
+            table,
+            changes: self.changes.clone(),
+            entity: PhantomData,
+        }
+    }
+
+    fn records<R>(&self, base: Box<dyn RecordStore<R>>, table: Table) -> StagedRecordStore<R> {
+        StagedRecordStore {
+            base,
+            table,
+            changes: self.changes.clone(),
+        }
+    }
+}
+
+impl<S> UnitOfWork for StagedUnitOfWork<S>
+where
+    S: DataStore + ApplyChanges,
+{
+    fn ledgers(&self) -> Result<Box<dyn EntityStore<LedgerKind, Ledger>>, Error> {
+        Ok(Box::new(
+            self.entities(self.store.ledgers()?, Table::Ledgers),
+        ))
+    }
+
+    fn journals(&self) -> Result<Box<dyn EntityStore<String, Journal>>, Error> {
+        Ok(Box::new(
+            self.entities(self.store.journals()?, Table::Journals),
+        ))
+    }
+
+    fn import_profiles(&self) -> Result<Box<dyn EntityStore<String, CsvProfile>>, Error> {
+        Ok(Box::new(self.entities(
+            self.store.import_profiles()?,
+            Table::ImportProfiles,
+        )))
+    }
+
+    fn parties(&self) -> Result<Box<dyn EntityStore<PartyId, Party>>, Error> {
+        Ok(Box::new(
+            self.entities(self.store.parties()?, Table::Parties),
+        ))
+    }
+
+    fn commodities(&self) -> Result<Box<dyn RecordStore<Security>>, Error> {
+        Ok(Box::new(
+            self.records(self.store.commodities()?, Table::Commodities),
+        ))
+    }
+
+    fn prices(&self) -> Result<Box<dyn RecordStore<RateRecord>>, Error> {
+        Ok(Box::new(self.records(self.store.prices()?, Table::Prices)))
+    }
+
+    fn reconciliations(&self) -> Result<Box<dyn RecordStore<Reconciled>>, Error> {
+        Ok(Box::new(self.records(
+            self.store.reconciliations()?,
+            Table::Reconciliations,
+        )))
+    }
+
+    fn commit(self: Box<Self>) -> Result<(), Error> {
+        let changes = std::mem::take(&mut *lock(&self.changes));
+        self.store.apply(changes)
+    }
+
+    fn rollback(self: Box<Self>) -> Result<(), Error> {
+        *lock(&self.changes) = Default::default();
+        Ok(())
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl<I, E> StagedEntityStore<I, E>
+where
+    I: Clone + Display + Eq + Hash,
+    E: Entity<I> + Serialize + DeserializeOwned,
+{
+    fn key(&self, id: &I) -> (Table, String) {
+        (self.table, id.to_string())
+    }
+}
+
+impl<I, E> EntityStore<I, E> for StagedEntityStore<I, E>
+where
+    I: Clone + Display + Eq + Hash,
+    E: Entity<I> + Serialize + DeserializeOwned,
+{
+    fn create(&self, entity: E) -> Result<I, Error> {
+        let id = entity.identifier().clone();
+        self.create_with_id(entity, id.clone())?;
+        Ok(id)
+    }
+
+    fn create_with_id(&self, entity: E, id: I) -> Result<(), Error> {
+        let value = serde_json::to_value(&entity).map_err(entity_file_error)?;
+        let mut changes = lock(&self.changes);
+        match changes.entities.entry(self.key(&id)) {
+            Entry::Occupied(mut entry) => match entry.get() {
+                Change::Delete => {
+                    let _ = entry.insert(Change::Update(value));
+                    Ok(())
+                }
+                _ => Err(entity_exists(entry.key().1.as_str())),
+            },
+            Entry::Vacant(entry) => {
+                if self.base.get_by_id(&id)?.is_some() {
+                    Err(entity_exists(entry.key().1.as_str()))
+                } else {
+                    let _ = entry.insert(Change::Create(value));
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    fn list(&self, query: &Query) -> Result<Page<E>, Error> {
+        let mut entities: BTreeMap<String, E> = self
+            .base
+            .list(&Query::default())?
+            .into_iter()
+            .map(|entity| (entity.identifier().to_string(), entity))
+            .collect();
+        for ((table, id), change) in &lock(&self.changes).entities {
+            if *table == self.table {
+                match change {
+                    Change::Create(value) | Change::Update(value) => {
+                        let _ = entities.insert(
+                            id.clone(),
+                            E::deserialize(value).map_err(entity_file_error)?,
+                        );
+                    }
+                    Change::Delete => {
+                        let _ = entities.remove(id);
+                    }
+                }
+            }
+        }
+        select_entities(query, entities.into_values().collect())
+    }
+
+    fn get_by_id(&self, id: &I) -> Result<Option<E>, Error> {
+        match lock(&self.changes).entities.get(&self.key(id)) {
+            Some(Change::Create(value)) | Some(Change::Update(value)) => {
+                Ok(Some(E::deserialize(value).map_err(entity_file_error)?))
+            }
+            Some(Change::Delete) => Ok(None),
+            None => self.base.get_by_id(id),
+        }
+    }
+
+    fn update(&self, entity: E) -> Result<(), Error> {
+        let value = serde_json::to_value(&entity).map_err(entity_file_error)?;
+        let id = entity.identifier();
+        let mut changes = lock(&self.changes);
+        match changes.entities.entry(self.key(id)) {
+            Entry::Occupied(mut entry) => match entry.get() {
+                Change::Create(_) => {
+                    let _ = entry.insert(Change::Create(value));
+                    Ok(())
+                }
+                Change::Update(_) => {
+                    let _ = entry.insert(Change::Update(value));
+                    Ok(())
+                }
+                Change::Delete => Err(entity_not_found(entry.key().1.as_str())),
+            },
+            Entry::Vacant(entry) => {
+                if self.base.get_by_id(id)?.is_some() {
+                    let _ = entry.insert(Change::Update(value));
+                    Ok(())
+                } else {
+                    Err(entity_not_found(entry.key().1.as_str()))
+                }
+            }
+        }
+    }
+
+    fn delete(&self, id: &I) -> Result<(), Error> {
+        let mut changes = lock(&self.changes);
+        match changes.entities.entry(self.key(id)) {
+            Entry::Occupied(mut entry) => match entry.get() {
+                Change::Create(_) => {
+                    let _ = entry.remove();
+                    Ok(())
+                }
+                Change::Update(_) => {
+                    let _ = entry.insert(Change::Delete);
+                    Ok(())
+                }
+                Change::Delete => Err(entity_not_found(entry.key().1.as_str())),
+            },
+            Entry::Vacant(entry) => {
+                if self.base.get_by_id(id)?.is_some() {
+                    let _ = entry.insert(Change::Delete);
+                    Ok(())
+                } else {
+                    Err(entity_not_found(entry.key().1.as_str()))
+                }
+            }
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+
+impl<R> RecordStore<R> for StagedRecordStore<R>
+where
+    R: Serialize + DeserializeOwned,
+{
+    fn list(&self) -> Result<Vec<R>, Error> {
+        let mut records = self.base.list()?;
+        for value in lock(&self.changes)
+            .records
+            .get(&self.table)
+            .into_iter()
+            .flatten()
+        {
+            records.push(R::deserialize(value).map_err(entity_file_error)?);
+        }
+        Ok(records)
+    }
+
+    fn append(&self, records: Vec<R>) -> Result<(), Error> {
+        let values = records
+            .iter()
+            .map(|record| serde_json::to_value(record).map_err(entity_file_error))
+            .collect::<Result<Vec<_>, _>>()?;
+        lock(&self.changes)
+            .records
+            .entry(self.table)
+            .or_default()
+            .extend(values);
+        Ok(())
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+///
+/// Lock the staged changes; each change replaces a whole entry, so a poisoned lock is simply
+/// taken over.
+///
+fn lock(changes: &Mutex<Changes>) -> MutexGuard<'_, Changes> {
+    changes.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+// ------------------------------------------------------------------------------------------------
+// Modules
+// ------------------------------------------------------------------------------------------------
@@ -0,0 +1,980 @@
+/*!
+Conformance tests that every `DataStore` backend must pass, so that code written against one
+backend behaves the same against any other.
+
+A backend opts in from its own test file with the `datastore_conformance!` macro, giving a
+function that returns the connection URI for a store that no other test uses, and a function
+that removes anything that store left behind.
+
+```rust,ignore
+#[macro_use]
+mod conformance;
+
+fn store_uri(name: &str) -> Url { ... }
+fn remove_store(uri: &Url) { ... }
+
+datastore_conformance!(store_uri, remove_store);
+```
+
+*/
+
+use chrono::{TimeZone, Utc};
+use codes_iso_4217::CurrencyCode;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sledge_model::audit::{Action, Resource, RoleId, UserId};
+use sledge_model::commodity::{CommodityId, MarketIdentifierCode, Quantity, RateRecord, Security};
+use sledge_model::import::{CsvAmount, CsvColumn, CsvProfile};
+use sledge_model::journal::{Journal, Reconciled, Split, Transaction, TransactionId};
+use sledge_model::ledger::{
+    Account, AccountId, AccountKind, AccountRepresents, Customer, Ledger, LedgerKind,
+};
+use sledge_model::party::{Address, CountryCode, LegalEntityId, Party, PartyId};
+use sledge_store::error::Error;
+use sledge_store::query::{Cursor, Filter, Query, SortKey, SortOrder};
+use sledge_store::{
+    create_datastore, datastore_exists, get_current_datastore, CreateDatastoreContents, Entity,
+    EntityStore, StorePermissions, StoreSettings,
+};
+use std::fmt::Display;
+use std::hash::Hash;
+use std::thread;
+use url::Url;
+
+// ------------------------------------------------------------------------------------------------
+// Public Macros
+// ------------------------------------------------------------------------------------------------
+
+///
+/// Add a test for each conformance check, run against stores reached through `$store_uri`.
+///
+macro_rules! datastore_conformance {
+    ($store_uri:expr, $remove_store:expr) => {
+        datastore_conformance!(
+            $store_uri,
+            $remove_store;
+            create_and_connect,
+            settings_and_permissions,
+            duplicate_create,
+            crud_round_trip,
+            list_paging,
+            records_in_order,
+            reconciliations,
+            query_transactions,
+            error_variants,
+            concurrent_connections,
+            unit_of_work
+        );
+    };
+    ($store_uri:expr, $remove_store:expr; $($check:ident),+) => {
+        mod datastore_conformance {
+            use super::*;
+
+            $(
+                #[test]
+                fn $check() {
+                    $crate::conformance::run(
+                        stringify!($check),
+                        $crate::conformance::$check,
+                        $store_uri,
+                        $remove_store,
+                    );
+                }
+            )+
+        }
+    };
+}
+
+// ------------------------------------------------------------------------------------------------
+// Public Types
+// ------------------------------------------------------------------------------------------------
+
+///
+/// Returns the connection URI for a store named `name`, unique to this backend's tests.
+///
+pub type StoreUri = fn(&str) -> Url;
+
+///
+/// Removes any files or other resources left behind by the store at a connection URI.
+///
+pub type RemoveStore = fn(&Url);
+
+// ------------------------------------------------------------------------------------------------
+// Public Functions
+// ------------------------------------------------------------------------------------------------
+
+pub fn run(name: &str, check: fn(&Url), store_uri: StoreUri, remove_store: RemoveStore) {
+    let uri = store_uri(&format!("conformance-{}", name));
+    remove_store(&uri);
+    let _cleanup = Cleanup(uri.clone(), remove_store);
+    check(&uri);
+}
+
+pub fn create_and_connect(uri: &Url) {
+    assert!(!datastore_exists(uri));
+    assert!(matches!(
+        get_current_datastore(uri),
+        Err(Error::StoreDoesNotExist { .. })
+    ));
+
+    let contents = contents();
+    let store = create_datastore(uri, &contents).unwrap();
+    assert!(datastore_exists(uri));
+    assert!(matches!(
+        create_datastore(uri, &Default::default()),
+        Err(Error::StoreExists { .. })
+    ));
+
+    let connected = get_current_datastore(uri).unwrap();
+    assert_eq!(
+        json(
+            &connected
+                .ledgers()
+                .unwrap()
+                .list(&Query::default())
+                .unwrap()
+                .into_items()
+        ),
+        json(&contents.ledgers)
+    );
+    assert_eq!(
+        json(
+            &connected
+                .journals()
+                .unwrap()
+                .list(&Query::default())
+                .unwrap()
+                .into_items()
+        ),
+        json(&contents.journals)
+    );
+    assert_eq!(
+        json(&connected.prices().unwrap().list().unwrap()),
+        json(&contents.prices)
+    );
+    assert_eq!(
+        connected.reconciliations().unwrap().list().unwrap(),
+        contents.reconciliations
+    );
+    assert!(connected
+        .parties()
+        .unwrap()
+        .list(&Query::default())
+        .unwrap()
+        .into_items()
+        .is_empty());
+    assert!(connected
+        .import_profiles()
+        .unwrap()
+        .list(&Query::default())
+        .unwrap()
+        .items()
+        .is_empty());
+    assert!(connected.commodities().unwrap().list().unwrap().is_empty());
+    drop(store);
+}
+
+pub fn settings_and_permissions(uri: &Url) {
+    let store = create_datastore(uri, &Default::default()).unwrap();
+    assert_eq!(store.settings().unwrap().default_commodity(), None);
+    assert_eq!(store.permissions().unwrap(), StorePermissions::default());
+
+    let settings = StoreSettings::default().with_default_commodity(eur());
+    let permissions = StorePermissions::default()
+        .with_grant(RoleId::from("clerk"), Resource::Journal, Action::Create)
+        .with_user_role(UserId::from("simon"), RoleId::from("clerk"));
+    store.update_settings(&settings).unwrap();
+    store.update_permissions(&permissions).unwrap();
+
+    let connected = get_current_datastore(uri).unwrap();
+    assert_eq!(connected.settings().unwrap(), settings);
+    assert_eq!(connected.permissions().unwrap(), permissions);
+    drop(store);
+}
+
+pub fn duplicate_create(uri: &Url) {
+    let store = create_datastore(uri, &Default::default()).unwrap();
+    check_duplicate(store.ledgers().unwrap(), || ledger("general", "General"));
+    check_duplicate(store.journals().unwrap(), || journal("general", 1));
+    check_duplicate(store.parties().unwrap(), || party("Example B.V."));
+    check_duplicate(store.import_profiles().unwrap(), || {
+        profile("bank/checking", CurrencyCode::EUR)
+    });
+}
+
+pub fn crud_round_trip(uri: &Url) {
+    let store = create_datastore(uri, &Default::default()).unwrap();
+    check_crud(
+        store.ledgers().unwrap(),
+        ledger("general", "General"),
+        ledger("general", "General").with_account(Account::new(
+            AccountId::from("4000"),
+            AccountKind::Income,
+            "Sales",
+            eur(),
+        )),
+    );
+    check_crud(
+        store.journals().unwrap(),
+        journal("general", 1),
+        journal("general", 3),
+    );
+    check_crud(
+        store.parties().unwrap(),
+        party("Example B.V."),
+        party("Example N.V."),
+    );
+    check_crud(
+        store.import_profiles().unwrap(),
+        profile("bank/checking", CurrencyCode::EUR),
+        profile("bank/checking", CurrencyCode::USD),
+    );
+}
+
+pub fn list_paging(uri: &Url) {
+    let store = create_datastore(uri, &Default::default()).unwrap();
+    check_paging(store.ledgers().unwrap(), |name| ledger(name, name));
+    check_paging(store.journals().unwrap(), |name| journal(name, 1));
+    check_paging(store.import_profiles().unwrap(), |name| {
+        profile(name, CurrencyCode::EUR)
+    });
+}
+
+pub fn records_in_order(uri: &Url) {
+    let store = create_datastore(uri, &Default::default()).unwrap();
+    let prices = store.prices().unwrap();
+    let first = vec![price(3, "92"), price(1, "91")];
+    let second = vec![price(2, "93")];
+    prices.append(first.clone()).unwrap();
+    prices.append(Vec::new()).unwrap();
+    prices.append(second.clone()).unwrap();
+    assert_eq!(prices.list().unwrap(), [first, second].concat());
+
+    let commodities = store.commodities().unwrap();
+    let securities = vec![
+        Security::new(MarketIdentifierCode::from("XAMS")).with_symbol("ASML"),
+        Security::new(MarketIdentifierCode::from("XNAS")).with_symbol("AAPL"),
+    ];
+    commodities.append(securities.clone()).unwrap();
+    assert_eq!(
+        get_current_datastore(uri)
+            .unwrap()
+            .commodities()
+            .unwrap()
+            .list()
+            .unwrap(),
+        securities
+    );
+}
+
+pub fn reconciliations(uri: &Url) {
+    let contents = CreateDatastoreContents {
+        journals: vec![journal("general", 3)],
+        reconciliations: vec![reconciled("T1", "STMT-1")],
+        ..Default::default()
+    };
+    let store = create_datastore(uri, &contents).unwrap();
+    store
+        .reconciliations()
+        .unwrap()
+        .append(vec![reconciled("T2", "STMT-2")])
+        .unwrap();
+
+    let work = store.begin().unwrap();
+    work.reconciliations()
+        .unwrap()
+        .append(vec![reconciled("T3", "STMT-2")])
+        .unwrap();
+    work.commit().unwrap();
+
+    let work = store.begin().unwrap();
+    work.reconciliations()
+        .unwrap()
+        .append(vec![reconciled("T3", "STMT-3")])
+        .unwrap();
+    work.rollback().unwrap();
+
+    assert_eq!(
+        get_current_datastore(uri)
+            .unwrap()
+            .reconciliations()
+            .unwrap()
+            .list()
+            .unwrap(),
+        vec![
+            reconciled("T1", "STMT-1"),
+            reconciled("T2", "STMT-2"),
+            reconciled("T3", "STMT-2"),
+        ]
+    );
+    drop(store);
+}
+
+pub fn query_transactions(uri: &Url) {
+    let store = create_datastore(
+        uri,
+        &CreateDatastoreContents {
+            ledgers: vec![ledger("general", "General")
+                .with_account(
+                    Account::new(
+                        AccountId::from("1100"),
+                        AccountKind::Asset,
+                        "Receivable",
+                        eur(),
+                    )
+                    .with_represents(AccountRepresents::Customer(Customer::new(party_id()))),
+                )
+                .with_account(Account::new(
+                    AccountId::from("4000"),
+                    AccountKind::Income,
+                    "Sales",
+                    eur(),
+                ))],
+            journals: vec![sales_journal()],
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let ids = |query: Query| -> Vec<String> {
+        let mut query = query;
+        let mut seen = Vec::new();
+        loop {
+            let page = store.transactions("sales", &query).unwrap();
+            let next = page.next().cloned();
+            seen.extend(
+                page.into_iter()
+                    .map(|transaction| transaction.id().to_string()),
+            );
+            match next {
+                Some(cursor) => query = query.after(cursor),
+                None => return seen,
+            }
+        }
+    };
+    let by_date = || Query::default().sorted_by(SortKey::Date, SortOrder::Ascending);
+
+    assert!(matches!(
+        store.transactions("missing", &Query::default()),
+        Err(Error::EntityNotFound { .. })
+    ));
+    assert_eq!(
+        json(
+            &store
+                .transactions("sales", &by_date())
+                .unwrap()
+                .into_items()
+        ),
+        json(&sales_journal().transactions().collect::<Vec<_>>())
+    );
+
+    assert_eq!(ids(Query::default()), ["T1", "T2", "T3", "T4", "T5", "T5"]);
+    assert_eq!(
+        ids(by_date().with_limit(2)),
+        ["T1", "T2", "T3", "T4", "T5", "T5"]
+    );
+    assert_eq!(
+        ids(Query::default()
+            .with_limit(4)
+            .sorted_by(SortKey::Date, SortOrder::Descending)),
+        ["T5", "T5", "T4", "T3", "T2", "T1"]
+    );
+    assert_eq!(
+        ids(Query::default()
+            .with_limit(1)
+            .sorted_by(SortKey::Label, SortOrder::Ascending)),
+        ["T4", "T5", "T2", "T5", "T1", "T3"]
+    );
+    assert_eq!(
+        ids(by_date().with_date_range(
+            Some(Utc.with_ymd_and_hms(2023, 1, 2, 0, 0, 0).unwrap()),
+            Some(Utc.with_ymd_and_hms(2023, 1, 5, 0, 0, 0).unwrap())
+        )),
+        ["T2", "T3", "T4"]
+    );
+    assert_eq!(
+        ids(by_date().with_filter(Filter::Account(AccountId::from("1000")))),
+        ["T1", "T3", "T4", "T5"]
+    );
+    assert_eq!(
+        ids(by_date().with_filter(Filter::Party(party_id()))),
+        ["T2", "T3", "T5"]
+    );
+    assert_eq!(
+        ids(by_date().with_filter(Filter::Text("inv-1".to_string()))),
+        ["T2", "T3"]
+    );
+    assert_eq!(
+        ids(by_date()
+            .with_limit(1)
+            .with_filter(Filter::Text("INVOICE".to_string()))),
+        ["T2", "T5"]
+    );
+    assert_eq!(
+        ids(by_date().with_filter(Filter::Text("überweisung".to_string()))),
+        ["T3"]
+    );
+    assert_eq!(
+        ids(by_date()
+            .with_filter(Filter::Account(AccountId::from("1100")))
+            .with_filter(Filter::Text("payment".to_string()))),
+        ["T3"]
+    );
+
+    let page = store
+        .transactions("sales", &by_date().with_limit(1))
+        .unwrap();
+    assert!(matches!(
+        store.transactions(
+            "sales",
+            &Query::default().after(page.next().unwrap().clone())
+        ),
+        Err(Error::InvalidCursor { .. })
+    ));
+    assert!(matches!(
+        "not a cursor".parse::<Cursor>(),
+        Err(Error::InvalidCursor { .. })
+    ));
+}
+
+pub fn error_variants(uri: &Url) {
+    let store = create_datastore(uri, &Default::default()).unwrap();
+    let journals = store.journals().unwrap();
+    let missing = "missing".to_string();
+    assert!(journals.get_by_id(&missing).unwrap().is_none());
+    assert!(matches!(
+        journals.update(journal("missing", 1)),
+        Err(Error::EntityNotFound { id }) if id == "missing"
+    ));
+    assert!(matches!(
+        journals.delete(&missing),
+        Err(Error::EntityNotFound { id }) if id == "missing"
+    ));
+    assert!(matches!(
+        store.ledgers().unwrap().delete(&LedgerKind::Sales),
+        Err(Error::EntityNotFound { id }) if id == "sales"
+    ));
+    assert!(store
+        .parties()
+        .unwrap()
+        .get_by_id(&party_id())
+        .unwrap()
+        .is_none());
+
+    let unknown = Url::parse(&format!("unknown-scheme:{}", uri.path())).unwrap();
+    assert!(!datastore_exists(&unknown));
+    assert!(matches!(
+        get_current_datastore(&unknown),
+        Err(Error::UnknownStoreSchema { .. })
+    ));
+    assert!(matches!(
+        create_datastore(&unknown, &Default::default()),
+        Err(Error::UnknownStoreSchema { .. })
+    ));
+}
+
+///
+/// Separate connections to one store, possibly from different threads, see each other's
+/// writes, and of several concurrent creates of the same entity exactly one succeeds.
+///
+pub fn concurrent_connections(uri: &Url) {
+    const THREADS: usize = 8;
+    let store = create_datastore(uri, &Default::default()).unwrap();
+
+    let other = get_current_datastore(uri).unwrap();
+    let _ = other
+        .journals()
+        .unwrap()
+        .create(journal("first", 1))
+        .unwrap();
+    assert!(store
+        .journals()
+        .unwrap()
+        .get_by_id(&"first".to_string())
+        .unwrap()
+        .is_some());
+
+    let handles: Vec<_> = (0..THREADS)
+        .map(|i| {
+            let uri = uri.clone();
+            thread::spawn(move || {
+                let store = get_current_datastore(&uri).unwrap();
+                let journals = store.journals().unwrap();
+                journals
+                    .create(journal(&format!("thread-{}", i), 1))
+                    .unwrap();
+                journals.create(journal("shared", 1))
+            })
+        })
+        .collect();
+    let results: Vec<_> = handles
+        .into_iter()
+        .map(|handle| handle.join().unwrap())
+        .collect();
+    assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 1);
+    assert!(results
+        .iter()
+        .filter_map(|result| result.as_ref().err())
+        .all(|e| matches!(e, Error::EntityExists { id } if id == "shared")));
+    assert_eq!(
+        store
+            .journals()
+            .unwrap()
+            .list(&Query::default())
+            .unwrap()
+            .into_items()
+            .len(),
+        THREADS + 2
+    );
+}
+
+///
+/// Changes made through a unit of work are seen within it straight away, by other connections
+/// only once it is committed, and not at all if it is rolled back or dropped.
+///
+pub fn unit_of_work(uri: &Url) {
+    let store = create_datastore(uri, &contents()).unwrap();
+    let general = "general".to_string();
+    let sales = "sales".to_string();
+
+    let unit = store.begin().unwrap();
+    let journals = unit.journals().unwrap();
+    journals.update(journal("general", 3)).unwrap();
+    let _ = journals.create(journal("sales", 1)).unwrap();
+    let _ = unit
+        .parties()
+        .unwrap()
+        .create(party("Example B.V."))
+        .unwrap();
+    unit.prices().unwrap().append(vec![price(3, "93")]).unwrap();
+    assert_eq!(
+        json(&journals.get_by_id(&general).unwrap()),
+        json(&Some(journal("general", 3)))
+    );
+    assert_eq!(
+        journals.list(&Query::default()).unwrap().into_items().len(),
+        2
+    );
+    assert_eq!(unit.prices().unwrap().list().unwrap().len(), 2);
+    assert!(matches!(
+        journals.create(journal("sales", 1)),
+        Err(Error::EntityExists { id }) if id == "sales"
+    ));
+
+    let other = get_current_datastore(uri).unwrap();
+    assert_eq!(
+        json(&other.journals().unwrap().get_by_id(&general).unwrap()),
+        json(&Some(journal("general", 2)))
+    );
+    assert!(other
+        .journals()
+        .unwrap()
+        .get_by_id(&sales)
+        .unwrap()
+        .is_none());
+    assert!(other
+        .parties()
+        .unwrap()
+        .get_by_id(&party_id())
+        .unwrap()
+        .is_none());
+    assert_eq!(other.prices().unwrap().list().unwrap().len(), 1);
+
+    unit.commit().unwrap();
+    assert_eq!(
+        json(&other.journals().unwrap().get_by_id(&general).unwrap()),
+        json(&Some(journal("general", 3)))
+    );
+    assert!(other
+        .journals()
+        .unwrap()
+        .get_by_id(&sales)
+        .unwrap()
+        .is_some());
+    assert!(other
+        .parties()
+        .unwrap()
+        .get_by_id(&party_id())
+        .unwrap()
+        .is_some());
+    assert_eq!(
+        other.prices().unwrap().list().unwrap(),
+        vec![price(2, "92"), price(3, "93")]
+    );
+
+    let unit = store.begin().unwrap();
+    let journals = unit.journals().unwrap();
+    journals.delete(&general).unwrap();
+    assert!(journals.get_by_id(&general).unwrap().is_none());
+    assert!(matches!(
+        journals.delete(&general),
+        Err(Error::EntityNotFound { id }) if id == "general"
+    ));
+    unit.parties()
+        .unwrap()
+        .update(party("Example N.V."))
+        .unwrap();
+    unit.rollback().unwrap();
+    assert!(other
+        .journals()
+        .unwrap()
+        .get_by_id(&general)
+        .unwrap()
+        .is_some());
+    assert_eq!(
+        json(&other.parties().unwrap().get_by_id(&party_id()).unwrap()),
+        json(&Some(party("Example B.V.")))
+    );
+
+    {
+        let unit = store.begin().unwrap();
+        unit.journals().unwrap().delete(&sales).unwrap();
+    }
+    assert!(other
+        .journals()
+        .unwrap()
+        .get_by_id(&sales)
+        .unwrap()
+        .is_some());
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Types
+// ------------------------------------------------------------------------------------------------
+
+struct Cleanup(Url, RemoveStore);
+
+// ------------------------------------------------------------------------------------------------
+// Implementations
+// ------------------------------------------------------------------------------------------------
+
+impl Drop for Cleanup {
+    fn drop(&mut self) {
+        (self.1)(&self.0)
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Private Functions
+// ------------------------------------------------------------------------------------------------
+
+fn check_duplicate<I, E, F>(entities: Box<dyn EntityStore<I, E>>, entity: F)
+where
+    I: Clone + Display + Eq + Hash,
+    E: Entity<I> + Serialize,
+    F: Fn() -> E,
+{
+    let original = entity();
+    let expected = json(&original);
+    let id = entities.create(original).unwrap();
+    assert!(matches!(
+        entities.create(entity()),
+        Err(Error::EntityExists { id: existing }) if existing == id.to_string()
+    ));
+    assert!(matches!(
+        entities.create_with_id(entity(), id.clone()),
+        Err(Error::EntityExists { .. })
+    ));
+    assert_eq!(
+        json(&entities.get_by_id(&id).unwrap()),
+        json(&Some(&expected))
+    );
+    assert_eq!(
+        entities.list(&Query::default()).unwrap().into_items().len(),
+        1
+    );
+}
+
+fn check_crud<I, E>(entities: Box<dyn EntityStore<I, E>>, entity: E, changed: E)
+where
+    I: Clone + Display + Eq + Hash,
+    E: Entity<I> + Serialize,
+{
+    let expected = json(&entity);
+    let id = entities.create(entity).unwrap();
+    assert!(&id == changed.identifier());
+    assert_eq!(
+        json(&entities.get_by_id(&id).unwrap()),
+        json(&Some(&expected))
+    );
+    assert_eq!(
+        json(&entities.list(&Query::default()).unwrap().into_items()),
+        json(&[&expected])
+    );
+
+    let expected = json(&changed);
+    entities.update(changed).unwrap();
+    assert_eq!(
+        json(&entities.get_by_id(&id).unwrap()),
+        json(&Some(&expected))
+    );
+
+    entities.delete(&id).unwrap();
+    assert!(entities.get_by_id(&id).unwrap().is_none());
+    assert!(entities
+        .list(&Query::default())
+        .unwrap()
+        .into_items()
+        .is_empty());
+}
+
+fn check_paging<I, E, F>(entities: Box<dyn EntityStore<I, E>>, entity: F)
+where
+    I: Clone + Display + Eq + Hash,
+    E: Entity<I>,
+    F: Fn(&str) -> E,
+{
+    for name in ["c", "a", "d", "b"] {
+        let _ = entities.create(entity(name)).unwrap();
+    }
+    let ids = |query: &Query| -> (Vec<String>, Option<Cursor>) {
+        let page = entities.list(query).unwrap();
+        let next = page.next().cloned();
+        (
+            page.into_iter()
+                .map(|entity| entity.identifier().to_string())
+                .collect(),
+            next,
+        )
+    };
+    let all = |query: Query| -> Vec<String> {
+        let mut query = query;
+        let mut seen = Vec::new();
+        loop {
+            let (page, next) = ids(&query);
+            seen.extend(page);
+            match next {
+                Some(cursor) => query = query.after(cursor.to_string().parse().unwrap()),
+                None => return seen,
+            }
+        }
+    };
+
+    assert_eq!(
+        ids(&Query::default()),
+        (vec!["a".into(), "b".into(), "c".into(), "d".into()], None)
+    );
+    let (first, next) = ids(&Query::default().with_limit(3));
+    assert_eq!(first, ["a", "b", "c"]);
+    let (rest, next) = ids(&Query::default().with_limit(3).after(next.unwrap()));
+    assert_eq!(rest, ["d"]);
+    assert!(next.is_none());
+    let (exact, next) = ids(&Query::default().with_limit(4));
+    assert_eq!(exact.len(), 4);
+    assert!(next.is_none());
+
+    assert_eq!(all(Query::default().with_limit(1)), ["a", "b", "c", "d"]);
+    assert_eq!(
+        all(Query::default()
+            .with_limit(1)
+            .sorted_by(SortKey::Identifier, SortOrder::Descending)),
+        ["d", "c", "b", "a"]
+    );
+    assert_eq!(
+        all(Query::default()
+            .with_limit(3)
+            .sorted_by(SortKey::Label, SortOrder::Ascending)),
+        ["a", "b", "c", "d"]
+    );
+    let mut query = Query::default()
+        .with_limit(2)
+        .sorted_by(SortKey::Date, SortOrder::Descending);
+    let mut by_date = Vec::new();
+    loop {
+        let page = entities.list(&query).unwrap();
+        let next = page.next().cloned();
+        by_date.extend(
+            page.into_iter()
+                .map(|entity| (entity.created(), entity.identifier().to_string())),
+        );
+        match next {
+            Some(cursor) => query = query.after(cursor),
+            None => break,
+        }
+    }
+    assert_eq!(by_date.len(), 4);
+    assert!(by_date.windows(2).all(|pair| pair[0] > pair[1]));
+    assert_eq!(
+        all(Query::default().with_filter(Filter::Text("B".to_string()))),
+        ["b"]
+    );
+
+    let (_, next) = ids(&Query::default().with_limit(1));
+    assert!(matches!(
+        entities.list(
+            &Query::default()
+                .sorted_by(SortKey::Label, SortOrder::Ascending)
+                .after(next.unwrap())
+        ),
+        Err(Error::InvalidCursor { .. })
+    ));
+    assert!(matches!(
+        entities.list(&Query::default().with_filter(Filter::Account(AccountId::from("1000")))),
+        Err(Error::UnsupportedFilter { .. })
+    ));
+}
+
+fn json<T>(value: &T) -> serde_json::Value
+where
+    T: Serialize + ?Sized,
+{
+    serde_json::to_value(value).unwrap()
+}
+
+pub fn eur() -> CommodityId {
+    CommodityId::Currency(CurrencyCode::EUR)
+}
+
+fn contents() -> CreateDatastoreContents {
+    CreateDatastoreContents {
+        ledgers: vec![ledger("general", "General")],
+        journals: vec![journal("general", 2)],
+        prices: vec![price(2, "92")],
+        reconciliations: vec![reconciled("T1", "STMT-1")],
+    }
+}
+
+fn ledger(name: &str, description: &str) -> Ledger {
+    let kind = match name {
+        "general" => LedgerKind::General,
+        name => LedgerKind::Other(name.to_string()),
+    };
+    Ledger::new(kind, description, CurrencyCode::EUR)
+        .with_created(Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 0).unwrap())
+        .with_account(
+            Account::new(AccountId::from("1000"), AccountKind::Asset, "Bank", eur())
+                .with_created(Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 0).unwrap()),
+        )
+        .with_account(
+            Account::new(
+                AccountId::from("3000"),
+                AccountKind::Equity,
+                "Capital",
+                eur(),
+            )
+            .with_created(Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 0).unwrap()),
+        )
+}
+
+///
+/// A journal with `transactions` balanced transactions, each posted a day after the last.
+///
+fn journal(name: &str, transactions: u32) -> Journal {
+    let mut journal = Journal::new(name, CurrencyCode::EUR)
+        .with_created(Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 0).unwrap());
+    for day in 1..=transactions {
+        let id = TransactionId::from(format!("T{}", day).as_str());
+        let amount = Decimal::new(10000 + day as i64, 2);
+        journal
+            .add_transaction(
+                Transaction::new(
+                    id.clone(),
+                    Utc.with_ymd_and_hms(2023, 1, day, 0, 0, 0).unwrap(),
+                )
+                .with_entered(Utc.with_ymd_and_hms(2023, 1, day, 12, 0, 0).unwrap())
+                .with_description(format!("Transaction {}", day))
+                .with_split(Split::new(
+                    id.clone(),
+                    AccountId::from("1000"),
+                    Quantity::new(eur(), amount),
+                ))
+                .with_split(Split::new(
+                    id,
+                    AccountId::from("3000"),
+                    Quantity::new(eur(), -amount),
+                )),
+            )
+            .unwrap();
+    }
+    journal
+}
+
+///
+/// A journal of sales in which two transactions share the identifier `T5`.
+///
+fn sales_journal() -> Journal {
+    let entry = |id: &str, day: u32, debit: &str, credit: &str| {
+        let id = TransactionId::from(id);
+        Transaction::new(
+            id.clone(),
+            Utc.with_ymd_and_hms(2023, 1, day, 0, 0, 0).unwrap(),
+        )
+        .with_entered(Utc.with_ymd_and_hms(2023, 1, day, 12, 0, 0).unwrap())
+        .with_split(Split::new(
+            id.clone(),
+            AccountId::from(debit),
+            Quantity::new(eur(), Decimal::new(5000, 2)),
+        ))
+        .with_split(Split::new(
+            id,
+            AccountId::from(credit),
+            Quantity::new(eur(), Decimal::new(-5000, 2)),
+        ))
+    };
+    let mut journal = Journal::new("sales", CurrencyCode::EUR)
+        .with_created(Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 0).unwrap());
+    for transaction in [
+        entry("T1", 1, "1000", "3000").with_description("Opening balance"),
+        entry("T2", 2, "1100", "4000")
+            .with_description("Invoice 1")
+            .with_reference("INV-1"),
+        entry("T3", 3, "1000", "1100")
+            .with_description("Payment received (Überweisung)")
+            .with_reference("INV-1"),
+        entry("T4", 4, "1000", "3000"),
+        entry("T5", 5, "1100", "4000")
+            .with_description("Invoice 2")
+            .with_reference("INV-2"),
+        entry("T5", 5, "1000", "3000").with_description("Deposit"),
+    ] {
+        journal.add_transaction(transaction).unwrap();
+    }
+    journal
+}
+
+fn party_id() -> PartyId {
+    PartyId::LegalEntity(LegalEntityId::from("5493001KJTIIGC8Y1R12"))
+}
+
+fn party(name: &str) -> Party {
+    Party::new(
+        party_id(),
+        name,
+        Address::new(
+            "1",
+            "Damrak",
+            "Amsterdam",
+            "",
+            "1012",
+            CountryCode::from("NL"),
+        ),
+    )
+    .with_created(Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 0).unwrap())
+}
+
+fn profile(name: &str, currency: CurrencyCode) -> CsvProfile {
+    CsvProfile::new(
+        name,
+        currency,
+        CsvColumn::Index(0),
+        CsvAmount::Signed(CsvColumn::Header("Amount".to_string())),
+    )
+}
+
+fn price(day: u32, rate: &str) -> RateRecord {
+    RateRecord::new(
+        CommodityId::Currency(CurrencyCode::USD),
+        eur(),
+        rate.parse().unwrap(),
+        Utc.with_ymd_and_hms(2023, 1, day, 0, 0, 0).unwrap(),
+        "ECB",
+    )
+}
+
+fn reconciled(split: &str, reference: &str) -> Reconciled {
+    Reconciled::new(
+        TransactionId::from(split),
+        AccountId::from("1000"),
+        reference,
+        Utc.with_ymd_and_hms(2023, 2, 1, 0, 0, 0).unwrap(),
+    )
+}
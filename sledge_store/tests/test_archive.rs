@@ -0,0 +1,276 @@
+use chrono::{TimeZone, Utc};
+use codes_iso_4217::CurrencyCode;
+use rust_decimal::Decimal;
+use sledge_model::audit::{Action, Authenticator, Resource, RoleId, UserId};
+use sledge_model::commodity::{
+    CommodityId, InternationalSecuritiesId, MarketIdentifierCode, Quantity, RateRecord, Security,
+};
+use sledge_model::import::{CsvAmount, CsvColumn, CsvProfile};
+use sledge_model::journal::{Journal, Reconciled, Split, Transaction, TransactionId};
+use sledge_model::ledger::{Account, AccountId, AccountKind, Ledger, LedgerKind};
+use sledge_model::party::{Address, CountryCode, LegalEntityId, Party, PartyId};
+use sledge_store::archive::{read_archive, write_archive, ArchiveFormat, StoreArchive};
+use sledge_store::error::Error;
+use sledge_store::query::Query;
+use sledge_store::{
+    create_datastore, CreateDatastoreContents, DataStore, StorePermissions, StoreSettings,
+    STORE_SCHEMA_VERSION,
+};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use url::Url;
+
+fn store_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("sledge-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&path);
+    path
+}
+
+fn store_uri(path: &Path) -> Url {
+    Url::parse(&format!("fstore://{}", path.display())).unwrap()
+}
+
+fn populated_store(path: &Path) -> Arc<dyn DataStore> {
+    let eur = CommodityId::Currency(CurrencyCode::EUR);
+    let posted = Utc.with_ymd_and_hms(2023, 1, 2, 0, 0, 0).unwrap();
+    let id = TransactionId::from("T1");
+    let mut journal = Journal::new("general", CurrencyCode::EUR);
+    journal
+        .add_transaction(
+            Transaction::new(id.clone(), posted)
+                .with_split(Split::new(
+                    id.clone(),
+                    AccountId::from("1000"),
+                    Quantity::new(eur.clone(), Decimal::new(12050, 2)),
+                ))
+                .with_split(Split::new(
+                    id,
+                    AccountId::from("3000"),
+                    Quantity::new(eur.clone(), Decimal::new(-12050, 2)),
+                )),
+        )
+        .unwrap();
+    let ledger = Ledger::new(LedgerKind::General, "General", CurrencyCode::EUR)
+        .with_account(Account::new(
+            AccountId::from("1000"),
+            AccountKind::Asset,
+            "Bank",
+            eur.clone(),
+        ))
+        .with_account(Account::new(
+            AccountId::from("3000"),
+            AccountKind::Equity,
+            "Capital",
+            eur.clone(),
+        ));
+    let content = CreateDatastoreContents {
+        ledgers: vec![ledger],
+        journals: vec![journal],
+        prices: vec![RateRecord::new(
+            CommodityId::Currency(CurrencyCode::USD),
+            eur.clone(),
+            Decimal::new(92, 2),
+            posted,
+            "ECB",
+        )],
+        reconciliations: vec![Reconciled::new(
+            TransactionId::from("T1"),
+            AccountId::from("1000"),
+            "STMT-1",
+            Utc.with_ymd_and_hms(2023, 2, 1, 0, 0, 0).unwrap(),
+        )],
+    };
+    let store = create_datastore(&store_uri(path), &content).unwrap();
+
+    store
+        .parties()
+        .unwrap()
+        .create(Party::new(
+            PartyId::LegalEntity(LegalEntityId::from("5493001KJTIIGC8Y1R12")),
+            "Example B.V.",
+            Address::new(
+                "1",
+                "Damrak",
+                "Amsterdam",
+                "",
+                "1012",
+                CountryCode::from("NL"),
+            ),
+        ))
+        .unwrap();
+    store
+        .commodities()
+        .unwrap()
+        .append(vec![Security::new(MarketIdentifierCode::from("XAMS"))
+            .with_symbol("ASML")
+            .with_isin(InternationalSecuritiesId::from("NL0010273215"))
+            .with_base_currency(CurrencyCode::EUR)])
+        .unwrap();
+    store
+        .import_profiles()
+        .unwrap()
+        .create(CsvProfile::new(
+            "bank/checking",
+            CurrencyCode::EUR,
+            CsvColumn::Index(0),
+            CsvAmount::Signed(CsvColumn::Header("Amount".to_string())),
+        ))
+        .unwrap();
+    store
+        .update_permissions(
+            &StorePermissions::default()
+                .with_grant(RoleId::from("clerk"), Resource::Journal, Action::Create)
+                .with_user_role(UserId::from("simon"), RoleId::from("clerk")),
+        )
+        .unwrap();
+    store
+        .update_settings(&StoreSettings::default().with_default_commodity(eur))
+        .unwrap();
+    store
+}
+
+fn contents(archive: &StoreArchive) -> serde_json::Value {
+    let mut value = serde_json::to_value(archive).unwrap();
+    value.as_object_mut().unwrap().remove("exported");
+    value
+}
+
+#[test]
+fn test_export_and_import_round_trip() {
+    let from_path = store_path("archive-from");
+    let from = populated_store(&from_path);
+    let archive = StoreArchive::export_from(&*from).unwrap();
+    assert_eq!(archive.version(), &STORE_SCHEMA_VERSION);
+    assert_eq!(archive.ledgers().count(), 1);
+    assert_eq!(archive.journals().next().unwrap().transactions().count(), 1);
+    assert_eq!(archive.parties().count(), 1);
+    assert_eq!(archive.commodities().count(), 1);
+    assert_eq!(archive.prices().count(), 1);
+    assert_eq!(archive.import_profiles().count(), 1);
+    assert_eq!(archive.reconciliations().count(), 1);
+    assert!(archive.permissions().user_can_perform(
+        UserId::from("simon"),
+        Action::Create,
+        Resource::Journal
+    ));
+
+    for format in [ArchiveFormat::Json, ArchiveFormat::JsonLines] {
+        let mut buffer = Vec::new();
+        write_archive(&mut buffer, &archive, format).unwrap();
+        if format == ArchiveFormat::JsonLines {
+            let text = String::from_utf8(buffer.clone()).unwrap();
+            assert_eq!(text.lines().count(), 8);
+            assert!(text.lines().nth(1).unwrap().starts_with("{\"party\":"));
+        }
+
+        let into_path = store_path(&format!("archive-into-{}", format));
+        let into = create_datastore(&store_uri(&into_path), &Default::default()).unwrap();
+        read_archive(buffer.as_slice(), format)
+            .unwrap()
+            .import_into(&*into)
+            .unwrap();
+        let copy = StoreArchive::export_from(&*into).unwrap();
+        assert_eq!(contents(&copy), contents(&archive));
+        assert_eq!(
+            into.settings().unwrap().default_commodity(),
+            Some(&CommodityId::Currency(CurrencyCode::EUR))
+        );
+
+        let again = read_archive(buffer.as_slice(), format).unwrap();
+        assert!(matches!(
+            again.import_into(&*into),
+            Err(Error::StoreNotEmpty { holds }) if holds == "ledgers"
+        ));
+        let _ = std::fs::remove_dir_all(&into_path);
+    }
+    let _ = std::fs::remove_dir_all(&from_path);
+}
+
+#[test]
+fn test_incompatible_archive_version() {
+    let path = store_path("archive-version");
+    let store = populated_store(&path);
+    let archive = StoreArchive::export_from(&*store).unwrap();
+
+    let mut value = serde_json::to_value(&archive).unwrap();
+    value["version"] = serde_json::json!("0.2.0");
+    let buffer = serde_json::to_vec(&value).unwrap();
+    assert!(matches!(
+        read_archive(buffer.as_slice(), ArchiveFormat::Json),
+        Err(Error::ArchiveVersion { version }) if version.minor == 2
+    ));
+
+    let mut buffer = Vec::new();
+    write_archive(&mut buffer, &archive, ArchiveFormat::JsonLines).unwrap();
+    let text = String::from_utf8(buffer).unwrap().replacen(
+        &format!("\"{}\"", STORE_SCHEMA_VERSION),
+        "\"1.0.0\"",
+        1,
+    );
+    assert!(matches!(
+        read_archive(text.as_bytes(), ArchiveFormat::JsonLines),
+        Err(Error::ArchiveVersion { .. })
+    ));
+    assert_eq!(
+        ArchiveFormat::from_path("backup.jsonl"),
+        ArchiveFormat::JsonLines
+    );
+    assert_eq!("json".parse::<ArchiveFormat>(), Ok(ArchiveFormat::Json));
+    let _ = std::fs::remove_dir_all(&path);
+}
+
+#[test]
+fn test_import_into_non_empty_store() {
+    let from_path = store_path("archive-non-empty-from");
+    let archive = StoreArchive::export_from(&*populated_store(&from_path)).unwrap();
+
+    let into_path = store_path("archive-non-empty-into");
+    let into = create_datastore(&store_uri(&into_path), &Default::default()).unwrap();
+    into.prices()
+        .unwrap()
+        .append(archive.prices().cloned().collect())
+        .unwrap();
+    assert!(matches!(
+        archive.import_into(&*into),
+        Err(Error::StoreNotEmpty { holds }) if holds == "prices"
+    ));
+    assert!(into
+        .parties()
+        .unwrap()
+        .list(&Query::default())
+        .unwrap()
+        .items()
+        .is_empty());
+    assert_eq!(into.settings().unwrap().default_commodity(), None);
+    let _ = std::fs::remove_dir_all(&into_path);
+    let _ = std::fs::remove_dir_all(&from_path);
+}
+
+#[test]
+fn test_failed_import_leaves_store_empty() {
+    let from_path = store_path("archive-failed-from");
+    let archive = StoreArchive::export_from(&*populated_store(&from_path)).unwrap();
+
+    let mut value = serde_json::to_value(&archive).unwrap();
+    let journals = value["journals"].as_array_mut().unwrap();
+    journals.push(journals[0].clone());
+    let buffer = serde_json::to_vec(&value).unwrap();
+
+    let into_path = store_path("archive-failed-into");
+    let into = create_datastore(&store_uri(&into_path), &Default::default()).unwrap();
+    assert!(matches!(
+        read_archive(buffer.as_slice(), ArchiveFormat::Json)
+            .unwrap()
+            .import_into(&*into),
+        Err(Error::EntityExists { .. })
+    ));
+    let copy = StoreArchive::export_from(&*into).unwrap();
+    assert_eq!(copy.parties().count(), 0);
+    assert_eq!(copy.ledgers().count(), 0);
+    assert_eq!(copy.prices().count(), 0);
+    assert_eq!(copy.reconciliations().count(), 0);
+    assert_eq!(into.settings().unwrap().default_commodity(), None);
+    assert_eq!(into.permissions().unwrap(), StorePermissions::default());
+    let _ = std::fs::remove_dir_all(&into_path);
+    let _ = std::fs::remove_dir_all(&from_path);
+}
@@ -0,0 +1,207 @@
+#[macro_use]
+mod conformance;
+
+use chrono::Utc;
+use codes_iso_4217::CurrencyCode;
+use rust_decimal::Decimal;
+use sledge_model::commodity::{CommodityId, RateRecord};
+use sledge_model::journal::Journal;
+use sledge_store::{create_datastore, get_current_datastore, CreateDatastoreContents};
+use std::fs;
+use std::path::Path;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use conformance::eur;
+use url::Url;
+
+fn conformance_uri(name: &str) -> Url {
+    let path = std::env::temp_dir().join(format!("sledge-{}-{}.fstore", name, std::process::id()));
+    Url::parse(&format!("fstore://{}", path.display())).unwrap()
+}
+
+fn remove_conformance_store(uri: &Url) {
+    let _ = std::fs::remove_dir_all(uri.path());
+}
+
+datastore_conformance!(conformance_uri, remove_conformance_store);
+
+fn hold_lock(uri: &Url) -> fs::File {
+    let lock = fs::File::options()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(Path::new(uri.path()).join("transaction.lock"))
+        .unwrap();
+    lock.lock().unwrap();
+    lock
+}
+
+fn price(rate: i64) -> RateRecord {
+    RateRecord::new(
+        CommodityId::Currency(CurrencyCode::USD),
+        eur(),
+        Decimal::new(rate, 2),
+        Utc::now(),
+        "ECB",
+    )
+}
+
+#[test]
+fn test_abandoned_log_replayed_on_connect() {
+    let uri = conformance_uri("abandoned-log");
+    remove_conformance_store(&uri);
+    let store = create_datastore(
+        &uri,
+        &CreateDatastoreContents {
+            journals: vec![Journal::new("general", CurrencyCode::EUR)],
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    drop(store);
+
+    // A unit of work that stopped after writing its log, and before making any change; its
+    // lock was released as the process stopped.
+    let wal_path = Path::new(uri.path()).join("transaction.wal");
+    let sales = Journal::new("sales", CurrencyCode::EUR);
+    fs::write(
+        &wal_path,
+        serde_json::to_string(&serde_json::json!([
+            {
+                "path": "journals/sales.json",
+                "contents": serde_json::to_string_pretty(&sales).unwrap()
+            },
+            { "path": "journals/general.json", "contents": null }
+        ]))
+        .unwrap(),
+    )
+    .unwrap();
+    let store = get_current_datastore(&uri).unwrap();
+    assert!(!wal_path.exists());
+    let journals = store.journals().unwrap();
+    assert!(journals.get_by_id(&"sales".to_string()).unwrap().is_some());
+    assert!(journals
+        .get_by_id(&"general".to_string())
+        .unwrap()
+        .is_none());
+    remove_conformance_store(&uri);
+}
+
+#[test]
+fn test_commit_waits_for_lock_holder() {
+    let uri = conformance_uri("lock-holder");
+    remove_conformance_store(&uri);
+    let store = create_datastore(&uri, &Default::default()).unwrap();
+
+    // Another process committing a unit of work holds the lock.
+    let lock = hold_lock(&uri);
+
+    let (sender, receiver) = mpsc::channel();
+    let committer = thread::spawn({
+        let uri = uri.clone();
+        move || {
+            let store = get_current_datastore(&uri).unwrap();
+            let work = store.begin().unwrap();
+            work.journals()
+                .unwrap()
+                .create(Journal::new("sales", CurrencyCode::EUR))
+                .unwrap();
+            work.commit().unwrap();
+            sender.send(()).unwrap();
+        }
+    });
+    assert!(receiver.recv_timeout(Duration::from_millis(200)).is_err());
+    assert!(store
+        .journals()
+        .unwrap()
+        .get_by_id(&"sales".to_string())
+        .unwrap()
+        .is_none());
+
+    drop(lock);
+    committer.join().unwrap();
+    assert!(store
+        .journals()
+        .unwrap()
+        .get_by_id(&"sales".to_string())
+        .unwrap()
+        .is_some());
+    remove_conformance_store(&uri);
+}
+
+#[test]
+fn test_direct_write_waits_for_lock_holder() {
+    let uri = conformance_uri("direct-lock-holder");
+    remove_conformance_store(&uri);
+    let store = create_datastore(
+        &uri,
+        &CreateDatastoreContents {
+            journals: vec![Journal::new("general", CurrencyCode::EUR)],
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    // A unit of work holds the lock between checking its changes and making them.
+    let lock = hold_lock(&uri);
+
+    let (sender, receiver) = mpsc::channel();
+    let writer = thread::spawn({
+        let uri = uri.clone();
+        move || {
+            let store = get_current_datastore(&uri).unwrap();
+            store
+                .journals()
+                .unwrap()
+                .update(Journal::new("general", CurrencyCode::USD))
+                .unwrap();
+            store.prices().unwrap().append(vec![price(92)]).unwrap();
+            sender.send(()).unwrap();
+        }
+    });
+    assert!(receiver.recv_timeout(Duration::from_millis(200)).is_err());
+    assert!(store.prices().unwrap().list().unwrap().is_empty());
+
+    drop(lock);
+    writer.join().unwrap();
+    let general = store
+        .journals()
+        .unwrap()
+        .get_by_id(&"general".to_string())
+        .unwrap()
+        .unwrap();
+    assert_eq!(general.currency(), CurrencyCode::USD);
+    assert_eq!(store.prices().unwrap().list().unwrap().len(), 1);
+    remove_conformance_store(&uri);
+}
+
+#[test]
+fn test_concurrent_appends_keep_every_record() {
+    let uri = conformance_uri("concurrent-appends");
+    remove_conformance_store(&uri);
+    let store = create_datastore(&uri, &Default::default()).unwrap();
+
+    let writers: Vec<_> = (0..8)
+        .map(|writer| {
+            let uri = uri.clone();
+            thread::spawn(move || {
+                let store = get_current_datastore(&uri).unwrap();
+                for rate in 0..10 {
+                    store
+                        .prices()
+                        .unwrap()
+                        .append(vec![price(writer * 10 + rate)])
+                        .unwrap();
+                }
+            })
+        })
+        .collect();
+    for writer in writers {
+        writer.join().unwrap();
+    }
+    assert_eq!(store.prices().unwrap().list().unwrap().len(), 80);
+    remove_conformance_store(&uri);
+}
@@ -0,0 +1,212 @@
+#[macro_use]
+mod conformance;
+
+use chrono::{TimeZone, Utc};
+use codes_iso_4217::CurrencyCode;
+use rust_decimal::Decimal;
+use sledge_model::commodity::{CommodityId, Quantity, RateRecord};
+use sledge_model::journal::{Journal, Split, Transaction, TransactionId};
+use sledge_model::ledger::{Account, AccountId, AccountKind, Ledger, LedgerKind};
+use sledge_store::archive::StoreArchive;
+use sledge_store::error::Error;
+use sledge_store::query::Query;
+use sledge_store::{create_datastore, get_current_datastore, CreateDatastoreContents};
+use url::Url;
+
+use conformance::eur;
+
+fn conformance_uri(name: &str) -> Url {
+    Url::parse(&format!("memory:{}", name)).unwrap()
+}
+
+fn remove_conformance_store(_: &Url) {}
+
+datastore_conformance!(conformance_uri, remove_conformance_store);
+
+fn contents() -> CreateDatastoreContents {
+    let posted = Utc.with_ymd_and_hms(2023, 1, 2, 0, 0, 0).unwrap();
+    let id = TransactionId::from("T1");
+    let mut journal = Journal::new("general", CurrencyCode::EUR);
+    journal
+        .add_transaction(
+            Transaction::new(id.clone(), posted)
+                .with_split(Split::new(
+                    id.clone(),
+                    AccountId::from("1000"),
+                    Quantity::new(eur(), Decimal::new(12050, 2)),
+                ))
+                .with_split(Split::new(
+                    id,
+                    AccountId::from("3000"),
+                    Quantity::new(eur(), Decimal::new(-12050, 2)),
+                )),
+        )
+        .unwrap();
+    CreateDatastoreContents {
+        ledgers: vec![
+            Ledger::new(LedgerKind::General, "General", CurrencyCode::EUR).with_account(
+                Account::new(AccountId::from("1000"), AccountKind::Asset, "Bank", eur()),
+            ),
+        ],
+        journals: vec![journal],
+        prices: vec![RateRecord::new(
+            CommodityId::Currency(CurrencyCode::USD),
+            eur(),
+            Decimal::new(92, 2),
+            posted,
+            "ECB",
+        )],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_memory_store_lifecycle() {
+    let uri = Url::parse("memory:test-lifecycle").unwrap();
+    assert!(matches!(
+        get_current_datastore(&uri),
+        Err(Error::StoreDoesNotExist { .. })
+    ));
+
+    let store = create_datastore(&uri, &contents()).unwrap();
+    assert!(matches!(
+        create_datastore(&uri, &Default::default()),
+        Err(Error::StoreExists { .. })
+    ));
+
+    let other = get_current_datastore(&uri).unwrap();
+    other
+        .journals()
+        .unwrap()
+        .create(Journal::new("sales", CurrencyCode::EUR))
+        .unwrap();
+    assert_eq!(
+        store
+            .journals()
+            .unwrap()
+            .list(&Query::default())
+            .unwrap()
+            .into_items()
+            .len(),
+        2
+    );
+    assert_eq!(
+        store
+            .journals()
+            .unwrap()
+            .get_by_id(&"general".to_string())
+            .unwrap()
+            .unwrap()
+            .transactions()
+            .count(),
+        1
+    );
+    assert_eq!(store.prices().unwrap().list().unwrap().len(), 1);
+
+    drop(store);
+    drop(other);
+    assert!(matches!(
+        get_current_datastore(&uri),
+        Err(Error::StoreDoesNotExist { .. })
+    ));
+
+    let unnamed = Url::parse("memory:").unwrap();
+    let first = create_datastore(&unnamed, &contents()).unwrap();
+    let second = create_datastore(&unnamed, &Default::default()).unwrap();
+    assert_eq!(
+        first
+            .ledgers()
+            .unwrap()
+            .list(&Query::default())
+            .unwrap()
+            .into_items()
+            .len(),
+        1
+    );
+    assert!(second
+        .ledgers()
+        .unwrap()
+        .list(&Query::default())
+        .unwrap()
+        .into_items()
+        .is_empty());
+    assert!(get_current_datastore(&unnamed).is_err());
+}
+
+#[test]
+fn test_memory_store_snapshot() {
+    let path = std::env::temp_dir().join(format!("sledge-snapshot-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let uri = Url::parse(&format!("memory:test-snapshot?snapshot={}", path.display())).unwrap();
+
+    let contents = contents();
+    let store = create_datastore(&uri, &contents).unwrap();
+    let exported = serde_json::to_value(StoreArchive::export_from(&*store).unwrap()).unwrap();
+    assert!(!path.exists());
+    store.disconnect().unwrap();
+    assert!(path.is_file());
+    drop(store);
+
+    assert!(matches!(
+        create_datastore(&uri, &Default::default()),
+        Err(Error::StoreExists { .. })
+    ));
+    let store = get_current_datastore(&uri).unwrap();
+    let loaded = serde_json::to_value(StoreArchive::export_from(&*store).unwrap()).unwrap();
+    for key in ["ledgers", "journals", "prices", "permissions"] {
+        assert_eq!(loaded[key], exported[key]);
+    }
+    drop(store);
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_memory_store_snapshot_error() {
+    let path = std::env::temp_dir()
+        .join(format!("sledge-missing-{}", std::process::id()))
+        .join("books.jsonl");
+    let uri = Url::parse(&format!(
+        "memory:test-snapshot-error?snapshot={}",
+        path.display()
+    ))
+    .unwrap();
+
+    let store = create_datastore(&uri, &contents()).unwrap();
+    assert!(store.disconnect().is_err());
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_memory_store_snapshot_replaced_whole() {
+    let dir = std::env::temp_dir().join(format!("sledge-snapshots-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("books.json");
+    let uri = Url::parse(&format!(
+        "memory:test-snapshot-replaced?snapshot={}",
+        path.display()
+    ))
+    .unwrap();
+    let store = create_datastore(&uri, &contents()).unwrap();
+    store.disconnect().unwrap();
+    let saved = std::fs::read(&path).unwrap();
+
+    // A snapshot that cannot be replaced leaves what is there, and no temporary file, behind.
+    std::fs::remove_file(&path).unwrap();
+    std::fs::create_dir(&path).unwrap();
+    store.prices().unwrap().append(contents().prices).unwrap();
+    assert!(store.disconnect().is_err());
+    assert!(path.is_dir());
+    let names: Vec<_> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert_eq!(names, ["books.json"]);
+
+    std::fs::remove_dir(&path).unwrap();
+    store.disconnect().unwrap();
+    assert_ne!(std::fs::read(&path).unwrap(), saved);
+    drop(store);
+    let _ = std::fs::remove_dir_all(&dir);
+}
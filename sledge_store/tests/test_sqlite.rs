@@ -0,0 +1,285 @@
+#[macro_use]
+mod conformance;
+
+use chrono::{Duration, TimeZone, Utc};
+use codes_iso_4217::CurrencyCode;
+use rust_decimal::Decimal;
+use sledge_model::commodity::{CommodityId, Quantity, RateRecord};
+use sledge_model::journal::{Journal, Split, Transaction, TransactionId};
+use sledge_model::ledger::{Account, AccountId, AccountKind, Ledger, LedgerKind};
+use sledge_model::party::{Address, CountryCode, LegalEntityId, Party, PartyId};
+use sledge_store::archive::StoreArchive;
+use sledge_store::error::Error;
+use sledge_store::query::Query;
+use sledge_store::{
+    create_datastore, get_current_datastore, CreateDatastoreContents, StoreSettings,
+};
+use std::path::{Path, PathBuf};
+use url::Url;
+
+use conformance::eur;
+
+fn store_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("sledge-{}-{}.db", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn store_uri(path: &Path) -> Url {
+    Url::parse(&format!("sqlite://{}", path.display())).unwrap()
+}
+
+fn conformance_uri(name: &str) -> Url {
+    store_uri(&store_path(name))
+}
+
+fn remove_conformance_store(uri: &Url) {
+    let _ = std::fs::remove_file(uri.path());
+}
+
+datastore_conformance!(conformance_uri, remove_conformance_store);
+
+fn transaction(id: &str, day: u32, amount: i64) -> Transaction {
+    let id = TransactionId::from(id);
+    Transaction::new(
+        id.clone(),
+        Utc.with_ymd_and_hms(2023, 1, day, 0, 0, 0).unwrap(),
+    )
+    .with_description("Opening balance")
+    .with_metadata("source", "bank.csv")
+    .with_split(
+        Split::new(
+            id.clone(),
+            AccountId::from("1000"),
+            Quantity::new(eur(), Decimal::new(amount, 2)),
+        )
+        .with_description("Bank"),
+    )
+    .with_split(Split::new(
+        id,
+        AccountId::from("3000"),
+        Quantity::new(eur(), Decimal::new(-amount, 2)),
+    ))
+}
+
+fn contents() -> CreateDatastoreContents {
+    let mut journal = Journal::new("general", CurrencyCode::EUR);
+    journal.add_transaction(transaction("T2", 3, 500)).unwrap();
+    journal
+        .add_transaction(transaction("T1", 2, 12050))
+        .unwrap();
+    let mut journal = journal.with_read_only_after(Duration::days(90));
+    journal.lock_until(Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 0).unwrap());
+    let ledger = Ledger::new(LedgerKind::General, "General", CurrencyCode::EUR)
+        .with_account(
+            Account::new(AccountId::from("1"), AccountKind::Asset, "Assets", eur()).non_recording(),
+        )
+        .with_account(
+            Account::new(AccountId::from("1000"), AccountKind::Asset, "Bank", eur())
+                .with_parent(AccountId::from("1")),
+        )
+        .with_account(Account::new(
+            AccountId::from("3000"),
+            AccountKind::Equity,
+            "Capital",
+            eur(),
+        ));
+    CreateDatastoreContents {
+        ledgers: vec![ledger],
+        journals: vec![journal],
+        prices: vec![RateRecord::new(
+            CommodityId::Currency(CurrencyCode::USD),
+            eur(),
+            Decimal::new(92, 2),
+            Utc.with_ymd_and_hms(2023, 1, 2, 0, 0, 0).unwrap(),
+            "ECB",
+        )],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_sqlite_store_round_trip() {
+    let path = store_path("sqlite-round-trip");
+    let uri = store_uri(&path);
+    let contents = contents();
+    let store = create_datastore(&uri, &contents).unwrap();
+    store
+        .update_settings(&StoreSettings::default().with_default_commodity(eur()))
+        .unwrap();
+    assert!(matches!(
+        create_datastore(&uri, &Default::default()),
+        Err(Error::StoreExists { .. })
+    ));
+
+    let store = get_current_datastore(&uri).unwrap();
+    assert_eq!(store.settings().unwrap().default_commodity(), Some(&eur()));
+    let journal = store
+        .journals()
+        .unwrap()
+        .get_by_id(&"general".to_string())
+        .unwrap()
+        .unwrap();
+    let ids: Vec<&str> = journal.transactions().map(|t| t.id().as_str()).collect();
+    assert_eq!(ids, ["T1", "T2"]);
+    let first = journal.transactions().next().unwrap();
+    assert_eq!(
+        first.metadata_value("source").map(String::as_str),
+        Some("bank.csv")
+    );
+    assert_eq!(first.splits().count(), 2);
+    assert_eq!(
+        first.splits().next().unwrap().quantity().quantity(),
+        Decimal::new(12050, 2)
+    );
+    assert_eq!(journal.read_only_after(), Some(Duration::days(90)));
+    assert!(journal.locked_until().is_some());
+
+    let ledger = store
+        .ledgers()
+        .unwrap()
+        .get_by_id(&LedgerKind::General)
+        .unwrap()
+        .unwrap();
+    let ids: Vec<&str> = ledger.book().map(|a| a.id().as_str()).collect();
+    assert_eq!(ids, ["1", "1000", "3000"]);
+    assert!(!ledger
+        .account(&AccountId::from("1"))
+        .unwrap()
+        .is_recording());
+
+    let archive = serde_json::to_value(StoreArchive::export_from(&*store).unwrap()).unwrap();
+    assert_eq!(
+        archive["ledgers"],
+        serde_json::to_value(&contents.ledgers).unwrap()
+    );
+    assert_eq!(
+        archive["journals"],
+        serde_json::to_value(&contents.journals).unwrap()
+    );
+    assert_eq!(
+        archive["prices"],
+        serde_json::to_value(&contents.prices).unwrap()
+    );
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_sqlite_journal_update_keeps_positions() {
+    let path = store_path("sqlite-positions");
+    let store = create_datastore(&store_uri(&path), &contents()).unwrap();
+    let journals = store.journals().unwrap();
+    let mut journal = Journal::new("sales", CurrencyCode::EUR);
+    journal.add_transaction(transaction("S1", 5, 100)).unwrap();
+    journal.add_transaction(transaction("S3", 15, 300)).unwrap();
+    let _ = journals.create(journal).unwrap();
+
+    let mut journal = journals.get_by_id(&"sales".to_string()).unwrap().unwrap();
+    journal.add_transaction(transaction("S2", 10, 200)).unwrap();
+    journals.update(journal).unwrap();
+
+    let journal = journals.get_by_id(&"sales".to_string()).unwrap().unwrap();
+    let ids: Vec<&str> = journal.transactions().map(|t| t.id().as_str()).collect();
+    assert_eq!(ids, vec!["S1", "S2", "S3"]);
+    assert!(journal.transactions().all(|t| t.splits().count() == 2));
+
+    let connection = rusqlite::Connection::open(&path).unwrap();
+    let mut statement = connection
+        .prepare("SELECT id, position FROM transactions WHERE journal = 'sales' ORDER BY position")
+        .unwrap();
+    let positions: Vec<(String, i64)> = statement
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        positions,
+        vec![
+            ("S1".to_string(), 0),
+            ("S3".to_string(), 1),
+            ("S2".to_string(), 2)
+        ]
+    );
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_sqlite_entity_stores() {
+    let path = store_path("sqlite-entities");
+    let store = create_datastore(&store_uri(&path), &contents()).unwrap();
+
+    let ledgers = store.ledgers().unwrap();
+    assert!(matches!(
+        ledgers.create(Ledger::new(LedgerKind::General, "Again", CurrencyCode::EUR)),
+        Err(Error::EntityExists { .. })
+    ));
+    let ledger = ledgers.get_by_id(&LedgerKind::General).unwrap().unwrap();
+    assert_eq!(ledger.book().count(), 3);
+    ledgers
+        .update(ledger.with_account(Account::new(
+            AccountId::from("4000"),
+            AccountKind::Income,
+            "Sales",
+            eur(),
+        )))
+        .unwrap();
+    assert_eq!(
+        ledgers
+            .get_by_id(&LedgerKind::General)
+            .unwrap()
+            .unwrap()
+            .book()
+            .count(),
+        4
+    );
+    assert!(matches!(
+        ledgers.update(Ledger::new(LedgerKind::Sales, "Sales", CurrencyCode::EUR)),
+        Err(Error::EntityNotFound { .. })
+    ));
+
+    let journals = store.journals().unwrap();
+    let mut journal = Journal::new("sales", CurrencyCode::EUR);
+    journal.add_transaction(transaction("S1", 5, 100)).unwrap();
+    assert_eq!(journals.create(journal).unwrap(), "sales");
+    assert!(matches!(
+        journals.create(Journal::new("sales", CurrencyCode::EUR)),
+        Err(Error::EntityExists { .. })
+    ));
+    let journal = journals.get_by_id(&"sales".to_string()).unwrap().unwrap();
+    assert_eq!(journal.transactions().count(), 1);
+    journals.delete(&"sales".to_string()).unwrap();
+    assert!(journals.get_by_id(&"sales".to_string()).unwrap().is_none());
+    assert!(matches!(
+        journals.delete(&"sales".to_string()),
+        Err(Error::EntityNotFound { .. })
+    ));
+    assert_eq!(
+        journals.list(&Query::default()).unwrap().into_items().len(),
+        1
+    );
+
+    let parties = store.parties().unwrap();
+    let id = parties
+        .create(Party::new(
+            PartyId::LegalEntity(LegalEntityId::from("5493001KJTIIGC8Y1R12")),
+            "Example B.V.",
+            Address::new(
+                "1",
+                "Damrak",
+                "Amsterdam",
+                "",
+                "1012",
+                CountryCode::from("NL"),
+            ),
+        ))
+        .unwrap();
+    assert_eq!(
+        parties.get_by_id(&id).unwrap().unwrap().name(),
+        "Example B.V."
+    );
+    assert_eq!(store.prices().unwrap().list().unwrap().len(), 1);
+
+    let _ = std::fs::remove_file(&path);
+}